@@ -1,2 +1,4 @@
 pub mod ci_processor;
+mod domain;
+pub mod dto;
 pub mod task_processor;
@@ -0,0 +1 @@
+pub mod async_task;
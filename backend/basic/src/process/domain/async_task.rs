@@ -0,0 +1,83 @@
+use tardis::chrono::{self, Utc};
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::prelude::Json;
+use tardis::db::sea_orm::*;
+use tardis::{TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
+
+/// Asynchronous task model
+///
+/// 异步任务模型
+///
+/// Used to persist the tasks executed by [`crate::process::task_processor::TaskProcessor`],
+/// so that the tasks can be listed, retried and taken over by other nodes.
+///
+/// 用于持久化由 [`crate::process::task_processor::TaskProcessor`] 执行的任务，以便任务可以被查询、重试及由其它节点接管。
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
+#[sea_orm(table_name = "bios_async_task")]
+pub struct Model {
+    /// Task id
+    ///
+    /// 任务id
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    /// Cache key of the task status
+    ///
+    /// 任务状态的缓存键
+    #[index]
+    pub cache_key: String,
+    /// Task type, used to find the registered task function
+    ///
+    /// 任务类型，用于查找注册的任务函数
+    #[index]
+    pub task_type: String,
+    /// Task status ([`crate::process::dto::task_processor_dto::AsyncTaskStatusKind`])
+    ///
+    /// 任务状态 （[`crate::process::dto::task_processor_dto::AsyncTaskStatusKind`]）
+    #[index]
+    pub status: String,
+    /// Task parameters
+    ///
+    /// 任务参数
+    pub params: Option<Json>,
+    /// Task progress
+    ///
+    /// 任务进度
+    pub progress: Option<Json>,
+    /// Task result
+    ///
+    /// 任务结果
+    pub result: Option<Json>,
+    /// Error message of the last execution
+    ///
+    /// 最后一次执行的错误信息
+    pub error: String,
+    /// Number of retries
+    ///
+    /// 重试次数
+    pub retry_times: i32,
+    /// Maximum number of retries
+    ///
+    /// 最大重试次数
+    pub max_retry_times: i32,
+    /// Owner context (JSON format)
+    ///
+    /// 所有者上下文（JSON格式）
+    pub ctx: String,
+    /// Id of the node executing the task
+    ///
+    /// 执行任务的节点id
+    #[index]
+    pub node_id: String,
+    /// Last heartbeat time of the executing node
+    ///
+    /// 执行节点的最后心跳时间
+    pub heartbeat_time: chrono::DateTime<Utc>,
+
+    #[index]
+    pub own_paths: String,
+    pub owner: String,
+    #[sea_orm(extra = "DEFAULT CURRENT_TIMESTAMP")]
+    pub create_time: chrono::DateTime<Utc>,
+    #[sea_orm(extra = "DEFAULT CURRENT_TIMESTAMP")]
+    pub update_time: chrono::DateTime<Utc>,
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+use tardis::chrono::{DateTime, Utc};
+use tardis::db::sea_orm;
+use tardis::serde_json::Value;
+use tardis::web::poem_openapi;
+
+/// Asynchronous task status
+///
+/// 异步任务状态
+#[derive(Display, EnumString, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum)]
+pub enum AsyncTaskStatusKind {
+    /// Running
+    ///
+    /// 执行中
+    Running,
+    /// Finished successfully
+    ///
+    /// 执行成功
+    Finished,
+    /// Failed
+    ///
+    /// 执行失败
+    Failed,
+    /// Cancelled
+    ///
+    /// 已取消
+    Cancelled,
+}
+
+/// Asynchronous task detail information
+///
+/// 异步任务详细信息
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object, sea_orm::FromQueryResult)]
+pub struct AsyncTaskDetailResp {
+    /// Task id
+    ///
+    /// 任务id
+    pub id: String,
+    /// Cache key of the task status
+    ///
+    /// 任务状态的缓存键
+    pub cache_key: String,
+    /// Task type
+    ///
+    /// 任务类型
+    pub task_type: String,
+    /// Task status ([`AsyncTaskStatusKind`])
+    ///
+    /// 任务状态 （[`AsyncTaskStatusKind`]）
+    pub status: String,
+    /// Task parameters
+    ///
+    /// 任务参数
+    pub params: Option<Value>,
+    /// Task progress
+    ///
+    /// 任务进度
+    pub progress: Option<Value>,
+    /// Task result
+    ///
+    /// 任务结果
+    pub result: Option<Value>,
+    /// Error message of the last execution
+    ///
+    /// 最后一次执行的错误信息
+    pub error: String,
+    /// Number of retries
+    ///
+    /// 重试次数
+    pub retry_times: i32,
+    /// Maximum number of retries
+    ///
+    /// 最大重试次数
+    pub max_retry_times: i32,
+    /// Id of the node executing the task
+    ///
+    /// 执行任务的节点id
+    pub node_id: String,
+    /// Last heartbeat time of the executing node
+    ///
+    /// 执行节点的最后心跳时间
+    pub heartbeat_time: DateTime<Utc>,
+
+    pub own_paths: String,
+    pub owner: String,
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+/// Asynchronous task filter
+///
+/// 异步任务过滤器
+#[derive(Serialize, Deserialize, Debug, Clone, Default, poem_openapi::Object)]
+#[serde(default)]
+pub struct AsyncTaskFilterReq {
+    /// Task type
+    ///
+    /// 任务类型
+    pub task_type: Option<String>,
+    /// Task status
+    ///
+    /// 任务状态
+    pub status: Option<AsyncTaskStatusKind>,
+    /// Whether to include the tasks of sub own paths
+    ///
+    /// 是否包含子所有权路径的任务
+    pub with_sub_own_paths: bool,
+}
@@ -8,21 +8,56 @@ use bios_sdk_invoke::clients::event_client::{
 };
 use lazy_static::lazy_static;
 
-use std::{collections::HashMap, future::Future, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    pin::Pin,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
     cache::cache_client::TardisCacheClient,
-    chrono::Local,
+    chrono::{self, Local, Utc},
+    db::{
+        reldb_client::TardisActiveModel,
+        sea_orm::{sea_query::*, Iterable, Set},
+    },
     log,
     serde_json::Value,
-    tokio::{sync::RwLock, task::JoinHandle},
-    TardisFuns,
+    tokio::{sync::RwLock, task::JoinHandle, time},
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
 };
 
+use crate::process::domain::async_task;
+use crate::process::dto::task_processor_dto::{AsyncTaskDetailResp, AsyncTaskFilterReq, AsyncTaskStatusKind};
+
+type TaskFun = Arc<dyn Fn(u64, Value, TardisContext) -> Pin<Box<dyn Future<Output = TardisResult<Value>> + Send>> + Send + Sync>;
+
+#[derive(Clone)]
+struct RegisteredTaskFun {
+    fun: TaskFun,
+    // Whether the task can be re-executed from scratch after being interrupted
+    idempotent: bool,
+}
+
 lazy_static! {
     static ref TASK_HANDLE: Arc<RwLock<HashMap<u64, JoinHandle<()>>>> = Arc::new(RwLock::new(HashMap::new()));
+    static ref TASK_FUNS: Arc<RwLock<HashMap<String, RegisteredTaskFun>>> = Arc::new(RwLock::new(HashMap::new()));
+    static ref TASK_HEARTBEAT_MODULES: Arc<RwLock<HashSet<String>>> = Arc::new(RwLock::new(HashSet::new()));
+    /// Id of the current node, regenerated on every startup, so the tasks of the previous process become orphaned
+    ///
+    /// 当前节点的id，每次启动时重新生成，所以前一个进程的任务会成为孤儿任务
+    static ref NODE_ID: String = TardisFuns::field.nanoid();
 }
 const TASK_PROCESSOR_DATA_EX_SEC: u64 = 60 * 60 * 24;
+const TASK_HEARTBEAT_INTERVAL_SEC: u64 = 10;
+/// Tasks whose node has not sent a heartbeat for this period are considered orphaned
+///
+/// 节点在此时间内未发送心跳的任务被视为孤儿任务
+const TASK_ORPHAN_TIMEOUT_SEC: i64 = 60;
 const TASK_IN_CTX_FLAG: &str = "task_id";
 #[cfg(feature = "with-mq")]
 const TASK_TOPIC: TopicCode = TopicCode::const_new("task");
@@ -226,11 +261,7 @@ impl TaskProcessor {
             // todo: broadcast event to users
         }
         if let Some(ctx) = ctx {
-            if let Some(exist_task_ids) = ctx.get_ext(TASK_IN_CTX_FLAG).await? {
-                ctx.add_ext(TASK_IN_CTX_FLAG, &format!("{exist_task_ids},{task_id}")).await?;
-            } else {
-                ctx.add_ext(TASK_IN_CTX_FLAG, &task_id.to_string()).await?;
-            }
+            Self::add_task_id_to_ctx(task_id, ctx).await?;
         }
         Ok(task_id)
     }
@@ -293,6 +324,561 @@ impl TaskProcessor {
     pub async fn get_task_id_with_ctx(ctx: &TardisContext) -> TardisResult<Option<String>> {
         ctx.get_ext(TASK_IN_CTX_FLAG).await
     }
+
+    async fn add_task_id_to_ctx(task_id: u64, ctx: &TardisContext) -> TardisResult<()> {
+        if let Some(exist_task_ids) = ctx.get_ext(TASK_IN_CTX_FLAG).await? {
+            ctx.add_ext(TASK_IN_CTX_FLAG, &format!("{exist_task_ids},{task_id}")).await?;
+        } else {
+            ctx.add_ext(TASK_IN_CTX_FLAG, &task_id.to_string()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Persistent task registry
+///
+/// 持久化的任务注册中心
+///
+/// Tasks executed by [`TaskProcessor::execute_registered_task`] are persisted with their type, owner context, parameters, progress, result, error and retry times.
+/// The task function is looked up by task type, so the task can be retried or taken over by another node after the executing node is restarted.
+///
+/// 由 [`TaskProcessor::execute_registered_task`] 执行的任务会持久化其类型、所有者上下文、参数、进度、结果、错误及重试次数。
+/// 任务函数通过任务类型查找，所以在执行节点重启后任务可以被重试或由其它节点接管。
+impl TaskProcessor {
+    /// Initialize the task registry
+    ///
+    /// 初始化任务注册中心
+    ///
+    /// Create the task table, start the heartbeat of the current node and take over the orphaned tasks.
+    /// Task functions should be registered by [`TaskProcessor::register_task_fun`] before calling this function.
+    ///
+    /// 创建任务表，启动当前节点的心跳并接管孤儿任务。调用此函数前应通过 [`TaskProcessor::register_task_fun`] 注册任务函数。
+    pub async fn init_registry(funs: &TardisFunsInst) -> TardisResult<()> {
+        let module_code = funs.module_code().to_string();
+        let mut tx = TardisFuns::reldb_by_module_or_default(&module_code).conn();
+        if TardisFuns::dict.get("__TASK_PROCESSOR_INIT__", &tx).await?.is_none() {
+            let db_kind = TardisFuns::reldb_by_module_or_default(&module_code).backend();
+            let compatible_type = TardisFuns::reldb_by_module_or_default(&module_code).compatible_type();
+            tx.begin().await?;
+            TardisFuns::dict.add("__TASK_PROCESSOR_INIT__", "", "", &tx).await?;
+            tx.init(async_task::ActiveModel::init(db_kind, Some("update_time"), compatible_type)).await?;
+            tx.commit().await?;
+        }
+        Self::recover_orphaned_tasks(funs).await?;
+        if TASK_HEARTBEAT_MODULES.write().await.insert(module_code.clone()) {
+            tardis::tokio::spawn(async move {
+                let mut interval = time::interval(Duration::from_secs(TASK_HEARTBEAT_INTERVAL_SEC));
+                loop {
+                    interval.tick().await;
+                    let funs = TardisFuns::inst_with_db_conn(module_code.clone(), None);
+                    if let Err(e) = Self::heartbeat(&funs).await {
+                        log::error!("[Bios.Task] heartbeat error:{:?}", e);
+                    }
+                    if let Err(e) = Self::recover_orphaned_tasks(&funs).await {
+                        log::error!("[Bios.Task] recover orphaned tasks error:{:?}", e);
+                    }
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Register the task function
+    ///
+    /// 注册任务函数
+    ///
+    /// The task function receives the task id, the task parameters and the owner context, and returns the task result.
+    ///
+    /// 任务函数接收任务id、任务参数及所有者上下文，返回任务结果。
+    ///
+    /// When ``idempotent`` is ``false``, the orphaned task will not be re-executed by other nodes, but marked as failed and needs to be retried manually.
+    ///
+    /// 当 ``idempotent`` 为 ``false`` 时，孤儿任务不会被其它节点重新执行，而是标记为失败，需要手工重试。
+    pub async fn register_task_fun<P, T>(task_type: &str, idempotent: bool, process_fun: P)
+    where
+        P: Fn(u64, Value, TardisContext) -> T + Send + Sync + 'static,
+        T: Future<Output = TardisResult<Value>> + Send + 'static,
+    {
+        let task_fun: TaskFun = Arc::new(move |task_id, params, ctx| Box::pin(process_fun(task_id, params, ctx)));
+        TASK_FUNS.write().await.insert(task_type.to_string(), RegisteredTaskFun { fun: task_fun, idempotent });
+    }
+
+    /// Execute the registered task and persist it
+    ///
+    /// 执行已注册的任务并持久化
+    #[allow(clippy::too_many_arguments)]
+    pub async fn execute_registered_task(
+        cache_key: &str,
+        task_type: &str,
+        params: Value,
+        max_retry_times: i32,
+        from_avatar: String,
+        to_avatars: Option<Vec<String>>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<u64> {
+        if !TASK_FUNS.read().await.contains_key(task_type) {
+            return Err(funs.err().not_found("task", "execute", &format!("task function {task_type} is not registered"), "404-task-fun-not-exist"));
+        }
+        let task_id = TaskProcessor::init_status(cache_key, None, &funs.cache()).await?;
+        // Use an independent connection so that the record is not affected by the transaction of the caller
+        //
+        // 使用独立的连接，使记录不受调用方事务的影响
+        let record_funs = TardisFuns::inst_with_db_conn(funs.module_code().to_string(), None);
+        record_funs
+            .db()
+            .insert_one(
+                async_task::ActiveModel {
+                    id: Set(task_id.to_string()),
+                    cache_key: Set(cache_key.to_string()),
+                    task_type: Set(task_type.to_string()),
+                    status: Set(AsyncTaskStatusKind::Running.to_string()),
+                    params: Set(Some(params.clone())),
+                    progress: Set(None),
+                    result: Set(None),
+                    error: Set("".to_string()),
+                    retry_times: Set(0),
+                    max_retry_times: Set(max_retry_times),
+                    ctx: Set(TardisFuns::json.obj_to_string(ctx)?),
+                    node_id: Set(NODE_ID.to_string()),
+                    heartbeat_time: Set(Utc::now()),
+                    own_paths: Set(ctx.own_paths.clone()),
+                    owner: Set(ctx.owner.clone()),
+                    ..Default::default()
+                },
+                ctx,
+            )
+            .await?;
+        Self::spawn_registered_task(
+            funs.module_code().to_string(),
+            cache_key.to_string(),
+            task_id,
+            task_type.to_string(),
+            params,
+            0,
+            max_retry_times,
+            ctx.clone(),
+            from_avatar,
+            to_avatars,
+        )
+        .await?;
+        Self::add_task_id_to_ctx(task_id, ctx).await?;
+        Ok(task_id)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn spawn_registered_task(
+        module_code: String,
+        cache_key: String,
+        task_id: u64,
+        task_type: String,
+        params: Value,
+        retry_times: i32,
+        max_retry_times: i32,
+        ctx: TardisContext,
+        from_avatar: String,
+        to_avatars: Option<Vec<String>>,
+    ) -> TardisResult<()> {
+        let task_fun = TASK_FUNS
+            .read()
+            .await
+            .get(&task_type)
+            .map(|task_fun| task_fun.fun.clone())
+            .ok_or_else(|| TardisError::not_found(&format!("task function {task_type} is not registered"), "404-task-fun-not-exist"))?;
+        // Hold the lock until the handle is inserted, so that the handle removal at the end of the task always happens after the insertion
+        //
+        // 持有锁直到句柄插入，以保证任务结束时的句柄移除总是发生在插入之后
+        let mut task_handles = TASK_HANDLE.write().await;
+        let handle = tardis::tokio::spawn(async move {
+            let mut retry_times = retry_times;
+            let (status, result, error) = loop {
+                match task_fun(task_id, params.clone(), ctx.clone()).await {
+                    Ok(result) => break (AsyncTaskStatusKind::Finished, Some(result), "".to_string()),
+                    Err(e) if retry_times < max_retry_times => {
+                        retry_times += 1;
+                        log::warn!("[Bios.Task] task [{}] process error, retry {}/{}:{:?}", task_id, retry_times, max_retry_times, e);
+                        let funs = TardisFuns::inst_with_db_conn(module_code.clone(), None);
+                        if let Err(e) = Self::update_task_record(task_id, None, None, &e.to_string(), true, &funs).await {
+                            log::error!("[Bios.Task] task [{}] update record error:{:?}", task_id, e);
+                        }
+                    }
+                    Err(e) => break (AsyncTaskStatusKind::Failed, None, e.to_string()),
+                }
+            };
+            if status == AsyncTaskStatusKind::Failed {
+                log::error!("Asynchronous task [{}] process error:{}", task_id, error);
+            }
+            let funs = TardisFuns::inst_with_db_conn(module_code, None);
+            if let Err(e) = Self::update_task_record(task_id, Some(status), result, &error, false, &funs).await {
+                log::error!("[Bios.Task] task [{}] update record error:{:?}", task_id, e);
+            }
+            // Failed tasks are also marked as completed, the caller can get the error from the task record
+            //
+            // 失败的任务也标记为已完成，调用方可以从任务记录中获取错误信息
+            if let Err(e) = TaskProcessor::set_status_with_event(&cache_key, task_id, true, &funs.cache(), from_avatar, to_avatars).await {
+                log::error!("Asynchronous task [{}] process error:{:?}", task_id, e);
+            }
+            TASK_HANDLE.write().await.remove(&task_id);
+        });
+        task_handles.insert(task_id, handle);
+        Ok(())
+    }
+
+    async fn update_task_record(
+        task_id: u64,
+        status: Option<AsyncTaskStatusKind>,
+        result: Option<Value>,
+        error: &str,
+        incr_retry: bool,
+        funs: &TardisFunsInst,
+    ) -> TardisResult<()> {
+        let mut query = Query::update();
+        query.table(async_task::Entity).value(async_task::Column::Error, error).value(async_task::Column::UpdateTime, Utc::now());
+        if let Some(status) = status {
+            query.value(async_task::Column::Status, status.to_string());
+        }
+        if result.is_some() {
+            query.value(async_task::Column::Result, result);
+        }
+        if incr_retry {
+            query.value(async_task::Column::RetryTimes, Expr::col(async_task::Column::RetryTimes).add(1));
+        }
+        // Only the running task of the current node can be updated, to avoid overwriting the cancellation or the takeover by another node
+        //
+        // 只能更新当前节点的执行中任务，以避免覆盖取消操作或其它节点的接管
+        query
+            .and_where(Expr::col(async_task::Column::Id).eq(task_id.to_string()))
+            .and_where(Expr::col(async_task::Column::Status).eq(AsyncTaskStatusKind::Running.to_string()))
+            .and_where(Expr::col(async_task::Column::NodeId).eq(NODE_ID.as_str()));
+        funs.db().execute(&query).await?;
+        Ok(())
+    }
+
+    /// Set the progress of the registered task
+    ///
+    /// 设置已注册任务的进度
+    pub async fn set_task_progress(task_id: u64, progress: Value, funs: &TardisFunsInst) -> TardisResult<()> {
+        let mut query = Query::update();
+        query
+            .table(async_task::Entity)
+            .value(async_task::Column::Progress, Some(progress))
+            .value(async_task::Column::UpdateTime, Utc::now())
+            .and_where(Expr::col(async_task::Column::Id).eq(task_id.to_string()));
+        funs.db().execute(&query).await?;
+        Ok(())
+    }
+
+    fn package_task_query() -> SelectStatement {
+        let mut query = Query::select();
+        query
+            .columns([
+                async_task::Column::Id,
+                async_task::Column::CacheKey,
+                async_task::Column::TaskType,
+                async_task::Column::Status,
+                async_task::Column::Params,
+                async_task::Column::Progress,
+                async_task::Column::Result,
+                async_task::Column::Error,
+                async_task::Column::RetryTimes,
+                async_task::Column::MaxRetryTimes,
+                async_task::Column::NodeId,
+                async_task::Column::HeartbeatTime,
+                async_task::Column::OwnPaths,
+                async_task::Column::Owner,
+                async_task::Column::CreateTime,
+                async_task::Column::UpdateTime,
+            ])
+            .from(async_task::Entity);
+        query
+    }
+
+    /// Only the tasks of the own paths of the context and its sub paths are visible
+    fn scope_task_query(query: &mut SelectStatement, ctx: &TardisContext) {
+        query.and_where(Expr::col(async_task::Column::OwnPaths).like(format!("{}%", ctx.own_paths)));
+    }
+
+    /// Get the registered task
+    ///
+    /// 获取已注册的任务
+    ///
+    /// Only the tasks of the own paths of the context and its sub paths can be got.
+    ///
+    /// 只能获取上下文所属路径及其子路径的任务。
+    pub async fn get_task(cache_key: &str, task_id: u64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<AsyncTaskDetailResp>> {
+        let mut query = Self::package_task_query();
+        query.and_where(Expr::col(async_task::Column::Id).eq(task_id.to_string())).and_where(Expr::col(async_task::Column::CacheKey).eq(cache_key));
+        Self::scope_task_query(&mut query, ctx);
+        funs.db().get_dto(&query).await
+    }
+
+    /// Find and page to get the registered tasks
+    ///
+    /// 查询并分页获取已注册的任务
+    pub async fn paginate_tasks(
+        cache_key: &str,
+        filter: &AsyncTaskFilterReq,
+        page_number: u32,
+        page_size: u32,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<TardisPage<AsyncTaskDetailResp>> {
+        let mut query = Self::package_task_query();
+        query.and_where(Expr::col(async_task::Column::CacheKey).eq(cache_key));
+        if let Some(task_type) = &filter.task_type {
+            query.and_where(Expr::col(async_task::Column::TaskType).eq(task_type));
+        }
+        if let Some(status) = &filter.status {
+            query.and_where(Expr::col(async_task::Column::Status).eq(status.to_string()));
+        }
+        if filter.with_sub_own_paths {
+            Self::scope_task_query(&mut query, ctx);
+        } else {
+            query.and_where(Expr::col(async_task::Column::OwnPaths).eq(ctx.own_paths.as_str()));
+        }
+        query.order_by(async_task::Column::CreateTime, Order::Desc);
+        let (records, total_size) = funs.db().paginate_dtos(&query, page_number as u64, page_size as u64).await?;
+        Ok(TardisPage {
+            page_size: page_size as u64,
+            page_number: page_number as u64,
+            total_size,
+            records,
+        })
+    }
+
+    /// Cancel the task
+    ///
+    /// 取消任务
+    ///
+    /// If the task is running on another node, that node will abort it on its next heartbeat.
+    /// Tasks that are not registered are stopped in the same way as [`TaskProcessor::stop_task_with_event`].
+    /// The registered tasks out of the own paths of the context and its sub paths can't be cancelled.
+    ///
+    /// 如果任务在其它节点执行，该节点会在下次心跳时中止它。未注册的任务按 [`TaskProcessor::stop_task_with_event`] 的方式停止。
+    /// 不能取消上下文所属路径及其子路径以外的已注册任务。
+    pub async fn cancel_task(cache_key: &str, task_id: u64, from_avatar: String, to_avatars: Option<Vec<String>>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let registered = funs
+            .db()
+            .count(
+                Query::select()
+                    .column(async_task::Column::Id)
+                    .from(async_task::Entity)
+                    .and_where(Expr::col(async_task::Column::Id).eq(task_id.to_string()))
+                    .and_where(Expr::col(async_task::Column::CacheKey).eq(cache_key)),
+            )
+            .await?
+            > 0;
+        if registered && Self::get_task(cache_key, task_id, funs, ctx).await?.is_none() {
+            return Err(funs.err().not_found("task", "cancel", &format!("task {task_id} not found"), "404-task-not-exist"));
+        }
+        let mut query = Query::update();
+        query
+            .table(async_task::Entity)
+            .value(async_task::Column::Status, AsyncTaskStatusKind::Cancelled.to_string())
+            .value(async_task::Column::UpdateTime, Utc::now())
+            .and_where(Expr::col(async_task::Column::Id).eq(task_id.to_string()))
+            .and_where(Expr::col(async_task::Column::Status).eq(AsyncTaskStatusKind::Running.to_string()));
+        funs.db().execute(&query).await?;
+        Self::stop_task_with_event(cache_key, task_id, &funs.cache(), from_avatar, to_avatars).await
+    }
+
+    /// Retry the failed or cancelled task on the current node
+    ///
+    /// 在当前节点重试失败或已取消的任务
+    ///
+    /// Only the tasks of the own paths of the context and its sub paths can be retried.
+    ///
+    /// 只能重试上下文所属路径及其子路径的任务。
+    pub async fn retry_task(cache_key: &str, task_id: u64, from_avatar: String, to_avatars: Option<Vec<String>>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let mut query = Query::select();
+        query
+            .columns(async_task::Column::iter())
+            .from(async_task::Entity)
+            .and_where(Expr::col(async_task::Column::Id).eq(task_id.to_string()))
+            .and_where(Expr::col(async_task::Column::CacheKey).eq(cache_key));
+        Self::scope_task_query(&mut query, ctx);
+        let task = funs
+            .db()
+            .get_dto::<async_task::Model>(&query)
+            .await?
+            .ok_or_else(|| funs.err().not_found("task", "retry", &format!("task {task_id} not found"), "404-task-not-exist"))?;
+        let status = AsyncTaskStatusKind::from_str(&task.status).map_err(|_| funs.err().format_error("task", "retry", "task status is invalid", "406-task-status-invalid"))?;
+        if status != AsyncTaskStatusKind::Failed && status != AsyncTaskStatusKind::Cancelled {
+            return Err(funs.err().conflict(
+                "task",
+                "retry",
+                &format!("task {task_id} is {status}, only failed or cancelled task can be retried"),
+                "409-task-status-conflict",
+            ));
+        }
+        if !Self::claim_task(&task, Some(status), funs).await? {
+            return Err(funs.err().conflict("task", "retry", &format!("task {task_id} has been retried by others"), "409-task-status-conflict"));
+        }
+        TaskProcessor::set_status(cache_key, task_id, false, &funs.cache()).await?;
+        Self::spawn_registered_task(
+            funs.module_code().to_string(),
+            task.cache_key,
+            task_id,
+            task.task_type,
+            task.params.unwrap_or(Value::Null),
+            task.retry_times + 1,
+            task.max_retry_times,
+            TardisFuns::json.str_to_obj(&task.ctx)?,
+            from_avatar,
+            to_avatars,
+        )
+        .await
+    }
+
+    /// Claim the task for the current node
+    ///
+    /// 当前节点认领任务
+    ///
+    /// When ``from_status`` is ``None``, only the orphaned running task can be claimed.
+    ///
+    /// 当 ``from_status`` 为 ``None`` 时，只能认领孤儿执行中任务。
+    async fn claim_task(task: &async_task::Model, from_status: Option<AsyncTaskStatusKind>, funs: &TardisFunsInst) -> TardisResult<bool> {
+        let mut query = Query::update();
+        query
+            .table(async_task::Entity)
+            .value(async_task::Column::Status, AsyncTaskStatusKind::Running.to_string())
+            .value(async_task::Column::NodeId, NODE_ID.as_str())
+            .value(async_task::Column::HeartbeatTime, Utc::now())
+            .value(async_task::Column::UpdateTime, Utc::now())
+            .value(async_task::Column::RetryTimes, Expr::col(async_task::Column::RetryTimes).add(1))
+            .value(async_task::Column::Error, "")
+            .and_where(Expr::col(async_task::Column::Id).eq(task.id.as_str()));
+        if let Some(from_status) = from_status {
+            query.and_where(Expr::col(async_task::Column::Status).eq(from_status.to_string()));
+        } else {
+            query
+                .and_where(Expr::col(async_task::Column::Status).eq(AsyncTaskStatusKind::Running.to_string()))
+                .and_where(Expr::col(async_task::Column::NodeId).eq(task.node_id.as_str()))
+                .and_where(Expr::col(async_task::Column::HeartbeatTime).lt(Utc::now() - chrono::Duration::seconds(TASK_ORPHAN_TIMEOUT_SEC)));
+        }
+        Ok(funs.db().execute(&query).await?.rows_affected() == 1)
+    }
+
+    /// Take over the orphaned tasks
+    ///
+    /// 接管孤儿任务
+    ///
+    /// Orphaned tasks are the running tasks whose node has not sent a heartbeat for a while (e.g. the node is restarted).
+    /// Only the tasks whose task function is registered on the current node will be taken over.
+    /// Non-idempotent tasks are marked as failed instead of being re-executed, because they may have been partially executed.
+    ///
+    /// 孤儿任务是指节点一段时间内未发送心跳（如节点重启）的执行中任务。只有在当前节点注册了任务函数的任务才会被接管。
+    /// 非幂等任务可能已部分执行，所以会被标记为失败而不是重新执行。
+    pub async fn recover_orphaned_tasks(funs: &TardisFunsInst) -> TardisResult<()> {
+        let (task_types, non_idempotent_task_types) = {
+            let task_funs = TASK_FUNS.read().await;
+            (
+                task_funs.keys().cloned().collect::<Vec<_>>(),
+                task_funs.iter().filter(|(_, task_fun)| !task_fun.idempotent).map(|(task_type, _)| task_type.clone()).collect::<HashSet<_>>(),
+            )
+        };
+        if task_types.is_empty() {
+            return Ok(());
+        }
+        let orphaned_tasks = funs
+            .db()
+            .find_dtos::<async_task::Model>(
+                Query::select()
+                    .columns(async_task::Column::iter())
+                    .from(async_task::Entity)
+                    .and_where(Expr::col(async_task::Column::Status).eq(AsyncTaskStatusKind::Running.to_string()))
+                    .and_where(Expr::col(async_task::Column::NodeId).ne(NODE_ID.as_str()))
+                    .and_where(Expr::col(async_task::Column::TaskType).is_in(task_types))
+                    .and_where(Expr::col(async_task::Column::HeartbeatTime).lt(Utc::now() - chrono::Duration::seconds(TASK_ORPHAN_TIMEOUT_SEC))),
+            )
+            .await?;
+        for task in orphaned_tasks {
+            let task_id = task.id.parse::<u64>().map_err(|_| funs.err().format_error("task", "recover", "task id format error", "406-task-id-format"))?;
+            if non_idempotent_task_types.contains(&task.task_type) {
+                if Self::fail_orphaned_task(&task, funs).await? {
+                    log::warn!("[Bios.Task] orphaned task [{}] of node [{}] is not idempotent, marked as failed", task_id, task.node_id);
+                    TaskProcessor::set_status(&task.cache_key, task_id, true, &funs.cache()).await?;
+                }
+                continue;
+            }
+            if !Self::claim_task(&task, None, funs).await? {
+                continue;
+            }
+            log::info!("[Bios.Task] take over orphaned task [{}] of node [{}]", task_id, task.node_id);
+            let ctx: TardisContext = TardisFuns::json.str_to_obj(&task.ctx)?;
+            Self::spawn_registered_task(
+                funs.module_code().to_string(),
+                task.cache_key,
+                task_id,
+                task.task_type,
+                task.params.unwrap_or(Value::Null),
+                task.retry_times + 1,
+                task.max_retry_times,
+                ctx,
+                "".to_string(),
+                None,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Mark the orphaned task as failed, return ``false`` if it has been handled by others
+    ///
+    /// 将孤儿任务标记为失败，如果已被其它节点处理则返回 ``false``
+    async fn fail_orphaned_task(task: &async_task::Model, funs: &TardisFunsInst) -> TardisResult<bool> {
+        let mut query = Query::update();
+        query
+            .table(async_task::Entity)
+            .value(async_task::Column::Status, AsyncTaskStatusKind::Failed.to_string())
+            .value(
+                async_task::Column::Error,
+                format!("task was interrupted on node {}, please retry it manually", task.node_id),
+            )
+            .value(async_task::Column::UpdateTime, Utc::now())
+            .and_where(Expr::col(async_task::Column::Id).eq(task.id.as_str()))
+            .and_where(Expr::col(async_task::Column::Status).eq(AsyncTaskStatusKind::Running.to_string()))
+            .and_where(Expr::col(async_task::Column::NodeId).eq(task.node_id.as_str()))
+            .and_where(Expr::col(async_task::Column::HeartbeatTime).lt(Utc::now() - chrono::Duration::seconds(TASK_ORPHAN_TIMEOUT_SEC)));
+        Ok(funs.db().execute(&query).await?.rows_affected() == 1)
+    }
+
+    /// Refresh the heartbeat of the tasks running on the current node and abort the tasks cancelled by other nodes
+    ///
+    /// 刷新当前节点执行中任务的心跳并中止被其它节点取消的任务
+    async fn heartbeat(funs: &TardisFunsInst) -> TardisResult<()> {
+        let local_task_ids = TASK_HANDLE.read().await.keys().map(|task_id| task_id.to_string()).collect::<Vec<_>>();
+        if local_task_ids.is_empty() {
+            return Ok(());
+        }
+        let mut query = Query::update();
+        query
+            .table(async_task::Entity)
+            .value(async_task::Column::HeartbeatTime, Utc::now())
+            .and_where(Expr::col(async_task::Column::NodeId).eq(NODE_ID.as_str()))
+            .and_where(Expr::col(async_task::Column::Status).eq(AsyncTaskStatusKind::Running.to_string()))
+            .and_where(Expr::col(async_task::Column::Id).is_in(local_task_ids.clone()));
+        funs.db().execute(&query).await?;
+        let cancelled_tasks = funs
+            .db()
+            .find_dtos::<async_task::Model>(
+                Query::select()
+                    .columns(async_task::Column::iter())
+                    .from(async_task::Entity)
+                    .and_where(Expr::col(async_task::Column::Status).eq(AsyncTaskStatusKind::Cancelled.to_string()))
+                    .and_where(Expr::col(async_task::Column::Id).is_in(local_task_ids)),
+            )
+            .await?;
+        for task in cancelled_tasks {
+            if let Ok(task_id) = task.id.parse::<u64>() {
+                if let Some(handle) = TASK_HANDLE.write().await.remove(&task_id) {
+                    log::info!("[Bios.Task] abort task [{}] cancelled by other node", task_id);
+                    handle.abort();
+                }
+            }
+        }
+        Ok(())
+    }
 }
 #[cfg(feature = "with-mq")]
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -363,3 +363,61 @@ pub struct RbumRelDetailResp {
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
+
+/// Resource relationship quota information
+///
+/// 资源关联配额信息
+///
+/// Generated from the [`crate::rbum::rbum_enumeration::RbumRelEnvKind::CallFrequency`] and [`crate::rbum::rbum_enumeration::RbumRelEnvKind::CallCount`] environments of the relationship.
+///
+/// 由关联的 [`crate::rbum::rbum_enumeration::RbumRelEnvKind::CallFrequency`] 及 [`crate::rbum::rbum_enumeration::RbumRelEnvKind::CallCount`] 环境生成。
+#[derive(Serialize, Deserialize, Debug, Clone, Default, poem_openapi::Object)]
+pub struct RbumRelQuotaResp {
+    /// Relationship id
+    ///
+    /// 关联id
+    pub rel_id: String,
+    /// Whether the quota is passed
+    ///
+    /// 是否通过配额检查
+    pub passed: bool,
+    /// Maximum number of calls in the window
+    ///
+    /// 窗口内的最大调用次数
+    pub call_frequency_limit: Option<i64>,
+    /// Remaining number of calls in the window
+    ///
+    /// 窗口内的剩余调用次数
+    pub call_frequency_remaining: Option<i64>,
+    /// Seconds until the window is reset
+    ///
+    /// 距离窗口重置的秒数
+    pub call_frequency_reset_sec: Option<i64>,
+    /// Maximum number of calls in the lifetime
+    ///
+    /// 生命周期内的最大调用次数
+    pub call_count_limit: Option<i64>,
+    /// Remaining number of calls in the lifetime
+    ///
+    /// 生命周期内的剩余调用次数
+    pub call_count_remaining: Option<i64>,
+}
+
+/// Resource relationship check result with quota
+///
+/// 带配额信息的资源关联检查结果
+#[derive(Serialize, Deserialize, Debug, Clone, Default, poem_openapi::Object)]
+pub struct RbumRelCheckWithQuotaResp {
+    /// Whether the relationship exists and the quota is passed
+    ///
+    /// 关联是否存在且通过配额检查
+    pub passed: bool,
+    /// Quota information
+    ///
+    /// 配额信息
+    ///
+    /// Only has value when the matched relationship has quota environments.
+    ///
+    /// 仅当匹配的关联存在配额环境时有值。
+    pub quota: Option<RbumRelQuotaResp>,
+}
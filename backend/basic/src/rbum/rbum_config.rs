@@ -61,6 +61,22 @@ pub struct RbumConfig {
     ///
     /// Format: ``rbum_item_id -> error times by cycle``
     pub cache_key_cert_err_times_: String,
+    /// Cache key prefix for relationship call frequency counter
+    ///
+    /// 关联调用频率计数器的缓存键前缀
+    ///
+    /// Format: ``rel_id:window index -> call times in window``
+    pub cache_key_rel_call_frequency_: String,
+    /// Cache key prefix for relationship call count counter
+    ///
+    /// 关联调用次数计数器的缓存键前缀
+    ///
+    /// Format: ``rel_id -> call times``
+    pub cache_key_rel_call_count_: String,
+    /// Default window size(seconds) of the relationship call frequency
+    ///
+    /// 关联调用频率的默认窗口大小（秒）
+    pub rel_call_frequency_window_sec: u32,
     /// Event domain configuration
     ///
     /// 事件域配置
@@ -87,6 +103,9 @@ impl Default for RbumConfig {
             cache_key_set_code_expire_sec: 60 * 60 * 24,
             cache_key_cert_locked_: "rbum:cert:locked:".to_string(),
             cache_key_cert_err_times_: "rbum:cert:err_times:".to_string(),
+            cache_key_rel_call_frequency_: "rbum:rel:call_frequency:".to_string(),
+            cache_key_rel_call_count_: "rbum:rel:call_count:".to_string(),
+            rel_call_frequency_window_sec: 60,
             event_domains: HashMap::from([("rbum_".to_string(), "cud".to_string())]),
             head_key_bios_ctx: "Bios-Ctx".to_string(),
         }
@@ -132,6 +151,9 @@ pub trait RbumConfigApi {
     fn rbum_conf_cache_key_set_code_expire_sec(&self) -> usize;
     fn rbum_conf_cache_key_cert_locked_(&self) -> String;
     fn rbum_conf_cache_key_cert_err_times_(&self) -> String;
+    fn rbum_conf_cache_key_rel_call_frequency_(&self) -> String;
+    fn rbum_conf_cache_key_rel_call_count_(&self) -> String;
+    fn rbum_conf_rel_call_frequency_window_sec(&self) -> u32;
     fn rbum_conf_match_event(&self, table_name: &str, operate: &str) -> bool;
     fn rbum_head_key_bios_ctx(&self) -> String;
 }
@@ -177,6 +199,18 @@ impl RbumConfigApi for TardisFunsInst {
         RbumConfigManager::get_config(self.module_code(), |conf| conf.cache_key_cert_err_times_.to_string())
     }
 
+    fn rbum_conf_cache_key_rel_call_frequency_(&self) -> String {
+        RbumConfigManager::get_config(self.module_code(), |conf| conf.cache_key_rel_call_frequency_.to_string())
+    }
+
+    fn rbum_conf_cache_key_rel_call_count_(&self) -> String {
+        RbumConfigManager::get_config(self.module_code(), |conf| conf.cache_key_rel_call_count_.to_string())
+    }
+
+    fn rbum_conf_rel_call_frequency_window_sec(&self) -> u32 {
+        RbumConfigManager::get_config(self.module_code(), |conf| conf.rel_call_frequency_window_sec)
+    }
+
     fn rbum_conf_match_event(&self, table_name: &str, operate: &str) -> bool {
         RbumConfigManager::match_event(self.module_code(), table_name, operate)
     }
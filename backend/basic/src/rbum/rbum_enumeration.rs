@@ -321,17 +321,21 @@ pub enum RbumRelEnvKind {
     ///
     /// 调用频率
     ///
-    /// Request value must be less than or equal to the set value.
+    /// Format: ``value1`` = maximum number of calls in the window, ``value2`` = window size in seconds (optional, see [`crate::rbum::rbum_config::RbumConfig::rel_call_frequency_window_sec`]).
+    /// The request value is the number of calls to consume, counted with a sliding window.
     ///
-    /// 请求的值要小于等于设置的值。
+    /// 格式：``value1`` = 窗口内的最大调用次数，``value2`` = 窗口大小（秒，可选）。
+    /// 请求的值为本次要消耗的调用次数，使用滑动窗口计数。
     CallFrequency,
     /// Call count
     ///
     /// 调用次数
     ///
-    /// Request value must be less than or equal to the set value.
+    /// Format: ``value1`` = maximum number of calls in the lifetime of the relationship.
+    /// The request value is the number of calls to consume.
     ///
-    /// 请求的值要小于等于设置的值。
+    /// 格式：``value1`` = 关联生命周期内的最大调用次数。
+    /// 请求的值为本次要消耗的调用次数。
     CallCount,
 }
 
@@ -1221,6 +1221,22 @@ impl RbumCertServ {
         Ok(())
     }
 
+    /// Modify the ext only if it has not been changed since it was read, returns whether it is modified.
+    /// The ownership should be checked when reading the cert.
+    ///
+    /// 仅当扩展信息自读取后未被修改时才修改，返回是否修改成功。读取证书时应已校验归属。
+    pub async fn modify_ext_if_unchanged(id: &str, original_ext: &str, new_ext: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        let mut query = Query::update();
+        query
+            .table(rbum_cert::Entity)
+            .value(rbum_cert::Column::Ext, new_ext)
+            .value(rbum_cert::Column::UpdateTime, Utc::now())
+            .value(rbum_cert::Column::UpdateBy, ctx.owner.as_str())
+            .and_where(Expr::col(rbum_cert::Column::Id).eq(id))
+            .and_where(Expr::col(rbum_cert::Column::Ext).eq(original_ext));
+        Ok(funs.db().execute(&query).await?.rows_affected() == 1)
+    }
+
     /// Change sk
     ///
     /// 更改sk
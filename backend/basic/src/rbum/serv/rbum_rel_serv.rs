@@ -4,7 +4,10 @@ use async_trait::async_trait;
 use itertools::Itertools;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::cache::AsyncCommands;
+use tardis::chrono::Utc;
 use tardis::db::reldb_client::IdResp;
+use tardis::db::sea_orm;
 use tardis::db::sea_orm::sea_query::*;
 use tardis::db::sea_orm::IdenStatic;
 use tardis::db::sea_orm::*;
@@ -17,8 +20,11 @@ use crate::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumRelExtFilterReq,
 use crate::rbum::dto::rbum_rel_agg_dto::{RbumRelAggAddReq, RbumRelAggResp};
 use crate::rbum::dto::rbum_rel_attr_dto::{RbumRelAttrAddReq, RbumRelAttrDetailResp, RbumRelAttrModifyReq};
 use crate::rbum::dto::rbum_rel_dto::RbumRelEnvCheckReq;
-use crate::rbum::dto::rbum_rel_dto::{RbumRelAddReq, RbumRelBoneResp, RbumRelCheckReq, RbumRelDetailResp, RbumRelModifyReq, RbumRelSimpleFindReq};
+use crate::rbum::dto::rbum_rel_dto::{
+    RbumRelAddReq, RbumRelBoneResp, RbumRelCheckReq, RbumRelCheckWithQuotaResp, RbumRelDetailResp, RbumRelModifyReq, RbumRelQuotaResp, RbumRelSimpleFindReq,
+};
 use crate::rbum::dto::rbum_rel_env_dto::{RbumRelEnvAddReq, RbumRelEnvDetailResp, RbumRelEnvModifyReq};
+use crate::rbum::rbum_config::RbumConfigApi;
 use crate::rbum::rbum_enumeration::{RbumRelEnvKind, RbumRelFromKind, RbumSetCateLevelQueryKind};
 use crate::rbum::serv::rbum_crud_serv::{NameResp, RbumCrudOperation, RbumCrudQueryPackage};
 use crate::rbum::serv::rbum_item_serv::RbumItemServ;
@@ -865,11 +871,22 @@ impl RbumRelServ {
     /// Check whether the relationship of the specified condition exists
     ///
     /// 检查指定的条件的关联是否存在
+    ///
+    /// The quota environments ([`crate::rbum::rbum_enumeration::RbumRelEnvKind::CallFrequency`] and [`crate::rbum::rbum_enumeration::RbumRelEnvKind::CallCount`]) are consumed when the check passes.
+    ///
+    /// 检查通过时会消耗配额环境（调用频率及调用次数）。
     pub async fn check_rel(check_req: &RbumRelCheckReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        Ok(Self::check_rel_with_quota(check_req, funs, ctx).await?.passed)
+    }
+
+    /// Check whether the relationship of the specified condition exists and return the quota information
+    ///
+    /// 检查指定的条件的关联是否存在并返回配额信息
+    pub async fn check_rel_with_quota(check_req: &RbumRelCheckReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<RbumRelCheckWithQuotaResp> {
         // 1. Check whether the direct association exists
         //
         // 1. 检查直接关联是否存在
-        if Self::do_check_rel(
+        let direct_result = Self::do_check_rel(
             &check_req.tag,
             Some(vec![check_req.from_rbum_kind.clone()]),
             Some(vec![check_req.from_rbum_id.clone()]),
@@ -880,10 +897,14 @@ impl RbumRelServ {
             funs,
             ctx,
         )
-        .await?
-        {
-            return Ok(true);
+        .await?;
+        if direct_result.passed {
+            return Ok(direct_result);
         }
+        // Keep the first denied quota so that the caller can know why the check failed
+        //
+        // 保留第一个被拒绝的配额信息，以便调用方知道检查失败的原因
+        let mut denied_quota = direct_result.quota;
         // 2. Get the resource set categories(nodes)
         //
         // 2. 获取对应的资源集分类（节点）集合
@@ -916,7 +937,10 @@ impl RbumRelServ {
             let set_cate = RbumSetCateServ::peek_rbum(&check_req.from_rbum_id, &RbumSetCateFilterReq::default(), funs, ctx).await?;
             HashMap::from([(set_cate.rel_rbum_set_id.clone(), vec![(set_cate.id, set_cate.sys_code)])])
         } else {
-            return Ok(false);
+            return Ok(RbumRelCheckWithQuotaResp {
+                passed: false,
+                quota: denied_quota,
+            });
         };
 
         for (set_id, cates) in rel_rbum_set_cates {
@@ -952,7 +976,7 @@ impl RbumRelServ {
             // 4. Check whether the association on the resource set/resource set category(node) exists
             //
             // 4. 检查资源集/资源集分类（节点）上的关联是否存在
-            if Self::do_check_rel(
+            let set_result = Self::do_check_rel(
                 &check_req.tag,
                 // Two source types are used here, and the ids of these two types are nanoid, so the conflict probability is very low.
                 //
@@ -966,12 +990,18 @@ impl RbumRelServ {
                 funs,
                 ctx,
             )
-            .await?
-            {
-                return Ok(true);
+            .await?;
+            if set_result.passed {
+                return Ok(set_result);
+            }
+            if denied_quota.is_none() {
+                denied_quota = set_result.quota;
             }
         }
-        Ok(false)
+        Ok(RbumRelCheckWithQuotaResp {
+            passed: false,
+            quota: denied_quota,
+        })
     }
 
     /// Execute the relationship check
@@ -1032,7 +1062,12 @@ impl RbumRelServ {
         envs: &Vec<RbumRelEnvCheckReq>,
         funs: &TardisFunsInst,
         ctx: &TardisContext,
-    ) -> TardisResult<bool> {
+    ) -> TardisResult<RbumRelCheckWithQuotaResp> {
+        // The number of calls to be consumed by the quota environments
+        //
+        // 配额环境需要消耗的调用次数
+        let mut call_frequency_cost = None;
+        let mut call_count_cost = None;
         let mut query = Query::select();
         query.column((rbum_rel::Entity, rbum_rel::Column::Id)).from(rbum_rel::Entity);
         query.and_where(Expr::col((rbum_rel::Entity, rbum_rel::Column::Tag)).eq(tag));
@@ -1154,6 +1189,11 @@ impl RbumRelServ {
                     },
                     RbumRelEnvKind::CallFrequency | RbumRelEnvKind::CallCount => match env.value.parse::<i64>() {
                         Ok(num) => {
+                            if env.kind == RbumRelEnvKind::CallFrequency {
+                                call_frequency_cost = Some(num);
+                            } else {
+                                call_count_cost = Some(num);
+                            }
                             env_conds = env_conds.add(all![
                                 Expr::col(rbum_rel_env::Column::Kind).eq(env.kind.to_int()),
                                 Expr::expr(Func::cast_as(Expr::col(rbum_rel_env::Column::Value1), Alias::new("INTEGER"))).gte(num)
@@ -1207,7 +1247,156 @@ impl RbumRelServ {
             query.and_where(Expr::col((rbum_rel_env::Entity, rbum_rel_env::Column::Id)).is_null());
         }
 
-        funs.db().count(&query).await.map(|i| i > 0)
+        if call_frequency_cost.is_none() && call_count_cost.is_none() {
+            return Ok(RbumRelCheckWithQuotaResp {
+                passed: funs.db().count(&query).await? > 0,
+                quota: None,
+            });
+        }
+        // Consume the quota of the matched relationships one by one until one of them passes
+        //
+        // 逐个消耗匹配到的关联的配额，直到其中一个通过
+        let mut denied_quota = None;
+        for rel_id in funs.db().find_dtos::<IdResp>(&query).await?.into_iter().map(|i| i.id).unique() {
+            let quota = Self::do_consume_rel_quota(&rel_id, call_frequency_cost.unwrap_or(0), call_count_cost.unwrap_or(0), funs).await?;
+            if quota.passed {
+                return Ok(RbumRelCheckWithQuotaResp { passed: true, quota: Some(quota) });
+            }
+            if denied_quota.is_none() {
+                denied_quota = Some(quota);
+            }
+        }
+        Ok(RbumRelCheckWithQuotaResp {
+            passed: false,
+            quota: denied_quota,
+        })
+    }
+
+    /// Consume the quota of the relationship
+    ///
+    /// 消耗关联的配额
+    ///
+    /// The quota is defined by the [`crate::rbum::rbum_enumeration::RbumRelEnvKind::CallFrequency`] and [`crate::rbum::rbum_enumeration::RbumRelEnvKind::CallCount`] environments of the relationship,
+    /// if the quota is exceeded, nothing is consumed and ``passed`` of the response is ``false``.
+    ///
+    /// 配额由关联的调用频率及调用次数环境定义，如果超出配额，则不消耗任何配额且返回的 ``passed`` 为 ``false``。
+    pub async fn consume_rel_quota(rel_id: &str, cost: i64, funs: &TardisFunsInst) -> TardisResult<RbumRelQuotaResp> {
+        Self::do_consume_rel_quota(rel_id, cost, cost, funs).await
+    }
+
+    /// Get the quota of the relationship (without consuming)
+    ///
+    /// 获取关联的配额（不消耗）
+    pub async fn get_rel_quota(rel_id: &str, funs: &TardisFunsInst) -> TardisResult<RbumRelQuotaResp> {
+        Self::do_consume_rel_quota(rel_id, 0, 0, funs).await
+    }
+
+    /// Reset the quota counters of the relationship
+    ///
+    /// 重置关联的配额计数器
+    pub async fn reset_rel_quota(rel_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.cache().del(&format!("{}{}", funs.rbum_conf_cache_key_rel_call_count_(), rel_id)).await?;
+        if let Some(window_sec) =
+            Self::find_rel_quota_envs(rel_id, funs).await?.into_iter().find(|env| env.kind == RbumRelEnvKind::CallFrequency).map(|env| Self::parse_window_sec(&env.value2, funs))
+        {
+            let window_idx = Utc::now().timestamp() / window_sec;
+            funs.cache().del(&format!("{}{}:{}", funs.rbum_conf_cache_key_rel_call_frequency_(), rel_id, window_idx)).await?;
+            funs.cache().del(&format!("{}{}:{}", funs.rbum_conf_cache_key_rel_call_frequency_(), rel_id, window_idx - 1)).await?;
+        }
+        Ok(())
+    }
+
+    async fn find_rel_quota_envs(rel_id: &str, funs: &TardisFunsInst) -> TardisResult<Vec<RbumRelQuotaEnvResp>> {
+        funs.db()
+            .find_dtos::<RbumRelQuotaEnvResp>(
+                Query::select()
+                    .columns([rbum_rel_env::Column::Kind, rbum_rel_env::Column::Value1, rbum_rel_env::Column::Value2])
+                    .from(rbum_rel_env::Entity)
+                    .and_where(Expr::col(rbum_rel_env::Column::RelRbumRelId).eq(rel_id))
+                    .and_where(Expr::col(rbum_rel_env::Column::Kind).is_in([RbumRelEnvKind::CallFrequency.to_int(), RbumRelEnvKind::CallCount.to_int()])),
+            )
+            .await
+    }
+
+    fn parse_window_sec(value: &str, funs: &TardisFunsInst) -> i64 {
+        value.parse::<i64>().ok().filter(|window_sec| *window_sec > 0).unwrap_or(funs.rbum_conf_rel_call_frequency_window_sec() as i64)
+    }
+
+    /// Consume the quota of the relationship
+    ///
+    /// 消耗关联的配额
+    ///
+    /// The call frequency uses a sliding window counter: the number of calls in the previous fixed window is weighted by its overlap with the sliding window
+    /// and added to the number of calls in the current fixed window.
+    /// Counters are incremented atomically first and rolled back when the limit is exceeded, so concurrent callers will never exceed the limit.
+    ///
+    /// 调用频率使用滑动窗口计数：上一个固定窗口的调用次数按其与滑动窗口的重叠比例加权后，与当前固定窗口的调用次数相加。
+    /// 计数器先原子递增，超出限制时再回滚，所以并发调用也不会超出限制。
+    async fn do_consume_rel_quota(rel_id: &str, call_frequency_cost: i64, call_count_cost: i64, funs: &TardisFunsInst) -> TardisResult<RbumRelQuotaResp> {
+        let mut quota = RbumRelQuotaResp {
+            rel_id: rel_id.to_string(),
+            passed: true,
+            ..Default::default()
+        };
+        let envs = Self::find_rel_quota_envs(rel_id, funs).await?;
+        let mut cache_cmd = funs.cache().cmd().await?;
+        // Key and cost of the call frequency counter that has been incremented, used for rollback
+        //
+        // 已递增的调用频率计数器的键及消耗值，用于回滚
+        let mut incremented_frequency_key = None;
+        if let Some(env) = envs.iter().find(|env| env.kind == RbumRelEnvKind::CallFrequency) {
+            let limit = env.value1.parse::<i64>().unwrap_or_default();
+            let window_sec = Self::parse_window_sec(&env.value2, funs);
+            let now = Utc::now().timestamp();
+            let window_idx = now / window_sec;
+            let elapsed_sec = now % window_sec;
+            let current_key = format!("{}{}:{}", funs.rbum_conf_cache_key_rel_call_frequency_(), rel_id, window_idx);
+            let previous_key = format!("{}{}:{}", funs.rbum_conf_cache_key_rel_call_frequency_(), rel_id, window_idx - 1);
+            let current_count: i64 = cache_cmd.incr(&current_key, call_frequency_cost).await?;
+            if current_count == call_frequency_cost {
+                // Keep the counter for two windows so that it can be used as the previous window
+                //
+                // 计数器保留两个窗口的时间，以便作为上一个窗口使用
+                let _: () = cache_cmd.expire(&current_key, window_sec * 2).await?;
+            }
+            let previous_count = funs.cache().get(&previous_key).await?.and_then(|v| v.parse::<i64>().ok()).unwrap_or_default();
+            let estimated_count = previous_count * (window_sec - elapsed_sec) / window_sec + current_count;
+            quota.call_frequency_limit = Some(limit);
+            quota.call_frequency_reset_sec = Some(window_sec - elapsed_sec);
+            if estimated_count > limit {
+                let _: i64 = cache_cmd.incr(&current_key, -call_frequency_cost).await?;
+                quota.passed = false;
+                quota.call_frequency_remaining = Some((limit - estimated_count + call_frequency_cost).max(0));
+            } else {
+                quota.call_frequency_remaining = Some(limit - estimated_count);
+                if call_frequency_cost != 0 {
+                    incremented_frequency_key = Some(current_key);
+                }
+            }
+        }
+        if let Some(env) = envs.iter().find(|env| env.kind == RbumRelEnvKind::CallCount) {
+            let limit = env.value1.parse::<i64>().unwrap_or_default();
+            let count_key = format!("{}{}", funs.rbum_conf_cache_key_rel_call_count_(), rel_id);
+            quota.call_count_limit = Some(limit);
+            if quota.passed {
+                let current_count: i64 = cache_cmd.incr(&count_key, call_count_cost).await?;
+                if current_count > limit {
+                    let _: i64 = cache_cmd.incr(&count_key, -call_count_cost).await?;
+                    quota.passed = false;
+                    quota.call_count_remaining = Some((limit - current_count + call_count_cost).max(0));
+                    if let Some(frequency_key) = incremented_frequency_key {
+                        let _: i64 = cache_cmd.incr(&frequency_key, -call_frequency_cost).await?;
+                        quota.call_frequency_remaining = quota.call_frequency_remaining.map(|remaining| remaining + call_frequency_cost);
+                    }
+                } else {
+                    quota.call_count_remaining = Some(limit - current_count);
+                }
+            } else {
+                let current_count = funs.cache().get(&count_key).await?.and_then(|v| v.parse::<i64>().ok()).unwrap_or_default();
+                quota.call_count_remaining = Some((limit - current_count).max(0));
+            }
+        }
+        Ok(quota)
     }
 
     /// Delete the relationship(Including all conditions)
@@ -1242,10 +1431,18 @@ impl RbumRelServ {
         for rbum_rel_attr_id in rbum_rel_attr_ids {
             RbumRelAttrServ::delete_rbum(&rbum_rel_attr_id, funs, ctx).await?;
         }
+        funs.cache().del(&format!("{}{}", funs.rbum_conf_cache_key_rel_call_count_(), id)).await?;
         RbumRelServ::delete_rbum(id, funs, ctx).await
     }
 }
 
+#[derive(Debug, sea_orm::FromQueryResult)]
+struct RbumRelQuotaEnvResp {
+    pub kind: RbumRelEnvKind,
+    pub value1: String,
+    pub value2: String,
+}
+
 #[async_trait]
 impl RbumCrudOperation<rbum_rel_attr::ActiveModel, RbumRelAttrAddReq, RbumRelAttrModifyReq, RbumRelAttrDetailResp, RbumRelAttrDetailResp, RbumRelExtFilterReq> for RbumRelAttrServ {
     fn get_table_name() -> &'static str {
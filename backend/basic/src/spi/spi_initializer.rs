@@ -436,3 +436,152 @@ EXECUTE PROCEDURE TARDIS_AUTO_UPDATE_TIME_{}();"###,
         Ok(())
     }
 }
+
+/// Some common in-memory initialization helper methods
+/// 一些公共的内存初始化辅助方法
+///
+/// The in-memory backend implementation stores data in the current process, which is suitable for local development, unit testing and edge deployment.
+/// The data will be lost after the process restarts.
+///
+/// 内存后端实现将数据存储在当前进程中，适用于本地开发、单元测试及边缘部署。进程重启后数据将丢失。
+pub mod common_memory {
+    use std::any::{type_name, Any};
+    use std::cmp::Ordering;
+    use std::collections::HashMap;
+    use std::sync::{Arc, OnceLock};
+
+    use tardis::{
+        basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+        chrono::DateTime,
+        serde_json::Value,
+        tokio::sync::RwLock,
+    };
+
+    use crate::enumeration::BasicQueryOpKind;
+    use crate::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst};
+
+    use super::common;
+
+    /// In-memory storage client
+    /// 内存存储客户端
+    ///
+    /// Each SPI service defines its own storage structure ``T``.
+    /// 每个SPI服务定义各自的存储结构``T``。
+    pub type SpiMemoryClient<T> = Arc<RwLock<T>>;
+
+    fn get_memory_stores() -> &'static RwLock<HashMap<String, Arc<dyn Any + Send + Sync>>> {
+        static MEMORY_STORES: OnceLock<RwLock<HashMap<String, Arc<dyn Any + Send + Sync>>>> = OnceLock::new();
+        MEMORY_STORES.get_or_init(Default::default)
+    }
+
+    /// Get the store key
+    /// 获取存储键
+    ///
+    /// Stores with the same connection URI are regarded as the same in-memory instance,
+    /// the data of each subject of the request (tenant or application) will be isolated into different stores by the isolation flag, similar to the schema of PostgreSQL.
+    ///
+    /// 连接URI相同的存储视为同一个内存实例，每个请求主体（租户或应用）的数据都会通过隔离标识隔离到不同的存储中，类似于PostgreSQL的schema。
+    fn get_store_key<T>(conn_uri: &str, isolation_flag: Option<&str>) -> String {
+        format!("{}:{}:{}", type_name::<T>(), conn_uri, isolation_flag.unwrap_or_default())
+    }
+
+    /// Initialize the in-memory backend implementation instance
+    /// 初始化内存的后端实现实例
+    pub async fn init<T>(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst>
+    where
+        T: Default + Send + Sync + 'static,
+    {
+        let mut ext = HashMap::new();
+        // If the connection is private, the isolation does not need to be processed.
+        // 如果连接为私有的，不需要处理隔离
+        let store_key = if bs_cert.private {
+            get_store_key::<T>(&bs_cert.conn_uri, None)
+        } else {
+            let isolation_flag = common::get_isolation_flag_from_context(ctx);
+            common::set_isolation_flag_to_ext(&isolation_flag, &mut ext);
+            get_store_key::<T>(&bs_cert.conn_uri, Some(&isolation_flag))
+        };
+        let mut stores = get_memory_stores().write().await;
+        let store = if let Some(store) = stores.get(&store_key) {
+            store.clone().downcast::<RwLock<T>>().map_err(|_| TardisError::internal_error("The type of the memory store does not match", ""))?
+        } else if mgr || bs_cert.private {
+            // Only in management mode can the store be created
+            // 仅管理模式下才能创建存储
+            let store: SpiMemoryClient<T> = Arc::new(RwLock::new(T::default()));
+            stores.insert(store_key, store.clone());
+            store
+        } else {
+            return Err(TardisError::bad_request("The requested store does not exist", ""));
+        };
+        Ok(SpiBsInst { client: Box::new(store), ext })
+    }
+
+    /// Compare two values of the same type, date-time strings are compared by time
+    /// 比较两个相同类型的值，日期时间字符串按时间比较
+    pub fn compare_value(left: &Value, right: &Value) -> Option<Ordering> {
+        match (left, right) {
+            (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+            (Value::String(left), Value::String(right)) => match (DateTime::parse_from_rfc3339(left), DateTime::parse_from_rfc3339(right)) {
+                (Ok(left), Ok(right)) => Some(left.cmp(&right)),
+                _ => Some(left.cmp(right)),
+            },
+            (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+            _ => None,
+        }
+    }
+
+    /// Check whether the value matches the condition
+    /// 检查值是否满足条件
+    ///
+    /// The semantics are the same as the conditions on ``jsonb`` fields generated by the PostgreSQL implementations,
+    /// e.g. a missing field never matches except for the null checks, ``In`` matches if the field (or any element of the field array) equals any of the values.
+    ///
+    /// 语义与PostgreSQL实现中针对``jsonb``字段生成的条件一致，
+    /// 例如除空值判断外缺失的字段总是不匹配，``In``在字段（或字段数组中的任一元素）等于任一值时匹配。
+    pub fn match_cond(value: Option<&Value>, op: &BasicQueryOpKind, cond_value: &Value) -> bool {
+        let value = value.filter(|value| !value.is_null());
+        let cond_values = match cond_value {
+            Value::Array(cond_values) => cond_values.iter().collect::<Vec<_>>(),
+            _ => vec![cond_value],
+        };
+        match op {
+            BasicQueryOpKind::IsNull => return value.is_none(),
+            BasicQueryOpKind::IsNotNull => return value.is_some(),
+            BasicQueryOpKind::IsNullOrEmpty => return value.map(|value| value.as_str() == Some("")).unwrap_or(true),
+            _ => {}
+        }
+        let (Some(value), Some(first_cond_value)) = (value, cond_values.first().copied()) else {
+            return false;
+        };
+        let contains = |cond_value: &Value| match value {
+            Value::Array(values) => values.contains(cond_value),
+            _ => value == cond_value,
+        };
+        let like = |check: fn(&str, &str) -> bool| match (value.as_str(), first_cond_value.as_str()) {
+            (Some(value), Some(cond_value)) => check(value, cond_value),
+            _ => false,
+        };
+        match op {
+            BasicQueryOpKind::In => cond_values.into_iter().any(contains),
+            BasicQueryOpKind::NotIn => !cond_values.into_iter().any(contains),
+            BasicQueryOpKind::Eq => compare_value(value, first_cond_value) == Some(Ordering::Equal),
+            BasicQueryOpKind::Ne => compare_value(value, first_cond_value).map(|ord| ord != Ordering::Equal).unwrap_or(false),
+            BasicQueryOpKind::Gt => compare_value(value, first_cond_value) == Some(Ordering::Greater),
+            BasicQueryOpKind::Ge => matches!(compare_value(value, first_cond_value), Some(Ordering::Greater | Ordering::Equal)),
+            BasicQueryOpKind::Lt => compare_value(value, first_cond_value) == Some(Ordering::Less),
+            BasicQueryOpKind::Le => matches!(compare_value(value, first_cond_value), Some(Ordering::Less | Ordering::Equal)),
+            BasicQueryOpKind::Like => like(|value, cond_value| value.contains(cond_value)),
+            BasicQueryOpKind::NotLike => like(|value, cond_value| !value.contains(cond_value)),
+            BasicQueryOpKind::LLike => like(|value, cond_value| value.ends_with(cond_value)),
+            BasicQueryOpKind::NotLLike => like(|value, cond_value| !value.ends_with(cond_value)),
+            BasicQueryOpKind::RLike => like(|value, cond_value| value.starts_with(cond_value)),
+            BasicQueryOpKind::NotRLike => like(|value, cond_value| !value.starts_with(cond_value)),
+            BasicQueryOpKind::Len => match (value, first_cond_value.as_u64()) {
+                (Value::Array(values), Some(len)) => values.len() as u64 == len,
+                (Value::String(value), Some(len)) => value.chars().count() as u64 == len,
+                _ => false,
+            },
+            BasicQueryOpKind::IsNull | BasicQueryOpKind::IsNotNull | BasicQueryOpKind::IsNullOrEmpty => false,
+        }
+    }
+}
@@ -1222,6 +1222,44 @@ async fn test_rbum_rel_use(context: &TardisContext) -> TardisResult<()> {
         .await?
     );
 
+    info!("【test_rbum_rel_use】 : Test Check Quota : RbumRelServ::check_rel_with_quota");
+    let check_result = RbumRelServ::check_rel_with_quota(
+        &RbumRelCheckReq {
+            tag: "bind".to_string(),
+            from_rbum_kind: RbumRelFromKind::Item,
+            from_rbum_id: item_reldb_inst1_id.to_string(),
+            to_rbum_item_id: item_account_a1_id.to_string(),
+            from_attrs: HashMap::from([("db_type".to_string(), "mysql".to_string()), ("db_version".to_string(), "8.0".to_string())]),
+            to_attrs: Default::default(),
+            envs: vec![
+                RbumRelEnvCheckReq {
+                    kind: RbumRelEnvKind::DatetimeRange,
+                    value: Utc::now().timestamp().to_string(),
+                },
+                RbumRelEnvCheckReq {
+                    kind: RbumRelEnvKind::CallCount,
+                    value: "9".to_string(),
+                },
+                RbumRelEnvCheckReq {
+                    kind: RbumRelEnvKind::Ips,
+                    value: "192.168.0.100".to_string(),
+                },
+            ],
+        },
+        &funs,
+        context,
+    )
+    .await?;
+    assert!(!check_result.passed);
+    let quota = check_result.quota.unwrap();
+    assert_eq!(quota.call_count_limit, Some(10));
+    assert_eq!(quota.call_count_remaining, Some(1));
+    assert_eq!(RbumRelServ::get_rel_quota(&quota.rel_id, &funs).await?.call_count_remaining, Some(1));
+    assert!(RbumRelServ::consume_rel_quota(&quota.rel_id, 1, &funs).await?.passed);
+    assert!(!RbumRelServ::consume_rel_quota(&quota.rel_id, 1, &funs).await?.passed);
+    RbumRelServ::reset_rel_quota(&quota.rel_id, &funs).await?;
+    assert_eq!(RbumRelServ::get_rel_quota(&quota.rel_id, &funs).await?.call_count_remaining, Some(10));
+
     // funs.commit().await?;
 
     // let mut funs = TardisFuns::inst_with_db_conn("".to_string(), None);
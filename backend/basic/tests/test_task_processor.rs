@@ -1,8 +1,16 @@
 use std::env;
 
+use std::time::Duration;
+
+use bios_basic::process::dto::task_processor_dto::AsyncTaskStatusKind;
 use bios_basic::process::task_processor::TaskProcessor;
 use bios_basic::test::init_test_container;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
 use tardis::basic::result::TardisResult;
+use tardis::chrono::{self, Utc};
+use tardis::db::sea_orm::sea_query::{Alias, Expr, Query};
+use tardis::serde_json::{json, Value};
 use tardis::{testcontainers, tokio, TardisFuns};
 
 #[tokio::test]
@@ -44,5 +52,78 @@ async fn test_task_processor() -> TardisResult<()> {
 
     assert!(!TaskProcessor::check_status("test2", u32::MAX as u64 + 1, &cache_client).await?);
 
+    // ------------------ Registered Task ------------------
+    let funs = TardisFuns::inst_with_db_conn("".to_string(), None);
+    let ctx = TardisContext {
+        own_paths: "t1".to_string(),
+        owner: "a1".to_string(),
+        ..Default::default()
+    };
+    TaskProcessor::register_task_fun("test/echo", true, |_task_id: u64, params: Value, _ctx: TardisContext| async move { Ok(params) }).await;
+    TaskProcessor::register_task_fun("test/fail", true, |_task_id: u64, _params: Value, _ctx: TardisContext| async move {
+        Err(TardisError::bad_request("mock error", "400-test-task-fail"))
+    })
+    .await;
+    TaskProcessor::register_task_fun("test/import", false, |_task_id: u64, _params: Value, _ctx: TardisContext| async move {
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        Ok(Value::Null)
+    })
+    .await;
+    TaskProcessor::init_registry(&funs).await?;
+
+    assert!(TaskProcessor::execute_registered_task("test3", "test/xxx", Value::Null, 0, "".to_string(), None, &funs, &ctx).await.is_err());
+
+    let task_id = TaskProcessor::execute_registered_task("test3", "test/echo", json!({"a": 1}), 0, "".to_string(), None, &funs, &ctx).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(TaskProcessor::check_status("test3", task_id, &cache_client).await?);
+    let task = TaskProcessor::get_task("test3", task_id, &funs, &ctx).await?.unwrap();
+    assert_eq!(task.status, AsyncTaskStatusKind::Finished.to_string());
+    assert_eq!(task.result, Some(json!({"a": 1})));
+    assert_eq!(task.own_paths, "t1");
+    assert!(TaskProcessor::retry_task("test3", task_id, "".to_string(), None, &funs, &ctx).await.is_err());
+    // the tasks of other tenants are invisible
+    let other_ctx = TardisContext {
+        own_paths: "t2".to_string(),
+        owner: "a2".to_string(),
+        ..Default::default()
+    };
+    assert!(TaskProcessor::get_task("test3", task_id, &funs, &other_ctx).await?.is_none());
+    assert!(TaskProcessor::cancel_task("test3", task_id, "".to_string(), None, &funs, &other_ctx).await.is_err());
+
+    let task_id = TaskProcessor::execute_registered_task("test3", "test/fail", Value::Null, 2, "".to_string(), None, &funs, &ctx).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let task = TaskProcessor::get_task("test3", task_id, &funs, &ctx).await?.unwrap();
+    assert_eq!(task.status, AsyncTaskStatusKind::Failed.to_string());
+    assert_eq!(task.retry_times, 2);
+    assert!(task.error.contains("mock error"));
+    assert_eq!(
+        TaskProcessor::retry_task("test3", task_id, "".to_string(), None, &funs, &other_ctx).await.unwrap_err().code,
+        "404-task-not-exist"
+    );
+
+    // ------------------ Orphaned Task ------------------
+    let orphan_task = |task_id: u64| {
+        let mut query = Query::update();
+        query
+            .table(Alias::new("bios_async_task"))
+            .value(Alias::new("node_id"), "dead_node")
+            .value(Alias::new("heartbeat_time"), Utc::now() - chrono::Duration::seconds(120))
+            .and_where(Expr::col(Alias::new("id")).eq(task_id.to_string()));
+        query
+    };
+    // the non-idempotent task is marked as failed instead of being re-executed
+    let task_id = TaskProcessor::execute_registered_task("test3", "test/import", Value::Null, 0, "".to_string(), None, &funs, &ctx).await?;
+    funs.db().execute(&orphan_task(task_id)).await?;
+    TaskProcessor::recover_orphaned_tasks(&funs).await?;
+    let task = TaskProcessor::get_task("test3", task_id, &funs, &ctx).await?.unwrap();
+    assert_eq!(task.status, AsyncTaskStatusKind::Failed.to_string());
+    assert_eq!(task.node_id, "dead_node");
+    assert!(task.error.contains("retry it manually"));
+    assert!(TaskProcessor::check_status("test3", task_id, &cache_client).await?);
+    // the failed task can be retried manually
+    TaskProcessor::retry_task("test3", task_id, "".to_string(), None, &funs, &ctx).await?;
+    let task = TaskProcessor::get_task("test3", task_id, &funs, &ctx).await?.unwrap();
+    assert_eq!(task.status, AsyncTaskStatusKind::Running.to_string());
+
     Ok(())
 }
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use serde_json::Value;
+use tardis::chrono::{DateTime, Utc};
 use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem::Request;
 use tardis::web::poem_openapi;
@@ -10,12 +11,15 @@ use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
 use crate::dto::flow_external_dto::FlowExternalCallbackOp;
 use crate::dto::flow_inst_dto::{
-    FlowInstAbortReq, FlowInstDetailResp, FlowInstFindNextTransitionResp, FlowInstFindNextTransitionsReq, FlowInstFindStateAndTransitionsReq, FlowInstFindStateAndTransitionsResp,
-    FlowInstModifyAssignedReq, FlowInstModifyCurrentVarsReq, FlowInstStartReq, FlowInstSummaryResp, FlowInstTransferReq, FlowInstTransferResp,
+    FlowInstAbortReq, FlowInstApprovalResp, FlowInstApproveReq, FlowInstDetailResp, FlowInstFindNextTransitionResp, FlowInstFindNextTransitionsReq,
+    FlowInstFindStateAndTransitionsReq, FlowInstFindStateAndTransitionsResp, FlowInstModifyAssignedReq, FlowInstModifyCurrentVarsReq, FlowInstSlaStatGroupKind,
+    FlowInstSlaStatResp, FlowInstStartReq, FlowInstSummaryResp, FlowInstTransferReq, FlowInstTransferResp,
 };
 use crate::flow_constants;
 use crate::helper::loop_check_helper;
+use crate::serv::flow_inst_approval_serv::FlowInstApprovalServ;
 use crate::serv::flow_inst_serv::FlowInstServ;
+use crate::serv::flow_inst_sla_serv::FlowInstSlaServ;
 #[derive(Clone)]
 pub struct FlowCcInstApi;
 
@@ -81,6 +85,25 @@ impl FlowCcInstApi {
         TardisResp::ok(result)
     }
 
+    /// Statistics of SLA Compliance
+    ///
+    /// 统计SLA达成情况
+    #[oai(path = "/sla/stat", method = "get")]
+    async fn stat_sla(
+        &self,
+        flow_model_id: Query<Option<String>>,
+        group_by: Query<FlowInstSlaStatGroupKind>,
+        start_time: Query<Option<DateTime<Utc>>>,
+        end_time: Query<Option<DateTime<Utc>>>,
+        ctx: TardisContextExtractor,
+        _request: &Request,
+    ) -> TardisApiResult<Vec<FlowInstSlaStatResp>> {
+        let funs = flow_constants::get_tardis_inst();
+        let result = FlowInstSlaServ::stat(flow_model_id.0, group_by.0, start_time.0, end_time.0, &funs, &ctx.0).await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
     /// Find Next Transitions
     ///
     /// 获取下一个流转状态列表
@@ -141,6 +164,25 @@ impl FlowCcInstApi {
         TardisResp::ok(result)
     }
 
+    /// Approve or reject the instance in the approval state
+    ///
+    /// 审批（会签状态）
+    #[oai(path = "/:flow_inst_id/approve", method = "put")]
+    async fn approve(
+        &self,
+        flow_inst_id: Path<String>,
+        approve_req: Json<FlowInstApproveReq>,
+        ctx: TardisContextExtractor,
+        _request: &Request,
+    ) -> TardisApiResult<FlowInstApprovalResp> {
+        let mut funs = flow_constants::get_tardis_inst();
+        funs.begin().await?;
+        let result = FlowInstApprovalServ::approve(&flow_inst_id.0, &approve_req.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
     /// Batch transfer State By Transaction Id
     ///
     /// 批量流转
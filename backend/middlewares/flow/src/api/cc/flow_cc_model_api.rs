@@ -16,10 +16,18 @@ use crate::dto::flow_model_dto::{
     FlowModelAddCustomModelReq, FlowModelAddCustomModelResp, FlowModelAddReq, FlowModelAggResp, FlowModelBindStateReq, FlowModelFilterReq, FlowModelFindRelStateResp,
     FlowModelModifyReq, FlowModelSortStatesReq, FlowModelSummaryResp, FlowModelUnbindStateReq,
 };
+use crate::dto::flow_model_exchange_dto::{FlowModelExchangeFormat, FlowModelImportReq, FlowModelImportResp};
+use crate::dto::flow_model_simulate_dto::{FlowModelSimulateReq, FlowModelSimulateResp};
+use crate::dto::flow_model_version_dto::{
+    FlowModelVersionDetailResp, FlowModelVersionDiffResp, FlowModelVersionMigrateReq, FlowModelVersionMigrateResp, FlowModelVersionPublishReq, FlowModelVersionSummaryResp,
+};
 use crate::dto::flow_state_dto::FlowStateRelModelModifyReq;
 use crate::dto::flow_transition_dto::{FlowTransitionModifyReq, FlowTransitionSortStatesReq};
 use crate::flow_constants;
+use crate::serv::flow_model_exchange_serv::FlowModelExchangeServ;
 use crate::serv::flow_model_serv::FlowModelServ;
+use crate::serv::flow_model_simulate_serv::FlowModelSimulateServ;
+use crate::serv::flow_model_version_serv::FlowModelVersionServ;
 use crate::serv::flow_rel_serv::{FlowRelKind, FlowRelServ};
 #[derive(Clone)]
 pub struct FlowCcModelApi;
@@ -293,7 +301,7 @@ impl FlowCcModelApi {
                         .map(|state| FlowStateRelModelModifyReq {
                             id: state.state_id,
                             sort: Some(state.sort),
-                            show_btns: None,
+                            ..Default::default()
                         })
                         .collect_vec(),
                 ),
@@ -402,6 +410,139 @@ impl FlowCcModelApi {
         TardisResp::ok(Void {})
     }
 
+    /// Publish the current structure of the model as a new version
+    ///
+    /// 发布模型版本
+    #[oai(path = "/:flow_model_id/version", method = "post")]
+    async fn publish_version(
+        &self,
+        flow_model_id: Path<String>,
+        publish_req: Json<FlowModelVersionPublishReq>,
+        ctx: TardisContextExtractor,
+        _request: &Request,
+    ) -> TardisApiResult<String> {
+        let mut funs = flow_constants::get_tardis_inst();
+        funs.begin().await?;
+        let result = FlowModelVersionServ::publish(&flow_model_id.0, &publish_req.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Find the versions of the model
+    ///
+    /// 获取模型版本列表
+    #[oai(path = "/:flow_model_id/version", method = "get")]
+    async fn find_versions(&self, flow_model_id: Path<String>, ctx: TardisContextExtractor, _request: &Request) -> TardisApiResult<Vec<FlowModelVersionSummaryResp>> {
+        let funs = flow_constants::get_tardis_inst();
+        let result = FlowModelVersionServ::find_versions(&flow_model_id.0, &funs).await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Compare the versions of the model, the current content of the model is used if the version is not specified
+    ///
+    /// 比较模型版本，未指定版本时使用模型当前的内容
+    #[oai(path = "/:flow_model_id/version/diff", method = "get")]
+    async fn diff_versions(
+        &self,
+        flow_model_id: Path<String>,
+        from_version_id: Query<Option<String>>,
+        to_version_id: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+        _request: &Request,
+    ) -> TardisApiResult<FlowModelVersionDiffResp> {
+        let funs = flow_constants::get_tardis_inst();
+        let result = FlowModelVersionServ::diff(&flow_model_id.0, from_version_id.0, to_version_id.0, &funs, &ctx.0).await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Migrate the unfinished instances to the version
+    ///
+    /// 迁移未结束的实例至指定版本
+    #[oai(path = "/:flow_model_id/version/migrate", method = "post")]
+    async fn migrate_version(
+        &self,
+        flow_model_id: Path<String>,
+        migrate_req: Json<FlowModelVersionMigrateReq>,
+        ctx: TardisContextExtractor,
+        _request: &Request,
+    ) -> TardisApiResult<FlowModelVersionMigrateResp> {
+        let mut funs = flow_constants::get_tardis_inst();
+        funs.begin().await?;
+        let result = FlowModelVersionServ::migrate(&flow_model_id.0, &migrate_req.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Get the version of the model
+    ///
+    /// 获取模型版本
+    #[oai(path = "/:flow_model_id/version/:flow_model_version_id", method = "get")]
+    async fn get_version(
+        &self,
+        flow_model_id: Path<String>,
+        flow_model_version_id: Path<String>,
+        ctx: TardisContextExtractor,
+        _request: &Request,
+    ) -> TardisApiResult<FlowModelVersionDetailResp> {
+        let funs = flow_constants::get_tardis_inst();
+        let result = FlowModelVersionServ::get_version(&flow_model_version_id.0, &funs).await?;
+        if result.rel_flow_model_id != flow_model_id.0 {
+            return TardisResp::err(funs.err().not_found(
+                "flow_model_version",
+                "get",
+                &format!("flow model version {} not found", flow_model_version_id.0),
+                "404-flow-model-version-not-found",
+            ));
+        }
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Export the model as a document
+    ///
+    /// 导出模型
+    #[oai(path = "/:flow_model_id/export", method = "get")]
+    async fn export(&self, flow_model_id: Path<String>, format: Query<FlowModelExchangeFormat>, ctx: TardisContextExtractor, _request: &Request) -> TardisApiResult<String> {
+        let funs = flow_constants::get_tardis_inst();
+        let result = FlowModelExchangeServ::export(&flow_model_id.0, &format.0, &funs, &ctx.0).await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Import the document as a new model
+    ///
+    /// 导入模型
+    #[oai(path = "/import", method = "post")]
+    async fn import(&self, import_req: Json<FlowModelImportReq>, ctx: TardisContextExtractor, _request: &Request) -> TardisApiResult<FlowModelImportResp> {
+        let mut funs = flow_constants::get_tardis_inst();
+        funs.begin().await?;
+        let result = FlowModelExchangeServ::import(&import_req.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
+    /// Simulate the transitions of a state against a hypothetical instance context, nothing is persisted
+    ///
+    /// 模拟流转
+    #[oai(path = "/:flow_model_id/simulate", method = "put")]
+    async fn simulate(
+        &self,
+        flow_model_id: Path<String>,
+        simulate_req: Json<FlowModelSimulateReq>,
+        ctx: TardisContextExtractor,
+        _request: &Request,
+    ) -> TardisApiResult<FlowModelSimulateResp> {
+        let funs = flow_constants::get_tardis_inst();
+        let result = FlowModelSimulateServ::simulate(&flow_model_id.0, &simulate_req.0, &funs, &ctx.0).await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(result)
+    }
+
     /// batch add rels with template and app
     ///
     /// 批量添加模板和应用的关联关系
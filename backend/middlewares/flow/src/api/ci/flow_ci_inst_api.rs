@@ -22,6 +22,8 @@ use crate::dto::flow_inst_dto::{
 use crate::flow_constants;
 use crate::helper::loop_check_helper;
 use crate::serv::flow_inst_serv::FlowInstServ;
+use crate::serv::flow_inst_sla_serv::FlowInstSlaServ;
+use crate::serv::flow_inst_timer_serv::FlowInstTimerServ;
 #[derive(Clone)]
 pub struct FlowCiInstApi;
 
@@ -284,4 +286,32 @@ impl FlowCiInstApi {
         });
         TardisResp::ok(Void {})
     }
+
+    /// Trigger the due timers of the timed transfers
+    ///
+    /// 触发到期的定时流转
+    #[oai(path = "/trigger_timer", method = "get")]
+    async fn trigger_timer(&self) -> TardisApiResult<Void> {
+        let funs = flow_constants::get_tardis_inst();
+        tokio::spawn(async move {
+            if let Err(e) = FlowInstTimerServ::trigger(&funs).await {
+                log::warn!("[Flow.Inst] failed to trigger timers:{e}")
+            }
+        });
+        TardisResp::ok(Void {})
+    }
+
+    /// Escalate the breached SLAs of the instances
+    ///
+    /// 升级处理实例超时的SLA
+    #[oai(path = "/trigger_sla", method = "get")]
+    async fn trigger_sla(&self) -> TardisApiResult<Void> {
+        let funs = flow_constants::get_tardis_inst();
+        tokio::spawn(async move {
+            if let Err(e) = FlowInstSlaServ::trigger(&funs).await {
+                log::warn!("[Flow.Inst] failed to trigger SLAs:{e}")
+            }
+        });
+        TardisResp::ok(Void {})
+    }
 }
@@ -1,4 +1,8 @@
 pub mod flow_inst;
+pub mod flow_inst_approval;
+pub mod flow_inst_state_log;
+pub mod flow_inst_timer;
 pub mod flow_model;
+pub mod flow_model_version;
 pub mod flow_state;
 pub mod flow_transition;
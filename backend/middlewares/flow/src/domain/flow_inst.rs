@@ -13,6 +13,12 @@ pub struct Model {
     pub id: String,
     #[index]
     pub rel_flow_model_id: String,
+    /// Associated model version / 关联的模型版本
+    ///
+    /// The published version of the model when the instance started, empty if the model had not been published
+    /// 实例启动时模型已发布的版本，模型未发布时为空
+    #[index]
+    pub rel_flow_model_version_id: Option<String>,
 
     /// Business object Id / 关联的业务对象Id
     #[index(unique = true)]
@@ -0,0 +1,50 @@
+use crate::dto::flow_inst_dto::FlowInstApprovalDecision;
+use tardis::chrono::Utc;
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::*;
+use tardis::{chrono, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
+
+/// Approval record of process instance / 流程实例审批记录
+///
+/// Recorded when an approver decides in an approval state, and archived when the instance leaves the state,
+/// so that only the decisions made since the instance entered the state count.
+/// 审批人在会签状态中审批时记录，实例离开该状态时归档，仅实例进入该状态后的审批计入进度。
+///
+/// Each approver decides once in each group until the records are archived, guaranteed by the unique index of the unarchived records.
+/// 归档前每个审批人在每个审批组中仅能审批一次，由未归档记录的唯一索引保证。
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
+#[sea_orm(table_name = "flow_inst_approval")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    /// Associated [flow_inst](super::flow_inst::Model) id / 关联的[流程实例](super::flow_inst::Model) id
+    #[index(index_id = "unique_decision", unique)]
+    pub rel_flow_inst_id: String,
+    /// Associated [flow_state](super::flow_state::Model) id / 关联的[状态](super::flow_state::Model) id
+    #[index(repeat(index_id = "unique_decision", unique))]
+    pub rel_flow_state_id: String,
+    /// Approval group code / 审批组编码
+    #[index(repeat(index_id = "unique_decision", unique))]
+    pub group_code: String,
+    /// Approver id / 审批人Id
+    #[index(index_id = "approver_index", repeat(index_id = "unique_decision", unique))]
+    pub approver_id: String,
+    /// Decision / 审批结果
+    #[tardis_entity(custom_type = "String")]
+    pub decision: FlowInstApprovalDecision,
+    /// Message / 审批意见
+    pub message: Option<String>,
+    /// Whether to be archived / 是否已归档
+    #[index]
+    pub archived: bool,
+    /// Archive id, empty until archived, the records archived together share the same id
+    /// 归档Id，归档前为空，同时归档的记录共用同一Id
+    #[index(repeat(index_id = "unique_decision", unique))]
+    pub archive_id: String,
+
+    /// Creation time / 创建时间
+    #[sea_orm(extra = "DEFAULT CURRENT_TIMESTAMP")]
+    pub create_time: chrono::DateTime<Utc>,
+
+    pub own_paths: String,
+}
@@ -0,0 +1,55 @@
+use crate::dto::flow_inst_dto::FlowOperationContext;
+use tardis::chrono::Utc;
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::*;
+use tardis::{chrono, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
+
+/// State log of process instance / 流程实例状态记录
+///
+/// Recorded when the instance enters a state and closed when it leaves the state, so that the time spent in each state is kept
+/// and the SLA of the state can be tracked.
+/// 实例进入状态时记录，离开该状态时关闭，以保留实例在各状态的停留时间并跟踪状态的SLA。
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
+#[sea_orm(table_name = "flow_inst_state_log")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    /// Associated [flow_inst](super::flow_inst::Model) id / 关联的[流程实例](super::flow_inst::Model) id
+    #[index]
+    pub rel_flow_inst_id: String,
+    /// Associated [flow_model](super::flow_model::Model) id / 关联的[模型](super::flow_model::Model) id
+    #[index]
+    pub rel_flow_model_id: String,
+    /// Associated [flow_state](super::flow_state::Model) id / 关联的[状态](super::flow_state::Model) id
+    #[index]
+    pub rel_flow_state_id: String,
+    /// Assignee, the ``assigned_to`` var of the instance when it leaves the state or the SLA is breached
+    /// 处理人，即实例离开该状态或超时时的 ``assigned_to`` 变量
+    #[index]
+    pub assigned_to: String,
+    /// Time of entering the state / 进入状态的时间
+    pub enter_time: chrono::DateTime<Utc>,
+    /// Time of leaving the state, empty if the instance is still in the state / 离开状态的时间，实例仍处于该状态时为空
+    #[index]
+    pub leave_time: Option<chrono::DateTime<Utc>>,
+    /// Seconds spent in the state, set when leaving the state / 在该状态停留的秒数，离开时记录
+    pub elapsed_secs: Option<i64>,
+    /// SLA target seconds of the state when entering it, empty if no SLA is configured
+    /// 进入状态时该状态的SLA目标时长（秒），未配置SLA时为空
+    pub sla_secs: Option<i64>,
+    /// Time when the SLA is breached / SLA超时的时间
+    #[index]
+    pub due_time: Option<chrono::DateTime<Utc>>,
+    /// Whether the SLA is breached / SLA是否已超时
+    #[index]
+    pub breached: bool,
+    /// Information of the operator who transferred the instance into the state, used to notify the breach
+    /// 将实例流转至该状态的操作者信息，通知超时时使用
+    pub op_ctx: FlowOperationContext,
+
+    /// Creation time / 创建时间
+    #[sea_orm(extra = "DEFAULT CURRENT_TIMESTAMP")]
+    pub create_time: chrono::DateTime<Utc>,
+
+    pub own_paths: String,
+}
@@ -0,0 +1,43 @@
+use crate::dto::flow_inst_dto::FlowOperationContext;
+use tardis::chrono::Utc;
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::*;
+use tardis::{chrono, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
+
+/// Process instance timer / 流程实例定时器
+///
+/// Registered when the instance enters a state which has timed transitions (``transfer_by_timer``),
+/// and removed when the instance leaves the state or the timer is fired.
+/// The due timer is leased to a node before it is fired, the lease is released only by removing the timer after it is fired successfully,
+/// so the timer is retried by any node after the lease expires if the node failed or crashed.
+/// 实例进入存在定时流转的状态时注册，离开该状态或定时器触发后删除。
+/// 到期的定时器在触发前租借给某一节点，触发成功后才删除，节点失败或宕机时租期过后可由任意节点重试。
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
+#[sea_orm(table_name = "flow_inst_timer")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    /// Associated [flow_inst](super::flow_inst::Model) id / 关联的[流程实例](super::flow_inst::Model) id
+    #[index]
+    pub rel_flow_inst_id: String,
+    /// Associated [flow_transition](super::flow_transition::Model) id / 关联的[流转](super::flow_transition::Model) id
+    pub rel_flow_transition_id: String,
+    /// The state of the instance when the timer is registered / 注册定时器时实例所处的状态
+    pub rel_flow_state_id: String,
+    /// Due time, postponed to the end of the lease when the timer is leased / 到期时间，租借时延后至租期结束
+    #[index]
+    pub due_time: chrono::DateTime<Utc>,
+    /// The owner of the current lease, empty if never leased / 当前租借的持有者，未被租借时为空
+    pub lease_owner: String,
+    /// Number of the times fired / 已触发次数
+    pub fire_times: i32,
+    /// Information of the operator who transferred the instance into the state, used to fire the transition
+    /// 将实例流转至该状态的操作者信息，触发流转时使用
+    pub op_ctx: FlowOperationContext,
+
+    /// Creation time / 创建时间
+    #[sea_orm(extra = "DEFAULT CURRENT_TIMESTAMP")]
+    pub create_time: chrono::DateTime<Utc>,
+
+    pub own_paths: String,
+}
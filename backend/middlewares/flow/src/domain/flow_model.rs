@@ -8,8 +8,8 @@ use tardis::{TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
 /// Used to define processes, each process contains one or more transitions (associated with `flow_transition`)
 /// 用于定义流程，每个流程包含一个或多个流转（关联 `flow_transition` ）
 ///
-/// The current model does not supports the version
-/// 当前模型不支持版本
+/// The model is the editable draft, the instances run on its published versions (associated with `flow_model_version`)
+/// 模型为可编辑的草稿，实例按其已发布的版本（关联 `flow_model_version` ）运行
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
 #[sea_orm(table_name = "flow_model")]
 pub struct Model {
@@ -0,0 +1,44 @@
+use crate::dto::flow_inst_dto::FlowOperationContext;
+use tardis::chrono::Utc;
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::prelude::Json;
+use tardis::db::sea_orm::*;
+use tardis::{chrono, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation};
+
+/// Published model version / 已发布的模型版本
+///
+/// An immutable snapshot of the structure (bound states, initial state and transitions) of the model when it is published,
+/// the instances are pinned to the latest version when started and keep running on it while the model is modified.
+/// The states are shared by the models, so their definitions are not part of the version.
+/// The version numbers are unique within the model, the concurrent publications retry on conflict.
+/// 模型发布时其结构（绑定的状态、初始状态及流转）的不可变快照，实例启动时固定于最新版本，模型修改后仍按该版本运行。
+/// 状态由多个模型共享，因此状态本身的定义不属于版本。
+/// 版本号在模型内唯一，并发发布时冲突则重试。
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, TardisCreateEntity, TardisEmptyBehavior, TardisEmptyRelation)]
+#[sea_orm(table_name = "flow_model_version")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    /// Associated [flow_model](super::flow_model::Model) id / 关联的[模型](super::flow_model::Model) id
+    #[index(index_id = "unique_version", unique)]
+    pub rel_flow_model_id: String,
+    /// Version number, increasing from 1 within the model / 版本号，模型内从1开始递增
+    #[index(repeat(index_id = "unique_version", unique))]
+    pub version: i32,
+    /// Description / 描述
+    pub info: String,
+    /// Initial state / 初始状态
+    pub init_state_id: String,
+    /// Bound states (Vec<FlowStateAggResp>) / 绑定的状态
+    pub states: Json,
+    /// Transitions (Vec<FlowTransitionDetailResp>) / 流转
+    pub transitions: Json,
+    /// Publisher information / 发布者信息
+    pub publish_ctx: FlowOperationContext,
+
+    /// Creation time / 创建时间
+    #[sea_orm(extra = "DEFAULT CURRENT_TIMESTAMP")]
+    pub create_time: chrono::DateTime<Utc>,
+
+    pub own_paths: String,
+}
@@ -40,7 +40,10 @@ pub struct Model {
     ///
     /// When there is a value, after the time is reached, it will be automatically transferred under the premise of meeting the conditions
     /// 存在值时，到达时间后，在满足条件的前提下自动流转
-    // TODO 该功能未实现
+    ///
+    /// The timer is registered when the instance enters the source state and cancelled when it leaves,
+    /// only ``guard_by_other_conds`` is checked when the timer is fired because there is no operator.
+    /// 定时器在实例进入来源状态时注册，离开时取消，由于触发时没有操作人，仅校验 ``guard_by_other_conds`` 。
     pub transfer_by_timer: String,
 
     /// Transfer condition: the current operator is the creator
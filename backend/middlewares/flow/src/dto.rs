@@ -2,6 +2,9 @@ pub mod flow_config_dto;
 pub mod flow_external_dto;
 pub mod flow_inst_dto;
 pub mod flow_model_dto;
+pub mod flow_model_exchange_dto;
+pub mod flow_model_simulate_dto;
+pub mod flow_model_version_dto;
 pub mod flow_state_dto;
 pub mod flow_transition_dto;
 pub mod flow_var_dto;
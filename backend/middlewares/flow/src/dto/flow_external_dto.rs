@@ -96,6 +96,8 @@ pub enum FlowExternalCallbackOp {
     VerifyContent,
     /// 条件触发
     ConditionalTrigger,
+    /// 定时流转
+    Timer,
 }
 
 /// 扩展字段
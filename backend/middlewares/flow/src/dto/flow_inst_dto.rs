@@ -4,8 +4,8 @@ use serde::{Deserialize, Serialize};
 use tardis::{
     basic::dto::TardisContext,
     chrono::{DateTime, Utc},
-    db::sea_orm,
-    serde_json::Value,
+    db::sea_orm::{self, prelude::*, EnumIter},
+    serde_json::{json, Value},
     web::poem_openapi,
 };
 
@@ -112,6 +112,8 @@ pub struct FlowInstDetailResp {
     ///
     /// 关联的[工作流模板](super::flow_model_dto::FlowModelDetailResp) 名称
     pub rel_flow_model_name: String,
+    /// 关联的模型版本ID，为空时使用模型当前的内容
+    pub rel_flow_model_version_id: Option<String>,
     /// 关联业务ID
     pub rel_business_obj_id: String,
     /// 当前状态ID
@@ -158,10 +160,35 @@ pub struct FlowInstDetailResp {
     pub output_message: Option<String>,
     /// 动作列表
     pub transitions: Option<Vec<FlowInstTransitionInfo>>,
+    /// 审批进度，仅当前状态为会签状态时存在
+    pub approval: Option<FlowInstApprovalResp>,
+    /// 当前状态的SLA进度，仅当前状态配置了SLA时存在
+    pub sla: Option<FlowInstSlaResp>,
 
     pub own_paths: String,
 }
 
+impl FlowInstDetailResp {
+    /// The vars which can be referenced by ``guard_by_other_conds``
+    ///
+    /// 可在 ``guard_by_other_conds`` 中引用的变量
+    ///
+    /// Including the current vars, the vars of the request and the approval progress of the current state.
+    /// 包括当前变量、请求中的变量及当前状态的审批进度。
+    pub fn guard_vars(&self, req_vars: &Option<HashMap<String, Value>>) -> HashMap<String, Value> {
+        let mut guard_vars = self.current_vars.clone().unwrap_or_default();
+        if let Some(req_vars) = req_vars {
+            guard_vars.extend(req_vars.clone());
+        }
+        if let Some(approval) = &self.approval {
+            guard_vars.insert("approval_status".to_string(), json!(approval.status));
+            guard_vars.insert("approval_approved_count".to_string(), json!(approval.approved_count()));
+            guard_vars.insert("approval_rejected_count".to_string(), json!(approval.rejected_count()));
+        }
+        guard_vars
+    }
+}
+
 /// 实例的动作信息
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, poem_openapi::Object, sea_orm::FromJsonQueryResult)]
 pub struct FlowInstTransitionInfo {
@@ -369,3 +396,137 @@ pub struct FlowInstSummaryResult {
 
     pub tag: String,
 }
+
+/// 审批请求
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowInstApproveReq {
+    /// 审批结果
+    pub decision: FlowInstApprovalDecision,
+    /// 审批组编码，为空时对当前用户所在的所有审批组生效
+    pub group_code: Option<String>,
+    /// 审批意见
+    pub message: Option<String>,
+}
+
+/// 审批结果
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, EnumIter, sea_orm::DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(255))")]
+pub enum FlowInstApprovalDecision {
+    /// 同意
+    #[sea_orm(string_value = "approve")]
+    Approve,
+    /// 驳回
+    #[sea_orm(string_value = "reject")]
+    Reject,
+}
+
+/// 审批状态
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum)]
+#[serde(rename_all = "snake_case")]
+#[oai(rename_all = "snake_case")]
+pub enum FlowInstApprovalStatus {
+    /// 审批中
+    Pending,
+    /// 已通过
+    Approved,
+    /// 已驳回
+    Rejected,
+}
+
+/// 审批进度
+#[derive(Serialize, Deserialize, Clone, Debug, poem_openapi::Object)]
+pub struct FlowInstApprovalResp {
+    /// 会签状态Id
+    pub flow_state_id: String,
+    /// 审批状态，所有审批组通过时为通过，任一审批组驳回时为驳回
+    pub status: FlowInstApprovalStatus,
+    /// 各审批组的进度
+    pub groups: Vec<FlowInstApprovalGroupResp>,
+    /// 审批记录
+    pub records: Vec<FlowInstApprovalRecordResp>,
+}
+
+impl FlowInstApprovalResp {
+    pub fn approved_count(&self) -> usize {
+        self.groups.iter().map(|group| group.approved_ids.len()).sum()
+    }
+
+    pub fn rejected_count(&self) -> usize {
+        self.groups.iter().map(|group| group.rejected_ids.len()).sum()
+    }
+}
+
+/// 审批组的进度
+#[derive(Serialize, Deserialize, Clone, Debug, poem_openapi::Object)]
+pub struct FlowInstApprovalGroupResp {
+    /// 审批组编码
+    pub code: String,
+    /// 显示名
+    pub name: Option<String>,
+    /// 审批状态
+    pub status: FlowInstApprovalStatus,
+    /// 通过所需的同意人数
+    pub required_count: usize,
+    /// 审批人Id列表
+    pub approver_ids: Vec<String>,
+    /// 已同意的审批人Id列表
+    pub approved_ids: Vec<String>,
+    /// 已驳回的审批人Id列表
+    pub rejected_ids: Vec<String>,
+}
+
+/// 审批记录
+#[derive(Serialize, Deserialize, Clone, Debug, poem_openapi::Object)]
+pub struct FlowInstApprovalRecordResp {
+    /// 审批组编码
+    pub group_code: String,
+    /// 审批人Id
+    pub approver_id: String,
+    /// 审批结果
+    pub decision: FlowInstApprovalDecision,
+    /// 审批意见
+    pub message: Option<String>,
+    /// 审批时间
+    pub create_time: DateTime<Utc>,
+}
+
+/// 当前状态的SLA进度
+#[derive(Serialize, Deserialize, Clone, Debug, poem_openapi::Object)]
+pub struct FlowInstSlaResp {
+    /// 状态Id
+    pub flow_state_id: String,
+    /// 进入状态的时间
+    pub enter_time: DateTime<Utc>,
+    /// SLA目标时长（秒）
+    pub sla_secs: i64,
+    /// 已停留的秒数
+    pub elapsed_secs: i64,
+    /// 剩余的秒数，超时后为负数
+    pub remaining_secs: i64,
+    /// 是否已超时
+    pub breached: bool,
+}
+
+/// SLA达成情况的统计维度
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, poem_openapi::Enum)]
+pub enum FlowInstSlaStatGroupKind {
+    /// 按状态
+    State,
+    /// 按处理人
+    Assignee,
+}
+
+/// SLA达成情况的统计结果
+#[derive(Serialize, Deserialize, Clone, Debug, poem_openapi::Object)]
+pub struct FlowInstSlaStatResp {
+    /// 统计维度的值，即状态Id或处理人Id
+    pub key: String,
+    /// 已离开或已超时的状态记录数
+    pub total: i64,
+    /// 其中超时的记录数
+    pub breached: i64,
+    /// 达成率，即未超时的记录数所占比例
+    pub compliance_rate: f64,
+    /// 已离开的记录的平均停留秒数
+    pub avg_elapsed_secs: Option<f64>,
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind;
+use serde::{Deserialize, Serialize};
+use tardis::{basic::field::TrimString, serde_json::Value, web::poem_openapi};
+
+use super::{
+    flow_state_dto::{FlowStateKind, FlowStateRelModelExt, FlowSysStateKind},
+    flow_transition_dto::FlowTransitionAddReq,
+};
+
+/// Version of the native exchange document
+pub const FLOW_MODEL_EXCHANGE_VERSION: &str = "1";
+
+/// 模型导入导出的文档格式
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, poem_openapi::Enum)]
+pub enum FlowModelExchangeFormat {
+    /// 原生JSON格式，可无损导入导出
+    Json,
+    /// BPMN 2.0 XML，平台特有的配置保存在 extensionElements 中
+    Bpmn,
+}
+
+/// 模型交换文档（原生JSON格式）
+///
+/// 文档是自包含的，状态及动作通过文档内的标识相互引用，导入时再映射为目标环境中的状态ID。
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelExchangeDoc {
+    /// 文档格式版本
+    pub format_version: String,
+    pub name: String,
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub info: String,
+    /// 标签
+    #[serde(default)]
+    pub tag: String,
+    /// 是否作为模板使用
+    #[serde(default)]
+    pub template: bool,
+    /// 初始化状态标识
+    pub init_state_key: String,
+    /// 状态列表
+    pub states: Vec<FlowModelExchangeState>,
+    /// 动作列表
+    #[serde(default)]
+    pub transitions: Vec<FlowModelExchangeTransition>,
+}
+
+/// 交换文档中的状态
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelExchangeState {
+    /// 状态标识，导出时为状态ID
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub color: String,
+    pub sys_state: FlowSysStateKind,
+    #[serde(default)]
+    pub info: String,
+    pub state_kind: FlowStateKind,
+    /// 状态类型的配置，其中引用的状态为状态标识
+    #[serde(default)]
+    pub kind_conf: Value,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 状态在模型中的扩展信息
+    #[serde(default)]
+    pub ext: FlowStateRelModelExt,
+}
+
+/// 交换文档中的动作
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelExchangeTransition {
+    /// 动作标识，导出时为动作ID
+    pub key: String,
+    /// 动作配置，其中的来源及目标状态为状态标识
+    pub transition: FlowTransitionAddReq,
+}
+
+/// 导入模型请求
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelImportReq {
+    /// 文档格式
+    pub format: FlowModelExchangeFormat,
+    /// 文档内容
+    pub content: String,
+    /// 模型名称，为空时使用文档中的名称
+    #[oai(validator(min_length = "2", max_length = "200"))]
+    pub name: Option<TrimString>,
+    /// 是否作为模板使用，为空时使用文档中的设置
+    pub template: Option<bool>,
+    /// 关联模板ID
+    pub rel_template_ids: Option<Vec<String>>,
+    pub scope_level: Option<RbumScopeLevelKind>,
+}
+
+/// 导入模型结果
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelImportResp {
+    /// 新建的模型ID，校验失败时为空
+    pub flow_model_id: Option<String>,
+    /// 状态标识与导入后状态ID的映射
+    pub state_mapping: HashMap<String, String>,
+    /// 校验错误，存在错误时不做任何导入
+    pub errors: Vec<FlowModelImportErrorResp>,
+}
+
+impl FlowModelImportResp {
+    pub fn failed(errors: Vec<FlowModelImportErrorResp>) -> Self {
+        Self {
+            flow_model_id: None,
+            state_mapping: HashMap::new(),
+            errors,
+        }
+    }
+}
+
+/// 导入校验错误
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, poem_openapi::Object)]
+pub struct FlowModelImportErrorResp {
+    /// 出错的元素，如 document、model、state:<标识>、transition:<标识>
+    pub element: String,
+    /// 错误信息
+    pub message: String,
+}
+
+impl FlowModelImportErrorResp {
+    pub fn new(element: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            element: element.into(),
+            message: message.into(),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tardis::{serde_json::Value, web::poem_openapi};
+
+use super::flow_transition_dto::{FlowTransitionActionChangeKind, FlowTransitionFrontActionInfo};
+
+/// 模拟流转请求
+///
+/// 以假设的实例上下文对模型进行评估，不会持久化任何数据。
+#[derive(Serialize, Deserialize, Debug, Default, Clone, poem_openapi::Object)]
+pub struct FlowModelSimulateReq {
+    /// 当前状态ID，为空时使用模型的初始化状态
+    pub current_state_id: Option<String>,
+    /// 模型版本ID，为空时使用模型当前的内容
+    pub flow_model_version_id: Option<String>,
+    /// 指定的动作ID，为空时评估当前状态的所有动作
+    pub flow_transition_id: Option<String>,
+    /// 实例的当前参数
+    pub vars: Option<HashMap<String, Value>>,
+    /// 操作人，为空时使用当前上下文的操作人
+    pub operator: Option<String>,
+    /// 操作人的角色，为空时使用当前上下文的角色
+    pub roles: Option<Vec<String>>,
+    /// 操作人的组织，为空时使用当前上下文的组织
+    pub orgs: Option<Vec<String>>,
+    /// 实例的创建人，为空时与操作人相同
+    pub creator: Option<String>,
+    /// 实例的历史操作人
+    pub his_operators: Option<Vec<String>>,
+}
+
+/// 模拟流转结果
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelSimulateResp {
+    /// 模型ID
+    pub flow_model_id: String,
+    /// 模型版本ID
+    pub flow_model_version_id: Option<String>,
+    /// 当前状态ID
+    pub current_state_id: String,
+    /// 当前状态名称
+    pub current_state_name: String,
+    /// 当前状态的动作评估结果
+    pub transitions: Vec<FlowModelSimulateTransitionResp>,
+    /// 后置动作是否可能出现循环
+    pub post_action_ring: bool,
+}
+
+/// 动作的评估结果
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelSimulateTransitionResp {
+    pub flow_transition_id: String,
+    pub flow_transition_name: String,
+    /// 目标状态ID
+    pub to_flow_state_id: String,
+    /// 目标状态名称
+    pub to_flow_state_name: String,
+    /// 是否可由该操作人执行
+    pub available: bool,
+    /// 权限校验过程
+    pub guards: Vec<FlowTransitionGuardCheckResp>,
+    /// 前置条件的评估结果，全部满足时该动作会被自动触发
+    pub front_changes: Vec<FlowModelSimulateFrontChangeResp>,
+    /// 后置动作的评估结果
+    pub post_changes: Vec<FlowModelSimulatePostChangeResp>,
+    /// 后置动作修改当前对象的字段后，因前置条件满足而自动触发的动作ID
+    pub auto_flow_transition_id: Option<String>,
+}
+
+/// 权限校验的类型
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, poem_openapi::Enum)]
+pub enum FlowTransitionGuardKind {
+    /// 其他条件，与其他校验同时生效
+    OtherConds,
+    /// 创建人
+    Creator,
+    /// 指定账号
+    SpecAccount,
+    /// 指定角色
+    SpecRole,
+    /// 指定组织
+    SpecOrg,
+    /// 处理人
+    Assigned,
+    /// 历史操作人
+    HisOperators,
+}
+
+/// 权限校验的结果
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowTransitionGuardCheckResp {
+    /// 校验类型
+    pub kind: FlowTransitionGuardKind,
+    /// 是否通过
+    pub passed: bool,
+    /// 校验说明
+    pub detail: String,
+}
+
+/// 前置条件的评估结果
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelSimulateFrontChangeResp {
+    /// 前置条件
+    pub condition: FlowTransitionFrontActionInfo,
+    /// 是否满足
+    pub passed: bool,
+}
+
+/// 后置动作的评估结果
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelSimulatePostChangeResp {
+    /// 后置动作类型
+    pub kind: FlowTransitionActionChangeKind,
+    /// 描述
+    pub describe: String,
+    /// 触发的对象Tag，为空时为当前对象
+    pub obj_tag: Option<String>,
+    /// 被修改的字段名，仅字段修改时存在
+    pub var_name: Option<String>,
+    /// 修改后的值，仅字段修改且可预知时存在
+    pub changed_val: Option<Value>,
+    /// 修改的目标状态ID，仅状态变更时存在
+    pub changed_state_id: Option<String>,
+    /// 级联触发的关联对象动作，按触发顺序排列
+    pub cascades: Vec<FlowModelSimulateCascadeResp>,
+}
+
+/// 级联触发的关联对象动作
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelSimulateCascadeResp {
+    /// 级联的深度，从1开始
+    pub depth: u32,
+    /// 关联对象的Tag
+    pub obj_tag: String,
+    /// 关联对象的模型ID，未找到模型时为空
+    pub flow_model_id: Option<String>,
+    /// 触发的动作ID，未找到可到达目标状态的动作时为空
+    pub flow_transition_id: Option<String>,
+    pub flow_transition_name: Option<String>,
+    /// 来源状态ID
+    pub from_flow_state_id: Option<String>,
+    /// 目标状态ID
+    pub to_flow_state_id: String,
+    /// 该动作已在本次级联中出现过，继续执行可能出现循环
+    pub loop_detected: bool,
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tardis::{
+    chrono::{DateTime, Utc},
+    db::sea_orm,
+    serde_json::Value,
+    web::poem_openapi,
+    TardisFuns,
+};
+
+use super::{flow_inst_dto::FlowOperationContext, flow_state_dto::FlowStateAggResp, flow_transition_dto::FlowTransitionDetailResp};
+
+/// 发布模型版本请求
+#[derive(Serialize, Deserialize, Debug, Default, Clone, poem_openapi::Object)]
+pub struct FlowModelVersionPublishReq {
+    /// 描述
+    pub info: Option<String>,
+}
+
+/// 模型版本概要信息
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object, sea_orm::FromQueryResult)]
+pub struct FlowModelVersionSummaryResp {
+    pub id: String,
+    /// 关联模型ID
+    pub rel_flow_model_id: String,
+    /// 版本号
+    pub version: i32,
+    /// 描述
+    pub info: String,
+    /// 初始化状态ID
+    pub init_state_id: String,
+    /// 发布者信息
+    pub publish_ctx: FlowOperationContext,
+    /// 发布时间
+    pub create_time: DateTime<Utc>,
+}
+
+/// 模型版本详细信息
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object, sea_orm::FromQueryResult)]
+pub struct FlowModelVersionDetailResp {
+    pub id: String,
+    /// 关联模型ID
+    pub rel_flow_model_id: String,
+    /// 版本号
+    pub version: i32,
+    /// 描述
+    pub info: String,
+    /// 初始化状态ID
+    pub init_state_id: String,
+    /// 状态信息
+    pub states: Value,
+    /// 动作信息
+    pub transitions: Value,
+    /// 发布者信息
+    pub publish_ctx: FlowOperationContext,
+    /// 发布时间
+    pub create_time: DateTime<Utc>,
+}
+
+impl FlowModelVersionDetailResp {
+    pub fn transitions(&self) -> Vec<FlowTransitionDetailResp> {
+        TardisFuns::json.json_to_obj(self.transitions.clone()).unwrap_or_default()
+    }
+
+    pub fn states(&self) -> Vec<FlowStateAggResp> {
+        TardisFuns::json.json_to_obj(self.states.clone()).unwrap_or_default()
+    }
+}
+
+/// 模型版本差异
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelVersionDiffResp {
+    /// 源版本ID，为空时表示模型当前（未发布）的内容
+    pub from_version_id: Option<String>,
+    /// 目标版本ID，为空时表示模型当前（未发布）的内容
+    pub to_version_id: Option<String>,
+    /// 初始化状态是否变更
+    pub init_state_changed: bool,
+    /// 新增的状态ID
+    pub added_state_ids: Vec<String>,
+    /// 移除的状态ID
+    pub removed_state_ids: Vec<String>,
+    /// 新增的动作
+    pub added_transitions: Vec<FlowModelVersionDiffTransitionResp>,
+    /// 移除的动作
+    pub removed_transitions: Vec<FlowModelVersionDiffTransitionResp>,
+    /// 修改的动作
+    pub modified_transitions: Vec<FlowModelVersionDiffTransitionResp>,
+}
+
+/// 动作差异
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelVersionDiffTransitionResp {
+    /// 动作ID
+    pub id: String,
+    /// 动作名称
+    pub name: String,
+    /// 修改前的来源状态ID
+    pub from_flow_state_id: String,
+    /// 修改前的目标状态ID
+    pub to_flow_state_id: String,
+    /// 变更的字段，仅修改的动作存在
+    pub changed_fields: Vec<String>,
+}
+
+/// 实例迁移请求
+///
+/// 将固定于源版本的未结束实例迁移至目标版本
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelVersionMigrateReq {
+    /// 源版本ID，为空时表示未固定版本的实例
+    pub from_version_id: Option<String>,
+    /// 目标版本ID
+    pub to_version_id: String,
+    /// 状态映射（源状态ID -> 目标状态ID），目标版本中仍存在的状态可不指定
+    #[oai(default)]
+    #[serde(default)]
+    pub state_mapping: HashMap<String, String>,
+    /// 是否仅试运行，试运行时只返回受影响的实例而不做修改
+    #[oai(default)]
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 实例迁移结果
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelVersionMigrateResp {
+    /// 是否仅试运行
+    pub dry_run: bool,
+    /// 可迁移的实例
+    pub migrated: Vec<FlowModelVersionMigrateInstResp>,
+    /// 当前状态在目标版本中不存在且未指定映射的实例，存在此类实例时不执行迁移
+    pub unmapped: Vec<FlowModelVersionMigrateInstResp>,
+}
+
+/// 受迁移影响的实例
+#[derive(Serialize, Deserialize, Debug, Clone, poem_openapi::Object)]
+pub struct FlowModelVersionMigrateInstResp {
+    /// 实例ID
+    pub flow_inst_id: String,
+    /// 关联业务ID
+    pub rel_business_obj_id: String,
+    /// 迁移前的状态ID
+    pub from_state_id: String,
+    /// 迁移后的状态ID，未映射时为空
+    pub to_state_id: Option<String>,
+}
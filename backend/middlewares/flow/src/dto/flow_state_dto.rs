@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use bios_basic::rbum::{
     dto::rbum_filer_dto::{RbumBasicFilterReq, RbumItemFilterFetcher, RbumItemRelFilterReq},
     rbum_enumeration::RbumScopeLevelKind,
@@ -129,21 +131,192 @@ pub enum FlowSysStateKind {
     Finish,
 }
 
+/// Kind of state, the behavior of each kind is configured by `kind_conf`
+///
+/// 状态类型，各类型的行为由 `kind_conf` 配置
+///
+/// The state can be reused by multiple models, so the targets in the configurations are state ids,
+/// the corresponding transitions of the model of the instance are used when executing.
+/// 状态可被多个模型复用，因此配置中的目标均为状态Id，执行时使用实例所属模型中对应的流转。
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, EnumIter, sea_orm::DeriveActiveEnum)]
 #[sea_orm(rs_type = "String", db_type = "String(StringLen::N(255))")]
 pub enum FlowStateKind {
+    /// 普通状态，无预定义行为
     #[sea_orm(string_value = "simple")]
     Simple,
+    /// 表单，配置见 [FlowStateFormConf]
     #[sea_orm(string_value = "form")]
     Form,
+    /// 邮件（消息），配置见 [FlowStateMailConf]
     #[sea_orm(string_value = "mail")]
     Mail,
+    /// 回调，配置见 [FlowStateCallbackConf]
     #[sea_orm(string_value = "callback")]
     Callback,
+    /// 定时，配置见 [FlowStateTimerConf]
     #[sea_orm(string_value = "timer")]
     Timer,
+    /// 脚本，配置见 [FlowStateScriptConf]
     #[sea_orm(string_value = "script")]
     Script,
+    /// 会签（多人审批），配置见 [FlowStateApprovalConf]
+    #[sea_orm(string_value = "approval")]
+    Approval,
+}
+
+/// 邮件（消息）状态配置
+///
+/// 进入该状态时，通过 reach 发送消息。消息模板在 reach 的触发场景中配置。
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateMailConf {
+    /// reach 触发场景编码
+    pub scene_code: String,
+    /// 接收者
+    pub receives: Vec<FlowStateMailReceive>,
+    /// 模板替换内容，值支持 `{变量名}` 占位符，以实例变量填充。
+    /// 内置变量：inst_id，rel_business_obj_id，state_name
+    #[oai(default)]
+    #[serde(default)]
+    pub replace: HashMap<String, String>,
+}
+
+/// 邮件（消息）接收者
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateMailReceive {
+    /// 接收组编码
+    pub receive_group_code: String,
+    /// 接收者类型，ACCOUNT/ROLE/APP/TENANT
+    pub receive_kind: String,
+    /// 接收者Id列表
+    #[oai(default)]
+    #[serde(default)]
+    pub receive_ids: Vec<String>,
+    /// 存放接收者Id的实例变量，多个Id以逗号分隔（例如：assigned_to）
+    #[oai(default)]
+    #[serde(default)]
+    pub receive_id_vars: Vec<String>,
+}
+
+/// 回调状态配置
+///
+/// 进入该状态时，以 POST 方式调用配置的地址，失败时重试。
+/// 响应体为对象且包含 `vars` 对象时，将其合并到实例变量中。
+/// 请求不会携带调用方上下文，配置了 `callback_sign_key` 时携带 `X-Flow-Timestamp` 、 `X-Flow-Own-Paths` 及
+/// `X-Flow-Signature` ( base64(hmac_sha256("{timestamp}\n{own_paths}\n{body}", callback_sign_key)) ) 请求头供接收方校验。
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateCallbackConf {
+    /// 回调地址
+    pub url: String,
+    /// 失败后的重试次数
+    #[oai(default)]
+    #[serde(default)]
+    pub retry_times: u32,
+    /// 重试间隔（秒）
+    #[oai(default)]
+    #[serde(default)]
+    pub retry_interval_sec: u32,
+    /// 回调成功后流转至的状态Id，为空时停留在该状态
+    pub success_to_flow_state_id: Option<String>,
+    /// 重试后仍失败时流转至的状态Id，为空时停留在该状态
+    pub fail_to_flow_state_id: Option<String>,
+}
+
+/// 定时状态配置
+///
+/// 进入该状态后等待指定时间，再流转至指定的状态
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateTimerConf {
+    /// 等待的秒数
+    pub delay_sec: i64,
+    /// 到期后流转至的状态Id
+    pub to_flow_state_id: String,
+}
+
+/// 脚本状态配置
+///
+/// 进入该状态时，以实例变量为上下文执行表达式，表达式中的赋值结果写回实例变量。
+/// 表达式在沙箱中执行，仅支持运算、比较、赋值及内置函数，无法访问外部资源。
+/// 例：`total = price * count; approved = total < 1000`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateScriptConf {
+    /// 表达式
+    #[oai(validator(max_length = "5000"))]
+    pub script: String,
+    /// 执行成功后流转至的状态Id，为空时停留在该状态
+    pub to_flow_state_id: Option<String>,
+}
+
+/// 表单状态配置
+///
+/// 声明的必填字段收集完成（存在于实例变量或流转请求的变量中）之前，不允许从该状态流转
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateFormConf {
+    /// 表单字段
+    pub fields: Vec<FlowStateFormField>,
+}
+
+/// 表单字段
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateFormField {
+    /// 字段名（对应实例变量名）
+    pub name: String,
+    /// 显示名
+    pub label: Option<String>,
+    /// 是否必填
+    #[oai(default = "default_true")]
+    #[serde(default = "default_true")]
+    pub required: bool,
+}
+
+/// 会签（多人审批）状态配置
+///
+/// 审批人以审批组划分，各组按自身的通过规则独立审批（并行分支），所有组均通过后该状态通过（汇合），任一组无法再达到通过规则时该状态被驳回。
+/// 审批进度可通过实例详情查看，也可在流转的 ``guard_by_other_conds`` 中以 `approval_status`、`approval_approved_count`、`approval_rejected_count` 变量引用。
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateApprovalConf {
+    /// 审批组
+    pub groups: Vec<FlowStateApprovalGroup>,
+    /// 通过后流转至的状态Id，为空时停留在该状态
+    pub pass_to_flow_state_id: Option<String>,
+    /// 驳回后流转至的状态Id，为空时停留在该状态
+    pub reject_to_flow_state_id: Option<String>,
+}
+
+/// 审批组
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object)]
+pub struct FlowStateApprovalGroup {
+    /// 审批组编码，同一状态内唯一
+    pub code: String,
+    /// 显示名
+    pub name: Option<String>,
+    /// 审批人Id列表
+    #[oai(default)]
+    #[serde(default)]
+    pub approver_ids: Vec<String>,
+    /// 存放审批人Id的实例变量，多个Id以逗号分隔（例如：assigned_to）
+    pub approver_var: Option<String>,
+    /// 通过规则
+    #[oai(default)]
+    #[serde(default)]
+    pub quorum: FlowStateApprovalQuorumKind,
+    /// 通过所需的最少同意人数，仅 [FlowStateApprovalQuorumKind::AtLeast] 时有效
+    pub quorum_count: Option<u32>,
+}
+
+/// 审批组的通过规则
+#[derive(Clone, Default, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum)]
+pub enum FlowStateApprovalQuorumKind {
+    /// 全部审批人同意
+    #[default]
+    All,
+    /// 任一审批人同意
+    Any,
+    /// 至少 `quorum_count` 个审批人同意
+    AtLeast,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
@@ -192,6 +365,20 @@ pub struct FlowStateCountGroupByStateResp {
 pub struct FlowStateRelModelExt {
     pub sort: i64,
     pub show_btns: Option<Vec<String>>,
+    /// SLA配置，为空时不限制实例在该状态的停留时间
+    pub sla: Option<FlowStateSlaConf>,
+}
+
+/// 状态的SLA配置
+///
+/// 实例在该状态的停留时间超过目标时长即为超时，超时时通过事件中心发布超时事件，并可通过 reach 发送消息。
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug, poem_openapi::Object, sea_orm::FromJsonQueryResult)]
+pub struct FlowStateSlaConf {
+    /// 目标时长（秒）
+    #[oai(validator(minimum(value = "1")))]
+    pub target_secs: i64,
+    /// 超时时发送的消息，内置变量另包含 sla_secs，elapsed_secs
+    pub reach: Option<FlowStateMailConf>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, poem_openapi::Object, sea_orm::FromQueryResult, Clone)]
@@ -199,6 +386,7 @@ pub struct FlowStateRelModelModifyReq {
     pub id: String,
     pub sort: Option<i64>,
     pub show_btns: Option<Vec<String>>,
+    pub sla: Option<FlowStateSlaConf>,
 }
 
 /// 工作流状态聚合信息
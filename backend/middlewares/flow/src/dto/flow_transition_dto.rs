@@ -46,6 +46,9 @@ pub struct FlowTransitionAddReq {
     /// 权限配置：为true时，指定组织可以操作
     pub guard_by_spec_org_ids: Option<Vec<String>>,
     /// 权限配置：满足条件时，允许操作
+    ///
+    /// 与其他权限配置同时生效（且关系），即需满足条件且满足其他权限配置中的任意一项（未配置时不限制操作人）。
+    /// 旧版本中该配置未生效，已配置该条件的动作在条件不满足时将不再允许操作。
     pub guard_by_other_conds: Option<Vec<Vec<BasicQueryCondInfo>>>,
     /// 二次确认配置信息
     pub double_check: Option<FlowTransitionDoubleCheckInfo>,
@@ -100,6 +103,9 @@ pub struct FlowTransitionModifyReq {
     /// 权限配置：为true时，指定组织可以操作
     pub guard_by_spec_org_ids: Option<Vec<String>>,
     /// 权限配置：满足条件时，允许操作
+    ///
+    /// 与其他权限配置同时生效（且关系），即需满足条件且满足其他权限配置中的任意一项（未配置时不限制操作人）。
+    /// 旧版本中该配置未生效，已配置该条件的动作在条件不满足时将不再允许操作。
     pub guard_by_other_conds: Option<Vec<Vec<BasicQueryCondInfo>>>,
     /// 二次确认配置信息
     pub double_check: Option<FlowTransitionDoubleCheckInfo>,
@@ -197,6 +203,13 @@ pub struct FlowTransitionDetailResp {
 }
 
 impl FlowTransitionDetailResp {
+    /// Delay seconds of the timed transfer, ``None`` if the timed transfer is not configured
+    ///
+    /// 定时流转的延时秒数，未配置定时流转时为 ``None``
+    pub fn transfer_by_timer_secs(&self) -> Option<i64> {
+        self.transfer_by_timer.trim().parse::<i64>().ok().filter(|secs| *secs > 0)
+    }
+
     pub fn guard_by_other_conds(&self) -> Option<Vec<Vec<BasicQueryCondInfo>>> {
         if self.guard_by_other_conds.is_array() && !&self.guard_by_other_conds.as_array().unwrap().is_empty() {
             Some(TardisFuns::json.json_to_obj(self.guard_by_other_conds.clone()).unwrap_or_default())
@@ -17,6 +17,14 @@ pub struct FlowConfig {
     pub search_url: String,
     pub log_url: String,
     pub iam_url: String,
+    pub reach_url: String,
+
+    /// Interval of checking the due timers of the timed transfers and the breached SLAs of the states,
+    /// 0 means they are only triggered by the ``/ci/inst/trigger_timer`` and ``/ci/inst/trigger_sla`` apis (e.g. by a job of the schedule middleware)
+    pub timer_check_interval_sec: u32,
+    /// Key used to sign the requests of the callback states (``X-Flow-Signature`` header),
+    /// empty means the requests are sent without signature
+    pub callback_sign_key: String,
 }
 
 impl Default for FlowConfig {
@@ -28,6 +36,9 @@ impl Default for FlowConfig {
             search_url: "http://127.0.0.1:8080/spi-search".to_string(),
             log_url: "http://127.0.0.1:8080/spi-log".to_string(),
             iam_url: "http://127.0.0.1:8080/iam".to_string(),
+            reach_url: "http://127.0.0.1:8080/reach".to_string(),
+            timer_check_interval_sec: 60,
+            callback_sign_key: "".to_string(),
         }
     }
 }
@@ -56,6 +67,14 @@ impl FlowConfig {
             format!("{}/", self.iam_url)
         }
     }
+
+    pub fn reach_url(&self) -> String {
+        if self.reach_url.ends_with('/') {
+            self.reach_url.clone()
+        } else {
+            format!("{}/", self.reach_url)
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
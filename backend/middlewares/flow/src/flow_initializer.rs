@@ -31,7 +31,7 @@ use crate::{
         cs::flow_cs_config_api,
         ct::flow_ct_model_api,
     },
-    domain::{flow_inst, flow_model, flow_state, flow_transition},
+    domain::{flow_inst, flow_inst_approval, flow_inst_state_log, flow_inst_timer, flow_model, flow_model_version, flow_state, flow_transition},
     dto::{
         flow_model_dto::FlowModelFilterReq,
         flow_state_dto::FlowSysStateKind,
@@ -40,6 +40,7 @@ use crate::{
     flow_config::{BasicInfo, FlowBasicInfoManager, FlowConfig},
     flow_constants,
     serv::{
+        flow_inst_timer_serv::FlowInstTimerServ,
         flow_model_serv::FlowModelServ,
         flow_rel_serv::{FlowRelKind, FlowRelServ},
         flow_state_serv::FlowStateServ,
@@ -48,7 +49,11 @@ use crate::{
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     let funs = flow_constants::get_tardis_inst();
+    let timer_check_interval_sec = funs.conf::<FlowConfig>().timer_check_interval_sec;
     init_db(funs).await?;
+    if timer_check_interval_sec > 0 {
+        FlowInstTimerServ::trigger_periodically(timer_check_interval_sec).await;
+    }
     init_api(web_server).await
 }
 
@@ -83,19 +88,29 @@ pub async fn init_db(mut funs: TardisFunsInst) -> TardisResult<()> {
         owner: "".to_string(),
         ..Default::default()
     };
+    let db_kind = TardisFuns::reldb().backend();
+    let compatible_type = TardisFuns::reldb().compatible_type();
     funs.begin().await?;
     if check_initialized(&funs, &ctx).await? {
         init_basic_info(&funs).await?;
         rebind_model_with_template(&funs, &ctx).await?;
+        // the column is added later than the table, it's migrated in place only on Postgres, which is the backend of the deployed environments,
+        // the other backends (MySQL has no `ADD COLUMN IF NOT EXISTS`) are expected to be initialized from scratch
+        if db_kind == sea_orm::DatabaseBackend::Postgres {
+            funs.db().execute_one("ALTER TABLE flow_inst ADD COLUMN IF NOT EXISTS rel_flow_model_version_id character varying", vec![]).await?;
+        }
     } else {
-        let db_kind = TardisFuns::reldb().backend();
-        let compatible_type = TardisFuns::reldb().compatible_type();
         funs.db().init(flow_state::ActiveModel::init(db_kind, None, compatible_type)).await?;
         funs.db().init(flow_model::ActiveModel::init(db_kind, None, compatible_type)).await?;
         funs.db().init(flow_transition::ActiveModel::init(db_kind, None, compatible_type)).await?;
         funs.db().init(flow_inst::ActiveModel::init(db_kind, None, compatible_type)).await?;
         init_rbum_data(&funs, &ctx).await?;
     };
+    // the tables are added later than the others, so they are also created for the initialized domain
+    funs.db().init(flow_inst_timer::ActiveModel::init(db_kind, None, compatible_type)).await?;
+    funs.db().init(flow_inst_approval::ActiveModel::init(db_kind, None, compatible_type)).await?;
+    funs.db().init(flow_model_version::ActiveModel::init(db_kind, None, compatible_type)).await?;
+    funs.db().init(flow_inst_state_log::ActiveModel::init(db_kind, None, compatible_type)).await?;
     funs.commit().await?;
     Ok(())
 }
@@ -234,6 +249,10 @@ pub async fn truncate_data<'a>(funs: &TardisFunsInst) -> TardisResult<()> {
     funs.db().execute(Table::truncate().table(flow_model::Entity)).await?;
     funs.db().execute(Table::truncate().table(flow_transition::Entity)).await?;
     funs.db().execute(Table::truncate().table(flow_inst::Entity)).await?;
+    funs.db().execute(Table::truncate().table(flow_inst_timer::Entity)).await?;
+    funs.db().execute(Table::truncate().table(flow_inst_approval::Entity)).await?;
+    funs.db().execute(Table::truncate().table(flow_model_version::Entity)).await?;
+    funs.db().execute(Table::truncate().table(flow_inst_state_log::Entity)).await?;
     funs.cache().flushdb().await?;
     Ok(())
 }
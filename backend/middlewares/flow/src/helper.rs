@@ -1 +1,2 @@
+pub(crate) mod bpmn_helper;
 pub(crate) mod loop_check_helper;
@@ -0,0 +1,380 @@
+//! BPMN 2.0 mapping of the flow model
+//!
+//! 工作流模型的 BPMN 2.0 映射
+//!
+//! Supported subset: a single ``process`` with one ``startEvent`` pointing to the initial state, tasks and intermediate/end events as states,
+//! and ``sequenceFlow`` as transitions. Gateways, sub processes and boundary events are not supported.
+//! The configurations specific to the platform (state kinds, guards, vars, post actions ...) are kept as json in ``extensionElements``,
+//! so that an exported document can be imported without loss, while documents from other tools are imported as simple states and transitions.
+//!
+//! 支持的子集：单个 ``process``，一个指向初始状态的 ``startEvent``，任务及中间/结束事件映射为状态，``sequenceFlow`` 映射为动作。
+//! 不支持网关、子流程及边界事件。平台特有的配置（状态类型、权限、变量、后置动作等）以json形式保存在 ``extensionElements`` 中，
+//! 因此导出的文档可以无损导入，其他工具产生的文档则导入为普通的状态及动作。
+
+use std::collections::{HashMap, HashSet};
+
+use quick_xml::{escape::escape, events::Event, Reader};
+use serde::{Deserialize, Serialize};
+use tardis::{
+    serde_json::{self, json},
+    TardisFuns,
+};
+
+use crate::dto::{
+    flow_model_exchange_dto::{FlowModelExchangeDoc, FlowModelExchangeState, FlowModelExchangeTransition, FlowModelImportErrorResp, FLOW_MODEL_EXCHANGE_VERSION},
+    flow_state_dto::{FlowStateKind, FlowStateRelModelExt, FlowSysStateKind},
+    flow_transition_dto::FlowTransitionAddReq,
+};
+
+const BPMN_NS: &str = "http://www.omg.org/spec/BPMN/20100524/MODEL";
+const BIOS_NS: &str = "https://bios.idealworld.group/schema/flow";
+const START_EVENT_ID: &str = "start";
+
+/// Elements mapped to states
+const STATE_ELEMENTS: [&str; 11] = [
+    "task",
+    "userTask",
+    "manualTask",
+    "sendTask",
+    "receiveTask",
+    "serviceTask",
+    "scriptTask",
+    "businessRuleTask",
+    "intermediateCatchEvent",
+    "intermediateThrowEvent",
+    "endEvent",
+];
+/// Flow elements which can not be mapped to the model
+const UNSUPPORTED_ELEMENTS: [&str; 10] = [
+    "exclusiveGateway",
+    "parallelGateway",
+    "inclusiveGateway",
+    "eventBasedGateway",
+    "complexGateway",
+    "subProcess",
+    "adHocSubProcess",
+    "transaction",
+    "callActivity",
+    "boundaryEvent",
+];
+
+#[derive(Serialize, Deserialize, Default)]
+struct BpmnModelExt {
+    #[serde(default)]
+    icon: String,
+    #[serde(default)]
+    tag: String,
+    #[serde(default)]
+    template: bool,
+}
+
+#[derive(Default)]
+struct XmlNode {
+    name: String,
+    attrs: HashMap<String, String>,
+    text: String,
+    children: Vec<XmlNode>,
+}
+
+impl XmlNode {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.get(name).map(|value| value.as_str()).filter(|value| !value.is_empty())
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// The text of the platform extension, e.g. ``<extensionElements><bios:state>...</bios:state></extensionElements>``
+    fn extension(&self, name: &str) -> Option<&str> {
+        self.child("extensionElements").and_then(|ext| ext.child(name)).map(|ext| ext.text.trim()).filter(|text| !text.is_empty())
+    }
+}
+
+/// Export the model document as BPMN 2.0 XML
+///
+/// 将模型文档导出为 BPMN 2.0 XML
+pub(crate) fn to_bpmn(doc: &FlowModelExchangeDoc, process_id: &str) -> String {
+    let json_text = |value: &serde_json::Value| escape(&value.to_string()).to_string();
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<bpmn:definitions xmlns:bpmn=\"{BPMN_NS}\" xmlns:bios=\"{BIOS_NS}\" id=\"definitions_{}\" targetNamespace=\"{BIOS_NS}\" exporter=\"bios-flow\" exporterVersion=\"{FLOW_MODEL_EXCHANGE_VERSION}\">\n",
+        escape(process_id)
+    ));
+    xml.push_str(&format!(
+        "  <bpmn:process id=\"process_{}\" name=\"{}\" isExecutable=\"true\">\n",
+        escape(process_id),
+        escape(&doc.name)
+    ));
+    if !doc.info.is_empty() {
+        xml.push_str(&format!("    <bpmn:documentation>{}</bpmn:documentation>\n", escape(&doc.info)));
+    }
+    xml.push_str(&format!(
+        "    <bpmn:extensionElements>\n      <bios:model>{}</bios:model>\n    </bpmn:extensionElements>\n",
+        json_text(&json!(BpmnModelExt {
+            icon: doc.icon.clone(),
+            tag: doc.tag.clone(),
+            template: doc.template,
+        }))
+    ));
+    xml.push_str(&format!("    <bpmn:startEvent id=\"{START_EVENT_ID}\" />\n"));
+    xml.push_str(&format!(
+        "    <bpmn:sequenceFlow id=\"{START_EVENT_ID}_flow\" sourceRef=\"{START_EVENT_ID}\" targetRef=\"{}\" />\n",
+        state_element_id(&doc.init_state_key)
+    ));
+    for state in &doc.states {
+        let element = match state.state_kind {
+            FlowStateKind::Simple | FlowStateKind::Form | FlowStateKind::Approval => "userTask",
+            FlowStateKind::Mail => "sendTask",
+            FlowStateKind::Callback => "serviceTask",
+            FlowStateKind::Script => "scriptTask",
+            FlowStateKind::Timer => "intermediateCatchEvent",
+        };
+        xml.push_str(&format!("    <bpmn:{element} id=\"{}\" name=\"{}\">\n", state_element_id(&state.key), escape(&state.name)));
+        xml.push_str(&format!(
+            "      <bpmn:extensionElements>\n        <bios:state>{}</bios:state>\n      </bpmn:extensionElements>\n",
+            json_text(&json!(state))
+        ));
+        if state.state_kind == FlowStateKind::Timer {
+            xml.push_str("      <bpmn:timerEventDefinition />\n");
+        }
+        xml.push_str(&format!("    </bpmn:{element}>\n"));
+    }
+    for transition in &doc.transitions {
+        xml.push_str(&format!(
+            "    <bpmn:sequenceFlow id=\"{}\" name=\"{}\" sourceRef=\"{}\" targetRef=\"{}\">\n",
+            transition_element_id(&transition.key),
+            escape(&transition.transition.name.as_ref().map(|name| name.to_string()).unwrap_or_default()),
+            state_element_id(&transition.transition.from_flow_state_id),
+            state_element_id(&transition.transition.to_flow_state_id)
+        ));
+        xml.push_str(&format!(
+            "      <bpmn:extensionElements>\n        <bios:transition>{}</bios:transition>\n      </bpmn:extensionElements>\n",
+            json_text(&json!(transition))
+        ));
+        xml.push_str("    </bpmn:sequenceFlow>\n");
+    }
+    xml.push_str("  </bpmn:process>\n");
+    xml.push_str("</bpmn:definitions>\n");
+    xml
+}
+
+/// Import the model document from BPMN 2.0 XML
+///
+/// 从 BPMN 2.0 XML 导入模型文档
+///
+/// The structure of the diagram (names, source and target of the sequence flows) takes precedence over the extensions,
+/// so that the document can still be edited by other tools after it is exported.
+/// 图的结构（名称、连线的来源及目标）优先于扩展信息，因此导出后的文档仍可由其他工具编辑。
+pub(crate) fn from_bpmn(content: &str) -> Result<FlowModelExchangeDoc, Vec<FlowModelImportErrorResp>> {
+    let root = parse_xml(content).map_err(|e| vec![FlowModelImportErrorResp::new("document", e)])?;
+    if root.name != "definitions" {
+        return Err(vec![FlowModelImportErrorResp::new("document", "the root element must be bpmn:definitions")]);
+    }
+    let Some(process) = root.child("process") else {
+        return Err(vec![FlowModelImportErrorResp::new("document", "bpmn:process is required")]);
+    };
+    let mut errors = vec![];
+    let model_ext = match process.extension("model").map(|text| TardisFuns::json.str_to_obj::<BpmnModelExt>(text)) {
+        Some(Ok(model_ext)) => model_ext,
+        Some(Err(e)) => {
+            errors.push(FlowModelImportErrorResp::new("model", format!("invalid extension: {}", e.message)));
+            BpmnModelExt::default()
+        }
+        None => BpmnModelExt::default(),
+    };
+
+    let mut start_event_ids = vec![];
+    let mut sequence_flows = vec![];
+    let mut states = vec![];
+    // element id -> state key
+    let mut state_keys = HashMap::new();
+    // the states without extension
+    let mut plain_state_keys = HashSet::new();
+    for element in &process.children {
+        let element_id = element.attr("id").unwrap_or_default().to_string();
+        if element.name == "startEvent" {
+            start_event_ids.push(element_id);
+        } else if element.name == "sequenceFlow" {
+            sequence_flows.push(element);
+        } else if UNSUPPORTED_ELEMENTS.contains(&element.name.as_str()) {
+            errors.push(FlowModelImportErrorResp::new(
+                format!("state:{element_id}"),
+                format!("bpmn:{} is not supported", element.name),
+            ));
+        } else if STATE_ELEMENTS.contains(&element.name.as_str()) {
+            if element_id.is_empty() {
+                errors.push(FlowModelImportErrorResp::new("state:", format!("the id of bpmn:{} is required", element.name)));
+                continue;
+            }
+            let mut state = match element.extension("state").map(|text| TardisFuns::json.str_to_obj::<FlowModelExchangeState>(text)) {
+                Some(Ok(state)) => state,
+                Some(Err(e)) => {
+                    errors.push(FlowModelImportErrorResp::new(format!("state:{element_id}"), format!("invalid extension: {}", e.message)));
+                    continue;
+                }
+                None => {
+                    plain_state_keys.insert(element_id.clone());
+                    FlowModelExchangeState {
+                        key: element_id.clone(),
+                        name: element_id.clone(),
+                        icon: "".to_string(),
+                        color: "".to_string(),
+                        sys_state: if element.name == "endEvent" {
+                            FlowSysStateKind::Finish
+                        } else {
+                            FlowSysStateKind::Progress
+                        },
+                        info: element.child("documentation").map(|doc| doc.text.trim().to_string()).unwrap_or_default(),
+                        state_kind: FlowStateKind::Simple,
+                        kind_conf: json!({}),
+                        tags: if model_ext.tag.is_empty() { vec![] } else { vec![model_ext.tag.clone()] },
+                        ext: FlowStateRelModelExt {
+                            sort: states.len() as i64 + 1,
+                            ..Default::default()
+                        },
+                    }
+                }
+            };
+            if let Some(name) = element.attr("name") {
+                state.name = name.to_string();
+            }
+            state_keys.insert(element_id, state.key.clone());
+            states.push(state);
+        }
+    }
+
+    let mut init_state_keys = vec![];
+    let mut transitions = vec![];
+    for sequence_flow in sequence_flows {
+        let element_id = sequence_flow.attr("id").unwrap_or_default();
+        let source_ref = sequence_flow.attr("sourceRef").unwrap_or_default();
+        let target_ref = sequence_flow.attr("targetRef").unwrap_or_default();
+        let Some(to_state_key) = state_keys.get(target_ref) else {
+            errors.push(FlowModelImportErrorResp::new(
+                format!("transition:{element_id}"),
+                format!("targetRef [{target_ref}] does not refer to a state"),
+            ));
+            continue;
+        };
+        if start_event_ids.iter().any(|start_event_id| start_event_id == source_ref) {
+            init_state_keys.push(to_state_key.clone());
+            continue;
+        }
+        let Some(from_state_key) = state_keys.get(source_ref) else {
+            errors.push(FlowModelImportErrorResp::new(
+                format!("transition:{element_id}"),
+                format!("sourceRef [{source_ref}] does not refer to a state"),
+            ));
+            continue;
+        };
+        let mut transition = match sequence_flow.extension("transition").map(|text| TardisFuns::json.str_to_obj::<FlowModelExchangeTransition>(text)) {
+            Some(Ok(transition)) => transition,
+            Some(Err(e)) => {
+                errors.push(FlowModelImportErrorResp::new(
+                    format!("transition:{element_id}"),
+                    format!("invalid extension: {}", e.message),
+                ));
+                continue;
+            }
+            None => FlowModelExchangeTransition {
+                key: element_id.to_string(),
+                transition: FlowTransitionAddReq::default(),
+            },
+        };
+        transition.transition.from_flow_state_id = from_state_key.clone();
+        transition.transition.to_flow_state_id = to_state_key.clone();
+        if let Some(name) = sequence_flow.attr("name") {
+            transition.transition.name = Some(name.into());
+        }
+        transitions.push(transition);
+    }
+    if init_state_keys.len() != 1 {
+        errors.push(FlowModelImportErrorResp::new(
+            "model",
+            "exactly one bpmn:startEvent with one outgoing bpmn:sequenceFlow to a state is required",
+        ));
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let init_state_key = init_state_keys.remove(0);
+    if plain_state_keys.contains(&init_state_key) {
+        if let Some(state) = states.iter_mut().find(|state| state.key == init_state_key) {
+            state.sys_state = FlowSysStateKind::Start;
+        }
+    }
+    Ok(FlowModelExchangeDoc {
+        format_version: FLOW_MODEL_EXCHANGE_VERSION.to_string(),
+        name: process.attr("name").unwrap_or_default().to_string(),
+        icon: model_ext.icon,
+        info: process.child("documentation").map(|doc| doc.text.trim().to_string()).unwrap_or_default(),
+        tag: model_ext.tag,
+        template: model_ext.template,
+        init_state_key,
+        states,
+        transitions,
+    })
+}
+
+fn state_element_id(state_key: &str) -> String {
+    format!("state_{}", escape(state_key))
+}
+
+fn transition_element_id(transition_key: &str) -> String {
+    format!("flow_{}", escape(transition_key))
+}
+
+fn parse_xml(content: &str) -> Result<XmlNode, String> {
+    let mut reader = Reader::from_str(content);
+    let mut stack: Vec<XmlNode> = vec![];
+    let mut root = None;
+    loop {
+        let node = match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                stack.push(new_node(&e)?);
+                continue;
+            }
+            Ok(Event::Empty(e)) => new_node(&e)?,
+            Ok(Event::End(_)) => stack.pop().ok_or_else(|| "unexpected end tag".to_string())?,
+            Ok(Event::Text(e)) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&e.unescape().map_err(|e| e.to_string())?);
+                }
+                continue;
+            }
+            Ok(Event::CData(e)) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&String::from_utf8_lossy(&e.into_inner()));
+                }
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => continue,
+            Err(e) => return Err(format!("invalid xml at position {}: {e}", reader.buffer_position())),
+        };
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => root = Some(node),
+        }
+    }
+    if !stack.is_empty() {
+        return Err("unexpected end of document".to_string());
+    }
+    root.ok_or_else(|| "the document is empty".to_string())
+}
+
+fn new_node(e: &quick_xml::events::BytesStart) -> Result<XmlNode, String> {
+    let mut attrs = HashMap::new();
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| e.to_string())?;
+        let value = attr.unescape_value().map_err(|e| e.to_string())?;
+        attrs.insert(String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string(), value.to_string());
+    }
+    Ok(XmlNode {
+        name: String::from_utf8_lossy(e.local_name().as_ref()).to_string(),
+        attrs,
+        ..Default::default()
+    })
+}
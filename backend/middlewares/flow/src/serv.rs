@@ -2,7 +2,14 @@ pub mod clients;
 pub mod flow_config_serv;
 pub mod flow_event_serv;
 pub mod flow_external_serv;
+pub mod flow_inst_approval_serv;
 pub mod flow_inst_serv;
+pub mod flow_inst_sla_serv;
+pub mod flow_inst_timer_serv;
+pub mod flow_model_exchange_serv;
 pub mod flow_model_serv;
+pub mod flow_model_simulate_serv;
+pub mod flow_model_version_serv;
 pub mod flow_rel_serv;
+pub mod flow_state_kind_serv;
 pub mod flow_state_serv;
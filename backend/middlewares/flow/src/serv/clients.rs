@@ -1,2 +1,3 @@
 pub mod flow_log_client;
+pub mod reach_client;
 pub mod search_client;
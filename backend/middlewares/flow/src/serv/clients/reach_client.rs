@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use bios_sdk_invoke::invoke_constants::TARDIS_CONTEXT;
+use serde::Serialize;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    web::web_resp::{TardisResp, Void},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::flow_config::FlowConfig;
+
+pub struct FlowReachClient;
+
+#[derive(Serialize, Default, Debug, Clone)]
+pub struct ReachMsgSendReq {
+    pub scene_code: String,
+    pub receives: Vec<ReachMsgReceive>,
+    pub rel_item_id: String,
+    pub replace: HashMap<String, String>,
+}
+
+#[derive(Serialize, Default, Debug, Clone)]
+pub struct ReachMsgReceive {
+    pub receive_group_code: String,
+    pub receive_kind: String,
+    pub receive_ids: Vec<String>,
+}
+
+impl FlowReachClient {
+    /// Send message by the trigger scene of reach
+    pub async fn send_message(req: &ReachMsgSendReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let url = format!("{}ci/message/send", funs.conf::<FlowConfig>().reach_url());
+        let headers = vec![(TARDIS_CONTEXT.to_string(), TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(ctx)?))];
+        let resp = funs.web_client().put::<ReachMsgSendReq, TardisResp<Void>>(&url, req, headers).await?;
+        match resp.body {
+            Some(body) if body.code.starts_with("200") => Ok(()),
+            Some(body) => Err(funs.err().conflict("flow_reach", "send_message", &body.msg, "409-flow-reach-send-failed")),
+            None => Err(funs.err().conflict(
+                "flow_reach",
+                "send_message",
+                &format!("send message failed with status {}", resp.code),
+                "409-flow-reach-send-failed",
+            )),
+        }
+    }
+}
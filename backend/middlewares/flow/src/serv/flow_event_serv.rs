@@ -19,7 +19,7 @@ use crate::{
     dto::{
         flow_external_dto::{FlowExternalCallbackOp, FlowExternalParams},
         flow_inst_dto::{FlowInstDetailResp, FlowInstTransferReq},
-        flow_model_dto::{FlowModelDetailResp, FlowModelFilterReq},
+        flow_model_dto::FlowModelDetailResp,
         flow_state_dto::FlowStateFilterReq,
         flow_transition_dto::{
             FlowTransitionActionByStateChangeInfo, FlowTransitionActionByVarChangeInfoChangedKind, FlowTransitionActionChangeAgg, FlowTransitionActionChangeKind,
@@ -29,7 +29,7 @@ use crate::{
     helper::loop_check_helper,
 };
 
-use super::{flow_external_serv::FlowExternalServ, flow_inst_serv::FlowInstServ, flow_model_serv::FlowModelServ, flow_state_serv::FlowStateServ};
+use super::{flow_external_serv::FlowExternalServ, flow_inst_serv::FlowInstServ, flow_model_version_serv::FlowModelVersionServ, flow_state_serv::FlowStateServ};
 use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
 
 use itertools::Itertools;
@@ -45,20 +45,7 @@ impl FlowEventServ {
         funs: &TardisFunsInst,
     ) -> TardisResult<()> {
         let flow_inst_detail = FlowInstServ::get(flow_inst_id, funs, ctx).await?;
-        let flow_model = FlowModelServ::get_item(
-            &flow_inst_detail.rel_flow_model_id,
-            &FlowModelFilterReq {
-                basic: RbumBasicFilterReq {
-                    with_sub_own_paths: true,
-                    own_paths: Some("".to_string()),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            funs,
-            ctx,
-        )
-        .await?;
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst_detail, funs, ctx).await?;
         let flow_transitions = flow_model
             .transitions()
             .into_iter()
@@ -104,7 +91,7 @@ impl FlowEventServ {
         Ok(true)
     }
 
-    fn do_check_front_condition(current_vars: &HashMap<String, Value>, condition: &FlowTransitionFrontActionInfo) -> TardisResult<bool> {
+    pub(crate) fn do_check_front_condition(current_vars: &HashMap<String, Value>, condition: &FlowTransitionFrontActionInfo) -> TardisResult<bool> {
         match condition.right_value {
             FlowTransitionFrontActionRightValue::ChangeContent => {
                 let left_value = if let Some(custom_value) = current_vars.get(&format!("custom_{}", condition.left_value)) {
@@ -165,20 +152,7 @@ impl FlowEventServ {
             own_paths: "".to_string(),
             ..ctx.clone()
         };
-        let flow_model = FlowModelServ::get_item(
-            &flow_inst_detail.rel_flow_model_id,
-            &FlowModelFilterReq {
-                basic: RbumBasicFilterReq {
-                    with_sub_own_paths: true,
-                    own_paths: Some("".to_string()),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            funs,
-            ctx,
-        )
-        .await?;
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst_detail, funs, ctx).await?;
         let model_transition = flow_model.transitions();
         let next_flow_transition = model_transition.iter().find(|trans| trans.id == flow_transition_id);
         if next_flow_transition.is_none() {
@@ -488,20 +462,7 @@ impl FlowEventServ {
         let insts = FlowInstServ::find_detail(rel_inst_ids, funs, ctx).await?;
         for rel_inst in insts {
             // find transition
-            let flow_model = FlowModelServ::get_item(
-                &rel_inst.rel_flow_model_id,
-                &FlowModelFilterReq {
-                    basic: RbumBasicFilterReq {
-                        with_sub_own_paths: true,
-                        own_paths: Some("".to_string()),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-                funs,
-                ctx,
-            )
-            .await?;
+            let flow_model = FlowModelVersionServ::get_inst_model(&rel_inst, funs, ctx).await?;
             let transition_resp = FlowInstServ::do_find_next_transitions(&rel_inst, &flow_model, None, &None, true, funs, ctx)
                 .await?
                 .next_flow_transitions
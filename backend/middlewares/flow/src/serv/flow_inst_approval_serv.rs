@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use bios_basic::rbum::{dto::rbum_filer_dto::RbumBasicFilterReq, serv::rbum_item_serv::RbumItemCrudOperation};
+use itertools::Itertools;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    db::{
+        reldb_client::IdResp,
+        sea_orm::{
+            sea_query::{Expr, Order, Query},
+            Iterable, Set,
+        },
+    },
+    serde_json::Value,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    domain::{flow_inst, flow_inst_approval},
+    dto::{
+        flow_inst_dto::{
+            FlowInstApprovalDecision, FlowInstApprovalGroupResp, FlowInstApprovalRecordResp, FlowInstApprovalResp, FlowInstApprovalStatus, FlowInstApproveReq, FlowInstDetailResp,
+        },
+        flow_state_dto::{FlowStateApprovalConf, FlowStateApprovalGroup, FlowStateApprovalQuorumKind, FlowStateDetailResp, FlowStateFilterReq, FlowStateKind},
+    },
+};
+
+use super::{flow_inst_serv::FlowInstServ, flow_state_kind_serv::FlowStateKindServ, flow_state_serv::FlowStateServ};
+
+/// Multi-approver (countersign) of the approval state
+///
+/// 会签状态的多人审批
+pub struct FlowInstApprovalServ;
+
+impl FlowInstApprovalServ {
+    /// Approve or reject the instance in the approval state by the current user
+    ///
+    /// 当前用户审批处于会签状态的实例
+    ///
+    /// Once the approval is decided, the instance is transferred to the configured state after the transaction is committed.
+    /// 审批结果确定后，在事务提交后流转至所配置的状态。
+    pub async fn approve(flow_inst_id: &str, approve_req: &FlowInstApproveReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<FlowInstApprovalResp> {
+        // Lock the instance until the end of the transaction, so that the concurrent decisions are counted one by one
+        // and the instance can't leave the state (archiving the records) in the meantime
+        funs.db()
+            .get_dto::<IdResp>(Query::select().column(flow_inst::Column::Id).from(flow_inst::Entity).and_where(Expr::col(flow_inst::Column::Id).eq(flow_inst_id)).lock_exclusive())
+            .await?;
+        let flow_inst = FlowInstServ::get(flow_inst_id, funs, ctx).await?;
+        if flow_inst.finish_time.is_some() {
+            return Err(funs.err().bad_request("flow_inst", "approve", "the instance has been finished", "400-flow-inst-finished"));
+        }
+        let state = Self::get_state(&flow_inst.current_state_id, funs, ctx).await?;
+        if state.state_kind != FlowStateKind::Approval {
+            return Err(funs.err().bad_request(
+                "flow_inst",
+                "approve",
+                "the current state of the instance is not an approval state",
+                "400-flow-inst-not-in-approval",
+            ));
+        }
+        let Some(approval) = &flow_inst.approval else {
+            return Err(funs.err().bad_request("flow_inst", "approve", "the approval state is not configured properly", "400-flow-state-kind-conf-invalid"));
+        };
+        if approval.status != FlowInstApprovalStatus::Pending {
+            return Err(funs.err().conflict("flow_inst", "approve", "the approval has been decided", "409-flow-inst-approval-decided"));
+        }
+        let groups = approval
+            .groups
+            .iter()
+            .filter(|group| approve_req.group_code.as_ref().map(|group_code| group_code == &group.code).unwrap_or(true))
+            .filter(|group| group.approver_ids.contains(&ctx.owner))
+            .collect_vec();
+        if groups.is_empty() {
+            return Err(funs.err().unauthorized("flow_inst", "approve", "the current user is not an approver of the state", "401-flow-inst-not-approver"));
+        }
+        if groups.iter().any(|group| group.approved_ids.contains(&ctx.owner) || group.rejected_ids.contains(&ctx.owner)) {
+            return Err(funs.err().conflict("flow_inst", "approve", "the current user has already decided", "409-flow-inst-approval-duplicated"));
+        }
+        for group in groups {
+            funs.db()
+                .insert_one(
+                    flow_inst_approval::ActiveModel {
+                        id: Set(TardisFuns::field.nanoid()),
+                        rel_flow_inst_id: Set(flow_inst_id.to_string()),
+                        rel_flow_state_id: Set(state.id.clone()),
+                        group_code: Set(group.code.clone()),
+                        approver_id: Set(ctx.owner.clone()),
+                        decision: Set(approve_req.decision.clone()),
+                        message: Set(approve_req.message.clone()),
+                        archived: Set(false),
+                        archive_id: Set("".to_string()),
+                        own_paths: Set(flow_inst.own_paths.clone()),
+                        ..Default::default()
+                    },
+                    ctx,
+                )
+                .await?;
+        }
+        let conf: FlowStateApprovalConf = TardisFuns::json.json_to_obj(state.kind_conf.clone())?;
+        let records = Self::find_records(&[flow_inst_id.to_string()], funs).await?.remove(flow_inst_id).unwrap_or_default();
+        let approval = Self::progress(&state.id, &conf, &flow_inst.current_vars.clone().unwrap_or_default(), &records);
+        if approval.status != FlowInstApprovalStatus::Pending {
+            FlowStateKindServ::async_on_enter(flow_inst_id, ctx).await?;
+        }
+        Ok(approval)
+    }
+
+    /// Transfer the instance according to the approval result, it stays in the state while the approval is pending
+    ///
+    /// 按审批结果流转实例，审批中时停留在该状态
+    pub(crate) async fn settle(flow_inst: &FlowInstDetailResp, state: &FlowStateDetailResp, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let Some(approval) = &flow_inst.approval else {
+            return Ok(());
+        };
+        let conf: FlowStateApprovalConf = TardisFuns::json.json_to_obj(state.kind_conf.clone())?;
+        let to_flow_state_id = match approval.status {
+            FlowInstApprovalStatus::Pending => None,
+            FlowInstApprovalStatus::Approved => conf.pass_to_flow_state_id,
+            FlowInstApprovalStatus::Rejected => conf.reject_to_flow_state_id,
+        };
+        if let Some(to_flow_state_id) = to_flow_state_id {
+            FlowStateKindServ::transfer_to(flow_inst, state, &to_flow_state_id, funs, ctx).await?;
+        }
+        Ok(())
+    }
+
+    /// Archive the decisions of the instance, called when the instance leaves its state
+    ///
+    /// 归档实例的审批记录，实例离开所处状态时调用
+    pub async fn archive(flow_inst_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.db()
+            .execute(
+                Query::update()
+                    .table(flow_inst_approval::Entity)
+                    .value(flow_inst_approval::Column::Archived, true)
+                    .value(flow_inst_approval::Column::ArchiveId, TardisFuns::field.nanoid())
+                    .and_where(Expr::col(flow_inst_approval::Column::RelFlowInstId).eq(flow_inst_id))
+                    .and_where(Expr::col(flow_inst_approval::Column::Archived).eq(false)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Find the unarchived decisions of the instances, grouped by instance id
+    ///
+    /// 获取实例未归档的审批记录，按实例Id分组
+    pub async fn find_records(flow_inst_ids: &[String], funs: &TardisFunsInst) -> TardisResult<HashMap<String, Vec<flow_inst_approval::Model>>> {
+        if flow_inst_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let records = funs
+            .db()
+            .find_dtos::<flow_inst_approval::Model>(
+                Query::select()
+                    .columns(flow_inst_approval::Column::iter())
+                    .from(flow_inst_approval::Entity)
+                    .and_where(Expr::col(flow_inst_approval::Column::RelFlowInstId).is_in(flow_inst_ids))
+                    .and_where(Expr::col(flow_inst_approval::Column::Archived).eq(false))
+                    .order_by(flow_inst_approval::Column::CreateTime, Order::Asc),
+            )
+            .await?;
+        Ok(records.into_iter().into_group_map_by(|record| record.rel_flow_inst_id.clone()))
+    }
+
+    /// Calculate the approval progress from the configuration of the state and the decisions
+    ///
+    /// 根据状态配置及审批记录计算审批进度
+    ///
+    /// A group is approved when the approvals reach the quorum, and rejected when the quorum can no longer be reached.
+    /// A group without approvers stays pending until they are assigned by the vars.
+    /// 审批组的同意人数达到通过规则时通过，无法再达到时驳回。没有审批人的审批组保持审批中，直至通过变量指定审批人。
+    pub fn progress(flow_state_id: &str, conf: &FlowStateApprovalConf, vars: &HashMap<String, Value>, records: &[flow_inst_approval::Model]) -> FlowInstApprovalResp {
+        let groups = conf
+            .groups
+            .iter()
+            .map(|group| {
+                let approver_ids = Self::approver_ids(group, vars);
+                let decided_ids = |decision: FlowInstApprovalDecision| {
+                    records
+                        .iter()
+                        .filter(|record| record.group_code == group.code && record.decision == decision && approver_ids.contains(&record.approver_id))
+                        .map(|record| record.approver_id.clone())
+                        .unique()
+                        .collect_vec()
+                };
+                let approved_ids = decided_ids(FlowInstApprovalDecision::Approve);
+                let rejected_ids = decided_ids(FlowInstApprovalDecision::Reject);
+                let required_count = match group.quorum {
+                    FlowStateApprovalQuorumKind::All => approver_ids.len(),
+                    FlowStateApprovalQuorumKind::Any => 1,
+                    FlowStateApprovalQuorumKind::AtLeast => group.quorum_count.unwrap_or(1) as usize,
+                };
+                let undecided_count = approver_ids.len() - approved_ids.len() - rejected_ids.len();
+                let status = if approver_ids.is_empty() {
+                    // waiting for the approvers to be assigned
+                    FlowInstApprovalStatus::Pending
+                } else if approved_ids.len() >= required_count {
+                    FlowInstApprovalStatus::Approved
+                } else if approved_ids.len() + undecided_count < required_count {
+                    FlowInstApprovalStatus::Rejected
+                } else {
+                    FlowInstApprovalStatus::Pending
+                };
+                FlowInstApprovalGroupResp {
+                    code: group.code.clone(),
+                    name: group.name.clone(),
+                    status,
+                    required_count,
+                    approver_ids,
+                    approved_ids,
+                    rejected_ids,
+                }
+            })
+            .collect_vec();
+        let status = if groups.iter().any(|group| group.status == FlowInstApprovalStatus::Rejected) {
+            FlowInstApprovalStatus::Rejected
+        } else if groups.iter().all(|group| group.status == FlowInstApprovalStatus::Approved) {
+            FlowInstApprovalStatus::Approved
+        } else {
+            FlowInstApprovalStatus::Pending
+        };
+        FlowInstApprovalResp {
+            flow_state_id: flow_state_id.to_string(),
+            status,
+            groups,
+            records: records
+                .iter()
+                .map(|record| FlowInstApprovalRecordResp {
+                    group_code: record.group_code.clone(),
+                    approver_id: record.approver_id.clone(),
+                    decision: record.decision.clone(),
+                    message: record.message.clone(),
+                    create_time: record.create_time,
+                })
+                .collect(),
+        }
+    }
+
+    fn approver_ids(group: &FlowStateApprovalGroup, vars: &HashMap<String, Value>) -> Vec<String> {
+        let mut approver_ids = group.approver_ids.clone();
+        match group.approver_var.as_ref().and_then(|approver_var| vars.get(approver_var)) {
+            Some(Value::String(ids)) => approver_ids.extend(ids.split(',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty())),
+            Some(Value::Array(ids)) => approver_ids.extend(ids.iter().filter_map(|id| id.as_str()).map(|id| id.to_string())),
+            _ => {}
+        }
+        approver_ids.into_iter().unique().collect()
+    }
+
+    async fn get_state(flow_state_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<FlowStateDetailResp> {
+        FlowStateServ::get_item(
+            flow_state_id,
+            &FlowStateFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            &TardisContext {
+                own_paths: "".to_string(),
+                ..ctx.clone()
+            },
+        )
+        .await
+    }
+}
@@ -23,7 +23,7 @@ use tardis::{
         sea_query::{Alias, Cond, Expr, Query, SelectStatement},
         JoinType, Set,
     },
-    futures_util::future::join_all,
+    futures_util::future::{join_all, try_join_all},
     log::{debug, error},
     serde_json::Value,
     web::web_resp::TardisPage,
@@ -40,7 +40,8 @@ use crate::{
             FlowInstTransferResp, FlowInstTransitionInfo, FlowOperationContext,
         },
         flow_model_dto::{FlowModelDetailResp, FlowModelFilterReq},
-        flow_state_dto::{FlowStateAggResp, FlowStateFilterReq, FlowStateRelModelExt, FlowSysStateKind},
+        flow_model_simulate_dto::{FlowTransitionGuardCheckResp, FlowTransitionGuardKind},
+        flow_state_dto::{FlowStateAggResp, FlowStateApprovalConf, FlowStateFilterReq, FlowStateKind, FlowStateRelModelExt, FlowSysStateKind},
         flow_transition_dto::{FlowTransitionDetailResp, FlowTransitionFrontActionInfo},
         flow_var_dto::FillType,
     },
@@ -52,7 +53,12 @@ use crate::{
 use super::{
     flow_event_serv::FlowEventServ,
     flow_external_serv::FlowExternalServ,
+    flow_inst_approval_serv::FlowInstApprovalServ,
+    flow_inst_sla_serv::FlowInstSlaServ,
+    flow_inst_timer_serv::FlowInstTimerServ,
+    flow_model_version_serv::FlowModelVersionServ,
     flow_rel_serv::{FlowRelKind, FlowRelServ},
+    flow_state_kind_serv::FlowStateKindServ,
 };
 
 pub struct FlowInstServ;
@@ -75,6 +81,8 @@ impl FlowInstServ {
             ctx,
         )
         .await?;
+        let flow_model_version_id = FlowModelVersionServ::find_latest_version_id(&flow_model_id, funs).await?;
+        let flow_model = FlowModelVersionServ::pin_model(flow_model, flow_model_version_id.as_deref(), funs).await?;
         let inst_id = TardisFuns::field.nanoid();
         let current_state_id = if let Some(current_state_name) = &current_state_name {
             if current_state_name.is_empty() {
@@ -89,9 +97,10 @@ impl FlowInstServ {
             id: Set(inst_id.clone()),
             tag: Set(Some(flow_model.tag.clone())),
             rel_flow_model_id: Set(flow_model_id.to_string()),
+            rel_flow_model_version_id: Set(flow_model_version_id),
             rel_business_obj_id: Set(start_req.rel_business_obj_id.to_string()),
 
-            current_state_id: Set(current_state_id),
+            current_state_id: Set(current_state_id.clone()),
 
             create_vars: Set(start_req.create_vars.as_ref().map(|vars| TardisFuns::json.obj_to_json(vars).unwrap())),
             create_ctx: Set(FlowOperationContext::from_ctx(ctx)),
@@ -100,6 +109,25 @@ impl FlowInstServ {
             ..Default::default()
         };
         funs.db().insert_one(flow_inst, ctx).await?;
+        let current_state = FlowStateServ::get_item(
+            &current_state_id,
+            &FlowStateFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            &TardisContext {
+                own_paths: "".to_string(),
+                ..ctx.clone()
+            },
+        )
+        .await?;
+        FlowInstTimerServ::reset(&inst_id, &flow_model.transitions(), &current_state, funs, ctx).await?;
+        FlowInstSlaServ::enter(&inst_id, &flow_model_id, &flow_model.states(), &current_state_id, start_req.create_vars.as_ref(), funs, ctx).await?;
+        FlowStateKindServ::async_on_enter(&inst_id, ctx).await?;
 
         Self::do_request_webhook(
             None,
@@ -130,12 +158,14 @@ impl FlowInstServ {
             let mut inst_id = Self::get_inst_ids_by_rel_business_obj_id(vec![rel_business_obj.rel_business_obj_id.clone().unwrap_or_default()], funs, ctx).await?.pop();
             if inst_id.is_none() {
                 let id = TardisFuns::field.nanoid();
+                let flow_model_version_id = FlowModelVersionServ::find_latest_version_id(&flow_model_id, funs).await?;
                 let flow_inst: flow_inst::ActiveModel = flow_inst::ActiveModel {
                     id: Set(id.clone()),
                     rel_flow_model_id: Set(flow_model_id.to_string()),
+                    rel_flow_model_version_id: Set(flow_model_version_id.clone()),
                     rel_business_obj_id: Set(rel_business_obj.rel_business_obj_id.clone().unwrap_or_default()),
 
-                    current_state_id: Set(current_state_id),
+                    current_state_id: Set(current_state_id.clone()),
 
                     create_ctx: Set(FlowOperationContext::from_ctx(&current_ctx)),
 
@@ -143,6 +173,39 @@ impl FlowInstServ {
                     ..Default::default()
                 };
                 funs.db().insert_one(flow_inst, &current_ctx).await?;
+                let flow_model = FlowModelServ::get_item(
+                    &flow_model_id,
+                    &FlowModelFilterReq {
+                        basic: RbumBasicFilterReq {
+                            with_sub_own_paths: true,
+                            own_paths: Some("".to_string()),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    funs,
+                    ctx,
+                )
+                .await?;
+                let flow_model = FlowModelVersionServ::pin_model(flow_model, flow_model_version_id.as_deref(), funs).await?;
+                let current_state = FlowStateServ::get_item(
+                    &current_state_id,
+                    &FlowStateFilterReq {
+                        basic: RbumBasicFilterReq {
+                            with_sub_own_paths: true,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    funs,
+                    &TardisContext {
+                        own_paths: "".to_string(),
+                        ..ctx.clone()
+                    },
+                )
+                .await?;
+                FlowInstTimerServ::reset(&id, &flow_model.transitions(), &current_state, funs, &current_ctx).await?;
+                FlowInstSlaServ::enter(&id, &flow_model_id, &flow_model.states(), &current_state_id, None, funs, &current_ctx).await?;
                 inst_id = Some(id);
             }
             let current_state_name = Self::get(inst_id.as_ref().unwrap(), funs, &current_ctx).await?.current_state_name.unwrap_or_default();
@@ -256,6 +319,8 @@ impl FlowInstServ {
             ..Default::default()
         };
         funs.db().update_one(flow_inst, ctx).await?;
+        FlowInstTimerServ::cancel(flow_inst_id, funs).await?;
+        FlowInstSlaServ::leave(flow_inst_id, None, funs).await?;
         Ok(())
     }
 
@@ -273,6 +338,7 @@ impl FlowInstServ {
         pub struct FlowInstDetailResult {
             pub id: String,
             pub rel_flow_model_id: String,
+            pub rel_flow_model_version_id: Option<String>,
             pub rel_flow_model_name: String,
 
             pub current_state_id: String,
@@ -280,6 +346,8 @@ impl FlowInstServ {
             pub current_state_color: Option<String>,
             pub current_state_kind: Option<FlowSysStateKind>,
             pub current_state_ext: Option<String>,
+            pub current_state_state_kind: Option<FlowStateKind>,
+            pub current_state_kind_conf: Option<Value>,
 
             pub current_vars: Option<Value>,
 
@@ -307,6 +375,7 @@ impl FlowInstServ {
             .columns([
                 (flow_inst::Entity, flow_inst::Column::Id),
                 (flow_inst::Entity, flow_inst::Column::RelFlowModelId),
+                (flow_inst::Entity, flow_inst::Column::RelFlowModelVersionId),
                 (flow_inst::Entity, flow_inst::Column::RelBusinessObjId),
                 (flow_inst::Entity, flow_inst::Column::CurrentStateId),
                 (flow_inst::Entity, flow_inst::Column::CurrentVars),
@@ -324,6 +393,8 @@ impl FlowInstServ {
             .expr_as(Expr::col((flow_state_table.clone(), Alias::new("color"))).if_null(""), Alias::new("current_state_color"))
             .expr_as(Expr::col((flow_state_table.clone(), Alias::new("sys_state"))).if_null(""), Alias::new("current_state_kind"))
             .expr_as(Expr::col((rbum_rel_table.clone(), Alias::new("ext"))).if_null(""), Alias::new("current_state_ext"))
+            .expr_as(Expr::col((flow_state_table.clone(), Alias::new("state_kind"))), Alias::new("current_state_state_kind"))
+            .expr_as(Expr::col((flow_state_table.clone(), Alias::new("kind_conf"))), Alias::new("current_state_kind_conf"))
             .expr_as(Expr::col((rel_model_table.clone(), NAME_FIELD.clone())).if_null(""), Alias::new("rel_flow_model_name"))
             .from(flow_inst::Entity)
             .join_as(
@@ -363,28 +434,56 @@ impl FlowInstServ {
             .and_where(Expr::col((flow_inst::Entity, flow_inst::Column::OwnPaths)).like(format!("{}%", ctx.own_paths)));
 
         let flow_insts = funs.db().find_dtos::<FlowInstDetailResult>(&query).await?;
+        let mut current_state_logs = FlowInstSlaServ::find_current(&flow_insts.iter().map(|inst| inst.id.clone()).collect_vec(), funs).await?;
+        let mut approval_records = FlowInstApprovalServ::find_records(
+            &flow_insts.iter().filter(|inst| inst.current_state_state_kind == Some(FlowStateKind::Approval)).map(|inst| inst.id.clone()).collect_vec(),
+            funs,
+        )
+        .await?;
         Ok(flow_insts
             .into_iter()
-            .map(|inst| FlowInstDetailResp {
-                id: inst.id,
-                rel_flow_model_id: inst.rel_flow_model_id,
-                rel_flow_model_name: inst.rel_flow_model_name,
-                create_vars: inst.create_vars.map(|create_vars| TardisFuns::json.json_to_obj(create_vars).unwrap()),
-                create_ctx: inst.create_ctx,
-                create_time: inst.create_time,
-                finish_ctx: inst.finish_ctx,
-                finish_time: inst.finish_time,
-                finish_abort: inst.finish_abort,
-                output_message: inst.output_message,
-                own_paths: inst.own_paths,
-                transitions: inst.transitions.map(|transitions| TardisFuns::json.json_to_obj(transitions).unwrap()),
-                current_state_id: inst.current_state_id,
-                current_state_name: inst.current_state_name,
-                current_state_color: inst.current_state_color,
-                current_state_kind: inst.current_state_kind,
-                current_state_ext: inst.current_state_ext.map(|ext| TardisFuns::json.str_to_obj::<FlowStateRelModelExt>(&ext).unwrap_or_default()),
-                current_vars: inst.current_vars.map(|current_vars| TardisFuns::json.json_to_obj(current_vars).unwrap()),
-                rel_business_obj_id: inst.rel_business_obj_id,
+            .map(|inst| {
+                let current_vars: Option<HashMap<String, Value>> = inst.current_vars.map(|current_vars| TardisFuns::json.json_to_obj(current_vars).unwrap());
+                let approval = if inst.current_state_state_kind == Some(FlowStateKind::Approval) {
+                    inst.current_state_kind_conf.and_then(|conf| TardisFuns::json.json_to_obj::<FlowStateApprovalConf>(conf).ok()).map(|conf| {
+                        FlowInstApprovalServ::progress(
+                            &inst.current_state_id,
+                            &conf,
+                            &current_vars.clone().unwrap_or_default(),
+                            &approval_records.remove(&inst.id).unwrap_or_default(),
+                        )
+                    })
+                } else {
+                    None
+                };
+                let sla = current_state_logs
+                    .remove(&inst.id)
+                    .filter(|state_log| state_log.rel_flow_state_id == inst.current_state_id)
+                    .and_then(|state_log| FlowInstSlaServ::progress(&state_log));
+                FlowInstDetailResp {
+                    id: inst.id,
+                    rel_flow_model_id: inst.rel_flow_model_id,
+                    rel_flow_model_version_id: inst.rel_flow_model_version_id,
+                    rel_flow_model_name: inst.rel_flow_model_name,
+                    create_vars: inst.create_vars.map(|create_vars| TardisFuns::json.json_to_obj(create_vars).unwrap()),
+                    create_ctx: inst.create_ctx,
+                    create_time: inst.create_time,
+                    finish_ctx: inst.finish_ctx,
+                    finish_time: inst.finish_time,
+                    finish_abort: inst.finish_abort,
+                    output_message: inst.output_message,
+                    own_paths: inst.own_paths,
+                    transitions: inst.transitions.map(|transitions| TardisFuns::json.json_to_obj(transitions).unwrap()),
+                    current_state_id: inst.current_state_id,
+                    current_state_name: inst.current_state_name,
+                    current_state_color: inst.current_state_color,
+                    current_state_kind: inst.current_state_kind,
+                    current_state_ext: inst.current_state_ext.map(|ext| TardisFuns::json.str_to_obj::<FlowStateRelModelExt>(&ext).unwrap_or_default()),
+                    current_vars,
+                    approval,
+                    sla,
+                    rel_business_obj_id: inst.rel_business_obj_id,
+                }
             })
             .collect_vec())
     }
@@ -466,18 +565,23 @@ impl FlowInstServ {
             ctx,
         )
         .await?;
-        let state_and_next_transitions = join_all(
+        // The pinned version may have been deleted, so the lookup errors are returned instead of panicking
+        try_join_all(
             flow_insts
                 .iter()
                 .map(|flow_inst| async {
                     let req = find_req.iter().find(|req| req.flow_inst_id == flow_inst.id).unwrap();
-                    let flow_model = flow_models.iter().find(|model| model.id == flow_inst.rel_flow_model_id).unwrap();
-                    Self::do_find_next_transitions(flow_inst, flow_model, None, &req.vars, false, funs, ctx).await.unwrap()
+                    let flow_model = flow_models
+                        .iter()
+                        .find(|model| model.id == flow_inst.rel_flow_model_id)
+                        .ok_or_else(|| funs.err().not_found("flow_inst", "find_state_and_next_transitions", "flow model not found", "404-flow-model-not-found"))?
+                        .clone();
+                    let flow_model = FlowModelVersionServ::pin_model(flow_model, flow_inst.rel_flow_model_version_id.as_deref(), funs).await?;
+                    Self::do_find_next_transitions(flow_inst, &flow_model, None, &req.vars, false, funs, ctx).await
                 })
                 .collect_vec(),
         )
-        .await;
-        Ok(state_and_next_transitions)
+        .await
     }
 
     pub async fn find_next_transitions(
@@ -487,40 +591,14 @@ impl FlowInstServ {
         ctx: &TardisContext,
     ) -> TardisResult<Vec<FlowInstFindNextTransitionResp>> {
         let flow_inst = Self::get(flow_inst_id, funs, ctx).await?;
-        let flow_model = FlowModelServ::get_item(
-            &flow_inst.rel_flow_model_id,
-            &FlowModelFilterReq {
-                basic: RbumBasicFilterReq {
-                    with_sub_own_paths: true,
-                    own_paths: Some("".to_string()),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            funs,
-            ctx,
-        )
-        .await?;
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst, funs, ctx).await?;
         let state_and_next_transitions = Self::do_find_next_transitions(&flow_inst, &flow_model, None, &next_req.vars, false, funs, ctx).await?;
         Ok(state_and_next_transitions.next_flow_transitions)
     }
 
     pub async fn check_transfer_vars(flow_inst_id: &str, transfer_req: &mut FlowInstTransferReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
         let flow_inst_detail: FlowInstDetailResp = Self::get(flow_inst_id, funs, ctx).await?;
-        let flow_model = FlowModelServ::get_item(
-            &flow_inst_detail.rel_flow_model_id,
-            &FlowModelFilterReq {
-                basic: RbumBasicFilterReq {
-                    with_sub_own_paths: true,
-                    own_paths: Some("".to_string()),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            funs,
-            ctx,
-        )
-        .await?;
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst_detail, funs, ctx).await?;
         let vars_collect = flow_model
             .transitions()
             .into_iter()
@@ -556,6 +634,15 @@ impl FlowInstServ {
         funs.begin().await?;
         let result = Self::do_transfer(flow_inst_id, transfer_req, skip_filter, callback_kind, &funs, ctx).await;
         funs.commit().await?;
+        if matches!(result, Ok((_, true))) {
+            let flow_inst_id_cp = flow_inst_id.to_string();
+            let ctx_cp = ctx.clone();
+            tardis::tokio::spawn(async move {
+                if let Err(e) = FlowStateKindServ::on_enter(&flow_inst_id_cp, &ctx_cp).await {
+                    error!("Flow Instance {} on_enter error:{:?}", flow_inst_id_cp, e);
+                }
+            });
+        }
         let flow_inst_id_cp = flow_inst_id.to_string();
         let flow_transition_id = transfer_req.flow_transition_id.clone();
         let ctx_cp = ctx.clone();
@@ -571,9 +658,10 @@ impl FlowInstServ {
             }
         });
 
-        result
+        result.map(|(resp, _)| resp)
     }
 
+    /// The second element of the result is whether the instance has entered the next state
     async fn do_transfer(
         flow_inst_id: &str,
         transfer_req: &FlowInstTransferReq,
@@ -581,26 +669,13 @@ impl FlowInstServ {
         callback_kind: FlowExternalCallbackOp,
         funs: &TardisFunsInst,
         ctx: &TardisContext,
-    ) -> TardisResult<FlowInstTransferResp> {
+    ) -> TardisResult<(FlowInstTransferResp, bool)> {
         let global_ctx = TardisContext {
             own_paths: "".to_string(),
             ..ctx.clone()
         };
         let flow_inst_detail = Self::get(flow_inst_id, funs, ctx).await?;
-        let flow_model = FlowModelServ::get_item(
-            &flow_inst_detail.rel_flow_model_id,
-            &FlowModelFilterReq {
-                basic: RbumBasicFilterReq {
-                    with_sub_own_paths: true,
-                    own_paths: Some("".to_string()),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            funs,
-            ctx,
-        )
-        .await?;
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst_detail, funs, ctx).await?;
         let next_flow_transition = Self::do_find_next_transitions(
             &flow_inst_detail,
             &flow_model,
@@ -614,13 +689,16 @@ impl FlowInstServ {
         .next_flow_transitions
         .pop();
         if next_flow_transition.is_none() {
-            return Self::gen_transfer_resp(
-                flow_inst_id,
-                &flow_model.transitions().into_iter().find(|trans| trans.id == transfer_req.flow_transition_id).unwrap().from_flow_state_id,
-                ctx,
-                funs,
-            )
-            .await;
+            return Ok((
+                Self::gen_transfer_resp(
+                    flow_inst_id,
+                    &flow_model.transitions().into_iter().find(|trans| trans.id == transfer_req.flow_transition_id).unwrap().from_flow_state_id,
+                    ctx,
+                    funs,
+                )
+                .await?,
+                false,
+            ));
         }
         let model_transition = flow_model.transitions();
         let next_transition_detail = model_transition.iter().find(|trans| trans.id == transfer_req.flow_transition_id).unwrap().to_owned();
@@ -655,6 +733,11 @@ impl FlowInstServ {
             &global_ctx,
         )
         .await?;
+        let mut collected_vars = flow_inst_detail.current_vars.clone().unwrap_or_default();
+        if let Some(req_vars) = &transfer_req.vars {
+            collected_vars.extend(req_vars.clone());
+        }
+        FlowStateKindServ::check_form_collected(&prev_flow_state, &collected_vars, funs)?;
 
         // notify modify vars
         if let Some(vars) = &transfer_req.vars {
@@ -725,6 +808,14 @@ impl FlowInstServ {
         }
 
         funs.db().update_one(flow_inst, ctx).await?;
+        FlowInstApprovalServ::archive(flow_inst_id, funs).await?;
+        FlowInstSlaServ::leave(flow_inst_id, Some(&new_vars), funs).await?;
+        if next_flow_state.sys_state == FlowSysStateKind::Finish {
+            FlowInstTimerServ::cancel(flow_inst_id, funs).await?;
+        } else {
+            FlowInstTimerServ::reset(flow_inst_id, &model_transition, &next_flow_state, funs, ctx).await?;
+            FlowInstSlaServ::enter(flow_inst_id, &flow_model.id, &flow_model.states(), &next_flow_state.id, Some(&new_vars), funs, ctx).await?;
+        }
 
         // get updated instance detail
         let flow_inst_detail = Self::get(flow_inst_id, funs, ctx).await?;
@@ -752,7 +843,7 @@ impl FlowInstServ {
         )
         .await?;
 
-        Self::gen_transfer_resp(flow_inst_id, &prev_flow_state.id, ctx, funs).await
+        Ok((Self::gen_transfer_resp(flow_inst_id, &prev_flow_state.id, ctx, funs).await?, true))
     }
 
     async fn gen_transfer_resp(flow_inst_id: &str, prev_flow_state_id: &str, ctx: &TardisContext, funs: &TardisFunsInst) -> TardisResult<FlowInstTransferResp> {
@@ -762,20 +853,7 @@ impl FlowInstServ {
         };
 
         let flow_inst_detail = Self::get(flow_inst_id, funs, ctx).await?;
-        let flow_model = FlowModelServ::get_item(
-            &flow_inst_detail.rel_flow_model_id,
-            &FlowModelFilterReq {
-                basic: RbumBasicFilterReq {
-                    with_sub_own_paths: true,
-                    own_paths: Some("".to_string()),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            funs,
-            ctx,
-        )
-        .await?;
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst_detail, funs, ctx).await?;
 
         let prev_flow_state = FlowStateServ::get_item(
             prev_flow_state_id,
@@ -870,85 +948,7 @@ impl FlowInstServ {
                 model_transition.from_flow_state_id == flow_inst.current_state_id
                     && (spec_flow_transition_id.is_none() || &model_transition.id == spec_flow_transition_id.as_ref().unwrap())
             })
-            .filter(|model_transition| {
-                if skip_filter {
-                    return true;
-                }
-                if !model_transition.guard_by_creator
-                    && model_transition.guard_by_spec_account_ids.is_empty()
-                    && model_transition.guard_by_spec_role_ids.is_empty()
-                    && model_transition.guard_by_spec_org_ids.is_empty()
-                    && !model_transition.guard_by_his_operators
-                    && !model_transition.guard_by_assigned
-                {
-                    return true;
-                }
-                if model_transition.guard_by_creator && !(flow_inst.create_ctx.own_paths != ctx.own_paths || flow_inst.create_ctx.owner != ctx.owner) {
-                    return true;
-                }
-                if !model_transition.guard_by_spec_account_ids.is_empty() && model_transition.guard_by_spec_account_ids.contains(&ctx.owner) {
-                    return true;
-                }
-                if !model_transition.guard_by_spec_role_ids.is_empty()
-                    && model_transition.guard_by_spec_role_ids.iter().any(|role_id| {
-                        ctx.roles
-                            .clone()
-                            .into_iter()
-                            .map(|ctx_role_id| ctx_role_id.split(':').collect_vec().first().unwrap_or(&"").to_string())
-                            .collect_vec()
-                            .contains(&role_id.split(':').collect_vec().first().unwrap_or(&"").to_string())
-                    })
-                {
-                    return true;
-                }
-                if !model_transition.guard_by_spec_org_ids.is_empty() && model_transition.guard_by_spec_org_ids.iter().any(|org_id| ctx.groups.contains(org_id)) {
-                    return true;
-                }
-                if model_transition.guard_by_assigned
-                    && flow_inst.current_vars.clone().unwrap_or_default().contains_key("assigned_to")
-                    && flow_inst
-                        .current_vars
-                        .clone()
-                        .unwrap_or_default()
-                        .get("assigned_to")
-                        .unwrap()
-                        .as_str()
-                        .unwrap_or_default()
-                        .split(',')
-                        .collect_vec()
-                        .contains(&ctx.owner.as_str())
-                {
-                    return true;
-                }
-                if model_transition.guard_by_his_operators
-                    && flow_inst
-                        .transitions
-                        .as_ref()
-                        .map(|inst_transitions| {
-                            // except creator
-                            inst_transitions
-                                .iter()
-                                .filter(|inst_transition| inst_transition.op_ctx.owner != flow_inst.create_ctx.owner)
-                                .any(|inst_transition| inst_transition.op_ctx.own_paths == ctx.own_paths && inst_transition.op_ctx.owner == ctx.owner)
-                        })
-                        .unwrap_or(false)
-                {
-                    return true;
-                }
-                if let Some(guard_by_other_conds) = model_transition.guard_by_other_conds() {
-                    let mut check_vars: HashMap<String, Value> = HashMap::new();
-                    if let Some(current_vars) = &flow_inst.current_vars {
-                        check_vars.extend(current_vars.clone());
-                    }
-                    if let Some(req_vars) = &req_vars {
-                        check_vars.extend(req_vars.clone());
-                    }
-                    if !BasicQueryCondInfo::check_or_and_conds(&guard_by_other_conds, &check_vars).unwrap() {
-                        return false;
-                    }
-                }
-                false
-            })
+            .filter(|model_transition| skip_filter || Self::check_transition_guards(flow_inst, model_transition, req_vars, ctx).0)
             .map(|model_transition| {
                 Ok(FlowInstFindNextTransitionResp {
                     next_flow_transition_id: model_transition.id.to_string(),
@@ -1012,6 +1012,92 @@ impl FlowInstServ {
         Ok(state_and_next_transitions)
     }
 
+    /// Check the guards of the transition for the operator of the context, returning whether it passes and the result of each configured guard
+    ///
+    /// 校验操作人是否满足动作的权限配置，返回是否通过及每项已配置校验的结果
+    ///
+    /// ``guard_by_other_conds`` restricts the transition in addition to the other guards (AND), which pass if any of them passes.
+    /// Note that the conditions were not enforced before, the transitions configured with them are now rejected when they are not met.
+    /// ``guard_by_other_conds`` 与其他校验同时生效（且关系），其他校验满足任意一项即可。
+    /// 注意旧版本中该条件未生效，现在条件不满足时将拒绝流转。
+    pub(crate) fn check_transition_guards(
+        flow_inst: &FlowInstDetailResp,
+        model_transition: &FlowTransitionDetailResp,
+        req_vars: &Option<HashMap<String, Value>>,
+        ctx: &TardisContext,
+    ) -> (bool, Vec<FlowTransitionGuardCheckResp>) {
+        let mut checks = vec![];
+        let mut conds_passed = true;
+        // the conditions restrict the transition in addition to the operator guards
+        if let Some(guard_by_other_conds) = model_transition.guard_by_other_conds() {
+            conds_passed = BasicQueryCondInfo::check_or_and_conds(&guard_by_other_conds, &flow_inst.guard_vars(req_vars)).unwrap_or(false);
+            checks.push(FlowTransitionGuardCheckResp {
+                kind: FlowTransitionGuardKind::OtherConds,
+                passed: conds_passed,
+                detail: TardisFuns::json.obj_to_string(&guard_by_other_conds).unwrap_or_default(),
+            });
+        }
+        if model_transition.guard_by_creator {
+            checks.push(FlowTransitionGuardCheckResp {
+                kind: FlowTransitionGuardKind::Creator,
+                passed: flow_inst.create_ctx.own_paths == ctx.own_paths && flow_inst.create_ctx.owner == ctx.owner,
+                detail: flow_inst.create_ctx.owner.clone(),
+            });
+        }
+        if !model_transition.guard_by_spec_account_ids.is_empty() {
+            checks.push(FlowTransitionGuardCheckResp {
+                kind: FlowTransitionGuardKind::SpecAccount,
+                passed: model_transition.guard_by_spec_account_ids.contains(&ctx.owner),
+                detail: model_transition.guard_by_spec_account_ids.join(","),
+            });
+        }
+        if !model_transition.guard_by_spec_role_ids.is_empty() {
+            let ctx_role_ids = ctx.roles.iter().map(|ctx_role_id| ctx_role_id.split(':').next().unwrap_or_default().to_string()).collect_vec();
+            checks.push(FlowTransitionGuardCheckResp {
+                kind: FlowTransitionGuardKind::SpecRole,
+                passed: model_transition.guard_by_spec_role_ids.iter().any(|role_id| ctx_role_ids.contains(&role_id.split(':').next().unwrap_or_default().to_string())),
+                detail: model_transition.guard_by_spec_role_ids.join(","),
+            });
+        }
+        if !model_transition.guard_by_spec_org_ids.is_empty() {
+            checks.push(FlowTransitionGuardCheckResp {
+                kind: FlowTransitionGuardKind::SpecOrg,
+                passed: model_transition.guard_by_spec_org_ids.iter().any(|org_id| ctx.groups.contains(org_id)),
+                detail: model_transition.guard_by_spec_org_ids.join(","),
+            });
+        }
+        if model_transition.guard_by_assigned {
+            let assigned_to = flow_inst.current_vars.as_ref().and_then(|current_vars| current_vars.get("assigned_to")).map(|assigned_to| assigned_to.as_str().unwrap_or_default());
+            checks.push(FlowTransitionGuardCheckResp {
+                kind: FlowTransitionGuardKind::Assigned,
+                passed: assigned_to.map(|assigned_to| assigned_to.split(',').any(|assigned| assigned == ctx.owner)).unwrap_or(false),
+                detail: assigned_to.unwrap_or_default().to_string(),
+            });
+        }
+        if model_transition.guard_by_his_operators {
+            // except creator
+            let his_operators = flow_inst
+                .transitions
+                .as_ref()
+                .map(|inst_transitions| {
+                    inst_transitions
+                        .iter()
+                        .filter(|inst_transition| inst_transition.op_ctx.owner != flow_inst.create_ctx.owner)
+                        .map(|inst_transition| &inst_transition.op_ctx)
+                        .collect_vec()
+                })
+                .unwrap_or_default();
+            checks.push(FlowTransitionGuardCheckResp {
+                kind: FlowTransitionGuardKind::HisOperators,
+                passed: his_operators.iter().any(|op_ctx| op_ctx.own_paths == ctx.own_paths && op_ctx.owner == ctx.owner),
+                detail: his_operators.iter().map(|op_ctx| op_ctx.owner.as_str()).unique().join(","),
+            });
+        }
+        let operator_checks = checks.iter().filter(|check| check.kind != FlowTransitionGuardKind::OtherConds).collect_vec();
+        let passed = conds_passed && (operator_checks.is_empty() || operator_checks.iter().any(|check| check.passed));
+        (passed, checks)
+    }
+
     pub async fn state_is_used(flow_model_id: &str, flow_state_id: &str, funs: &TardisFunsInst, _ctx: &TardisContext) -> TardisResult<bool> {
         if funs
             .db()
@@ -1069,20 +1155,7 @@ impl FlowInstServ {
             .into_iter()
             .next()
             .ok_or_else(|| funs.err().not_found("flow_inst", "get_new_vars", "illegal response", "404-flow-inst-not-found"))?;
-        let flow_model = FlowModelServ::get_item(
-            &flow_inst_detail.rel_flow_model_id,
-            &FlowModelFilterReq {
-                basic: RbumBasicFilterReq {
-                    with_sub_own_paths: true,
-                    own_paths: Some("".to_string()),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            funs,
-            ctx,
-        )
-        .await?;
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst_detail, funs, ctx).await?;
 
         Ok(
             FlowExternalServ::do_query_field(&flow_model.tag, vec![flow_inst_detail.rel_business_obj_id.clone()], &flow_inst_detail.own_paths, ctx, funs)
@@ -1206,6 +1279,11 @@ impl FlowInstServ {
         let mut update_statement = Query::update();
         update_statement.table(flow_inst::Entity);
         update_statement.value(flow_inst::Column::RelFlowModelId, modify_model_id);
+        // the instances follow the latest version of the new model
+        update_statement.value(
+            flow_inst::Column::RelFlowModelVersionId,
+            FlowModelVersionServ::find_latest_version_id(modify_model_id, funs).await?,
+        );
         update_statement.and_where(Expr::col((flow_inst::Entity, flow_inst::Column::Tag)).eq(tag));
         update_statement.and_where(Expr::col((flow_inst::Entity, flow_inst::Column::OwnPaths)).eq(ctx.own_paths.as_str()));
 
@@ -1251,7 +1329,8 @@ impl FlowInstServ {
                         ..Default::default()
                     };
                     funs.db().update_one(flow_inst, &mock_ctx).await.unwrap();
-                    let model_tag = FlowModelServ::get_item(
+                    FlowInstApprovalServ::archive(&inst.id, funs).await?;
+                    let flow_model = FlowModelServ::get_item(
                         modify_model_id,
                         &FlowModelFilterReq {
                             basic: RbumBasicFilterReq {
@@ -1264,8 +1343,7 @@ impl FlowInstServ {
                         funs,
                         ctx,
                     )
-                    .await
-                    .map(|detail| detail.tag);
+                    .await;
                     let next_flow_state = FlowStateServ::get_item(
                         state_id,
                         &FlowStateFilterReq {
@@ -1280,6 +1358,12 @@ impl FlowInstServ {
                     )
                     .await
                     .unwrap();
+                    if let Ok(flow_model) = &flow_model {
+                        FlowInstTimerServ::reset(&inst.id, &flow_model.transitions(), &next_flow_state, funs, &mock_ctx).await?;
+                        FlowInstSlaServ::leave(&inst.id, None, funs).await?;
+                        FlowInstSlaServ::enter(&inst.id, modify_model_id, &flow_model.states(), state_id, None, funs, &mock_ctx).await?;
+                    }
+                    let model_tag = flow_model.map(|detail| detail.tag);
 
                     FlowExternalServ::do_notify_changes(
                         model_tag.unwrap_or_default().as_str(),
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use bios_sdk_invoke::clients::{
+    event_client::{get_topic, EventAttributeExt, SPI_RPC_TOPIC},
+    flow_client::FlowSlaBreachEvent,
+};
+use itertools::Itertools;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{self, DateTime, Utc},
+    db::sea_orm::{
+        self,
+        sea_query::{Alias, Cond, Expr, Order, Query},
+        Iterable, Set,
+    },
+    log::{trace, warn},
+    serde_json::{json, Value},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    domain::flow_inst_state_log,
+    dto::{
+        flow_inst_dto::{FlowInstSlaResp, FlowInstSlaStatGroupKind, FlowInstSlaStatResp, FlowOperationContext},
+        flow_state_dto::{FlowStateAggResp, FlowStateSlaConf},
+    },
+};
+
+use super::{flow_inst_serv::FlowInstServ, flow_model_version_serv::FlowModelVersionServ, flow_state_kind_serv::FlowStateKindServ};
+
+/// Number of the breached state logs loaded at a time when triggering
+const TRIGGER_BATCH_SIZE: u64 = 200;
+
+/// Time spent in each state and the SLA of the states
+///
+/// 实例在各状态的停留时间及状态的SLA
+pub struct FlowInstSlaServ;
+
+impl FlowInstSlaServ {
+    /// Record that the instance has entered the state
+    ///
+    /// 记录实例进入状态
+    ///
+    /// The log of the previous state is closed. If the instance is already in the state (e.g. when migrated to another version of the model),
+    /// the stay continues and only the SLA of the state is refreshed.
+    /// 关闭上一状态的记录。若实例已处于该状态（例如迁移至模型的其他版本时），则继续计时，仅更新该状态的SLA。
+    pub async fn enter(
+        flow_inst_id: &str,
+        flow_model_id: &str,
+        model_states: &[FlowStateAggResp],
+        flow_state_id: &str,
+        vars: Option<&HashMap<String, Value>>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<()> {
+        let sla_secs = Self::sla_conf(model_states, flow_state_id).map(|sla| sla.target_secs);
+        if let Some(current_log) = Self::find_current(&[flow_inst_id.to_string()], funs).await?.remove(flow_inst_id) {
+            if current_log.rel_flow_state_id == flow_state_id {
+                funs.db()
+                    .execute(
+                        Query::update()
+                            .table(flow_inst_state_log::Entity)
+                            .value(flow_inst_state_log::Column::RelFlowModelId, flow_model_id)
+                            .value(flow_inst_state_log::Column::SlaSecs, sla_secs)
+                            .value(
+                                flow_inst_state_log::Column::DueTime,
+                                sla_secs.map(|sla_secs| current_log.enter_time + chrono::Duration::seconds(sla_secs)),
+                            )
+                            .and_where(Expr::col(flow_inst_state_log::Column::Id).eq(current_log.id.as_str())),
+                    )
+                    .await?;
+                return Ok(());
+            }
+            Self::close(&current_log, vars, funs).await?;
+        }
+        let now = Utc::now();
+        let state_log = flow_inst_state_log::ActiveModel {
+            id: Set(TardisFuns::field.nanoid()),
+            rel_flow_inst_id: Set(flow_inst_id.to_string()),
+            rel_flow_model_id: Set(flow_model_id.to_string()),
+            rel_flow_state_id: Set(flow_state_id.to_string()),
+            assigned_to: Set(Self::assigned_to(vars).unwrap_or_default()),
+            enter_time: Set(now),
+            leave_time: Set(None),
+            elapsed_secs: Set(None),
+            sla_secs: Set(sla_secs),
+            due_time: Set(sla_secs.map(|sla_secs| now + chrono::Duration::seconds(sla_secs))),
+            breached: Set(false),
+            op_ctx: Set(FlowOperationContext::from_ctx(ctx)),
+            own_paths: Set(ctx.own_paths.to_string()),
+            ..Default::default()
+        };
+        funs.db().insert_one(state_log, ctx).await?;
+        Ok(())
+    }
+
+    /// Record that the instance has left its current state without entering another one (i.e. finished or aborted)
+    ///
+    /// 记录实例离开当前状态且未进入其他状态（即结束或终止）
+    pub async fn leave(flow_inst_id: &str, vars: Option<&HashMap<String, Value>>, funs: &TardisFunsInst) -> TardisResult<()> {
+        if let Some(current_log) = Self::find_current(&[flow_inst_id.to_string()], funs).await?.remove(flow_inst_id) {
+            Self::close(&current_log, vars, funs).await?;
+        }
+        Ok(())
+    }
+
+    async fn close(state_log: &flow_inst_state_log::Model, vars: Option<&HashMap<String, Value>>, funs: &TardisFunsInst) -> TardisResult<()> {
+        let now = Utc::now();
+        let breached = state_log.breached || state_log.due_time.map(|due_time| due_time < now).unwrap_or(false);
+        funs.db()
+            .execute(
+                Query::update()
+                    .table(flow_inst_state_log::Entity)
+                    .value(flow_inst_state_log::Column::LeaveTime, now)
+                    .value(flow_inst_state_log::Column::ElapsedSecs, (now - state_log.enter_time).num_seconds())
+                    .value(flow_inst_state_log::Column::Breached, breached)
+                    .value(
+                        flow_inst_state_log::Column::AssignedTo,
+                        Self::assigned_to(vars).unwrap_or_else(|| state_log.assigned_to.clone()),
+                    )
+                    .and_where(Expr::col(flow_inst_state_log::Column::Id).eq(state_log.id.as_str())),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Find the logs of the states the instances are currently in
+    ///
+    /// 获取实例当前所处状态的记录
+    pub async fn find_current(flow_inst_ids: &[String], funs: &TardisFunsInst) -> TardisResult<HashMap<String, flow_inst_state_log::Model>> {
+        if flow_inst_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let state_logs = funs
+            .db()
+            .find_dtos::<flow_inst_state_log::Model>(
+                Query::select()
+                    .columns(flow_inst_state_log::Column::iter())
+                    .from(flow_inst_state_log::Entity)
+                    .and_where(Expr::col(flow_inst_state_log::Column::RelFlowInstId).is_in(flow_inst_ids))
+                    .and_where(Expr::col(flow_inst_state_log::Column::LeaveTime).is_null())
+                    .order_by(flow_inst_state_log::Column::EnterTime, Order::Asc),
+            )
+            .await?;
+        Ok(state_logs.into_iter().map(|state_log| (state_log.rel_flow_inst_id.clone(), state_log)).collect())
+    }
+
+    /// Calculate the SLA progress of the current state, empty if no SLA is configured for the state
+    ///
+    /// 计算当前状态的SLA进度，该状态未配置SLA时为空
+    pub fn progress(state_log: &flow_inst_state_log::Model) -> Option<FlowInstSlaResp> {
+        let (Some(sla_secs), Some(due_time)) = (state_log.sla_secs, state_log.due_time) else {
+            return None;
+        };
+        let now = Utc::now();
+        Some(FlowInstSlaResp {
+            flow_state_id: state_log.rel_flow_state_id.clone(),
+            enter_time: state_log.enter_time,
+            sla_secs,
+            elapsed_secs: (now - state_log.enter_time).num_seconds(),
+            remaining_secs: (due_time - now).num_seconds(),
+            breached: state_log.breached || due_time < now,
+        })
+    }
+
+    /// Escalate the breached SLAs
+    ///
+    /// 升级处理超时的SLA
+    ///
+    /// Each breach is claimed by marking the log as breached, so that it is escalated only once even if multiple nodes are triggering at the same time.
+    /// The breach event is published through the event middleware, and the message is sent through reach if configured.
+    /// 每个超时通过标记记录为已超时认领，多个节点同时触发时也只会升级一次。超时事件通过事件中心发布，若有配置则通过 reach 发送消息。
+    pub async fn trigger(funs: &TardisFunsInst) -> TardisResult<()> {
+        loop {
+            let breached_logs = funs
+                .db()
+                .find_dtos::<flow_inst_state_log::Model>(
+                    Query::select()
+                        .columns(flow_inst_state_log::Column::iter())
+                        .from(flow_inst_state_log::Entity)
+                        .and_where(Expr::col(flow_inst_state_log::Column::LeaveTime).is_null())
+                        .and_where(Expr::col(flow_inst_state_log::Column::Breached).eq(false))
+                        .and_where(Expr::col(flow_inst_state_log::Column::DueTime).lte(Utc::now()))
+                        .order_by(flow_inst_state_log::Column::DueTime, Order::Asc)
+                        .limit(TRIGGER_BATCH_SIZE),
+                )
+                .await?;
+            let fetched_count = breached_logs.len() as u64;
+            for state_log in breached_logs {
+                let claimed = funs
+                    .db()
+                    .execute(
+                        Query::update()
+                            .table(flow_inst_state_log::Entity)
+                            .value(flow_inst_state_log::Column::Breached, true)
+                            .and_where(Expr::col(flow_inst_state_log::Column::Id).eq(state_log.id.as_str()))
+                            .and_where(Expr::col(flow_inst_state_log::Column::Breached).eq(false)),
+                    )
+                    .await?
+                    .rows_affected()
+                    == 1;
+                if !claimed {
+                    continue;
+                }
+                trace!(
+                    "[Flow.Inst] SLA of instance [{}] state [{}] breached",
+                    state_log.rel_flow_inst_id,
+                    state_log.rel_flow_state_id
+                );
+                if let Err(e) = Self::escalate(&state_log, funs).await {
+                    warn!("[Flow.Inst] escalate SLA breach of instance [{}] error: {:?}", state_log.rel_flow_inst_id, e);
+                }
+            }
+            if fetched_count < TRIGGER_BATCH_SIZE {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn escalate(state_log: &flow_inst_state_log::Model, funs: &TardisFunsInst) -> TardisResult<()> {
+        let ctx = TardisContext {
+            own_paths: state_log.op_ctx.own_paths.clone(),
+            ak: state_log.op_ctx.ak.clone(),
+            owner: state_log.op_ctx.owner.clone(),
+            roles: state_log.op_ctx.roles.clone(),
+            groups: state_log.op_ctx.groups.clone(),
+            ..Default::default()
+        };
+        let flow_inst = FlowInstServ::get(&state_log.rel_flow_inst_id, funs, &ctx).await?;
+        let assigned_to = Self::assigned_to(flow_inst.current_vars.as_ref()).unwrap_or_else(|| state_log.assigned_to.clone());
+        funs.db()
+            .execute(
+                Query::update()
+                    .table(flow_inst_state_log::Entity)
+                    .value(flow_inst_state_log::Column::AssignedTo, assigned_to.as_str())
+                    .and_where(Expr::col(flow_inst_state_log::Column::Id).eq(state_log.id.as_str())),
+            )
+            .await?;
+        let sla_secs = state_log.sla_secs.unwrap_or_default();
+        let elapsed_secs = (Utc::now() - state_log.enter_time).num_seconds();
+        if let Some(topic) = get_topic(&SPI_RPC_TOPIC) {
+            let event = FlowSlaBreachEvent {
+                inst_id: flow_inst.id.clone(),
+                rel_business_obj_id: flow_inst.rel_business_obj_id.clone(),
+                flow_model_id: state_log.rel_flow_model_id.clone(),
+                state_id: state_log.rel_flow_state_id.clone(),
+                assigned_to,
+                sla_secs,
+                elapsed_secs,
+            };
+            if let Err(e) = topic.send_event(event.inject_context(funs, &ctx).json()).await {
+                warn!("[Flow.Inst] send SLA breach event of instance [{}] error: {:?}", flow_inst.id, e);
+            }
+        }
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst, funs, &ctx).await?;
+        let model_states = flow_model.states();
+        if let Some(reach) = Self::sla_conf(&model_states, &state_log.rel_flow_state_id).and_then(|sla| sla.reach) {
+            let state_name = model_states.iter().find(|state| state.id == state_log.rel_flow_state_id).map(|state| state.name.clone()).unwrap_or_default();
+            FlowStateKindServ::send_reach_message(
+                &flow_inst,
+                reach,
+                &state_name,
+                HashMap::from([("sla_secs".to_string(), json!(sla_secs)), ("elapsed_secs".to_string(), json!(elapsed_secs))]),
+                funs,
+                &ctx,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Statistics of the SLA compliance by state or assignee
+    ///
+    /// 按状态或处理人统计SLA达成情况
+    ///
+    /// Only the stays in the states with SLA which have been left or breached are counted, the time range applies to the time of entering the state.
+    /// 仅统计配置了SLA且已离开或已超时的状态记录，时间范围以进入状态的时间为准。
+    pub async fn stat(
+        flow_model_id: Option<String>,
+        group_by: FlowInstSlaStatGroupKind,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<Vec<FlowInstSlaStatResp>> {
+        #[derive(sea_orm::FromQueryResult)]
+        struct FlowInstSlaStatResult {
+            key: String,
+            total: i64,
+            breached: i64,
+            avg_elapsed_secs: Option<f64>,
+        }
+        let group_column = match group_by {
+            FlowInstSlaStatGroupKind::State => flow_inst_state_log::Column::RelFlowStateId,
+            FlowInstSlaStatGroupKind::Assignee => flow_inst_state_log::Column::AssignedTo,
+        };
+        let mut query = Query::select();
+        query
+            .expr_as(Expr::col(group_column), Alias::new("key"))
+            .expr_as(Expr::col(flow_inst_state_log::Column::Id).count(), Alias::new("total"))
+            .expr_as(Expr::cust("CAST(SUM(CASE WHEN breached THEN 1 ELSE 0 END) AS BIGINT)"), Alias::new("breached"))
+            .expr_as(Expr::cust("CAST(AVG(elapsed_secs) AS DOUBLE PRECISION)"), Alias::new("avg_elapsed_secs"))
+            .from(flow_inst_state_log::Entity)
+            .and_where(Expr::col(flow_inst_state_log::Column::SlaSecs).is_not_null())
+            .cond_where(Cond::any().add(Expr::col(flow_inst_state_log::Column::LeaveTime).is_not_null()).add(Expr::col(flow_inst_state_log::Column::Breached).eq(true)))
+            .and_where(Expr::col(flow_inst_state_log::Column::OwnPaths).like(format!("{}%", ctx.own_paths)))
+            .group_by_col(group_column)
+            .order_by(group_column, Order::Asc);
+        if let Some(flow_model_id) = &flow_model_id {
+            query.and_where(Expr::col(flow_inst_state_log::Column::RelFlowModelId).eq(flow_model_id));
+        }
+        if let Some(start_time) = start_time {
+            query.and_where(Expr::col(flow_inst_state_log::Column::EnterTime).gte(start_time));
+        }
+        if let Some(end_time) = end_time {
+            query.and_where(Expr::col(flow_inst_state_log::Column::EnterTime).lt(end_time));
+        }
+        let stats = funs.db().find_dtos::<FlowInstSlaStatResult>(&query).await?;
+        Ok(stats
+            .into_iter()
+            .map(|stat| FlowInstSlaStatResp {
+                compliance_rate: if stat.total == 0 {
+                    1.0
+                } else {
+                    (stat.total - stat.breached) as f64 / stat.total as f64
+                },
+                key: stat.key,
+                total: stat.total,
+                breached: stat.breached,
+                avg_elapsed_secs: stat.avg_elapsed_secs,
+            })
+            .collect_vec())
+    }
+
+    fn sla_conf(model_states: &[FlowStateAggResp], flow_state_id: &str) -> Option<FlowStateSlaConf> {
+        model_states.iter().find(|state| state.id == flow_state_id).and_then(|state| state.ext.sla.clone())
+    }
+
+    fn assigned_to(vars: Option<&HashMap<String, Value>>) -> Option<String> {
+        match vars?.get("assigned_to")? {
+            Value::String(assigned_to) => Some(assigned_to.clone()),
+            _ => None,
+        }
+    }
+}
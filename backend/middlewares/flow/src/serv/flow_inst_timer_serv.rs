@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use bios_basic::dto::BasicQueryCondInfo;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{self, Utc},
+    db::sea_orm::{
+        sea_query::{Expr, Order, Query},
+        Iterable, Set,
+    },
+    log::{trace, warn},
+    tokio::{self, time},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    domain::flow_inst_timer,
+    dto::{
+        flow_external_dto::FlowExternalCallbackOp,
+        flow_inst_dto::{FlowInstTransferReq, FlowOperationContext},
+        flow_state_dto::FlowStateDetailResp,
+        flow_transition_dto::FlowTransitionDetailResp,
+    },
+    flow_constants,
+    helper::loop_check_helper,
+};
+
+use super::{flow_inst_serv::FlowInstServ, flow_inst_sla_serv::FlowInstSlaServ, flow_model_version_serv::FlowModelVersionServ, flow_state_kind_serv::FlowStateKindServ};
+
+/// Number of the due timers loaded at a time when triggering
+const TRIGGER_BATCH_SIZE: u64 = 200;
+/// The leased timer can be retried by other nodes after the lease expires
+const TRIGGER_LEASE_SEC: i64 = 300;
+/// The timer is dropped after it fails to fire so many times
+const TRIGGER_MAX_FIRE_TIMES: i32 = 5;
+
+pub struct FlowInstTimerServ;
+
+impl FlowInstTimerServ {
+    /// Replace the timers of the instance with the timed transitions of the state it has entered
+    ///
+    /// 以实例所进入状态的定时流转替换该实例的定时器
+    ///
+    /// The timer state is also handled here, its timer fires the transition to the configured target state.
+    /// 定时状态也在此处理，其定时器触发流转至所配置目标状态的流转。
+    pub async fn reset(
+        flow_inst_id: &str,
+        model_transitions: &[FlowTransitionDetailResp],
+        current_state: &FlowStateDetailResp,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<()> {
+        Self::cancel(flow_inst_id, funs).await?;
+        let now = Utc::now();
+        let state_timer_conf = FlowStateKindServ::timer_conf(current_state);
+        for model_transition in model_transitions.iter().filter(|model_transition| model_transition.from_flow_state_id == current_state.id) {
+            let delay_secs = match &state_timer_conf {
+                Some(state_timer_conf) if state_timer_conf.to_flow_state_id == model_transition.to_flow_state_id => Some(state_timer_conf.delay_sec),
+                _ => model_transition.transfer_by_timer_secs(),
+            };
+            let Some(delay_secs) = delay_secs else {
+                continue;
+            };
+            let flow_inst_timer = flow_inst_timer::ActiveModel {
+                id: Set(TardisFuns::field.nanoid()),
+                rel_flow_inst_id: Set(flow_inst_id.to_string()),
+                rel_flow_transition_id: Set(model_transition.id.clone()),
+                rel_flow_state_id: Set(current_state.id.to_string()),
+                due_time: Set(now + chrono::Duration::seconds(delay_secs)),
+                op_ctx: Set(FlowOperationContext::from_ctx(ctx)),
+                lease_owner: Set("".to_string()),
+                fire_times: Set(0),
+                own_paths: Set(ctx.own_paths.to_string()),
+                ..Default::default()
+            };
+            funs.db().insert_one(flow_inst_timer, ctx).await?;
+        }
+        Ok(())
+    }
+
+    /// Cancel all timers of the instance
+    ///
+    /// 取消实例的所有定时器
+    pub async fn cancel(flow_inst_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.db().execute(Query::delete().from_table(flow_inst_timer::Entity).and_where(Expr::col(flow_inst_timer::Column::RelFlowInstId).eq(flow_inst_id))).await?;
+        Ok(())
+    }
+
+    /// Check the due timers and the breached SLAs periodically
+    ///
+    /// 定期检查到期的定时器及超时的SLA
+    pub async fn trigger_periodically(check_interval_sec: u32) {
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(check_interval_sec.max(1) as u64));
+            loop {
+                interval.tick().await;
+                let funs = flow_constants::get_tardis_inst();
+                if let Err(e) = Self::trigger(&funs).await {
+                    warn!("[Flow.Inst] trigger timers error: {:?}", e);
+                }
+                if let Err(e) = FlowInstSlaServ::trigger(&funs).await {
+                    warn!("[Flow.Inst] trigger SLAs error: {:?}", e);
+                }
+            }
+        });
+    }
+
+    /// Fire the due timers
+    ///
+    /// 触发到期的定时器
+    ///
+    /// Each timer is claimed by leasing it (postponing its due time), so that it is fired by only one node even if multiple nodes are triggering at the same time,
+    /// and it is deleted after it is fired successfully, otherwise it is retried after the lease expires.
+    /// The timers are persistent, the timers that became due while the service was down are fired at the next check.
+    /// 每个定时器通过租借（延后到期时间）认领，多个节点同时触发时也只会由一个节点执行，触发成功后删除，否则在租期过后重试。
+    /// 定时器是持久化的，服务停止期间到期的定时器会在下次检查时触发。
+    pub async fn trigger(funs: &TardisFunsInst) -> TardisResult<()> {
+        let lease_owner = TardisFuns::field.nanoid();
+        loop {
+            let due_timers = funs
+                .db()
+                .find_dtos::<flow_inst_timer::Model>(
+                    Query::select()
+                        .columns(flow_inst_timer::Column::iter())
+                        .from(flow_inst_timer::Entity)
+                        .and_where(Expr::col(flow_inst_timer::Column::DueTime).lte(Utc::now()))
+                        .order_by(flow_inst_timer::Column::DueTime, Order::Asc)
+                        .limit(TRIGGER_BATCH_SIZE),
+                )
+                .await?;
+            let fetched_count = due_timers.len() as u64;
+            for timer in due_timers {
+                let now = Utc::now();
+                let claimed = funs
+                    .db()
+                    .execute(
+                        Query::update()
+                            .table(flow_inst_timer::Entity)
+                            .value(flow_inst_timer::Column::DueTime, now + chrono::Duration::seconds(TRIGGER_LEASE_SEC))
+                            .value(flow_inst_timer::Column::LeaseOwner, lease_owner.as_str())
+                            .value(flow_inst_timer::Column::FireTimes, Expr::col(flow_inst_timer::Column::FireTimes).add(1))
+                            .and_where(Expr::col(flow_inst_timer::Column::Id).eq(timer.id.as_str()))
+                            .and_where(Expr::col(flow_inst_timer::Column::DueTime).lte(now)),
+                    )
+                    .await?
+                    .rows_affected()
+                    == 1;
+                if !claimed {
+                    continue;
+                }
+                trace!(
+                    "[Flow.Inst] fire timer of instance [{}] transition [{}]",
+                    timer.rel_flow_inst_id,
+                    timer.rel_flow_transition_id
+                );
+                match Self::fire(&timer, funs).await {
+                    Ok(_) => Self::release(&timer.id, &lease_owner, funs).await?,
+                    Err(e) if timer.fire_times + 1 >= TRIGGER_MAX_FIRE_TIMES => {
+                        warn!(
+                            "[Flow.Inst] fire timer of instance [{}] error: {:?}, dropped after {} times",
+                            timer.rel_flow_inst_id, e, TRIGGER_MAX_FIRE_TIMES
+                        );
+                        Self::release(&timer.id, &lease_owner, funs).await?;
+                    }
+                    Err(e) => warn!(
+                        "[Flow.Inst] fire timer of instance [{}] error: {:?}, retry after the lease expires",
+                        timer.rel_flow_inst_id, e
+                    ),
+                }
+            }
+            if fetched_count < TRIGGER_BATCH_SIZE {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Delete the timer if it is still leased by the owner,
+    /// it may have been deleted by the transition (leaving the state) or leased by another node after the lease expired
+    async fn release(timer_id: &str, lease_owner: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.db()
+            .execute(
+                Query::delete()
+                    .from_table(flow_inst_timer::Entity)
+                    .and_where(Expr::col(flow_inst_timer::Column::Id).eq(timer_id))
+                    .and_where(Expr::col(flow_inst_timer::Column::LeaseOwner).eq(lease_owner)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// The transition is fired only if the instance is still in the state where the timer was registered and the conditions are met,
+    /// otherwise the timer is dropped.
+    async fn fire(timer: &flow_inst_timer::Model, funs: &TardisFunsInst) -> TardisResult<()> {
+        let ctx = TardisContext {
+            own_paths: timer.op_ctx.own_paths.clone(),
+            ak: timer.op_ctx.ak.clone(),
+            owner: timer.op_ctx.owner.clone(),
+            roles: timer.op_ctx.roles.clone(),
+            groups: timer.op_ctx.groups.clone(),
+            ..Default::default()
+        };
+        let flow_inst = FlowInstServ::get(&timer.rel_flow_inst_id, funs, &ctx).await?;
+        if flow_inst.finish_time.is_some() || flow_inst.current_state_id != timer.rel_flow_state_id {
+            return Ok(());
+        }
+        let flow_model = FlowModelVersionServ::get_inst_model(&flow_inst, funs, &ctx).await?;
+        let Some(model_transition) = flow_model
+            .transitions()
+            .into_iter()
+            .find(|model_transition| model_transition.id == timer.rel_flow_transition_id && model_transition.from_flow_state_id == flow_inst.current_state_id)
+        else {
+            return Ok(());
+        };
+        if let Some(guard_by_other_conds) = model_transition.guard_by_other_conds() {
+            if !BasicQueryCondInfo::check_or_and_conds(&guard_by_other_conds, &flow_inst.guard_vars(&None))? {
+                return Ok(());
+            }
+        }
+        FlowInstServ::transfer(
+            &flow_inst.id,
+            &FlowInstTransferReq {
+                flow_transition_id: model_transition.id,
+                message: None,
+                vars: None,
+            },
+            true,
+            FlowExternalCallbackOp::Timer,
+            loop_check_helper::InstancesTransition::default(),
+            &ctx,
+        )
+        .await?;
+        Ok(())
+    }
+}
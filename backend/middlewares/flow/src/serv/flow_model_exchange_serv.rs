@@ -0,0 +1,364 @@
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::rbum::{dto::rbum_filer_dto::RbumBasicFilterReq, serv::rbum_item_serv::RbumItemCrudOperation};
+use itertools::Itertools;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    serde_json::{json, Value},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::{
+        flow_model_dto::{FlowModelAddReq, FlowModelBindStateReq, FlowModelFilterReq},
+        flow_model_exchange_dto::{
+            FlowModelExchangeDoc, FlowModelExchangeFormat, FlowModelExchangeState, FlowModelExchangeTransition, FlowModelImportErrorResp, FlowModelImportReq, FlowModelImportResp,
+            FLOW_MODEL_EXCHANGE_VERSION,
+        },
+        flow_state_dto::{FlowStateAddReq, FlowStateFilterReq, FlowStateKind, FlowStateModifyReq, FlowStateTimerConf},
+        flow_transition_dto::FlowTransitionAddReq,
+    },
+    helper::bpmn_helper,
+};
+
+use super::{flow_model_serv::FlowModelServ, flow_state_kind_serv::FlowStateKindServ, flow_state_serv::FlowStateServ};
+
+/// Import and export of the model
+///
+/// 模型的导入导出
+pub struct FlowModelExchangeServ;
+
+impl FlowModelExchangeServ {
+    /// Export the model (or template) as a self-contained document
+    ///
+    /// 将模型（或模板）导出为自包含的文档
+    pub async fn export(flow_model_id: &str, format: &FlowModelExchangeFormat, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let doc = Self::export_doc(flow_model_id, funs, ctx).await?;
+        match format {
+            FlowModelExchangeFormat::Json => TardisFuns::json.obj_to_string(&doc),
+            FlowModelExchangeFormat::Bpmn => Ok(bpmn_helper::to_bpmn(&doc, flow_model_id)),
+        }
+    }
+
+    pub async fn export_doc(flow_model_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<FlowModelExchangeDoc> {
+        let global_ctx = TardisContext {
+            own_paths: "".to_string(),
+            ..ctx.clone()
+        };
+        let flow_model = FlowModelServ::get_item(
+            flow_model_id,
+            &FlowModelFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    own_paths: Some("".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        let model_states = flow_model.states();
+        let states = FlowStateServ::find_detail_items(
+            &FlowStateFilterReq {
+                basic: RbumBasicFilterReq {
+                    ids: Some(model_states.iter().map(|state| state.id.clone()).collect()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            &global_ctx,
+        )
+        .await?;
+        let states = model_states
+            .into_iter()
+            .sorted_by_key(|model_state| model_state.ext.sort)
+            .filter_map(|model_state| {
+                states.iter().find(|state| state.id == model_state.id).map(|state| FlowModelExchangeState {
+                    key: state.id.clone(),
+                    name: state.name.clone(),
+                    icon: state.icon.clone(),
+                    color: state.color.clone(),
+                    sys_state: state.sys_state.clone(),
+                    info: state.info.clone(),
+                    state_kind: state.state_kind.clone(),
+                    kind_conf: state.kind_conf.clone(),
+                    tags: state.tags.split(',').filter(|tag| !tag.is_empty()).map(|tag| tag.to_string()).collect(),
+                    ext: model_state.ext,
+                })
+            })
+            .collect_vec();
+        let transitions = flow_model
+            .transitions()
+            .into_iter()
+            .map(|transition| FlowModelExchangeTransition {
+                key: transition.id.clone(),
+                transition: FlowTransitionAddReq::from(transition),
+            })
+            .collect_vec();
+        Ok(FlowModelExchangeDoc {
+            format_version: FLOW_MODEL_EXCHANGE_VERSION.to_string(),
+            name: flow_model.name,
+            icon: flow_model.icon,
+            info: flow_model.info,
+            tag: flow_model.tag,
+            template: flow_model.template,
+            init_state_key: flow_model.init_state_id,
+            states,
+            transitions,
+        })
+    }
+
+    /// Import the document as a new model
+    ///
+    /// 将文档导入为新的模型
+    ///
+    /// Nothing is imported if the document is invalid, the errors are reported per element.
+    /// The states of the document are matched to the existing states by id (same environment), then by name for simple states
+    /// (same tag, system state and kind), and added if no state matches.
+    /// 文档校验失败时不做任何导入，错误按元素返回。文档中的状态先按ID（同一环境）匹配已有状态，普通状态再按名称（相同标签、系统状态及类型）匹配，
+    /// 均未匹配时新建状态。
+    pub async fn import(import_req: &FlowModelImportReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<FlowModelImportResp> {
+        let doc = match import_req.format {
+            FlowModelExchangeFormat::Json => {
+                TardisFuns::json.str_to_obj::<FlowModelExchangeDoc>(&import_req.content).map_err(|e| vec![FlowModelImportErrorResp::new("document", e.message)])
+            }
+            FlowModelExchangeFormat::Bpmn => bpmn_helper::from_bpmn(&import_req.content),
+        };
+        let doc = match doc {
+            Ok(doc) => doc,
+            Err(errors) => return Ok(FlowModelImportResp::failed(errors)),
+        };
+        let errors = Self::validate(&doc, funs);
+        if !errors.is_empty() {
+            return Ok(FlowModelImportResp::failed(errors));
+        }
+
+        let mut state_mapping = HashMap::new();
+        let mut added_states = vec![];
+        for state in &doc.states {
+            let state_id = match Self::find_state(state, &doc.tag, funs, ctx).await? {
+                Some(state_id) => state_id,
+                None => {
+                    let state_id = FlowStateServ::add_item(
+                        &mut FlowStateAddReq {
+                            id_prefix: None,
+                            name: Some(state.name.as_str().into()),
+                            icon: Some(state.icon.clone()).filter(|icon| !icon.is_empty()),
+                            color: Some(state.color.clone()),
+                            sys_state: state.sys_state.clone(),
+                            info: Some(state.info.clone()),
+                            state_kind: Some(state.state_kind.clone()),
+                            kind_conf: Some(Self::kind_conf(state)),
+                            template: None,
+                            rel_state_id: None,
+                            tags: Some(state.tags.clone()),
+                            scope_level: import_req.scope_level.clone(),
+                            disabled: None,
+                        },
+                        funs,
+                        ctx,
+                    )
+                    .await?;
+                    added_states.push((state_id.clone(), state));
+                    state_id
+                }
+            };
+            state_mapping.insert(state.key.clone(), state_id);
+        }
+        // the states referenced in the configurations of the added states are known only after all states are matched
+        for (state_id, state) in added_states {
+            let kind_conf = Self::kind_conf(state);
+            let mapped_kind_conf = Self::map_state_keys(&kind_conf, &state_mapping);
+            if mapped_kind_conf != kind_conf {
+                FlowStateServ::modify_item(
+                    &state_id,
+                    &mut FlowStateModifyReq {
+                        kind_conf: Some(mapped_kind_conf),
+                        ..Default::default()
+                    },
+                    funs,
+                    ctx,
+                )
+                .await?;
+            }
+        }
+
+        let transitions = doc
+            .transitions
+            .iter()
+            .map(|transition| {
+                let mut transition = transition.transition.clone();
+                transition.from_flow_state_id = state_mapping[&transition.from_flow_state_id].clone();
+                transition.to_flow_state_id = state_mapping[&transition.to_flow_state_id].clone();
+                if let Some(action_by_post_changes) = transition.action_by_post_changes.as_mut() {
+                    for post_change in action_by_post_changes.iter_mut() {
+                        if let Some(state_id) = state_mapping.get(&post_change.changed_state_id) {
+                            post_change.changed_state_id = state_id.clone();
+                        }
+                        if let Some(obj_current_state_ids) = post_change.obj_current_state_id.as_mut() {
+                            for obj_current_state_id in obj_current_state_ids.iter_mut() {
+                                if let Some(state_id) = state_mapping.get(obj_current_state_id) {
+                                    *obj_current_state_id = state_id.clone();
+                                }
+                            }
+                        }
+                    }
+                }
+                transition
+            })
+            .collect_vec();
+        let flow_model_id = FlowModelServ::add_item(
+            &mut FlowModelAddReq {
+                name: import_req.name.clone().unwrap_or_else(|| doc.name.as_str().into()),
+                icon: Some(doc.icon.clone()),
+                info: Some(doc.info.clone()),
+                init_state_id: state_mapping[&doc.init_state_key].clone(),
+                rel_template_ids: import_req.rel_template_ids.clone(),
+                transitions: if transitions.is_empty() { None } else { Some(transitions) },
+                states: Some(
+                    doc.states
+                        .iter()
+                        .map(|state| FlowModelBindStateReq {
+                            state_id: state_mapping[&state.key].clone(),
+                            ext: state.ext.clone(),
+                        })
+                        .collect(),
+                ),
+                template: import_req.template.unwrap_or(doc.template),
+                rel_model_id: None,
+                tag: Some(doc.tag.clone()).filter(|tag| !tag.is_empty()),
+                scope_level: import_req.scope_level.clone(),
+                disabled: None,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(FlowModelImportResp {
+            flow_model_id: Some(flow_model_id),
+            state_mapping,
+            errors: vec![],
+        })
+    }
+
+    fn validate(doc: &FlowModelExchangeDoc, funs: &TardisFunsInst) -> Vec<FlowModelImportErrorResp> {
+        let mut errors = vec![];
+        if doc.format_version != FLOW_MODEL_EXCHANGE_VERSION {
+            errors.push(FlowModelImportErrorResp::new("document", format!("unsupported format version [{}]", doc.format_version)));
+        }
+        if doc.name.trim().chars().count() < 2 {
+            errors.push(FlowModelImportErrorResp::new("model", "the name must be at least 2 characters"));
+        }
+        let mut state_keys = HashSet::new();
+        for state in &doc.states {
+            let element = format!("state:{}", state.key);
+            if state.key.trim().is_empty() {
+                errors.push(FlowModelImportErrorResp::new(element.clone(), "the key is required"));
+            } else if !state_keys.insert(state.key.as_str()) {
+                errors.push(FlowModelImportErrorResp::new(element.clone(), "the key is duplicated"));
+            }
+            if state.name.trim().is_empty() {
+                errors.push(FlowModelImportErrorResp::new(element.clone(), "the name is required"));
+            }
+            if let Err(e) = FlowStateKindServ::validate_kind_conf(&state.state_kind, &Self::kind_conf(state), funs) {
+                errors.push(FlowModelImportErrorResp::new(element.clone(), e.message));
+            }
+            if state.state_kind == FlowStateKind::Timer {
+                if let Ok(timer_conf) = TardisFuns::json.json_to_obj::<FlowStateTimerConf>(state.kind_conf.clone()) {
+                    if !doc.states.iter().any(|state| state.key == timer_conf.to_flow_state_id) {
+                        errors.push(FlowModelImportErrorResp::new(
+                            element,
+                            format!("the target state [{}] of the timer does not exist", timer_conf.to_flow_state_id),
+                        ));
+                    }
+                }
+            }
+        }
+        if doc.states.is_empty() {
+            errors.push(FlowModelImportErrorResp::new("model", "at least one state is required"));
+        } else if !state_keys.contains(doc.init_state_key.as_str()) {
+            errors.push(FlowModelImportErrorResp::new("model", format!("the initial state [{}] does not exist", doc.init_state_key)));
+        }
+        let mut transition_keys = HashSet::new();
+        for transition in &doc.transitions {
+            let element = format!("transition:{}", transition.key);
+            if !transition.key.trim().is_empty() && !transition_keys.insert(transition.key.as_str()) {
+                errors.push(FlowModelImportErrorResp::new(element.clone(), "the key is duplicated"));
+            }
+            for state_key in [&transition.transition.from_flow_state_id, &transition.transition.to_flow_state_id] {
+                if !state_keys.contains(state_key.as_str()) {
+                    errors.push(FlowModelImportErrorResp::new(element.clone(), format!("the state [{state_key}] does not exist")));
+                }
+            }
+            if let Some(transfer_by_timer) = transition.transition.transfer_by_timer.as_ref().map(|timer| timer.trim()).filter(|timer| !timer.is_empty()) {
+                if transfer_by_timer.parse::<i64>().map(|secs| secs <= 0).unwrap_or(true) {
+                    errors.push(FlowModelImportErrorResp::new(element, "transfer_by_timer must be a positive number of seconds"));
+                }
+            }
+        }
+        errors
+    }
+
+    async fn find_state(state: &FlowModelExchangeState, tag: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<String>> {
+        let same_id = FlowStateServ::find_one_item(
+            &FlowStateFilterReq {
+                basic: RbumBasicFilterReq {
+                    ids: Some(vec![state.key.clone()]),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        if let Some(same_id) = same_id {
+            return Ok(Some(same_id.id));
+        }
+        if state.state_kind != FlowStateKind::Simple {
+            return Ok(None);
+        }
+        let same_name = FlowStateServ::find_items(
+            &FlowStateFilterReq {
+                basic: RbumBasicFilterReq {
+                    names: Some(vec![state.name.clone()]),
+                    ..Default::default()
+                },
+                sys_state: Some(state.sys_state.clone()),
+                tag: Some(tag.to_string()).filter(|tag| !tag.is_empty()),
+                state_kind: Some(FlowStateKind::Simple),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(same_name.into_iter().next().map(|state| state.id))
+    }
+
+    fn kind_conf(state: &FlowModelExchangeState) -> Value {
+        if state.kind_conf.is_null() {
+            json!({})
+        } else {
+            state.kind_conf.clone()
+        }
+    }
+
+    /// Replace the state keys referenced in the configuration with the imported state ids
+    fn map_state_keys(value: &Value, state_mapping: &HashMap<String, String>) -> Value {
+        match value {
+            Value::String(key) => state_mapping.get(key).map(|state_id| Value::String(state_id.clone())).unwrap_or_else(|| value.clone()),
+            Value::Array(values) => Value::Array(values.iter().map(|value| Self::map_state_keys(value, state_mapping)).collect()),
+            Value::Object(values) => Value::Object(values.iter().map(|(field, value)| (field.clone(), Self::map_state_keys(value, state_mapping))).collect()),
+            _ => value.clone(),
+        }
+    }
+}
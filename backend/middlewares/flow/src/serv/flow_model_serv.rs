@@ -566,7 +566,10 @@ impl FlowModelServ {
         for (i, state_id) in state_ids.into_iter().enumerate() {
             bind_states.push(FlowModelBindStateReq {
                 state_id,
-                ext: FlowStateRelModelExt { sort: i as i64, show_btns: None },
+                ext: FlowStateRelModelExt {
+                    sort: i as i64,
+                    ..Default::default()
+                },
             });
         }
         // transitions
@@ -1418,6 +1421,9 @@ impl FlowModelServ {
         if let Some(show_btns) = modify_req.show_btns.clone() {
             ext.show_btns = Some(show_btns);
         }
+        if let Some(sla) = modify_req.sla.clone() {
+            ext.sla = Some(sla);
+        }
         FlowRelServ::modify_simple_rel(
             &FlowRelKind::FlowModelState,
             flow_model_id,
@@ -0,0 +1,366 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+use rust_decimal::Decimal;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{SecondsFormat, Utc},
+    serde_json::{json, Value},
+    TardisFunsInst,
+};
+
+use crate::dto::{
+    flow_inst_dto::{FlowInstDetailResp, FlowInstTransitionInfo, FlowOperationContext},
+    flow_model_dto::FlowModelDetailResp,
+    flow_model_simulate_dto::{
+        FlowModelSimulateCascadeResp, FlowModelSimulateFrontChangeResp, FlowModelSimulatePostChangeResp, FlowModelSimulateReq, FlowModelSimulateResp,
+        FlowModelSimulateTransitionResp,
+    },
+    flow_transition_dto::{
+        FlowTransitionActionByStateChangeInfo, FlowTransitionActionByVarChangeInfo, FlowTransitionActionByVarChangeInfoChangedKind, FlowTransitionActionChangeAgg,
+        FlowTransitionActionChangeKind, FlowTransitionDetailResp, TagRelKind,
+    },
+};
+
+use super::{flow_event_serv::FlowEventServ, flow_inst_serv::FlowInstServ, flow_model_serv::FlowModelServ, flow_model_version_serv::FlowModelVersionServ};
+
+/// Dry run of the transitions of the model
+///
+/// 模型动作的模拟流转
+pub struct FlowModelSimulateServ;
+
+impl FlowModelSimulateServ {
+    /// Evaluate the transitions of a state against a hypothetical instance context without persisting anything
+    ///
+    /// 以假设的实例上下文评估某状态下的动作，不会持久化任何数据
+    ///
+    /// The related objects are not fetched, the cascades are derived from the current content of the related models.
+    /// 不会获取实际的关联对象，级联动作根据关联模型当前的内容推导。
+    pub async fn simulate(flow_model_id: &str, simulate_req: &FlowModelSimulateReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<FlowModelSimulateResp> {
+        let mut flow_model = FlowModelVersionServ::get_model(flow_model_id, funs, ctx).await?;
+        if let Some(flow_model_version_id) = &simulate_req.flow_model_version_id {
+            if FlowModelVersionServ::get_version(flow_model_version_id, funs).await?.rel_flow_model_id != flow_model_id {
+                return Err(funs.err().bad_request(
+                    "flow_model_version",
+                    "simulate",
+                    "the version does not belong to the model",
+                    "400-flow-model-version-not-match",
+                ));
+            }
+            flow_model = FlowModelVersionServ::pin_model(flow_model, Some(flow_model_version_id), funs).await?;
+        }
+        let current_state_id = simulate_req.current_state_id.clone().unwrap_or_else(|| flow_model.init_state_id.clone());
+        let current_state = flow_model.states().into_iter().find(|state| state.id == current_state_id).ok_or_else(|| {
+            funs.err().not_found(
+                "flow_model",
+                "simulate",
+                &format!("state {} not found in the model", current_state_id),
+                "404-flow-model-simulate-state-not-found",
+            )
+        })?;
+
+        let operator = simulate_req.operator.clone().unwrap_or_else(|| ctx.owner.clone());
+        let simulate_ctx = TardisContext {
+            owner: operator.clone(),
+            roles: simulate_req.roles.clone().unwrap_or_else(|| ctx.roles.clone()),
+            groups: simulate_req.orgs.clone().unwrap_or_else(|| ctx.groups.clone()),
+            ..ctx.clone()
+        };
+        let vars = simulate_req.vars.clone().unwrap_or_default();
+        let flow_inst = Self::mock_inst(&flow_model, &current_state_id, &current_state.name, simulate_req, &simulate_ctx);
+
+        let available_transition_ids = FlowInstServ::do_find_next_transitions(&flow_inst, &flow_model, simulate_req.flow_transition_id.clone(), &None, false, funs, &simulate_ctx)
+            .await?
+            .next_flow_transitions
+            .into_iter()
+            .map(|transition| transition.next_flow_transition_id)
+            .collect::<HashSet<_>>();
+        let model_transitions = flow_model
+            .transitions()
+            .into_iter()
+            .filter(|transition| {
+                transition.from_flow_state_id == current_state_id && simulate_req.flow_transition_id.as_ref().map(|transition_id| transition_id == &transition.id).unwrap_or(true)
+            })
+            .sorted_by_key(|transition| transition.sort)
+            .collect_vec();
+        let rel_models =
+            if model_transitions.iter().any(|transition| transition.action_by_post_changes().iter().any(|post_change| post_change.kind == FlowTransitionActionChangeKind::State)) {
+                Self::find_rel_model_details(&flow_model, funs, ctx).await?
+            } else {
+                HashMap::new()
+            };
+
+        let mut transitions = vec![];
+        for model_transition in model_transitions {
+            let (_, guards) = FlowInstServ::check_transition_guards(&flow_inst, &model_transition, &None, &simulate_ctx);
+            let front_changes = model_transition
+                .action_by_front_changes()
+                .into_iter()
+                .map(|condition| {
+                    Ok(FlowModelSimulateFrontChangeResp {
+                        passed: FlowEventServ::do_check_front_condition(&vars, &condition)?,
+                        condition,
+                    })
+                })
+                .collect::<TardisResult<Vec<_>>>()?;
+            let mut changed_vars = vars.clone();
+            let mut self_var_changed = false;
+            let mut visited = HashSet::from([(flow_model.tag.clone(), model_transition.id.clone())]);
+            let mut post_changes = vec![];
+            for post_change in model_transition.action_by_post_changes() {
+                let post_change = FlowTransitionActionChangeAgg::from(post_change);
+                if let Some(change_info) = post_change.var_change_info {
+                    let is_self = change_info.obj_tag.clone().unwrap_or_default().is_empty();
+                    let changed_val = Self::preview_changed_val(&change_info, &changed_vars, is_self, &operator);
+                    if is_self {
+                        changed_vars.insert(change_info.var_name.clone(), changed_val.clone().unwrap_or(Value::Null));
+                        self_var_changed = true;
+                    }
+                    post_changes.push(FlowModelSimulatePostChangeResp {
+                        kind: FlowTransitionActionChangeKind::Var,
+                        describe: change_info.describe,
+                        obj_tag: change_info.obj_tag.filter(|obj_tag| !obj_tag.is_empty()),
+                        var_name: Some(change_info.var_name),
+                        changed_val,
+                        changed_state_id: None,
+                        cascades: vec![],
+                    });
+                } else if let Some(change_info) = post_change.state_change_info {
+                    let mut cascades = vec![];
+                    Self::cascade_state_change(&flow_model.tag, &change_info, &rel_models, 1, &mut visited, &mut cascades);
+                    post_changes.push(FlowModelSimulatePostChangeResp {
+                        kind: FlowTransitionActionChangeKind::State,
+                        describe: change_info.describe,
+                        obj_tag: Some(change_info.obj_tag),
+                        var_name: None,
+                        changed_val: None,
+                        changed_state_id: Some(change_info.changed_state_id),
+                        cascades,
+                    });
+                }
+            }
+            // the front conditions of the target state are checked again after the fields of the current object are modified
+            let auto_flow_transition_id = if self_var_changed {
+                Self::find_front_change_transition(&flow_model, &model_transition.to_flow_state_id, &changed_vars)?
+            } else {
+                None
+            };
+            transitions.push(FlowModelSimulateTransitionResp {
+                available: available_transition_ids.contains(&model_transition.id),
+                flow_transition_id: model_transition.id,
+                flow_transition_name: model_transition.name,
+                to_flow_state_id: model_transition.to_flow_state_id,
+                to_flow_state_name: model_transition.to_flow_state_name,
+                guards,
+                front_changes,
+                post_changes,
+                auto_flow_transition_id,
+            });
+        }
+        let loop_detected = transitions.iter().flat_map(|transition| &transition.post_changes).flat_map(|post_change| &post_change.cascades).any(|cascade| cascade.loop_detected);
+        Ok(FlowModelSimulateResp {
+            flow_model_id: flow_model.id.clone(),
+            flow_model_version_id: simulate_req.flow_model_version_id.clone(),
+            current_state_id,
+            current_state_name: current_state.name,
+            transitions,
+            post_action_ring: loop_detected || FlowModelServ::check_post_action_ring(&flow_model, funs, ctx).await?,
+        })
+    }
+
+    fn mock_inst(
+        flow_model: &FlowModelDetailResp,
+        current_state_id: &str,
+        current_state_name: &str,
+        simulate_req: &FlowModelSimulateReq,
+        ctx: &TardisContext,
+    ) -> FlowInstDetailResp {
+        let op_ctx = |owner: &str| FlowOperationContext {
+            own_paths: ctx.own_paths.clone(),
+            owner: owner.to_string(),
+            ..Default::default()
+        };
+        FlowInstDetailResp {
+            id: "".to_string(),
+            rel_flow_model_id: flow_model.id.clone(),
+            rel_flow_model_name: flow_model.name.clone(),
+            rel_flow_model_version_id: simulate_req.flow_model_version_id.clone(),
+            rel_business_obj_id: "".to_string(),
+            current_state_id: current_state_id.to_string(),
+            current_state_name: Some(current_state_name.to_string()),
+            current_state_color: None,
+            current_state_kind: None,
+            current_state_ext: None,
+            current_vars: simulate_req.vars.clone(),
+            create_vars: None,
+            create_ctx: op_ctx(simulate_req.creator.as_deref().unwrap_or(&ctx.owner)),
+            create_time: Utc::now(),
+            finish_ctx: None,
+            finish_time: None,
+            finish_abort: None,
+            output_message: None,
+            transitions: simulate_req.his_operators.as_ref().map(|his_operators| {
+                his_operators
+                    .iter()
+                    .map(|his_operator| FlowInstTransitionInfo {
+                        id: "".to_string(),
+                        start_time: Utc::now(),
+                        op_ctx: op_ctx(his_operator),
+                        output_message: None,
+                    })
+                    .collect_vec()
+            }),
+            approval: None,
+            sla: None,
+            own_paths: ctx.own_paths.clone(),
+        }
+    }
+
+    /// The models of the related objects, grouped by tag
+    async fn find_rel_model_details(flow_model: &FlowModelDetailResp, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<HashMap<String, FlowModelDetailResp>> {
+        let mut rel_models = HashMap::new();
+        if flow_model.template {
+            rel_models.insert(flow_model.tag.clone(), flow_model.clone());
+            return Ok(rel_models);
+        }
+        for (tag, model) in FlowModelServ::find_rel_models(flow_model.rel_template_ids.last().cloned(), false, funs, ctx).await? {
+            if model.id == flow_model.id {
+                rel_models.insert(tag, flow_model.clone());
+            } else {
+                rel_models.insert(tag, FlowModelVersionServ::get_model(&model.id, funs, ctx).await?);
+            }
+        }
+        Ok(rel_models)
+    }
+
+    /// The value of the field after the post change, ``None`` if it depends on the related object
+    fn preview_changed_val(change_info: &FlowTransitionActionByVarChangeInfo, vars: &HashMap<String, Value>, is_self: bool, operator: &str) -> Option<Value> {
+        match change_info.changed_kind {
+            Some(FlowTransitionActionByVarChangeInfoChangedKind::Clean) => Some(Value::Null),
+            Some(FlowTransitionActionByVarChangeInfoChangedKind::AutoGetOperateTime) => Some(json!(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true))),
+            Some(FlowTransitionActionByVarChangeInfoChangedKind::AutoGetOperator) => Some(json!(operator)),
+            Some(FlowTransitionActionByVarChangeInfoChangedKind::SelectField) if is_self => {
+                change_info.changed_val.as_ref().and_then(|field| field.as_str()).and_then(|field| vars.get(field)).cloned()
+            }
+            Some(FlowTransitionActionByVarChangeInfoChangedKind::AddOrSub) if is_self => {
+                let changed_val = change_info.changed_val.as_ref()?.as_object()?;
+                let target_value = Decimal::from_str(&changed_val.get("value")?.as_str().unwrap_or_default().parse::<f64>().unwrap_or_default().to_string()).ok()?;
+                let original_value = vars
+                    .get(&format!("custom_{}", change_info.var_name))
+                    .or_else(|| vars.get(&change_info.var_name))
+                    .and_then(|original_value| original_value.as_str())
+                    .unwrap_or_default()
+                    .parse::<f64>()
+                    .unwrap_or_default();
+                let original_value = Decimal::from_str(&original_value.to_string()).ok()?;
+                match changed_val.get("op")?.as_str().unwrap_or_default() {
+                    "add" => Some(json!(original_value + target_value)),
+                    "sub" => Some(json!(original_value - target_value)),
+                    _ => None,
+                }
+            }
+            Some(FlowTransitionActionByVarChangeInfoChangedKind::SelectField) | Some(FlowTransitionActionByVarChangeInfoChangedKind::AddOrSub) => None,
+            Some(FlowTransitionActionByVarChangeInfoChangedKind::ChangeContent) | None => change_info.changed_val.clone(),
+        }
+    }
+
+    /// The transitions of the related models which reach the changed state, and the state changes they trigger in turn
+    fn cascade_state_change(
+        current_tag: &str,
+        change_info: &FlowTransitionActionByStateChangeInfo,
+        rel_models: &HashMap<String, FlowModelDetailResp>,
+        depth: u32,
+        visited: &mut HashSet<(String, String)>,
+        cascades: &mut Vec<FlowModelSimulateCascadeResp>,
+    ) {
+        // the parent and sub objects use the model of the same tag
+        let obj_tag = if change_info.obj_tag_rel_kind == Some(TagRelKind::ParentOrSub) {
+            current_tag.to_string()
+        } else {
+            change_info.obj_tag.clone()
+        };
+        let Some(rel_model) = rel_models.get(&obj_tag) else {
+            cascades.push(FlowModelSimulateCascadeResp {
+                depth,
+                obj_tag,
+                flow_model_id: None,
+                flow_transition_id: None,
+                flow_transition_name: None,
+                from_flow_state_id: None,
+                to_flow_state_id: change_info.changed_state_id.clone(),
+                loop_detected: false,
+            });
+            return;
+        };
+        let obj_current_state_ids = change_info.obj_current_state_id.clone().unwrap_or_default();
+        // as the engine does, the last transition of each state which reaches the changed state is used
+        let rel_transitions = rel_model
+            .transitions()
+            .into_iter()
+            .filter(|transition| {
+                transition.to_flow_state_id == change_info.changed_state_id && (obj_current_state_ids.is_empty() || obj_current_state_ids.contains(&transition.from_flow_state_id))
+            })
+            .rev()
+            .unique_by(|transition| transition.from_flow_state_id.clone())
+            .collect::<Vec<FlowTransitionDetailResp>>();
+        if rel_transitions.is_empty() {
+            cascades.push(FlowModelSimulateCascadeResp {
+                depth,
+                obj_tag,
+                flow_model_id: Some(rel_model.id.clone()),
+                flow_transition_id: None,
+                flow_transition_name: None,
+                from_flow_state_id: None,
+                to_flow_state_id: change_info.changed_state_id.clone(),
+                loop_detected: false,
+            });
+            return;
+        }
+        for rel_transition in rel_transitions {
+            let loop_detected = !visited.insert((obj_tag.clone(), rel_transition.id.clone()));
+            cascades.push(FlowModelSimulateCascadeResp {
+                depth,
+                obj_tag: obj_tag.clone(),
+                flow_model_id: Some(rel_model.id.clone()),
+                flow_transition_id: Some(rel_transition.id.clone()),
+                flow_transition_name: Some(rel_transition.name.clone()),
+                from_flow_state_id: Some(rel_transition.from_flow_state_id.clone()),
+                to_flow_state_id: rel_transition.to_flow_state_id.clone(),
+                loop_detected,
+            });
+            if loop_detected {
+                continue;
+            }
+            for post_change in rel_transition.action_by_post_changes() {
+                if let Some(rel_change_info) = FlowTransitionActionChangeAgg::from(post_change).state_change_info {
+                    Self::cascade_state_change(&obj_tag, &rel_change_info, rel_models, depth + 1, visited, cascades);
+                }
+            }
+        }
+    }
+
+    /// The first transition of the state whose front conditions are all satisfied
+    fn find_front_change_transition(flow_model: &FlowModelDetailResp, flow_state_id: &str, vars: &HashMap<String, Value>) -> TardisResult<Option<String>> {
+        for transition in flow_model
+            .transitions()
+            .into_iter()
+            .filter(|transition| transition.from_flow_state_id == flow_state_id && !transition.action_by_front_changes().is_empty())
+            .sorted_by_key(|transition| transition.sort)
+        {
+            let mut passed = true;
+            for condition in transition.action_by_front_changes() {
+                if !FlowEventServ::do_check_front_condition(vars, &condition)? {
+                    passed = false;
+                    break;
+                }
+            }
+            if passed {
+                return Ok(Some(transition.id));
+            }
+        }
+        Ok(None)
+    }
+}
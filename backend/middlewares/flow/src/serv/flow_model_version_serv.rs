@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::rbum::{dto::rbum_filer_dto::RbumBasicFilterReq, serv::rbum_item_serv::RbumItemCrudOperation};
+use itertools::Itertools;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    db::sea_orm::{
+        self,
+        sea_query::{Expr, OnConflict, Order, Query},
+        Set,
+    },
+    serde_json::Value,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    domain::{flow_inst, flow_model_version},
+    dto::{
+        flow_inst_dto::{FlowInstDetailResp, FlowOperationContext},
+        flow_model_dto::{FlowModelDetailResp, FlowModelFilterReq},
+        flow_model_version_dto::{
+            FlowModelVersionDetailResp, FlowModelVersionDiffResp, FlowModelVersionDiffTransitionResp, FlowModelVersionMigrateInstResp, FlowModelVersionMigrateReq,
+            FlowModelVersionMigrateResp, FlowModelVersionPublishReq, FlowModelVersionSummaryResp,
+        },
+        flow_state_dto::FlowStateFilterReq,
+        flow_transition_dto::FlowTransitionDetailResp,
+    },
+};
+
+use super::{
+    flow_inst_approval_serv::FlowInstApprovalServ, flow_inst_sla_serv::FlowInstSlaServ, flow_inst_timer_serv::FlowInstTimerServ, flow_model_serv::FlowModelServ,
+    flow_state_serv::FlowStateServ,
+};
+
+/// The fields of the transition which are derived from the states rather than configured on the transition
+const DERIVED_TRANSITION_FIELDS: [&str; 4] = ["from_flow_state_name", "from_flow_state_color", "to_flow_state_name", "to_flow_state_color"];
+/// The max attempts to allocate the version number when publishing concurrently
+const PUBLISH_MAX_ATTEMPTS: usize = 5;
+
+/// Published versions of the model
+///
+/// 模型的已发布版本
+pub struct FlowModelVersionServ;
+
+impl FlowModelVersionServ {
+    /// Publish the current structure of the model as a new immutable version
+    ///
+    /// 将模型当前的结构发布为新的不可变版本
+    pub async fn publish(flow_model_id: &str, publish_req: &FlowModelVersionPublishReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let flow_model = Self::get_model(flow_model_id, funs, ctx).await?;
+        if flow_model.init_state_id.is_empty() {
+            return Err(funs.err().bad_request(
+                "flow_model_version",
+                "publish",
+                "the initial state of the model is not set",
+                "400-flow-model-init-state-missing",
+            ));
+        }
+        let id = TardisFuns::field.nanoid();
+        // the version number is allocated optimistically, the unique index rejects the one taken by a concurrent publication,
+        // the conflict is ignored rather than raised so that the transaction is not aborted and the next number can be tried
+        for _ in 0..PUBLISH_MAX_ATTEMPTS {
+            let version = Self::find_versions(flow_model_id, funs).await?.first().map(|version| version.version).unwrap_or(0) + 1;
+            let mut query = Query::insert();
+            query
+                .into_table(flow_model_version::Entity)
+                .columns([
+                    flow_model_version::Column::Id,
+                    flow_model_version::Column::RelFlowModelId,
+                    flow_model_version::Column::Version,
+                    flow_model_version::Column::Info,
+                    flow_model_version::Column::InitStateId,
+                    flow_model_version::Column::States,
+                    flow_model_version::Column::Transitions,
+                    flow_model_version::Column::PublishCtx,
+                    flow_model_version::Column::OwnPaths,
+                ])
+                .values_panic([
+                    id.clone().into(),
+                    flow_model_id.into(),
+                    version.into(),
+                    publish_req.info.clone().unwrap_or_default().into(),
+                    flow_model.init_state_id.clone().into(),
+                    flow_model.states.clone().unwrap_or(Value::Array(vec![])).into(),
+                    flow_model.transitions.clone().unwrap_or(Value::Array(vec![])).into(),
+                    FlowOperationContext::from_ctx(ctx).into(),
+                    ctx.own_paths.clone().into(),
+                ])
+                .on_conflict(OnConflict::columns([flow_model_version::Column::RelFlowModelId, flow_model_version::Column::Version]).do_nothing().to_owned());
+            if funs.db().execute(&query).await?.rows_affected() == 1 {
+                return Ok(id);
+            }
+        }
+        Err(funs.err().conflict(
+            "flow_model_version",
+            "publish",
+            "the model is being published concurrently, please try again",
+            "409-flow-model-version-conflict",
+        ))
+    }
+
+    /// Find the versions of the model, the latest first
+    ///
+    /// 获取模型的版本列表，最新版本在前
+    pub async fn find_versions(flow_model_id: &str, funs: &TardisFunsInst) -> TardisResult<Vec<FlowModelVersionSummaryResp>> {
+        funs.db()
+            .find_dtos::<FlowModelVersionSummaryResp>(
+                Query::select()
+                    .columns([
+                        flow_model_version::Column::Id,
+                        flow_model_version::Column::RelFlowModelId,
+                        flow_model_version::Column::Version,
+                        flow_model_version::Column::Info,
+                        flow_model_version::Column::InitStateId,
+                        flow_model_version::Column::PublishCtx,
+                        flow_model_version::Column::CreateTime,
+                    ])
+                    .from(flow_model_version::Entity)
+                    .and_where(Expr::col(flow_model_version::Column::RelFlowModelId).eq(flow_model_id))
+                    .order_by(flow_model_version::Column::Version, Order::Desc),
+            )
+            .await
+    }
+
+    /// The latest version of the model, ``None`` if the model has not been published
+    ///
+    /// 模型的最新版本，模型未发布时为 ``None``
+    pub async fn find_latest_version_id(flow_model_id: &str, funs: &TardisFunsInst) -> TardisResult<Option<String>> {
+        #[derive(sea_orm::FromQueryResult)]
+        struct IdResult {
+            id: String,
+        }
+        Ok(funs
+            .db()
+            .get_dto::<IdResult>(
+                Query::select()
+                    .column(flow_model_version::Column::Id)
+                    .from(flow_model_version::Entity)
+                    .and_where(Expr::col(flow_model_version::Column::RelFlowModelId).eq(flow_model_id))
+                    .order_by(flow_model_version::Column::Version, Order::Desc)
+                    .limit(1),
+            )
+            .await?
+            .map(|version| version.id))
+    }
+
+    pub async fn get_version(flow_model_version_id: &str, funs: &TardisFunsInst) -> TardisResult<FlowModelVersionDetailResp> {
+        funs.db()
+            .get_dto::<FlowModelVersionDetailResp>(
+                Query::select()
+                    .columns([
+                        flow_model_version::Column::Id,
+                        flow_model_version::Column::RelFlowModelId,
+                        flow_model_version::Column::Version,
+                        flow_model_version::Column::Info,
+                        flow_model_version::Column::InitStateId,
+                        flow_model_version::Column::States,
+                        flow_model_version::Column::Transitions,
+                        flow_model_version::Column::PublishCtx,
+                        flow_model_version::Column::CreateTime,
+                    ])
+                    .from(flow_model_version::Entity)
+                    .and_where(Expr::col(flow_model_version::Column::Id).eq(flow_model_version_id)),
+            )
+            .await?
+            .ok_or_else(|| {
+                funs.err().not_found(
+                    "flow_model_version",
+                    "get",
+                    &format!("flow model version {} not found", flow_model_version_id),
+                    "404-flow-model-version-not-found",
+                )
+            })
+    }
+
+    /// The model used by the instance, that is the version it is pinned to, or the current model if it is not pinned
+    ///
+    /// 实例所使用的模型，即实例所固定的版本，未固定版本时为模型当前的内容
+    pub async fn get_inst_model(flow_inst: &FlowInstDetailResp, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<FlowModelDetailResp> {
+        let flow_model = Self::get_model(&flow_inst.rel_flow_model_id, funs, ctx).await?;
+        Self::pin_model(flow_model, flow_inst.rel_flow_model_version_id.as_deref(), funs).await
+    }
+
+    /// Replace the structure of the model with that of the version
+    ///
+    /// 以版本的结构替换模型的结构
+    pub async fn pin_model(mut flow_model: FlowModelDetailResp, flow_model_version_id: Option<&str>, funs: &TardisFunsInst) -> TardisResult<FlowModelDetailResp> {
+        if let Some(flow_model_version_id) = flow_model_version_id {
+            Self::apply_version(&mut flow_model, Self::get_version(flow_model_version_id, funs).await?);
+        }
+        Ok(flow_model)
+    }
+
+    fn apply_version(flow_model: &mut FlowModelDetailResp, version: FlowModelVersionDetailResp) {
+        flow_model.init_state_id = version.init_state_id;
+        flow_model.states = Some(version.states);
+        flow_model.transitions = Some(version.transitions);
+    }
+
+    /// Compare the structures of two versions, ``None`` means the current content of the model
+    ///
+    /// 比较两个版本的结构，``None`` 表示模型当前的内容
+    pub async fn diff(
+        flow_model_id: &str,
+        from_version_id: Option<String>,
+        to_version_id: Option<String>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<FlowModelVersionDiffResp> {
+        let current_model = Self::get_model(flow_model_id, funs, ctx).await?;
+        let mut structures = vec![];
+        for version_id in [&from_version_id, &to_version_id] {
+            let mut flow_model = current_model.clone();
+            if let Some(version_id) = version_id {
+                let version = Self::get_version(version_id, funs).await?;
+                if version.rel_flow_model_id != flow_model_id {
+                    return Err(funs.err().bad_request("flow_model_version", "diff", "the version does not belong to the model", "400-flow-model-version-not-match"));
+                }
+                Self::apply_version(&mut flow_model, version);
+            }
+            structures.push(flow_model);
+        }
+        let (from_model, to_model) = (&structures[0], &structures[1]);
+        let from_state_ids = from_model.states().into_iter().map(|state| state.id).collect::<HashSet<_>>();
+        let to_state_ids = to_model.states().into_iter().map(|state| state.id).collect::<HashSet<_>>();
+        let from_transitions = from_model.transitions();
+        let to_transitions = to_model.transitions();
+        let diff_transition = |transition: &FlowTransitionDetailResp, changed_fields: Vec<String>| FlowModelVersionDiffTransitionResp {
+            id: transition.id.clone(),
+            name: transition.name.clone(),
+            from_flow_state_id: transition.from_flow_state_id.clone(),
+            to_flow_state_id: transition.to_flow_state_id.clone(),
+            changed_fields,
+        };
+        let mut modified_transitions = vec![];
+        for from_transition in &from_transitions {
+            if let Some(to_transition) = to_transitions.iter().find(|to_transition| to_transition.id == from_transition.id) {
+                let changed_fields = Self::changed_fields(from_transition, to_transition)?;
+                if !changed_fields.is_empty() {
+                    modified_transitions.push(diff_transition(from_transition, changed_fields));
+                }
+            }
+        }
+        Ok(FlowModelVersionDiffResp {
+            from_version_id,
+            to_version_id,
+            init_state_changed: from_model.init_state_id != to_model.init_state_id,
+            added_state_ids: to_state_ids.difference(&from_state_ids).cloned().sorted().collect(),
+            removed_state_ids: from_state_ids.difference(&to_state_ids).cloned().sorted().collect(),
+            added_transitions: to_transitions
+                .iter()
+                .filter(|to_transition| !from_transitions.iter().any(|from_transition| from_transition.id == to_transition.id))
+                .map(|transition| diff_transition(transition, vec![]))
+                .collect(),
+            removed_transitions: from_transitions
+                .iter()
+                .filter(|from_transition| !to_transitions.iter().any(|to_transition| to_transition.id == from_transition.id))
+                .map(|transition| diff_transition(transition, vec![]))
+                .collect(),
+            modified_transitions,
+        })
+    }
+
+    fn changed_fields(from_transition: &FlowTransitionDetailResp, to_transition: &FlowTransitionDetailResp) -> TardisResult<Vec<String>> {
+        let from_fields = TardisFuns::json.obj_to_json(from_transition)?;
+        let to_fields = TardisFuns::json.obj_to_json(to_transition)?;
+        let (Value::Object(from_fields), Value::Object(to_fields)) = (from_fields, to_fields) else {
+            return Ok(vec![]);
+        };
+        Ok(from_fields
+            .iter()
+            .filter(|(field, _)| !DERIVED_TRANSITION_FIELDS.contains(&field.as_str()))
+            .filter(|(field, value)| to_fields.get(*field) != Some(*value))
+            .map(|(field, _)| field.clone())
+            .sorted()
+            .collect())
+    }
+
+    /// Migrate the unfinished instances pinned to the source version to the target version
+    ///
+    /// 将固定于源版本的未结束实例迁移至目标版本
+    ///
+    /// The current state of each instance is mapped by ``state_mapping``, or kept if it still exists in the target version.
+    /// Nothing is changed in the dry run, or if there is any instance whose state can not be mapped.
+    /// 各实例的当前状态按 ``state_mapping`` 映射，目标版本中仍存在的状态保持不变。试运行或存在无法映射状态的实例时不做任何修改。
+    pub async fn migrate(flow_model_id: &str, migrate_req: &FlowModelVersionMigrateReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<FlowModelVersionMigrateResp> {
+        let to_version = Self::get_version(&migrate_req.to_version_id, funs).await?;
+        if to_version.rel_flow_model_id != flow_model_id {
+            return Err(funs.err().bad_request(
+                "flow_model_version",
+                "migrate",
+                "the version does not belong to the model",
+                "400-flow-model-version-not-match",
+            ));
+        }
+        let to_state_ids = to_version.states().into_iter().map(|state| state.id).collect::<HashSet<_>>();
+        if let Some((_, to_state_id)) = migrate_req.state_mapping.iter().find(|(_, to_state_id)| !to_state_ids.contains(*to_state_id)) {
+            return Err(funs.err().bad_request(
+                "flow_model_version",
+                "migrate",
+                &format!("the state {} does not exist in the target version", to_state_id),
+                "400-flow-model-version-state-not-found",
+            ));
+        }
+
+        #[derive(sea_orm::FromQueryResult)]
+        struct FlowInstMigrateResult {
+            id: String,
+            rel_business_obj_id: String,
+            current_state_id: String,
+        }
+        let mut query = Query::select();
+        query
+            .columns([flow_inst::Column::Id, flow_inst::Column::RelBusinessObjId, flow_inst::Column::CurrentStateId])
+            .from(flow_inst::Entity)
+            .and_where(Expr::col(flow_inst::Column::RelFlowModelId).eq(flow_model_id))
+            .and_where(Expr::col(flow_inst::Column::FinishTime).is_null())
+            .and_where(Expr::col(flow_inst::Column::OwnPaths).like(format!("{}%", ctx.own_paths)));
+        if let Some(from_version_id) = &migrate_req.from_version_id {
+            query.and_where(Expr::col(flow_inst::Column::RelFlowModelVersionId).eq(from_version_id));
+        } else {
+            query.and_where(Expr::col(flow_inst::Column::RelFlowModelVersionId).is_null());
+        }
+        let flow_insts = funs.db().find_dtos::<FlowInstMigrateResult>(&query).await?;
+
+        let (migrated, unmapped): (Vec<_>, Vec<_>) = flow_insts
+            .into_iter()
+            .map(|flow_inst| {
+                let to_state_id = migrate_req
+                    .state_mapping
+                    .get(&flow_inst.current_state_id)
+                    .cloned()
+                    .or_else(|| to_state_ids.contains(&flow_inst.current_state_id).then(|| flow_inst.current_state_id.clone()));
+                FlowModelVersionMigrateInstResp {
+                    flow_inst_id: flow_inst.id,
+                    rel_business_obj_id: flow_inst.rel_business_obj_id,
+                    from_state_id: flow_inst.current_state_id,
+                    to_state_id,
+                }
+            })
+            .partition(|flow_inst| flow_inst.to_state_id.is_some());
+        if migrate_req.dry_run || !unmapped.is_empty() {
+            return Ok(FlowModelVersionMigrateResp {
+                dry_run: migrate_req.dry_run,
+                migrated,
+                unmapped,
+            });
+        }
+
+        let global_ctx = TardisContext {
+            own_paths: "".to_string(),
+            ..ctx.clone()
+        };
+        let to_transitions = to_version.transitions();
+        let to_model_states = to_version.states();
+        let mut to_states = HashMap::new();
+        for flow_inst in &migrated {
+            let to_state_id = flow_inst.to_state_id.clone().unwrap_or_default();
+            funs.db()
+                .update_one(
+                    flow_inst::ActiveModel {
+                        id: Set(flow_inst.flow_inst_id.clone()),
+                        rel_flow_model_version_id: Set(Some(to_version.id.clone())),
+                        current_state_id: Set(to_state_id.clone()),
+                        ..Default::default()
+                    },
+                    ctx,
+                )
+                .await?;
+            if flow_inst.from_state_id != to_state_id {
+                FlowInstApprovalServ::archive(&flow_inst.flow_inst_id, funs).await?;
+            }
+            if !to_states.contains_key(&to_state_id) {
+                let to_state = FlowStateServ::get_item(
+                    &to_state_id,
+                    &FlowStateFilterReq {
+                        basic: RbumBasicFilterReq {
+                            with_sub_own_paths: true,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    funs,
+                    &global_ctx,
+                )
+                .await?;
+                to_states.insert(to_state_id.clone(), to_state);
+            }
+            FlowInstTimerServ::reset(&flow_inst.flow_inst_id, &to_transitions, &to_states[&to_state_id], funs, ctx).await?;
+            FlowInstSlaServ::enter(&flow_inst.flow_inst_id, flow_model_id, &to_model_states, &to_state_id, None, funs, ctx).await?;
+        }
+        Ok(FlowModelVersionMigrateResp {
+            dry_run: false,
+            migrated,
+            unmapped,
+        })
+    }
+
+    pub(crate) async fn get_model(flow_model_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<FlowModelDetailResp> {
+        FlowModelServ::get_item(
+            flow_model_id,
+            &FlowModelFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    own_paths: Some("".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+}
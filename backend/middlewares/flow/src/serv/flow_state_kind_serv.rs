@@ -0,0 +1,425 @@
+use std::{collections::HashMap, time::Duration};
+
+use bios_basic::rbum::{dto::rbum_filer_dto::RbumBasicFilterReq, serv::rbum_item_serv::RbumItemCrudOperation};
+use evalexpr::{ContextWithMutableVariables, HashMapContext, IterateVariablesContext, Value as ExprValue};
+use itertools::Itertools;
+use serde::de::DeserializeOwned;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::Utc,
+    log::{trace, warn},
+    serde_json::{json, Number, Value},
+    tokio::{self, time},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::{
+        flow_external_dto::FlowExternalCallbackOp,
+        flow_inst_dto::{FlowInstDetailResp, FlowInstTransferReq},
+        flow_state_dto::{
+            FlowStateApprovalConf, FlowStateApprovalQuorumKind, FlowStateCallbackConf, FlowStateDetailResp, FlowStateFilterReq, FlowStateFormConf, FlowStateKind,
+            FlowStateMailConf, FlowStateScriptConf, FlowStateTimerConf,
+        },
+    },
+    flow_config::FlowConfig,
+    flow_constants,
+    helper::loop_check_helper,
+};
+
+use super::{
+    clients::reach_client::{FlowReachClient, ReachMsgReceive, ReachMsgSendReq},
+    flow_inst_approval_serv::FlowInstApprovalServ,
+    flow_inst_serv::FlowInstServ,
+    flow_model_version_serv::FlowModelVersionServ,
+    flow_state_serv::FlowStateServ,
+};
+
+/// Predefined behaviors of the state kinds, configured by ``kind_conf``
+///
+/// 状态类型的预定义行为，由 ``kind_conf`` 配置
+pub struct FlowStateKindServ;
+
+impl FlowStateKindServ {
+    /// Validate the ``kind_conf`` against the schema of the state kind
+    ///
+    /// 按状态类型校验 ``kind_conf``
+    pub fn validate_kind_conf(state_kind: &FlowStateKind, kind_conf: &Value, funs: &TardisFunsInst) -> TardisResult<()> {
+        let invalid = |msg: &str| {
+            funs.err().bad_request(
+                "flow_state",
+                "validate_kind_conf",
+                &format!("kind_conf of {state_kind:?} is invalid: {msg}"),
+                "400-flow-state-kind-conf-invalid",
+            )
+        };
+        match state_kind {
+            FlowStateKind::Simple => {}
+            FlowStateKind::Mail => {
+                let conf: FlowStateMailConf = Self::parse_conf(kind_conf, funs)?;
+                if conf.scene_code.trim().is_empty() {
+                    return Err(invalid("scene_code is required"));
+                }
+                if conf.receives.is_empty() {
+                    return Err(invalid("receives is required"));
+                }
+            }
+            FlowStateKind::Callback => {
+                let conf: FlowStateCallbackConf = Self::parse_conf(kind_conf, funs)?;
+                if !conf.url.starts_with("http://") && !conf.url.starts_with("https://") {
+                    return Err(invalid("url must be a http(s) url"));
+                }
+            }
+            FlowStateKind::Timer => {
+                let conf: FlowStateTimerConf = Self::parse_conf(kind_conf, funs)?;
+                if conf.delay_sec <= 0 {
+                    return Err(invalid("delay_sec must be greater than 0"));
+                }
+                if conf.to_flow_state_id.trim().is_empty() {
+                    return Err(invalid("to_flow_state_id is required"));
+                }
+            }
+            FlowStateKind::Script => {
+                let conf: FlowStateScriptConf = Self::parse_conf(kind_conf, funs)?;
+                if conf.script.trim().is_empty() {
+                    return Err(invalid("script is required"));
+                }
+                evalexpr::build_operator_tree(&conf.script).map_err(|e| invalid(&e.to_string()))?;
+            }
+            FlowStateKind::Form => {
+                let conf: FlowStateFormConf = Self::parse_conf(kind_conf, funs)?;
+                if conf.fields.is_empty() || conf.fields.iter().any(|field| field.name.trim().is_empty()) {
+                    return Err(invalid("fields and their names are required"));
+                }
+            }
+            FlowStateKind::Approval => {
+                let conf: FlowStateApprovalConf = Self::parse_conf(kind_conf, funs)?;
+                if conf.groups.is_empty() {
+                    return Err(invalid("groups is required"));
+                }
+                if conf.groups.iter().any(|group| group.code.trim().is_empty()) || conf.groups.iter().map(|group| &group.code).unique().count() != conf.groups.len() {
+                    return Err(invalid("the codes of the groups are required and must be unique"));
+                }
+                if conf.groups.iter().any(|group| group.approver_ids.is_empty() && group.approver_var.is_none()) {
+                    return Err(invalid("approver_ids or approver_var of the groups is required"));
+                }
+                if conf.groups.iter().any(|group| group.quorum == FlowStateApprovalQuorumKind::AtLeast && group.quorum_count.unwrap_or(0) == 0) {
+                    return Err(invalid("quorum_count must be greater than 0"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_conf<T: DeserializeOwned>(kind_conf: &Value, funs: &TardisFunsInst) -> TardisResult<T> {
+        TardisFuns::json.json_to_obj(kind_conf.clone()).map_err(|e| {
+            funs.err().bad_request(
+                "flow_state",
+                "validate_kind_conf",
+                &format!("kind_conf does not match the state kind: {}", e.message),
+                "400-flow-state-kind-conf-invalid",
+            )
+        })
+    }
+
+    /// The timer configuration if the state is a timer state
+    ///
+    /// 定时状态的配置
+    pub fn timer_conf(state: &FlowStateDetailResp) -> Option<FlowStateTimerConf> {
+        if state.state_kind == FlowStateKind::Timer {
+            TardisFuns::json.json_to_obj(state.kind_conf.clone()).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Check whether the fields declared by the form state have been collected
+    ///
+    /// 检查表单状态声明的字段是否已收集
+    pub fn check_form_collected(state: &FlowStateDetailResp, vars: &HashMap<String, Value>, funs: &TardisFunsInst) -> TardisResult<()> {
+        if state.state_kind != FlowStateKind::Form {
+            return Ok(());
+        }
+        let conf: FlowStateFormConf = Self::parse_conf(&state.kind_conf, funs)?;
+        let missing_fields = conf
+            .fields
+            .iter()
+            .filter(|field| field.required)
+            .filter(|field| match vars.get(&field.name) {
+                None | Some(Value::Null) => true,
+                Some(Value::String(value)) => value.is_empty(),
+                Some(Value::Array(value)) => value.is_empty(),
+                _ => false,
+            })
+            .map(|field| field.label.clone().unwrap_or_else(|| field.name.clone()))
+            .collect::<Vec<_>>();
+        if !missing_fields.is_empty() {
+            return Err(funs.err().bad_request(
+                "flow_inst",
+                "transfer",
+                &format!("the form fields [{}] have not been collected", missing_fields.join(",")),
+                "400-flow-inst-form-field-missing",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Execute the behavior of the state after the transaction is committed
+    ///
+    /// 在事务提交后执行状态的行为
+    pub async fn async_on_enter(flow_inst_id: &str, ctx: &TardisContext) -> TardisResult<()> {
+        let flow_inst_id = flow_inst_id.to_string();
+        let ctx_clone = ctx.clone();
+        ctx.add_async_task(Box::new(|| {
+            Box::pin(async move {
+                tokio::spawn(async move {
+                    if let Err(e) = Self::on_enter(&flow_inst_id, &ctx_clone).await {
+                        warn!("[Flow.Inst] execute state behavior of instance [{}] error: {:?}", flow_inst_id, e);
+                    }
+                });
+                Ok(())
+            })
+        }))
+        .await
+    }
+
+    /// Execute the behavior of the state the instance has entered
+    ///
+    /// 执行实例所进入状态的行为
+    ///
+    /// The timer state is handled by the timers of the instance, and the form state takes effect when transferring.
+    /// The approval state is executed again whenever an approver decides.
+    /// 定时状态由实例定时器处理，表单状态在流转时生效。会签状态在每次审批后再次执行。
+    pub async fn on_enter(flow_inst_id: &str, ctx: &TardisContext) -> TardisResult<()> {
+        let funs = flow_constants::get_tardis_inst();
+        let flow_inst = FlowInstServ::get(flow_inst_id, &funs, ctx).await?;
+        if flow_inst.finish_time.is_some() {
+            return Ok(());
+        }
+        let state = FlowStateServ::get_item(
+            &flow_inst.current_state_id,
+            &FlowStateFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            &funs,
+            &TardisContext {
+                own_paths: "".to_string(),
+                ..ctx.clone()
+            },
+        )
+        .await?;
+        trace!("[Flow.Inst] execute {:?} state behavior of instance [{}]", state.state_kind, flow_inst_id);
+        match state.state_kind {
+            FlowStateKind::Mail => Self::send_mail(&flow_inst, &state, &funs, ctx).await,
+            FlowStateKind::Callback => Self::do_callback(&flow_inst, &state, &funs, ctx).await,
+            FlowStateKind::Script => Self::run_script(&flow_inst, &state, &funs, ctx).await,
+            FlowStateKind::Approval => FlowInstApprovalServ::settle(&flow_inst, &state, &funs, ctx).await,
+            FlowStateKind::Simple | FlowStateKind::Form | FlowStateKind::Timer => Ok(()),
+        }
+    }
+
+    async fn send_mail(flow_inst: &FlowInstDetailResp, state: &FlowStateDetailResp, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let conf: FlowStateMailConf = Self::parse_conf(&state.kind_conf, funs)?;
+        Self::send_reach_message(flow_inst, conf, &state.name, HashMap::new(), funs, ctx).await
+    }
+
+    /// Send the message configured by the mail conf through reach
+    ///
+    /// 通过 reach 发送邮件（消息）配置的消息
+    ///
+    /// The ``{var_name}`` placeholders are rendered with the vars of the instance, the built-in vars and the extra vars.
+    /// ``{变量名}`` 占位符以实例变量、内置变量及额外变量填充。
+    pub(crate) async fn send_reach_message(
+        flow_inst: &FlowInstDetailResp,
+        conf: FlowStateMailConf,
+        state_name: &str,
+        extra_vars: HashMap<String, Value>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<()> {
+        let mut vars = flow_inst.current_vars.clone().unwrap_or_default();
+        vars.insert("inst_id".to_string(), json!(flow_inst.id));
+        vars.insert("rel_business_obj_id".to_string(), json!(flow_inst.rel_business_obj_id));
+        vars.insert("state_name".to_string(), json!(state_name));
+        vars.extend(extra_vars);
+        let receives = conf
+            .receives
+            .into_iter()
+            .map(|receive| {
+                let mut receive_ids = receive.receive_ids;
+                for var_name in &receive.receive_id_vars {
+                    if let Some(Value::String(ids)) = vars.get(var_name) {
+                        receive_ids.extend(ids.split(',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()));
+                    }
+                }
+                ReachMsgReceive {
+                    receive_group_code: receive.receive_group_code,
+                    receive_kind: receive.receive_kind,
+                    receive_ids,
+                }
+            })
+            .collect();
+        let replace = conf.replace.into_iter().map(|(key, template)| (key, Self::render(&template, &vars))).collect();
+        FlowReachClient::send_message(
+            &ReachMsgSendReq {
+                scene_code: conf.scene_code,
+                receives,
+                rel_item_id: flow_inst.rel_business_obj_id.clone(),
+                replace,
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    /// Replace the ``{var_name}`` placeholders with the values of the vars
+    fn render(template: &str, vars: &HashMap<String, Value>) -> String {
+        vars.iter().fold(template.to_string(), |content, (var_name, value)| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Null => "".to_string(),
+                _ => value.to_string(),
+            };
+            content.replace(&format!("{{{var_name}}}"), &value)
+        })
+    }
+
+    async fn do_callback(flow_inst: &FlowInstDetailResp, state: &FlowStateDetailResp, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let conf: FlowStateCallbackConf = Self::parse_conf(&state.kind_conf, funs)?;
+        let body = json!({
+            "inst_id": flow_inst.id,
+            "rel_business_obj_id": flow_inst.rel_business_obj_id,
+            "state_id": state.id,
+            "state_name": state.name,
+            "vars": flow_inst.current_vars.clone().unwrap_or_default(),
+        });
+        let body = TardisFuns::json.obj_to_string(&body)?;
+        // The caller context is never forwarded to the (user configured) url, the receiver verifies the request by the signature instead
+        let mut headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+        let sign_key = &funs.conf::<FlowConfig>().callback_sign_key;
+        if !sign_key.is_empty() {
+            let timestamp = Utc::now().timestamp().to_string();
+            let signature = TardisFuns::crypto.base64.encode(TardisFuns::crypto.digest.hmac_sha256(format!("{}\n{}\n{}", timestamp, ctx.own_paths, body), sign_key)?);
+            headers.push(("X-Flow-Timestamp".to_string(), timestamp));
+            headers.push(("X-Flow-Own-Paths".to_string(), ctx.own_paths.clone()));
+            headers.push(("X-Flow-Signature".to_string(), signature));
+        }
+        let mut resp_body = None;
+        for attempt in 0..=conf.retry_times {
+            if attempt > 0 {
+                time::sleep(Duration::from_secs(conf.retry_interval_sec as u64)).await;
+            }
+            match funs.web_client().post_str_to_str(&conf.url, &body, headers.clone()).await {
+                Ok(resp) if (200..300).contains(&resp.code) => {
+                    resp_body = Some(resp.body.unwrap_or_default());
+                    break;
+                }
+                Ok(resp) => warn!("[Flow.Inst] callback {} of instance [{}] failed with status {}", conf.url, flow_inst.id, resp.code),
+                Err(e) => warn!("[Flow.Inst] callback {} of instance [{}] error: {:?}", conf.url, flow_inst.id, e),
+            }
+        }
+        let Some(resp_body) = resp_body else {
+            if let Some(fail_to_flow_state_id) = &conf.fail_to_flow_state_id {
+                Self::transfer_to(flow_inst, state, fail_to_flow_state_id, funs, ctx).await?;
+            }
+            return Ok(());
+        };
+        if let Ok(Value::Object(resp)) = TardisFuns::json.str_to_obj::<Value>(&resp_body) {
+            if let Some(Value::Object(vars)) = resp.get("vars") {
+                FlowInstServ::modify_current_vars(&flow_inst.id, &vars.clone().into_iter().collect(), loop_check_helper::InstancesTransition::default(), ctx).await?;
+            }
+        }
+        if let Some(success_to_flow_state_id) = &conf.success_to_flow_state_id {
+            Self::transfer_to(flow_inst, state, success_to_flow_state_id, funs, ctx).await?;
+        }
+        Ok(())
+    }
+
+    async fn run_script(flow_inst: &FlowInstDetailResp, state: &FlowStateDetailResp, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let conf: FlowStateScriptConf = Self::parse_conf(&state.kind_conf, funs)?;
+        let vars = flow_inst.current_vars.clone().unwrap_or_default();
+        let changed_vars =
+            Self::eval_script(&conf.script, &vars).map_err(|e| funs.err().bad_request("flow_inst", "run_script", &format!("script error: {e}"), "400-flow-inst-script-error"))?;
+        if !changed_vars.is_empty() {
+            FlowInstServ::modify_current_vars(&flow_inst.id, &changed_vars, loop_check_helper::InstancesTransition::default(), ctx).await?;
+        }
+        if let Some(to_flow_state_id) = &conf.to_flow_state_id {
+            Self::transfer_to(flow_inst, state, to_flow_state_id, funs, ctx).await?;
+        }
+        Ok(())
+    }
+
+    /// Evaluate the script with the vars and return the vars which are added or changed by the script
+    ///
+    /// The script has no access to anything other than the vars, and there is no loop in the expression language, so it always terminates.
+    fn eval_script(script: &str, vars: &HashMap<String, Value>) -> Result<HashMap<String, Value>, String> {
+        let mut context = HashMapContext::new();
+        for (var_name, value) in vars {
+            if let Some(value) = Self::json_to_expr_value(value) {
+                context.set_value(var_name.to_string(), value).map_err(|e| e.to_string())?;
+            }
+        }
+        evalexpr::eval_with_context_mut(script, &mut context).map_err(|e| e.to_string())?;
+        Ok(context.iter_variables().map(|(var_name, value)| (var_name, Self::expr_value_to_json(&value))).filter(|(var_name, value)| vars.get(var_name) != Some(value)).collect())
+    }
+
+    fn json_to_expr_value(value: &Value) -> Option<ExprValue> {
+        match value {
+            Value::Bool(value) => Some(ExprValue::Boolean(*value)),
+            Value::Number(value) => value.as_i64().map(ExprValue::Int).or_else(|| value.as_f64().map(ExprValue::Float)),
+            Value::String(value) => Some(ExprValue::String(value.clone())),
+            Value::Array(values) => values.iter().map(Self::json_to_expr_value).collect::<Option<Vec<_>>>().map(ExprValue::Tuple),
+            Value::Null | Value::Object(_) => None,
+        }
+    }
+
+    fn expr_value_to_json(value: &ExprValue) -> Value {
+        match value {
+            ExprValue::Boolean(value) => Value::Bool(*value),
+            ExprValue::Int(value) => json!(value),
+            ExprValue::Float(value) => Number::from_f64(*value).map(Value::Number).unwrap_or(Value::Null),
+            ExprValue::String(value) => Value::String(value.clone()),
+            ExprValue::Tuple(values) => Value::Array(values.iter().map(Self::expr_value_to_json).collect()),
+            ExprValue::Empty => Value::Null,
+        }
+    }
+
+    /// Fire the transition of the model from the state to the target state
+    pub(crate) async fn transfer_to(
+        flow_inst: &FlowInstDetailResp,
+        state: &FlowStateDetailResp,
+        to_flow_state_id: &str,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<()> {
+        let flow_model = FlowModelVersionServ::get_inst_model(flow_inst, funs, ctx).await?;
+        let Some(model_transition) =
+            flow_model.transitions().into_iter().find(|model_transition| model_transition.from_flow_state_id == state.id && model_transition.to_flow_state_id == to_flow_state_id)
+        else {
+            warn!(
+                "[Flow.Inst] no transition from state [{}] to state [{}] in model [{}]",
+                state.id, to_flow_state_id, flow_model.id
+            );
+            return Ok(());
+        };
+        FlowInstServ::transfer(
+            &flow_inst.id,
+            &FlowInstTransferReq {
+                flow_transition_id: model_transition.id,
+                message: None,
+                vars: None,
+            },
+            true,
+            FlowExternalCallbackOp::Default,
+            loop_check_helper::InstancesTransition::default(),
+            ctx,
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -35,6 +35,7 @@ use super::{
     flow_inst_serv::FlowInstServ,
     flow_model_serv::FlowModelServ,
     flow_rel_serv::{FlowRelKind, FlowRelServ},
+    flow_state_kind_serv::FlowStateKindServ,
 };
 
 pub struct FlowStateServ;
@@ -84,6 +85,14 @@ impl RbumItemCrudOperation<flow_state::ActiveModel, FlowStateAddReq, FlowStateMo
         })
     }
 
+    async fn before_add_item(add_req: &mut FlowStateAddReq, funs: &TardisFunsInst, _: &TardisContext) -> TardisResult<()> {
+        FlowStateKindServ::validate_kind_conf(
+            add_req.state_kind.as_ref().unwrap_or(&FlowStateKind::Simple),
+            add_req.kind_conf.as_ref().unwrap_or(&json!({})),
+            funs,
+        )
+    }
+
     async fn after_add_item(id: &str, add_req: &mut FlowStateAddReq, _funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
         FlowLogClient::add_ctx_task(
             LogParamTag::DynamicLog,
@@ -104,7 +113,15 @@ impl RbumItemCrudOperation<flow_state::ActiveModel, FlowStateAddReq, FlowStateMo
         Ok(())
     }
 
-    async fn before_modify_item(_id: &str, _modify_req: &mut FlowStateModifyReq, _funs: &TardisFunsInst, _ctx: &TardisContext) -> TardisResult<()> {
+    async fn before_modify_item(id: &str, modify_req: &mut FlowStateModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        if modify_req.state_kind.is_some() || modify_req.kind_conf.is_some() {
+            let state = Self::get_item(id, &FlowStateFilterReq::default(), funs, ctx).await?;
+            FlowStateKindServ::validate_kind_conf(
+                modify_req.state_kind.as_ref().unwrap_or(&state.state_kind),
+                modify_req.kind_conf.as_ref().unwrap_or(&state.kind_conf),
+                funs,
+            )?;
+        }
         // Modifications are allowed only where non-key fields are modified or not used
         // if (modify_req.scope_level.is_some()
         //     || modify_req.disabled.is_some()
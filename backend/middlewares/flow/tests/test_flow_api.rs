@@ -18,7 +18,14 @@ use tardis::web::web_resp::Void;
 use tardis::{testcontainers, tokio, TardisFuns};
 
 mod mock_api;
+mod test_flow_scenes_approval;
+mod test_flow_scenes_exchange;
 mod test_flow_scenes_fsm1;
+mod test_flow_scenes_simulate;
+mod test_flow_scenes_sla;
+mod test_flow_scenes_state_kind;
+mod test_flow_scenes_timer;
+mod test_flow_scenes_version;
 
 #[tokio::test]
 async fn test_flow_api() -> TardisResult<()> {
@@ -45,6 +52,13 @@ async fn test_flow_api() -> TardisResult<()> {
     init_spi_search().await?;
 
     test_flow_scenes_fsm1::test(&mut flow_client, &mut search_client).await?;
+    test_flow_scenes_timer::test(&mut flow_client).await?;
+    test_flow_scenes_state_kind::test(&mut flow_client).await?;
+    test_flow_scenes_approval::test(&mut flow_client).await?;
+    test_flow_scenes_version::test(&mut flow_client).await?;
+    test_flow_scenes_exchange::test(&mut flow_client).await?;
+    test_flow_scenes_simulate::test(&mut flow_client).await?;
+    test_flow_scenes_sla::test(&mut flow_client).await?;
     truncate_flow_data().await?;
 
     Ok(())
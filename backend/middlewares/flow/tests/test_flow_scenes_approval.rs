@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use bios_basic::dto::BasicQueryCondInfo;
+use bios_basic::enumeration::BasicQueryOpKind;
+use bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_mw_flow::dto::flow_inst_dto::{
+    FlowInstApprovalDecision, FlowInstApprovalResp, FlowInstApprovalStatus, FlowInstApproveReq, FlowInstDetailResp, FlowInstFindNextTransitionResp, FlowInstFindNextTransitionsReq,
+    FlowInstModifyAssignedReq, FlowInstStartReq,
+};
+use bios_mw_flow::dto::flow_model_dto::{FlowModelAddReq, FlowModelAggResp, FlowModelBindStateReq, FlowModelModifyReq};
+use bios_mw_flow::dto::flow_state_dto::{FlowStateAddReq, FlowStateKind, FlowStateRelModelExt, FlowStateSummaryResp, FlowSysStateKind};
+use bios_mw_flow::dto::flow_transition_dto::FlowTransitionAddReq;
+use serde_json::json;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisPage, Void};
+use tardis::TardisFuns;
+
+pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_flow_scenes_approval】");
+    let mut ctx = TardisContext {
+        own_paths: "t_approval".to_string(),
+        ak: "u001".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "u001".to_string(),
+        ..Default::default()
+    };
+    flow_client.set_auth(&ctx)?;
+
+    let req_states: TardisPage<FlowStateSummaryResp> = flow_client.get("/cc/state?tag=REQ&enabled=true&page_number=1&page_size=100").await;
+    let processing_state_id = req_states.records[1].id.clone(); // 进行中
+    let closed_state_id = req_states.records[3].id.clone(); // 已关闭
+                                                            // the dev group needs any one of its approvers, the security group needs at least 2 of its approvers
+    let approval_state_id: String = flow_client
+        .post(
+            "/cc/state",
+            &FlowStateAddReq {
+                name: Some("变更审批".into()),
+                sys_state: FlowSysStateKind::Start,
+                state_kind: Some(FlowStateKind::Approval),
+                kind_conf: Some(json!({
+                    "groups": [
+                        {"code": "dev", "approver_var": "current_assigned", "quorum": "Any"},
+                        {"code": "sec", "approver_ids": ["s001", "s002", "s003"], "quorum": "AtLeast", "quorum_count": 2}
+                    ],
+                    "pass_to_flow_state_id": processing_state_id,
+                    "reject_to_flow_state_id": closed_state_id
+                })),
+                ..Default::default()
+            },
+        )
+        .await;
+    let model: FlowModelAggResp = flow_client
+        .post(
+            "/cc/model",
+            &FlowModelAddReq {
+                name: "会签模型".into(),
+                info: None,
+                init_state_id: "".to_string(),
+                rel_template_ids: None,
+                template: false,
+                tag: Some("REQ".to_string()),
+                scope_level: Some(RbumScopeLevelKind::Private),
+                icon: None,
+                transitions: None,
+                states: None,
+                rel_model_id: None,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = flow_client
+        .patch(
+            &format!("/cc/model/{}", model.id),
+            &FlowModelModifyReq {
+                init_state_id: Some(approval_state_id.clone()),
+                bind_states: Some(vec![
+                    FlowModelBindStateReq {
+                        state_id: approval_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: processing_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 2, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: closed_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 3, ..Default::default() },
+                    },
+                ]),
+                add_transitions: Some(vec![
+                    FlowTransitionAddReq {
+                        from_flow_state_id: approval_state_id.clone(),
+                        to_flow_state_id: processing_state_id.clone(),
+                        name: Some("通过".into()),
+                        guard_by_other_conds: Some(vec![vec![BasicQueryCondInfo {
+                            field: "approval_status".to_string(),
+                            op: BasicQueryOpKind::Eq,
+                            value: json!("approved"),
+                        }]]),
+                        ..Default::default()
+                    },
+                    FlowTransitionAddReq {
+                        from_flow_state_id: approval_state_id.clone(),
+                        to_flow_state_id: closed_state_id.clone(),
+                        name: Some("驳回".into()),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            },
+        )
+        .await;
+
+    let start = |rel_business_obj_id: String| FlowInstStartReq {
+        tag: "REQ".to_string(),
+        create_vars: None,
+        rel_business_obj_id,
+    };
+    let passed_inst_id: String = flow_client.post("/cc/inst", &start(TardisFuns::field.nanoid())).await;
+    let rejected_inst_id: String = flow_client.post("/cc/inst", &start(TardisFuns::field.nanoid())).await;
+    for inst_id in [&passed_inst_id, &rejected_inst_id] {
+        let _: Void = flow_client
+            .post(
+                &format!("/cc/inst/{}/transition/modify_assigned", inst_id),
+                &FlowInstModifyAssignedReq {
+                    current_assigned: "d001,d002".to_string(),
+                },
+            )
+            .await;
+    }
+    let inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", passed_inst_id)).await;
+    let approval = inst.approval.unwrap();
+    assert_eq!(approval.status, FlowInstApprovalStatus::Pending);
+    assert_eq!(approval.groups[0].approver_ids, vec!["d001".to_string(), "d002".to_string()]);
+    assert_eq!(approval.groups[1].required_count, 2);
+
+    let approve = |decision: FlowInstApprovalDecision| FlowInstApproveReq {
+        decision,
+        group_code: None,
+        message: None,
+    };
+    // not an approver
+    let resp = flow_client.put_resp::<FlowInstApproveReq, FlowInstApprovalResp>(&format!("/cc/inst/{}/approve", passed_inst_id), &approve(FlowInstApprovalDecision::Approve)).await;
+    assert_eq!(resp.code, "401-flow-flow_inst-approve");
+
+    for (approver_id, decision, status) in [
+        ("d002", FlowInstApprovalDecision::Approve, FlowInstApprovalStatus::Pending),
+        ("s001", FlowInstApprovalDecision::Approve, FlowInstApprovalStatus::Pending),
+        ("s002", FlowInstApprovalDecision::Reject, FlowInstApprovalStatus::Pending),
+    ] {
+        ctx.owner = approver_id.to_string();
+        flow_client.set_auth(&ctx)?;
+        let approval: FlowInstApprovalResp = flow_client.put(&format!("/cc/inst/{}/approve", passed_inst_id), &approve(decision)).await;
+        assert_eq!(approval.status, status);
+    }
+    let resp = flow_client.put_resp::<FlowInstApproveReq, FlowInstApprovalResp>(&format!("/cc/inst/{}/approve", passed_inst_id), &approve(FlowInstApprovalDecision::Approve)).await;
+    assert_eq!(resp.code, "409-flow-flow_inst-approve");
+    // the guard references the approval progress
+    let next_transitions: Vec<FlowInstFindNextTransitionResp> =
+        flow_client.put(&format!("/cc/inst/{}/transition/next", passed_inst_id), &FlowInstFindNextTransitionsReq { vars: None }).await;
+    assert!(next_transitions.iter().all(|transition| transition.next_flow_state_id != processing_state_id));
+    let inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", passed_inst_id)).await;
+    let approval = inst.approval.unwrap();
+    assert_eq!(approval.groups[0].status, FlowInstApprovalStatus::Approved);
+    assert_eq!(approval.groups[1].status, FlowInstApprovalStatus::Pending);
+    assert_eq!(approval.records.len(), 3);
+
+    ctx.owner = "s003".to_string();
+    flow_client.set_auth(&ctx)?;
+    let approval: FlowInstApprovalResp = flow_client.put(&format!("/cc/inst/{}/approve", passed_inst_id), &approve(FlowInstApprovalDecision::Approve)).await;
+    assert_eq!(approval.status, FlowInstApprovalStatus::Approved);
+    sleep(Duration::from_millis(1000)).await;
+    let inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", passed_inst_id)).await;
+    assert_eq!(inst.current_state_id, processing_state_id);
+    assert!(inst.approval.is_none());
+
+    // the security group can no longer reach the quorum
+    for approver_id in ["s001", "s002"] {
+        ctx.owner = approver_id.to_string();
+        flow_client.set_auth(&ctx)?;
+        let _: FlowInstApprovalResp = flow_client.put(&format!("/cc/inst/{}/approve", rejected_inst_id), &approve(FlowInstApprovalDecision::Reject)).await;
+    }
+    sleep(Duration::from_millis(1000)).await;
+    let inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", rejected_inst_id)).await;
+    assert_eq!(inst.current_state_id, closed_state_id);
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use bios_basic::dto::BasicQueryCondInfo;
+use bios_basic::enumeration::BasicQueryOpKind;
+use bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_mw_flow::dto::flow_model_dto::{FlowModelAddReq, FlowModelAggResp, FlowModelBindStateReq, FlowModelModifyReq};
+use bios_mw_flow::dto::flow_model_exchange_dto::{FlowModelExchangeDoc, FlowModelExchangeFormat, FlowModelImportErrorResp, FlowModelImportReq, FlowModelImportResp};
+use bios_mw_flow::dto::flow_state_dto::{FlowStateAddReq, FlowStateDetailResp, FlowStateKind, FlowStateRelModelExt, FlowStateSummaryResp, FlowSysStateKind};
+use bios_mw_flow::dto::flow_transition_dto::FlowTransitionAddReq;
+use serde_json::json;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::web::web_resp::{TardisPage, Void};
+use tardis::TardisFuns;
+
+pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_flow_scenes_exchange】");
+    let mut ctx = TardisContext {
+        own_paths: "t_exchange".to_string(),
+        ak: "u001".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "u001".to_string(),
+        ..Default::default()
+    };
+    flow_client.set_auth(&ctx)?;
+
+    let req_states: TardisPage<FlowStateSummaryResp> = flow_client.get("/cc/state?tag=REQ&enabled=true&page_number=1&page_size=100").await;
+    let processing_state_id = req_states.records[1].id.clone(); // 进行中
+    let closed_state_id = req_states.records[3].id.clone(); // 已关闭
+    let waiting_state_id: String = flow_client
+        .post(
+            "/cc/state",
+            &FlowStateAddReq {
+                name: Some("等待处理".into()),
+                sys_state: FlowSysStateKind::Start,
+                state_kind: Some(FlowStateKind::Timer),
+                kind_conf: Some(json!({"delay_sec": 3600, "to_flow_state_id": closed_state_id})),
+                scope_level: Some(RbumScopeLevelKind::Private),
+                ..Default::default()
+            },
+        )
+        .await;
+    let model: FlowModelAggResp = flow_client
+        .post(
+            "/cc/model",
+            &FlowModelAddReq {
+                name: "导出模型".into(),
+                info: Some("导出测试".to_string()),
+                init_state_id: "".to_string(),
+                rel_template_ids: None,
+                template: false,
+                tag: Some("REQ".to_string()),
+                scope_level: Some(RbumScopeLevelKind::Private),
+                icon: None,
+                transitions: None,
+                states: None,
+                rel_model_id: None,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = flow_client
+        .patch(
+            &format!("/cc/model/{}", model.id),
+            &FlowModelModifyReq {
+                init_state_id: Some(waiting_state_id.clone()),
+                bind_states: Some(vec![
+                    FlowModelBindStateReq {
+                        state_id: waiting_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: processing_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 2, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: closed_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 3, ..Default::default() },
+                    },
+                ]),
+                add_transitions: Some(vec![
+                    FlowTransitionAddReq {
+                        from_flow_state_id: waiting_state_id.clone(),
+                        to_flow_state_id: processing_state_id.clone(),
+                        name: Some("开始".into()),
+                        guard_by_other_conds: Some(vec![vec![BasicQueryCondInfo {
+                            field: "priority".to_string(),
+                            op: BasicQueryOpKind::Eq,
+                            value: json!("high"),
+                        }]]),
+                        ..Default::default()
+                    },
+                    FlowTransitionAddReq {
+                        from_flow_state_id: waiting_state_id.clone(),
+                        to_flow_state_id: closed_state_id.clone(),
+                        name: Some("超时关闭".into()),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            },
+        )
+        .await;
+
+    let content: String = flow_client.get(&format!("/cc/model/{}/export?format=Json", model.id)).await;
+    let doc: FlowModelExchangeDoc = TardisFuns::json.str_to_obj(&content)?;
+    assert_eq!(doc.init_state_key, waiting_state_id);
+    assert_eq!(
+        doc.states.iter().map(|state| state.key.clone()).collect::<Vec<_>>(),
+        vec![waiting_state_id.clone(), processing_state_id.clone(), closed_state_id.clone()]
+    );
+    assert_eq!(doc.states[0].state_kind, FlowStateKind::Timer);
+    assert_eq!(doc.transitions.len(), 2);
+
+    // import into another tenant, the private state is added and the shared states are reused
+    ctx.own_paths = "t_exchange2".to_string();
+    flow_client.set_auth(&ctx)?;
+    let import_resp: FlowModelImportResp = flow_client
+        .post(
+            "/cc/model/import",
+            &FlowModelImportReq {
+                format: FlowModelExchangeFormat::Json,
+                content,
+                name: Some("导入模型".into()),
+                template: None,
+                rel_template_ids: None,
+                scope_level: Some(RbumScopeLevelKind::Private),
+            },
+        )
+        .await;
+    assert!(import_resp.errors.is_empty());
+    assert_eq!(import_resp.state_mapping[&processing_state_id], processing_state_id);
+    assert_eq!(import_resp.state_mapping[&closed_state_id], closed_state_id);
+    let imported_waiting_state_id = import_resp.state_mapping[&waiting_state_id].clone();
+    assert_ne!(imported_waiting_state_id, waiting_state_id);
+    let imported_waiting_state: FlowStateDetailResp = flow_client.get(&format!("/cc/state/{}", imported_waiting_state_id)).await;
+    assert_eq!(imported_waiting_state.state_kind, FlowStateKind::Timer);
+    assert_eq!(imported_waiting_state.kind_conf["to_flow_state_id"], json!(closed_state_id));
+    let imported_model: FlowModelAggResp = flow_client.get(&format!("/cc/model/{}", import_resp.flow_model_id.unwrap())).await;
+    assert_eq!(imported_model.name, "导入模型");
+    assert_eq!(imported_model.init_state_id, imported_waiting_state_id);
+    let imported_waiting = imported_model.states.iter().find(|state| state.id == imported_waiting_state_id).unwrap();
+    assert_eq!(imported_waiting.transitions.len(), 2);
+    assert!(imported_waiting.transitions.iter().any(|transition| transition.name == "开始" && transition.guard_by_other_conds().is_some()));
+
+    // round trip through bpmn
+    ctx.own_paths = "t_exchange".to_string();
+    flow_client.set_auth(&ctx)?;
+    let content: String = flow_client.get(&format!("/cc/model/{}/export?format=Bpmn", model.id)).await;
+    assert!(content.contains("bpmn:definitions"));
+    let import_resp: FlowModelImportResp = flow_client
+        .post(
+            "/cc/model/import",
+            &FlowModelImportReq {
+                format: FlowModelExchangeFormat::Bpmn,
+                content,
+                name: None,
+                template: None,
+                rel_template_ids: None,
+                scope_level: None,
+            },
+        )
+        .await;
+    assert!(import_resp.errors.is_empty());
+    assert_eq!(import_resp.state_mapping[&waiting_state_id], waiting_state_id);
+    let imported_model: FlowModelAggResp = flow_client.get(&format!("/cc/model/{}", import_resp.flow_model_id.unwrap())).await;
+    assert_eq!(imported_model.name, "导出模型");
+    assert_eq!(imported_model.init_state_id, waiting_state_id);
+    assert_eq!(imported_model.states.iter().map(|state| state.transitions.len()).sum::<usize>(), 2);
+
+    // the documents of other tools are imported as simple states and transitions
+    let bpmn = r#"<?xml version="1.0" encoding="UTF-8"?>
+<definitions xmlns="http://www.omg.org/spec/BPMN/20100524/MODEL" id="d1" targetNamespace="http://example.com">
+  <process id="p1" name="外部流程">
+    <startEvent id="s1" />
+    <userTask id="review" name="外部评审" />
+    <endEvent id="done" name="外部归档" />
+    <sequenceFlow id="f0" sourceRef="s1" targetRef="review" />
+    <sequenceFlow id="f1" name="归档" sourceRef="review" targetRef="done" />
+  </process>
+</definitions>"#;
+    let import_resp: FlowModelImportResp = flow_client
+        .post(
+            "/cc/model/import",
+            &FlowModelImportReq {
+                format: FlowModelExchangeFormat::Bpmn,
+                content: bpmn.to_string(),
+                name: None,
+                template: None,
+                rel_template_ids: None,
+                scope_level: None,
+            },
+        )
+        .await;
+    assert!(import_resp.errors.is_empty());
+    let review_state: FlowStateDetailResp = flow_client.get(&format!("/cc/state/{}", import_resp.state_mapping["review"])).await;
+    assert_eq!(review_state.sys_state, FlowSysStateKind::Start);
+    let done_state: FlowStateDetailResp = flow_client.get(&format!("/cc/state/{}", import_resp.state_mapping["done"])).await;
+    assert_eq!(done_state.sys_state, FlowSysStateKind::Finish);
+
+    // errors are reported per element
+    let invalid_bpmn = bpmn
+        .replace(
+            r#"<userTask id="review" name="外部评审" />"#,
+            r#"<userTask id="review" name="外部评审" /><exclusiveGateway id="gw" />"#,
+        )
+        .replace(r#"<startEvent id="s1" />"#, "");
+    let import_resp: FlowModelImportResp = flow_client
+        .post(
+            "/cc/model/import",
+            &FlowModelImportReq {
+                format: FlowModelExchangeFormat::Bpmn,
+                content: invalid_bpmn,
+                name: None,
+                template: None,
+                rel_template_ids: None,
+                scope_level: None,
+            },
+        )
+        .await;
+    assert!(import_resp.flow_model_id.is_none());
+    let error_elements = import_resp.errors.iter().map(|error| error.element.as_str()).collect::<Vec<_>>();
+    assert!(error_elements.contains(&"state:gw"));
+    assert!(error_elements.contains(&"model"));
+    let mut invalid_doc = doc.clone();
+    invalid_doc.transitions[0].transition.to_flow_state_id = "unknown".to_string();
+    invalid_doc.states[0].kind_conf = json!({"delay_sec": 0, "to_flow_state_id": closed_state_id});
+    let import_resp: FlowModelImportResp = flow_client
+        .post(
+            "/cc/model/import",
+            &FlowModelImportReq {
+                format: FlowModelExchangeFormat::Json,
+                content: TardisFuns::json.obj_to_string(&invalid_doc)?,
+                name: None,
+                template: None,
+                rel_template_ids: None,
+                scope_level: None,
+            },
+        )
+        .await;
+    assert!(import_resp.flow_model_id.is_none());
+    assert_eq!(
+        import_resp.errors.iter().map(|error| error.element.clone()).collect::<Vec<_>>(),
+        vec![format!("state:{}", waiting_state_id), format!("transition:{}", invalid_doc.transitions[0].key)]
+    );
+    assert!(import_resp.errors.iter().all(|error: &FlowModelImportErrorResp| !error.message.is_empty()));
+    Ok(())
+}
@@ -392,7 +392,7 @@ pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
             &format!("/cc/model/{}/bind_state", &req_share_model_id),
             &FlowModelBindStateReq {
                 state_id: custom_state_id.clone(),
-                ext: FlowStateRelModelExt { sort: 1, show_btns: None },
+                ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
             },
         )
         .await;
@@ -491,7 +491,7 @@ pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
             &format!("/cc/model/{}/bind_state", &share_model_id),
             &FlowModelBindStateReq {
                 state_id: custom_state_id.clone(),
-                ext: FlowStateRelModelExt { sort: 1, show_btns: None },
+                ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
             },
         )
         .await;
@@ -104,19 +104,19 @@ pub async fn test(flow_client: &mut TestHttpClient, search_client: &mut TestHttp
                 bind_states: Some(vec![
                     FlowModelBindStateReq {
                         state_id: init_state_id.clone(),
-                        ext: FlowStateRelModelExt { sort: 1, show_btns: None },
+                        ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
                     },
                     FlowModelBindStateReq {
                         state_id: processing_state_id.clone(),
-                        ext: FlowStateRelModelExt { sort: 2, show_btns: None },
+                        ext: FlowStateRelModelExt { sort: 2, ..Default::default() },
                     },
                     FlowModelBindStateReq {
                         state_id: finish_state_id.clone(),
-                        ext: FlowStateRelModelExt { sort: 3, show_btns: None },
+                        ext: FlowStateRelModelExt { sort: 3, ..Default::default() },
                     },
                     FlowModelBindStateReq {
                         state_id: closed_state_id.clone(),
-                        ext: FlowStateRelModelExt { sort: 4, show_btns: None },
+                        ext: FlowStateRelModelExt { sort: 4, ..Default::default() },
                     },
                 ]),
                 add_transitions: Some(vec![
@@ -194,7 +194,7 @@ pub async fn test(flow_client: &mut TestHttpClient, search_client: &mut TestHttp
                 init_state_id: Some(init_state_id.to_string()),
                 bind_states: Some(vec![FlowModelBindStateReq {
                     state_id: init_state_id.clone(),
-                    ext: FlowStateRelModelExt { sort: 1, show_btns: None },
+                    ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
                 }]),
                 add_transitions: None,
                 ..Default::default()
@@ -233,6 +233,7 @@ pub async fn test(flow_client: &mut TestHttpClient, search_client: &mut TestHttp
                 },
                 query: SearchItemQueryReq { ..Default::default() },
                 adv_query: None,
+                highlight: None,
                 sort: None,
                 page: SearchItemSearchPageReq {
                     number: 1,
@@ -269,6 +270,7 @@ pub async fn test(flow_client: &mut TestHttpClient, search_client: &mut TestHttp
                 },
                 query: SearchItemQueryReq { ..Default::default() },
                 adv_query: None,
+                highlight: None,
                 sort: None,
                 page: SearchItemSearchPageReq {
                     number: 1,
@@ -309,6 +311,7 @@ pub async fn test(flow_client: &mut TestHttpClient, search_client: &mut TestHttp
                 },
                 query: SearchItemQueryReq { ..Default::default() },
                 adv_query: None,
+                highlight: None,
                 sort: None,
                 page: SearchItemSearchPageReq {
                     number: 1,
@@ -333,6 +336,7 @@ pub async fn test(flow_client: &mut TestHttpClient, search_client: &mut TestHttp
                 },
                 query: SearchItemQueryReq { ..Default::default() },
                 adv_query: None,
+                highlight: None,
                 sort: None,
                 page: SearchItemSearchPageReq {
                     number: 1,
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use bios_basic::dto::BasicQueryCondInfo;
+use bios_basic::enumeration::BasicQueryOpKind;
+use bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_mw_flow::dto::flow_model_dto::{FlowModelAddReq, FlowModelAggResp, FlowModelBindStateReq, FlowModelModifyReq};
+use bios_mw_flow::dto::flow_model_simulate_dto::{FlowModelSimulateReq, FlowModelSimulateResp, FlowTransitionGuardKind};
+use bios_mw_flow::dto::flow_state_dto::{FlowStateRelModelExt, FlowStateSummaryResp};
+use bios_mw_flow::dto::flow_transition_dto::{
+    FlowTransitionActionByVarChangeInfoChangedKind, FlowTransitionActionChangeKind, FlowTransitionAddReq, FlowTransitionFrontActionInfo,
+    FlowTransitionFrontActionInfoRelevanceRelation, FlowTransitionFrontActionRightValue, FlowTransitionPostActionInfo,
+};
+use serde_json::json;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::web::web_resp::{TardisPage, Void};
+
+pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_flow_scenes_simulate】");
+    let ctx = TardisContext {
+        own_paths: "t_simulate".to_string(),
+        ak: "u001".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "u001".to_string(),
+        ..Default::default()
+    };
+    flow_client.set_auth(&ctx)?;
+
+    let req_states: TardisPage<FlowStateSummaryResp> = flow_client.get("/cc/state?tag=REQ&enabled=true&page_number=1&page_size=100").await;
+    let processing_state_id = req_states.records[1].id.clone(); // 进行中
+    let finish_state_id = req_states.records[2].id.clone(); // 已完成
+    let closed_state_id = req_states.records[3].id.clone(); // 已关闭
+    let model: FlowModelAggResp = flow_client
+        .post(
+            "/cc/model",
+            &FlowModelAddReq {
+                name: "模拟模型".into(),
+                info: None,
+                init_state_id: "".to_string(),
+                rel_template_ids: None,
+                template: false,
+                tag: Some("REQ".to_string()),
+                scope_level: Some(RbumScopeLevelKind::Private),
+                icon: None,
+                transitions: None,
+                states: None,
+                rel_model_id: None,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = flow_client
+        .patch(
+            &format!("/cc/model/{}", model.id),
+            &FlowModelModifyReq {
+                init_state_id: Some(processing_state_id.clone()),
+                bind_states: Some(vec![
+                    FlowModelBindStateReq {
+                        state_id: processing_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: finish_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 2, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: closed_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 3, ..Default::default() },
+                    },
+                ]),
+                add_transitions: Some(vec![
+                    FlowTransitionAddReq {
+                        from_flow_state_id: processing_state_id.clone(),
+                        to_flow_state_id: finish_state_id.clone(),
+                        name: Some("完成".into()),
+                        guard_by_spec_role_ids: Some(vec!["admin".to_string()]),
+                        action_by_post_changes: Some(vec![FlowTransitionPostActionInfo {
+                            kind: FlowTransitionActionChangeKind::Var,
+                            describe: "降低优先级".to_string(),
+                            var_name: "priority".to_string(),
+                            changed_val: Some(json!("low")),
+                            changed_kind: Some(FlowTransitionActionByVarChangeInfoChangedKind::ChangeContent),
+                            ..Default::default()
+                        }]),
+                        sort: Some(1),
+                        ..Default::default()
+                    },
+                    FlowTransitionAddReq {
+                        from_flow_state_id: processing_state_id.clone(),
+                        to_flow_state_id: closed_state_id.clone(),
+                        name: Some("关闭".into()),
+                        guard_by_creator: Some(true),
+                        guard_by_other_conds: Some(vec![vec![BasicQueryCondInfo {
+                            field: "priority".to_string(),
+                            op: BasicQueryOpKind::Eq,
+                            value: json!("high"),
+                        }]]),
+                        sort: Some(2),
+                        ..Default::default()
+                    },
+                    FlowTransitionAddReq {
+                        from_flow_state_id: finish_state_id.clone(),
+                        to_flow_state_id: closed_state_id.clone(),
+                        name: Some("自动关闭".into()),
+                        action_by_front_changes: Some(vec![FlowTransitionFrontActionInfo {
+                            relevance_relation: FlowTransitionFrontActionInfoRelevanceRelation::Eq,
+                            relevance_label: "等于".to_string(),
+                            left_value: "priority".to_string(),
+                            left_label: "优先级".to_string(),
+                            right_value: FlowTransitionFrontActionRightValue::ChangeContent,
+                            select_field: None,
+                            select_field_label: None,
+                            change_content: Some(json!("low")),
+                            change_content_label: None,
+                        }]),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            },
+        )
+        .await;
+    let model: FlowModelAggResp = flow_client.get(&format!("/cc/model/{}", model.id)).await;
+    let auto_close_transition_id = model.states.iter().find(|state| state.id == finish_state_id).unwrap().transitions[0].id.clone();
+
+    // another operator with the role, the instance was created by u001
+    let resp: FlowModelSimulateResp = flow_client
+        .put(
+            &format!("/cc/model/{}/simulate", model.id),
+            &FlowModelSimulateReq {
+                vars: Some(HashMap::from([("priority".to_string(), json!("high"))])),
+                operator: Some("u002".to_string()),
+                roles: Some(vec!["admin:app1".to_string()]),
+                creator: Some("u001".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_eq!(resp.current_state_id, processing_state_id);
+    assert!(!resp.post_action_ring);
+    assert_eq!(resp.transitions.len(), 2);
+    let finish = &resp.transitions[0];
+    assert_eq!(finish.to_flow_state_id, finish_state_id);
+    assert!(finish.available);
+    assert_eq!(finish.guards.len(), 1);
+    assert_eq!(finish.guards[0].kind, FlowTransitionGuardKind::SpecRole);
+    assert!(finish.guards[0].passed);
+    assert_eq!(finish.post_changes.len(), 1);
+    assert_eq!(finish.post_changes[0].var_name, Some("priority".to_string()));
+    assert_eq!(finish.post_changes[0].changed_val, Some(json!("low")));
+    assert_eq!(finish.auto_flow_transition_id, Some(auto_close_transition_id.clone()));
+    let close = &resp.transitions[1];
+    assert!(!close.available);
+    assert_eq!(
+        close.guards.iter().map(|guard| (guard.kind.clone(), guard.passed)).collect::<Vec<_>>(),
+        vec![(FlowTransitionGuardKind::OtherConds, true), (FlowTransitionGuardKind::Creator, false)]
+    );
+
+    // the creator without the role
+    let resp: FlowModelSimulateResp = flow_client
+        .put(
+            &format!("/cc/model/{}/simulate", model.id),
+            &FlowModelSimulateReq {
+                current_state_id: Some(processing_state_id.clone()),
+                vars: Some(HashMap::from([("priority".to_string(), json!("low"))])),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(resp.transitions.iter().all(|transition| !transition.available));
+    assert_eq!(
+        resp.transitions[1].guards.iter().map(|guard| (guard.kind.clone(), guard.passed)).collect::<Vec<_>>(),
+        vec![(FlowTransitionGuardKind::OtherConds, false), (FlowTransitionGuardKind::Creator, true)]
+    );
+
+    // the front conditions of the transition itself
+    let resp: FlowModelSimulateResp = flow_client
+        .put(
+            &format!("/cc/model/{}/simulate", model.id),
+            &FlowModelSimulateReq {
+                current_state_id: Some(finish_state_id.clone()),
+                vars: Some(HashMap::from([("priority".to_string(), json!("low"))])),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_eq!(resp.transitions.len(), 1);
+    assert!(resp.transitions[0].available);
+    assert!(resp.transitions[0].front_changes.iter().all(|front_change| front_change.passed));
+
+    let resp = flow_client
+        .put_resp::<FlowModelSimulateReq, FlowModelSimulateResp>(
+            &format!("/cc/model/{}/simulate", model.id),
+            &FlowModelSimulateReq {
+                current_state_id: Some("unknown".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "404-flow-flow_model-simulate");
+    Ok(())
+}
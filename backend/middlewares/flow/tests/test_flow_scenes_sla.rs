@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_mw_flow::dto::flow_inst_dto::{FlowInstDetailResp, FlowInstSlaStatResp, FlowInstStartReq, FlowInstTransferReq, FlowInstTransferResp};
+use bios_mw_flow::dto::flow_model_dto::{FlowModelAddReq, FlowModelAggResp, FlowModelBindStateReq, FlowModelModifyReq};
+use bios_mw_flow::dto::flow_state_dto::{FlowStateRelModelExt, FlowStateSlaConf, FlowStateSummaryResp};
+use bios_mw_flow::dto::flow_transition_dto::FlowTransitionAddReq;
+use serde_json::json;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisPage, Void};
+use tardis::TardisFuns;
+
+pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_flow_scenes_sla】");
+    let ctx = TardisContext {
+        own_paths: "t_sla".to_string(),
+        ak: "u001".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "u001".to_string(),
+        ..Default::default()
+    };
+    flow_client.set_auth(&ctx)?;
+
+    let req_states: TardisPage<FlowStateSummaryResp> = flow_client.get("/cc/state?tag=REQ&enabled=true&page_number=1&page_size=100").await;
+    let init_state_id = req_states.records[0].id.clone(); // 待开始
+    let processing_state_id = req_states.records[1].id.clone(); // 进行中
+    let model: FlowModelAggResp = flow_client
+        .post(
+            "/cc/model",
+            &FlowModelAddReq {
+                name: "SLA模型".into(),
+                info: None,
+                init_state_id: "".to_string(),
+                rel_template_ids: None,
+                template: false,
+                tag: Some("REQ".to_string()),
+                scope_level: Some(RbumScopeLevelKind::Private),
+                icon: None,
+                transitions: None,
+                states: None,
+                rel_model_id: None,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = flow_client
+        .patch(
+            &format!("/cc/model/{}", model.id),
+            &FlowModelModifyReq {
+                init_state_id: Some(init_state_id.clone()),
+                bind_states: Some(vec![
+                    FlowModelBindStateReq {
+                        state_id: init_state_id.clone(),
+                        ext: FlowStateRelModelExt {
+                            sort: 1,
+                            show_btns: None,
+                            sla: Some(FlowStateSlaConf { target_secs: 1, reach: None }),
+                        },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: processing_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 2, ..Default::default() },
+                    },
+                ]),
+                add_transitions: Some(vec![FlowTransitionAddReq {
+                    from_flow_state_id: init_state_id.clone(),
+                    to_flow_state_id: processing_state_id.clone(),
+                    name: Some("开始".into()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+        )
+        .await;
+    let model: FlowModelAggResp = flow_client.get(&format!("/cc/model/{}", model.id)).await;
+    let start_transition_id = model.states.iter().find(|state| state.id == init_state_id).unwrap().transitions[0].id.clone();
+
+    let breached_inst_id: String = flow_client
+        .post(
+            "/cc/inst",
+            &FlowInstStartReq {
+                tag: "REQ".to_string(),
+                create_vars: Some(HashMap::from([("assigned_to".to_string(), json!("u002"))])),
+                rel_business_obj_id: TardisFuns::field.nanoid(),
+            },
+        )
+        .await;
+    let breached_inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", breached_inst_id)).await;
+    let sla = breached_inst.sla.unwrap();
+    assert_eq!(sla.flow_state_id, init_state_id);
+    assert_eq!(sla.sla_secs, 1);
+    assert!(sla.remaining_secs <= 1);
+    assert!(!sla.breached);
+
+    // transferred within the SLA
+    let complied_inst_id: String = flow_client
+        .post(
+            "/cc/inst",
+            &FlowInstStartReq {
+                tag: "REQ".to_string(),
+                create_vars: Some(HashMap::from([("assigned_to".to_string(), json!("u003"))])),
+                rel_business_obj_id: TardisFuns::field.nanoid(),
+            },
+        )
+        .await;
+    let transfer: FlowInstTransferResp = flow_client
+        .put(
+            &format!("/cc/inst/{}/transition/transfer", complied_inst_id),
+            &FlowInstTransferReq {
+                flow_transition_id: start_transition_id.clone(),
+                vars: None,
+                message: None,
+            },
+        )
+        .await;
+    assert_eq!(transfer.new_flow_state_id, processing_state_id);
+    // the state without SLA
+    let complied_inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", complied_inst_id)).await;
+    assert!(complied_inst.sla.is_none());
+
+    sleep(Duration::from_millis(2000)).await;
+    let _: Void = flow_client.get("/ci/inst/trigger_sla").await;
+    sleep(Duration::from_millis(500)).await;
+    let breached_inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", breached_inst_id)).await;
+    let sla = breached_inst.sla.unwrap();
+    assert!(sla.breached);
+    assert!(sla.elapsed_secs >= 2);
+    assert!(sla.remaining_secs < 0);
+    let _: FlowInstTransferResp = flow_client
+        .put(
+            &format!("/cc/inst/{}/transition/transfer", breached_inst_id),
+            &FlowInstTransferReq {
+                flow_transition_id: start_transition_id.clone(),
+                vars: None,
+                message: None,
+            },
+        )
+        .await;
+
+    let stats: Vec<FlowInstSlaStatResp> = flow_client.get(&format!("/cc/inst/sla/stat?flow_model_id={}&group_by=State", model.id)).await;
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].key, init_state_id);
+    assert_eq!(stats[0].total, 2);
+    assert_eq!(stats[0].breached, 1);
+    assert_eq!(stats[0].compliance_rate, 0.5);
+    assert!(stats[0].avg_elapsed_secs.is_some());
+    let stats: Vec<FlowInstSlaStatResp> = flow_client.get(&format!("/cc/inst/sla/stat?flow_model_id={}&group_by=Assignee", model.id)).await;
+    assert_eq!(
+        stats.iter().map(|stat| (stat.key.as_str(), stat.total, stat.breached)).collect::<Vec<_>>(),
+        vec![("u002", 1, 1), ("u003", 1, 0)]
+    );
+    Ok(())
+}
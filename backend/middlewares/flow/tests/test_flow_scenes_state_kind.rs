@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_mw_flow::dto::flow_inst_dto::{FlowInstDetailResp, FlowInstStartReq, FlowInstTransferReq, FlowInstTransferResp};
+use bios_mw_flow::dto::flow_model_dto::{FlowModelAddReq, FlowModelAggResp, FlowModelBindStateReq, FlowModelModifyReq};
+use bios_mw_flow::dto::flow_state_dto::{FlowStateAddReq, FlowStateKind, FlowStateRelModelExt, FlowStateSummaryResp, FlowSysStateKind};
+use bios_mw_flow::dto::flow_transition_dto::FlowTransitionAddReq;
+use serde_json::json;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisPage, Void};
+use tardis::TardisFuns;
+
+pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_flow_scenes_state_kind】");
+    let ctx = TardisContext {
+        own_paths: "t_state_kind".to_string(),
+        ak: "u001".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "u001".to_string(),
+        ..Default::default()
+    };
+    flow_client.set_auth(&ctx)?;
+
+    // invalid kind conf
+    let resp = flow_client
+        .post_resp::<FlowStateAddReq, String>(
+            "/cc/state",
+            &FlowStateAddReq {
+                name: Some("非法脚本".into()),
+                sys_state: FlowSysStateKind::Progress,
+                state_kind: Some(FlowStateKind::Script),
+                kind_conf: Some(json!({"script": "total = price *"})),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-flow-flow_state-validate_kind_conf");
+    let resp = flow_client
+        .post_resp::<FlowStateAddReq, String>(
+            "/cc/state",
+            &FlowStateAddReq {
+                name: Some("缺少配置".into()),
+                sys_state: FlowSysStateKind::Progress,
+                state_kind: Some(FlowStateKind::Timer),
+                kind_conf: Some(json!({})),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-flow-flow_state-validate_kind_conf");
+
+    let req_states: TardisPage<FlowStateSummaryResp> = flow_client.get("/cc/state?tag=REQ&enabled=true&page_number=1&page_size=100").await;
+    let closed_state_id = req_states.records[3].id.clone(); // 已关闭
+    let form_state_id: String = flow_client
+        .post(
+            "/cc/state",
+            &FlowStateAddReq {
+                name: Some("填写报价".into()),
+                sys_state: FlowSysStateKind::Start,
+                state_kind: Some(FlowStateKind::Form),
+                kind_conf: Some(json!({"fields": [{"name": "price", "label": "单价"}, {"name": "count"}, {"name": "remark", "required": false}]})),
+                ..Default::default()
+            },
+        )
+        .await;
+    let script_state_id: String = flow_client
+        .post(
+            "/cc/state",
+            &FlowStateAddReq {
+                name: Some("计算总价".into()),
+                sys_state: FlowSysStateKind::Progress,
+                state_kind: Some(FlowStateKind::Script),
+                kind_conf: Some(json!({"script": "total = price * count", "to_flow_state_id": closed_state_id})),
+                ..Default::default()
+            },
+        )
+        .await;
+    let model: FlowModelAggResp = flow_client
+        .post(
+            "/cc/model",
+            &FlowModelAddReq {
+                name: "状态类型模型".into(),
+                info: None,
+                init_state_id: "".to_string(),
+                rel_template_ids: None,
+                template: false,
+                tag: Some("REQ".to_string()),
+                scope_level: Some(RbumScopeLevelKind::Private),
+                icon: None,
+                transitions: None,
+                states: None,
+                rel_model_id: None,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = flow_client
+        .patch(
+            &format!("/cc/model/{}", model.id),
+            &FlowModelModifyReq {
+                init_state_id: Some(form_state_id.clone()),
+                bind_states: Some(vec![
+                    FlowModelBindStateReq {
+                        state_id: form_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: script_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 2, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: closed_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 3, ..Default::default() },
+                    },
+                ]),
+                add_transitions: Some(vec![
+                    FlowTransitionAddReq {
+                        from_flow_state_id: form_state_id.clone(),
+                        to_flow_state_id: script_state_id.clone(),
+                        name: Some("提交".into()),
+                        ..Default::default()
+                    },
+                    FlowTransitionAddReq {
+                        from_flow_state_id: script_state_id.clone(),
+                        to_flow_state_id: closed_state_id.clone(),
+                        name: Some("完成".into()),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            },
+        )
+        .await;
+    let model: FlowModelAggResp = flow_client.get(&format!("/cc/model/{}", model.id)).await;
+    let submit_transition_id = model.states.iter().find(|state| state.id == form_state_id).unwrap().transitions[0].id.clone();
+
+    let inst_id: String = flow_client
+        .post(
+            "/cc/inst",
+            &FlowInstStartReq {
+                tag: "REQ".to_string(),
+                create_vars: None,
+                rel_business_obj_id: TardisFuns::field.nanoid(),
+            },
+        )
+        .await;
+    // the form fields are required before leaving the form state
+    let resp = flow_client
+        .put_resp::<FlowInstTransferReq, FlowInstTransferResp>(
+            &format!("/cc/inst/{}/transition/transfer", inst_id),
+            &FlowInstTransferReq {
+                flow_transition_id: submit_transition_id.clone(),
+                vars: Some(HashMap::from([("price".to_string(), json!(10))])),
+                message: None,
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-flow-flow_inst-transfer");
+    let transfer: FlowInstTransferResp = flow_client
+        .put(
+            &format!("/cc/inst/{}/transition/transfer", inst_id),
+            &FlowInstTransferReq {
+                flow_transition_id: submit_transition_id,
+                vars: Some(HashMap::from([("price".to_string(), json!(10)), ("count".to_string(), json!(3))])),
+                message: None,
+            },
+        )
+        .await;
+    assert_eq!(transfer.new_flow_state_id, script_state_id);
+
+    // the script state computes the vars and moves on by itself
+    sleep(Duration::from_millis(1000)).await;
+    let inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", inst_id)).await;
+    assert_eq!(inst.current_state_id, closed_state_id);
+    assert_eq!(inst.current_vars.unwrap().get("total"), Some(&json!(30)));
+    Ok(())
+}
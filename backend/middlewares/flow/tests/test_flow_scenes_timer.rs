@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_mw_flow::dto::flow_inst_dto::{FlowInstDetailResp, FlowInstStartReq, FlowInstTransferReq, FlowInstTransferResp};
+use bios_mw_flow::dto::flow_model_dto::{FlowModelAddReq, FlowModelAggResp, FlowModelBindStateReq, FlowModelModifyReq};
+use bios_mw_flow::dto::flow_state_dto::{FlowStateRelModelExt, FlowStateSummaryResp};
+use bios_mw_flow::dto::flow_transition_dto::FlowTransitionAddReq;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisPage, Void};
+use tardis::TardisFuns;
+
+pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_flow_scenes_timer】");
+    let ctx = TardisContext {
+        own_paths: "t_timer".to_string(),
+        ak: "u001".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "u001".to_string(),
+        ..Default::default()
+    };
+    flow_client.set_auth(&ctx)?;
+
+    let req_states: TardisPage<FlowStateSummaryResp> = flow_client.get("/cc/state?tag=REQ&enabled=true&page_number=1&page_size=100").await;
+    let init_state_id = req_states.records[0].id.clone(); // 待开始
+    let processing_state_id = req_states.records[1].id.clone(); // 进行中
+    let closed_state_id = req_states.records[3].id.clone(); // 已关闭
+    let model: FlowModelAggResp = flow_client
+        .post(
+            "/cc/model",
+            &FlowModelAddReq {
+                name: "定时流转模型".into(),
+                info: None,
+                init_state_id: "".to_string(),
+                rel_template_ids: None,
+                template: false,
+                tag: Some("REQ".to_string()),
+                scope_level: Some(RbumScopeLevelKind::Private),
+                icon: None,
+                transitions: None,
+                states: None,
+                rel_model_id: None,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = flow_client
+        .patch(
+            &format!("/cc/model/{}", model.id),
+            &FlowModelModifyReq {
+                init_state_id: Some(init_state_id.clone()),
+                bind_states: Some(vec![
+                    FlowModelBindStateReq {
+                        state_id: init_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: processing_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 2, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: closed_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 3, ..Default::default() },
+                    },
+                ]),
+                add_transitions: Some(vec![
+                    FlowTransitionAddReq {
+                        from_flow_state_id: init_state_id.clone(),
+                        to_flow_state_id: processing_state_id.clone(),
+                        name: Some("超时开始".into()),
+                        transfer_by_timer: Some("1".to_string()),
+                        ..Default::default()
+                    },
+                    FlowTransitionAddReq {
+                        from_flow_state_id: init_state_id.clone(),
+                        to_flow_state_id: closed_state_id.clone(),
+                        name: Some("关闭".into()),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            },
+        )
+        .await;
+    let model: FlowModelAggResp = flow_client.get(&format!("/cc/model/{}", model.id)).await;
+    let close_transition_id = model
+        .states
+        .iter()
+        .find(|state| state.id == init_state_id)
+        .unwrap()
+        .transitions
+        .iter()
+        .find(|transition| transition.to_flow_state_id == closed_state_id)
+        .unwrap()
+        .id
+        .clone();
+
+    let timed_inst_id: String = flow_client
+        .post(
+            "/cc/inst",
+            &FlowInstStartReq {
+                tag: "REQ".to_string(),
+                create_vars: None,
+                rel_business_obj_id: TardisFuns::field.nanoid(),
+            },
+        )
+        .await;
+    let closed_inst_id: String = flow_client
+        .post(
+            "/cc/inst",
+            &FlowInstStartReq {
+                tag: "REQ".to_string(),
+                create_vars: None,
+                rel_business_obj_id: TardisFuns::field.nanoid(),
+            },
+        )
+        .await;
+    // leaving the state cancels the timer
+    let transfer: FlowInstTransferResp = flow_client
+        .put(
+            &format!("/cc/inst/{}/transition/transfer", closed_inst_id),
+            &FlowInstTransferReq {
+                flow_transition_id: close_transition_id,
+                vars: None,
+                message: None,
+            },
+        )
+        .await;
+    assert_eq!(transfer.new_flow_state_id, closed_state_id);
+
+    // not due yet
+    let _: Void = flow_client.get("/ci/inst/trigger_timer").await;
+    sleep(Duration::from_millis(500)).await;
+    let timed_inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", timed_inst_id)).await;
+    assert_eq!(timed_inst.current_state_id, init_state_id);
+
+    sleep(Duration::from_millis(1000)).await;
+    let _: Void = flow_client.get("/ci/inst/trigger_timer").await;
+    sleep(Duration::from_millis(1000)).await;
+    let timed_inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", timed_inst_id)).await;
+    assert_eq!(timed_inst.current_state_id, processing_state_id);
+    assert_eq!(timed_inst.transitions.unwrap().len(), 1);
+    let closed_inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", closed_inst_id)).await;
+    assert_eq!(closed_inst.current_state_id, closed_state_id);
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_mw_flow::dto::flow_inst_dto::{FlowInstDetailResp, FlowInstFindNextTransitionResp, FlowInstFindNextTransitionsReq, FlowInstStartReq};
+use bios_mw_flow::dto::flow_model_dto::{FlowModelAddReq, FlowModelAggResp, FlowModelBindStateReq, FlowModelModifyReq};
+use bios_mw_flow::dto::flow_model_version_dto::{
+    FlowModelVersionDetailResp, FlowModelVersionDiffResp, FlowModelVersionMigrateReq, FlowModelVersionMigrateResp, FlowModelVersionPublishReq, FlowModelVersionSummaryResp,
+};
+use bios_mw_flow::dto::flow_state_dto::{FlowStateRelModelExt, FlowStateSummaryResp};
+use bios_mw_flow::dto::flow_transition_dto::FlowTransitionAddReq;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::web::web_resp::{TardisPage, Void};
+use tardis::TardisFuns;
+
+pub async fn test(flow_client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_flow_scenes_version】");
+    let ctx = TardisContext {
+        own_paths: "t_version".to_string(),
+        ak: "u001".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "u001".to_string(),
+        ..Default::default()
+    };
+    flow_client.set_auth(&ctx)?;
+
+    let req_states: TardisPage<FlowStateSummaryResp> = flow_client.get("/cc/state?tag=REQ&enabled=true&page_number=1&page_size=100").await;
+    let processing_state_id = req_states.records[1].id.clone(); // 进行中
+    let finish_state_id = req_states.records[2].id.clone(); // 已完成
+    let closed_state_id = req_states.records[3].id.clone(); // 已关闭
+    let model: FlowModelAggResp = flow_client
+        .post(
+            "/cc/model",
+            &FlowModelAddReq {
+                name: "版本模型".into(),
+                info: None,
+                init_state_id: "".to_string(),
+                rel_template_ids: None,
+                template: false,
+                tag: Some("REQ".to_string()),
+                scope_level: Some(RbumScopeLevelKind::Private),
+                icon: None,
+                transitions: None,
+                states: None,
+                rel_model_id: None,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = flow_client
+        .patch(
+            &format!("/cc/model/{}", model.id),
+            &FlowModelModifyReq {
+                init_state_id: Some(processing_state_id.clone()),
+                bind_states: Some(vec![
+                    FlowModelBindStateReq {
+                        state_id: processing_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 1, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: finish_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 2, ..Default::default() },
+                    },
+                    FlowModelBindStateReq {
+                        state_id: closed_state_id.clone(),
+                        ext: FlowStateRelModelExt { sort: 3, ..Default::default() },
+                    },
+                ]),
+                add_transitions: Some(vec![FlowTransitionAddReq {
+                    from_flow_state_id: processing_state_id.clone(),
+                    to_flow_state_id: closed_state_id.clone(),
+                    name: Some("关闭".into()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+        )
+        .await;
+    let v1_id: String = flow_client.post(&format!("/cc/model/{}/version", model.id), &FlowModelVersionPublishReq { info: Some("v1".to_string()) }).await;
+    let v1: FlowModelVersionDetailResp = flow_client.get(&format!("/cc/model/{}/version/{}", model.id, v1_id)).await;
+    assert_eq!(v1.version, 1);
+    assert_eq!(v1.init_state_id, processing_state_id);
+
+    let start = || FlowInstStartReq {
+        tag: "REQ".to_string(),
+        create_vars: None,
+        rel_business_obj_id: TardisFuns::field.nanoid(),
+    };
+    let inst_id: String = flow_client.post("/cc/inst", &start()).await;
+    let inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", inst_id)).await;
+    assert_eq!(inst.rel_flow_model_version_id, Some(v1_id.clone()));
+
+    // the change of the model does not affect the instance pinned to v1
+    let _: Void = flow_client
+        .patch(
+            &format!("/cc/model/{}", model.id),
+            &FlowModelModifyReq {
+                add_transitions: Some(vec![FlowTransitionAddReq {
+                    from_flow_state_id: processing_state_id.clone(),
+                    to_flow_state_id: finish_state_id.clone(),
+                    name: Some("完成".into()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+        )
+        .await;
+    let next_transitions: Vec<FlowInstFindNextTransitionResp> =
+        flow_client.put(&format!("/cc/inst/{}/transition/next", inst_id), &FlowInstFindNextTransitionsReq { vars: None }).await;
+    assert_eq!(next_transitions.len(), 1);
+    assert_eq!(next_transitions[0].next_flow_state_id, closed_state_id);
+
+    let diff: FlowModelVersionDiffResp = flow_client.get(&format!("/cc/model/{}/version/diff?from_version_id={}", model.id, v1_id)).await;
+    assert!(!diff.init_state_changed);
+    assert!(diff.added_state_ids.is_empty() && diff.removed_state_ids.is_empty());
+    assert_eq!(diff.added_transitions.len(), 1);
+    assert_eq!(diff.added_transitions[0].to_flow_state_id, finish_state_id);
+    assert!(diff.removed_transitions.is_empty() && diff.modified_transitions.is_empty());
+
+    let v2_id: String = flow_client.post(&format!("/cc/model/{}/version", model.id), &FlowModelVersionPublishReq { info: None }).await;
+    let versions: Vec<FlowModelVersionSummaryResp> = flow_client.get(&format!("/cc/model/{}/version", model.id)).await;
+    assert_eq!(versions.iter().map(|version| version.version).collect::<Vec<_>>(), vec![2, 1]);
+    let new_inst_id: String = flow_client.post("/cc/inst", &start()).await;
+    let new_inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", new_inst_id)).await;
+    assert_eq!(new_inst.rel_flow_model_version_id, Some(v2_id.clone()));
+
+    // migrate the instances of v1 to v2
+    let resp = flow_client
+        .post_resp::<FlowModelVersionMigrateReq, FlowModelVersionMigrateResp>(
+            &format!("/cc/model/{}/version/migrate", model.id),
+            &FlowModelVersionMigrateReq {
+                from_version_id: Some(v1_id.clone()),
+                to_version_id: v2_id.clone(),
+                state_mapping: HashMap::from([(processing_state_id.clone(), "unknown".to_string())]),
+                dry_run: true,
+            },
+        )
+        .await;
+    assert_eq!(resp.code, "400-flow-flow_model_version-migrate");
+    let migrate_req = |dry_run: bool| FlowModelVersionMigrateReq {
+        from_version_id: Some(v1_id.clone()),
+        to_version_id: v2_id.clone(),
+        state_mapping: HashMap::new(),
+        dry_run,
+    };
+    let migrate_resp: FlowModelVersionMigrateResp = flow_client.post(&format!("/cc/model/{}/version/migrate", model.id), &migrate_req(true)).await;
+    assert!(migrate_resp.dry_run);
+    assert_eq!(migrate_resp.migrated.len(), 1);
+    assert_eq!(migrate_resp.migrated[0].flow_inst_id, inst_id);
+    assert_eq!(migrate_resp.migrated[0].to_state_id, Some(processing_state_id.clone()));
+    assert!(migrate_resp.unmapped.is_empty());
+    let inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", inst_id)).await;
+    assert_eq!(inst.rel_flow_model_version_id, Some(v1_id.clone()));
+
+    let migrate_resp: FlowModelVersionMigrateResp = flow_client.post(&format!("/cc/model/{}/version/migrate", model.id), &migrate_req(false)).await;
+    assert!(!migrate_resp.dry_run);
+    assert_eq!(migrate_resp.migrated.len(), 1);
+    let inst: FlowInstDetailResp = flow_client.get(&format!("/cc/inst/{}", inst_id)).await;
+    assert_eq!(inst.rel_flow_model_version_id, Some(v2_id));
+    assert_eq!(inst.current_state_id, processing_state_id);
+    let next_transitions: Vec<FlowInstFindNextTransitionResp> =
+        flow_client.put(&format!("/cc/inst/{}/transition/next", inst_id), &FlowInstFindNextTransitionsReq { vars: None }).await;
+    assert!(next_transitions.iter().any(|transition| transition.next_flow_state_id == finish_state_id));
+    Ok(())
+}
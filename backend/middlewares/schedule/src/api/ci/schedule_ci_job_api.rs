@@ -9,7 +9,7 @@ use tardis::web::poem_openapi::param::{Path, Query};
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
-use crate::dto::schedule_job_dto::{ScheduleJob, ScheduleJobInfoResp, ScheduleTaskInfoResp};
+use crate::dto::schedule_job_dto::{ScheduleJob, ScheduleJobInfoResp, ScheduleJobRunResp, ScheduleTaskInfoResp};
 use crate::serv::{schedule_job_serv, schedule_job_serv_v2};
 
 #[derive(Clone)]
@@ -57,6 +57,65 @@ impl ScheduleCiJobApi {
         TardisResp::ok(resp)
     }
 
+    /// Run schedule job now Api, the run doesn't affect the schedule of the job, return the run id
+    /// 立即执行调度任务，不影响任务的调度，返回运行id
+    #[oai(path = "/jobs/:code/run", method = "put")]
+    async fn run_now(&self, code: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<String> {
+        let funs = request.tardis_fun_inst();
+        let run_id = schedule_job_serv_v2::run_now(&code.0, funs, ctx.0).await?;
+        TardisResp::ok(run_id)
+    }
+
+    /// Find the status of each job in a run of the workflow Api
+    /// 查询工作流的一次运行中各任务的状态
+    #[oai(path = "/runs/:run_id", method = "get")]
+    async fn find_run(&self, run_id: Path<String>, _ctx: TardisContextExtractor) -> TardisApiResult<Vec<ScheduleJobRunResp>> {
+        let resp = schedule_job_serv_v2::find_run(&run_id.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Re-run the workflow from the job within the run Api, the upstream jobs of the job should have succeeded in the run
+    /// 在工作流的一次运行中从指定任务开始重新执行，该任务的上游任务须均已成功
+    #[oai(path = "/runs/:run_id/jobs/:code/rerun", method = "put")]
+    async fn rerun(&self, run_id: Path<String>, code: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        let funs = request.tardis_fun_inst();
+        schedule_job_serv_v2::rerun(&run_id.0, &code.0, funs, ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Pause schedule job Api
+    /// 暂停调度任务
+    #[oai(path = "/jobs/:code/pause", method = "put")]
+    async fn pause(&self, code: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        let funs = request.tardis_fun_inst();
+        schedule_job_serv_v2::pause(&code.0, funs, ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Resume schedule job Api
+    /// 恢复调度任务
+    #[oai(path = "/jobs/:code/resume", method = "put")]
+    async fn resume(&self, code: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        let funs = request.tardis_fun_inst();
+        schedule_job_serv_v2::resume(&code.0, funs, ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Find the next fire times of schedule job Api, empty if the job is paused
+    /// 查询调度任务之后的触发时间，任务暂停时为空
+    #[oai(path = "/jobs/:code/next_fire_times", method = "get")]
+    async fn find_next_fire_times(
+        &self,
+        code: Path<String>,
+        size: Query<Option<u16>>,
+        ctx: TardisContextExtractor,
+        request: &Request,
+    ) -> TardisApiResult<Vec<chrono::DateTime<Utc>>> {
+        let funs = request.tardis_fun_inst();
+        let resp = schedule_job_serv_v2::find_next_fire_times(&code.0, size.0.unwrap_or(10) as usize, funs, ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// find schedule task Api page
     /// 查询调度任务分页
     #[oai(path = "/task", method = "get")]
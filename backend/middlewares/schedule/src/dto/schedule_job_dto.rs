@@ -1,4 +1,4 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, str::FromStr, time::Duration};
 
 use bios_sdk_invoke::clients::spi_kv_client::KvItemDetailResp;
 use serde::{Deserialize, Serialize};
@@ -48,6 +48,177 @@ pub struct ScheduleJob {
     #[oai(default)]
     #[serde(default)]
     pub disable_time: Option<DateTime<Utc>>,
+    /// Timeout of each callback request in seconds, no timeout if empty
+    /// 每次回调请求的超时时间（秒），为空时不超时
+    #[oai(default)]
+    #[serde(default)]
+    pub timeout_sec: Option<u32>,
+    /// Retry policy of the failed callbacks, no retry if empty
+    /// 回调失败的重试策略，为空时不重试
+    #[oai(default)]
+    #[serde(default)]
+    pub retry: Option<ScheduleJobRetryPolicy>,
+    /// Criteria of a successful callback, any 2xx status code is regarded as success if empty
+    /// 回调成功的判定条件，为空时任意2xx状态码均视为成功
+    #[oai(default)]
+    #[serde(default)]
+    pub success_criteria: Option<ScheduleJobSuccessCriteria>,
+    /// Policy of the runs overlapping with a run still executing
+    /// 与仍在执行的运行重叠时的处理策略
+    #[oai(default)]
+    #[serde(default)]
+    pub concurrency_policy: ScheduleJobConcurrencyPolicy,
+    /// Policy of the fires missed while no node was running
+    /// 所有节点均未运行期间错过的触发的处理策略
+    #[oai(default)]
+    #[serde(default)]
+    pub misfire_policy: ScheduleJobMisfirePolicy,
+    /// Whether the job is paused, the paused job is kept but not fired until it's resumed
+    /// 任务是否已暂停，暂停的任务会保留，但在恢复之前不会触发
+    #[oai(default)]
+    #[serde(default)]
+    pub paused: bool,
+    /// Codes of the upstream jobs, the job isn't fired by its cron but runs when all the upstream jobs succeed within the same run
+    /// 上游任务的编码，任务不按其cron触发，而是在同一次运行中所有上游任务均成功后执行
+    #[oai(default)]
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// Retry policy of the callback
+/// 回调的重试策略
+#[derive(poem_openapi::Object, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduleJobRetryPolicy {
+    /// Max retry times after the first attempt
+    /// 首次请求之后的最大重试次数
+    pub max_times: u32,
+    /// Interval before the first retry in seconds
+    /// 首次重试前的间隔（秒）
+    #[oai(default = "ScheduleJobRetryPolicy::default_interval_sec")]
+    #[serde(default = "ScheduleJobRetryPolicy::default_interval_sec")]
+    pub interval_sec: u32,
+    /// Multiplier of the interval for each following retry, 1 means a fixed interval
+    /// 后续每次重试间隔的倍数，为1时间隔固定
+    #[oai(default = "ScheduleJobRetryPolicy::default_backoff_factor")]
+    #[serde(default = "ScheduleJobRetryPolicy::default_backoff_factor")]
+    pub backoff_factor: u32,
+    /// Upper limit of the interval in seconds
+    /// 重试间隔的上限（秒）
+    #[oai(default = "ScheduleJobRetryPolicy::default_max_interval_sec")]
+    #[serde(default = "ScheduleJobRetryPolicy::default_max_interval_sec")]
+    pub max_interval_sec: u32,
+}
+
+impl ScheduleJobRetryPolicy {
+    fn default_interval_sec() -> u32 {
+        1
+    }
+    fn default_backoff_factor() -> u32 {
+        2
+    }
+    fn default_max_interval_sec() -> u32 {
+        60
+    }
+    /// The interval before the retry, `retry_time` starts from 1
+    pub fn backoff(&self, retry_time: u32) -> Duration {
+        let interval_sec = (self.interval_sec as u64).saturating_mul((self.backoff_factor.max(1) as u64).saturating_pow(retry_time.saturating_sub(1)));
+        Duration::from_secs(interval_sec.min(self.max_interval_sec as u64))
+    }
+}
+
+/// Criteria of a successful callback
+/// 回调成功的判定条件
+#[derive(poem_openapi::Object, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ScheduleJobSuccessCriteria {
+    /// Status codes regarded as success, any 2xx status code if empty
+    /// 视为成功的状态码，为空时为任意2xx状态码
+    #[oai(default)]
+    #[serde(default)]
+    pub status_codes: Vec<u16>,
+    /// Dot separated path of the field in the json response body, e.g. `code` or `data.status`
+    /// 响应体json中字段的路径，以点分隔，例如 `code` 或 `data.status`
+    #[oai(default)]
+    #[serde(default)]
+    pub json_path: Option<String>,
+    /// Values of the field regarded as success, the field only needs to exist if empty
+    /// 视为成功的字段值，为空时字段存在即可
+    #[oai(default)]
+    #[serde(default)]
+    pub json_path_values: Vec<Value>,
+}
+
+impl ScheduleJobSuccessCriteria {
+    pub fn is_success(&self, status_code: u16, body: &str) -> bool {
+        let status_matched = if self.status_codes.is_empty() {
+            (200..300).contains(&status_code)
+        } else {
+            self.status_codes.contains(&status_code)
+        };
+        if !status_matched {
+            return false;
+        }
+        let Some(json_path) = &self.json_path else {
+            return true;
+        };
+        let Ok(body) = serde_json::from_str::<Value>(body) else {
+            return false;
+        };
+        let field = json_path.trim_start_matches('$').trim_start_matches('.').split('.').filter(|segment| !segment.is_empty()).try_fold(&body, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        });
+        match field {
+            Some(field) => self.json_path_values.is_empty() || self.json_path_values.contains(field),
+            None => false,
+        }
+    }
+}
+
+/// Policy of the overlapping runs of a job
+/// 任务重叠运行的处理策略
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScheduleJobConcurrencyPolicy {
+    /// Skip the new run while the previous one is still executing
+    /// 上一次运行仍在执行时跳过本次运行
+    #[default]
+    Forbid,
+    /// Allow the runs to overlap
+    /// 允许重叠运行
+    Allow,
+    /// Cancel the previous run and start the new one
+    /// 取消上一次运行并开始本次运行
+    Replace,
+}
+
+/// Policy of the missed fires of a job
+/// 任务错过触发的处理策略
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScheduleJobMisfirePolicy {
+    /// Skip all the missed fires
+    /// 跳过所有错过的触发
+    #[default]
+    Skip,
+    /// Fire once no matter how many fires are missed
+    /// 无论错过多少次触发，只补触发一次
+    FireOnce,
+    /// Fire as many times as missed
+    /// 按错过的次数补触发
+    FireAll,
+}
+
+/// Status of a job in a run of the workflow
+/// 任务在工作流的一次运行中的状态
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScheduleJobRunStatus {
+    /// 执行中
+    Running,
+    /// 执行成功
+    Success,
+    /// 执行失败
+    Failed,
+    /// Not executed since an upstream job failed or the previous run of the job was still executing
+    /// 因上游任务失败或该任务的上一次运行仍在执行而未执行
+    Skipped,
 }
 
 impl Default for ScheduleJob {
@@ -61,11 +232,22 @@ impl Default for ScheduleJob {
             callback_body: Default::default(),
             enable_time: Default::default(),
             disable_time: Default::default(),
+            timeout_sec: Default::default(),
+            retry: Default::default(),
+            success_criteria: Default::default(),
+            concurrency_policy: Default::default(),
+            misfire_policy: Default::default(),
+            paused: Default::default(),
+            depends_on: Default::default(),
         }
     }
 }
 
 impl ScheduleJob {
+    /// Whether the job is fired by its cron, the paused jobs and the jobs with upstream jobs are not
+    pub fn is_fired_by_cron(&self) -> bool {
+        !self.paused && self.depends_on.is_empty()
+    }
     pub fn parse_time_from_json_value(value: &Value) -> Option<DateTime<Utc>> {
         match value {
             Value::String(s) => Some(chrono::DateTime::parse_from_rfc3339(s).or_else(|_| chrono::DateTime::parse_from_rfc2822(s)).ok()?.to_utc()),
@@ -95,6 +277,19 @@ impl ScheduleJob {
         let callback_body = value.get("callback_body").and_then(|v| v.as_str()).map(|s| s.to_string());
         let enable_time = value.get("enable_time").and_then(ScheduleJob::parse_time_from_json_value);
         let disable_time = value.get("disable_time").and_then(ScheduleJob::parse_time_from_json_value);
+        let timeout_sec = value.get("timeout_sec").and_then(|v| v.as_u64()).map(|v| v as u32);
+        let retry = value.get("retry").and_then(|v| serde_json::from_value(v.clone()).ok());
+        let success_criteria = value.get("success_criteria").and_then(|v| serde_json::from_value(v.clone()).ok());
+        let concurrency_policy = value.get("concurrency_policy").and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default();
+        let misfire_policy = value.get("misfire_policy").and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default();
+        let paused = value.get("paused").and_then(|v| v.as_bool()).unwrap_or_default();
+        let depends_on = value
+            .get("depends_on")
+            .map(|v| match v {
+                serde_json::Value::Array(arr) => arr.iter().filter_map(|v| v.as_str()).map(|s| s.to_string()).collect(),
+                _ => vec![],
+            })
+            .unwrap_or_default();
         Self {
             code: code.into(),
             cron,
@@ -104,6 +299,13 @@ impl ScheduleJob {
             callback_body,
             enable_time,
             disable_time,
+            timeout_sec,
+            retry,
+            success_criteria,
+            concurrency_policy,
+            misfire_policy,
+            paused,
+            depends_on,
         }
     }
     pub fn build_request(&self) -> TardisResult<tardis::web::reqwest::Request> {
@@ -114,6 +316,9 @@ impl ScheduleJob {
             request.body_mut().replace(tardis::web::reqwest::Body::from(body.to_string()));
         }
         request.headers_mut().extend(self.callback_headers.iter().filter_map(|(k, v)| Some((header::HeaderName::from_str(k).ok()?, header::HeaderValue::from_str(v).ok()?))));
+        if let Some(timeout_sec) = self.timeout_sec {
+            request.timeout_mut().replace(Duration::from_secs(timeout_sec as u64));
+        }
         Ok(request)
     }
 }
@@ -137,6 +342,13 @@ pub struct ScheduleJobInfoResp {
     pub callback_body: Option<String>,
     pub enable_time: Option<DateTime<Utc>>,
     pub disable_time: Option<DateTime<Utc>>,
+    pub timeout_sec: Option<u32>,
+    pub retry: Option<ScheduleJobRetryPolicy>,
+    pub success_criteria: Option<ScheduleJobSuccessCriteria>,
+    pub concurrency_policy: ScheduleJobConcurrencyPolicy,
+    pub misfire_policy: ScheduleJobMisfirePolicy,
+    pub paused: bool,
+    pub depends_on: Vec<String>,
     pub create_time: Option<chrono::DateTime<Utc>>,
     pub update_time: Option<chrono::DateTime<Utc>>,
 }
@@ -190,6 +402,13 @@ impl ScheduleJobInfoResp {
             callback_body: self.callback_body.clone(),
             enable_time: self.enable_time,
             disable_time: self.disable_time,
+            timeout_sec: self.timeout_sec,
+            retry: self.retry.clone(),
+            success_criteria: self.success_criteria.clone(),
+            concurrency_policy: self.concurrency_policy,
+            misfire_policy: self.misfire_policy,
+            paused: self.paused,
+            depends_on: self.depends_on.clone(),
         }
     }
 }
@@ -205,7 +424,16 @@ pub struct ScheduleTaskLogFindResp {
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
 pub struct ScheduleTaskInfoResp {
+    /// Id of the run, the jobs of a workflow share the id within the same run
+    /// 运行id，工作流中的任务在同一次运行中共用该id
+    pub run_id: Option<String>,
     pub start: Option<chrono::DateTime<Utc>>,
     pub end: Option<chrono::DateTime<Utc>>,
     pub err_msg: Option<String>,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ScheduleJobRunResp {
+    pub code: String,
+    pub status: ScheduleJobRunStatus,
+}
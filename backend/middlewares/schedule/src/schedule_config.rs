@@ -11,10 +11,18 @@ pub struct ScheduleConfig {
     pub invoke: InvokeConfig,
     pub cache_key_job_changed_info: String,
     pub cache_key_job_changed_timer_sec: u32,
-    /// The expire time of the distributed lock on a certain task, in seconds, defualt 1 seconds
+    /// The expire time of the distributed lock on a certain task, in seconds, defualt 30 seconds.
+    /// The lock of a running task is renewed every third of this time until the task finishes
     pub distributed_lock_expire_sec: u32,
     /// The expire key prefix of the distributed lock, default "schedual:job:lock:"
     pub distributed_lock_key_prefix: String,
+    /// The max times of the missed fires to catch up for the jobs with the `FireAll` misfire policy, default 100
+    pub misfire_max_times: u32,
+    /// The key prefix of the state of the workflow runs, default "schedule:job:run:"
+    pub workflow_run_key_prefix: String,
+    /// The expire time of the state of a workflow run, in seconds, default one day.
+    /// The failed jobs of the run can be re-run before it expires
+    pub workflow_run_expire_sec: u32,
 }
 
 impl Default for ScheduleConfig {
@@ -27,6 +35,9 @@ impl Default for ScheduleConfig {
             cache_key_job_changed_timer_sec: 30,
             distributed_lock_expire_sec: 30,
             distributed_lock_key_prefix: "schedual:job:lock:".to_string(),
+            misfire_max_times: 100,
+            workflow_run_key_prefix: "schedule:job:run:".to_string(),
+            workflow_run_expire_sec: 60 * 60 * 24,
         }
     }
 }
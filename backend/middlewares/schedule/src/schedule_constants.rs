@@ -6,3 +6,6 @@ pub const OP_ADD: &str = "add";
 pub const OP_DELETE: &str = "delete";
 pub const OP_EXECUTE_START: &str = "exec-start";
 pub const OP_EXECUTE_END: &str = "exec-end";
+/// Header of the callback request carrying the fencing token of the run,
+/// the callee can reject the requests whose token is smaller than the largest one it has seen
+pub const FENCING_TOKEN_HEADER: &str = "bios-schedule-fencing-token";
@@ -53,6 +53,13 @@ pub(crate) async fn find_job(code: Option<String>, page_number: u32, page_size:
                     update_time: Some(record.update_time),
                     enable_time: job.enable_time,
                     disable_time: job.disable_time,
+                    timeout_sec: job.timeout_sec,
+                    retry: job.retry,
+                    success_criteria: job.success_criteria,
+                    concurrency_policy: job.concurrency_policy,
+                    misfire_policy: job.misfire_policy,
+                    paused: job.paused,
+                    depends_on: job.depends_on,
                 }
             })
             .collect(),
@@ -89,6 +96,7 @@ pub(crate) async fn find_task(
     let mut log_iter = page.records.into_iter();
     while let Some(start_log) = log_iter.next() {
         let mut task = ScheduleTaskInfoResp {
+            run_id: Some(start_log.rel_key).filter(|run_id| !run_id.is_empty()),
             start: Some(start_log.ts),
             end: None,
             err_msg: None,
@@ -3,17 +3,23 @@ use std::{sync::Arc, time::Duration};
 use service::ScheduleJobService;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
     log::{error, info, warn},
     tardis_static, TardisFuns, TardisFunsInst,
 };
 
-use crate::{dto::schedule_job_dto::ScheduleJob, schedule_constants::DOMAIN_CODE};
+use crate::{
+    dto::schedule_job_dto::{ScheduleJob, ScheduleJobRunResp},
+    schedule_constants::DOMAIN_CODE,
+};
 use event::{EventComponent, SpiLog};
 use repo::{Repository, SpiKv};
 
 pub mod event;
+mod lock;
 pub mod repo;
 pub mod service;
+mod workflow;
 
 tardis_static! {
     service: ScheduleJobService<SpiKv, SpiLog>;
@@ -35,6 +41,59 @@ pub async fn delete(code: &str, funs: TardisFunsInst, ctx: TardisContext) -> Tar
     service().delete_job(code, repo, event).await
 }
 
+pub async fn pause(code: &str, funs: TardisFunsInst, ctx: TardisContext) -> TardisResult<()> {
+    let funs = Arc::new(funs);
+    let ctx = Arc::new(ctx);
+    let repo = repo::SpiKv::from_context(funs.clone(), ctx.clone());
+    let event = event::SpiLog::from_context(funs, ctx);
+    service().pause_job(code, repo, event).await
+}
+
+pub async fn resume(code: &str, funs: TardisFunsInst, ctx: TardisContext) -> TardisResult<()> {
+    let funs = Arc::new(funs);
+    let ctx = Arc::new(ctx);
+    let repo = repo::SpiKv::from_context(funs.clone(), ctx.clone());
+    let event = event::SpiLog::from_context(funs, ctx);
+    service().resume_job(code, repo, event).await
+}
+
+pub async fn run_now(code: &str, funs: TardisFunsInst, ctx: TardisContext) -> TardisResult<String> {
+    let funs = Arc::new(funs);
+    let ctx = Arc::new(ctx);
+    let repo = repo::SpiKv::from_context(funs.clone(), ctx.clone());
+    let event = event::SpiLog::from_context(funs, ctx);
+    service().run_job_now(code, repo, event).await
+}
+
+pub async fn find_run(run_id: &str) -> TardisResult<Vec<ScheduleJobRunResp>> {
+    service().find_run(run_id).await
+}
+
+pub async fn rerun(run_id: &str, code: &str, funs: TardisFunsInst, ctx: TardisContext) -> TardisResult<()> {
+    let funs = Arc::new(funs);
+    let ctx = Arc::new(ctx);
+    let repo = repo::SpiKv::from_context(funs.clone(), ctx.clone());
+    let event = event::SpiLog::from_context(funs, ctx);
+    service().rerun_job(run_id, code, repo, event).await
+}
+
+pub async fn find_next_fire_times(code: &str, size: usize, funs: TardisFunsInst, ctx: TardisContext) -> TardisResult<Vec<DateTime<Utc>>> {
+    let repo = repo::SpiKv::from_context(funs, ctx);
+    service().find_next_fire_times(code, size, repo).await
+}
+
+/// 补触发任务在所有节点均未运行期间错过的触发，服务启动时对所有任务执行
+pub async fn catch_up_misfire(code: &str, funs: TardisFunsInst, ctx: TardisContext) -> TardisResult<()> {
+    let funs = Arc::new(funs);
+    let ctx = Arc::new(ctx);
+    let repo = repo::SpiKv::from_context(funs.clone(), ctx.clone());
+    let Some(job) = repo.get_one(code).await? else {
+        return Err(funs.err().not_found("schedule_job", "catch_up_misfire", &format!("job {code} is not found"), "404-schedule-job-not-found"));
+    };
+    let event = event::SpiLog::from_context(funs, ctx);
+    service().catch_up_misfire(&job, repo, event).await
+}
+
 pub(crate) fn init() {
     tardis::tokio::spawn(async move {
         // 这里初始化服务
@@ -60,11 +119,18 @@ pub(crate) fn init() {
             // 从仓库同步所有任务
             if let Ok(jobs) = repo.get_all().await {
                 for job in jobs {
-                    if let Ok(task) = service.make_task(&job, spi_log.clone()) {
-                        service.local_set_job(&job.code, task).await;
-                    } else {
-                        error!("fail to create task for job {job:?}");
+                    if let Err(e) = service.local_sync_job(&job, repo.clone(), spi_log.clone()).await {
+                        error!("fail to create task for job {job:?}: {e}");
+                        continue;
                     }
+                    // 补触发所有节点均未运行期间错过的触发
+                    let repo = repo.clone();
+                    let spi_log = spi_log.clone();
+                    tardis::tokio::spawn(async move {
+                        if let Err(e) = service.catch_up_misfire(&job, repo, spi_log).await {
+                            error!("fail to catch up the missed fires of job {}: {e}", job.code);
+                        }
+                    });
                 }
                 info!("synced all jobs from kv");
                 break;
@@ -79,10 +79,11 @@ impl EventComponent for SpiLog {
     }
 
     #[instrument(skip(self))]
-    fn notify_execute_start(&self, code: &str) {
+    fn notify_execute_start(&self, code: &str, run_id: &str) {
         let funs = self.funs.clone();
         let ctx = self.ctx.clone();
         let code = code.to_string();
+        let run_id = run_id.to_string();
         let _handle = tokio::spawn(async move {
             let result = SpiLogClient::add(
                 LogItemAddReq {
@@ -90,6 +91,7 @@ impl EventComponent for SpiLog {
                     content: "start request".into(),
                     key: Some(code.to_string()),
                     op: Some(OP_EXECUTE_START.to_string()),
+                    rel_key: Some(run_id),
                     ts: Some(Utc::now()),
                     ..Default::default()
                 },
@@ -104,10 +106,11 @@ impl EventComponent for SpiLog {
     }
 
     #[instrument(skip(self))]
-    fn notify_execute_end(&self, code: &str, message: String, ext: tardis::serde_json::Value) {
+    fn notify_execute_end(&self, code: &str, run_id: &str, message: String, ext: tardis::serde_json::Value) {
         let funs = self.funs.clone();
         let ctx = self.ctx.clone();
         let code = code.to_string();
+        let run_id = run_id.to_string();
         let _handle = tokio::spawn(async move {
             let result = SpiLogClient::addv2(
                 LogItemAddV2Req {
@@ -116,6 +119,7 @@ impl EventComponent for SpiLog {
                     ext: Some(ext),
                     key: Some(code.to_string()),
                     op: Some(OP_EXECUTE_END.to_string()),
+                    rel_key: Some(run_id),
                     ts: Some(Utc::now()),
                     msg: Some(message),
                     ..Default::default()
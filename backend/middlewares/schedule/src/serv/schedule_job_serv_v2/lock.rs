@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use tardis::{
+    basic::result::TardisResult,
+    log::{trace, warn},
+    tokio, TardisFuns,
+};
+
+use crate::dto::schedule_job_dto::ScheduleJobConcurrencyPolicy;
+
+/// Renew the lock only if it's still held by the given token, return 1 if renewed
+const RENEW_SCRIPT: &str = r#"if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('EXPIRE', KEYS[1], ARGV[2]) else return 0 end"#;
+/// Delete the lock only if it's still held by the given token
+const RELEASE_SCRIPT: &str = r#"if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) else return 0 end"#;
+
+/// Set the key with an expiration if it does not exist, atomically, return `true` if it's set
+pub(crate) async fn set_nx_ex(key: &str, value: &str, expire_sec: u32) -> TardisResult<bool> {
+    let result: Option<String> =
+        TardisFuns::cache().script(r#"return redis.call('SET', KEYS[1], ARGV[1], 'NX', 'EX', ARGV[2])"#).key(key).arg(value).arg(expire_sec).invoke().await?;
+    Ok(result.is_some())
+}
+
+/// Lock of a running job, it's renewed while the job executes
+///
+/// Each run gets a fencing token which is larger than those of all previous runs of the job,
+/// the token is sent to the callee so that it can reject the requests of a stale run.
+pub(crate) struct RunLock {
+    key: String,
+    token: u64,
+    policy: ScheduleJobConcurrencyPolicy,
+    expire_sec: u32,
+}
+
+impl RunLock {
+    /// Try to acquire the lock according to the concurrency policy, return `None` if the run should be skipped
+    pub(crate) async fn acquire(lock_key: &str, policy: ScheduleJobConcurrencyPolicy, expire_sec: u32) -> TardisResult<Option<Self>> {
+        let cache_client = TardisFuns::cache();
+        let token = cache_client.incr(&format!("{lock_key}:fence"), 1).await? as u64;
+        let key = format!("{lock_key}:run");
+        match policy {
+            ScheduleJobConcurrencyPolicy::Allow => {}
+            ScheduleJobConcurrencyPolicy::Forbid => {
+                if !set_nx_ex(&key, &token.to_string(), expire_sec).await? {
+                    return Ok(None);
+                }
+            }
+            ScheduleJobConcurrencyPolicy::Replace => {
+                // the previous run finds that the lock has been taken over when renewing it, and then stops
+                cache_client.set_ex(&key, &token.to_string(), expire_sec as u64).await?;
+            }
+        }
+        Ok(Some(Self { key, token, policy, expire_sec }))
+    }
+
+    pub(crate) fn token(&self) -> u64 {
+        self.token
+    }
+
+    /// Renew the lock periodically, return when the lock is lost, i.e. taken over by a newer run or expired
+    pub(crate) async fn keep_alive(&self) {
+        if self.policy == ScheduleJobConcurrencyPolicy::Allow {
+            return std::future::pending().await;
+        }
+        let cache_client = TardisFuns::cache();
+        let mut interval = tokio::time::interval(Duration::from_secs((self.expire_sec / 3).max(1) as u64));
+        // the first tick completes immediately
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let renewed: TardisResult<i64> = cache_client.script(RENEW_SCRIPT).key(&self.key).arg(self.token).arg(self.expire_sec).invoke().await;
+            match renewed {
+                Ok(1) => {}
+                Ok(_) => {
+                    trace!("lock {} with token {} is lost", self.key, self.token);
+                    return;
+                }
+                // keep running on the transient errors, the lock is regarded as lost only when it's confirmed
+                Err(e) => warn!("cannot renew lock {}, error: {e}", self.key),
+            }
+        }
+    }
+
+    /// Release the lock if it's still held by this run
+    pub(crate) async fn release(self) {
+        if self.policy == ScheduleJobConcurrencyPolicy::Allow {
+            return;
+        }
+        let released: TardisResult<i64> = TardisFuns::cache().script(RELEASE_SCRIPT).key(&self.key).arg(self.token).invoke().await;
+        if let Err(e) = released {
+            warn!("cannot release lock {}, error: {e}", self.key);
+        }
+    }
+}
@@ -67,6 +67,13 @@ impl super::Repository for SpiKv {
                         callback_body: job.callback_body,
                         enable_time: job.enable_time,
                         disable_time: job.disable_time,
+                        timeout_sec: job.timeout_sec,
+                        retry: job.retry,
+                        success_criteria: job.success_criteria,
+                        concurrency_policy: job.concurrency_policy,
+                        misfire_policy: job.misfire_policy,
+                        paused: job.paused,
+                        depends_on: job.depends_on,
                     }
                 })
                 .collect(),
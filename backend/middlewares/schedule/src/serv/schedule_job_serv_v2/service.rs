@@ -1,26 +1,47 @@
-use std::{collections::HashMap, marker::PhantomData, net::SocketAddr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    net::SocketAddr,
+    sync::Arc,
+};
 
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
-    chrono::TimeDelta,
-    futures::StreamExt,
-    log::{debug, error, trace},
-    serde_json,
+    chrono::{DateTime, TimeDelta, Utc},
+    futures::{future::BoxFuture, FutureExt, StreamExt},
+    log::{debug, error, info, trace},
+    serde_json::{self, Value},
     tokio::sync::RwLock,
+    web::reqwest::{header::HeaderValue, Request},
     TardisFuns, TardisFunsInst,
 };
 use tsuki_scheduler::{
     runtime::Tokio,
-    schedule::{Cron, ScheduleDynBuilder},
+    schedule::{Cron, Schedule, ScheduleDynBuilder},
     AsyncSchedulerClient, AsyncSchedulerRunner, Task, TaskUid,
 };
 
-use crate::{dto::schedule_job_dto::ScheduleJob, schedule_config::ScheduleConfig, schedule_constants::DOMAIN_CODE};
+use crate::{
+    dto::schedule_job_dto::{
+        ScheduleJob, ScheduleJobConcurrencyPolicy, ScheduleJobMisfirePolicy, ScheduleJobRetryPolicy, ScheduleJobRunResp, ScheduleJobRunStatus, ScheduleJobSuccessCriteria,
+    },
+    schedule_config::ScheduleConfig,
+    schedule_constants::{DOMAIN_CODE, FENCING_TOKEN_HEADER},
+};
 
 use super::{
     event::{self, EventComponent},
+    lock::{self, RunLock},
     repo::Repository,
+    workflow::{self, WorkflowRun},
 };
+
+/// The triggers of the runs recorded in the logs
+const TRIGGER_SCHEDULE: &str = "schedule";
+const TRIGGER_MANUAL: &str = "manual";
+const TRIGGER_MISFIRE: &str = "misfire";
+const TRIGGER_UPSTREAM: &str = "upstream";
+const TRIGGER_RERUN: &str = "rerun";
 #[derive(Clone)]
 pub struct ScheduleJobService<R, E> {
     pub repository: PhantomData<fn(R)>,
@@ -80,8 +101,9 @@ where
                             let event_hub = E::from_context(funs.clone(), ctx.clone());
                             let repo = R::from_context(funs.clone(), ctx.clone());
                             let Ok(Some(job)) = repo.get_one(&code).await else { continue };
-                            let Ok(task) = this.make_task(&job, event_hub) else { continue };
-                            this.local_set_job(&code, task).await;
+                            if let Err(e) = this.local_sync_job(&job, repo, event_hub).await {
+                                error!("fail to sync task of job {code}: {e}");
+                            }
                         }
                     }
                 }
@@ -112,110 +134,79 @@ where
         self.client.add_task(task_uid, task);
     }
 
-    /// 生成分布式锁的key
-    fn gen_distributed_lock_key(code: &str, config: &ScheduleConfig) -> String {
-        format!("{}{}", config.distributed_lock_key_prefix, code)
+    /// 创建任务
+    pub(crate) fn make_task(&self, job: &ScheduleJob, repo: R, event: E) -> TardisResult<Task<Tokio>> {
+        let runner = JobRunner::new(job, repo, event, self.funs.conf::<ScheduleConfig>())?;
+        let task = Task::tokio(build_schedule(job), move || {
+            let runner = runner.clone();
+            async move { runner.fire().await }
+        });
+        Ok(task)
     }
 
-    /// 创建任务
-    pub(crate) fn make_task(&self, job: &ScheduleJob, event: E) -> TardisResult<Task<Tokio>> {
-        let schedule_config = self.funs.conf::<ScheduleConfig>();
-        let callback_req = job.build_request()?;
-        let code = job.code.to_string();
-        // 分布式锁的key
-        let lock_key = Self::gen_distributed_lock_key(&code, &schedule_config);
+    /// 按任务状态同步本地调度器，暂停的任务和有上游任务的任务不会被调度
+    pub(crate) async fn local_sync_job(&self, job: &ScheduleJob, repo: R, event: E) -> TardisResult<()> {
+        if job.is_fired_by_cron() {
+            let task = self.make_task(job, repo, event)?;
+            self.local_set_job(&job.code, task).await;
+        } else {
+            self.local_delete_job(&job.code).await;
+        }
+        Ok(())
+    }
 
-        let distributed_lock_expire_sec = schedule_config.distributed_lock_expire_sec;
-        let enable_time = job.enable_time;
-        let disable_time = job.disable_time;
+    async fn get_job(&self, code: &str, repo: &R, op: &str) -> TardisResult<ScheduleJob> {
+        repo.get_one(code).await?.ok_or_else(|| self.funs.err().not_found("schedule_job", op, &format!("job {code} is not found"), "404-schedule-job-not-found"))
+    }
 
-        // 生成任务
-        let mut schedule_builder = job.cron.iter().filter_map(|cron| Cron::local_from_cron_expr(cron).ok()).fold(ScheduleDynBuilder::default(), ScheduleDynBuilder::or);
-        if let Some(enable_time) = enable_time {
-            schedule_builder = schedule_builder.after(enable_time);
-        }
-        if let Some(disable_time) = disable_time {
-            schedule_builder = schedule_builder.before(disable_time);
-        }
-        // 一个节点下一分钟内只能执行一次
-        schedule_builder = schedule_builder.throttling(TimeDelta::minutes(1));
-        let task = Task::tokio(schedule_builder, move || {
-            let callback_req = callback_req.try_clone().expect("body should be a string");
-            let code = code.clone();
-            let lock_key = lock_key.clone();
-            let event = event.clone();
-            async move {
-                let cache_client = TardisFuns::cache();
-                // about set and setnx, see:
-                // 1. https://redis.io/commands/set/
-                // 2. https://redis.io/commands/setnx/
-                // At Redis version 2.6.12, setnx command is regarded as deprecated. see: https://redis.io/commands/setnx/
-                // "executing" could be any string now, it's just a placeholder
-                match cache_client.set_nx(&lock_key, "executing").await {
-                    Ok(true) => {
-                        // safety: it's ok to unwrap in this closure, scheduler will restart this job when after panic
-                        let Ok(()) = cache_client.expire(&lock_key, distributed_lock_expire_sec as i64).await else {
-                            return;
-                        };
-                        trace!("executing schedule task {code}");
-                        // 1. write log exec start
-                        event.notify_execute_start(&code);
-                        // 2. request webhook
-                        match TardisFuns::web_client().raw().execute(callback_req).await {
-                            Ok(resp) => {
-                                let status_code = resp.status();
-                                let remote_addr = resp.remote_addr().as_ref().map(SocketAddr::to_string);
-                                let response_header: HashMap<String, String> = resp
-                                    .headers()
-                                    .into_iter()
-                                    .filter_map(|(k, v)| {
-                                        let v = v.to_str().ok()?.to_string();
-                                        Some((k.to_string(), v))
-                                    })
-                                    .collect();
-                                let ext = serde_json::json! {
-                                    {
-                                        "remote_addr": remote_addr,
-                                        "status_code": status_code.to_string(),
-                                        "headers": response_header
-                                    }
-                                };
-                                let content = resp.text().await.unwrap_or_default();
-                                // 3.1. write log exec end
-                                event.notify_execute_end(&code, content, ext);
-                            }
-                            Err(e) => {
-                                event.notify_execute_end(&code, e.to_string(), serde_json::Value::Null);
-                            }
-                        }
-                        trace!("executed schedule task {code}");
-                    }
-                    Ok(false) => {
-                        trace!("schedule task {} is executed by other nodes, skip", code);
-                    }
-                    Err(e) => {
-                        error!("cannot set lock to schedule task {code}, error: {e}");
-                    }
-                }
+    /// 检查任务的上游任务均存在，且不形成环
+    async fn check_dependencies(&self, job: &ScheduleJob, repo: &R) -> TardisResult<()> {
+        if job.depends_on.is_empty() {
+            return Ok(());
+        }
+        let jobs = repo.get_all().await?;
+        let codes = jobs.iter().map(|job| job.code.to_string()).collect::<HashSet<_>>();
+        for upstream_code in &job.depends_on {
+            if !codes.contains(upstream_code) {
+                return Err(self.funs.err().bad_request(
+                    "schedule_job",
+                    "set",
+                    &format!("upstream job {upstream_code} is not found"),
+                    "400-schedule-job-upstream-not-found",
+                ));
             }
-        });
-
-        Ok(task)
+        }
+        let downstream_codes = workflow::downstream_codes(&job.code, &jobs);
+        if let Some(upstream_code) = job.depends_on.iter().find(|upstream_code| **upstream_code == *job.code || downstream_codes.contains(upstream_code)) {
+            return Err(self.funs.err().bad_request(
+                "schedule_job",
+                "set",
+                &format!("depending on job {upstream_code} forms a cycle"),
+                "400-schedule-job-dependency-cycle",
+            ));
+        }
+        Ok(())
     }
 
     pub async fn set_job(&self, job: ScheduleJob, repo: R, event: E) -> Result<(), TardisError> {
         let code = job.code.to_string();
+        self.check_dependencies(&job, &repo).await?;
         // 如果存在，先删除
         self.local_delete_job(&code).await;
 
         // 生成任务
-        let task = self.make_task(&job, event.clone())?;
+        let task = self.make_task(&job, repo.clone(), event.clone())?;
 
         // 写入仓库
         repo.create(&job).await?;
 
+        // 从现在开始计算错过的触发
+        mark_fired(&gen_distributed_lock_key(&code, &self.funs.conf::<ScheduleConfig>()), Utc::now()).await?;
+
         // 写入调度器
-        self.local_set_job(&code, task).await;
+        if job.is_fired_by_cron() {
+            self.local_set_job(&code, task).await;
+        }
 
         // 通知创建成功
         event.notify_create(&code);
@@ -223,14 +214,386 @@ where
     }
 
     pub async fn delete_job(&self, code: &str, repo: R, event: E) -> Result<(), TardisError> {
+        if let Some(downstream_job) = repo.get_all().await?.into_iter().find(|job| job.depends_on.iter().any(|upstream_code| upstream_code == code)) {
+            return Err(self.funs.err().conflict(
+                "schedule_job",
+                "delete",
+                &format!("job {code} is depended on by job {}", downstream_job.code),
+                "409-schedule-job-depended",
+            ));
+        }
         // 从仓库删除
         repo.delete(code).await?;
 
         // 删除调度器
         self.local_delete_job(code).await;
+        TardisFuns::cache().del(&last_fired_key(&gen_distributed_lock_key(code, &self.funs.conf::<ScheduleConfig>()))).await?;
 
         // 通知删除成功
         event.notify_delete(code);
         Ok(())
     }
+
+    /// 暂停任务，任务保留在仓库中但不再触发
+    pub async fn pause_job(&self, code: &str, repo: R, event: E) -> TardisResult<()> {
+        let mut job = self.get_job(code, &repo, "pause").await?;
+        if job.paused {
+            return Ok(());
+        }
+        job.paused = true;
+        repo.update(&job).await?;
+        self.local_delete_job(code).await;
+
+        // 通知其他节点移除任务
+        event.notify_update(code);
+        Ok(())
+    }
+
+    /// 恢复已暂停的任务
+    pub async fn resume_job(&self, code: &str, repo: R, event: E) -> TardisResult<()> {
+        let mut job = self.get_job(code, &repo, "resume").await?;
+        if !job.paused {
+            return Ok(());
+        }
+        job.paused = false;
+        repo.update(&job).await?;
+
+        // 暂停期间的触发不视为错过的触发
+        mark_fired(&gen_distributed_lock_key(code, &self.funs.conf::<ScheduleConfig>()), Utc::now()).await?;
+        self.local_sync_job(&job, repo, event.clone()).await?;
+
+        // 通知其他节点恢复任务
+        event.notify_update(code);
+        Ok(())
+    }
+
+    /// 立即执行一次任务，不影响任务的调度，返回运行id
+    pub async fn run_job_now(&self, code: &str, repo: R, event: E) -> TardisResult<String> {
+        let job = self.get_job(code, &repo, "run").await?;
+        let runner = JobRunner::new(&job, repo, event, self.funs.conf::<ScheduleConfig>())?;
+        let run_id = TardisFuns::field.nanoid();
+        tardis::tokio::spawn(runner.run(run_id.clone(), TRIGGER_MANUAL));
+        Ok(run_id)
+    }
+
+    /// 查询工作流的一次运行中各任务的状态
+    pub async fn find_run(&self, run_id: &str) -> TardisResult<Vec<ScheduleJobRunResp>> {
+        let statuses = WorkflowRun::new(run_id, &self.funs.conf::<ScheduleConfig>()).get_statuses().await?;
+        if statuses.is_empty() {
+            return Err(self.funs.err().not_found("schedule_job", "find_run", &format!("run {run_id} is not found"), "404-schedule-run-not-found"));
+        }
+        let mut runs = statuses.into_iter().map(|(code, status)| ScheduleJobRunResp { code, status }).collect::<Vec<_>>();
+        runs.sort_by(|a, b| a.code.cmp(&b.code));
+        Ok(runs)
+    }
+
+    /// 在工作流的一次运行中从指定任务开始重新执行，该任务的上游任务须均已成功
+    pub async fn rerun_job(&self, run_id: &str, code: &str, repo: R, event: E) -> TardisResult<()> {
+        let job = self.get_job(code, &repo, "rerun").await?;
+        let schedule_config = self.funs.conf::<ScheduleConfig>();
+        let workflow_run = WorkflowRun::new(run_id, &schedule_config);
+        let statuses = workflow_run.get_statuses().await?;
+        if statuses.is_empty() {
+            return Err(self.funs.err().not_found("schedule_job", "rerun", &format!("run {run_id} is not found"), "404-schedule-run-not-found"));
+        }
+        if statuses.get(code) == Some(&ScheduleJobRunStatus::Running) {
+            return Err(self.funs.err().conflict("schedule_job", "rerun", &format!("job {code} is running in run {run_id}"), "409-schedule-job-running"));
+        }
+        if let Some(upstream_code) = job.depends_on.iter().find(|upstream_code| statuses.get(*upstream_code) != Some(&ScheduleJobRunStatus::Success)) {
+            return Err(self.funs.err().conflict(
+                "schedule_job",
+                "rerun",
+                &format!("upstream job {upstream_code} hasn't succeeded in run {run_id}"),
+                "409-schedule-job-upstream-not-succeeded",
+            ));
+        }
+        let mut codes = workflow::downstream_codes(code, &repo.get_all().await?);
+        codes.push(code.to_string());
+        workflow_run.reset(&codes).await?;
+        let runner = JobRunner::new(&job, repo, event, schedule_config)?;
+        tardis::tokio::spawn(runner.run(run_id.to_string(), TRIGGER_RERUN));
+        Ok(())
+    }
+
+    /// 查询任务之后的触发时间，暂停的任务和有上游任务的任务没有触发时间
+    pub async fn find_next_fire_times(&self, code: &str, size: usize, repo: R) -> TardisResult<Vec<DateTime<Utc>>> {
+        let job = self.get_job(code, &repo, "find_next_fire_times").await?;
+        if !job.is_fired_by_cron() {
+            return Ok(vec![]);
+        }
+        Ok(fire_times_after(&job, Utc::now()).take(size).collect())
+    }
+
+    /// 按任务的错过触发策略，补触发所有节点均未运行期间错过的触发
+    pub(crate) async fn catch_up_misfire(&self, job: &ScheduleJob, repo: R, event: E) -> TardisResult<()> {
+        if !job.is_fired_by_cron() || job.misfire_policy == ScheduleJobMisfirePolicy::Skip {
+            return Ok(());
+        }
+        let schedule_config = self.funs.conf::<ScheduleConfig>();
+        let lock_key = gen_distributed_lock_key(&job.code, &schedule_config);
+        let Some(last_fired) = get_last_fired(&lock_key).await? else {
+            return Ok(());
+        };
+        let now = Utc::now();
+        let missed = fire_times_after(job, last_fired).take_while(|time| *time < now).take(schedule_config.misfire_max_times as usize).count();
+        if missed == 0 {
+            return Ok(());
+        }
+        // the nodes restarting together find the same missed fires, only the one claiming them catches them up
+        let claim_key = format!("{lock_key}:misfire:{}", last_fired.timestamp_millis());
+        if !lock::set_nx_ex(&claim_key, "claimed", schedule_config.distributed_lock_expire_sec).await? {
+            return Ok(());
+        }
+        mark_fired(&lock_key, now).await?;
+        let times = match job.misfire_policy {
+            ScheduleJobMisfirePolicy::FireAll => missed,
+            _ => 1,
+        };
+        info!("catching up {times} of {missed} missed fires of schedule task {}", job.code);
+        let runner = JobRunner::new(job, repo, event, schedule_config)?;
+        for _ in 0..times {
+            runner.clone().run(TardisFuns::field.nanoid(), TRIGGER_MISFIRE).await;
+        }
+        Ok(())
+    }
+}
+
+/// 生成分布式锁的key
+fn gen_distributed_lock_key(code: &str, config: &ScheduleConfig) -> String {
+    format!("{}{}", config.distributed_lock_key_prefix, code)
+}
+
+/// 生成任务的调度规则
+fn build_schedule(job: &ScheduleJob) -> ScheduleDynBuilder {
+    let mut schedule_builder = job.cron.iter().filter_map(|cron| Cron::local_from_cron_expr(cron).ok()).fold(ScheduleDynBuilder::default(), ScheduleDynBuilder::or);
+    if let Some(enable_time) = job.enable_time {
+        schedule_builder = schedule_builder.after(enable_time);
+    }
+    if let Some(disable_time) = job.disable_time {
+        schedule_builder = schedule_builder.before(disable_time);
+    }
+    // 一个节点下一分钟内只能执行一次
+    schedule_builder.throttling(TimeDelta::minutes(1))
+}
+
+/// The fire times of the job after the given time, in ascending order
+fn fire_times_after(job: &ScheduleJob, from: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> {
+    let mut schedule = build_schedule(job).build();
+    schedule.forward_to(from);
+    std::iter::from_fn(move || schedule.next()).skip_while(move |time| *time <= from)
+}
+
+fn last_fired_key(lock_key: &str) -> String {
+    format!("{lock_key}:last_fired")
+}
+
+/// Record the time of the last fire, the missed fires are counted from it
+async fn mark_fired(lock_key: &str, time: DateTime<Utc>) -> TardisResult<()> {
+    TardisFuns::cache().set(&last_fired_key(lock_key), &time.to_rfc3339()).await
+}
+
+async fn get_last_fired(lock_key: &str) -> TardisResult<Option<DateTime<Utc>>> {
+    let last_fired = TardisFuns::cache().get(&last_fired_key(lock_key)).await?;
+    Ok(last_fired.and_then(|time| DateTime::parse_from_rfc3339(&time).ok()).map(|time| time.to_utc()))
+}
+
+/// Everything to run a job, shared by the scheduled fires, the manual runs, the catch-ups of the missed fires and the runs triggered by the upstream jobs
+#[derive(Clone)]
+struct JobRunner<R, E> {
+    code: String,
+    depends_on: Vec<String>,
+    callback_req: Arc<Request>,
+    lock_key: String,
+    retry: Option<ScheduleJobRetryPolicy>,
+    success_criteria: ScheduleJobSuccessCriteria,
+    concurrency_policy: ScheduleJobConcurrencyPolicy,
+    config: Arc<ScheduleConfig>,
+    repo: R,
+    event: E,
+}
+
+impl<R: Repository, E: EventComponent> JobRunner<R, E> {
+    fn new(job: &ScheduleJob, repo: R, event: E, config: Arc<ScheduleConfig>) -> TardisResult<Self> {
+        Ok(Self {
+            code: job.code.to_string(),
+            depends_on: job.depends_on.clone(),
+            callback_req: Arc::new(job.build_request()?),
+            lock_key: gen_distributed_lock_key(&job.code, &config),
+            retry: job.retry.clone(),
+            success_criteria: job.success_criteria.clone().unwrap_or_default(),
+            concurrency_policy: job.concurrency_policy,
+            config,
+            repo,
+            event,
+        })
+    }
+
+    /// Run a scheduled fire, each fire is run by only one of the nodes
+    async fn fire(&self) {
+        let cache_client = TardisFuns::cache();
+        // about set and setnx, see:
+        // 1. https://redis.io/commands/set/
+        // 2. https://redis.io/commands/setnx/
+        // At Redis version 2.6.12, setnx command is regarded as deprecated. see: https://redis.io/commands/setnx/
+        // "executing" could be any string now, it's just a placeholder
+        match cache_client.set_nx(&self.lock_key, "executing").await {
+            Ok(true) => {
+                // safety: it's ok to unwrap in this closure, scheduler will restart this job when after panic
+                let Ok(()) = cache_client.expire(&self.lock_key, self.config.distributed_lock_expire_sec as i64).await else {
+                    return;
+                };
+                if let Err(e) = mark_fired(&self.lock_key, Utc::now()).await {
+                    error!("cannot record the fire of schedule task {}, error: {e}", self.code);
+                }
+                // the lock above only prevents the nodes from running the same fire,
+                // the overlapping with a previous run which is still executing is controlled by the run lock
+                self.clone().run(TardisFuns::field.nanoid(), TRIGGER_SCHEDULE).await;
+            }
+            Ok(false) => {
+                trace!("schedule task {} is executed by other nodes, skip", self.code);
+            }
+            Err(e) => {
+                error!("cannot set lock to schedule task {}, error: {e}", self.code);
+            }
+        }
+    }
+
+    /// Run the job in the run of the given id, and trigger the downstream jobs whose upstream jobs all succeed in the run
+    fn run(self, run_id: String, trigger: &'static str) -> BoxFuture<'static, ()> {
+        async move {
+            let jobs = self.repo.get_all().await.unwrap_or_else(|e| {
+                error!("cannot find the downstream jobs of schedule task {}, error: {e}", self.code);
+                vec![]
+            });
+            let downstream_jobs = jobs.iter().filter(|job| job.depends_on.contains(&self.code)).collect::<Vec<_>>();
+            // not a job of any workflow
+            if self.depends_on.is_empty() && downstream_jobs.is_empty() {
+                self.execute(&run_id, trigger).await;
+                return;
+            }
+            let workflow_run = WorkflowRun::new(&run_id, &self.config);
+            if let Err(e) = workflow_run.set_status(&self.code, ScheduleJobRunStatus::Running).await {
+                error!("cannot set the status of schedule task {} in run {run_id}, error: {e}", self.code);
+            }
+            let status = self.execute(&run_id, trigger).await;
+            if let Err(e) = self.trigger_downstream(&workflow_run, &run_id, status, &jobs, &downstream_jobs).await {
+                error!("cannot trigger the downstream jobs of schedule task {} in run {run_id}, error: {e}", self.code);
+            }
+        }
+        .boxed()
+    }
+
+    async fn trigger_downstream(
+        &self,
+        workflow_run: &WorkflowRun,
+        run_id: &str,
+        status: ScheduleJobRunStatus,
+        jobs: &[ScheduleJob],
+        downstream_jobs: &[&ScheduleJob],
+    ) -> TardisResult<()> {
+        workflow_run.set_status(&self.code, status).await?;
+        if status != ScheduleJobRunStatus::Success {
+            // the failure is propagated to all the downstream jobs
+            return workflow_run.skip_downstream(&self.code, jobs).await;
+        }
+        let statuses = workflow_run.get_statuses().await?;
+        for downstream_job in downstream_jobs {
+            if !downstream_job.depends_on.iter().all(|upstream_code| statuses.get(upstream_code) == Some(&ScheduleJobRunStatus::Success)) {
+                continue;
+            }
+            if !workflow_run.claim_trigger(&downstream_job.code).await? {
+                continue;
+            }
+            if downstream_job.paused {
+                workflow_run.set_status(&downstream_job.code, ScheduleJobRunStatus::Skipped).await?;
+                workflow_run.skip_downstream(&downstream_job.code, jobs).await?;
+                continue;
+            }
+            let runner = JobRunner::new(downstream_job, self.repo.clone(), self.event.clone(), self.config.clone())?;
+            tardis::tokio::spawn(runner.run(run_id.to_string(), TRIGGER_UPSTREAM));
+        }
+        Ok(())
+    }
+
+    /// Run the job once, `trigger` is recorded in the log of the run
+    async fn execute(&self, run_id: &str, trigger: &str) -> ScheduleJobRunStatus {
+        let code = &self.code;
+        let run_lock = match RunLock::acquire(&self.lock_key, self.concurrency_policy, self.config.distributed_lock_expire_sec).await {
+            Ok(Some(run_lock)) => run_lock,
+            Ok(None) => {
+                trace!("schedule task {code} is still executing, skip");
+                return ScheduleJobRunStatus::Skipped;
+            }
+            Err(e) => {
+                error!("cannot set run lock to schedule task {code}, error: {e}");
+                return ScheduleJobRunStatus::Failed;
+            }
+        };
+        trace!("executing schedule task {code}");
+        // 1. write log exec start
+        self.event.notify_execute_start(code, run_id);
+        // 2. request webhook, stop if the run lock is lost
+        let (content, mut ext) = tardis::tokio::select! {
+            result = request_webhook(&self.callback_req, self.retry.as_ref(), &self.success_criteria, run_lock.token()) => result,
+            _ = run_lock.keep_alive() => (
+                "the run is cancelled since the lock is lost".to_string(),
+                serde_json::json!({ "fencing_token": run_lock.token(), "success": false }),
+            ),
+        };
+        run_lock.release().await;
+        ext["trigger"] = serde_json::json!(trigger);
+        let success = ext["success"].as_bool().unwrap_or_default();
+        // 3. write log exec end
+        self.event.notify_execute_end(code, run_id, content, ext);
+        trace!("executed schedule task {code}");
+        if success {
+            ScheduleJobRunStatus::Success
+        } else {
+            ScheduleJobRunStatus::Failed
+        }
+    }
+}
+
+/// Request the webhook and retry on failure, return the content of the last response and the extra info of the run
+async fn request_webhook(callback_req: &Request, retry: Option<&ScheduleJobRetryPolicy>, success_criteria: &ScheduleJobSuccessCriteria, fencing_token: u64) -> (String, Value) {
+    let max_retry_times = retry.map(|retry| retry.max_times).unwrap_or_default();
+    let mut retry_time = 0;
+    loop {
+        let mut req = callback_req.try_clone().expect("body should be a string");
+        req.headers_mut().insert(FENCING_TOKEN_HEADER, HeaderValue::from(fencing_token));
+        let (success, content, mut ext) = match TardisFuns::web_client().raw().execute(req).await {
+            Ok(resp) => {
+                let status_code = resp.status();
+                let remote_addr = resp.remote_addr().as_ref().map(SocketAddr::to_string);
+                let response_header: HashMap<String, String> = resp
+                    .headers()
+                    .into_iter()
+                    .filter_map(|(k, v)| {
+                        let v = v.to_str().ok()?.to_string();
+                        Some((k.to_string(), v))
+                    })
+                    .collect();
+                let ext = serde_json::json! {
+                    {
+                        "remote_addr": remote_addr,
+                        "status_code": status_code.to_string(),
+                        "headers": response_header
+                    }
+                };
+                let content = resp.text().await.unwrap_or_default();
+                (success_criteria.is_success(status_code.as_u16(), &content), content, ext)
+            }
+            Err(e) => (false, e.to_string(), serde_json::json!({})),
+        };
+        if success || retry_time >= max_retry_times {
+            ext["attempts"] = serde_json::json!(retry_time + 1);
+            ext["success"] = serde_json::json!(success);
+            ext["fencing_token"] = serde_json::json!(fencing_token);
+            return (content, ext);
+        }
+        retry_time += 1;
+        let backoff = retry.map(|retry| retry.backoff(retry_time)).unwrap_or_default();
+        debug!("schedule task request failed, retry {retry_time}/{max_retry_times} after {backoff:?}");
+        tardis::tokio::time::sleep(backoff).await;
+    }
 }
@@ -0,0 +1,86 @@
+use std::collections::{HashMap, HashSet};
+
+use tardis::{basic::result::TardisResult, TardisFuns};
+
+use crate::{
+    dto::schedule_job_dto::{ScheduleJob, ScheduleJobRunStatus},
+    schedule_config::ScheduleConfig,
+};
+
+/// State of a run of the workflow, i.e. the status of each job in the run
+///
+/// The state is kept in the cache and shared by the nodes, the downstream jobs are triggered according to it,
+/// and the failed jobs can be re-run within the run before it expires.
+pub(crate) struct WorkflowRun {
+    key: String,
+    expire_sec: u32,
+}
+
+impl WorkflowRun {
+    pub(crate) fn new(run_id: &str, config: &ScheduleConfig) -> Self {
+        Self {
+            key: format!("{}{run_id}", config.workflow_run_key_prefix),
+            expire_sec: config.workflow_run_expire_sec,
+        }
+    }
+
+    pub(crate) async fn set_status(&self, code: &str, status: ScheduleJobRunStatus) -> TardisResult<()> {
+        let cache_client = TardisFuns::cache();
+        cache_client.hset(&self.key, code, &TardisFuns::json.obj_to_string(&status)?).await?;
+        cache_client.expire(&self.key, self.expire_sec as i64).await
+    }
+
+    pub(crate) async fn get_statuses(&self) -> TardisResult<HashMap<String, ScheduleJobRunStatus>> {
+        let statuses = TardisFuns::cache().hgetall(&self.key).await?;
+        Ok(statuses.into_iter().filter_map(|(code, status)| Some((code, TardisFuns::json.str_to_obj(&status).ok()?))).collect())
+    }
+
+    /// Claim the trigger of the job, the job is triggered only once even if its upstream jobs finish on different nodes at the same time
+    pub(crate) async fn claim_trigger(&self, code: &str) -> TardisResult<bool> {
+        let cache_client = TardisFuns::cache();
+        let trigger_key = format!("{}:trigger:{code}", self.key);
+        if !cache_client.set_nx(&trigger_key, "triggered").await? {
+            return Ok(false);
+        }
+        cache_client.expire(&trigger_key, self.expire_sec as i64).await?;
+        Ok(true)
+    }
+
+    /// Clear the statuses and the triggers of the jobs, so that they can run again in the run
+    pub(crate) async fn reset(&self, codes: &[String]) -> TardisResult<()> {
+        let cache_client = TardisFuns::cache();
+        for code in codes {
+            cache_client.hdel(&self.key, code).await?;
+            cache_client.del(&format!("{}:trigger:{code}", self.key)).await?;
+        }
+        Ok(())
+    }
+
+    /// Mark the jobs depending on the given job directly or indirectly as skipped, except those already having a status
+    pub(crate) async fn skip_downstream(&self, code: &str, jobs: &[ScheduleJob]) -> TardisResult<()> {
+        let statuses = self.get_statuses().await?;
+        for downstream_code in downstream_codes(code, jobs) {
+            if !statuses.contains_key(&downstream_code) {
+                self.set_status(&downstream_code, ScheduleJobRunStatus::Skipped).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Codes of the jobs depending on the given job directly or indirectly
+pub(crate) fn downstream_codes(code: &str, jobs: &[ScheduleJob]) -> Vec<String> {
+    let mut visited = HashSet::new();
+    let mut result = vec![];
+    let mut pending = vec![code.to_string()];
+    while let Some(upstream_code) = pending.pop() {
+        for job in jobs.iter().filter(|job| job.depends_on.contains(&upstream_code)) {
+            let job_code = job.code.to_string();
+            if visited.insert(job_code.clone()) {
+                result.push(job_code.clone());
+                pending.push(job_code);
+            }
+        }
+    }
+    result
+}
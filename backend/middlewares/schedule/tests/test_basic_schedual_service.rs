@@ -1,15 +1,16 @@
 use bios_mw_schedule::{
-    dto::schedule_job_dto::ScheduleJob,
+    dto::schedule_job_dto::{ScheduleJob, ScheduleJobMisfirePolicy, ScheduleJobRetryPolicy, ScheduleJobRunStatus, ScheduleJobSuccessCriteria},
     schedule_config::ScheduleConfig,
     schedule_constants::DOMAIN_CODE,
-    serv::schedule_job_serv_v2::{add_or_modify, delete},
+    serv::schedule_job_serv_v2::{add_or_modify, catch_up_misfire, delete, find_next_fire_times, find_run, pause, rerun, resume, run_now},
 };
 use std::{collections::VecDeque, env, sync::atomic::Ordering, time::Duration};
 
 use tardis::{
     basic::result::TardisResult,
-    chrono::{self, Utc},
+    chrono::{self, DurationRound, Utc},
     rand::random,
+    serde_json,
     test::test_container::TardisTestContainer,
     tokio, TardisFuns, TardisFunsInst,
 };
@@ -46,6 +47,10 @@ async fn test_basic_schedual_service() -> TardisResult<()> {
     let config = ScheduleConfig::default();
 
     test_add_delete(&test_env).await;
+    test_retry(&test_env).await;
+    test_pause_and_run_now(&test_env).await;
+    test_workflow(&test_env).await;
+    test_misfire(&test_env).await;
     // test_random_ops(&config, &test_env).await;
     drop(holder);
     Ok(())
@@ -73,6 +78,169 @@ async fn test_add_delete(test_env: &TestEnv) {
     delete(code, funs, Default::default()).await.expect("fail to delete schedule task");
 }
 
+async fn test_retry(test_env: &TestEnv) {
+    test_env.counter.store(0, Ordering::SeqCst);
+    let code = "retry-flaky";
+    add_or_modify(
+        ScheduleJob {
+            code: code.into(),
+            cron: vec!["1/2 * * * * *".to_string()],
+            callback_url: "http://127.0.0.1:8080/callback/flaky".into(),
+            timeout_sec: Some(5),
+            retry: Some(ScheduleJobRetryPolicy {
+                max_times: 3,
+                interval_sec: 1,
+                backoff_factor: 2,
+                max_interval_sec: 60,
+            }),
+            // the failures of tardis apis are responded with the code in the body
+            success_criteria: Some(ScheduleJobSuccessCriteria {
+                json_path: Some("code".to_string()),
+                json_path_values: vec![serde_json::json!("200")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        funs(),
+        Default::default(),
+    )
+    .await
+    .expect("fail to modify");
+    // fired once in a minute, and succeeded at the second retry after 1 + 2 seconds
+    tokio::time::sleep(Duration::from_secs(7)).await;
+    assert_eq!(test_env.counter.load(Ordering::SeqCst), 3);
+    delete(code, funs(), Default::default()).await.expect("fail to delete schedule task");
+}
+
+async fn test_pause_and_run_now(test_env: &TestEnv) {
+    test_env.counter.store(0, Ordering::SeqCst);
+    let code = "pause-run-now";
+    add_or_modify(
+        ScheduleJob {
+            code: code.into(),
+            cron: vec!["1/2 * * * * *".to_string()],
+            callback_url: "http://127.0.0.1:8080/callback/inc".into(),
+            paused: true,
+            ..Default::default()
+        },
+        funs(),
+        Default::default(),
+    )
+    .await
+    .expect("fail to modify");
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(test_env.counter.load(Ordering::SeqCst), 0);
+    assert!(find_next_fire_times(code, 3, funs(), Default::default()).await.expect("fail to find next fire times").is_empty());
+
+    // the paused job can still be run manually
+    run_now(code, funs(), Default::default()).await.expect("fail to run now");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(test_env.counter.load(Ordering::SeqCst), 1);
+
+    resume(code, funs(), Default::default()).await.expect("fail to resume");
+    let next_fire_times = find_next_fire_times(code, 3, funs(), Default::default()).await.expect("fail to find next fire times");
+    assert_eq!(next_fire_times.len(), 3);
+    // fired once in a minute
+    assert!(next_fire_times.windows(2).all(|times| times[1] - times[0] >= chrono::TimeDelta::minutes(1)));
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(test_env.counter.load(Ordering::SeqCst), 2);
+
+    pause(code, funs(), Default::default()).await.expect("fail to pause");
+    run_now(code, funs(), Default::default()).await.expect("fail to run now");
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(test_env.counter.load(Ordering::SeqCst), 3);
+    assert!(run_now("not-exist", funs(), Default::default()).await.is_err());
+    delete(code, funs(), Default::default()).await.expect("fail to delete schedule task");
+}
+
+async fn test_workflow(test_env: &TestEnv) {
+    test_env.counter.store(0, Ordering::SeqCst);
+    let new_job = |code: &str, callback: &str, depends_on: &[&str]| ScheduleJob {
+        code: code.into(),
+        // only fired manually in this test
+        cron: vec!["0 0 0 1 1 *".to_string()],
+        callback_url: format!("http://127.0.0.1:8080/callback/{callback}"),
+        success_criteria: Some(ScheduleJobSuccessCriteria {
+            json_path: Some("code".to_string()),
+            json_path_values: vec![serde_json::json!("200")],
+            ..Default::default()
+        }),
+        depends_on: depends_on.iter().map(|code| code.to_string()).collect(),
+        ..Default::default()
+    };
+    add_or_modify(new_job("wf-extract", "inc", &[]), funs(), Default::default()).await.expect("fail to modify");
+    add_or_modify(new_job("wf-transform", "flaky", &["wf-extract"]), funs(), Default::default()).await.expect("fail to modify");
+    add_or_modify(new_job("wf-load", "inc", &["wf-transform"]), funs(), Default::default()).await.expect("fail to modify");
+    assert!(add_or_modify(new_job("wf-extract", "inc", &["wf-load"]), funs(), Default::default()).await.is_err());
+    assert!(add_or_modify(new_job("wf-other", "inc", &["not-exist"]), funs(), Default::default()).await.is_err());
+    assert!(delete("wf-extract", funs(), Default::default()).await.is_err());
+    // the downstream jobs are not fired by cron
+    assert!(find_next_fire_times("wf-load", 3, funs(), Default::default()).await.expect("fail to find next fire times").is_empty());
+
+    // the transform fails at the first call, and the load is skipped
+    let run_id = run_now("wf-extract", funs(), Default::default()).await.expect("fail to run now");
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let statuses = find_run(&run_id).await.expect("fail to find run").into_iter().map(|run| (run.code, run.status)).collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        vec![
+            ("wf-extract".to_string(), ScheduleJobRunStatus::Success),
+            ("wf-load".to_string(), ScheduleJobRunStatus::Skipped),
+            ("wf-transform".to_string(), ScheduleJobRunStatus::Failed),
+        ]
+    );
+    assert_eq!(test_env.counter.load(Ordering::SeqCst), 2);
+
+    // re-run from the failed job within the same run
+    assert!(rerun(&run_id, "wf-load", funs(), Default::default()).await.is_err());
+    rerun(&run_id, "wf-transform", funs(), Default::default()).await.expect("fail to rerun");
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let statuses = find_run(&run_id).await.expect("fail to find run");
+    assert_eq!(statuses.len(), 3);
+    assert!(statuses.iter().all(|run| run.status == ScheduleJobRunStatus::Success));
+    assert_eq!(test_env.counter.load(Ordering::SeqCst), 4);
+
+    for code in ["wf-load", "wf-transform", "wf-extract"] {
+        delete(code, funs(), Default::default()).await.expect("fail to delete schedule task");
+    }
+}
+
+async fn test_misfire(test_env: &TestEnv) {
+    let minute = chrono::TimeDelta::minutes(1);
+    let now = Utc::now().duration_trunc(minute).expect("fail to truncate time");
+    for (code, misfire_policy, expected) in [
+        ("misfire-skip", ScheduleJobMisfirePolicy::Skip, 0),
+        ("misfire-fire-once", ScheduleJobMisfirePolicy::FireOnce, 1),
+        ("misfire-fire-all", ScheduleJobMisfirePolicy::FireAll, 4),
+    ] {
+        test_env.counter.store(0, Ordering::SeqCst);
+        add_or_modify(
+            ScheduleJob {
+                code: code.into(),
+                // fired at the start of every minute, and disabled before the current minute so that it's not fired during the test
+                cron: vec!["0 * * * * *".to_string()],
+                callback_url: "http://127.0.0.1:8080/callback/inc".into(),
+                disable_time: Some(now - chrono::TimeDelta::seconds(30)),
+                misfire_policy,
+                ..Default::default()
+            },
+            funs(),
+            Default::default(),
+        )
+        .await
+        .expect("fail to modify");
+        // simulate that all the nodes were down since 5 minutes ago, the fires of the last 4 minutes are missed
+        let last_fired_key = format!("{}{code}:last_fired", funs().conf::<ScheduleConfig>().distributed_lock_key_prefix);
+        TardisFuns::cache().set(&last_fired_key, &(now - minute * 5).to_rfc3339()).await.expect("fail to set last fired time");
+        catch_up_misfire(code, funs(), Default::default()).await.expect("fail to catch up misfire");
+        assert_eq!(test_env.counter.load(Ordering::SeqCst), expected);
+        // the missed fires are caught up only once
+        catch_up_misfire(code, funs(), Default::default()).await.expect("fail to catch up misfire");
+        assert_eq!(test_env.counter.load(Ordering::SeqCst), expected);
+        delete(code, funs(), Default::default()).await.expect("fail to delete schedule task");
+    }
+}
+
 async fn test_random_ops(config: &ScheduleConfig, test_env: &TestEnv) {
     test_env.counter.store(0, Ordering::SeqCst);
     let mut tasks = VecDeque::<String>::new();
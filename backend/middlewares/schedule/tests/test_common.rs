@@ -19,7 +19,7 @@ use bios_mw_schedule::{
 use bios_spi_kv::kv_initializer;
 use bios_spi_log::log_initializer;
 use tardis::{
-    basic::{dto::TardisContext, field::TrimString, result::TardisResult},
+    basic::{dto::TardisContext, error::TardisError, field::TrimString, result::TardisResult},
     web::{
         poem_openapi,
         web_resp::{TardisApiResult, TardisResp, Void},
@@ -44,6 +44,17 @@ impl CallbackApi {
         tardis::log::info!("callback: inc to {counter}");
         TardisResp::ok(Void {})
     }
+
+    /// Fail unless it's the third call, for testing the retries
+    #[oai(path = "/flaky", method = "get")]
+    pub async fn flaky(&self) -> TardisApiResult<Void> {
+        let counter = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        tardis::log::info!("callback: flaky called {counter} times");
+        if counter % 3 != 0 {
+            return TardisResp::err(TardisError::internal_error("flaky callback failed", "500-schedule-test-flaky"));
+        }
+        TardisResp::ok(Void {})
+    }
 }
 #[allow(dead_code)]
 pub async fn init_tardis() -> TardisResult<()> {
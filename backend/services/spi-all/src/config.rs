@@ -8,6 +8,8 @@ use bios_spi_search::search_config::SearchConfig;
 use bios_spi_stats::stats_config::StatsConfig;
 use tardis::serde::{Deserialize, Serialize};
 
+pub const DOMAIN_CODE: &str = "spi-all";
+
 #[derive(Default, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct BiosConfig {
@@ -20,3 +22,31 @@ pub struct BiosConfig {
     pub search: SearchConfig,
     pub stats: StatsConfig,
 }
+
+/// Configuration of the ``spi-all`` service itself
+///
+/// ``spi-all`` 服务自身的配置
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpiAllConfig {
+    pub memory: SpiAllMemoryConfig,
+}
+
+/// Bind the SPI services to the in-memory backend on startup
+///
+/// 启动时将SPI服务绑定到内存后端
+///
+/// When enabled, an in-memory backend service is added to cache, kv, log, search and stats and bound to ``app_tenant_ids``, so that no external storage is needed by these SPIs.
+/// The rbum registry (backend services and their bindings) still requires PostgreSQL and Redis configured in ``fw.db`` and ``fw.cache``.
+///
+/// 启用后，会为cache、kv、log、search及stats添加内存后端服务并绑定到``app_tenant_ids``，这些SPI不再需要外部存储。
+/// rbum注册中心（后端服务及其绑定关系）仍然需要在``fw.db``及``fw.cache``中配置PostgreSQL及Redis。
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpiAllMemoryConfig {
+    pub enabled: bool,
+    /// The app or tenant ids (``ctx.ak`` of the request) bound to the in-memory backend service
+    ///
+    /// 绑定到内存后端服务的应用或租户id（请求的``ctx.ak``）
+    pub app_tenant_ids: Vec<String>,
+}
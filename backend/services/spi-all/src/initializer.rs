@@ -1,16 +1,30 @@
+use bios_basic::rbum::dto::rbum_filer_dto::RbumBasicFilterReq;
 use bios_basic::rbum::rbum_config::RbumConfig;
 use bios_basic::rbum::rbum_initializer;
-use bios_spi_cache::cache_initializer;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_basic::rbum::serv::rbum_kind_serv::RbumKindServ;
+use bios_basic::spi::dto::spi_bs_dto::{SpiBsAddReq, SpiBsFilterReq};
+use bios_basic::spi::serv::spi_bs_serv::SpiBsServ;
+use bios_basic::spi::spi_constants;
+use bios_spi_cache::{cache_constants, cache_initializer};
 
-use bios_spi_kv::kv_initializer;
-use bios_spi_log::log_initializer;
+use bios_spi_kv::{kv_constants, kv_initializer};
+use bios_spi_log::{log_constants, log_initializer};
 use bios_spi_object::object_initializer;
 use bios_spi_plugin::plugin_initializer;
 use bios_spi_reldb::reldb_initializer;
-use bios_spi_search::search_initializer;
-use bios_spi_stats::stats_initializer;
+use bios_spi_search::{search_constants, search_initializer};
+use bios_spi_stats::{stats_constants, stats_initializer};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
 use tardis::basic::result::TardisResult;
+use tardis::log::info;
 use tardis::web::web_server::TardisWebServer;
+use tardis::TardisFuns;
+
+use crate::config::{SpiAllConfig, SpiAllMemoryConfig, DOMAIN_CODE};
+
+const MEMORY_BS_NAME: &str = "spi-all-memory";
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     rbum_initializer::init("bios-spi", RbumConfig::default()).await?;
@@ -23,5 +37,71 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     search_initializer::init(web_server).await?;
     stats_initializer::init(web_server).await?;
     // conf_initializer::init(web_server).await?;
+    let config = TardisFuns::cs_config::<SpiAllConfig>(DOMAIN_CODE);
+    if config.memory.enabled {
+        init_memory_bs(&config.memory).await?;
+    }
+    Ok(())
+}
+
+/// Add an in-memory backend service to each SPI that supports it and bind it to the configured apps or tenants
+async fn init_memory_bs(config: &SpiAllMemoryConfig) -> TardisResult<()> {
+    let ctx = TardisContext {
+        own_paths: "".to_string(),
+        ak: "_".to_string(),
+        owner: "".to_string(),
+        ..Default::default()
+    };
+    for domain_code in [
+        cache_constants::DOMAIN_CODE,
+        kv_constants::DOMAIN_CODE,
+        log_constants::DOMAIN_CODE,
+        search_constants::DOMAIN_CODE,
+        stats_constants::DOMAIN_CODE,
+    ] {
+        let mut funs = TardisFuns::inst_with_db_conn(domain_code.to_string(), None);
+        let kind_id = RbumKindServ::get_rbum_kind_id_by_code(spi_constants::SPI_MEMORY_KIND_CODE, &funs)
+            .await?
+            .ok_or_else(|| funs.err().not_found("spi_all", "init_memory_bs", &format!("not found memory kind of {domain_code}"), "404-spi-kind-not-exist"))?;
+        funs.begin().await?;
+        let exist_bs = SpiBsServ::find_one_item(
+            &SpiBsFilterReq {
+                basic: RbumBasicFilterReq {
+                    name: Some(MEMORY_BS_NAME.to_string()),
+                    ..Default::default()
+                },
+                kind_id: Some(kind_id.clone()),
+                domain_code: Some(domain_code.to_string()),
+                ..Default::default()
+            },
+            &funs,
+            &ctx,
+        )
+        .await?;
+        let bs_id = if let Some(exist_bs) = exist_bs {
+            exist_bs.id
+        } else {
+            SpiBsServ::add_item(
+                &mut SpiBsAddReq {
+                    name: TrimString(MEMORY_BS_NAME.to_string()),
+                    kind_id: TrimString(kind_id),
+                    conn_uri: format!("memory://{DOMAIN_CODE}"),
+                    ak: TrimString("".to_string()),
+                    sk: TrimString("".to_string()),
+                    ext: "".to_string(),
+                    private: false,
+                    disabled: None,
+                },
+                &funs,
+                &ctx,
+            )
+            .await?
+        };
+        for app_tenant_id in &config.app_tenant_ids {
+            SpiBsServ::add_rel(&bs_id, app_tenant_id, &funs, &ctx).await?;
+        }
+        funs.commit().await?;
+        info!("[BIOS.SPI-ALL] In-memory backend service of {} bound to {:?}", domain_code, config.app_tenant_ids);
+    }
     Ok(())
 }
@@ -1,4 +1,4 @@
-use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_constants, spi_funs::SpiBsInst, spi_initializer};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     log::info,
@@ -27,7 +27,10 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 }
 
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    #[cfg(feature = "spi-redis")]
     spi_initializer::add_kind(cache_constants::SPI_REDIS_KIND_CODE, funs, ctx).await?;
+    #[cfg(feature = "spi-memory")]
+    spi_initializer::add_kind(spi_constants::SPI_MEMORY_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
@@ -41,6 +44,8 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
     let inst = match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-redis")]
         cache_constants::SPI_REDIS_KIND_CODE => serv::redis::cache_redis_initializer::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => serv::memory::cache_memory_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }?;
     info!("[BIOS.Cache] Fun [{}]({}) initialized", bs_cert.kind_code, bs_cert.conn_uri);
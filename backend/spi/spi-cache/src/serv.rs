@@ -1,3 +1,5 @@
 pub mod cache_proc_serv;
+#[cfg(feature = "spi-memory")]
+pub mod memory;
 #[cfg(feature = "spi-redis")]
 pub mod redis;
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 
 use tardis::basic::result::TardisResult;
@@ -8,6 +9,9 @@ use crate::dto::cache_proc_dto::*;
 use crate::{cache_constants, cache_initializer};
 use bios_basic::spi_dispatch_service;
 
+#[cfg(feature = "spi-memory")]
+use super::memory;
+#[cfg(feature = "spi-redis")]
 use super::redis;
 spi_dispatch_service! {
     @mgr: true,
@@ -15,6 +19,8 @@ spi_dispatch_service! {
     @dispatch: {
         #[cfg(feature = "spi-redis")]
         cache_constants::SPI_REDIS_KIND_CODE => redis::cache_redis_proc_serv,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::cache_memory_proc_serv,
     },
     @method: {
         set(req: &KvReq) -> TardisResult<()>;
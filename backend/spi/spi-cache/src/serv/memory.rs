@@ -0,0 +1,2 @@
+pub mod cache_memory_initializer;
+pub mod cache_memory_proc_serv;
@@ -0,0 +1,86 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::basic::{dto::TardisContext, result::TardisResult};
+
+#[derive(Default)]
+pub struct CacheMemoryStore {
+    pub(crate) items: HashMap<String, CacheMemoryItem>,
+}
+
+pub(crate) struct CacheMemoryItem {
+    pub(crate) value: CacheMemoryValue,
+    pub(crate) expire_at: Option<Instant>,
+}
+
+pub(crate) enum CacheMemoryValue {
+    String(String),
+    List(VecDeque<String>),
+    Hash(CacheMemoryHash),
+    Bitmap(Vec<u8>),
+}
+
+/// Hash that keeps the insertion order of the fields, like the small hash of redis
+#[derive(Default, Clone)]
+pub(crate) struct CacheMemoryHash(Vec<(String, String)>);
+
+impl CacheMemoryHash {
+    pub(crate) fn get(&self, field: &str) -> Option<&String> {
+        self.0.iter().find(|(f, _)| f == field).map(|(_, v)| v)
+    }
+
+    pub(crate) fn contains_key(&self, field: &str) -> bool {
+        self.get(field).is_some()
+    }
+
+    pub(crate) fn insert(&mut self, field: String, value: String) {
+        if let Some((_, v)) = self.0.iter_mut().find(|(f, _)| *f == field) {
+            *v = value;
+        } else {
+            self.0.push((field, value));
+        }
+    }
+
+    pub(crate) fn remove(&mut self, field: &str) {
+        self.0.retain(|(f, _)| f != field);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &(String, String)> {
+        self.0.iter()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl CacheMemoryItem {
+    pub(crate) fn new(value: CacheMemoryValue, exp_sec: Option<u64>) -> Self {
+        CacheMemoryItem {
+            value,
+            expire_at: exp_sec.map(|exp_sec| Instant::now() + Duration::from_secs(exp_sec)),
+        }
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.expire_at.map(|expire_at| expire_at <= Instant::now()).unwrap_or(false)
+    }
+}
+
+impl CacheMemoryStore {
+    /// Remove expired items lazily, like the passive expiration of redis
+    pub(crate) fn clean_expired(&mut self, key: &str) {
+        if self.items.get(key).map(|item| item.is_expired()).unwrap_or(false) {
+            self.items.remove(key);
+        }
+    }
+}
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    spi_initializer::common_memory::init::<CacheMemoryStore>(bs_cert, ctx, mgr).await
+}
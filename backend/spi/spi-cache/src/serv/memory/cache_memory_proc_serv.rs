@@ -0,0 +1,297 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_memory::SpiMemoryClient};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    TardisFunsInst,
+};
+
+use crate::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+
+use super::cache_memory_initializer::{CacheMemoryHash, CacheMemoryItem, CacheMemoryStore, CacheMemoryValue};
+
+fn wrong_type(key: &str) -> TardisError {
+    TardisError::bad_request(&format!("Operation against key {key} holding the wrong kind of value"), "400-spi-cache-wrong-type")
+}
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<CacheMemoryStore> {
+    inst.inst::<SpiMemoryClient<CacheMemoryStore>>().0
+}
+
+fn get_string<'a>(store: &'a mut CacheMemoryStore, key: &str) -> TardisResult<Option<&'a String>> {
+    store.clean_expired(key);
+    match store.items.get(key).map(|item| &item.value) {
+        None => Ok(None),
+        Some(CacheMemoryValue::String(value)) => Ok(Some(value)),
+        Some(_) => Err(wrong_type(key)),
+    }
+}
+
+fn get_list<'a>(store: &'a mut CacheMemoryStore, key: &str) -> TardisResult<Option<&'a VecDeque<String>>> {
+    store.clean_expired(key);
+    match store.items.get(key).map(|item| &item.value) {
+        None => Ok(None),
+        Some(CacheMemoryValue::List(value)) => Ok(Some(value)),
+        Some(_) => Err(wrong_type(key)),
+    }
+}
+
+fn get_hash<'a>(store: &'a mut CacheMemoryStore, key: &str) -> TardisResult<Option<&'a CacheMemoryHash>> {
+    store.clean_expired(key);
+    match store.items.get(key).map(|item| &item.value) {
+        None => Ok(None),
+        Some(CacheMemoryValue::Hash(value)) => Ok(Some(value)),
+        Some(_) => Err(wrong_type(key)),
+    }
+}
+
+fn get_bitmap<'a>(store: &'a mut CacheMemoryStore, key: &str) -> TardisResult<Option<&'a Vec<u8>>> {
+    store.clean_expired(key);
+    match store.items.get(key).map(|item| &item.value) {
+        None => Ok(None),
+        Some(CacheMemoryValue::Bitmap(value)) => Ok(Some(value)),
+        Some(_) => Err(wrong_type(key)),
+    }
+}
+
+fn get_or_insert_mut<'a>(store: &'a mut CacheMemoryStore, key: &str, default_value: impl FnOnce() -> CacheMemoryValue) -> &'a mut CacheMemoryValue {
+    store.clean_expired(key);
+    &mut store.items.entry(key.to_string()).or_insert_with(|| CacheMemoryItem::new(default_value(), None)).value
+}
+
+fn count_bits(bitmap: &[u8], start: usize, end: usize) -> u32 {
+    (start..=end).filter(|offset| bitmap.get(offset / 8).map(|byte| byte & (0x80 >> (offset % 8)) != 0).unwrap_or(false)).count() as u32
+}
+
+pub async fn set(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    store.items.insert(req.key.to_string(), CacheMemoryItem::new(CacheMemoryValue::String(req.value.clone()), None));
+    Ok(())
+}
+
+pub async fn set_ex(req: &KvWithExReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    store.items.insert(req.key.to_string(), CacheMemoryItem::new(CacheMemoryValue::String(req.value.clone()), Some(req.exp_sec)));
+    Ok(())
+}
+
+pub async fn set_nx(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let mut store = get_client(inst).write().await;
+    store.clean_expired(&req.key);
+    if store.items.contains_key(&*req.key) {
+        return Ok(false);
+    }
+    store.items.insert(req.key.to_string(), CacheMemoryItem::new(CacheMemoryValue::String(req.value.clone()), None));
+    Ok(true)
+}
+
+pub async fn get(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_string(&mut store, &req.key)?.cloned())
+}
+
+pub async fn getset(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    let mut store = get_client(inst).write().await;
+    let old_value = get_string(&mut store, &req.key)?.cloned();
+    store.items.insert(req.key.to_string(), CacheMemoryItem::new(CacheMemoryValue::String(req.value.clone()), None));
+    Ok(old_value)
+}
+
+pub async fn incr(req: &KIncrReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
+    let mut store = get_client(inst).write().await;
+    let value = get_string(&mut store, &req.key)?
+        .map(|value| value.parse::<i64>().map_err(|_| TardisError::bad_request("Value is not an integer or out of range", "400-spi-cache-not-integer")))
+        .transpose()?
+        .unwrap_or(0)
+        + req.delta;
+    store.clean_expired(&req.key);
+    match store.items.get_mut(&*req.key) {
+        // Keep the expiration time like redis
+        Some(item) => item.value = CacheMemoryValue::String(value.to_string()),
+        None => {
+            store.items.insert(req.key.to_string(), CacheMemoryItem::new(CacheMemoryValue::String(value.to_string()), None));
+        }
+    }
+    Ok(value)
+}
+
+pub async fn del(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    store.items.remove(&*req.key);
+    Ok(())
+}
+
+pub async fn exists(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let mut store = get_client(inst).write().await;
+    store.clean_expired(&req.key);
+    Ok(store.items.contains_key(&*req.key))
+}
+
+pub async fn expire(req: &ExpReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    store.clean_expired(&req.key);
+    if let Some(item) = store.items.get_mut(&*req.key) {
+        item.expire_at = Some(Instant::now() + Duration::from_secs(req.exp_sec));
+    }
+    Ok(())
+}
+
+pub async fn ttl(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let mut store = get_client(inst).write().await;
+    store.clean_expired(&req.key);
+    // Consistent with the redis implementation, which converts -2 (not exist) and -1 (no expiration) to u64
+    Ok(match store.items.get(&*req.key) {
+        None => -2_i64 as u64,
+        Some(CacheMemoryItem { expire_at: None, .. }) => -1_i64 as u64,
+        Some(CacheMemoryItem { expire_at: Some(expire_at), .. }) => expire_at.saturating_duration_since(Instant::now()).as_secs_f64().round() as u64,
+    })
+}
+
+// list operations
+
+pub async fn lpush(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    match get_or_insert_mut(&mut store, &req.key, || CacheMemoryValue::List(VecDeque::new())) {
+        CacheMemoryValue::List(list) => list.push_front(req.value.clone()),
+        _ => return Err(wrong_type(&req.key)),
+    }
+    Ok(())
+}
+
+pub async fn lrangeall(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_list(&mut store, &req.key)?.map(|list| list.iter().cloned().collect()).unwrap_or_default())
+}
+
+pub async fn llen(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_list(&mut store, &req.key)?.map(|list| list.len() as u64).unwrap_or(0))
+}
+
+// hash operations
+
+pub async fn hget(req: &KfReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_hash(&mut store, &req.key)?.and_then(|hash| hash.get(&*req.field).cloned()))
+}
+
+pub async fn hset(req: &KfvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    match get_or_insert_mut(&mut store, &req.key, || CacheMemoryValue::Hash(CacheMemoryHash::default())) {
+        CacheMemoryValue::Hash(hash) => hash.insert(req.field.to_string(), req.value.clone()),
+        _ => return Err(wrong_type(&req.key)),
+    };
+    Ok(())
+}
+
+pub async fn hset_nx(req: &KfvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let mut store = get_client(inst).write().await;
+    match get_or_insert_mut(&mut store, &req.key, || CacheMemoryValue::Hash(CacheMemoryHash::default())) {
+        CacheMemoryValue::Hash(hash) => {
+            if hash.contains_key(&*req.field) {
+                Ok(false)
+            } else {
+                hash.insert(req.field.to_string(), req.value.clone());
+                Ok(true)
+            }
+        }
+        _ => Err(wrong_type(&req.key)),
+    }
+}
+
+pub async fn hdel(req: &KfReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    get_hash(&mut store, &req.key)?;
+    let is_empty = match store.items.get_mut(&*req.key).map(|item| &mut item.value) {
+        Some(CacheMemoryValue::Hash(hash)) => {
+            hash.remove(&*req.field);
+            hash.is_empty()
+        }
+        _ => false,
+    };
+    // Redis removes the key when the hash becomes empty
+    if is_empty {
+        store.items.remove(&*req.key);
+    }
+    Ok(())
+}
+
+pub async fn hincr(req: &KfIncrReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
+    let mut store = get_client(inst).write().await;
+    match get_or_insert_mut(&mut store, &req.key, || CacheMemoryValue::Hash(CacheMemoryHash::default())) {
+        CacheMemoryValue::Hash(hash) => {
+            let value = hash
+                .get(&*req.field)
+                .map(|value| value.parse::<i64>().map_err(|_| TardisError::bad_request("Hash value is not an integer", "400-spi-cache-not-integer")))
+                .transpose()?
+                .unwrap_or(0)
+                + req.delta;
+            hash.insert(req.field.to_string(), value.to_string());
+            Ok(value)
+        }
+        _ => Err(wrong_type(&req.key)),
+    }
+}
+
+pub async fn hexists(req: &KfReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_hash(&mut store, &req.key)?.map(|hash| hash.contains_key(&*req.field)).unwrap_or(false))
+}
+
+pub async fn hkeys(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_hash(&mut store, &req.key)?.map(|hash| hash.iter().map(|(f, _)| f.clone()).collect()).unwrap_or_default())
+}
+
+pub async fn hvals(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_hash(&mut store, &req.key)?.map(|hash| hash.iter().map(|(_, v)| v.clone()).collect()).unwrap_or_default())
+}
+
+pub async fn hgetall(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<HashMap<String, String>> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_hash(&mut store, &req.key)?.map(|hash| hash.iter().cloned().collect()).unwrap_or_default())
+}
+
+pub async fn hlen(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_hash(&mut store, &req.key)?.map(|hash| hash.len() as u64).unwrap_or(0))
+}
+
+// bitmap operations
+
+pub async fn setbit(req: &KbvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let mut store = get_client(inst).write().await;
+    match get_or_insert_mut(&mut store, &req.key, || CacheMemoryValue::Bitmap(Vec::new())) {
+        CacheMemoryValue::Bitmap(bitmap) => {
+            let (idx, mask) = ((req.offset / 8) as usize, 0x80_u8 >> (req.offset % 8));
+            if bitmap.len() <= idx {
+                bitmap.resize(idx + 1, 0);
+            }
+            let old_value = bitmap[idx] & mask != 0;
+            if req.value {
+                bitmap[idx] |= mask;
+            } else {
+                bitmap[idx] &= !mask;
+            }
+            Ok(old_value)
+        }
+        _ => Err(wrong_type(&req.key)),
+    }
+}
+
+pub async fn getbit(req: &KbReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_bitmap(&mut store, &req.key)?.map(|bitmap| count_bits(bitmap, req.offset as usize, req.offset as usize) > 0).unwrap_or(false))
+}
+
+pub async fn bitcount(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u32> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_bitmap(&mut store, &req.key)?.map(|bitmap| bitmap.iter().map(|byte| byte.count_ones()).sum()).unwrap_or(0))
+}
+
+pub async fn bitcount_range_by_bit(req: &KbRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u32> {
+    let mut store = get_client(inst).write().await;
+    Ok(get_bitmap(&mut store, &req.key)?.map(|bitmap| count_bits(bitmap, req.start as usize, req.end as usize)).unwrap_or(0))
+}
@@ -7,10 +7,10 @@ use tardis::basic::result::TardisResult;
 use tardis::log::info;
 use tardis::web::web_resp::{TardisResp, Void};
 
-pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+pub async fn test(app_code: &str, client: &mut TestHttpClient) -> TardisResult<()> {
     client.set_auth(&TardisContext {
-        own_paths: "t1/app001".to_string(),
-        ak: "app001".to_string(),
+        own_paths: format!("t1/{app_code}"),
+        ak: app_code.to_string(),
         roles: vec![],
         groups: vec![],
         owner: "".to_string(),
@@ -1,9 +1,16 @@
+DROP FUNCTION IF EXISTS GRAPH_SEARCH(CHARACTER varying, CHARACTER varying, CHARACTER varying, int, int8);
+-- I_TAGS: only the relationships with these tags are traversed, empty means all
+-- I_PROPS: only the relationships whose properties contain these properties are traversed, empty means all
+-- I_REVERSE_DEPTH: the depth limit of the reverse relationships
 CREATE OR REPLACE FUNCTION GRAPH_SEARCH(
   IN I_SCHEMA CHARACTER varying,
   IN I_ROOT_KEY CHARACTER varying,
   IN I_ROOT_VERSION CHARACTER varying,
   IN I_DEPTH int DEFAULT 99999,
   IN I_LIMIT int8 DEFAULT 2000000000,
+  IN I_TAGS CHARACTER varying[] DEFAULT '{}',
+  IN I_PROPS jsonb DEFAULT '{}',
+  IN I_REVERSE_DEPTH int DEFAULT 99999,
  OUT O_TAG CHARACTER varying,
  OUT O_FROM_KEY CHARACTER varying,
  OUT O_FROM_VERSION CHARACTER varying,
@@ -12,16 +19,17 @@ CREATE OR REPLACE FUNCTION GRAPH_SEARCH(
  OUT O_TAGS CHARACTER varying[],
  OUT O_DEPTH int,
  OUT O_PATHS CHARACTER varying[],
- OUT O_REVERSE BOOL) RETURNS
+ OUT O_REVERSE BOOL,
+ OUT O_PROPS jsonb) RETURNS
 SETOF RECORD AS $$
 declare
   sql text;
 begin
 sql := format($_$
 WITH RECURSIVE search_graph(
-  tag, from_key, from_version, to_key, to_version, tags, depth, paths, reverse
+  tag, from_key, from_version, to_key, to_version, tags, depth, paths, reverse, props
 ) AS (
-        select tag, from_key, from_version, to_key, to_version, tags, depth, paths, reverse from (
+        select tag, from_key, from_version, to_key, to_version, tags, depth, paths, reverse, props from (
         SELECT
           g.tag,
           g.from_key,
@@ -31,14 +39,17 @@ WITH RECURSIVE search_graph(
 	      ARRAY[g.tag] as tags,
           1 as depth,
           ARRAY[(g.from_key || g.from_version)::varchar, (g.to_key || g.to_version)::varchar] as paths,
-          g.reverse
+          g.reverse,
+          g.props
 			FROM %s.starsys_graph AS g
         WHERE
           from_key = '%s' AND from_version = '%s'
+          AND (cardinality(%L::varchar[]) = 0 OR g.tag = ANY(%L::varchar[]))
+          AND (%L::jsonb = '{}'::jsonb OR g.props @> %L::jsonb)
           limit %s
         ) t
       UNION ALL
-        select tag, from_key, from_version, to_key, to_version, tags, depth, paths, reverse from (
+        select tag, from_key, from_version, to_key, to_version, tags, depth, paths, reverse, props from (
         SELECT
           DISTINCT ON (g.from_key, g.from_version, g.to_key, g.to_version, g.tag)
           g.tag,
@@ -49,18 +60,21 @@ WITH RECURSIVE search_graph(
 	      sg.tags || ARRAY[g.tag] as tags,
           sg.depth + 1 as depth,
           sg.paths || ARRAY[g.to_key || g.to_version] as paths,
-          g.reverse
+          g.reverse,
+          g.props
 			FROM %s.starsys_graph AS g, search_graph AS sg
         WHERE
           g.from_key = sg.to_key AND g.from_version = sg.to_version
           AND g.to_key || g.to_version <> ALL(sg.paths)
-          AND sg.depth <= %s AND sg.reverse = g.reverse
+          AND sg.depth <= (CASE WHEN sg.reverse THEN %s ELSE %s END) AND sg.reverse = g.reverse
+          AND (cardinality(%L::varchar[]) = 0 OR g.tag = ANY(%L::varchar[]))
+          AND (%L::jsonb = '{}'::jsonb OR g.props @> %L::jsonb)
           limit %s
         ) t
 )
-SELECT tag as o_tag, from_key as o_from_key, from_version as o_from_version, to_key as o_to_key, to_version as o_to_version, tags as o_tags, depth as o_depth, paths as o_paths, reverse as o_reverse
+SELECT tag as o_tag, from_key as o_from_key, from_version as o_from_version, to_key as o_to_key, to_version as o_to_version, tags as o_tags, depth as o_depth, paths as o_paths, reverse as o_reverse, props as o_props
 FROM search_graph;
-$_$, i_schema, i_root_key, i_root_version, i_limit, i_schema, i_depth, i_limit
+$_$, i_schema, i_root_key, i_root_version, i_tags, i_tags, i_props, i_props, i_limit, i_schema, i_reverse_depth, i_depth, i_tags, i_tags, i_props, i_props, i_limit
 );
 return query execute sql;
 end;
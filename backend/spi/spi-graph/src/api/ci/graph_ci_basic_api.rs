@@ -4,8 +4,12 @@ use tardis::web::poem_openapi;
 use tardis::web::poem_openapi::param::Query;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
+use tardis::TardisFuns;
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::dto::graph_dto::{
+    GraphNodeAddOrModifyReq, GraphNodeResp, GraphNodeVersionResp, GraphPathFindReq, GraphPathResp, GraphReachableFindReq, GraphReachableResp, GraphRelAddReq, GraphRelDetailResp,
+    GraphRelFilterReq, GraphRelUpgradeVersionReq,
+};
 use crate::serv::graph_basic_serv;
 #[derive(Clone)]
 pub struct GraphCiRelApi;
@@ -54,10 +58,57 @@ impl GraphCiRelApi {
     }
 
     /// Find Rels
+    ///
+    /// `tags` is separated by commas, `rel_props` is a json object that the properties of the relationships must contain.
     #[oai(path = "/rels", method = "get")]
-    async fn find_rels(&self, from_key: Query<String>, from_version: Query<String>, depth: Query<Option<u8>>, ctx: TardisContextExtractor) -> TardisApiResult<GraphRelDetailResp> {
+    async fn find_rels(
+        &self,
+        from_key: Query<String>,
+        from_version: Query<String>,
+        depth: Query<Option<u8>>,
+        reverse_depth: Query<Option<u8>>,
+        tags: Query<Option<String>>,
+        rel_props: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<GraphRelDetailResp> {
+        let funs = crate::get_tardis_inst();
+        let filter = GraphRelFilterReq {
+            tags: tags.0.map(|tags| tags.split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect()),
+            props: rel_props.0.map(|rel_props| TardisFuns::json.str_to_json(&rel_props)).transpose()?,
+        };
+        let resp = graph_basic_serv::find_rels(from_key.0, from_version.0, depth.0, reverse_depth.0, &filter, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Add Or Modify Node
+    #[oai(path = "/node", method = "put")]
+    async fn add_or_modify_node(&self, add_or_modify_req: Json<GraphNodeAddOrModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        graph_basic_serv::add_or_modify_node(&add_or_modify_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Get Node
+    #[oai(path = "/node", method = "get")]
+    async fn get_node(&self, key: Query<String>, version: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<Option<GraphNodeResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::get_node(key.0, version.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Shortest Path
+    #[oai(path = "/path/shortest", method = "put")]
+    async fn find_shortest_path(&self, find_req: Json<GraphPathFindReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<GraphPathResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::find_shortest_path(&find_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Reachable Nodes
+    #[oai(path = "/reachable", method = "put")]
+    async fn find_reachable(&self, find_req: Json<GraphReachableFindReq>, ctx: TardisContextExtractor) -> TardisApiResult<GraphReachableResp> {
         let funs = crate::get_tardis_inst();
-        let resp = graph_basic_serv::find_rels(from_key.0, from_version.0, depth.0, &funs, &ctx.0).await?;
+        let resp = graph_basic_serv::find_reachable(&find_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
 }
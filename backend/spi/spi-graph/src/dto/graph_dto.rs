@@ -5,6 +5,7 @@ use tardis::{
     basic::field::TrimString,
     chrono::{DateTime, Utc},
     db::sea_orm,
+    serde_json::Value,
     web::poem_openapi,
 };
 
@@ -18,6 +19,8 @@ pub struct GraphRelAddReq {
     pub to_key: TrimString,
     #[oai(validator(pattern = r"^[a-z0-9-_.]+$"))]
     pub to_version: String,
+    // Properties of the relationship, must be a json object
+    pub props: Option<Value>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -49,6 +52,112 @@ pub struct GraphRelUpgradeDelRelReq {
 pub struct GraphRelDetailResp {
     pub key: String,
     pub version: String,
+    // Properties of the relationship to this node, empty for the root node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rel_props: Option<Value>,
     pub form_rels: HashMap<String, Vec<GraphRelDetailResp>>,
     pub to_rels: HashMap<String, Vec<GraphRelDetailResp>>,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default, Clone)]
+pub struct GraphRelFilterReq {
+    // Only the relationships with these tags are traversed
+    pub tags: Option<Vec<String>>,
+    // Only the relationships whose properties contain these properties are traversed
+    pub props: Option<Value>,
+}
+
+impl GraphRelFilterReq {
+    pub fn get_tags(&self) -> Option<&Vec<String>> {
+        self.tags.as_ref().filter(|tags| !tags.is_empty())
+    }
+
+    pub fn get_props(&self) -> Option<&Value> {
+        self.props.as_ref().filter(|props| !props.is_null() && props.as_object().map(|props| !props.is_empty()).unwrap_or(true))
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphNodeAddOrModifyReq {
+    pub key: TrimString,
+    #[oai(validator(pattern = r"^[a-z0-9-_.]+$"))]
+    pub version: String,
+    // Properties of the node, must be a json object
+    pub props: Value,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, sea_orm::FromQueryResult)]
+pub struct GraphNodeResp {
+    pub key: String,
+    pub version: String,
+    pub props: Value,
+    pub ts: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GraphNodeRefResp {
+    pub key: String,
+    pub version: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, sea_orm::FromQueryResult)]
+pub struct GraphRelResp {
+    pub tag: String,
+    pub from_key: String,
+    pub from_version: String,
+    pub to_key: String,
+    pub to_version: String,
+    // Whether the relationship is traversed against its direction
+    pub reverse: bool,
+    pub props: Option<Value>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphPathFindReq {
+    pub from_key: TrimString,
+    #[oai(validator(pattern = r"^[a-z0-9-_.]+$"))]
+    pub from_version: String,
+    pub to_key: TrimString,
+    #[oai(validator(pattern = r"^[a-z0-9-_.]+$"))]
+    pub to_version: String,
+    // Direction of the traversal, `false` = along the relationships, `true` = against the relationships, empty = both
+    pub reverse: Option<bool>,
+    // Maximum number of relationships in the path
+    pub max_depth: Option<u32>,
+    pub filter: Option<GraphRelFilterReq>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphPathResp {
+    // Relationships from the start node to the end node in order
+    pub rels: Vec<GraphRelResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphReachableFindReq {
+    pub key: TrimString,
+    #[oai(validator(pattern = r"^[a-z0-9-_.]+$"))]
+    pub version: String,
+    // Direction of the traversal, `false` (default) = along the relationships (impact analysis), `true` = against the relationships (traceability)
+    pub reverse: Option<bool>,
+    // Maximum number of relationships from the start node
+    pub max_depth: Option<u32>,
+    pub filter: Option<GraphRelFilterReq>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphReachableResp {
+    // Reachable nodes ordered by depth, the start node is not included
+    pub nodes: Vec<GraphReachableNodeResp>,
+    // Cycles found during the traversal, each cycle starts and ends with the same node
+    pub cycles: Vec<Vec<GraphNodeRefResp>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphReachableNodeResp {
+    pub key: String,
+    pub version: String,
+    // Minimum number of relationships from the start node
+    pub depth: u32,
+    pub props: Option<Value>,
+}
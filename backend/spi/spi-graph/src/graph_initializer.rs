@@ -22,7 +22,10 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 }
 
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    #[cfg(feature = "spi-pg")]
     spi_initializer::add_kind(spi_constants::SPI_PG_KIND_CODE, funs, ctx).await?;
+    #[cfg(feature = "spi-memory")]
+    spi_initializer::add_kind(spi_constants::SPI_MEMORY_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
@@ -36,6 +39,8 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
     let inst = match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => crate::serv::memory::graph_memory_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }?;
     info!("[BIOS.Graph] Fun [{}]({}) initialized", bs_cert.kind_code, bs_cert.conn_uri);
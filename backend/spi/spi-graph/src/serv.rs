@@ -1,2 +1,6 @@
 pub mod graph_basic_serv;
+pub(crate) mod graph_traversal;
+#[cfg(feature = "spi-memory")]
+pub mod memory;
+#[cfg(feature = "spi-pg")]
 pub mod pg;
@@ -3,11 +3,18 @@ use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use bios_basic::spi_dispatch_service;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::serde_json::Value;
 use tardis::TardisFunsInst;
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::dto::graph_dto::{
+    GraphNodeAddOrModifyReq, GraphNodeResp, GraphNodeVersionResp, GraphPathFindReq, GraphPathResp, GraphReachableFindReq, GraphReachableResp, GraphRelAddReq, GraphRelDetailResp,
+    GraphRelFilterReq, GraphRelUpgradeVersionReq,
+};
 use crate::graph_initializer;
 
+#[cfg(feature = "spi-memory")]
+use super::memory;
+#[cfg(feature = "spi-pg")]
 use super::pg;
 
 spi_dispatch_service! {
@@ -16,12 +23,27 @@ spi_dispatch_service! {
     @dispatch: {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::graph_pg_basic_serv,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::graph_memory_basic_serv,
     },
     @method: {
         add_rel(add_req: &GraphRelAddReq) -> TardisResult<()>;
         upgrade_version(upgrade_version_req: &GraphRelUpgradeVersionReq) -> TardisResult<()>;
         find_versions(tag: String, key: String) -> TardisResult<Vec<GraphNodeVersionResp>>;
-        find_rels(from_key: String, from_version: String, depth: Option<u8>) -> TardisResult<GraphRelDetailResp>;
+        find_rels(from_key: String, from_version: String, depth: Option<u8>, reverse_depth: Option<u8>, filter: &GraphRelFilterReq) -> TardisResult<GraphRelDetailResp>;
+        add_or_modify_node(add_or_modify_req: &GraphNodeAddOrModifyReq) -> TardisResult<()>;
+        get_node(key: String, version: String) -> TardisResult<Option<GraphNodeResp>>;
+        find_shortest_path(find_req: &GraphPathFindReq) -> TardisResult<Option<GraphPathResp>>;
+        find_reachable(find_req: &GraphReachableFindReq) -> TardisResult<GraphReachableResp>;
+    }
+}
+
+/// The properties of the nodes and relationships must be json objects
+pub(crate) fn check_props(props: Option<&Value>, funs: &TardisFunsInst) -> TardisResult<()> {
+    if props.map(|props| props.is_object()).unwrap_or(true) {
+        Ok(())
+    } else {
+        Err(funs.err().bad_request("spi-graph", "check-props", "[props] must be a json object", "400-spi-graph-props-not-object"))
     }
 }
 
@@ -46,6 +68,8 @@ pub async fn delete_rels(
     match inst.kind_code() {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::graph_pg_basic_serv::delete_rels(tag, from_key, to_key, from_version, to_version, funs, ctx, &inst).await,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::graph_memory_basic_serv::delete_rels(tag, from_key, to_key, from_version, to_version, funs, ctx, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
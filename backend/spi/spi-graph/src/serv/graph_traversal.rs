@@ -0,0 +1,133 @@
+//! Traversal algorithms shared by all implementations, the implementations are only responsible for providing the relationships.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use tardis::serde_json::Value;
+
+use crate::dto::graph_dto::{GraphNodeRefResp, GraphPathResp, GraphRelFilterReq, GraphRelResp};
+
+/// Consistent with the default depth of ``GRAPH_SEARCH`` in the PostgreSQL implementation
+pub(crate) const DEFAULT_DEPTH: u32 = 99999;
+
+type Node<'a> = (&'a str, &'a str);
+
+pub(crate) fn match_filter(tag: &str, props: Option<&Value>, filter: &GraphRelFilterReq) -> bool {
+    filter.get_tags().map(|tags| tags.iter().any(|t| t == tag)).unwrap_or(true)
+        && filter.get_props().map(|expect| props.map(|props| contains_json(props, expect)).unwrap_or(false)).unwrap_or(true)
+}
+
+/// Same as the ``@>`` operator of the PostgreSQL jsonb
+pub(crate) fn contains_json(value: &Value, expect: &Value) -> bool {
+    match (value, expect) {
+        (Value::Object(value), Value::Object(expect)) => expect.iter().all(|(k, v)| value.get(k).map(|value| contains_json(value, v)).unwrap_or(false)),
+        (Value::Array(value), Value::Array(expect)) => expect.iter().all(|e| value.iter().any(|v| contains_json(v, e))),
+        (Value::Array(value), expect) if !expect.is_object() => value.contains(expect),
+        _ => value == expect,
+    }
+}
+
+fn package_adjacency(rels: &[GraphRelResp]) -> HashMap<Node, Vec<&GraphRelResp>> {
+    let mut adjacency: HashMap<Node, Vec<&GraphRelResp>> = HashMap::new();
+    for rel in rels {
+        adjacency.entry((rel.from_key.as_str(), rel.from_version.as_str())).or_default().push(rel);
+    }
+    // Make the result stable
+    for node_rels in adjacency.values_mut() {
+        node_rels.sort_by(|a, b| (&a.tag, &a.to_key, &a.to_version, a.reverse).cmp(&(&b.tag, &b.to_key, &b.to_version, b.reverse)));
+    }
+    adjacency
+}
+
+fn to_node_ref((key, version): Node) -> GraphNodeRefResp {
+    GraphNodeRefResp {
+        key: key.to_string(),
+        version: version.to_string(),
+    }
+}
+
+/// Breadth-first search of the shortest path, returns ``None`` if the end node is not reachable within ``max_depth`` relationships.
+pub(crate) fn shortest_path(rels: &[GraphRelResp], from: Node, to: Node, max_depth: u32) -> Option<GraphPathResp> {
+    if from == to {
+        return Some(GraphPathResp { rels: vec![] });
+    }
+    let adjacency = package_adjacency(rels);
+    let mut parents: HashMap<Node, &GraphRelResp> = HashMap::new();
+    let mut visited: HashSet<Node> = HashSet::from([from]);
+    let mut queue: VecDeque<(Node, u32)> = VecDeque::from([(from, 0)]);
+    while let Some((node, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+        for rel in adjacency.get(&node).into_iter().flatten() {
+            let next = (rel.to_key.as_str(), rel.to_version.as_str());
+            if !visited.insert(next) {
+                continue;
+            }
+            parents.insert(next, rel);
+            if next == to {
+                let mut path = vec![];
+                let mut current = to;
+                while let Some(rel) = parents.get(&current) {
+                    path.push((*rel).clone());
+                    current = (rel.from_key.as_str(), rel.from_version.as_str());
+                }
+                path.reverse();
+                return Some(GraphPathResp { rels: path });
+            }
+            queue.push_back((next, depth + 1));
+        }
+    }
+    None
+}
+
+/// Find the nodes reachable from the start node within ``max_depth`` relationships and the cycles among them.
+///
+/// Returns the reachable nodes (excluding the start node) with their minimum depth, and the cycles found by depth-first search.
+pub(crate) fn reachable(rels: &[GraphRelResp], start: Node, max_depth: u32) -> (Vec<(GraphNodeRefResp, u32)>, Vec<Vec<GraphNodeRefResp>>) {
+    let adjacency = package_adjacency(rels);
+    let mut depths: HashMap<Node, u32> = HashMap::from([(start, 0)]);
+    let mut nodes = vec![];
+    let mut queue: VecDeque<(Node, u32)> = VecDeque::from([(start, 0)]);
+    while let Some((node, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+        for rel in adjacency.get(&node).into_iter().flatten() {
+            let next = (rel.to_key.as_str(), rel.to_version.as_str());
+            if depths.contains_key(&next) {
+                continue;
+            }
+            depths.insert(next, depth + 1);
+            nodes.push((to_node_ref(next), depth + 1));
+            queue.push_back((next, depth + 1));
+        }
+    }
+
+    // Iterative depth-first search, true = the node is in the current path, false = the node has been finished
+    let mut cycles = vec![];
+    let mut states: HashMap<Node, bool> = HashMap::from([(start, true)]);
+    let mut stack: Vec<(Node, usize)> = vec![(start, 0)];
+    while let Some(&(node, idx)) = stack.last() {
+        let rel = adjacency.get(&node).filter(|_| depths.get(&node).map(|depth| *depth < max_depth).unwrap_or(false)).and_then(|node_rels| node_rels.get(idx));
+        let Some(rel) = rel else {
+            states.insert(node, false);
+            stack.pop();
+            continue;
+        };
+        if let Some(last) = stack.last_mut() {
+            last.1 += 1;
+        }
+        let next = (rel.to_key.as_str(), rel.to_version.as_str());
+        match states.get(&next) {
+            Some(true) => {
+                let pos = stack.iter().position(|(n, _)| *n == next).unwrap_or_default();
+                cycles.push(stack[pos..].iter().map(|(n, _)| to_node_ref(*n)).chain([to_node_ref(next)]).collect());
+            }
+            Some(false) => {}
+            None => {
+                states.insert(next, true);
+                stack.push((next, 0));
+            }
+        }
+    }
+    (nodes, cycles)
+}
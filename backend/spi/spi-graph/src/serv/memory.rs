@@ -0,0 +1,2 @@
+pub mod graph_memory_basic_serv;
+pub mod graph_memory_initializer;
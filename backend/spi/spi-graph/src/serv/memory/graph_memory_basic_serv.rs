@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_memory::SpiMemoryClient};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::Utc,
+    serde_json::Value,
+    TardisFunsInst,
+};
+
+use crate::{
+    dto::graph_dto::{
+        GraphNodeAddOrModifyReq, GraphNodeResp, GraphNodeVersionResp, GraphPathFindReq, GraphPathResp, GraphReachableFindReq, GraphReachableNodeResp, GraphReachableResp,
+        GraphRelAddReq, GraphRelDetailResp, GraphRelFilterReq, GraphRelResp, GraphRelUpgradeDelRelReq, GraphRelUpgradeVersionReq,
+    },
+    serv::{
+        graph_basic_serv,
+        graph_traversal::{self, DEFAULT_DEPTH},
+    },
+};
+
+use super::graph_memory_initializer::{GraphMemoryNode, GraphMemoryRecord, GraphMemoryStore};
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<GraphMemoryStore> {
+    inst.inst::<SpiMemoryClient<GraphMemoryStore>>().0
+}
+
+pub async fn add_rel(add_req: &GraphRelAddReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    if add_req.from_key.to_string() == add_req.to_key.to_string() {
+        return Err(funs.err().bad_request("spi-graph-rel", "add-rel", "[from_key] and [to_key] cannot be the same", "400-spi-graph-key-same"));
+    }
+    graph_basic_serv::check_props(add_req.props.as_ref(), funs)?;
+    let now = Utc::now();
+    let record = GraphMemoryRecord {
+        tag: add_req.tag.clone(),
+        from_key: add_req.from_key.to_string(),
+        from_version: add_req.from_version.clone(),
+        to_key: add_req.to_key.to_string(),
+        to_version: add_req.to_version.clone(),
+        reverse: false,
+        props: add_req.props.clone(),
+        ts: now,
+    };
+    let reverse_record = GraphMemoryRecord {
+        tag: add_req.tag.clone(),
+        from_key: add_req.to_key.to_string(),
+        from_version: add_req.to_version.clone(),
+        to_key: add_req.from_key.to_string(),
+        to_version: add_req.from_version.clone(),
+        reverse: true,
+        props: add_req.props.clone(),
+        ts: now,
+    };
+    let mut store = get_client(inst).write().await;
+    if store.records.iter().any(|r| r.is_same(&record) || r.is_same(&reverse_record)) {
+        return Err(funs.err().conflict("spi-graph-rel", "add-rel", "the relationship already exists", "409-spi-graph-rel-exist"));
+    }
+    store.records.push(record);
+    store.records.push(reverse_record);
+    Ok(())
+}
+
+fn is_del_rel(record: &GraphMemoryRecord, rel_key: &str, rel_version: &str, del_rels: &[GraphRelUpgradeDelRelReq]) -> bool {
+    del_rels.iter().any(|del_rel| {
+        del_rel.tag.as_ref().map(|tag| tag == &record.tag).unwrap_or(true)
+            && del_rel.rel_key.as_ref().map(|key| key.to_string() == rel_key).unwrap_or(true)
+            && del_rel.rel_version.as_ref().map(|version| version == rel_version).unwrap_or(true)
+    })
+}
+
+pub async fn upgrade_version(upgrade_version_req: &GraphRelUpgradeVersionReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let key = upgrade_version_req.key.to_string();
+    let mut store = get_client(inst).write().await;
+    let mut new_records = Vec::new();
+    for record in &store.records {
+        if record.from_key == key
+            && record.from_version == upgrade_version_req.old_version
+            && !is_del_rel(record, &record.to_key, &record.to_version, &upgrade_version_req.del_rels)
+        {
+            new_records.push(GraphMemoryRecord {
+                from_version: upgrade_version_req.new_version.clone(),
+                ..record.clone()
+            });
+        }
+        if record.to_key == key
+            && record.to_version == upgrade_version_req.old_version
+            && !is_del_rel(record, &record.from_key, &record.from_version, &upgrade_version_req.del_rels)
+        {
+            new_records.push(GraphMemoryRecord {
+                to_version: upgrade_version_req.new_version.clone(),
+                ..record.clone()
+            });
+        }
+    }
+    for new_record in new_records {
+        if !store.records.iter().any(|r| r.is_same(&new_record)) {
+            store.records.push(new_record);
+        }
+    }
+    // The properties of the node are inherited by the new version
+    let new_node = store.nodes.iter().find(|node| node.key == key && node.version == upgrade_version_req.old_version).map(|node| GraphMemoryNode {
+        version: upgrade_version_req.new_version.clone(),
+        ts: Utc::now(),
+        ..node.clone()
+    });
+    if let Some(new_node) = new_node {
+        if !store.nodes.iter().any(|node| node.key == new_node.key && node.version == new_node.version) {
+            store.nodes.push(new_node);
+        }
+    }
+    Ok(())
+}
+
+pub async fn find_versions(tag: String, key: String, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<GraphNodeVersionResp>> {
+    let store = get_client(inst).read().await;
+    let mut versions: Vec<GraphNodeVersionResp> = Vec::new();
+    for record in store.records.iter().filter(|r| r.tag == tag && r.from_key == key && !r.reverse) {
+        if !versions.iter().any(|v| v.version == record.from_version) {
+            versions.push(GraphNodeVersionResp {
+                version: record.from_version.clone(),
+                ts: record.ts,
+            });
+        }
+    }
+    versions.sort_by(|a, b| b.version.cmp(&a.version));
+    Ok(versions)
+}
+
+pub async fn find_rels(
+    from_key: String,
+    from_version: String,
+    depth: Option<u8>,
+    reverse_depth: Option<u8>,
+    filter: &GraphRelFilterReq,
+    _funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<GraphRelDetailResp> {
+    let store = get_client(inst).read().await;
+    let traversal = RelsTraversal {
+        records: &store.records,
+        filter,
+        max_depth: depth.map(|depth| depth as u32).unwrap_or(DEFAULT_DEPTH),
+        max_reverse_depth: reverse_depth.map(|depth| depth as u32).unwrap_or(DEFAULT_DEPTH),
+    };
+    let mut paths = vec![format!("{from_key}{from_version}")];
+    Ok(traversal.package_rels(&from_key, &from_version, None, None, 0, &mut paths))
+}
+
+struct RelsTraversal<'a> {
+    records: &'a [GraphMemoryRecord],
+    filter: &'a GraphRelFilterReq,
+    max_depth: u32,
+    max_reverse_depth: u32,
+}
+
+impl RelsTraversal<'_> {
+    /// Traverse the relationships recursively, the traversal rules are the same as ``GRAPH_SEARCH`` in the PostgreSQL implementation:
+    /// the direction of the relationships in a path is consistent, and the nodes in a path are not repeated.
+    fn package_rels(&self, key: &str, version: &str, reverse: Option<bool>, rel_props: Option<&Value>, depth: u32, paths: &mut Vec<String>) -> GraphRelDetailResp {
+        let mut form_rels: HashMap<String, Vec<GraphRelDetailResp>> = HashMap::new();
+        let mut to_rels: HashMap<String, Vec<GraphRelDetailResp>> = HashMap::new();
+        for record in self.records.iter().filter(|r| {
+            r.from_key == key
+                && r.from_version == version
+                && reverse.map(|reverse| reverse == r.reverse).unwrap_or(true)
+                && depth <= if r.reverse { self.max_reverse_depth } else { self.max_depth }
+                && graph_traversal::match_filter(&r.tag, r.props.as_ref(), self.filter)
+        }) {
+            let node = format!("{}{}", record.to_key, record.to_version);
+            if depth > 0 && paths.contains(&node) {
+                continue;
+            }
+            paths.push(node);
+            let rel = self.package_rels(&record.to_key, &record.to_version, Some(record.reverse), record.props.as_ref(), depth + 1, paths);
+            paths.pop();
+            if record.reverse {
+                to_rels.entry(record.tag.clone()).or_default().push(rel);
+            } else {
+                form_rels.entry(record.tag.clone()).or_default().push(rel);
+            }
+        }
+        GraphRelDetailResp {
+            key: key.to_string(),
+            version: version.to_string(),
+            rel_props: rel_props.cloned(),
+            form_rels,
+            to_rels,
+        }
+    }
+}
+
+pub async fn add_or_modify_node(add_or_modify_req: &GraphNodeAddOrModifyReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    graph_basic_serv::check_props(Some(&add_or_modify_req.props), funs)?;
+    let key = add_or_modify_req.key.to_string();
+    let mut store = get_client(inst).write().await;
+    if let Some(node) = store.nodes.iter_mut().find(|node| node.key == key && node.version == add_or_modify_req.version) {
+        node.props = add_or_modify_req.props.clone();
+        node.ts = Utc::now();
+    } else {
+        store.nodes.push(GraphMemoryNode {
+            key,
+            version: add_or_modify_req.version.clone(),
+            props: add_or_modify_req.props.clone(),
+            ts: Utc::now(),
+        });
+    }
+    Ok(())
+}
+
+pub async fn get_node(key: String, version: String, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<GraphNodeResp>> {
+    let store = get_client(inst).read().await;
+    Ok(store.nodes.iter().find(|node| node.key == key && node.version == version).map(|node| GraphNodeResp {
+        key: node.key.clone(),
+        version: node.version.clone(),
+        props: node.props.clone(),
+        ts: node.ts,
+    }))
+}
+
+fn find_traversable_rels(records: &[GraphMemoryRecord], reverse: Option<bool>, filter: Option<&GraphRelFilterReq>) -> Vec<GraphRelResp> {
+    records
+        .iter()
+        .filter(|r| reverse.map(|reverse| reverse == r.reverse).unwrap_or(true))
+        .filter(|r| filter.map(|filter| graph_traversal::match_filter(&r.tag, r.props.as_ref(), filter)).unwrap_or(true))
+        .map(|r| r.to_resp())
+        .collect()
+}
+
+pub async fn find_shortest_path(find_req: &GraphPathFindReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<GraphPathResp>> {
+    let store = get_client(inst).read().await;
+    let rels = find_traversable_rels(&store.records, find_req.reverse, find_req.filter.as_ref());
+    let (from_key, to_key) = (find_req.from_key.to_string(), find_req.to_key.to_string());
+    Ok(graph_traversal::shortest_path(
+        &rels,
+        (&from_key, &find_req.from_version),
+        (&to_key, &find_req.to_version),
+        find_req.max_depth.unwrap_or(DEFAULT_DEPTH),
+    ))
+}
+
+pub async fn find_reachable(find_req: &GraphReachableFindReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<GraphReachableResp> {
+    let store = get_client(inst).read().await;
+    let rels = find_traversable_rels(&store.records, Some(find_req.reverse.unwrap_or(false)), find_req.filter.as_ref());
+    let key = find_req.key.to_string();
+    let (nodes, cycles) = graph_traversal::reachable(&rels, (&key, &find_req.version), find_req.max_depth.unwrap_or(DEFAULT_DEPTH));
+    let nodes = nodes
+        .into_iter()
+        .map(|(node, depth)| GraphReachableNodeResp {
+            props: store.nodes.iter().find(|n| n.key == node.key && n.version == node.version).map(|n| n.props.clone()),
+            key: node.key,
+            version: node.version,
+            depth,
+        })
+        .collect();
+    Ok(GraphReachableResp { nodes, cycles })
+}
+
+pub async fn delete_rels(
+    tag: String,
+    from_key: Option<String>,
+    to_key: Option<String>,
+    from_version: Option<String>,
+    to_version: Option<String>,
+    _funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let is_match = |key: &str, expect: &Option<String>| expect.as_ref().map(|expect| expect == key).unwrap_or(true);
+    get_client(inst).write().await.records.retain(|r| {
+        let matched = if r.reverse {
+            is_match(&r.to_key, &from_key) && is_match(&r.from_key, &to_key) && is_match(&r.to_version, &from_version) && is_match(&r.from_version, &to_version)
+        } else {
+            is_match(&r.from_key, &from_key) && is_match(&r.to_key, &to_key) && is_match(&r.from_version, &from_version) && is_match(&r.to_version, &to_version)
+        };
+        !(r.tag == tag && matched)
+    });
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    serde_json::Value,
+};
+
+use crate::dto::graph_dto::GraphRelResp;
+
+#[derive(Default)]
+pub struct GraphMemoryStore {
+    pub(crate) records: Vec<GraphMemoryRecord>,
+    pub(crate) nodes: Vec<GraphMemoryNode>,
+}
+
+/// Same as the row of the PostgreSQL implementation, each relationship is stored twice, the second one is reversed
+#[derive(Clone)]
+pub(crate) struct GraphMemoryRecord {
+    pub(crate) tag: String,
+    pub(crate) from_key: String,
+    pub(crate) from_version: String,
+    pub(crate) to_key: String,
+    pub(crate) to_version: String,
+    pub(crate) reverse: bool,
+    pub(crate) props: Option<Value>,
+    pub(crate) ts: DateTime<Utc>,
+}
+
+impl GraphMemoryRecord {
+    pub(crate) fn is_same(&self, other: &GraphMemoryRecord) -> bool {
+        self.tag == other.tag && self.from_key == other.from_key && self.from_version == other.from_version && self.to_key == other.to_key && self.to_version == other.to_version
+    }
+
+    pub(crate) fn to_resp(&self) -> GraphRelResp {
+        GraphRelResp {
+            tag: self.tag.clone(),
+            from_key: self.from_key.clone(),
+            from_version: self.from_version.clone(),
+            to_key: self.to_key.clone(),
+            to_version: self.to_version.clone(),
+            reverse: self.reverse,
+            props: self.props.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct GraphMemoryNode {
+    pub(crate) key: String,
+    pub(crate) version: String,
+    pub(crate) props: Value,
+    pub(crate) ts: DateTime<Utc>,
+}
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    spi_initializer::common_memory::init::<GraphMemoryStore>(bs_cert, ctx, mgr).await
+}
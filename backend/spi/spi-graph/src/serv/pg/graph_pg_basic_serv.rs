@@ -3,26 +3,36 @@ use itertools::Itertools;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     db::{
-        reldb_client::TardisRelDBClient,
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
         sea_orm::{self, Value},
     },
-    TardisFunsInst,
+    serde_json, TardisFunsInst,
 };
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::{
+    dto::graph_dto::{
+        GraphNodeAddOrModifyReq, GraphNodeResp, GraphNodeVersionResp, GraphPathFindReq, GraphPathResp, GraphReachableFindReq, GraphReachableNodeResp, GraphReachableResp,
+        GraphRelAddReq, GraphRelDetailResp, GraphRelFilterReq, GraphRelResp, GraphRelUpgradeVersionReq,
+    },
+    serv::{
+        graph_basic_serv,
+        graph_traversal::{self, DEFAULT_DEPTH},
+    },
+};
 
 use super::graph_pg_initializer;
 
-pub async fn add_rel(add_req: &GraphRelAddReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+pub async fn add_rel(add_req: &GraphRelAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    graph_basic_serv::check_props(add_req.props.as_ref(), funs)?;
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
     conn.begin().await?;
     conn.execute_one(
         &format!(
             r#"INSERT INTO {table_name}
-        (tag, from_key, from_version, to_key, to_version, reverse)
+        (tag, from_key, from_version, to_key, to_version, reverse, props)
     VALUES
-        ($1, $2, $3, $4, $5, false)
+        ($1, $2, $3, $4, $5, false, $6)
         "#
         ),
         vec![
@@ -31,15 +41,16 @@ pub async fn add_rel(add_req: &GraphRelAddReq, _funs: &TardisFunsInst, ctx: &Tar
             Value::from(add_req.from_version.as_str()),
             Value::from(add_req.to_key.to_string()),
             Value::from(add_req.to_version.as_str()),
+            Value::from(add_req.props.clone()),
         ],
     )
     .await?;
     conn.execute_one(
         &format!(
             r#"INSERT INTO {table_name}
-        (tag, from_key, from_version, to_key, to_version, reverse)
+        (tag, from_key, from_version, to_key, to_version, reverse, props)
     VALUES
-        ($1, $2, $3, $4, $5, true)
+        ($1, $2, $3, $4, $5, true, $6)
         "#
         ),
         vec![
@@ -48,6 +59,7 @@ pub async fn add_rel(add_req: &GraphRelAddReq, _funs: &TardisFunsInst, ctx: &Tar
             Value::from(add_req.to_version.as_str()),
             Value::from(add_req.from_key.to_string()),
             Value::from(add_req.from_version.as_str()),
+            Value::from(add_req.props.clone()),
         ],
     )
     .await?;
@@ -88,8 +100,8 @@ pub async fn upgrade_version(upgrade_version_req: &GraphRelUpgradeVersionReq, _f
     conn.begin().await?;
     conn.execute_one(
         format!(
-            r#"INSERT INTO {table_name}
-SELECT tag, from_key, $1, to_key, to_version, reverse
+            r#"INSERT INTO {table_name} (tag, from_key, from_version, to_key, to_version, reverse, props)
+SELECT tag, from_key, $1, to_key, to_version, reverse, props
 FROM {table_name}
 WHERE from_key = $2 AND from_version = $3 {}"#,
             where_fragments.replace("rel_key", "to_key").replace("rel_version", "to_version"),
@@ -100,8 +112,8 @@ WHERE from_key = $2 AND from_version = $3 {}"#,
     .await?;
     conn.execute_one(
         format!(
-            r#"INSERT INTO {table_name}
-SELECT tag, from_key, from_version, to_key, $1, reverse
+            r#"INSERT INTO {table_name} (tag, from_key, from_version, to_key, to_version, reverse, props)
+SELECT tag, from_key, from_version, to_key, $1, reverse, props
 FROM {table_name}
 WHERE to_key = $2 AND to_version = $3 {}"#,
             where_fragments.replace("rel_key", "from_key").replace("rel_version", "from_version"),
@@ -110,6 +122,23 @@ WHERE to_key = $2 AND to_version = $3 {}"#,
         sql_vals,
     )
     .await?;
+    // The properties of the node are inherited by the new version
+    let (_, node_table_name) = graph_pg_initializer::init_node_table_and_conn(bs_inst, ctx, true).await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {node_table_name} (key, version, props)
+SELECT key, $1, props
+FROM {node_table_name}
+WHERE key = $2 AND version = $3
+ON CONFLICT (key, version) DO NOTHING"#
+        ),
+        vec![
+            Value::from(upgrade_version_req.new_version.to_string()),
+            Value::from(upgrade_version_req.key.to_string()),
+            Value::from(upgrade_version_req.old_version.as_str()),
+        ],
+    )
+    .await?;
     conn.commit().await?;
     Ok(())
 }
@@ -135,6 +164,8 @@ pub async fn find_rels(
     from_key: String,
     from_version: String,
     depth: Option<u8>,
+    reverse_depth: Option<u8>,
+    filter: &GraphRelFilterReq,
     _funs: &TardisFunsInst,
     ctx: &TardisContext,
     inst: &SpiBsInst,
@@ -148,28 +179,41 @@ pub async fn find_rels(
     }
     sql_vals.push(Value::from(from_key.as_str()));
     sql_vals.push(Value::from(from_version.as_str()));
-    if let Some(depth) = depth {
-        sql_vals.push(Value::from(depth));
+    sql_vals.push(Value::from(depth.map(|depth| depth as i32).unwrap_or(DEFAULT_DEPTH as i32)));
+    sql_vals.push(Value::from(reverse_depth.map(|depth| depth as i32).unwrap_or(DEFAULT_DEPTH as i32)));
+    let tags = filter.get_tags().cloned().unwrap_or_default();
+    let tags_fragment = format!(
+        "ARRAY[{}]::varchar[]",
+        (0..tags.len()).map(|idx| format!("${}", sql_vals.len() + idx + 1)).collect::<Vec<String>>().join(", ")
+    );
+    for tag in tags {
+        sql_vals.push(Value::from(tag));
     }
+    sql_vals.push(Value::from(filter.get_props().cloned().unwrap_or_else(|| serde_json::json!({}))));
+    let props_fragment = format!("${}::jsonb", sql_vals.len());
     let (conn, _) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
-    let result = conn.find_dtos_by_sql(
-        &format!(r#"SELECT o_tag AS tag, o_from_key AS from_key, o_from_version AS from_version, o_to_key AS to_key, o_to_version AS to_version, o_reverse AS reverse FROM public.GRAPH_SEARCH($1, $2, $3{}) ORDER BY O_DEPTH, O_TAG"#, if depth.is_some() { ", $4" } else { "" }),
-        sql_vals.clone(),
-    )
-    .await?;
+    let result = conn
+        .find_dtos_by_sql(
+            &format!(
+                r#"SELECT o_tag AS tag, o_from_key AS from_key, o_from_version AS from_version, o_to_key AS to_key, o_to_version AS to_version, o_reverse AS reverse, o_props AS props
+FROM public.GRAPH_SEARCH($1, $2, $3, $4::int, I_TAGS => {tags_fragment}, I_PROPS => {props_fragment}, I_REVERSE_DEPTH => $5::int) ORDER BY O_DEPTH, O_TAG"#
+            ),
+            sql_vals.clone(),
+        )
+        .await?;
 
-    let result = package_rels(&from_key, &from_version, &result);
+    let result = package_rels(&from_key, &from_version, None, &result);
     Ok(result)
 }
 
-fn package_rels(from_key: &str, from_version: &str, records: &[GraphRelRecord]) -> GraphRelDetailResp {
+fn package_rels(from_key: &str, from_version: &str, rel_props: Option<&serde_json::Value>, records: &[GraphRelRecord]) -> GraphRelDetailResp {
     let form_rels = records
         .iter()
         .filter(|r| r.from_key == from_key && r.from_version == from_version && !r.reverse)
         .group_by(|r| r.tag.clone())
         .into_iter()
         .map(|(tag, rr)| {
-            let rels = rr.map(|rrr| package_rels(&rrr.to_key, &rrr.to_version, records)).collect();
+            let rels = rr.map(|rrr| package_rels(&rrr.to_key, &rrr.to_version, rrr.props.as_ref(), records)).collect();
             (tag, rels)
         })
         .collect();
@@ -179,18 +223,152 @@ fn package_rels(from_key: &str, from_version: &str, records: &[GraphRelRecord])
         .group_by(|r| r.tag.clone())
         .into_iter()
         .map(|(tag, rr)| {
-            let rels = rr.map(|rrr| package_rels(&rrr.to_key, &rrr.to_version, records)).collect();
+            let rels = rr.map(|rrr| package_rels(&rrr.to_key, &rrr.to_version, rrr.props.as_ref(), records)).collect();
             (tag, rels)
         })
         .collect();
     GraphRelDetailResp {
         key: from_key.to_string(),
         version: from_version.to_string(),
+        rel_props: rel_props.cloned(),
         form_rels,
         to_rels,
     }
 }
 
+pub async fn add_or_modify_node(add_or_modify_req: &GraphNodeAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    graph_basic_serv::check_props(Some(&add_or_modify_req.props), funs)?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_node_table_and_conn(bs_inst, ctx, true).await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+        (key, version, props)
+    VALUES
+        ($1, $2, $3)
+    ON CONFLICT (key, version) DO UPDATE SET props = EXCLUDED.props, ts = CURRENT_TIMESTAMP"#
+        ),
+        vec![
+            Value::from(add_or_modify_req.key.to_string()),
+            Value::from(add_or_modify_req.version.as_str()),
+            Value::from(add_or_modify_req.props.clone()),
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn get_node(key: String, version: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<GraphNodeResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_node_table_and_conn(bs_inst, ctx, true).await?;
+    conn.find_dto_by_sql(
+        &format!("SELECT key, version, props, ts FROM {table_name} WHERE key = $1 AND version = $2"),
+        vec![Value::from(key), Value::from(version)],
+    )
+    .await
+}
+
+/// Find the relationships that can be traversed from the start node,
+/// the relationships are deduplicated by ``UNION``, so the recursion terminates even if there are cycles.
+async fn find_traversable_rels(
+    key: &str,
+    version: &str,
+    reverse: Option<bool>,
+    filter: Option<&GraphRelFilterReq>,
+    conn: &TardisRelDBlConnection,
+    table_name: &str,
+) -> TardisResult<Vec<GraphRelResp>> {
+    let mut sql_vals: Vec<Value> = vec![Value::from(key), Value::from(version)];
+    let mut where_fragments: Vec<String> = vec![];
+    if let Some(reverse) = reverse {
+        sql_vals.push(Value::from(reverse));
+        where_fragments.push(format!("g.reverse = ${}", sql_vals.len()));
+    }
+    if let Some(tags) = filter.and_then(|filter| filter.get_tags()) {
+        where_fragments.push(format!(
+            "g.tag = ANY (ARRAY[{}])",
+            (0..tags.len()).map(|idx| format!("${}", sql_vals.len() + idx + 1)).collect::<Vec<String>>().join(", ")
+        ));
+        for tag in tags {
+            sql_vals.push(Value::from(tag.as_str()));
+        }
+    }
+    if let Some(props) = filter.and_then(|filter| filter.get_props()) {
+        sql_vals.push(Value::from(props.clone()));
+        where_fragments.push(format!("g.props @> ${}", sql_vals.len()));
+    }
+    let where_fragments = where_fragments.iter().map(|fragment| format!(" AND {fragment}")).collect::<String>();
+    conn.find_dtos_by_sql(
+        &format!(
+            r#"WITH RECURSIVE sub_graph(tag, from_key, from_version, to_key, to_version, reverse, props) AS (
+    SELECT g.tag, g.from_key, g.from_version, g.to_key, g.to_version, g.reverse, g.props
+    FROM {table_name} AS g
+    WHERE g.from_key = $1 AND g.from_version = $2{where_fragments}
+  UNION
+    SELECT g.tag, g.from_key, g.from_version, g.to_key, g.to_version, g.reverse, g.props
+    FROM {table_name} AS g, sub_graph AS sg
+    WHERE g.from_key = sg.to_key AND g.from_version = sg.to_version{where_fragments}
+)
+SELECT tag, from_key, from_version, to_key, to_version, reverse, props FROM sub_graph"#
+        ),
+        sql_vals,
+    )
+    .await
+}
+
+pub async fn find_shortest_path(find_req: &GraphPathFindReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<GraphPathResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let (from_key, to_key) = (find_req.from_key.to_string(), find_req.to_key.to_string());
+    let rels = find_traversable_rels(&from_key, &find_req.from_version, find_req.reverse, find_req.filter.as_ref(), &conn, &table_name).await?;
+    Ok(graph_traversal::shortest_path(
+        &rels,
+        (&from_key, &find_req.from_version),
+        (&to_key, &find_req.to_version),
+        find_req.max_depth.unwrap_or(DEFAULT_DEPTH),
+    ))
+}
+
+pub async fn find_reachable(find_req: &GraphReachableFindReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<GraphReachableResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let key = find_req.key.to_string();
+    let rels = find_traversable_rels(
+        &key,
+        &find_req.version,
+        Some(find_req.reverse.unwrap_or(false)),
+        find_req.filter.as_ref(),
+        &conn,
+        &table_name,
+    )
+    .await?;
+    let (nodes, cycles) = graph_traversal::reachable(&rels, (&key, &find_req.version), find_req.max_depth.unwrap_or(DEFAULT_DEPTH));
+    let node_props: Vec<GraphNodeResp> = if nodes.is_empty() {
+        vec![]
+    } else {
+        let (_, node_table_name) = graph_pg_initializer::init_node_table_and_conn(bs_inst, ctx, true).await?;
+        let keys = nodes.iter().map(|(node, _)| node.key.as_str()).unique().collect::<Vec<&str>>();
+        conn.find_dtos_by_sql(
+            &format!(
+                "SELECT key, version, props, ts FROM {node_table_name} WHERE key = ANY (ARRAY[{}])",
+                (1..=keys.len()).map(|idx| format!("${idx}")).collect::<Vec<String>>().join(", ")
+            ),
+            keys.into_iter().map(Value::from).collect(),
+        )
+        .await?
+    };
+    let nodes = nodes
+        .into_iter()
+        .map(|(node, depth)| GraphReachableNodeResp {
+            props: node_props.iter().find(|n| n.key == node.key && n.version == node.version).map(|n| n.props.clone()),
+            key: node.key,
+            version: node.version,
+            depth,
+        })
+        .collect();
+    Ok(GraphReachableResp { nodes, cycles })
+}
+
 pub async fn delete_rels(
     tag: String,
     from_key: Option<String>,
@@ -255,4 +433,5 @@ struct GraphRelRecord {
     pub to_key: String,
     pub to_version: String,
     pub reverse: bool,
+    pub props: Option<serde_json::Value>,
 }
@@ -5,7 +5,7 @@ use tardis::{
 };
 
 pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
-    spi_initializer::common_pg::init_table_and_conn(
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
         ctx,
         mgr,
@@ -17,6 +17,7 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
     to_key character varying NOT NULL,
     to_version character varying NOT NULL,
     reverse bool DEFAULT false NOT NULL, 
+    props jsonb,
     ts timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     check (from_key <> to_key),  
     unique (from_key, from_version, to_key, to_version, tag)"#,
@@ -31,5 +32,28 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
         None,
         None,
     )
+    .await?;
+    // Add the props field (compatible with the existing tables)
+    conn.execute_one(&format!(r#"ALTER TABLE {table_name} ADD COLUMN IF NOT EXISTS props jsonb;"#), vec![]).await?;
+    Ok((conn, table_name))
+}
+
+pub async fn init_node_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "graph_node",
+        r#"key character varying NOT NULL,
+    version character varying NOT NULL,
+    props jsonb NOT NULL,
+    ts timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    unique (key, version)"#,
+        None,
+        vec![("key", "btree"), ("version", "btree"), ("props", "gin")],
+        None,
+        None,
+    )
     .await
 }
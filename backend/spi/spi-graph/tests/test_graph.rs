@@ -74,7 +74,29 @@ async fn init_data() -> TardisResult<()> {
 
     let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app001", bs_id), &Void {}).await;
 
-    test_graph_rel::test(&mut client).await?;
+    test_graph_rel::test("app001", &mut client).await?;
+
+    // In-memory backend
+    client.set_auth(&ctx)?;
+    let kind_id = RbumKindServ::get_rbum_kind_id_by_code(spi_constants::SPI_MEMORY_KIND_CODE, &funs).await?.unwrap();
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-memory".to_string()),
+                kind_id: TrimString(kind_id),
+                conn_uri: "memory://test".to_string(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: "{}".to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app002", bs_id), &Void {}).await;
+
+    test_graph_rel::test("app002", &mut client).await?;
 
     Ok(())
 }
@@ -1,18 +1,20 @@
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_graph::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeDelRelReq, GraphRelUpgradeVersionReq};
+use bios_spi_graph::dto::graph_dto::{
+    GraphNodeResp, GraphNodeVersionResp, GraphPathResp, GraphReachableResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeDelRelReq, GraphRelUpgradeVersionReq,
+};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::serde_json::json;
-use tardis::web::web_resp::Void;
+use tardis::web::web_resp::{TardisResp, Void};
 use tardis::TardisFuns;
 
-pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+pub async fn test(app: &str, client: &mut TestHttpClient) -> TardisResult<()> {
     client.set_auth(&TardisContext {
-        own_paths: "t1/app001".to_string(),
-        ak: "".to_string(),
+        own_paths: format!("t1/{app}"),
+        ak: app.to_string(),
         roles: vec![],
         groups: vec![],
-        owner: "app001".to_string(),
+        owner: app.to_string(),
         ..Default::default()
     })?;
 
@@ -43,6 +45,7 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
                     from_version: from_version.to_string(),
                     to_key: to_key.into(),
                     to_version: to_version.to_string(),
+                    props: None,
                 },
             )
             .await;
@@ -445,5 +448,101 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
             }
         })
     );
+
+    // Rels with properties
+    let trace_rels = vec![
+        ("req-task", "reqa", "taska", Some(json!({"kind": "impl"}))),
+        ("task-test", "taska", "testa", Some(json!({"kind": "verify"}))),
+        ("req-test", "reqa", "testa", Some(json!({"kind": "verify"}))),
+        ("test-req", "testa", "reqa", None),
+    ];
+    for (tag, from_key, to_key, props) in trace_rels {
+        let _: Void = client
+            .put(
+                "/ci/rel",
+                &GraphRelAddReq {
+                    tag: tag.to_string(),
+                    from_key: from_key.into(),
+                    from_version: "1".to_string(),
+                    to_key: to_key.into(),
+                    to_version: "1".to_string(),
+                    props,
+                },
+            )
+            .await;
+    }
+    let result: TardisResp<Void> = client
+        .put_resp(
+            "/ci/rel",
+            &json!({"tag": "req-task", "from_key": "reqb", "from_version": "1", "to_key": "taskb", "to_version": "1", "props": "impl"}),
+        )
+        .await;
+    assert!(result.code.starts_with("400"));
+
+    // Node properties
+    let _: Void = client.put("/ci/node", &json!({"key": "testa", "version": "1", "props": {"result": "passed"}})).await;
+    let result: GraphNodeResp = client.get("/ci/node?key=testa&version=1").await;
+    assert_eq!(result.props, json!({"result": "passed"}));
+
+    // Find Rels with filters
+    let result: GraphRelDetailResp = client.get("/ci/rels?from_key=reqa&from_version=1&tags=req-task").await;
+    assert_eq!(result.form_rels.len(), 1);
+    assert_eq!(result.form_rels["req-task"][0].key, "taska");
+    assert_eq!(result.form_rels["req-task"][0].rel_props, Some(json!({"kind": "impl"})));
+    assert!(result.form_rels["req-task"][0].form_rels.is_empty());
+    assert!(result.to_rels.is_empty());
+    let result: GraphRelDetailResp = client.get("/ci/rels?from_key=reqa&from_version=1&rel_props=%7B%22kind%22%3A%22verify%22%7D").await;
+    assert_eq!(result.form_rels.len(), 1);
+    assert_eq!(result.form_rels["req-test"][0].key, "testa");
+    assert!(result.to_rels.is_empty());
+    let result: GraphRelDetailResp = client.get("/ci/rels?from_key=testa&from_version=1&reverse_depth=0").await;
+    assert_eq!(result.to_rels.len(), 2);
+    assert!(result.to_rels["task-test"][0].to_rels.is_empty());
+
+    // Find Shortest Path
+    let result: GraphPathResp = client
+        .put(
+            "/ci/path/shortest",
+            &json!({"from_key": "reqa", "from_version": "1", "to_key": "testa", "to_version": "1", "reverse": false}),
+        )
+        .await;
+    assert_eq!(result.rels.len(), 1);
+    assert_eq!(result.rels[0].tag, "req-test");
+    let result: GraphPathResp = client
+        .put(
+            "/ci/path/shortest",
+            &json!({"from_key": "reqa", "from_version": "1", "to_key": "testa", "to_version": "1", "reverse": false, "filter": {"tags": ["req-task", "task-test"]}}),
+        )
+        .await;
+    assert_eq!(result.rels.len(), 2);
+    assert_eq!(result.rels[0].to_key, "taska");
+    assert_eq!(result.rels[1].to_key, "testa");
+    let result: TardisResp<GraphPathResp> = client
+        .put_resp(
+            "/ci/path/shortest",
+            &json!({"from_key": "reqa", "from_version": "1", "to_key": "testa", "to_version": "1", "reverse": false, "max_depth": 1, "filter": {"tags": ["req-task", "task-test"]}}),
+        )
+        .await;
+    assert!(result.data.is_none());
+    let result: GraphPathResp = client.put("/ci/path/shortest", &json!({"from_key": "taska", "from_version": "1", "to_key": "reqa", "to_version": "1"})).await;
+    assert_eq!(result.rels.len(), 1);
+    assert!(result.rels[0].reverse);
+
+    // Find Reachable
+    let result: GraphReachableResp = client.put("/ci/reachable", &json!({"key": "reqa", "version": "1"})).await;
+    assert_eq!(result.nodes.len(), 2);
+    assert!(result.nodes.iter().all(|node| node.depth == 1));
+    assert_eq!(result.nodes.iter().find(|node| node.key == "testa").unwrap().props, Some(json!({"result": "passed"})));
+    assert_eq!(result.cycles.len(), 1);
+    assert_eq!(result.cycles[0].first(), result.cycles[0].last());
+    let result: GraphReachableResp = client
+        .put(
+            "/ci/reachable",
+            &json!({"key": "testa", "version": "1", "reverse": true, "filter": {"props": {"kind": "verify"}}}),
+        )
+        .await;
+    assert_eq!(result.nodes.len(), 2);
+    assert!(result.cycles.is_empty());
+
     Ok(())
 }
@@ -5,7 +5,8 @@ use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
 use crate::dto::kv_item_dto::{
-    KvItemAddOrModifyReq, KvItemDetailResp, KvItemKeyReq, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    KvItemAddOrModifyReq, KvItemDetailResp, KvItemHistoryResp, KvItemKeyReq, KvItemMatchReq, KvItemRestoreReq, KvItemSummaryResp, KvItemWatchResp, KvNameAddOrModifyReq,
+    KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
 };
 use crate::serv::kv_item_serv;
 
@@ -23,6 +24,7 @@ impl KvCiItemApi {
     #[oai(path = "/item", method = "put")]
     async fn add_or_modify_item(&self, mut add_or_modify_req: Json<KvItemAddOrModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let funs = crate::get_tardis_inst();
+        kv_item_serv::register_expire_ctx(&ctx.0).await;
         kv_item_serv::add_or_modify_item(&mut add_or_modify_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
@@ -83,6 +85,30 @@ impl KvCiItemApi {
         TardisResp::ok(resp)
     }
 
+    /// Watch Items By key prefix
+    ///
+    /// Wait until the items are changed after the revision or the timeout is reached (long polling),
+    /// returns the latest revision immediately if the revision is empty,
+    /// returns the ``409-spi-kv-revision-compacted`` error if the changes after the revision have been deleted, the items should be re-read then
+    ///
+    /// 通过key前缀监听Items
+    ///
+    /// 等待直到修订号之后Items发生变更或超时（长轮询），修订号为空时立即返回最新的修订号，
+    /// 修订号之后的变更已被清理时返回 ``409-spi-kv-revision-compacted`` 错误，此时应重新读取Items
+    #[oai(path = "/item/watch", method = "get")]
+    async fn watch_items(
+        &self,
+        key_prefix: Query<String>,
+        key_like: Query<Option<bool>>,
+        revision: Query<Option<i64>>,
+        timeout_sec: Query<Option<u32>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<KvItemWatchResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = kv_item_serv::watch_items(key_prefix.0, key_like.0, revision.0, timeout_sec.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// Match Items
     ///
     /// 匹配Items
@@ -139,6 +165,32 @@ impl KvCiItemApi {
         TardisResp::ok(Void {})
     }
 
+    /// Page Item Histories
+    ///
+    /// 分页获取Item历史版本
+    #[oai(path = "/item/history", method = "get")]
+    async fn page_item_histories(
+        &self,
+        key: Query<String>,
+        page_number: Query<u32>,
+        page_size: Query<u16>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<KvItemHistoryResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = kv_item_serv::page_item_histories(key.0, page_number.0, page_size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Restore Item To History Version
+    ///
+    /// 恢复Item到历史版本
+    #[oai(path = "/item/history/restore", method = "put")]
+    async fn restore_item(&self, mut restore_req: Json<KvItemRestoreReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        kv_item_serv::restore_item(&mut restore_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
     /// Add Or Modify Key-Name
     ///
     /// 添加或修改Key-Name
@@ -15,6 +15,14 @@ pub struct KvItemAddOrModifyReq {
     pub disable: Option<bool>,
     pub info: Option<String>,
     pub scope_level: Option<i16>,
+    /// Time to live in seconds, 0 means never expire, empty means keep the current setting when modifying
+    ///
+    /// 存活时间（秒），0表示永不过期，修改时为空表示保持原有设置
+    pub ttl_sec: Option<u32>,
+    /// Expected current version, the modification is rejected if it does not match, 0 means the item must not exist
+    ///
+    /// 期望的当前版本，不匹配时拒绝修改，0表示该Item必须不存在
+    pub expected_version: Option<i64>,
 }
 impl From<bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq> for KvItemAddOrModifyReq {
     fn from(req: bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq) -> Self {
@@ -24,6 +32,8 @@ impl From<bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq> for KvI
             info: req.info,
             scope_level: req.scope_level,
             disable: None,
+            ttl_sec: None,
+            expected_version: None,
         }
     }
 }
@@ -39,6 +49,11 @@ pub struct KvItemDetailResp {
     pub scope_level: i16,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
+    /// Increased by one on each modification
+    ///
+    /// 每次修改加一
+    pub version: i64,
+    pub expire_time: Option<DateTime<Utc>>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, sea_orm::FromQueryResult)]
@@ -53,6 +68,55 @@ pub struct KvItemSummaryResp {
     pub scope_level: i16,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
+    /// Increased by one on each modification
+    ///
+    /// 每次修改加一
+    pub version: i64,
+    pub expire_time: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, sea_orm::FromQueryResult)]
+pub struct KvItemHistoryResp {
+    pub key: String,
+    pub version: i64,
+    pub value: Value,
+    pub info: String,
+    pub owner: String,
+    pub own_paths: String,
+    pub disable: bool,
+    pub scope_level: i16,
+    pub update_time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemRestoreReq {
+    #[oai(validator(min_length = "2"))]
+    pub key: TrimString,
+    /// The history version to restore
+    ///
+    /// 要恢复的历史版本
+    pub version: i64,
+    pub expected_version: Option<i64>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemWatchResp {
+    /// The latest revision, used as the ``revision`` parameter of the next watch
+    ///
+    /// 最新的修订号，用作下一次监听的``revision``参数
+    pub revision: i64,
+    pub changes: Vec<KvItemChangeResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemChangeResp {
+    pub key: String,
+    pub revision: i64,
+    /// Whether the item has been deleted or expired
+    ///
+    /// Item是否已被删除或过期
+    pub deleted: bool,
+    pub item: Option<KvItemSummaryResp>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
@@ -1,7 +1,7 @@
 use crate::{get_tardis_inst, serv};
 use bios_sdk_invoke::clients::{
     event_client::{get_topic, mq_error, ContextHandler, SPI_RPC_TOPIC},
-    spi_kv_client::{KvItemAddOrModifyReq, KvItemDeleteReq},
+    spi_kv_client::{KvItemAddOrModifyReq, KvItemChangeEvent, KvItemDeleteReq},
 };
 use tardis::basic::result::TardisResult;
 use tardis::{
@@ -22,6 +22,12 @@ async fn handle_kv_delete_event(req: KvItemDeleteReq, ctx: TardisContext) -> Tar
     Ok(())
 }
 
+#[instrument]
+async fn handle_kv_change_event(_req: KvItemChangeEvent, _ctx: TardisContext) -> TardisResult<()> {
+    serv::kv_item_serv::on_remote_change();
+    Ok(())
+}
+
 pub async fn handle_events() -> TardisResult<()> {
     use bios_sdk_invoke::clients::event_client::asteroid_mq::prelude::*;
     if let Some(topic) = get_topic(&SPI_RPC_TOPIC) {
@@ -32,6 +38,7 @@ pub async fn handle_events() -> TardisResult<()> {
             .create_event_loop()
             .with_handler(ContextHandler(handle_kv_add_event))
             .with_handler(ContextHandler(handle_kv_delete_event))
+            .with_handler(ContextHandler(handle_kv_change_event))
             .spawn();
     }
     // let topic = get_topic(&SPI_RPC_TOPIC).expect("topic not initialized");
@@ -2,8 +2,30 @@ use bios_basic::rbum::rbum_config::RbumConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct KvConfig {
     pub rbum: RbumConfig,
+    /// Interval for cleaning expired items
+    pub expire_check_interval_sec: u32,
+    /// Maximum waiting time of a watch request
+    pub watch_max_timeout_sec: u32,
+    /// Interval for re-checking changes while watching,
+    /// ensures the changes made by other nodes are found even if the change events are lost
+    pub watch_poll_interval_sec: u32,
+    /// Retention of the change records used by the watchers, 0 means they are kept forever,
+    /// watching from a revision whose changes have been deleted returns the ``409-spi-kv-revision-compacted`` error
+    pub change_retention_sec: u32,
+}
+
+impl Default for KvConfig {
+    fn default() -> Self {
+        KvConfig {
+            rbum: Default::default(),
+            expire_check_interval_sec: 60,
+            watch_max_timeout_sec: 60,
+            watch_poll_interval_sec: 5,
+            change_retention_sec: 7 * 24 * 60 * 60,
+        }
+    }
 }
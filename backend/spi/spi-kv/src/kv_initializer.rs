@@ -6,11 +6,13 @@ use tardis::{
     TardisFuns, TardisFunsInst,
 };
 
-use crate::{api::ci::kv_ci_item_api, kv_config::KvConfig, kv_constants::DOMAIN_CODE};
+use crate::{api::ci::kv_ci_item_api, kv_config::KvConfig, kv_constants::DOMAIN_CODE, serv::kv_item_serv};
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     info!("[BIOS.KV] Module initializing");
     let mut funs = crate::get_tardis_inst();
+    let expire_check_interval_sec = funs.conf::<KvConfig>().expire_check_interval_sec;
+    let change_retention_sec = funs.conf::<KvConfig>().change_retention_sec;
     crate::event::handle_events().await?;
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<KvConfig>().rbum.clone()).await?;
     funs.begin().await?;
@@ -18,12 +20,16 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     init_db(&funs, &ctx).await?;
     funs.commit().await?;
     init_api(web_server).await?;
+    kv_item_serv::clean_expired_items(expire_check_interval_sec, change_retention_sec).await;
     info!("[BIOS.KV] Module initialized");
     Ok(())
 }
 
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    #[cfg(feature = "spi-pg")]
     spi_initializer::add_kind(spi_constants::SPI_PG_KIND_CODE, funs, ctx).await?;
+    #[cfg(feature = "spi-memory")]
+    spi_initializer::add_kind(spi_constants::SPI_MEMORY_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
@@ -37,6 +43,8 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
     let inst = match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => crate::serv::memory::kv_memory_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }?;
     info!("[BIOS.KV] Fun [{}]({}) initialized", bs_cert.kind_code, bs_cert.conn_uri);
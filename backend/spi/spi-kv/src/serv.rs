@@ -1,2 +1,5 @@
 pub mod kv_item_serv;
+#[cfg(feature = "spi-memory")]
+pub mod memory;
+#[cfg(feature = "spi-pg")]
 pub mod pg;
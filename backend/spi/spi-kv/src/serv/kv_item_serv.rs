@@ -1,15 +1,30 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::log::{trace, warn};
+use tardis::tokio::sync::{Notify, RwLock};
+use tardis::tokio::time::{self, Duration, Instant};
 use tardis::web::web_resp::TardisPage;
+use tardis::TardisFunsInst;
 
 use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use bios_basic::spi_dispatch_service;
+use bios_sdk_invoke::clients::event_client::{get_topic, EventAttributeExt, SPI_RPC_TOPIC};
+use bios_sdk_invoke::clients::spi_kv_client::KvItemChangeEvent;
 
 use crate::dto::kv_item_dto::{
-    KvItemAddOrModifyReq, KvItemDetailResp, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    KvItemAddOrModifyReq, KvItemDetailResp, KvItemHistoryResp, KvItemMatchReq, KvItemRestoreReq, KvItemSummaryResp, KvItemWatchResp, KvNameAddOrModifyReq, KvNameFindResp,
+    KvTagAddOrModifyReq, KvTagFindResp,
 };
+use crate::kv_config::KvConfig;
 use crate::kv_initializer;
 
+#[cfg(feature = "spi-memory")]
+use super::memory;
+#[cfg(feature = "spi-pg")]
 use super::pg;
 
 spi_dispatch_service! {
@@ -18,6 +33,8 @@ spi_dispatch_service! {
     @dispatch: {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::kv_pg_item_serv,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::kv_memory_item_serv,
     },
     @method: {
         add_or_modify_item(add_or_modify_req: &mut KvItemAddOrModifyReq) -> TardisResult<()>;
@@ -40,5 +57,101 @@ spi_dispatch_service! {
         delete_item(key: String) -> TardisResult<()>;
         disable_item(key: String) -> TardisResult<()>;
         enabled_item(key: String) -> TardisResult<()>;
+        page_item_histories(key: String, page_number: u32, page_size: u16) -> TardisResult<TardisPage<KvItemHistoryResp>>;
+        restore_item(restore_req: &mut KvItemRestoreReq) -> TardisResult<()>;
+        delete_expired_items() -> TardisResult<()>;
+        delete_expired_changes(retention_sec: u32) -> TardisResult<()>;
+        find_changes(key_prefix: String, key_like: Option<bool>, revision: Option<i64>) -> TardisResult<KvItemWatchResp>;
+    }
+}
+
+lazy_static! {
+    /// Contexts of the tenants/apps that have written items, used to locate their backend instances when cleaning expired items and changes
+    static ref EXPIRE_CTX_CONTAINER: RwLock<HashMap<String, TardisContext>> = RwLock::new(HashMap::new());
+    /// Wake up the local watchers when items are changed on this node or other nodes
+    static ref CHANGE_NOTIFY: Notify = Notify::new();
+}
+
+/// Notify the watchers of all nodes that the item has been changed
+pub(crate) async fn notify_change(key: &str, funs: &TardisFunsInst, ctx: &TardisContext) {
+    CHANGE_NOTIFY.notify_waiters();
+    if let Some(topic) = get_topic(&SPI_RPC_TOPIC) {
+        let event = KvItemChangeEvent { key: key.to_string() };
+        // The watchers of other nodes will still be woken up by polling if the event fails to be sent
+        if let Err(e) = topic.send_event(event.inject_context(funs, ctx).json()).await {
+            warn!("[SPI-KV] send change event of {} error: {:?}", key, e);
+        }
     }
 }
+
+pub(crate) fn on_remote_change() {
+    CHANGE_NOTIFY.notify_waiters();
+}
+
+/// Wait until the items matching the key prefix are changed after the revision or the timeout is reached
+pub async fn watch_items(
+    key_prefix: String,
+    key_like: Option<bool>,
+    revision: Option<i64>,
+    timeout_sec: Option<u32>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<KvItemWatchResp> {
+    let conf = funs.conf::<KvConfig>();
+    let timeout = Duration::from_secs(timeout_sec.unwrap_or(conf.watch_max_timeout_sec).min(conf.watch_max_timeout_sec) as u64);
+    let poll_interval = Duration::from_secs(conf.watch_poll_interval_sec.max(1) as u64);
+    let deadline = Instant::now() + timeout;
+    loop {
+        // Register before querying to avoid missing the notifications during the query
+        let notified = CHANGE_NOTIFY.notified();
+        let resp = find_changes(key_prefix.clone(), key_like, revision, funs, ctx).await?;
+        let now = Instant::now();
+        if revision.is_none() || !resp.changes.is_empty() || now >= deadline {
+            return Ok(resp);
+        }
+        let _ = time::timeout((deadline - now).min(poll_interval), notified).await;
+    }
+}
+
+/// The changes after the revision have been partially deleted by the retention,
+/// the watcher has to re-read the items and watch again without the revision
+pub(crate) fn check_revision_not_compacted(revision: i64, earliest_revision: i64, funs: &TardisFunsInst) -> TardisResult<()> {
+    if revision < earliest_revision - 1 {
+        return Err(funs.err().conflict(
+            "item",
+            "watch",
+            &format!("The changes after revision {revision} have been compacted, the earliest revision is {earliest_revision}"),
+            "409-spi-kv-revision-compacted",
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) async fn register_expire_ctx(ctx: &TardisContext) {
+    if EXPIRE_CTX_CONTAINER.read().await.contains_key(&ctx.ak) {
+        return;
+    }
+    EXPIRE_CTX_CONTAINER.write().await.insert(ctx.ak.clone(), ctx.clone());
+}
+
+pub async fn clean_expired_items(check_interval_sec: u32, change_retention_sec: u32) {
+    tardis::tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(check_interval_sec.max(1) as u64));
+        loop {
+            interval.tick().await;
+            let ctxs = EXPIRE_CTX_CONTAINER.read().await.values().cloned().collect::<Vec<_>>();
+            let funs = crate::get_tardis_inst();
+            for ctx in ctxs {
+                trace!("[SPI-KV] clean expired items of {}", ctx.ak);
+                if let Err(e) = delete_expired_items(&funs, &ctx).await {
+                    warn!("[SPI-KV] clean expired items of {} error: {:?}", ctx.ak, e);
+                }
+                if change_retention_sec > 0 {
+                    if let Err(e) = delete_expired_changes(change_retention_sec, &funs, &ctx).await {
+                        warn!("[SPI-KV] clean expired changes of {} error: {:?}", ctx.ak, e);
+                    }
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,2 @@
+pub mod kv_memory_initializer;
+pub mod kv_memory_item_serv;
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    serde_json::Value,
+};
+
+#[derive(Default)]
+pub struct KvMemoryStore {
+    pub(crate) items: HashMap<String, KvMemoryItem>,
+    /// Used to keep the insertion order of the items
+    pub(crate) seq: u64,
+    /// Historical versions grouped by key in ascending order of version, equivalent to the ``kv_history`` table,
+    /// kept after the item is deleted
+    pub(crate) histories: HashMap<String, Vec<KvMemoryItem>>,
+    /// Change records in ascending order of revision, equivalent to the ``kv_change`` table
+    pub(crate) changes: Vec<KvMemoryChange>,
+    pub(crate) revision: i64,
+}
+
+#[derive(Clone)]
+pub(crate) struct KvMemoryChange {
+    pub(crate) revision: i64,
+    pub(crate) key: String,
+    pub(crate) deleted: bool,
+    pub(crate) own_paths: String,
+    pub(crate) scope_level: i16,
+    pub(crate) create_time: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub(crate) struct KvMemoryItem {
+    pub(crate) seq: u64,
+    pub(crate) key: String,
+    pub(crate) value: Value,
+    pub(crate) info: String,
+    pub(crate) owner: String,
+    pub(crate) own_paths: String,
+    pub(crate) disable: bool,
+    pub(crate) scope_level: i16,
+    pub(crate) version: i64,
+    pub(crate) expire_time: Option<DateTime<Utc>>,
+    pub(crate) create_time: DateTime<Utc>,
+    pub(crate) update_time: DateTime<Utc>,
+}
+
+impl KvMemoryItem {
+    pub(crate) fn alive(&self) -> bool {
+        self.expire_time.map(|expire_time| expire_time > Utc::now()).unwrap_or(true)
+    }
+}
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    spi_initializer::common_memory::init::<KvMemoryStore>(bs_cert, ctx, mgr).await
+}
@@ -0,0 +1,625 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use bios_basic::{
+    rbum::{dto::rbum_filer_dto::RbumBasicFilterReq, helper::rbum_scope_helper},
+    spi::{spi_funs::SpiBsInst, spi_initializer::common_memory::SpiMemoryClient},
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{Duration, Utc},
+    serde_json::{json, Value},
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::kv_item_dto::{
+        KvItemAddOrModifyReq, KvItemChangeResp, KvItemDetailResp, KvItemHistoryResp, KvItemMatchReq, KvItemRestoreReq, KvItemSummaryResp, KvItemWatchResp, KvNameAddOrModifyReq,
+        KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    },
+    kv_constants,
+    serv::kv_item_serv,
+};
+
+use super::kv_memory_initializer::{KvMemoryChange, KvMemoryItem, KvMemoryStore};
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<KvMemoryStore> {
+    inst.inst::<SpiMemoryClient<KvMemoryStore>>().0
+}
+
+fn check_scope(item: &KvMemoryItem, ctx: &TardisContext) -> bool {
+    rbum_scope_helper::check_scope(
+        &item.own_paths,
+        Some(item.scope_level),
+        &RbumBasicFilterReq {
+            ignore_scope: false,
+            ..Default::default()
+        },
+        &ctx.own_paths,
+    )
+}
+
+fn extract_value(value: &Value, extract: Option<&str>) -> Value {
+    if let Some(extract) = extract {
+        value.get(extract).cloned().unwrap_or(Value::Null)
+    } else {
+        value.clone()
+    }
+}
+
+fn package_summary(item: &KvMemoryItem, extract: Option<&str>) -> KvItemSummaryResp {
+    KvItemSummaryResp {
+        key: item.key.clone(),
+        value: extract_value(&item.value, extract),
+        info: item.info.clone(),
+        owner: item.owner.clone(),
+        own_paths: item.own_paths.clone(),
+        disable: item.disable,
+        scope_level: item.scope_level,
+        version: item.version,
+        expire_time: item.expire_time,
+        create_time: item.create_time,
+        update_time: item.update_time,
+    }
+}
+
+fn record_change(store: &mut KvMemoryStore, item: &KvMemoryItem, deleted: bool) {
+    store.revision += 1;
+    let change = KvMemoryChange {
+        revision: store.revision,
+        key: item.key.clone(),
+        deleted,
+        own_paths: item.own_paths.clone(),
+        scope_level: item.scope_level,
+        create_time: Utc::now(),
+    };
+    store.changes.push(change);
+}
+
+fn record_history(store: &mut KvMemoryStore, key: &str) {
+    if let Some(item) = store.items.get(key).cloned() {
+        record_change(store, &item, false);
+        store.histories.entry(item.key.clone()).or_default().push(item);
+    }
+}
+
+pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let now = Utc::now();
+    let key = add_or_modify_req.key.to_string();
+    if store.items.get(&key).map(|item| !item.alive()).unwrap_or(false) {
+        store.items.remove(&key);
+    }
+    let current_version = store.items.get(&key).map(|item| item.version).unwrap_or(0);
+    if let Some(expected_version) = add_or_modify_req.expected_version {
+        if expected_version != current_version {
+            return Err(funs.err().conflict(
+                "item",
+                "add_or_modify",
+                &format!("The version of item [{key}] is {current_version}, but {expected_version} is expected"),
+                "409-spi-kv-version-conflict",
+            ));
+        }
+    }
+    // The version keeps increasing even if the item has been deleted and re-added
+    let version = store.histories.get(&key).and_then(|histories| histories.last()).map(|history| history.version).unwrap_or(0).max(current_version) + 1;
+    let expire_time = add_or_modify_req.ttl_sec.map(|ttl_sec| if ttl_sec > 0 { Some(now + Duration::seconds(ttl_sec as i64)) } else { None });
+    if let Some(item) = store.items.get_mut(&key) {
+        item.value = add_or_modify_req.value.clone();
+        if let Some(info) = &add_or_modify_req.info {
+            item.info = info.clone();
+        }
+        item.owner = ctx.owner.clone();
+        item.own_paths = ctx.own_paths.clone();
+        if let Some(disable) = add_or_modify_req.disable {
+            item.disable = disable;
+        }
+        if let Some(scope_level) = add_or_modify_req.scope_level {
+            item.scope_level = scope_level;
+        }
+        item.version = version;
+        if let Some(expire_time) = expire_time {
+            item.expire_time = expire_time;
+        }
+        item.update_time = now;
+    } else {
+        store.seq += 1;
+        let item = KvMemoryItem {
+            seq: store.seq,
+            key: key.clone(),
+            value: add_or_modify_req.value.clone(),
+            info: add_or_modify_req.info.clone().unwrap_or_default(),
+            owner: ctx.owner.clone(),
+            own_paths: ctx.own_paths.clone(),
+            disable: add_or_modify_req.disable.unwrap_or(false),
+            scope_level: add_or_modify_req.scope_level.unwrap_or(0),
+            version,
+            expire_time: expire_time.flatten(),
+            create_time: now,
+            update_time: now,
+        };
+        store.items.insert(item.key.clone(), item);
+    }
+    record_history(&mut store, &key);
+    drop(store);
+    kv_item_serv::notify_change(&key, funs, ctx).await;
+    Ok(())
+}
+
+pub async fn add_or_modify_key_name(add_or_modify_req: &mut KvNameAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let req = KvItemAddOrModifyReq {
+        key: format!("{}{}", kv_constants::KEY_PREFIX_BY_KEY_NAME, add_or_modify_req.key).into(),
+        value: json!(add_or_modify_req.name),
+        scope_level: add_or_modify_req.scope_level,
+        disable: add_or_modify_req.disable,
+        info: None,
+        ttl_sec: None,
+        expected_version: None,
+    };
+    self::add_or_modify_item(&req, funs, ctx, inst).await
+}
+
+pub async fn add_or_modify_tag(add_or_modify_req: &mut KvTagAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let req = KvItemAddOrModifyReq {
+        key: format!("{}{}", kv_constants::KEY_PREFIX_BY_TAG, add_or_modify_req.key).into(),
+        value: TardisFuns::json.obj_to_json(&add_or_modify_req.items)?,
+        scope_level: add_or_modify_req.scope_level,
+        info: None,
+        disable: add_or_modify_req.disable,
+        ttl_sec: None,
+        expected_version: None,
+    };
+    self::add_or_modify_item(&req, funs, ctx, inst).await
+}
+
+pub async fn get_item(key: String, extract: Option<String>, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<KvItemDetailResp>> {
+    let store = get_client(inst).read().await;
+    Ok(store.items.get(&key).filter(|item| item.alive() && check_scope(item, ctx)).map(|item| KvItemDetailResp {
+        key: item.key.clone(),
+        value: extract_value(&item.value, extract.as_deref()),
+        info: item.info.clone(),
+        owner: item.owner.clone(),
+        own_paths: item.own_paths.clone(),
+        disable: item.disable,
+        scope_level: item.scope_level,
+        version: item.version,
+        expire_time: item.expire_time,
+        create_time: item.create_time,
+        update_time: item.update_time,
+    }))
+}
+
+pub async fn find_items(keys: Vec<String>, extract: Option<String>, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<KvItemSummaryResp>> {
+    let store = get_client(inst).read().await;
+    let mut items = keys.iter().filter_map(|key| store.items.get(key)).filter(|item| item.alive() && check_scope(item, ctx)).collect::<Vec<_>>();
+    items.sort_by_key(|item| item.seq);
+    items.dedup_by_key(|item| item.seq);
+    Ok(items.into_iter().map(|item| package_summary(item, extract.as_deref())).collect())
+}
+
+pub async fn find_key_names(keys: Vec<String>, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<KvNameFindResp>> {
+    let keys = keys.into_iter().map(|key| format!("{}{}", kv_constants::KEY_PREFIX_BY_KEY_NAME, key)).collect();
+    let items = self::find_items(keys, None, funs, ctx, inst).await?;
+    Ok(items
+        .into_iter()
+        .map(|item| KvNameFindResp {
+            key: item.key.strip_prefix(kv_constants::KEY_PREFIX_BY_KEY_NAME).unwrap_or("").to_string(),
+            name: item.value.as_str().unwrap_or("").to_string(),
+            disable: item.disable,
+            create_time: item.create_time,
+            update_time: item.update_time,
+        })
+        .collect())
+}
+
+pub async fn find_tags(keys: Vec<String>, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<KvTagFindResp>> {
+    let keys = keys.iter().map(|r| format!("{}{}", kv_constants::KEY_PREFIX_BY_TAG, r)).collect::<Vec<_>>();
+    let items = self::find_items(keys, None, funs, ctx, inst).await?;
+    items
+        .into_iter()
+        .map(|item| {
+            Ok(KvTagFindResp {
+                key: item.key.strip_prefix(kv_constants::KEY_PREFIX_BY_TAG).unwrap_or("").to_string(),
+                items: TardisFuns::json.json_to_obj(item.value)?,
+                disable: item.disable,
+                create_time: item.create_time,
+                update_time: item.update_time,
+            })
+        })
+        .collect::<TardisResult<Vec<_>>>()
+}
+
+pub async fn match_items(match_req: KvItemMatchReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<KvItemSummaryResp>> {
+    let store = get_client(inst).read().await;
+    let mut items = Vec::new();
+    for item in store.items.values().filter(|item| item.alive()) {
+        if match_req.key_like.unwrap_or(true) {
+            if !item.key.starts_with(&match_req.key_prefix) {
+                continue;
+            }
+        } else if item.key != match_req.key_prefix {
+            continue;
+        }
+        if let Some(query_path) = &match_req.query_path {
+            if !json_path::exists(&item.value, query_path, match_req.query_values.as_ref())? {
+                continue;
+            }
+        }
+        if match_req.create_time_start.map(|t| item.create_time < t).unwrap_or(false)
+            || match_req.create_time_end.map(|t| item.create_time > t).unwrap_or(false)
+            || match_req.update_time_start.map(|t| item.update_time < t).unwrap_or(false)
+            || match_req.update_time_end.map(|t| item.update_time > t).unwrap_or(false)
+            || match_req.disable.map(|disable| item.disable != disable).unwrap_or(false)
+            || !check_scope(item, ctx)
+        {
+            continue;
+        }
+        items.push(item);
+    }
+    items.sort_by(|a, b| {
+        let mut ordering = Ordering::Equal;
+        if let Some(desc_sort_by_create) = match_req.desc_sort_by_create {
+            ordering = ordering.then(if desc_sort_by_create {
+                b.create_time.cmp(&a.create_time)
+            } else {
+                a.create_time.cmp(&b.create_time)
+            });
+        }
+        if let Some(desc_sort_by_update) = match_req.desc_sort_by_update {
+            ordering = ordering.then(if desc_sort_by_update {
+                b.update_time.cmp(&a.update_time)
+            } else {
+                a.update_time.cmp(&b.update_time)
+            });
+        }
+        ordering.then(a.seq.cmp(&b.seq))
+    });
+    let total_size = items.len() as u64;
+    let records = items
+        .into_iter()
+        .skip((match_req.page_number.max(1) as usize - 1) * match_req.page_size as usize)
+        .take(match_req.page_size as usize)
+        .map(|item| package_summary(item, match_req.extract.as_deref()))
+        .collect();
+    Ok(TardisPage {
+        page_size: match_req.page_size as u64,
+        page_number: match_req.page_number as u64,
+        total_size,
+        records,
+    })
+}
+
+pub async fn delete_item(key: String, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let Some(item) = store.items.remove(&key) else {
+        return Ok(());
+    };
+    record_change(&mut store, &item, true);
+    drop(store);
+    kv_item_serv::notify_change(&key, funs, ctx).await;
+    Ok(())
+}
+
+async fn modify_disable(key: &str, disable: bool, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let Some(item) = store.items.get_mut(key).filter(|item| item.alive()) else {
+        return Ok(());
+    };
+    item.disable = disable;
+    item.version += 1;
+    item.update_time = Utc::now();
+    record_history(&mut store, key);
+    drop(store);
+    kv_item_serv::notify_change(key, funs, ctx).await;
+    Ok(())
+}
+
+pub async fn disable_item(key: String, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify_disable(&key, true, funs, ctx, inst).await
+}
+
+pub async fn enabled_item(key: String, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify_disable(&key, false, funs, ctx, inst).await
+}
+
+pub async fn page_tags(
+    key_prefix: String,
+    key_like: Option<bool>,
+    page_number: u32,
+    page_size: u16,
+    disable: Option<bool>,
+    desc_sort_by_create: Option<bool>,
+    desc_sort_by_update: Option<bool>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<KvTagFindResp>> {
+    let key_prefix = format!("{}{}", kv_constants::KEY_PREFIX_BY_TAG, key_prefix);
+    let items = self::match_items(
+        KvItemMatchReq {
+            key_prefix,
+            key_like,
+            page_number,
+            page_size,
+            disable,
+            desc_sort_by_create,
+            desc_sort_by_update,
+            ..Default::default()
+        },
+        funs,
+        ctx,
+        inst,
+    )
+    .await?;
+    Ok(TardisPage {
+        page_size: items.page_size,
+        page_number: items.page_number,
+        total_size: items.total_size,
+        records: items
+            .records
+            .into_iter()
+            .map(|item| {
+                Ok(KvTagFindResp {
+                    key: item.key.strip_prefix(kv_constants::KEY_PREFIX_BY_TAG).unwrap_or("").to_string(),
+                    items: TardisFuns::json.json_to_obj(item.value)?,
+                    disable: item.disable,
+                    create_time: item.create_time,
+                    update_time: item.update_time,
+                })
+            })
+            .collect::<TardisResult<Vec<_>>>()?,
+    })
+}
+
+fn package_history(item: &KvMemoryItem) -> KvItemHistoryResp {
+    KvItemHistoryResp {
+        key: item.key.clone(),
+        version: item.version,
+        value: item.value.clone(),
+        info: item.info.clone(),
+        owner: item.owner.clone(),
+        own_paths: item.own_paths.clone(),
+        disable: item.disable,
+        scope_level: item.scope_level,
+        update_time: item.update_time,
+    }
+}
+
+pub async fn page_item_histories(
+    key: String,
+    page_number: u32,
+    page_size: u16,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<KvItemHistoryResp>> {
+    let store = get_client(inst).read().await;
+    let histories = store.histories.get(&key).map(|histories| histories.iter().rev().collect::<Vec<_>>()).unwrap_or_default();
+    let total_size = histories.len() as u64;
+    let records = histories
+        .into_iter()
+        .skip((page_number.max(1) as usize - 1) * page_size as usize)
+        .take(page_size as usize)
+        .filter(|history| check_scope(history, ctx))
+        .map(package_history)
+        .collect();
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size,
+        records,
+    })
+}
+
+pub async fn restore_item(restore_req: &mut KvItemRestoreReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let history = get_client(inst)
+        .read()
+        .await
+        .histories
+        .get(&restore_req.key.to_string())
+        .and_then(|histories| histories.iter().find(|history| history.version == restore_req.version))
+        .filter(|history| check_scope(history, ctx))
+        .cloned()
+        .ok_or_else(|| funs.err().not_found("item", "restore", "The history version of item does not exist", "404-spi-kv-history-not-exist"))?;
+    let req = KvItemAddOrModifyReq {
+        key: restore_req.key.clone(),
+        value: history.value,
+        info: Some(history.info),
+        disable: Some(history.disable),
+        scope_level: Some(history.scope_level),
+        ttl_sec: None,
+        expected_version: restore_req.expected_version,
+    };
+    self::add_or_modify_item(&req, funs, ctx, inst).await
+}
+
+pub async fn delete_expired_items(funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let expired_items = store.items.values().filter(|item| !item.alive()).cloned().collect::<Vec<_>>();
+    for item in &expired_items {
+        store.items.remove(&item.key);
+        record_change(&mut store, item, true);
+    }
+    drop(store);
+    for item in expired_items {
+        kv_item_serv::notify_change(&item.key, funs, ctx).await;
+    }
+    Ok(())
+}
+
+/// Delete the changes older than the retention, the latest change is always kept like the PostgreSQL implementation
+pub async fn delete_expired_changes(retention_sec: u32, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let expire_time = Utc::now() - Duration::seconds(retention_sec as i64);
+    let latest_revision = store.revision;
+    store.changes.retain(|change| change.create_time >= expire_time || change.revision == latest_revision);
+    Ok(())
+}
+
+pub async fn find_changes(
+    key_prefix: String,
+    key_like: Option<bool>,
+    revision: Option<i64>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<KvItemWatchResp> {
+    let store = get_client(inst).read().await;
+    let Some(revision) = revision else {
+        return Ok(KvItemWatchResp {
+            revision: store.revision,
+            changes: vec![],
+        });
+    };
+    kv_item_serv::check_revision_not_compacted(revision, store.changes.first().map(|change| change.revision).unwrap_or(0), funs)?;
+    // Only the latest change of each key is returned, the item is taken from the current value
+    let mut latest_changes: HashMap<&str, &KvMemoryChange> = HashMap::new();
+    for change in store.changes.iter().filter(|change| change.revision > revision) {
+        let matched = if key_like.unwrap_or(true) {
+            change.key.starts_with(&key_prefix)
+        } else {
+            change.key == key_prefix
+        };
+        if matched {
+            latest_changes.insert(change.key.as_str(), change);
+        }
+    }
+    let mut latest_changes = latest_changes.into_values().collect::<Vec<_>>();
+    latest_changes.sort_by_key(|change| change.revision);
+    let changes = latest_changes
+        .into_iter()
+        .filter_map(|change| {
+            let item = store.items.get(&change.key).filter(|item| !change.deleted && item.alive());
+            let visible = if let Some(item) = item {
+                check_scope(item, ctx)
+            } else {
+                rbum_scope_helper::check_scope(
+                    &change.own_paths,
+                    Some(change.scope_level),
+                    &RbumBasicFilterReq {
+                        ignore_scope: false,
+                        ..Default::default()
+                    },
+                    &ctx.own_paths,
+                )
+            };
+            visible.then(|| KvItemChangeResp {
+                key: change.key.clone(),
+                revision: change.revision,
+                deleted: item.is_none(),
+                item: item.map(|item| package_summary(item, None)),
+            })
+        })
+        .collect();
+    Ok(KvItemWatchResp {
+        revision: store.revision.max(revision),
+        changes,
+    })
+}
+
+/// A subset of the PostgreSQL ``jsonb_path_exists`` semantics (lax mode)
+///
+/// Supported: ``$.a.b``, ``$.a[*]``, ``$.*`` and a single filter expression ``? (@[.a.b] <op> <operand>)``,
+/// ``<op>`` is one of ``==, !=, <>, <, <=, >, >=``, ``<operand>`` is a ``$variable``, a string, a number, ``true``, ``false`` or ``null``.
+mod json_path {
+    use std::{cmp::Ordering, collections::HashMap};
+
+    use tardis::{
+        basic::{error::TardisError, result::TardisResult},
+        serde_json::Value,
+    };
+
+    fn unsupported(query_path: &str) -> TardisError {
+        TardisError::bad_request(
+            &format!("The query path [{query_path}] is not supported by the memory backend"),
+            "400-spi-kv-query-path-unsupported",
+        )
+    }
+
+    fn select<'a>(values: Vec<&'a Value>, path: &str, query_path: &str) -> TardisResult<Vec<&'a Value>> {
+        let mut values = values;
+        let mut rest = path;
+        while !rest.is_empty() {
+            let (segment, next) = if let Some(stripped) = rest.strip_prefix("[*]") {
+                ("*", stripped)
+            } else if let Some(stripped) = rest.strip_prefix('.') {
+                let end = stripped.find(['.', '[']).unwrap_or(stripped.len());
+                (&stripped[..end], &stripped[end..])
+            } else {
+                return Err(unsupported(query_path));
+            };
+            let segment = segment.trim_matches('"');
+            values = values
+                .into_iter()
+                .flat_map(|value| match (value, segment) {
+                    (Value::Array(arr), "*") => arr.iter().collect::<Vec<_>>(),
+                    (Value::Object(obj), "*") => obj.values().collect(),
+                    // Lax mode: automatically unwrap the array
+                    (Value::Array(arr), segment) => arr.iter().filter_map(|v| v.get(segment)).collect(),
+                    (value, segment) => value.get(segment).into_iter().collect(),
+                })
+                .collect();
+            rest = next;
+        }
+        Ok(values)
+    }
+
+    fn parse_operand(operand: &str, query_values: Option<&Value>, query_path: &str) -> TardisResult<Value> {
+        let operand = operand.trim();
+        if let Some(var) = operand.strip_prefix('$') {
+            return Ok(query_values.and_then(|values| values.get(var)).cloned().unwrap_or(Value::Null));
+        }
+        tardis::serde_json::from_str(operand).map_err(|_| unsupported(query_path))
+    }
+
+    fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+        match (left, right) {
+            (Value::Number(l), Value::Number(r)) => l.as_f64()?.partial_cmp(&r.as_f64()?),
+            (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+            (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+            (Value::Null, Value::Null) => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+
+    pub fn exists(value: &Value, query_path: &str, query_values: Option<&Value>) -> TardisResult<bool> {
+        let (path, filter) = match query_path.split_once('?') {
+            Some((path, filter)) => (path.trim(), Some(filter.trim())),
+            None => (query_path.trim(), None),
+        };
+        let path = path.strip_prefix('$').ok_or_else(|| unsupported(query_path))?;
+        let values = select(vec![value], path, query_path)?;
+        let Some(filter) = filter else {
+            return Ok(!values.is_empty());
+        };
+        let filter = filter.strip_prefix('(').and_then(|f| f.strip_suffix(')')).ok_or_else(|| unsupported(query_path))?.trim();
+        let filter = filter.strip_prefix('@').ok_or_else(|| unsupported(query_path))?;
+        let (op_idx, op) = ["==", "!=", "<>", "<=", ">=", "<", ">"]
+            .iter()
+            .filter_map(|op| filter.find(op).map(|idx| (idx, *op)))
+            .min_by_key(|(idx, _)| *idx)
+            .ok_or_else(|| unsupported(query_path))?;
+        let filter_path = filter[..op_idx].trim();
+        let operand = parse_operand(&filter[op_idx + op.len()..], query_values, query_path)?;
+        // Lax mode: automatically unwrap the array
+        let values = values.into_iter().flat_map(|value| if let Value::Array(arr) = value { arr.iter().collect() } else { vec![value] }).collect::<Vec<_>>();
+        for value in values {
+            for left in select(vec![value], filter_path, query_path)? {
+                let ordering = compare(left, &operand);
+                let matched = match op {
+                    "==" => ordering == Some(Ordering::Equal),
+                    "!=" | "<>" => ordering.map(|o| o != Ordering::Equal).unwrap_or(false),
+                    "<" => ordering == Some(Ordering::Less),
+                    "<=" => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    ">" => ordering == Some(Ordering::Greater),
+                    ">=" => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                    _ => false,
+                };
+                if matched {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
@@ -5,7 +5,7 @@ use tardis::{
 };
 
 pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
-    spi_initializer::common_pg::init_table_and_conn(
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
         ctx,
         mgr,
@@ -18,12 +18,70 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
     owner VARCHAR(255) NULL,
     scope_level SMALLINT NULL,
     disable BOOLEAN NOT NULL,
+    version BIGINT NOT NULL DEFAULT 1,
+    expire_time timestamp with time zone NULL,
     create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
         None,
-        vec![("k", "btree"), ("v", "gin")],
+        vec![("k", "btree"), ("v", "gin"), ("expire_time", "btree")],
         None,
         Some("update_time"),
     )
+    .await?;
+    // Add the version and expiration fields (compatible with the existing tables)
+    conn.execute_one(
+        &format!(
+            r#"ALTER TABLE {table_name}
+  ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1,
+  ADD COLUMN IF NOT EXISTS expire_time timestamp with time zone NULL;"#
+        ),
+        vec![],
+    )
+    .await?;
+    Ok((conn, table_name))
+}
+
+pub async fn init_history_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "kv_history",
+        r#"k character varying NOT NULL,
+    version BIGINT NOT NULL,
+    v jsonb NOT NULL,
+    info character varying  NOT NULL,
+    own_paths VARCHAR(255) NULL,
+    owner VARCHAR(255) NULL,
+    scope_level SMALLINT NULL,
+    disable BOOLEAN NOT NULL,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        None,
+        vec![("k", "btree")],
+        Some(vec!["k", "version"]),
+        None,
+    )
+    .await
+}
+
+pub async fn init_change_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "kv_change",
+        r#"revision BIGSERIAL NOT NULL PRIMARY KEY,
+    k character varying NOT NULL,
+    deleted BOOLEAN NOT NULL,
+    own_paths VARCHAR(255) NULL,
+    scope_level SMALLINT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        None,
+        vec![("k", "btree")],
+        None,
+        None,
+    )
     .await
 }
@@ -3,29 +3,83 @@ use bios_basic::{
     spi::spi_funs::{SpiBsInst, SpiBsInstExtractor},
 };
 use tardis::{
-    basic::{dto::TardisContext, result::TardisResult},
-    db::{reldb_client::TardisRelDBClient, sea_orm::Value},
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
     serde_json::json,
     web::web_resp::TardisPage,
     TardisFuns, TardisFunsInst,
 };
 
 use crate::{
-    dto::kv_item_dto::{KvItemAddOrModifyReq, KvItemDetailResp, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp},
+    dto::kv_item_dto::{
+        KvItemAddOrModifyReq, KvItemChangeResp, KvItemDetailResp, KvItemHistoryResp, KvItemMatchReq, KvItemRestoreReq, KvItemSummaryResp, KvItemWatchResp, KvNameAddOrModifyReq,
+        KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    },
     kv_constants,
+    serv::kv_item_serv,
 };
 
 use super::kv_pg_initializer;
 
-pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+/// Filter out the expired items, the expired items that have not been cleaned are treated as non-existent
+const ALIVE_CONDITION: &str = "(expire_time IS NULL OR expire_time > CURRENT_TIMESTAMP)";
+
+pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let key = add_or_modify_req.key.to_string();
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let (_, change_table_name) = kv_pg_initializer::init_change_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    // Serialize the modifications of the same key, including the creation of a non-existent key
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(format!("{table_name}:{key}"))]).await?;
+    let current = conn
+        .query_one(
+            &format!("SELECT version, (expire_time IS NOT NULL AND expire_time <= CURRENT_TIMESTAMP) AS expired FROM {table_name} WHERE k = $1"),
+            vec![Value::from(key.as_str())],
+        )
+        .await?;
+    let current_version = match current {
+        Some(current) if current.try_get::<bool>("", "expired")? => {
+            conn.execute_one(&format!("DELETE FROM {table_name} WHERE k = $1"), vec![Value::from(key.as_str())]).await?;
+            0
+        }
+        Some(current) => current.try_get::<i64>("", "version")?,
+        None => 0,
+    };
+    if let Some(expected_version) = add_or_modify_req.expected_version {
+        if expected_version != current_version {
+            return Err(funs.err().conflict(
+                "item",
+                "add_or_modify",
+                &format!("The version of item [{key}] is {current_version}, but {expected_version} is expected"),
+                "409-spi-kv-version-conflict",
+            ));
+        }
+    }
+    // The version keeps increasing even if the item has been deleted and re-added
+    let max_history_version = conn
+        .query_one(
+            &format!("SELECT COALESCE(MAX(version), 0) AS version FROM {history_table_name} WHERE k = $1"),
+            vec![Value::from(key.as_str())],
+        )
+        .await?
+        .map(|r| r.try_get::<i64>("", "version"))
+        .transpose()?
+        .unwrap_or(0);
     let params = vec![
-        Value::from(add_or_modify_req.key.to_string()),
+        Value::from(key.as_str()),
         Value::from(add_or_modify_req.value.clone()),
         Value::from(add_or_modify_req.info.as_ref().unwrap_or(&"".to_string()).as_str()),
         Value::from(ctx.owner.clone()),
         Value::from(ctx.own_paths.clone()),
         Value::from(add_or_modify_req.disable.unwrap_or(false)),
         Value::from(add_or_modify_req.scope_level.unwrap_or(0)),
+        Value::from(current_version.max(max_history_version) + 1),
+        Value::from(add_or_modify_req.ttl_sec.unwrap_or(0) as i64),
     ];
     let mut update_opt_fragments: Vec<&str> = Vec::new();
     update_opt_fragments.push("v = $2");
@@ -40,15 +94,16 @@ pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, _funs:
     if add_or_modify_req.scope_level.is_some() {
         update_opt_fragments.push("scope_level = $7");
     }
-    let bs_inst = inst.inst::<TardisRelDBClient>();
-    let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
-    conn.begin().await?;
+    update_opt_fragments.push("version = $8");
+    if add_or_modify_req.ttl_sec.is_some() {
+        update_opt_fragments.push("expire_time = EXCLUDED.expire_time");
+    }
     conn.execute_one(
         &format!(
             r#"INSERT INTO {} 
-    (k, v, info, owner, own_paths, disable, scope_level)
+    (k, v, info, owner, own_paths, disable, scope_level, version, expire_time)
 VALUES
-    ($1, $2, $3, $4, $5, $6, $7)
+    ($1, $2, $3, $4, $5, $6, $7, $8, CASE WHEN $9 > 0 THEN CURRENT_TIMESTAMP + $9 * INTERVAL '1 second' ELSE NULL END)
 ON CONFLICT (k)
 DO UPDATE SET
     {}
@@ -59,7 +114,82 @@ DO UPDATE SET
         params,
     )
     .await?;
+    record_history(&key, &conn, &table_name, &history_table_name).await?;
+    record_change(&key, &conn, &table_name, &change_table_name).await?;
     conn.commit().await?;
+    kv_item_serv::notify_change(&key, funs, ctx).await;
+    Ok(())
+}
+
+/// Serialize the allocations of the revisions until the transaction is committed,
+/// otherwise a smaller revision may be committed after a larger one and be skipped by the watchers.
+///
+/// It must be the last lock taken by the transaction, after the locks of the items, to avoid deadlocks
+async fn lock_change_table(conn: &TardisRelDBlConnection, change_table_name: &str) -> TardisResult<()> {
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(change_table_name)]).await?;
+    Ok(())
+}
+
+async fn record_change(key: &str, conn: &TardisRelDBlConnection, table_name: &str, change_table_name: &str) -> TardisResult<()> {
+    lock_change_table(conn, change_table_name).await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {change_table_name}
+    (k, deleted, own_paths, scope_level)
+SELECT k, false, own_paths, scope_level
+FROM {table_name}
+WHERE
+    k = $1"#
+        ),
+        vec![Value::from(key)],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Delete the items matching the condition and record the deletions, returns the deleted keys
+async fn delete_and_record_change(condition: &str, params: Vec<Value>, conn: &TardisRelDBlConnection, table_name: &str, change_table_name: &str) -> TardisResult<Vec<String>> {
+    // The rows are deleted (locked) before the change table is locked
+    let deleted = conn.query_all(&format!("DELETE FROM {table_name} WHERE {condition} RETURNING k, own_paths, scope_level"), params).await?;
+    if deleted.is_empty() {
+        return Ok(vec![]);
+    }
+    lock_change_table(conn, change_table_name).await?;
+    let mut deleted_keys = Vec::with_capacity(deleted.len());
+    for r in deleted {
+        let key: String = r.try_get("", "k")?;
+        conn.execute_one(
+            &format!(
+                r#"INSERT INTO {change_table_name}
+    (k, deleted, own_paths, scope_level)
+VALUES
+    ($1, true, $2, $3)"#
+            ),
+            vec![
+                Value::from(key.as_str()),
+                Value::from(r.try_get::<Option<String>>("", "own_paths")?),
+                Value::from(r.try_get::<Option<i16>>("", "scope_level")?),
+            ],
+        )
+        .await?;
+        deleted_keys.push(key);
+    }
+    Ok(deleted_keys)
+}
+
+async fn record_history(key: &str, conn: &TardisRelDBlConnection, table_name: &str, history_table_name: &str) -> TardisResult<()> {
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {history_table_name}
+    (k, version, v, info, owner, own_paths, disable, scope_level)
+SELECT k, version, v, info, owner, own_paths, disable, scope_level
+FROM {table_name}
+WHERE
+    k = $1"#
+        ),
+        vec![Value::from(key)],
+    )
+    .await?;
     Ok(())
 }
 
@@ -70,6 +200,8 @@ pub async fn add_or_modify_key_name(add_or_modify_req: &mut KvNameAddOrModifyReq
         scope_level: add_or_modify_req.scope_level,
         disable: add_or_modify_req.disable,
         info: None,
+        ttl_sec: None,
+        expected_version: None,
     };
     self::add_or_modify_item(&req, funs, ctx, inst).await
 }
@@ -81,6 +213,8 @@ pub async fn add_or_modify_tag(add_or_modify_req: &mut KvTagAddOrModifyReq, funs
         scope_level: add_or_modify_req.scope_level,
         info: None,
         disable: add_or_modify_req.disable,
+        ttl_sec: None,
+        expected_version: None,
     };
     self::add_or_modify_item(&req, funs, ctx, inst).await
 }
@@ -91,12 +225,13 @@ pub async fn get_item(key: String, extract: Option<String>, _funs: &TardisFunsIn
     let result = conn
         .get_dto_by_sql::<KvItemDetailResp>(
             &format!(
-                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, create_time, update_time
+                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, version, expire_time, create_time, update_time
 FROM {}
 WHERE 
-    k = $1"#,
+    k = $1 AND {}"#,
                 if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() },
                 table_name,
+                ALIVE_CONDITION,
             ),
             vec![Value::from(key)],
         )
@@ -133,13 +268,14 @@ pub async fn find_items(keys: Vec<String>, extract: Option<String>, funs: &Tardi
     let result = conn
         .find_dtos_by_sql::<KvItemSummaryResp>(
             &format!(
-                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, create_time, update_time
+                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, version, expire_time, create_time, update_time
 FROM {}
 WHERE 
-    k IN ({})"#,
+    k IN ({}) AND {}"#,
                 if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() },
                 table_name,
-                place_holder
+                place_holder,
+                ALIVE_CONDITION
             ),
             sql_vals,
         )
@@ -199,6 +335,7 @@ pub async fn match_items(match_req: KvItemMatchReq, _funs: &TardisFunsInst, ctx:
     let mut where_fragments: Vec<String> = Vec::new();
     let mut sql_vals: Vec<Value> = vec![];
     let mut order_fragments: Vec<String> = Vec::new();
+    where_fragments.push(ALIVE_CONDITION.to_string());
     if match_req.key_like.unwrap_or(true) {
         sql_vals.push(Value::from(format!("{}%", match_req.key_prefix)));
         where_fragments.push(format!("k LIKE ${}", sql_vals.len()));
@@ -259,7 +396,7 @@ pub async fn match_items(match_req: KvItemMatchReq, _funs: &TardisFunsInst, ctx:
     let result = conn
         .query_all(
             &format!(
-                r#"SELECT k, v{} AS v, info, owner, own_paths, disable, scope_level, create_time, update_time, count(*) OVER() AS total
+                r#"SELECT k, v{} AS v, info, owner, own_paths, disable, scope_level, version, expire_time, create_time, update_time, count(*) OVER() AS total
 FROM {}
 WHERE 
     {}
@@ -299,6 +436,8 @@ WHERE
                 own_paths: item.try_get("", "own_paths")?,
                 disable: item.try_get("", "disable")?,
                 scope_level: item.try_get("", "scope_level")?,
+                version: item.try_get("", "version")?,
+                expire_time: item.try_get("", "expire_time")?,
                 create_time: item.try_get("", "create_time")?,
                 update_time: item.try_get("", "update_time")?,
             })
@@ -324,31 +463,47 @@ WHERE
     })
 }
 
-pub async fn delete_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+pub async fn delete_item(key: String, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let (_, change_table_name) = kv_pg_initializer::init_change_table_and_conn(bs_inst, ctx, true).await?;
     conn.begin().await?;
-    conn.execute_one(&format!("DELETE FROM {table_name} WHERE k = $1"), vec![Value::from(key)]).await?;
+    let deleted_keys = delete_and_record_change("k = $1", vec![Value::from(key)], &conn, &table_name, &change_table_name).await?;
     conn.commit().await?;
+    for key in deleted_keys {
+        kv_item_serv::notify_change(&key, funs, ctx).await;
+    }
     Ok(())
 }
 
-pub async fn disable_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+async fn modify_disable(key: &str, disable: bool, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let (_, change_table_name) = kv_pg_initializer::init_change_table_and_conn(bs_inst, ctx, true).await?;
     conn.begin().await?;
-    conn.execute_one(&format!("UPDATE {table_name} SET disable = true WHERE k = $1"), vec![Value::from(key)]).await?;
+    let result = conn
+        .execute_one(
+            &format!("UPDATE {table_name} SET disable = $2, version = version + 1 WHERE k = $1 AND {ALIVE_CONDITION}"),
+            vec![Value::from(key), Value::from(disable)],
+        )
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(());
+    }
+    record_history(key, &conn, &table_name, &history_table_name).await?;
+    record_change(key, &conn, &table_name, &change_table_name).await?;
     conn.commit().await?;
+    kv_item_serv::notify_change(key, funs, ctx).await;
     Ok(())
 }
 
-pub async fn enabled_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
-    let bs_inst = inst.inst::<TardisRelDBClient>();
-    let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
-    conn.begin().await?;
-    conn.execute_one(&format!("UPDATE {table_name} SET disable = false WHERE k = $1"), vec![Value::from(key)]).await?;
-    conn.commit().await?;
-    Ok(())
+pub async fn disable_item(key: String, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify_disable(&key, true, funs, ctx, inst).await
+}
+
+pub async fn enabled_item(key: String, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify_disable(&key, false, funs, ctx, inst).await
 }
 
 pub async fn page_tags(
@@ -401,3 +556,248 @@ pub async fn page_tags(
         })
     })
 }
+
+pub async fn page_item_histories(
+    key: String,
+    page_number: u32,
+    page_size: u16,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<KvItemHistoryResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let mut params = vec![Value::from(key), Value::from(page_size), Value::from((page_number.max(1) - 1) * page_size as u32)];
+    // The scope is filtered in sql, so that the total is counted correctly
+    let scope_condition = package_scope_condition(&ctx.own_paths, &mut params);
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT k, version, v, info, owner, own_paths, disable, scope_level, update_time, count(*) OVER() AS total
+FROM {history_table_name}
+WHERE 
+    k = $1
+    AND {scope_condition}
+ORDER BY version DESC
+LIMIT $2 OFFSET $3"#
+            ),
+            params,
+        )
+        .await?;
+    let mut total_size: i64 = 0;
+    let records = result
+        .into_iter()
+        .map(|item| {
+            if total_size == 0 {
+                total_size = item.try_get("", "total")?;
+            }
+            Ok(KvItemHistoryResp {
+                key: item.try_get("", "k")?,
+                version: item.try_get("", "version")?,
+                value: item.try_get("", "v")?,
+                info: item.try_get("", "info")?,
+                owner: item.try_get("", "owner")?,
+                own_paths: item.try_get("", "own_paths")?,
+                disable: item.try_get("", "disable")?,
+                scope_level: item.try_get("", "scope_level")?,
+                update_time: item.try_get("", "update_time")?,
+            })
+        })
+        .collect::<TardisResult<Vec<KvItemHistoryResp>>>()?;
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size: total_size as u64,
+        records,
+    })
+}
+
+/// The sql condition that is equivalent to [rbum_scope_helper::check_scope] with the context's own paths, the params are appended to `params`
+fn package_scope_condition(ctx_own_paths: &str, params: &mut Vec<Value>) -> String {
+    params.push(Value::from(ctx_own_paths.to_string()));
+    let mut conditions = vec![format!("own_paths = ${}", params.len())];
+    let max_level = if ctx_own_paths.is_empty() { 0 } else { ctx_own_paths.split('/').count() };
+    for level in 0..=max_level {
+        if let Some(pre_paths) = rbum_scope_helper::get_pre_paths(level as i16, ctx_own_paths) {
+            params.push(Value::from(pre_paths));
+            conditions.push(format!(
+                "(scope_level = {level} AND starts_with(${idx}, LEFT(own_paths, LENGTH(${idx}))))",
+                idx = params.len()
+            ));
+        }
+    }
+    format!("({})", conditions.join(" OR "))
+}
+
+pub async fn restore_item(restore_req: &mut KvItemRestoreReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let history = conn
+        .get_dto_by_sql::<KvItemHistoryResp>(
+            &format!(
+                r#"SELECT k AS key, version, v AS value, info, owner, own_paths, disable, scope_level, update_time
+FROM {history_table_name}
+WHERE 
+    k = $1 AND version = $2"#
+            ),
+            vec![Value::from(restore_req.key.to_string()), Value::from(restore_req.version)],
+        )
+        .await?
+        .filter(|history| {
+            rbum_scope_helper::check_scope(
+                &history.own_paths,
+                Some(history.scope_level),
+                &RbumBasicFilterReq {
+                    ignore_scope: false,
+                    ..Default::default()
+                },
+                &ctx.own_paths,
+            )
+        })
+        .ok_or_else(|| funs.err().not_found("item", "restore", "The history version of item does not exist", "404-spi-kv-history-not-exist"))?;
+    let req = KvItemAddOrModifyReq {
+        key: restore_req.key.clone(),
+        value: history.value,
+        info: Some(history.info),
+        disable: Some(history.disable),
+        scope_level: Some(history.scope_level),
+        ttl_sec: None,
+        expected_version: restore_req.expected_version,
+    };
+    self::add_or_modify_item(&req, funs, ctx, inst).await
+}
+
+pub async fn delete_expired_items(funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let (_, change_table_name) = kv_pg_initializer::init_change_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    let deleted_keys = delete_and_record_change("expire_time <= CURRENT_TIMESTAMP", vec![], &conn, &table_name, &change_table_name).await?;
+    conn.commit().await?;
+    for key in deleted_keys {
+        kv_item_serv::notify_change(&key, funs, ctx).await;
+    }
+    Ok(())
+}
+
+/// Delete the changes older than the retention, the latest change is always kept to keep the latest revision
+pub async fn delete_expired_changes(retention_sec: u32, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, change_table_name) = kv_pg_initializer::init_change_table_and_conn(bs_inst, ctx, true).await?;
+    conn.execute_one(
+        &format!(
+            r#"DELETE FROM {change_table_name}
+WHERE create_time < CURRENT_TIMESTAMP - $1 * INTERVAL '1 second'
+    AND revision < (SELECT MAX(revision) FROM {change_table_name})"#
+        ),
+        vec![Value::from(retention_sec as i64)],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn find_changes(
+    key_prefix: String,
+    key_like: Option<bool>,
+    revision: Option<i64>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<KvItemWatchResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let (_, change_table_name) = kv_pg_initializer::init_change_table_and_conn(bs_inst, ctx, true).await?;
+    let (earliest_revision, latest_revision) = conn
+        .query_one(
+            &format!("SELECT COALESCE(MIN(revision), 0) AS earliest_revision, COALESCE(MAX(revision), 0) AS revision FROM {change_table_name}"),
+            vec![],
+        )
+        .await?
+        .map(|r| Ok::<_, TardisError>((r.try_get::<i64>("", "earliest_revision")?, r.try_get::<i64>("", "revision")?)))
+        .transpose()?
+        .unwrap_or((0, 0));
+    let Some(revision) = revision else {
+        return Ok(KvItemWatchResp {
+            revision: latest_revision,
+            changes: vec![],
+        });
+    };
+    kv_item_serv::check_revision_not_compacted(revision, earliest_revision, funs)?;
+    let key_condition = if key_like.unwrap_or(true) { "k LIKE $2" } else { "k = $2" };
+    let key_value = if key_like.unwrap_or(true) { format!("{key_prefix}%") } else { key_prefix };
+    // Only the latest change of each key is returned, the item is taken from the current value
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT change.k, change.revision, change.deleted, change.own_paths AS change_own_paths, change.scope_level AS change_scope_level,
+    COALESCE(item.k IS NOT NULL AND (item.expire_time IS NULL OR item.expire_time > CURRENT_TIMESTAMP), false) AS alive,
+    item.v, item.info, item.owner, item.own_paths, item.disable, item.scope_level, item.version, item.expire_time, item.create_time, item.update_time
+FROM (
+    SELECT DISTINCT ON (k) k, revision, deleted, own_paths, scope_level
+    FROM {change_table_name}
+    WHERE revision > $1 AND {key_condition}
+    ORDER BY k, revision DESC
+) AS change
+LEFT JOIN {table_name} AS item ON item.k = change.k
+ORDER BY change.revision"#
+            ),
+            vec![Value::from(revision), Value::from(key_value)],
+        )
+        .await?;
+    let changes = result
+        .into_iter()
+        .map(|change| {
+            let key: String = change.try_get("", "k")?;
+            let item = if !change.try_get::<bool>("", "deleted")? && change.try_get::<bool>("", "alive")? {
+                Some(KvItemSummaryResp {
+                    key: key.clone(),
+                    value: change.try_get("", "v")?,
+                    info: change.try_get("", "info")?,
+                    owner: change.try_get("", "owner")?,
+                    own_paths: change.try_get("", "own_paths")?,
+                    disable: change.try_get("", "disable")?,
+                    scope_level: change.try_get("", "scope_level")?,
+                    version: change.try_get("", "version")?,
+                    expire_time: change.try_get("", "expire_time")?,
+                    create_time: change.try_get("", "create_time")?,
+                    update_time: change.try_get("", "update_time")?,
+                })
+            } else {
+                None
+            };
+            let (own_paths, scope_level) = if let Some(item) = &item {
+                (item.own_paths.clone(), item.scope_level)
+            } else {
+                (change.try_get("", "change_own_paths")?, change.try_get("", "change_scope_level")?)
+            };
+            Ok((
+                KvItemChangeResp {
+                    key,
+                    revision: change.try_get("", "revision")?,
+                    deleted: item.is_none(),
+                    item,
+                },
+                own_paths,
+                scope_level,
+            ))
+        })
+        .collect::<TardisResult<Vec<(KvItemChangeResp, String, i16)>>>()?
+        .into_iter()
+        .filter(|(_, own_paths, scope_level)| {
+            rbum_scope_helper::check_scope(
+                own_paths,
+                Some(*scope_level),
+                &RbumBasicFilterReq {
+                    ignore_scope: false,
+                    ..Default::default()
+                },
+                &ctx.own_paths,
+            )
+        })
+        .map(|(change, _, _)| change)
+        .collect();
+    Ok(KvItemWatchResp {
+        revision: latest_revision.max(revision),
+        changes,
+    })
+}
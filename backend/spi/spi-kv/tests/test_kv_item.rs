@@ -1,20 +1,21 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_kv::dto::kv_item_dto::{KvItemDetailResp, KvItemSummaryResp, KvNameFindResp, KvTagFindResp};
+use bios_spi_kv::dto::kv_item_dto::{KvItemDetailResp, KvItemHistoryResp, KvItemSummaryResp, KvItemWatchResp, KvNameFindResp, KvTagFindResp};
+use bios_spi_kv::kv_constants::DOMAIN_CODE;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::serde_json::json;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::{TardisPage, TardisResp, Void};
 
-pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+pub async fn test(app_code: &str, client: &mut TestHttpClient) -> TardisResult<()> {
     let mut ctx = TardisContext {
-        own_paths: "t1/app001".to_string(),
-        ak: "app001".to_string(),
+        own_paths: format!("t1/{app_code}"),
+        ak: app_code.to_string(),
         roles: vec![],
         groups: vec![],
-        owner: "app001".to_string(),
+        owner: app_code.to_string(),
         ..Default::default()
     };
     client.set_auth(&ctx)?;
@@ -259,6 +260,216 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     assert_eq!(result.records[1].key, "feed:kind");
     assert_eq!(result.records[1].items[1].code, "task");
 
+    // compare and set
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"cfg:timeout",
+                "value": 10,
+                "expected_version": 0,
+            }),
+        )
+        .await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=cfg:timeout").await;
+    assert_eq!(result.version, 1);
+    assert!(result.expire_time.is_none());
+    let resp: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item",
+            &json!({
+                "key":"cfg:timeout",
+                "value": 20,
+                "expected_version": 0,
+            }),
+        )
+        .await;
+    assert!(resp.code.starts_with("409"));
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"cfg:timeout",
+                "value": 20,
+                "expected_version": 1,
+            }),
+        )
+        .await;
+    let resp: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item",
+            &json!({
+                "key":"cfg:timeout",
+                "value": 30,
+                "expected_version": 1,
+            }),
+        )
+        .await;
+    assert!(resp.code.starts_with("409"));
+    let result: KvItemDetailResp = client.get("/ci/item?key=cfg:timeout").await;
+    assert_eq!(result.value, 20);
+    assert_eq!(result.version, 2);
+
+    // history and restore
+    let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=cfg:timeout&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 2);
+    assert_eq!(result.records[0].version, 2);
+    assert_eq!(result.records[0].value, 20);
+    assert_eq!(result.records[1].version, 1);
+    assert_eq!(result.records[1].value, 10);
+    let resp: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item/history/restore",
+            &json!({
+                "key":"cfg:timeout",
+                "version": 99,
+            }),
+        )
+        .await;
+    assert!(resp.code.starts_with("404"));
+    let resp: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item/history/restore",
+            &json!({
+                "key":"cfg:timeout",
+                "version": 1,
+                "expected_version": 1,
+            }),
+        )
+        .await;
+    assert!(resp.code.starts_with("409"));
+    let _: Void = client
+        .put(
+            "/ci/item/history/restore",
+            &json!({
+                "key":"cfg:timeout",
+                "version": 1,
+                "expected_version": 2,
+            }),
+        )
+        .await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=cfg:timeout").await;
+    assert_eq!(result.value, 10);
+    assert_eq!(result.version, 3);
+    let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=cfg:timeout&page_number=1&page_size=1").await;
+    assert_eq!(result.total_size, 3);
+    assert_eq!(result.records.len(), 1);
+    assert_eq!(result.records[0].version, 3);
+
+    // the version keeps increasing after the item is deleted
+    client.delete("/ci/item?key=cfg:timeout").await;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"cfg:timeout",
+                "value": 40,
+                "expected_version": 0,
+            }),
+        )
+        .await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=cfg:timeout").await;
+    assert_eq!(result.version, 4);
+
+    // ttl
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"cfg:token",
+                "value": "xxx",
+                "ttl_sec": 1,
+            }),
+        )
+        .await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=cfg:token").await;
+    assert!(result.expire_time.is_some());
+    let result: Vec<KvItemSummaryResp> = client.get("/ci/items?keys=cfg:token").await;
+    assert_eq!(result.len(), 1);
+    sleep(Duration::from_millis(1500)).await;
+    let result: TardisResp<KvItemDetailResp> = client.get_resp("/ci/item?key=cfg:token").await;
+    assert!(result.data.is_none());
+    let result: Vec<KvItemSummaryResp> = client.get("/ci/items?keys=cfg:token").await;
+    assert!(result.is_empty());
+    let result: TardisPage<KvItemSummaryResp> = client.get("/ci/item/match?key_prefix=cfg:token&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 0);
+    // the expired item is treated as non-existent
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"cfg:token",
+                "value": "yyy",
+                "expected_version": 0,
+            }),
+        )
+        .await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=cfg:token").await;
+    assert_eq!(result.value, "yyy");
+    assert_eq!(result.version, 2);
+    assert!(result.expire_time.is_none());
+
+    // watch
+    let result: KvItemWatchResp = client.get("/ci/item/watch?key_prefix=watch:").await;
+    assert!(result.changes.is_empty());
+    let revision = result.revision;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"watch:a",
+                "value": "a1",
+            }),
+        )
+        .await;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"unwatched:a",
+                "value": "a1",
+            }),
+        )
+        .await;
+    let result: KvItemWatchResp = client.get(&format!("/ci/item/watch?key_prefix=watch:&revision={revision}")).await;
+    assert_eq!(result.changes.len(), 1);
+    assert_eq!(result.changes[0].key, "watch:a");
+    assert!(!result.changes[0].deleted);
+    assert_eq!(result.changes[0].item.as_ref().unwrap().value, "a1");
+    let revision = result.revision;
+    client.delete("/ci/item?key=watch:a").await;
+    let result: KvItemWatchResp = client.get(&format!("/ci/item/watch?key_prefix=watch:&revision={revision}")).await;
+    assert_eq!(result.changes.len(), 1);
+    assert!(result.changes[0].deleted);
+    assert!(result.changes[0].item.is_none());
+    let revision = result.revision;
+    // no change until timeout
+    let start = Instant::now();
+    let result: KvItemWatchResp = client.get(&format!("/ci/item/watch?key_prefix=watch:&revision={revision}&timeout_sec=1")).await;
+    assert!(result.changes.is_empty());
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    // woken up by the change
+    let mut other_client = TestHttpClient::new(format!("https://127.0.0.1:8080/{}", DOMAIN_CODE));
+    other_client.set_auth(&ctx)?;
+    let writer = tardis::tokio::spawn(async move {
+        sleep(Duration::from_millis(500)).await;
+        let _: Void = other_client
+            .put(
+                "/ci/item",
+                &json!({
+                    "key":"watch:b",
+                    "value": "b1",
+                }),
+            )
+            .await;
+    });
+    let start = Instant::now();
+    let result: KvItemWatchResp = client.get(&format!("/ci/item/watch?key_prefix=watch:&revision={revision}&timeout_sec=30")).await;
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(result.changes.len(), 1);
+    assert_eq!(result.changes[0].key, "watch:b");
+    writer.await.unwrap();
+
     // filter own_paths
     let _: Void = client
         .put(
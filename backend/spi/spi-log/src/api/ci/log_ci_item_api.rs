@@ -4,7 +4,9 @@ use tardis::web::poem_openapi::param::Path;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
-use crate::dto::log_item_dto::{LogConfigReq, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp};
+use crate::dto::log_item_dto::{
+    LogChainCheckpointResp, LogChainVerifyReq, LogChainVerifyResp, LogConfigReq, LogExportReq, LogExportResp, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp,
+};
 use crate::serv::log_item_serv;
 use tardis::serde_json::Value;
 
@@ -76,4 +78,36 @@ impl LogCiItemApiV2 {
         log_item_serv::delete_config(&mut find_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
+
+    /// Verify the hash chain of the tag and report the first broken link
+    #[oai(path = "/chain/verify", method = "put")]
+    async fn verify_chain(&self, mut verify_req: Json<LogChainVerifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<LogChainVerifyResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::verify_chain(&mut verify_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Add a signed checkpoint of the hash chain of the tag
+    #[oai(path = "/chain/:tag/checkpoint", method = "post")]
+    async fn add_chain_checkpoint(&self, tag: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Option<LogChainCheckpointResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::add_chain_checkpoint(&tag.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Export the records of the tag in the time range to spi-object
+    #[oai(path = "/export", method = "put")]
+    async fn export(&self, export_req: Json<LogExportReq>, ctx: TardisContextExtractor) -> TardisApiResult<LogExportResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::export_items(&export_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Archive and purge the expired records of the tags with retention policies immediately
+    #[oai(path = "/retention/purge", method = "post")]
+    async fn purge_expired_items(&self, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        log_item_serv::do_purge_expired_items(&funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
 }
@@ -110,6 +110,8 @@ pub struct LogItemFindReq {
     pub rel_keys: Option<Vec<TrimString>>,
     pub ts_start: Option<DateTime<Utc>>,
     pub ts_end: Option<DateTime<Utc>>,
+    /// Exclude the records at exactly ``ts_end``, i.e. find in ``[ts_start, ts_end)``
+    pub ts_end_exclusive: Option<bool>,
     pub page_number: u32,
     pub page_size: u16,
 }
@@ -151,7 +153,116 @@ pub struct LogItemFindResp {
 pub struct LogConfigReq {
     #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
     pub tag: String,
-    pub ref_field: String,
+    /// The field referenced by the records of the tag.
+    ///
+    /// It was required before the hash chain and retention configs were added to this request,
+    /// now it is optional so that those configs can be changed alone. The requests with it are still accepted as before.
+    pub ref_field: Option<String>,
+    /// Whether to enable the tamper-evident hash chain mode of the tag.
+    ///
+    /// Once enabled, each record stores a hash of its content chained to the hash of the previous record.
+    pub hash_chain: Option<bool>,
+    /// Retention days of the records, the expired records are purged by the scheduled job
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub retention_days: Option<u32>,
+    /// Partition interval of the tag table, only takes effect if it is set before the first record of the tag is written
+    pub partition_interval: Option<LogPartitionInterval>,
+    /// If set, the expired records are exported to spi-object in this format before being purged
+    pub archive_format: Option<LogExportFormat>,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogPartitionInterval {
+    Day,
+    Month,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogExportFormat {
+    Ndjson,
+    Csv,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogExportReq {
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub tag: String,
+    pub ts_start: Option<DateTime<Utc>>,
+    /// Exclusive end of the range, defaults to the time of the export
+    pub ts_end: Option<DateTime<Utc>>,
+    pub format: LogExportFormat,
+    /// Path prefix of the exported objects, each object is suffixed with the part number and the format extension
+    #[oai(validator(pattern = r"^[a-zA-Z0-9_\-\./]+$"))]
+    pub object_path: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogExportResp {
+    pub count: u64,
+    pub object_paths: Vec<String>,
+}
+
+/// A range of the expired records of a tag to be archived and purged
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogExpiredRange {
+    pub tag: String,
+    pub ts_start: Option<DateTime<Utc>>,
+    /// Exclusive end of the range, consistent with the range ``[start, end)`` of the partition
+    pub ts_end: DateTime<Utc>,
+    pub archive_format: Option<LogExportFormat>,
+    /// The partition that contains exactly the records of the range, which is dropped as a whole
+    pub partition: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogChainVerifyReq {
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub tag: String,
+    pub ts_start: Option<DateTime<Utc>>,
+    pub ts_end: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogChainVerifyResp {
+    /// Number of the records whose hashes have been verified
+    pub verified_count: u64,
+    pub last_chain_seq: Option<i64>,
+    pub last_hash: Option<String>,
+    /// The first broken link, ``None`` means the chain is intact
+    pub broken: Option<LogChainBrokenResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogChainBrokenResp {
+    pub chain_seq: i64,
+    pub kind: LogChainBrokenKind,
+    /// Empty if the record is missing
+    pub id: Option<String>,
+    pub ts: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum LogChainBrokenKind {
+    /// The record has been deleted
+    Missing,
+    /// The sequence of the record is duplicated or out of order
+    Disordered,
+    /// The previous hash stored in the record does not match the hash of the previous record
+    PrevHashMismatch,
+    /// The content of the record has been modified
+    HashMismatch,
+    /// The record does not match the signed checkpoint, or the checkpoint has been forged
+    CheckpointMismatch,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct LogChainCheckpointResp {
+    pub chain_seq: i64,
+    pub hash: String,
+    /// Whether the checkpoint is added when the records up to it are purged by the retention policy
+    pub purged: bool,
+    pub signature: String,
+    pub create_time: DateTime<Utc>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -1,11 +1,35 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
+use bios_sdk_invoke::invoke_config::InvokeConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct LogConfig {
     pub rbum: RbumConfig,
+    /// Interval for creating the signed checkpoints of the hash chained tags
+    pub chain_checkpoint_interval_sec: u32,
+    /// Key for signing the checkpoints, should be kept secret.
+    /// The hash chain mode can not be enabled and no checkpoints are signed while it is empty
+    pub chain_checkpoint_sign_key: String,
+    /// Interval for purging (and archiving) the expired records of the tags with retention policies
+    pub retention_check_interval_sec: u32,
+    /// Max number of records in each exported object
+    pub export_part_size: u32,
+    pub invoke: InvokeConfig,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            rbum: Default::default(),
+            chain_checkpoint_interval_sec: 300,
+            chain_checkpoint_sign_key: "".to_string(),
+            retention_check_interval_sec: 3600,
+            export_part_size: 10000,
+            invoke: Default::default(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -11,5 +11,11 @@ pub const TABLE_LOG_FLAG_V2: &str = "logv2";
 pub const PARENT_TABLE_NAME: &str = "spi_log_parent";
 //配置表名
 pub const CONFIG_TABLE_NAME: &str = "spi_log_config";
+//哈希链配置表名
+pub const CHAIN_CONFIG_TABLE_NAME: &str = "spi_log_chain_config";
+//哈希链检查点表名
+pub const CHAIN_CHECKPOINT_TABLE_NAME: &str = "spi_log_chain_checkpoint";
+//保留策略配置表名
+pub const RETENTION_CONFIG_TABLE_NAME: &str = "spi_log_retention_config";
 //ref flag  __STARSYS_LOG_REF__@{ts}#{key}
 pub const LOG_REF_FLAG: &str = "__STARSYS_LOG_REF__";
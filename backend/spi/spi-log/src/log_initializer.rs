@@ -1,7 +1,8 @@
 use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_constants, spi_funs::SpiBsInst, spi_initializer};
+use bios_sdk_invoke::invoke_initializer;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    log::info,
+    log::{info, warn},
     web::web_server::TardisWebServer,
     TardisFuns, TardisFunsInst,
 };
@@ -10,18 +11,27 @@ use crate::{
     api::ci::log_ci_item_api,
     log_config::LogConfig,
     log_constants::{self, DOMAIN_CODE},
+    serv::log_item_serv,
 };
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     info!("[BIOS.Log] Module initializing");
     let mut funs = crate::get_tardis_inst();
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<LogConfig>().rbum.clone()).await?;
+    invoke_initializer::init(funs.module_code(), funs.conf::<LogConfig>().invoke.clone())?;
     funs.begin().await?;
     let ctx = spi_initializer::init(DOMAIN_CODE, &funs).await?;
     init_db(&funs, &ctx).await?;
     funs.commit().await?;
     crate::event::handle_events().await?;
     init_api(web_server).await?;
+    let conf = funs.conf::<LogConfig>();
+    if conf.chain_checkpoint_sign_key.is_empty() {
+        warn!("[BIOS.Log] The chain checkpoint sign key is empty, the hash chain mode can not be enabled");
+    } else {
+        log_item_serv::checkpoint_chains(conf.chain_checkpoint_interval_sec).await;
+    }
+    log_item_serv::purge_expired_items(conf.retention_check_interval_sec).await;
     info!("[BIOS.Log] Module initialized");
     Ok(())
 }
@@ -29,11 +39,13 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     spi_initializer::add_kind(spi_constants::SPI_PG_KIND_CODE, funs, ctx).await?;
     spi_initializer::add_kind(log_constants::SPI_PG_V2_KIND_CODE, funs, ctx).await?;
+    #[cfg(feature = "spi-memory")]
+    spi_initializer::add_kind(spi_constants::SPI_MEMORY_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
 async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
-    web_server.add_module(DOMAIN_CODE, (spi_ci_bs_api::SpiCiBsApi, log_ci_item_api::LogCiItemApi)).await;
+    web_server.add_module(DOMAIN_CODE, (spi_ci_bs_api::SpiCiBsApi, log_ci_item_api::LogCiItemApi, log_ci_item_api::LogCiItemApiV2)).await;
     Ok(())
 }
 
@@ -43,6 +55,8 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
         log_constants::SPI_PG_V2_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => crate::serv::memory::log_memory_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }?;
     info!("[BIOS.Log] Fun [{}]({}) initialized", bs_cert.kind_code, bs_cert.conn_uri);
@@ -1,3 +1,6 @@
+pub mod log_chain_serv;
 pub mod log_item_serv;
+#[cfg(feature = "spi-memory")]
+pub mod memory;
 pub mod pg;
 pub mod pgv2;
@@ -0,0 +1,216 @@
+//! Backend independent parts of the tamper-evident hash chain mode.
+//!
+//! Each chained record stores ``chain_seq`` (starting from 1 for each tag), ``prev_hash`` (empty for the first record) and ``hash``.
+//! The hash covers the previous hash, the sequence and the immutable fields of the record,
+//! ``ext`` and ``disable`` are excluded because they can be legitimately modified after the record is written.
+use std::collections::HashMap;
+
+use tardis::{
+    basic::{error::TardisError, result::TardisResult},
+    chrono::{DateTime, Utc},
+    serde_json::{json, Map, Value as JsonValue},
+    TardisFuns,
+};
+
+use crate::dto::log_item_dto::{LogChainBrokenKind, LogChainBrokenResp, LogChainCheckpointResp, LogChainVerifyResp};
+
+/// Number of the records loaded at a time when verifying
+pub(crate) const VERIFY_BATCH_SIZE: u64 = 500;
+
+pub(crate) struct LogChainRecord {
+    pub(crate) chain_seq: i64,
+    pub(crate) prev_hash: String,
+    pub(crate) hash: String,
+    pub(crate) id: String,
+    pub(crate) ts: DateTime<Utc>,
+    pub(crate) key: String,
+    pub(crate) kind: String,
+    pub(crate) tag: String,
+    pub(crate) op: String,
+    pub(crate) content: JsonValue,
+    pub(crate) owner: String,
+    pub(crate) owner_name: String,
+    pub(crate) own_paths: String,
+    pub(crate) rel_key: String,
+    pub(crate) msg: String,
+}
+
+impl LogChainRecord {
+    pub(crate) fn compute_hash(&self) -> TardisResult<String> {
+        let canonical = json!([
+            self.prev_hash,
+            self.chain_seq,
+            self.id,
+            self.ts.timestamp_micros(),
+            self.key,
+            self.kind,
+            self.tag,
+            self.op,
+            canonicalize(&self.content),
+            self.owner,
+            self.owner_name,
+            self.own_paths,
+            self.rel_key,
+            self.msg
+        ]);
+        TardisFuns::crypto.digest.sha256(&canonical.to_string())
+    }
+}
+
+/// Sort the keys of the objects so that the hash does not depend on the key order returned by the storage
+fn canonicalize(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(obj) => {
+            let mut keys = obj.keys().collect::<Vec<_>>();
+            keys.sort();
+            JsonValue::Object(keys.into_iter().map(|k| (k.to_string(), canonicalize(&obj[k]))).collect::<Map<String, JsonValue>>())
+        }
+        JsonValue::Array(values) => JsonValue::Array(values.iter().map(canonicalize).collect()),
+        _ => value.clone(),
+    }
+}
+
+/// The checkpoints signed by an empty key can be forged, so the hash chain mode requires ``chain_checkpoint_sign_key`` to be configured
+pub(crate) fn check_sign_key(sign_key: &str) -> TardisResult<()> {
+    if sign_key.is_empty() {
+        return Err(TardisError::conflict(
+            "The chain checkpoint sign key is not configured, the hash chain mode is not available",
+            "409-spi-log-chain-sign-key-empty",
+        ));
+    }
+    Ok(())
+}
+
+/// Sign the checkpoint, the scope binds the checkpoint to the chain (e.g. the table name)
+pub(crate) fn sign_checkpoint(scope: &str, chain_seq: i64, hash: &str, purged: bool, sign_key: &str) -> TardisResult<String> {
+    check_sign_key(sign_key)?;
+    let data = if purged {
+        format!("{scope}:{chain_seq}:{hash}:purged")
+    } else {
+        format!("{scope}:{chain_seq}:{hash}")
+    };
+    Ok(TardisFuns::crypto.base64.encode(TardisFuns::crypto.digest.hmac_sha256(data, sign_key)?))
+}
+
+fn is_valid_checkpoint(checkpoint: &LogChainCheckpointResp, scope: &str, sign_key: &str) -> TardisResult<bool> {
+    Ok(sign_checkpoint(scope, checkpoint.chain_seq, &checkpoint.hash, checkpoint.purged, sign_key)? == checkpoint.signature)
+}
+
+/// Walk the chained records in sequence order and stop at the first broken link.
+pub(crate) struct LogChainVerifier<'a> {
+    scope: &'a str,
+    sign_key: &'a str,
+    checkpoints: HashMap<i64, LogChainCheckpointResp>,
+    last_chain_seq: i64,
+    last_hash: Option<String>,
+    verified_count: u64,
+    broken: Option<LogChainBrokenResp>,
+}
+
+impl<'a> LogChainVerifier<'a> {
+    /// ``first_chain_seq`` is the sequence of the first record to be verified,
+    /// ``prev_hash`` is the stored hash of its previous record, ``None`` if the previous record is missing.
+    /// ``checkpoints`` should contain the checkpoints from ``first_chain_seq - 1``,
+    /// the checkpoint of the missing previous record is used as the start of the chain if the record has been purged by the retention policy.
+    pub(crate) fn new(first_chain_seq: i64, prev_hash: Option<String>, checkpoints: Vec<LogChainCheckpointResp>, scope: &'a str, sign_key: &'a str) -> TardisResult<Self> {
+        let mut checkpoints: HashMap<i64, LogChainCheckpointResp> = checkpoints.into_iter().map(|checkpoint| (checkpoint.chain_seq, checkpoint)).collect();
+        let prev_checkpoint = checkpoints.remove(&(first_chain_seq - 1));
+        let prev_checkpoint_hash = match prev_checkpoint {
+            Some(checkpoint) if is_valid_checkpoint(&checkpoint, scope, sign_key)? => Some(checkpoint.hash),
+            _ => None,
+        };
+        let (last_hash, broken) = if first_chain_seq <= 1 {
+            (Some("".to_string()), None)
+        } else if prev_hash.is_some() {
+            (prev_hash, None)
+        } else if prev_checkpoint_hash.is_some() {
+            (prev_checkpoint_hash, None)
+        } else {
+            (
+                None,
+                Some(LogChainBrokenResp {
+                    chain_seq: first_chain_seq - 1,
+                    kind: LogChainBrokenKind::Missing,
+                    id: None,
+                    ts: None,
+                }),
+            )
+        };
+        Ok(LogChainVerifier {
+            scope,
+            sign_key,
+            checkpoints,
+            last_chain_seq: first_chain_seq.max(1) - 1,
+            last_hash,
+            verified_count: 0,
+            broken,
+        })
+    }
+
+    pub(crate) fn is_broken(&self) -> bool {
+        self.broken.is_some()
+    }
+
+    pub(crate) fn feed(&mut self, record: &LogChainRecord) -> TardisResult<()> {
+        if self.broken.is_some() {
+            return Ok(());
+        }
+        let broken = |kind: LogChainBrokenKind| LogChainBrokenResp {
+            chain_seq: record.chain_seq,
+            kind,
+            id: Some(record.id.clone()),
+            ts: Some(record.ts),
+        };
+        let expected_chain_seq = self.last_chain_seq + 1;
+        if record.chain_seq > expected_chain_seq {
+            self.broken = Some(LogChainBrokenResp {
+                chain_seq: expected_chain_seq,
+                kind: LogChainBrokenKind::Missing,
+                id: None,
+                ts: None,
+            });
+            return Ok(());
+        }
+        if record.chain_seq < expected_chain_seq {
+            self.broken = Some(broken(LogChainBrokenKind::Disordered));
+            return Ok(());
+        }
+        if self.last_hash.as_ref() != Some(&record.prev_hash) {
+            self.broken = Some(broken(LogChainBrokenKind::PrevHashMismatch));
+            return Ok(());
+        }
+        if record.compute_hash()? != record.hash {
+            self.broken = Some(broken(LogChainBrokenKind::HashMismatch));
+            return Ok(());
+        }
+        if let Some(checkpoint) = self.checkpoints.get(&record.chain_seq) {
+            if checkpoint.hash != record.hash || !is_valid_checkpoint(checkpoint, self.scope, self.sign_key)? {
+                self.broken = Some(broken(LogChainBrokenKind::CheckpointMismatch));
+                return Ok(());
+            }
+        }
+        self.last_chain_seq = record.chain_seq;
+        self.last_hash = Some(record.hash.clone());
+        self.verified_count += 1;
+        Ok(())
+    }
+
+    /// ``check_tail`` should only be set when the whole chain is walked,
+    /// it reports the records after the last verified one as missing if a later checkpoint exists, which means the tail of the chain has been deleted.
+    pub(crate) fn finish(mut self, check_tail: bool) -> LogChainVerifyResp {
+        if self.broken.is_none() && check_tail && self.checkpoints.keys().any(|chain_seq| *chain_seq > self.last_chain_seq) {
+            self.broken = Some(LogChainBrokenResp {
+                chain_seq: self.last_chain_seq + 1,
+                kind: LogChainBrokenKind::Missing,
+                id: None,
+                ts: None,
+            });
+        }
+        LogChainVerifyResp {
+            verified_count: self.verified_count,
+            last_chain_seq: if self.verified_count > 0 { Some(self.last_chain_seq) } else { None },
+            last_hash: if self.verified_count > 0 { self.last_hash } else { None },
+            broken: self.broken,
+        }
+    }
+}
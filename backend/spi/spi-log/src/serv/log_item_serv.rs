@@ -1,14 +1,30 @@
+use std::collections::HashMap;
+
+use bios_sdk_invoke::clients::spi_object_client::SpiObjectClient;
+use lazy_static::lazy_static;
+use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::chrono::Utc;
+use tardis::log::{info, trace, warn};
+use tardis::tokio::sync::RwLock;
+use tardis::tokio::time::{self, Duration};
+use tardis::{TardisFuns, TardisFunsInst};
 
 use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use bios_basic::spi_dispatch_service;
 
-use crate::dto::log_item_dto::{LogConfigReq, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp};
+use crate::dto::log_item_dto::{
+    LogChainCheckpointResp, LogChainVerifyReq, LogChainVerifyResp, LogConfigReq, LogExpiredRange, LogExportFormat, LogExportReq, LogExportResp, LogItemAddReq, LogItemAddV2Req,
+    LogItemFindReq, LogItemFindResp,
+};
+use crate::log_config::LogConfig;
 use crate::log_initializer;
 use tardis::web::web_resp::TardisPage;
 
 use super::super::log_constants;
+#[cfg(feature = "spi-memory")]
+use super::memory;
 use super::pg;
 use super::pgv2;
 use tardis::serde_json::Value;
@@ -20,6 +36,8 @@ spi_dispatch_service! {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::log_pg_item_serv,
         log_constants::SPI_PG_V2_KIND_CODE => pgv2::log_pg_item_serv,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::log_memory_item_serv,
     },
     @method: {
         add(add_req: &mut LogItemAddReq) -> TardisResult<String>;
@@ -29,5 +47,170 @@ spi_dispatch_service! {
         modify_ext(tag: &str,key: &str, ext: &mut Value) -> TardisResult<()>;
         add_config(config: &mut LogConfigReq) -> TardisResult<()>;
         delete_config(config: &mut LogConfigReq) -> TardisResult<()>;
+        verify_chain(verify_req: &mut LogChainVerifyReq) -> TardisResult<LogChainVerifyResp>;
+        add_chain_checkpoint(tag: &str) -> TardisResult<Option<LogChainCheckpointResp>>;
+        add_chain_checkpoints() -> TardisResult<()>;
+        find_expired_ranges() -> TardisResult<Vec<LogExpiredRange>>;
+        purge_expired_range(range: &LogExpiredRange) -> TardisResult<()>;
+    }
+}
+
+lazy_static! {
+    /// Contexts of the tenants/apps that have hash chained or retention configured tags, used to locate their backend instances in the background tasks
+    static ref TASK_CTX_CONTAINER: RwLock<HashMap<String, TardisContext>> = RwLock::new(HashMap::new());
+}
+
+pub(crate) async fn register_task_ctx(ctx: &TardisContext) {
+    if TASK_CTX_CONTAINER.read().await.contains_key(&ctx.ak) {
+        return;
+    }
+    TASK_CTX_CONTAINER.write().await.insert(ctx.ak.clone(), ctx.clone());
+}
+
+pub async fn checkpoint_chains(checkpoint_interval_sec: u32) {
+    tardis::tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(checkpoint_interval_sec.max(1) as u64));
+        loop {
+            interval.tick().await;
+            let ctxs = TASK_CTX_CONTAINER.read().await.values().cloned().collect::<Vec<_>>();
+            let funs = crate::get_tardis_inst();
+            for ctx in ctxs {
+                trace!("[SPI-LOG] add chain checkpoints of {}", ctx.ak);
+                if let Err(e) = add_chain_checkpoints(&funs, &ctx).await {
+                    warn!("[SPI-LOG] add chain checkpoints of {} error: {:?}", ctx.ak, e);
+                }
+            }
+        }
+    });
+}
+
+pub async fn purge_expired_items(retention_check_interval_sec: u32) {
+    tardis::tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(retention_check_interval_sec.max(1) as u64));
+        loop {
+            interval.tick().await;
+            let ctxs = TASK_CTX_CONTAINER.read().await.values().cloned().collect::<Vec<_>>();
+            let funs = crate::get_tardis_inst();
+            for ctx in ctxs {
+                trace!("[SPI-LOG] purge expired items of {}", ctx.ak);
+                if let Err(e) = do_purge_expired_items(&funs, &ctx).await {
+                    warn!("[SPI-LOG] purge expired items of {} error: {:?}", ctx.ak, e);
+                }
+            }
+        }
+    });
+}
+
+/// The expired records are archived before being purged, the range is kept if the archiving fails and will be retried in the next round
+pub async fn do_purge_expired_items(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    for range in find_expired_ranges(funs, ctx).await? {
+        if let Some(format) = range.archive_format {
+            let export_resp = export_items(
+                &LogExportReq {
+                    tag: range.tag.clone(),
+                    ts_start: range.ts_start,
+                    ts_end: Some(range.ts_end),
+                    format,
+                    object_path: Some(format!("log-archive/{}/{}", range.tag, range.ts_end.format("%Y%m%d%H%M%S"))),
+                },
+                funs,
+                ctx,
+            )
+            .await?;
+            info!("[SPI-LOG] archived {} expired items of {} to {:?}", export_resp.count, range.tag, export_resp.object_paths);
+        }
+        purge_expired_range(&range, funs, ctx).await?;
+    }
+    Ok(())
+}
+
+const EXPORT_PAGE_SIZE: u16 = 500;
+const EXPORT_CSV_HEADER: &str = "ts,id,key,kind,op,owner,owner_name,own_paths,rel_key,msg,content,ext";
+
+/// Export the records of the tag in the time range to spi-object, each object contains at most ``export_part_size`` records
+pub async fn export_items(export_req: &LogExportReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<LogExportResp> {
+    let part_size = funs.conf::<LogConfig>().export_part_size.max(1) as usize;
+    let object_path = export_req.object_path.clone().unwrap_or_else(|| format!("log-export/{}/{}", export_req.tag, TardisFuns::field.nanoid()));
+    let mut find_req = LogItemFindReq {
+        tag: export_req.tag.clone(),
+        kinds: None,
+        keys: None,
+        ops: None,
+        owners: None,
+        own_paths: None,
+        ext_or: None,
+        ext: None,
+        adv_query: None,
+        rel_keys: None,
+        ts_start: export_req.ts_start,
+        // Fix the end of the range so that the records added during the export do not shift the pages
+        ts_end: Some(export_req.ts_end.unwrap_or_else(Utc::now)),
+        ts_end_exclusive: Some(true),
+        page_number: 1,
+        page_size: EXPORT_PAGE_SIZE,
+    };
+    let mut resp = LogExportResp { count: 0, object_paths: vec![] };
+    let mut lines = vec![];
+    loop {
+        let page = findv2(&mut find_req, funs, ctx).await?;
+        let fetched = page.records.len();
+        for record in page.records {
+            lines.push(match export_req.format {
+                LogExportFormat::Ndjson => TardisFuns::json.obj_to_string(&record)?,
+                LogExportFormat::Csv => to_csv_line(&record),
+            });
+            if lines.len() >= part_size {
+                resp.count += lines.len() as u64;
+                resp.object_paths.push(put_export_part(&object_path, resp.object_paths.len() + 1, export_req.format, &lines, funs, ctx).await?);
+                lines.clear();
+            }
+        }
+        if fetched < EXPORT_PAGE_SIZE as usize {
+            break;
+        }
+        find_req.page_number += 1;
+    }
+    if !lines.is_empty() {
+        resp.count += lines.len() as u64;
+        resp.object_paths.push(put_export_part(&object_path, resp.object_paths.len() + 1, export_req.format, &lines, funs, ctx).await?);
+    }
+    Ok(resp)
+}
+
+async fn put_export_part(object_path: &str, part: usize, format: LogExportFormat, lines: &[String], funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+    let (object_path, content) = match format {
+        LogExportFormat::Ndjson => (format!("{object_path}-{part:05}.ndjson"), lines.join("\n")),
+        LogExportFormat::Csv => (format!("{object_path}-{part:05}.csv"), format!("{EXPORT_CSV_HEADER}\n{}", lines.join("\n"))),
+    };
+    SpiObjectClient::put_obj(&object_path, &content, Some(true), funs, ctx).await?;
+    Ok(object_path)
+}
+
+fn to_csv_line(record: &LogItemFindResp) -> String {
+    [
+        record.ts.to_rfc3339(),
+        record.id.clone(),
+        record.key.clone(),
+        record.kind.clone(),
+        record.op.clone(),
+        record.owner.clone(),
+        record.owner_name.clone(),
+        record.own_paths.clone(),
+        record.rel_key.clone(),
+        record.msg.clone(),
+        record.content.to_string(),
+        record.ext.to_string(),
+    ]
+    .iter()
+    .map(|field| escape_csv_field(field))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
     }
 }
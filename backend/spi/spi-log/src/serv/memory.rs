@@ -0,0 +1,2 @@
+pub mod log_memory_initializer;
+pub mod log_memory_item_serv;
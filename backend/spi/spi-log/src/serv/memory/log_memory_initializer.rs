@@ -0,0 +1,46 @@
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    serde_json::Value,
+};
+
+use crate::dto::log_item_dto::{LogChainCheckpointResp, LogExportFormat};
+
+#[derive(Default)]
+pub struct LogMemoryStore {
+    /// Log records grouped by tag, in insertion order
+    pub(crate) items: HashMap<String, Vec<LogMemoryItem>>,
+    /// Reference fields grouped by tag, corresponding to ``spi_log_config`` in the PostgreSQL implementation
+    pub(crate) ref_fields: HashMap<String, Vec<String>>,
+    /// Tags with the hash chain mode enabled, corresponding to ``spi_log_chain_config`` in the PostgreSQL implementation
+    pub(crate) chained_tags: HashSet<String>,
+    /// Signed checkpoints of the hash chains grouped by tag
+    pub(crate) checkpoints: HashMap<String, Vec<LogChainCheckpointResp>>,
+    /// ``(retention_days, archive_format)`` grouped by tag, the partition interval is ignored because the records are not partitioned in memory
+    pub(crate) retentions: HashMap<String, (Option<u32>, Option<LogExportFormat>)>,
+}
+
+#[derive(Clone)]
+pub(crate) struct LogMemoryItem {
+    pub(crate) id: String,
+    pub(crate) kind: String,
+    pub(crate) key: String,
+    pub(crate) op: String,
+    pub(crate) content: Value,
+    pub(crate) owner: String,
+    pub(crate) owner_name: String,
+    pub(crate) own_paths: String,
+    pub(crate) ext: Value,
+    pub(crate) rel_key: String,
+    pub(crate) msg: String,
+    pub(crate) ts: DateTime<Utc>,
+    /// ``(chain_seq, prev_hash, hash)``, only set for the records written in the hash chain mode
+    pub(crate) chain: Option<(i64, String, String)>,
+}
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    spi_initializer::common_memory::init::<LogMemoryStore>(bs_cert, ctx, mgr).await
+}
@@ -0,0 +1,432 @@
+use bios_basic::{
+    enumeration::BasicQueryOpKind,
+    spi::{
+        spi_funs::SpiBsInst,
+        spi_initializer::common_memory::{self, SpiMemoryClient},
+    },
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{Duration, Utc},
+    serde_json::{json, Value as JsonValue},
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::log_item_dto::{
+        LogChainCheckpointResp, LogChainVerifyReq, LogChainVerifyResp, LogConfigReq, LogExpiredRange, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp,
+    },
+    log_config::LogConfig,
+    serv::{
+        log_chain_serv::{check_sign_key, sign_checkpoint, LogChainRecord, LogChainVerifier},
+        pgv2::log_pg_item_serv::push_to_eda,
+    },
+};
+
+use super::log_memory_initializer::{LogMemoryItem, LogMemoryStore};
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<LogMemoryStore> {
+    inst.inst::<SpiMemoryClient<LogMemoryStore>>().0
+}
+
+pub async fn add(add_req: &mut LogItemAddReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<String> {
+    let id = add_req.id.clone().unwrap_or(TardisFuns::field.nanoid());
+    let item = LogMemoryItem {
+        id: id.clone(),
+        kind: add_req.kind.as_ref().map(|kind| kind.to_string()).unwrap_or_default(),
+        key: add_req.key.as_ref().map(|key| key.to_string()).unwrap_or_default(),
+        op: add_req.op.clone().unwrap_or_default(),
+        content: TardisFuns::json.str_to_json(&add_req.content).unwrap_or_else(|_| JsonValue::String(add_req.content.clone())),
+        owner: add_req.owner.clone().unwrap_or_default(),
+        owner_name: "".to_string(),
+        own_paths: add_req.own_paths.clone().unwrap_or_default(),
+        ext: add_req.ext.clone().unwrap_or_else(|| json!({})),
+        rel_key: add_req.rel_key.as_ref().map(|rel_key| rel_key.to_string()).unwrap_or_default(),
+        msg: "".to_string(),
+        ts: add_req.ts.unwrap_or_else(Utc::now),
+        chain: None,
+    };
+    get_client(inst).write().await.items.entry(add_req.tag.clone()).or_default().push(item);
+    Ok(id)
+}
+
+pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<LogItemFindResp>> {
+    findv2(find_req, funs, ctx, inst).await
+}
+
+/// Unlike the PostgreSQL implementation, the memory implementation stores the merged content directly instead of the references to the previous record,
+/// so the records can be returned without resolving the references.
+pub async fn addv2(add_req: &mut LogItemAddV2Req, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<String> {
+    let id = add_req.idempotent_id.clone().unwrap_or(TardisFuns::field.nanoid());
+    let mut store = get_client(inst).write().await;
+    let ref_fields = store.ref_fields.get(&add_req.tag).cloned().unwrap_or_default();
+    let chained = store.chained_tags.contains(&add_req.tag);
+    // The chain continues from the purge anchor if all the chained records have been purged
+    let purged_anchor = store
+        .checkpoints
+        .get(&add_req.tag)
+        .into_iter()
+        .flatten()
+        .filter(|checkpoint| checkpoint.purged)
+        .max_by_key(|checkpoint| checkpoint.chain_seq)
+        .map(|checkpoint| (checkpoint.chain_seq + 1, checkpoint.hash.clone()));
+    let items = store.items.entry(add_req.tag.clone()).or_default();
+    let mut content = add_req.content.clone();
+    if let Some(key) = add_req.key.as_ref() {
+        if let Some(last_item) = items.iter().filter(|item| item.key == key.to_string()).max_by_key(|item| item.ts) {
+            let mut last_content = last_item.content.clone();
+            if let (Some(last_content), Some(add_req_content)) = (last_content.as_object_mut(), add_req.content.as_object()) {
+                for (k, v) in add_req_content {
+                    last_content.insert(k.to_string(), v.clone());
+                }
+            }
+            content = last_content;
+        }
+    }
+    let mut item = LogMemoryItem {
+        id: id.clone(),
+        kind: add_req.kind.as_ref().map(|kind| kind.to_string()).unwrap_or_default(),
+        key: add_req.key.as_ref().map(|key| key.to_string()).unwrap_or_default(),
+        op: add_req.op.clone().unwrap_or_default(),
+        content,
+        owner: add_req.owner.clone().unwrap_or_default(),
+        owner_name: add_req.owner_name.clone().unwrap_or_default(),
+        own_paths: add_req.own_paths.clone().unwrap_or_default(),
+        ext: add_req.ext.clone().unwrap_or_else(|| json!({})),
+        rel_key: add_req.rel_key.as_ref().map(|rel_key| rel_key.to_string()).unwrap_or_default(),
+        msg: add_req.msg.clone().unwrap_or_default(),
+        ts: add_req.ts.unwrap_or_else(Utc::now),
+        chain: None,
+    };
+    if chained {
+        let (chain_seq, prev_hash) =
+            items.iter().rev().find_map(|item| item.chain.as_ref()).map(|(chain_seq, _, hash)| (chain_seq + 1, hash.clone())).or(purged_anchor).unwrap_or((1, "".to_string()));
+        item.chain = Some((chain_seq, prev_hash, "".to_string()));
+        let hash = to_chain_record(&add_req.tag, &item).map(|record| record.compute_hash()).transpose()?.unwrap_or_default();
+        if let Some((_, _, item_hash)) = item.chain.as_mut() {
+            *item_hash = hash;
+        }
+    }
+    items.push(item);
+    drop(store);
+    if chained {
+        crate::serv::log_item_serv::register_task_ctx(ctx).await;
+    }
+    //if push is true, then push to EDA
+    if add_req.push {
+        push_to_eda(add_req, &ref_fields, funs, ctx).await?;
+    }
+    Ok(id)
+}
+
+pub async fn findv2(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<LogItemFindResp>> {
+    let check_in = |values: &Option<Vec<String>>, value: &str| values.as_ref().map(|values| values.iter().any(|v| v == value)).unwrap_or(true);
+    let kinds = find_req.kinds.as_ref().map(|kinds| kinds.iter().map(|kind| kind.to_string()).collect::<Vec<_>>());
+    let keys = find_req.keys.as_ref().map(|keys| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>());
+    let rel_keys = find_req.rel_keys.as_ref().map(|rel_keys| rel_keys.iter().map(|rel_key| rel_key.to_string()).collect::<Vec<_>>());
+    for cond in find_req.ext.iter().flatten().chain(find_req.ext_or.iter().flatten()) {
+        check_cond_value(&cond.field, &cond.op, &cond.value, funs)?;
+    }
+    for cond in find_req.adv_query.iter().flatten().flat_map(|group_query| group_query.ext.iter().flatten()) {
+        check_cond_value(&cond.field, &cond.op, &cond.value, funs)?;
+    }
+
+    let store = get_client(inst).read().await;
+    let mut records = store
+        .items
+        .get(&find_req.tag)
+        .map(|items| items.iter().rev().collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|item| {
+            check_in(&kinds, &item.kind)
+                && check_in(&keys, &item.key)
+                && check_in(&find_req.ops, &item.op)
+                && check_in(&find_req.owners, &item.owner)
+                && check_in(&rel_keys, &item.rel_key)
+                && find_req.own_paths.as_ref().map(|own_paths| item.own_paths.starts_with(own_paths)).unwrap_or(true)
+                && find_req.ts_start.map(|ts_start| item.ts >= ts_start).unwrap_or(true)
+                && find_req.ts_end.map(|ts_end| if find_req.ts_end_exclusive == Some(true) { item.ts < ts_end } else { item.ts <= ts_end }).unwrap_or(true)
+        })
+        .filter(|item| find_req.ext.iter().flatten().all(|cond| common_memory::match_cond(item.ext.get(&cond.field), &cond.op, &cond.value)))
+        .filter(|item| find_req.ext_or.as_ref().map(|ext_or| ext_or.iter().any(|cond| common_memory::match_cond(item.ext.get(&cond.field), &cond.op, &cond.value))).unwrap_or(true))
+        .filter(|item| {
+            let mut matched: Option<bool> = None;
+            for group_query in find_req.adv_query.iter().flatten() {
+                let group_matched = group_query.ext.iter().flatten().all(|cond| {
+                    let value = if cond.in_ext.unwrap_or(true) {
+                        item.ext.get(&cond.field).cloned()
+                    } else {
+                        get_field_value(item, &cond.field)
+                    };
+                    common_memory::match_cond(value.as_ref(), &cond.op, &cond.value)
+                });
+                matched = Some(match matched {
+                    Some(matched) if group_query.group_by_or.unwrap_or(false) => matched || group_matched,
+                    Some(matched) => matched && group_matched,
+                    None => group_matched,
+                });
+            }
+            matched.unwrap_or(true)
+        })
+        .collect::<Vec<_>>();
+    // The items are traversed in reverse insertion order, so a stable sort keeps the latest added item first when the timestamps are equal
+    records.sort_by(|a, b| b.ts.cmp(&a.ts));
+    let total_size = records.len() as u64;
+    let records = records
+        .into_iter()
+        .skip(((find_req.page_number.max(1) - 1) * find_req.page_size as u32) as usize)
+        .take(find_req.page_size as usize)
+        .map(|item| LogItemFindResp {
+            content: item.content.clone(),
+            kind: item.kind.clone(),
+            ext: item.ext.clone(),
+            owner: item.owner.clone(),
+            owner_name: item.owner_name.clone(),
+            own_paths: item.own_paths.clone(),
+            id: item.id.clone(),
+            key: item.key.clone(),
+            op: item.op.clone(),
+            rel_key: item.rel_key.clone(),
+            ts: item.ts,
+            msg: item.msg.clone(),
+        })
+        .collect();
+    Ok(TardisPage {
+        page_size: find_req.page_size as u64,
+        page_number: find_req.page_number as u64,
+        total_size,
+        records,
+    })
+}
+
+pub async fn modify_ext(tag: &str, key: &str, ext: &mut JsonValue, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    for item in store.items.get_mut(tag).into_iter().flatten().filter(|item| item.key == key) {
+        match (item.ext.as_object_mut(), ext.as_object()) {
+            (Some(item_ext), Some(ext)) => {
+                for (k, v) in ext {
+                    item_ext.insert(k.to_string(), v.clone());
+                }
+            }
+            _ => item.ext = ext.clone(),
+        }
+    }
+    Ok(())
+}
+
+pub async fn add_config(req: &LogConfigReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    if req.hash_chain == Some(true) {
+        check_sign_key(&funs.conf::<LogConfig>().chain_checkpoint_sign_key)?;
+    }
+    let mut store = get_client(inst).write().await;
+    if let Some(ref_field) = &req.ref_field {
+        let ref_fields = store.ref_fields.entry(req.tag.clone()).or_default();
+        if !ref_fields.contains(ref_field) {
+            ref_fields.push(ref_field.clone());
+        }
+    }
+    let mut register_ctx = false;
+    if req.retention_days.is_some() || req.archive_format.is_some() {
+        let retention = store.retentions.entry(req.tag.clone()).or_default();
+        retention.0 = req.retention_days.or(retention.0);
+        retention.1 = req.archive_format.or(retention.1);
+        register_ctx = true;
+    }
+    match req.hash_chain {
+        Some(true) => {
+            store.chained_tags.insert(req.tag.clone());
+            register_ctx = true;
+        }
+        Some(false) => {
+            store.chained_tags.remove(&req.tag);
+        }
+        None => {}
+    }
+    drop(store);
+    if register_ctx {
+        crate::serv::log_item_serv::register_task_ctx(ctx).await;
+    }
+    Ok(())
+}
+
+pub async fn delete_config(config: &mut LogConfigReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if let (Some(ref_fields), Some(deleted_ref_field)) = (store.ref_fields.get_mut(&config.tag), &config.ref_field) {
+        ref_fields.retain(|ref_field| ref_field != deleted_ref_field);
+    }
+    if config.hash_chain.unwrap_or(false) {
+        store.chained_tags.remove(&config.tag);
+    }
+    if config.retention_days.is_some() {
+        store.retentions.remove(&config.tag);
+    }
+    Ok(())
+}
+
+fn to_chain_record(tag: &str, item: &LogMemoryItem) -> Option<LogChainRecord> {
+    item.chain.as_ref().map(|(chain_seq, prev_hash, hash)| LogChainRecord {
+        chain_seq: *chain_seq,
+        prev_hash: prev_hash.clone(),
+        hash: hash.clone(),
+        id: item.id.clone(),
+        ts: item.ts,
+        key: item.key.clone(),
+        kind: item.kind.clone(),
+        tag: tag.to_string(),
+        op: item.op.clone(),
+        content: item.content.clone(),
+        owner: item.owner.clone(),
+        owner_name: item.owner_name.clone(),
+        own_paths: item.own_paths.clone(),
+        rel_key: item.rel_key.clone(),
+        msg: item.msg.clone(),
+    })
+}
+
+pub async fn verify_chain(verify_req: &mut LogChainVerifyReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LogChainVerifyResp> {
+    let sign_key = funs.conf::<LogConfig>().chain_checkpoint_sign_key.clone();
+    let store = get_client(inst).read().await;
+    let mut records = store.items.get(&verify_req.tag).into_iter().flatten().filter_map(|item| to_chain_record(&verify_req.tag, item)).collect::<Vec<_>>();
+    records.sort_by_key(|record| record.chain_seq);
+    let in_range =
+        |record: &LogChainRecord| verify_req.ts_start.map(|ts_start| record.ts >= ts_start).unwrap_or(true) && verify_req.ts_end.map(|ts_end| record.ts <= ts_end).unwrap_or(true);
+    // All the records may have been purged by the retention policy
+    let purged_chain_seq =
+        store.checkpoints.get(&verify_req.tag).into_iter().flatten().filter(|checkpoint| checkpoint.purged).map(|checkpoint| checkpoint.chain_seq).max().unwrap_or(0);
+    let first_chain_seq = records.iter().filter(|record| in_range(record)).map(|record| record.chain_seq).min().unwrap_or(purged_chain_seq + 1);
+    let max_chain_seq = records.iter().filter(|record| in_range(record)).map(|record| record.chain_seq).max().unwrap_or(0);
+    let prev_hash = records.iter().find(|record| record.chain_seq == first_chain_seq - 1).map(|record| record.hash.clone());
+    let checkpoints = store.checkpoints.get(&verify_req.tag).into_iter().flatten().filter(|checkpoint| checkpoint.chain_seq >= first_chain_seq - 1).cloned().collect();
+    let mut verifier = LogChainVerifier::new(first_chain_seq, prev_hash, checkpoints, &verify_req.tag, &sign_key)?;
+    for record in records.iter().filter(|record| record.chain_seq >= first_chain_seq && record.chain_seq <= max_chain_seq) {
+        if verifier.is_broken() {
+            break;
+        }
+        verifier.feed(record)?;
+    }
+    Ok(verifier.finish(verify_req.ts_start.is_none() && verify_req.ts_end.is_none()))
+}
+
+pub async fn add_chain_checkpoint(tag: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<LogChainCheckpointResp>> {
+    let mut store = get_client(inst).write().await;
+    if !store.chained_tags.contains(tag) {
+        return Err(funs.err().bad_request(
+            "item",
+            "add_chain_checkpoint",
+            &format!("The hash chain of tag {tag} is not enabled"),
+            "400-spi-log-chain-not-enabled",
+        ));
+    }
+    do_add_chain_checkpoint(&mut store, tag, funs)
+}
+
+pub async fn add_chain_checkpoints(funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    for tag in store.chained_tags.clone() {
+        do_add_chain_checkpoint(&mut store, &tag, funs)?;
+    }
+    Ok(())
+}
+
+/// Sign the latest hash of the chain, nothing is added if the chain is empty or has not changed since the last checkpoint
+fn do_add_chain_checkpoint(store: &mut LogMemoryStore, tag: &str, funs: &TardisFunsInst) -> TardisResult<Option<LogChainCheckpointResp>> {
+    let Some((chain_seq, _, hash)) = store.items.get(tag).into_iter().flatten().filter_map(|item| item.chain.clone()).max_by_key(|(chain_seq, _, _)| *chain_seq) else {
+        return Ok(None);
+    };
+    let checkpoints = store.checkpoints.entry(tag.to_string()).or_default();
+    if checkpoints.iter().any(|checkpoint| checkpoint.chain_seq == chain_seq) {
+        return Ok(None);
+    }
+    let checkpoint = LogChainCheckpointResp {
+        chain_seq,
+        signature: sign_checkpoint(tag, chain_seq, &hash, false, &funs.conf::<LogConfig>().chain_checkpoint_sign_key)?,
+        hash,
+        purged: false,
+        create_time: Utc::now(),
+    };
+    checkpoints.push(checkpoint.clone());
+    Ok(Some(checkpoint))
+}
+
+pub async fn find_expired_ranges(_funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<LogExpiredRange>> {
+    let store = get_client(inst).read().await;
+    let mut ranges = vec![];
+    for (tag, (retention_days, archive_format)) in &store.retentions {
+        let Some(retention_days) = retention_days else {
+            continue;
+        };
+        let expire_ts = Utc::now() - Duration::days(*retention_days as i64);
+        if store.items.get(tag).into_iter().flatten().any(|item| item.ts < expire_ts) {
+            ranges.push(LogExpiredRange {
+                tag: tag.clone(),
+                ts_start: None,
+                ts_end: expire_ts,
+                archive_format: *archive_format,
+                partition: None,
+            });
+        }
+    }
+    Ok(ranges)
+}
+
+/// Consistent with the PostgreSQL implementation, a purge anchor checkpoint is added at the last purged record of the hash chained tag
+pub async fn purge_expired_range(range: &LogExpiredRange, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let in_range = |item: &LogMemoryItem| item.ts < range.ts_end && range.ts_start.map(|ts_start| item.ts >= ts_start).unwrap_or(true);
+    let last_purged =
+        store.items.get(&range.tag).into_iter().flatten().filter(|item| in_range(item)).filter_map(|item| item.chain.clone()).max_by_key(|(chain_seq, _, _)| *chain_seq);
+    if let Some((chain_seq, _, hash)) = last_purged {
+        let checkpoint = LogChainCheckpointResp {
+            chain_seq,
+            signature: sign_checkpoint(&range.tag, chain_seq, &hash, true, &funs.conf::<LogConfig>().chain_checkpoint_sign_key)?,
+            hash,
+            purged: true,
+            create_time: Utc::now(),
+        };
+        let checkpoints = store.checkpoints.entry(range.tag.clone()).or_default();
+        checkpoints.retain(|exist_checkpoint| exist_checkpoint.chain_seq != chain_seq);
+        checkpoints.push(checkpoint);
+    }
+    if let Some(items) = store.items.get_mut(&range.tag) {
+        items.retain(|item| !in_range(item));
+    }
+    Ok(())
+}
+
+/// Get the value of the basic field, used for the advanced query with ``in_ext = false``
+fn get_field_value(item: &LogMemoryItem, field: &str) -> Option<JsonValue> {
+    match field {
+        "id" | "idempotent_id" => Some(json!(item.id)),
+        "kind" => Some(json!(item.kind)),
+        "key" => Some(json!(item.key)),
+        "op" => Some(json!(item.op)),
+        "owner" => Some(json!(item.owner)),
+        "owner_name" => Some(json!(item.owner_name)),
+        "own_paths" => Some(json!(item.own_paths)),
+        "rel_key" => Some(json!(item.rel_key)),
+        "msg" => Some(json!(item.msg)),
+        "ts" => Some(json!(item.ts)),
+        _ => None,
+    }
+}
+
+/// Consistent with the PostgreSQL implementation, the condition without value is not legal (except for the null checks).
+fn check_cond_value(field: &str, op: &BasicQueryOpKind, value: &JsonValue, funs: &TardisFunsInst) -> TardisResult<()> {
+    let legal = match value {
+        JsonValue::Null => false,
+        JsonValue::Array(values) => !values.is_empty() && (values.len() == 1 || op == &BasicQueryOpKind::In || op == &BasicQueryOpKind::NotIn),
+        _ => true,
+    };
+    if legal || op == &BasicQueryOpKind::IsNull || op == &BasicQueryOpKind::IsNotNull || op == &BasicQueryOpKind::IsNullOrEmpty {
+        Ok(())
+    } else {
+        Err(funs.err().not_found(
+            "item",
+            "log",
+            &format!("The ext field=[{}] value=[{}] operation=[{}] is not legal.", field, value, op),
+            "404-spi-log-op-not-legal",
+        ))
+    }
+}
@@ -8,7 +8,10 @@ use tardis::{
 
 use bios_basic::{dto::BasicQueryCondInfo, enumeration::BasicQueryOpKind, helper::db_helper, spi::spi_funs::SpiBsInst};
 
-use crate::dto::log_item_dto::{AdvBasicQueryCondInfo, LogConfigReq, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp};
+use crate::dto::log_item_dto::{
+    AdvBasicQueryCondInfo, LogChainCheckpointResp, LogChainVerifyReq, LogChainVerifyResp, LogConfigReq, LogExpiredRange, LogItemAddReq, LogItemAddV2Req, LogItemFindReq,
+    LogItemFindResp,
+};
 
 use super::log_pg_initializer;
 
@@ -122,7 +125,7 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
     }
     if let Some(ts_end) = find_req.ts_end {
         sql_vals.push(Value::from(ts_end));
-        where_fragments.push(format!("ts <= ${}", sql_vals.len()));
+        where_fragments.push(format!("ts {} ${}", if find_req.ts_end_exclusive == Some(true) { "<" } else { "<=" }, sql_vals.len()));
     }
     let err_notfound = |ext: &BasicQueryCondInfo| {
         Err(funs.err().not_found(
@@ -528,3 +531,35 @@ pub async fn add_config(_req: &LogConfigReq, funs: &TardisFunsInst, _ctx: &Tardi
 pub async fn delete_config(_config: &mut LogConfigReq, funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<()> {
     Err(funs.err().bad_request("item", "delete_config", "Delete config is not supported", "400-spi-log-delete-config-not-supported"))
 }
+
+pub async fn verify_chain(_verify_req: &mut LogChainVerifyReq, funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<LogChainVerifyResp> {
+    Err(funs.err().bad_request("item", "verify_chain", "Verify chain is not supported", "400-spi-log-verify-chain-not-supported"))
+}
+
+pub async fn add_chain_checkpoint(_tag: &str, funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<Option<LogChainCheckpointResp>> {
+    Err(funs.err().bad_request(
+        "item",
+        "add_chain_checkpoint",
+        "Add chain checkpoint is not supported",
+        "400-spi-log-add-chain-checkpoint-not-supported",
+    ))
+}
+
+/// No chained tags exist in this implementation
+pub async fn add_chain_checkpoints(_funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<()> {
+    Ok(())
+}
+
+/// No retention policies exist in this implementation
+pub async fn find_expired_ranges(_funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<Vec<LogExpiredRange>> {
+    Ok(vec![])
+}
+
+pub async fn purge_expired_range(_range: &LogExpiredRange, funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<()> {
+    Err(funs.err().bad_request(
+        "item",
+        "purge_expired_range",
+        "Purge expired range is not supported",
+        "400-spi-log-purge-expired-range-not-supported",
+    ))
+}
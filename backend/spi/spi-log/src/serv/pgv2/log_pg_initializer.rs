@@ -1,15 +1,60 @@
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+    chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    log::{info, warn},
 };
 
 use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
 
-use crate::log_constants::{self, CONFIG_TABLE_NAME};
+use crate::{
+    dto::log_item_dto::LogPartitionInterval,
+    log_constants::{self, CHAIN_CHECKPOINT_TABLE_NAME, CHAIN_CONFIG_TABLE_NAME, CONFIG_TABLE_NAME, RETENTION_CONFIG_TABLE_NAME},
+};
+
+const TAG_TABLE_INDEXES: [(&str, &str); 13] = [
+    ("kind", "btree"),
+    ("ts", "btree"),
+    ("key", "btree"),
+    ("content", "gin"),
+    ("ext", "gin"),
+    ("owner", "btree"),
+    ("own_paths", "btree"),
+    ("rel_key", "btree"),
+    ("idempotent_id", "btree"),
+    ("disable", "btree"),
+    ("tag", "btree"),
+    ("push", "btree"),
+    ("chain_seq", "btree"),
+];
 
 pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
-    //添加父表
     let schema_name = spi_initializer::common_pg::get_schema_name_from_context(ctx);
+    init_shared_tables(bs_inst, &schema_name).await?;
+    if mgr {
+        init_partitioned_table(bs_inst, tag, ctx, &schema_name).await?;
+    }
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        Some(tag),
+        log_constants::TABLE_LOG_FLAG_V2,
+        "",
+        Some(format!("{schema_name}.{}", crate::log_constants::PARENT_TABLE_NAME)),
+        TAG_TABLE_INDEXES.to_vec(),
+        None,
+        None,
+    )
+    .await
+}
+
+/// Create the parent table and the config tables shared by all tags
+pub async fn init_shared_tables(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, schema_name: &str) -> TardisResult<()> {
+    //添加父表
     bs_inst
         .0
         .conn()
@@ -38,6 +83,22 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
         )
         .await?;
 
+    //添加哈希链字段（兼容已存在的父表）
+    bs_inst
+        .0
+        .conn()
+        .execute_one(
+            &format!(
+                r#"ALTER TABLE {schema_name}.{}
+                  ADD COLUMN IF NOT EXISTS chain_seq bigint NULL,
+                  ADD COLUMN IF NOT EXISTS prev_hash varchar NULL,
+                  ADD COLUMN IF NOT EXISTS hash varchar NULL;"#,
+                log_constants::PARENT_TABLE_NAME
+            ),
+            vec![],
+        )
+        .await?;
+
     //添加配置表
     bs_inst
         .0
@@ -67,30 +128,199 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
         )
         .await?;
 
-    spi_initializer::common_pg::init_table_and_conn(
-        bs_inst,
-        ctx,
-        mgr,
-        Some(tag),
-        log_constants::TABLE_LOG_FLAG_V2,
-        "",
-        Some(format!("{schema_name}.{}", crate::log_constants::PARENT_TABLE_NAME)),
-        vec![
-            ("kind", "btree"),
-            ("ts", "btree"),
-            ("key", "btree"),
-            ("content", "gin"),
-            ("ext", "gin"),
-            ("owner", "btree"),
-            ("own_paths", "btree"),
-            ("rel_key", "btree"),
-            ("idempotent_id", "btree"),
-            ("disable", "btree"),
-            ("tag", "btree"),
-            ("push", "btree"),
-        ],
-        None,
-        None,
+    //添加哈希链配置表
+    bs_inst
+        .0
+        .conn()
+        .execute_one(
+            &format!(
+                r#"CREATE TABLE IF NOT EXISTS {schema_name}.{CHAIN_CONFIG_TABLE_NAME}(
+                  table_name VARCHAR NOT NULL PRIMARY KEY,
+                  create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+                );"#
+            ),
+            vec![],
+        )
+        .await?;
+
+    //添加哈希链检查点表
+    bs_inst
+        .0
+        .conn()
+        .execute_one(
+            &format!(
+                r#"CREATE TABLE IF NOT EXISTS {schema_name}.{CHAIN_CHECKPOINT_TABLE_NAME}(
+                  table_name VARCHAR NOT NULL,
+                  chain_seq bigint NOT NULL,
+                  hash VARCHAR NOT NULL,
+                  purged boolean NOT NULL DEFAULT false,
+                  signature VARCHAR NOT NULL,
+                  create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                  PRIMARY KEY (table_name, chain_seq)
+                );"#
+            ),
+            vec![],
+        )
+        .await?;
+
+    //添加保留策略配置表
+    bs_inst
+        .0
+        .conn()
+        .execute_one(
+            &format!(
+                r#"CREATE TABLE IF NOT EXISTS {schema_name}.{RETENTION_CONFIG_TABLE_NAME}(
+                  table_name VARCHAR NOT NULL PRIMARY KEY,
+                  tag VARCHAR NOT NULL,
+                  retention_days integer NULL,
+                  partition_interval VARCHAR NULL,
+                  archive_format VARCHAR NULL,
+                  create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+                );"#
+            ),
+            vec![],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Create the tag table with range partitioning by ``ts`` if the partition interval is configured before the table is created.
+///
+/// The partitioned table can not inherit the parent table, so it copies the columns of the parent table instead.
+/// The table created before the partition interval is configured is kept as it is.
+async fn init_partitioned_table(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext, schema_name: &str) -> TardisResult<()> {
+    let table_flag = format!("{}_{tag}", log_constants::TABLE_LOG_FLAG_V2);
+    let mut conn = bs_inst.0.conn();
+    let table_full_name = spi_initializer::common_pg::get_table_full_name(bs_inst.1, log_constants::TABLE_LOG_FLAG_V2.to_string(), tag.to_string());
+    let Some(partition_interval) = conn
+        .query_one(
+            &format!("SELECT partition_interval FROM {schema_name}.{RETENTION_CONFIG_TABLE_NAME} WHERE table_name = $1 AND partition_interval IS NOT NULL"),
+            vec![Value::from(table_full_name.as_str())],
+        )
+        .await?
+    else {
+        return Ok(());
+    };
+    if spi_initializer::common_pg::check_table_exit(&table_flag, &conn, ctx).await? {
+        // The existing table is not converted, which would rewrite all its records, the expired records are still purged row by row
+        if !is_partitioned_table(&conn, &table_full_name).await? {
+            warn!("[SPI-LOG] table {table_full_name} was created before the partition interval was configured, it stays unpartitioned");
+        }
+        return Ok(());
+    }
+    let partition_interval: String = partition_interval.try_get("", "partition_interval")?;
+    info!("[SPI-LOG] create table {table_full_name} partitioned by {partition_interval}");
+    conn.begin().await?;
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(table_full_name.as_str())]).await?;
+    if !spi_initializer::common_pg::check_table_exit(&table_flag, &conn, ctx).await? {
+        conn.execute_one(
+            &format!(
+                "CREATE TABLE {table_full_name} (LIKE {schema_name}.{} INCLUDING DEFAULTS) PARTITION BY RANGE (ts)",
+                log_constants::PARENT_TABLE_NAME
+            ),
+            vec![],
+        )
+        .await?;
+        for (field_name, index_type) in TAG_TABLE_INDEXES {
+            conn.execute_one(&format!("CREATE INDEX ON {table_full_name} USING {index_type}({field_name})"), vec![]).await?;
+        }
+    }
+    conn.commit().await?;
+    Ok(())
+}
+
+pub async fn is_partitioned_table(conn: &TardisRelDBlConnection, table_full_name: &str) -> TardisResult<bool> {
+    Ok(conn
+        .query_one(
+            "SELECT partrelid FROM pg_partitioned_table WHERE partrelid = to_regclass($1)",
+            vec![Value::from(table_full_name)],
+        )
+        .await?
+        .is_some())
+}
+
+/// Get the partition suffix and the range ``[start, end)`` that the time belongs to
+pub fn get_partition_range(partition_interval: LogPartitionInterval, ts: &DateTime<Utc>) -> (String, DateTime<Utc>, DateTime<Utc>) {
+    let date = ts.date_naive();
+    let (suffix, start, end) = match partition_interval {
+        LogPartitionInterval::Day => (format!("p{}", date.format("%Y%m%d")), date, date + Duration::days(1)),
+        LogPartitionInterval::Month => {
+            let start = date.with_day(1).unwrap_or(date);
+            let end = if start.month() == 12 {
+                NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+            } else {
+                NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+            }
+            .unwrap_or(start);
+            (format!("p{}", date.format("%Y%m")), start, end)
+        }
+    };
+    (
+        suffix,
+        Utc.from_utc_datetime(&start.and_time(NaiveTime::MIN)),
+        Utc.from_utc_datetime(&end.and_time(NaiveTime::MIN)),
     )
-    .await
+}
+
+/// Parse the range ``[start, end)`` from the partition suffix, ``None`` if the partition is not created by [`init_partition`]
+pub fn parse_partition_range(partition_interval: LogPartitionInterval, suffix: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let date = suffix.strip_prefix('p')?;
+    let date = match partition_interval {
+        LogPartitionInterval::Day if date.len() == 8 => NaiveDate::parse_from_str(date, "%Y%m%d").ok()?,
+        LogPartitionInterval::Month if date.len() == 6 => NaiveDate::parse_from_str(&format!("{date}01"), "%Y%m%d").ok()?,
+        _ => return None,
+    };
+    let (_, start, end) = get_partition_range(partition_interval, &Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)));
+    Some((start, end))
+}
+
+/// Create the partition that the time belongs to if it does not exist, must be called in a transaction
+pub async fn init_partition(conn: &TardisRelDBlConnection, table_full_name: &str, partition_interval: LogPartitionInterval, ts: &DateTime<Utc>) -> TardisResult<()> {
+    let (suffix, start, end) = get_partition_range(partition_interval, ts);
+    let partition_full_name = format!("{table_full_name}_{suffix}");
+    if is_table_exist(conn, &partition_full_name).await? {
+        return Ok(());
+    }
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(partition_full_name.as_str())]).await?;
+    if is_table_exist(conn, &partition_full_name).await? {
+        return Ok(());
+    }
+    conn.execute_one(
+        &format!(
+            "CREATE TABLE {partition_full_name} PARTITION OF {table_full_name} FOR VALUES FROM ('{}') TO ('{}')",
+            start.to_rfc3339(),
+            end.to_rfc3339()
+        ),
+        vec![],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn is_table_exist(conn: &TardisRelDBlConnection, table_full_name: &str) -> TardisResult<bool> {
+    let result = conn.query_one("SELECT to_regclass($1) IS NOT NULL AS exist", vec![Value::from(table_full_name)]).await?;
+    Ok(result.map(|row| row.try_get::<bool>("", "exist")).transpose()?.unwrap_or(false))
+}
+
+/// Find the partitions of the table with their ranges ``[start, end)``
+pub async fn find_partitions(
+    conn: &TardisRelDBlConnection,
+    table_full_name: &str,
+    partition_interval: LogPartitionInterval,
+) -> TardisResult<Vec<(String, DateTime<Utc>, DateTime<Utc>)>> {
+    let (schema_name, table_name) = table_full_name.split_once('.').unwrap_or(("public", table_full_name));
+    let partitions = conn
+        .query_all(
+            "SELECT c.relname FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid WHERE i.inhparent = to_regclass($1)",
+            vec![Value::from(table_full_name)],
+        )
+        .await?;
+    let mut result = vec![];
+    for partition in partitions {
+        let partition_name: String = partition.try_get("", "relname")?;
+        if let Some((start, end)) = partition_name.strip_prefix(&format!("{table_name}_")).and_then(|suffix| parse_partition_range(partition_interval, suffix)) {
+            result.push((format!("{schema_name}.{partition_name}"), start, end));
+        }
+    }
+    Ok(result)
 }
@@ -3,10 +3,10 @@ use std::{collections::HashMap, str::FromStr, vec};
 use bios_sdk_invoke::clients::event_client::{get_topic, mq_error, EventAttributeExt as _, SPI_RPC_TOPIC};
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
-    chrono::{DateTime, Utc},
+    chrono::{DateTime, Duration, Utc},
     db::{
         reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
-        sea_orm::Value,
+        sea_orm::{QueryResult, Value},
     },
     futures::TryFutureExt as _,
     serde_json::{self, Value as JsonValue},
@@ -22,8 +22,13 @@ use bios_basic::{
 };
 
 use crate::{
-    dto::log_item_dto::{AdvBasicQueryCondInfo, LogConfigReq, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp, StatsItemAddReq},
-    log_constants::{CONFIG_TABLE_NAME, LOG_REF_FLAG, TABLE_LOG_FLAG_V2},
+    dto::log_item_dto::{
+        AdvBasicQueryCondInfo, LogChainCheckpointResp, LogChainVerifyReq, LogChainVerifyResp, LogConfigReq, LogExpiredRange, LogExportFormat, LogItemAddReq, LogItemAddV2Req,
+        LogItemFindReq, LogItemFindResp, LogPartitionInterval, StatsItemAddReq,
+    },
+    log_config::LogConfig,
+    log_constants::{CHAIN_CHECKPOINT_TABLE_NAME, CHAIN_CONFIG_TABLE_NAME, CONFIG_TABLE_NAME, LOG_REF_FLAG, RETENTION_CONFIG_TABLE_NAME, TABLE_LOG_FLAG_V2},
+    serv::log_chain_serv::{check_sign_key, sign_checkpoint, LogChainRecord, LogChainVerifier, VERIFY_BATCH_SIZE},
 };
 
 use super::log_pg_initializer;
@@ -42,7 +47,49 @@ pub async fn addv2(add_req: &mut LogItemAddV2Req, funs: &TardisFunsInst, ctx: &T
     let mut insert_content = add_req.content.clone();
     let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &add_req.tag, ctx, true).await?;
     conn.begin().await?;
-    let ref_fields = get_ref_fields_by_table_name(&conn, &get_schema_name_from_ext(&inst.ext).expect("ignore"), &table_name).await?;
+    let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+    let ref_fields = get_ref_fields_by_table_name(&conn, &schema_name, &table_name).await?;
+    let chain = if is_chain_enabled(&conn, &schema_name, &table_name).await? {
+        crate::serv::log_item_serv::register_task_ctx(ctx).await;
+        // Serialize the writes of the chain to keep the sequence continuous
+        conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(table_name.clone())]).await?;
+        let last_record = conn
+            .query_one(
+                &format!("SELECT chain_seq, hash FROM {table_name} WHERE chain_seq IS NOT NULL ORDER BY chain_seq DESC LIMIT 1"),
+                vec![],
+            )
+            .await?;
+        // The chain continues from the purge anchor if all the chained records have been purged
+        let last_record = match last_record {
+            Some(last_record) => Some(last_record),
+            None => {
+                conn.query_one(
+                    &format!("SELECT chain_seq, hash FROM {schema_name}.{CHAIN_CHECKPOINT_TABLE_NAME} WHERE table_name = $1 AND purged ORDER BY chain_seq DESC LIMIT 1"),
+                    vec![Value::from(table_name.as_str())],
+                )
+                .await?
+            }
+        };
+        Some(match last_record {
+            Some(last_record) => (
+                last_record.try_get::<i64>("", "chain_seq")? + 1,
+                last_record.try_get::<Option<String>>("", "hash")?.unwrap_or_default(),
+            ),
+            None => (1, "".to_string()),
+        })
+    } else {
+        None
+    };
+    if let Some(retention_config) = find_retention_config(&conn, &schema_name, &table_name).await? {
+        crate::serv::log_item_serv::register_task_ctx(ctx).await;
+        if let Some(partition_interval) = retention_config.partition_interval {
+            if log_pg_initializer::is_partitioned_table(&conn, &table_name).await? {
+                // The partition is located by the timestamp, so it can not be left to the default value of the database
+                let ts = *add_req.ts.get_or_insert_with(Utc::now);
+                log_pg_initializer::init_partition(&conn, &table_name, partition_interval, &ts).await?;
+            }
+        }
+    }
     if let Some(key) = add_req.key.as_ref() {
         let get_last_record = conn
             .query_one(
@@ -96,22 +143,45 @@ pub async fn addv2(add_req: &mut LogItemAddV2Req, funs: &TardisFunsInst, ctx: &T
         Value::from(add_req.rel_key.as_ref().unwrap_or(&"".into()).to_string()),
         Value::from(add_req.msg.as_ref().unwrap_or(&"".into()).as_str()),
     ];
+    let mut fields = "idempotent_id, kind, key, tag, op, content, owner, owner_name, own_paths, push, ext, rel_key, msg".to_string();
     if let Some(ts) = add_req.ts {
         params.push(Value::from(ts));
+        fields.push_str(", ts");
+    }
+    if let Some((chain_seq, prev_hash)) = &chain {
+        params.push(Value::from(*chain_seq));
+        params.push(Value::from(prev_hash.as_str()));
+        fields.push_str(", chain_seq, prev_hash");
     }
     conn.execute_one(
         &format!(
             r#"INSERT INTO {table_name}
-  (idempotent_id, kind, key, tag, op, content, owner, owner_name, own_paths, push, ext, rel_key, msg{})
+  ({fields})
 VALUES
-  ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13{})
+  ({})
 "#,
-            if add_req.ts.is_some() { ", ts" } else { "" },
-            if add_req.ts.is_some() { ", $14" } else { "" },
+            (1..=params.len()).map(|idx| format!("${idx}")).collect::<Vec<String>>().join(", ")
         ),
         params,
     )
     .await?;
+    if let Some((chain_seq, _)) = chain {
+        // The hash is computed from the stored values, so that it is not affected by the normalization of the database (e.g. jsonb, timestamp precision)
+        if let Some(record) = conn
+            .query_one(
+                &format!("SELECT {CHAIN_RECORD_FIELDS} FROM {table_name} WHERE chain_seq = $1"),
+                vec![Value::from(chain_seq)],
+            )
+            .await?
+        {
+            let hash = to_chain_record(&record)?.compute_hash()?;
+            conn.execute_one(
+                &format!("UPDATE {table_name} SET hash = $1 WHERE chain_seq = $2"),
+                vec![Value::from(hash), Value::from(chain_seq)],
+            )
+            .await?;
+        }
+    }
     conn.commit().await?;
     //if push is true, then push to EDA
     if add_req.push {
@@ -220,7 +290,7 @@ pub async fn findv2(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &
     }
     if let Some(ts_end) = find_req.ts_end {
         sql_vals.push(Value::from(ts_end));
-        where_fragments.push(format!("ts <= ${}", sql_vals.len()));
+        where_fragments.push(format!("ts {} ${}", if find_req.ts_end_exclusive == Some(true) { "<" } else { "<=" }, sql_vals.len()));
     }
     let err_notfound = |ext: &BasicQueryCondInfo| {
         Err(funs.err().not_found(
@@ -645,47 +715,472 @@ pub async fn modify_ext(tag: &str, key: &str, ext: &mut JsonValue, _funs: &Tardi
     Ok(())
 }
 
-pub async fn add_config(req: &LogConfigReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
-    let table_full_name = bios_basic::spi::spi_initializer::common_pg::get_table_full_name(&inst.ext, TABLE_LOG_FLAG_V2.to_string(), req.tag.clone());
-    let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+pub async fn add_config(req: &LogConfigReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    if req.hash_chain == Some(true) {
+        check_sign_key(&funs.conf::<LogConfig>().chain_checkpoint_sign_key)?;
+    }
     let bs_inst = inst.inst::<TardisRelDBClient>();
-    if bs_inst
-        .0
-        .conn()
-        .query_one(
-            &format!("select table_name,ref_field from {schema_name}.{CONFIG_TABLE_NAME} where table_name = $1 and ref_field = $2"),
-            vec![Value::from(table_full_name.clone()), Value::from(req.ref_field.clone())],
+    let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+    if req.retention_days.is_some() || req.partition_interval.is_some() || req.archive_format.is_some() {
+        // The retention config is saved before the table is created, so that the table can be created with partitions
+        log_pg_initializer::init_shared_tables(bs_inst, &schema_name).await?;
+        let conn = bs_inst.0.conn();
+        let table_full_name = bios_basic::spi::spi_initializer::common_pg::get_table_full_name(&inst.ext, TABLE_LOG_FLAG_V2.to_string(), req.tag.clone());
+        if let Some(partition_interval) = req.partition_interval {
+            let current_partition_interval = find_retention_config(&conn, &schema_name, &table_full_name).await?.and_then(|config| config.partition_interval);
+            if current_partition_interval != Some(partition_interval) && log_pg_initializer::is_table_exist(&conn, &table_full_name).await? {
+                return Err(funs.err().bad_request(
+                    "item",
+                    "add_config",
+                    &format!("The partition interval of tag {} can not be changed after the table is created", req.tag),
+                    "400-spi-log-partition-interval-immutable",
+                ));
+            }
+        }
+        conn.execute_one(
+            &format!(
+                r#"INSERT INTO {schema_name}.{RETENTION_CONFIG_TABLE_NAME} AS t(table_name, tag, retention_days, partition_interval, archive_format) VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (table_name) DO UPDATE SET
+  retention_days = COALESCE(EXCLUDED.retention_days, t.retention_days),
+  partition_interval = COALESCE(EXCLUDED.partition_interval, t.partition_interval),
+  archive_format = COALESCE(EXCLUDED.archive_format, t.archive_format)"#
+            ),
+            vec![
+                Value::from(table_full_name),
+                Value::from(req.tag.as_str()),
+                Value::from(req.retention_days.map(|retention_days| retention_days as i32)),
+                Value::from(req.partition_interval.map(|partition_interval| enum_to_string(&partition_interval)).transpose()?),
+                Value::from(req.archive_format.map(|archive_format| enum_to_string(&archive_format)).transpose()?),
+            ],
         )
-        .await?
-        .is_some()
-    {
-        Ok(())
-    } else {
-        //新增记录
+        .await?;
+        crate::serv::log_item_serv::register_task_ctx(ctx).await;
+    }
+    // Make sure the config tables exist
+    let (conn, table_full_name) = log_pg_initializer::init_table_and_conn(bs_inst, &req.tag, ctx, true).await?;
+    if let Some(ref_field) = &req.ref_field {
+        if conn
+            .query_one(
+                &format!("select table_name,ref_field from {schema_name}.{CONFIG_TABLE_NAME} where table_name = $1 and ref_field = $2"),
+                vec![Value::from(table_full_name.clone()), Value::from(ref_field.clone())],
+            )
+            .await?
+            .is_none()
+        {
+            //新增记录
+            conn.execute_one(
+                &format!("insert into {schema_name}.{CONFIG_TABLE_NAME}(table_name,ref_field) VALUES ($1,$2)"),
+                vec![Value::from(table_full_name.clone()), Value::from(ref_field.clone())],
+            )
+            .await?;
+        }
+    }
+    match req.hash_chain {
+        Some(true) => {
+            conn.execute_one(
+                &format!("insert into {schema_name}.{CHAIN_CONFIG_TABLE_NAME}(table_name) VALUES ($1) on conflict (table_name) do nothing"),
+                vec![Value::from(table_full_name)],
+            )
+            .await?;
+            crate::serv::log_item_serv::register_task_ctx(ctx).await;
+        }
+        Some(false) => {
+            conn.execute_one(
+                &format!("delete from {schema_name}.{CHAIN_CONFIG_TABLE_NAME} where table_name = $1"),
+                vec![Value::from(table_full_name)],
+            )
+            .await?;
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+pub async fn delete_config(config: &mut LogConfigReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let table_full_name = bios_basic::spi::spi_initializer::common_pg::get_table_full_name(&inst.ext, TABLE_LOG_FLAG_V2.to_string(), config.tag.clone());
+    let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    if let Some(ref_field) = &config.ref_field {
         bs_inst
             .0
             .conn()
             .execute_one(
-                &format!("insert into {schema_name}.{CONFIG_TABLE_NAME}(table_name,ref_field) VALUES ($1,$2)"),
-                vec![Value::from(table_full_name), Value::from(req.ref_field.clone())],
+                &format!("delete from {schema_name}.{CONFIG_TABLE_NAME} where table_name = $1 and ref_field = $2"),
+                vec![Value::from(table_full_name.clone()), Value::from(ref_field.clone())],
             )
             .await?;
-        Ok(())
     }
+    // The chained records are kept and can still be verified after the hash chain mode is disabled
+    if config.hash_chain.unwrap_or(false) {
+        bs_inst
+            .0
+            .conn()
+            .execute_one(
+                &format!("delete from {schema_name}.{CHAIN_CONFIG_TABLE_NAME} where table_name = $1"),
+                vec![Value::from(table_full_name.clone())],
+            )
+            .await?;
+    }
+    // The partition interval is kept because the partitions of the new records still need to be created
+    if config.retention_days.is_some() {
+        bs_inst
+            .0
+            .conn()
+            .execute_one(
+                &format!("update {schema_name}.{RETENTION_CONFIG_TABLE_NAME} set retention_days = null, archive_format = null where table_name = $1"),
+                vec![Value::from(table_full_name)],
+            )
+            .await?;
+    }
+    Ok(())
 }
 
-pub async fn delete_config(config: &mut LogConfigReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
-    let table_full_name = bios_basic::spi::spi_initializer::common_pg::get_table_full_name(&inst.ext, TABLE_LOG_FLAG_V2.to_string(), config.tag.clone());
+struct RetentionConfig {
+    table_name: String,
+    tag: String,
+    retention_days: Option<i32>,
+    partition_interval: Option<LogPartitionInterval>,
+    archive_format: Option<LogExportFormat>,
+}
+
+fn enum_to_string<T: serde::Serialize>(value: &T) -> TardisResult<String> {
+    Ok(TardisFuns::json.obj_to_json(value)?.as_str().unwrap_or_default().to_string())
+}
+
+fn string_to_enum<T: serde::de::DeserializeOwned>(value: Option<String>) -> TardisResult<Option<T>> {
+    value.map(|value| TardisFuns::json.json_to_obj(JsonValue::String(value))).transpose()
+}
+
+fn to_retention_config(row: &QueryResult) -> TardisResult<RetentionConfig> {
+    Ok(RetentionConfig {
+        table_name: row.try_get("", "table_name")?,
+        tag: row.try_get("", "tag")?,
+        retention_days: row.try_get("", "retention_days")?,
+        partition_interval: string_to_enum(row.try_get("", "partition_interval")?)?,
+        archive_format: string_to_enum(row.try_get("", "archive_format")?)?,
+    })
+}
+
+async fn find_retention_config(conn: &TardisRelDBlConnection, schema_name: &str, table_full_name: &str) -> TardisResult<Option<RetentionConfig>> {
+    conn.query_one(
+        &format!("select table_name, tag, retention_days, partition_interval, archive_format from {schema_name}.{RETENTION_CONFIG_TABLE_NAME} where table_name = $1"),
+        vec![Value::from(table_full_name)],
+    )
+    .await?
+    .map(|row| to_retention_config(&row))
+    .transpose()
+}
+
+const CHAIN_RECORD_FIELDS: &str = "chain_seq, prev_hash, hash, idempotent_id, ts, key, kind, tag, op, content, owner, owner_name, own_paths, rel_key, msg";
+
+fn to_chain_record(record: &QueryResult) -> TardisResult<LogChainRecord> {
+    Ok(LogChainRecord {
+        chain_seq: record.try_get("", "chain_seq")?,
+        prev_hash: record.try_get::<Option<String>>("", "prev_hash")?.unwrap_or_default(),
+        hash: record.try_get::<Option<String>>("", "hash")?.unwrap_or_default(),
+        id: record.try_get("", "idempotent_id")?,
+        ts: record.try_get("", "ts")?,
+        key: record.try_get("", "key")?,
+        kind: record.try_get("", "kind")?,
+        tag: record.try_get("", "tag")?,
+        op: record.try_get("", "op")?,
+        content: record.try_get("", "content")?,
+        owner: record.try_get("", "owner")?,
+        owner_name: record.try_get("", "owner_name")?,
+        own_paths: record.try_get("", "own_paths")?,
+        rel_key: record.try_get("", "rel_key")?,
+        msg: record.try_get("", "msg")?,
+    })
+}
+
+async fn is_chain_enabled(conn: &TardisRelDBlConnection, schema_name: &str, table_full_name: &str) -> TardisResult<bool> {
+    Ok(conn
+        .query_one(
+            &format!("select table_name from {schema_name}.{CHAIN_CONFIG_TABLE_NAME} where table_name = $1"),
+            vec![Value::from(table_full_name)],
+        )
+        .await?
+        .is_some())
+}
+
+async fn find_chain_checkpoints(conn: &TardisRelDBlConnection, schema_name: &str, table_full_name: &str, min_chain_seq: i64) -> TardisResult<Vec<LogChainCheckpointResp>> {
+    conn.query_all(
+        &format!(
+            "select chain_seq, hash, purged, signature, create_time from {schema_name}.{CHAIN_CHECKPOINT_TABLE_NAME} where table_name = $1 and chain_seq >= $2 order by chain_seq"
+        ),
+        vec![Value::from(table_full_name), Value::from(min_chain_seq)],
+    )
+    .await?
+    .iter()
+    .map(to_chain_checkpoint)
+    .collect()
+}
+
+fn to_chain_checkpoint(row: &QueryResult) -> TardisResult<LogChainCheckpointResp> {
+    Ok(LogChainCheckpointResp {
+        chain_seq: row.try_get("", "chain_seq")?,
+        hash: row.try_get("", "hash")?,
+        purged: row.try_get("", "purged")?,
+        signature: row.try_get("", "signature")?,
+        create_time: row.try_get("", "create_time")?,
+    })
+}
+
+pub async fn verify_chain(verify_req: &mut LogChainVerifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LogChainVerifyResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &verify_req.tag, ctx, false).await?;
     let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+    let sign_key = funs.conf::<LogConfig>().chain_checkpoint_sign_key.clone();
+
+    let mut where_fragments = vec!["chain_seq IS NOT NULL".to_string()];
+    let mut sql_vals: Vec<Value> = vec![];
+    if let Some(ts_start) = verify_req.ts_start {
+        sql_vals.push(Value::from(ts_start));
+        where_fragments.push(format!("ts >= ${}", sql_vals.len()));
+    }
+    if let Some(ts_end) = verify_req.ts_end {
+        sql_vals.push(Value::from(ts_end));
+        where_fragments.push(format!("ts <= ${}", sql_vals.len()));
+    }
+    let (min_chain_seq, max_chain_seq) = match conn
+        .query_one(
+            &format!(
+                "SELECT min(chain_seq) AS min_seq, max(chain_seq) AS max_seq FROM {table_name} WHERE {}",
+                where_fragments.join(" AND ")
+            ),
+            sql_vals,
+        )
+        .await?
+    {
+        Some(range) => (range.try_get::<Option<i64>>("", "min_seq")?, range.try_get::<Option<i64>>("", "max_seq")?),
+        None => (None, None),
+    };
+    let first_chain_seq = match min_chain_seq {
+        Some(min_chain_seq) => min_chain_seq,
+        // All the records may have been purged by the retention policy
+        None => conn
+            .query_one(
+                &format!("SELECT max(chain_seq) AS chain_seq FROM {schema_name}.{CHAIN_CHECKPOINT_TABLE_NAME} WHERE table_name = $1 AND purged"),
+                vec![Value::from(table_name.as_str())],
+            )
+            .await?
+            .map(|row| row.try_get::<Option<i64>>("", "chain_seq"))
+            .transpose()?
+            .flatten()
+            .map(|chain_seq| chain_seq + 1)
+            .unwrap_or(1),
+    };
+    let prev_hash = if first_chain_seq > 1 {
+        conn.query_one(&format!("SELECT hash FROM {table_name} WHERE chain_seq = $1"), vec![Value::from(first_chain_seq - 1)])
+            .await?
+            .map(|record| record.try_get::<Option<String>>("", "hash"))
+            .transpose()?
+            .flatten()
+    } else {
+        None
+    };
+    let checkpoints = find_chain_checkpoints(&conn, &schema_name, &table_name, first_chain_seq - 1).await?;
+    let mut verifier = LogChainVerifier::new(first_chain_seq, prev_hash, checkpoints, &table_name, &sign_key)?;
+    if let Some(max_chain_seq) = max_chain_seq {
+        let mut next_chain_seq = first_chain_seq;
+        while !verifier.is_broken() && next_chain_seq <= max_chain_seq {
+            let records = conn
+                .query_all(
+                    &format!("SELECT {CHAIN_RECORD_FIELDS} FROM {table_name} WHERE chain_seq >= $1 AND chain_seq <= $2 ORDER BY chain_seq LIMIT {VERIFY_BATCH_SIZE}"),
+                    vec![Value::from(next_chain_seq), Value::from(max_chain_seq)],
+                )
+                .await?;
+            for record in &records {
+                let record = to_chain_record(record)?;
+                verifier.feed(&record)?;
+                next_chain_seq = record.chain_seq + 1;
+            }
+            if (records.len() as u64) < VERIFY_BATCH_SIZE {
+                break;
+            }
+        }
+    }
+    Ok(verifier.finish(verify_req.ts_start.is_none() && verify_req.ts_end.is_none()))
+}
+
+pub async fn add_chain_checkpoint(tag: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<LogChainCheckpointResp>> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
-    bs_inst
-        .0
-        .conn()
-        .execute_one(
-            &format!("delete from {schema_name}.{CONFIG_TABLE_NAME} where table_name = $1 and ref_field = $2"),
-            vec![Value::from(table_full_name), Value::from(config.ref_field.clone())],
+    let (conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+    if !is_chain_enabled(&conn, &schema_name, &table_name).await? {
+        return Err(funs.err().bad_request(
+            "item",
+            "add_chain_checkpoint",
+            &format!("The hash chain of tag {tag} is not enabled"),
+            "400-spi-log-chain-not-enabled",
+        ));
+    }
+    do_add_chain_checkpoint(&conn, &schema_name, &table_name, funs).await
+}
+
+pub async fn add_chain_checkpoints(funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let conn = inst.inst::<TardisRelDBClient>().0.conn();
+    let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+    let table_names = conn
+        .query_all(&format!("select table_name from {schema_name}.{CHAIN_CONFIG_TABLE_NAME}"), vec![])
+        .await?
+        .into_iter()
+        .map(|row| row.try_get::<String>("", "table_name"))
+        .collect::<Result<Vec<_>, _>>()?;
+    for table_name in table_names {
+        do_add_chain_checkpoint(&conn, &schema_name, &table_name, funs).await?;
+    }
+    Ok(())
+}
+
+/// Sign the latest hash of the chain, nothing is added if the chain is empty or has not changed since the last checkpoint
+async fn do_add_chain_checkpoint(conn: &TardisRelDBlConnection, schema_name: &str, table_full_name: &str, funs: &TardisFunsInst) -> TardisResult<Option<LogChainCheckpointResp>> {
+    let Some(last_record) = conn
+        .query_one(
+            &format!("SELECT chain_seq, hash FROM {table_full_name} WHERE chain_seq IS NOT NULL ORDER BY chain_seq DESC LIMIT 1"),
+            vec![],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let chain_seq: i64 = last_record.try_get("", "chain_seq")?;
+    let hash: String = last_record.try_get::<Option<String>>("", "hash")?.unwrap_or_default();
+    let signature = sign_checkpoint(table_full_name, chain_seq, &hash, false, &funs.conf::<LogConfig>().chain_checkpoint_sign_key)?;
+    let checkpoint = conn
+        .query_one(
+            &format!(
+                r#"INSERT INTO {schema_name}.{CHAIN_CHECKPOINT_TABLE_NAME}(table_name, chain_seq, hash, signature) VALUES ($1, $2, $3, $4)
+ON CONFLICT (table_name, chain_seq) DO NOTHING
+RETURNING chain_seq, hash, purged, signature, create_time"#
+            ),
+            vec![Value::from(table_full_name), Value::from(chain_seq), Value::from(hash), Value::from(signature)],
         )
         .await?;
+    checkpoint.as_ref().map(to_chain_checkpoint).transpose()
+}
+
+pub async fn find_expired_ranges(_funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<LogExpiredRange>> {
+    let conn = inst.inst::<TardisRelDBClient>().0.conn();
+    let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+    if !log_pg_initializer::is_table_exist(&conn, &format!("{schema_name}.{RETENTION_CONFIG_TABLE_NAME}")).await? {
+        return Ok(vec![]);
+    }
+    let retention_configs = conn
+        .query_all(
+            &format!(
+                "select table_name, tag, retention_days, partition_interval, archive_format from {schema_name}.{RETENTION_CONFIG_TABLE_NAME} where retention_days is not null and to_regclass(table_name) is not null"
+            ),
+            vec![],
+        )
+        .await?
+        .iter()
+        .map(to_retention_config)
+        .collect::<TardisResult<Vec<_>>>()?;
+    let mut ranges = vec![];
+    for retention_config in retention_configs {
+        let Some(retention_days) = retention_config.retention_days else {
+            continue;
+        };
+        let expire_ts = Utc::now() - Duration::days(retention_days as i64);
+        match retention_config.partition_interval {
+            Some(partition_interval) if log_pg_initializer::is_partitioned_table(&conn, &retention_config.table_name).await? => {
+                let mut partitions = log_pg_initializer::find_partitions(&conn, &retention_config.table_name, partition_interval).await?;
+                // The older partitions are purged first to keep the purge anchors of the hash chain in order
+                partitions.sort_by_key(|(_, start, _)| *start);
+                for (partition, start, end) in partitions.into_iter().filter(|(_, _, end)| *end <= expire_ts) {
+                    ranges.push(LogExpiredRange {
+                        tag: retention_config.tag.clone(),
+                        ts_start: Some(start),
+                        ts_end: end,
+                        archive_format: retention_config.archive_format,
+                        partition: Some(partition),
+                    });
+                }
+            }
+            _ => {
+                if conn
+                    .query_one(
+                        &format!("SELECT ts FROM {} WHERE ts < $1 LIMIT 1", retention_config.table_name),
+                        vec![Value::from(expire_ts)],
+                    )
+                    .await?
+                    .is_some()
+                {
+                    ranges.push(LogExpiredRange {
+                        tag: retention_config.tag.clone(),
+                        ts_start: None,
+                        ts_end: expire_ts,
+                        archive_format: retention_config.archive_format,
+                        partition: None,
+                    });
+                }
+            }
+        }
+    }
+    Ok(ranges)
+}
+
+/// Purge the records of the range, the partition is dropped as a whole if the range is a partition.
+///
+/// For the hash chained tag, a purge anchor checkpoint is added at the last purged record,
+/// so that the remaining records can still be verified from the anchor.
+pub async fn purge_expired_range(range: &LogExpiredRange, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &range.tag, ctx, false).await?;
+    let schema_name = get_schema_name_from_ext(&inst.ext).expect("ignore");
+    let (target_table_name, mut where_fragments, mut sql_vals) = match &range.partition {
+        Some(partition) => {
+            if !partition.starts_with(&format!("{table_name}_p")) {
+                return Err(funs.err().bad_request(
+                    "item",
+                    "purge_expired_range",
+                    &format!("The partition {partition} does not belong to tag {}", range.tag),
+                    "400-spi-log-partition-not-belong",
+                ));
+            }
+            (partition.clone(), vec![], vec![])
+        }
+        None => (table_name.clone(), vec!["ts < $1".to_string()], vec![Value::from(range.ts_end)]),
+    };
+    if let (None, Some(ts_start)) = (&range.partition, range.ts_start) {
+        sql_vals.push(Value::from(ts_start));
+        where_fragments.push(format!("ts >= ${}", sql_vals.len()));
+    }
+    let where_fragment = if where_fragments.is_empty() {
+        "1 = 1".to_string()
+    } else {
+        where_fragments.join(" AND ")
+    };
+    conn.begin().await?;
+    if is_chain_enabled(&conn, &schema_name, &table_name).await? {
+        conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(table_name.as_str())]).await?;
+        if let Some(last_record) = conn
+            .query_one(
+                &format!("SELECT chain_seq, hash FROM {target_table_name} WHERE chain_seq IS NOT NULL AND {where_fragment} ORDER BY chain_seq DESC LIMIT 1"),
+                sql_vals.clone(),
+            )
+            .await?
+        {
+            let chain_seq: i64 = last_record.try_get("", "chain_seq")?;
+            let hash: String = last_record.try_get::<Option<String>>("", "hash")?.unwrap_or_default();
+            let signature = sign_checkpoint(&table_name, chain_seq, &hash, true, &funs.conf::<LogConfig>().chain_checkpoint_sign_key)?;
+            conn.execute_one(
+                &format!(
+                    r#"INSERT INTO {schema_name}.{CHAIN_CHECKPOINT_TABLE_NAME}(table_name, chain_seq, hash, purged, signature) VALUES ($1, $2, $3, true, $4)
+ON CONFLICT (table_name, chain_seq) DO UPDATE SET hash = EXCLUDED.hash, purged = true, signature = EXCLUDED.signature"#
+                ),
+                vec![Value::from(table_name.as_str()), Value::from(chain_seq), Value::from(hash), Value::from(signature)],
+            )
+            .await?;
+        }
+    }
+    if range.partition.is_some() {
+        conn.execute_one(&format!("DROP TABLE IF EXISTS {target_table_name}"), vec![]).await?;
+    } else {
+        conn.execute_one(&format!("DELETE FROM {target_table_name} WHERE {where_fragment}"), sql_vals).await?;
+    }
+    conn.commit().await?;
     Ok(())
 }
 
@@ -706,7 +1201,7 @@ async fn get_ref_fields_by_table_name(conn: &TardisRelDBlConnection, schema_name
     Ok(ref_fields)
 }
 
-async fn push_to_eda(req: &LogItemAddV2Req, ref_fields: &Vec<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+pub(crate) async fn push_to_eda(req: &LogItemAddV2Req, ref_fields: &Vec<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     if let Some(topic) = get_topic(&SPI_RPC_TOPIC) {
         let mut req_clone = req.clone();
         for ref_field in ref_fields {
@@ -14,7 +14,9 @@ use tardis::basic::result::TardisResult;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::Void;
 use tardis::{testcontainers, tokio, TardisFuns};
+mod test_log_chain;
 mod test_log_item;
+mod test_log_retention;
 
 #[tokio::test]
 async fn test_log() -> TardisResult<()> {
@@ -41,6 +43,7 @@ async fn init_data() -> TardisResult<()> {
     let funs = TardisFuns::inst_with_db_conn(DOMAIN_CODE.to_string(), None);
     let kind_id = RbumKindServ::get_rbum_kind_id_by_code(spi_constants::SPI_PG_KIND_CODE, &funs).await?.unwrap();
     let kind_v2_id = RbumKindServ::get_rbum_kind_id_by_code(SPI_PG_V2_KIND_CODE, &funs).await?.unwrap();
+    let kind_memory_id = RbumKindServ::get_rbum_kind_id_by_code(spi_constants::SPI_MEMORY_KIND_CODE, &funs).await?.unwrap();
     let ctx = TardisContext {
         own_paths: "".to_string(),
         ak: "".to_string(),
@@ -86,12 +89,37 @@ async fn init_data() -> TardisResult<()> {
         )
         .await;
 
+    let bs_memory_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-memory".to_string()),
+                kind_id: TrimString(kind_memory_id),
+                conn_uri: "memory://test".to_string(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: "{}".to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+
     let app001 = "app001";
     let app002 = "app002";
+    let app003 = "app003";
     let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/{}", bs_id, app001), &Void {}).await;
     let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/{}", bs_v2_id, app002), &Void {}).await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/{}", bs_memory_id, app003), &Void {}).await;
 
     test_log_item::test(app001, &mut client).await?;
     test_log_item::test(app002, &mut client).await?;
+    test_log_chain::test(SPI_PG_V2_KIND_CODE, app002, &mut client).await?;
+    test_log_retention::test(SPI_PG_V2_KIND_CODE, app002, &mut client).await?;
+
+    // In-memory backend
+    test_log_item::test(app003, &mut client).await?;
+    test_log_chain::test(spi_constants::SPI_MEMORY_KIND_CODE, app003, &mut client).await?;
+    test_log_retention::test(spi_constants::SPI_MEMORY_KIND_CODE, app003, &mut client).await?;
     Ok(())
 }
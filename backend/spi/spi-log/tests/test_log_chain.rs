@@ -0,0 +1,120 @@
+use bios_basic::spi::spi_constants;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_log::dto::log_item_dto::{LogChainBrokenKind, LogChainCheckpointResp, LogChainVerifyResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::db::sea_orm::Value;
+use tardis::serde_json::json;
+use tardis::web::web_resp::{TardisResp, Void};
+use tardis::TardisFuns;
+
+pub async fn test(kind_code: &str, app: &str, client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: format!("t1/{}", app),
+        ak: app.to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: app.to_string(),
+        ..Default::default()
+    })?;
+
+    // enable hash chain mode
+    let _: Void = client.post("/ci/v2/item/config", &json!({"tag":"audit_chain", "hash_chain": true})).await;
+    for (idx, op) in ["login", "modify", "logout"].iter().enumerate() {
+        let _: String = client
+            .post(
+                "/ci/v2/item",
+                &json!({
+                    "tag":"audit_chain",
+                    "key": format!("account00{idx}"),
+                    "content": {"name":"测试账号","idx":idx},
+                    "op":op,
+                    "owner":"account001",
+                    "push":false
+                }),
+            )
+            .await;
+    }
+    let verify_resp: LogChainVerifyResp = client.put("/ci/v2/item/chain/verify", &json!({"tag":"audit_chain"})).await;
+    assert_eq!(verify_resp.verified_count, 3);
+    assert_eq!(verify_resp.last_chain_seq, Some(3));
+    assert!(verify_resp.broken.is_none());
+
+    // checkpoint
+    let checkpoint: Option<LogChainCheckpointResp> = client.post("/ci/v2/item/chain/audit_chain/checkpoint", &Void {}).await;
+    let checkpoint = checkpoint.unwrap();
+    assert_eq!(checkpoint.chain_seq, 3);
+    assert_eq!(Some(checkpoint.hash), verify_resp.last_hash);
+    let resp: TardisResp<Option<LogChainCheckpointResp>> = client.post_resp("/ci/v2/item/chain/audit_chain/checkpoint", &Void {}).await;
+    assert!(resp.data.unwrap().is_none());
+    let resp: TardisResp<Option<LogChainCheckpointResp>> = client.post_resp("/ci/v2/item/chain/audit/checkpoint", &Void {}).await;
+    assert!(resp.code.starts_with("400"));
+
+    // ext is not covered by the hash
+    let _: Void = client.post("/ci/v2/item/modify/audit_chain/account001/ext", &json!({"remark":"ok"})).await;
+    let verify_resp: LogChainVerifyResp = client.put("/ci/v2/item/chain/verify", &json!({"tag":"audit_chain"})).await;
+    assert!(verify_resp.broken.is_none());
+    if kind_code == spi_constants::SPI_MEMORY_KIND_CODE {
+        // the records of the in-memory implementation can not be tampered from outside
+        return Ok(());
+    }
+
+    // tamper the content
+    let table_name = get_table_name("audit_chain").await?;
+    TardisFuns::reldb().conn().execute_one(&format!(r#"UPDATE {table_name} SET content = '{{"name":"篡改"}}' WHERE chain_seq = 2"#), vec![]).await?;
+    let verify_resp: LogChainVerifyResp = client.put("/ci/v2/item/chain/verify", &json!({"tag":"audit_chain"})).await;
+    assert_eq!(verify_resp.verified_count, 1);
+    let broken = verify_resp.broken.unwrap();
+    assert_eq!(broken.chain_seq, 2);
+    assert_eq!(broken.kind, LogChainBrokenKind::HashMismatch);
+    assert!(broken.id.is_some());
+
+    // delete a record in the middle
+    TardisFuns::reldb().conn().execute_one(&format!("DELETE FROM {table_name} WHERE chain_seq = 2"), vec![]).await?;
+    let verify_resp: LogChainVerifyResp = client.put("/ci/v2/item/chain/verify", &json!({"tag":"audit_chain"})).await;
+    let broken = verify_resp.broken.unwrap();
+    assert_eq!(broken.chain_seq, 2);
+    assert_eq!(broken.kind, LogChainBrokenKind::Missing);
+    assert!(broken.id.is_none());
+
+    // delete the tail, which can only be found by the checkpoint
+    let _: Void = client.post("/ci/v2/item/config", &json!({"tag":"audit_chain_tail", "hash_chain": true})).await;
+    for idx in 0..2 {
+        let _: String = client.post("/ci/v2/item", &json!({"tag":"audit_chain_tail", "content": {"idx":idx}, "op":"login", "push":false})).await;
+    }
+    let _: Option<LogChainCheckpointResp> = client.post("/ci/v2/item/chain/audit_chain_tail/checkpoint", &Void {}).await;
+    let table_name = get_table_name("audit_chain_tail").await?;
+    TardisFuns::reldb().conn().execute_one(&format!("DELETE FROM {table_name} WHERE chain_seq = $1"), vec![Value::from(2)]).await?;
+    let verify_resp: LogChainVerifyResp = client.put("/ci/v2/item/chain/verify", &json!({"tag":"audit_chain_tail"})).await;
+    assert_eq!(verify_resp.verified_count, 1);
+    let broken = verify_resp.broken.unwrap();
+    assert_eq!(broken.chain_seq, 2);
+    assert_eq!(broken.kind, LogChainBrokenKind::Missing);
+
+    // records written after the hash chain mode is disabled are not chained
+    let _: Void = client.post("/ci/v2/item/config", &json!({"tag":"audit_chain_tail", "hash_chain": false})).await;
+    let _: String = client.post("/ci/v2/item", &json!({"tag":"audit_chain_tail", "content": {"idx":2}, "op":"login", "push":false})).await;
+    let verify_resp: LogChainVerifyResp = client
+        .put(
+            "/ci/v2/item/chain/verify",
+            &json!({"tag":"audit_chain_tail", "ts_start": "2000-01-01T00:00:00.000Z", "ts_end": "2099-01-01T00:00:00.000Z"}),
+        )
+        .await;
+    assert_eq!(verify_resp.verified_count, 1);
+    assert!(verify_resp.broken.is_none());
+    Ok(())
+}
+
+async fn get_table_name(tag: &str) -> TardisResult<String> {
+    let table_name = format!("starsys_logv2_{tag}");
+    let schema_name: String = TardisFuns::reldb()
+        .conn()
+        .query_one(
+            "SELECT table_schema FROM information_schema.tables WHERE table_name = $1",
+            vec![Value::from(table_name.as_str())],
+        )
+        .await?
+        .unwrap()
+        .try_get("", "table_schema")?;
+    Ok(format!("{schema_name}.{table_name}"))
+}
@@ -0,0 +1,81 @@
+use bios_basic::spi::spi_constants;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_log::dto::log_item_dto::{LogChainVerifyResp, LogItemFindResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::db::sea_orm::Value;
+use tardis::serde_json::json;
+use tardis::web::web_resp::{TardisPage, TardisResp, Void};
+use tardis::TardisFuns;
+
+pub async fn test(kind_code: &str, app: &str, client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: format!("t1/{}", app),
+        ak: app.to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: app.to_string(),
+        ..Default::default()
+    })?;
+
+    // partitioned by day
+    let _: Void = client.post("/ci/v2/item/config", &json!({"tag":"audit_retention", "retention_days": 30, "partition_interval": "Day"})).await;
+    let _: String = client
+        .post(
+            "/ci/v2/item",
+            &json!({"tag":"audit_retention", "content": {"idx":0}, "op":"login", "ts":"2000-01-01T08:00:00.000Z", "push":false}),
+        )
+        .await;
+    let _: String = client.post("/ci/v2/item", &json!({"tag":"audit_retention", "content": {"idx":1}, "op":"login", "push":false})).await;
+    let find_result: TardisPage<LogItemFindResp> = client.put("/ci/v2/item/find", &json!({"tag":"audit_retention", "page_number":1, "page_size":10})).await;
+    assert_eq!(find_result.total_size, 2);
+    // the in-memory implementation has no partitions
+    let partitioned = kind_code != spi_constants::SPI_MEMORY_KIND_CODE;
+    if partitioned {
+        assert_eq!(count_partitions("audit_retention").await?, 2);
+
+        // the partition interval can not be changed after the table is created
+        let resp: TardisResp<Void> = client.post_resp("/ci/v2/item/config", &json!({"tag":"audit_retention", "partition_interval": "Month"})).await;
+        assert!(resp.code.starts_with("400"));
+    }
+
+    // the expired partition is dropped
+    let _: Void = client.post("/ci/v2/item/retention/purge", &Void {}).await;
+    let find_result: TardisPage<LogItemFindResp> = client.put("/ci/v2/item/find", &json!({"tag":"audit_retention", "page_number":1, "page_size":10})).await;
+    assert_eq!(find_result.total_size, 1);
+    assert_eq!(find_result.records[0].content, json!({"idx":1}));
+    if partitioned {
+        assert_eq!(count_partitions("audit_retention").await?, 1);
+    }
+
+    // the hash chain can still be verified after the expired records are purged
+    let _: Void = client.post("/ci/v2/item/config", &json!({"tag":"audit_retention_chain", "hash_chain": true})).await;
+    let _: String = client
+        .post(
+            "/ci/v2/item",
+            &json!({"tag":"audit_retention_chain", "content": {"idx":0}, "op":"login", "ts":"2000-01-01T08:00:00.000Z", "push":false}),
+        )
+        .await;
+    let _: String = client.post("/ci/v2/item", &json!({"tag":"audit_retention_chain", "content": {"idx":1}, "op":"login", "push":false})).await;
+    let _: Void = client.post("/ci/v2/item/config", &json!({"tag":"audit_retention_chain", "retention_days": 30})).await;
+    let _: Void = client.post("/ci/v2/item/retention/purge", &Void {}).await;
+    let find_result: TardisPage<LogItemFindResp> = client.put("/ci/v2/item/find", &json!({"tag":"audit_retention_chain", "page_number":1, "page_size":10})).await;
+    assert_eq!(find_result.total_size, 1);
+    let verify_resp: LogChainVerifyResp = client.put("/ci/v2/item/chain/verify", &json!({"tag":"audit_retention_chain"})).await;
+    assert_eq!(verify_resp.verified_count, 1);
+    assert_eq!(verify_resp.last_chain_seq, Some(2));
+    assert!(verify_resp.broken.is_none());
+    Ok(())
+}
+
+async fn count_partitions(tag: &str) -> TardisResult<i64> {
+    TardisFuns::reldb()
+        .conn()
+        .query_one(
+            "SELECT count(*) AS count FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhparent WHERE c.relname = $1",
+            vec![Value::from(format!("starsys_logv2_{tag}"))],
+        )
+        .await?
+        .unwrap()
+        .try_get("", "count")
+}
@@ -9,7 +9,9 @@ use tardis::web::poem_openapi::param::Path;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
-use crate::dto::search_item_dto::{SearchItemAddReq, SearchItemModifyReq, SearchItemSearchReq, SearchItemSearchResp, SearchQueryMetricsReq, SearchQueryMetricsResp};
+use crate::dto::search_item_dto::{
+    SearchItemAddReq, SearchItemModifyReq, SearchItemSearchReq, SearchItemSearchResp, SearchItemSuggestReq, SearchItemSuggestResp, SearchQueryMetricsReq, SearchQueryMetricsResp,
+};
 use crate::serv::search_item_serv;
 
 #[derive(Clone)]
@@ -61,6 +63,16 @@ impl SearchCiItemApi {
         TardisResp::ok(resp)
     }
 
+    /// Suggest Items
+    ///
+    /// 根据输入的前缀或相似内容提示标题
+    #[oai(path = "/suggest", method = "put")]
+    async fn suggest(&self, suggest_req: Json<SearchItemSuggestReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<SearchItemSuggestResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = search_item_serv::suggest(&suggest_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// Query Metrics
     #[oai(path = "/metrics", method = "put")]
     async fn query_metrics(&self, query_req: Json<SearchQueryMetricsReq>, ctx: TardisContextExtractor) -> TardisApiResult<SearchQueryMetricsResp> {
@@ -4,7 +4,7 @@ use crate::search_enumeration::{SearchDataTypeKind, SearchQueryAggFunKind, Searc
 use bios_basic::{dto::BasicQueryCondInfo, enumeration::BasicQueryOpKind};
 use serde::{Deserialize, Serialize};
 use tardis::{
-    basic::field::TrimString,
+    basic::{error::TardisError, field::TrimString, result::TardisResult},
     chrono::{DateTime, Utc},
     serde_json::{self, Value},
     web::poem_openapi,
@@ -151,6 +151,56 @@ pub struct SearchItemSearchReq {
     // When the record set is very large, it will seriously affect the performance, it is not recommended to use.
     pub sort: Option<Vec<SearchItemSearchSortReq>>,
     pub page: SearchItemSearchPageReq,
+    // Highlight the matched fragments, only works when `query.q` is specified
+    pub highlight: Option<SearchItemSearchHighlightReq>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct SearchItemSearchHighlightReq {
+    // Fields to be highlighted, supports `title` and `content`, the default is the fields of `query.q_scope`
+    pub fields: Option<Vec<String>>,
+    // Size of each fragment.
+    // It is the number of characters in Elasticsearch (default 100) and the number of words in PostgreSQL (default 35).
+    #[oai(validator(minimum(value = "2", exclusive = "false")))]
+    pub fragment_size: Option<u16>,
+    // Maximum number of fragments of each field, default is 3
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub number_of_fragments: Option<u16>,
+    // Tag inserted before the matched words, default is `<em>`
+    pub pre_tag: Option<String>,
+    // Tag inserted after the matched words, default is `</em>`
+    pub post_tag: Option<String>,
+}
+
+impl SearchItemSearchHighlightReq {
+    pub fn get_fields(&self, q_scope: Option<&SearchItemSearchQScopeKind>) -> TardisResult<Vec<String>> {
+        if let Some(fields) = &self.fields {
+            if let Some(field) = fields.iter().find(|field| field.as_str() != "title" && field.as_str() != "content") {
+                return Err(TardisError::bad_request(
+                    &format!("highlight field [{field}] is not supported, only title and content are supported"),
+                    "400-spi-search-highlight-field-not-legal",
+                ));
+            }
+            return Ok(fields.clone());
+        }
+        Ok(match q_scope.unwrap_or(&SearchItemSearchQScopeKind::Title) {
+            SearchItemSearchQScopeKind::Title => vec!["title".to_string()],
+            SearchItemSearchQScopeKind::Content => vec!["content".to_string()],
+            SearchItemSearchQScopeKind::TitleContent => vec!["title".to_string(), "content".to_string()],
+        })
+    }
+
+    pub fn get_number_of_fragments(&self) -> u16 {
+        self.number_of_fragments.unwrap_or(3)
+    }
+
+    pub fn get_pre_tag(&self) -> &str {
+        self.pre_tag.as_deref().unwrap_or("<em>")
+    }
+
+    pub fn get_post_tag(&self) -> &str {
+        self.post_tag.as_deref().unwrap_or("</em>")
+    }
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
@@ -299,6 +349,34 @@ pub struct SearchItemSearchResp {
     pub ext: Value,
     pub rank_title: f32,
     pub rank_content: f32,
+    // Highlighted fragments, key = field name (`title` / `content`), value = fragments
+    pub highlight: Option<HashMap<String, Vec<String>>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSuggestReq {
+    #[oai(validator(pattern = r"^[a-z0-9-_]+$"))]
+    pub tag: String,
+    // Search context for record permission filtering
+    pub ctx: SearchItemSearchCtxReq,
+    // Text entered by the user, matched against the title
+    #[oai(validator(min_length = "1"))]
+    pub q: String,
+    pub kinds: Option<Vec<String>>,
+    // Also return similar titles ("did you mean"), otherwise only titles with the prefix are returned
+    pub fuzzy: Option<bool>,
+    // Maximum number of suggestions, default is 10
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub size: Option<u16>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSuggestResp {
+    pub kind: String,
+    pub key: String,
+    pub title: String,
+    // Relevance of the suggestion, the larger the better
+    pub rank: f32,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
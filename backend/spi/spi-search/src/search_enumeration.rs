@@ -5,9 +5,9 @@ use serde::{Deserialize, Serialize};
 use strum::Display;
 use tardis::{
     basic::{error::TardisError, result::TardisResult},
-    chrono::{DateTime, NaiveDate, Utc},
+    chrono::{DateTime, Datelike, NaiveDate, Utc},
     db::sea_orm::{self, prelude::DateTimeWithTimeZone, DbErr, QueryResult, TryGetError, TryGetable},
-    serde_json,
+    serde_json::{self, json},
     web::poem_openapi,
 };
 
@@ -328,6 +328,17 @@ impl SearchQueryAggFunKind {
             SearchQueryAggFunKind::Count => format!("count({column_name})"),
         }
     }
+
+    /// Elasticsearch metrics aggregation, missing values are treated as 0 which is consistent with ``COALESCE`` of the PostgreSQL implementation
+    pub(crate) fn to_es_agg(&self, field: &str) -> serde_json::Value {
+        match self {
+            SearchQueryAggFunKind::Sum => json!({"sum": {"field": field, "missing": 0}}),
+            SearchQueryAggFunKind::Avg => json!({"avg": {"field": field, "missing": 0}}),
+            SearchQueryAggFunKind::Max => json!({"max": {"field": field, "missing": 0}}),
+            SearchQueryAggFunKind::Min => json!({"min": {"field": field, "missing": 0}}),
+            SearchQueryAggFunKind::Count => json!({"value_count": {"field": field}}),
+        }
+    }
 }
 
 impl TryGetable for SearchQueryAggFunKind {
@@ -376,7 +387,7 @@ impl SearchQueryTimeWindowKind {
                 LPAD(date_part('day', timezone('UTC', {column_name}::timestamp))::text, 2, '0'))"
                 ),
                 SearchQueryTimeWindowKind::Week => format!(
-                    "CONCAT(date_part('year', timezone('UTC', {column_name}::timestamp)), ' ',
+                    "CONCAT(date_part('isoyear', timezone('UTC', {column_name}::timestamp)), ' ',
                     date_part('week', timezone('UTC', {column_name}::timestamp)))"
                 ),
                 // SearchQueryTimeWindowKind::Month => format!("date_part('month',timezone('UTC', {column_name}))"),
@@ -404,7 +415,7 @@ impl SearchQueryTimeWindowKind {
                 LPAD(date_part('day', timezone('UTC', {column_name}::timestamp))::text, 2, '0'))"
                 ),
                 SearchQueryTimeWindowKind::Week => format!(
-                    "CONCAT(date_part('year', timezone('UTC', {column_name}::timestamp)), ' ',
+                    "CONCAT(date_part('isoyear', timezone('UTC', {column_name}::timestamp)), ' ',
                     date_part('week', timezone('UTC', {column_name}::timestamp)))"
                 ),
                 SearchQueryTimeWindowKind::Month => {
@@ -414,6 +425,41 @@ impl SearchQueryTimeWindowKind {
             }
         }
     }
+
+    /// Calendar interval of the Elasticsearch ``date_histogram`` aggregation
+    pub(crate) fn to_es_calendar_interval(&self) -> &'static str {
+        match self {
+            SearchQueryTimeWindowKind::Date | SearchQueryTimeWindowKind::Day => "day",
+            SearchQueryTimeWindowKind::Hour => "hour",
+            SearchQueryTimeWindowKind::Week => "week",
+            SearchQueryTimeWindowKind::Month => "month",
+            SearchQueryTimeWindowKind::Year => "year",
+        }
+    }
+
+    /// Date format and rounding unit of the Elasticsearch ``range`` query,
+    /// the format is the same as the result of [`SearchQueryTimeWindowKind::to_sql`]
+    pub(crate) fn to_es_range_format(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            SearchQueryTimeWindowKind::Date | SearchQueryTimeWindowKind::Day => Some(("yyyy-MM-dd", "d")),
+            SearchQueryTimeWindowKind::Hour => Some(("yyyy-MM-dd HH", "h")),
+            SearchQueryTimeWindowKind::Month => Some(("yyyy-MM", "M")),
+            SearchQueryTimeWindowKind::Year => Some(("yyyy", "y")),
+            // The ISO week number cannot be parsed by the Elasticsearch date format
+            SearchQueryTimeWindowKind::Week => None,
+        }
+    }
+
+    /// Format the key of the Elasticsearch ``date_histogram`` bucket, the result is the same as [`SearchQueryTimeWindowKind::to_sql`]
+    pub(crate) fn format_es_bucket_key(&self, time: &DateTime<Utc>) -> String {
+        match self {
+            SearchQueryTimeWindowKind::Date | SearchQueryTimeWindowKind::Day => time.format("%Y-%m-%d").to_string(),
+            SearchQueryTimeWindowKind::Hour => time.format("%Y-%m-%d %H").to_string(),
+            SearchQueryTimeWindowKind::Week => format!("{} {}", time.iso_week().year(), time.iso_week().week()),
+            SearchQueryTimeWindowKind::Month => time.format("%Y-%m").to_string(),
+            SearchQueryTimeWindowKind::Year => time.format("%Y").to_string(),
+        }
+    }
 }
 
 impl TryGetable for SearchQueryTimeWindowKind {
@@ -28,6 +28,8 @@ async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()>
     spi_initializer::add_kind(spi_constants::SPI_PG_KIND_CODE, funs, ctx).await?;
     #[cfg(feature = "spi-es")]
     spi_initializer::add_kind(spi_constants::SPI_ES_KIND_CODE, funs, ctx).await?;
+    #[cfg(feature = "spi-memory")]
+    spi_initializer::add_kind(spi_constants::SPI_MEMORY_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
@@ -43,6 +45,8 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
         spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
         #[cfg(feature = "spi-es")]
         spi_constants::SPI_ES_KIND_CODE => serv::es::search_es_initializer::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => serv::memory::search_memory_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }?;
     info!("[BIOS.Search] Fun [{}]({}) initialized", bs_cert.kind_code, bs_cert.conn_uri);
@@ -1,3 +1,5 @@
 pub mod es;
+#[cfg(feature = "spi-memory")]
+pub mod memory;
 pub mod pg;
 pub mod search_item_serv;
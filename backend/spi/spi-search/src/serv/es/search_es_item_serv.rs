@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
 
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, TimeZone, Utc},
     search::search_client::TardisSearchClient,
-    serde_json::{self, json, Value},
+    serde_json::{self, json, Map, Value},
     web::web_resp::TardisPage,
     TardisFuns, TardisFunsInst,
 };
@@ -13,13 +14,27 @@ use bios_basic::{
     spi::{spi_funs::SpiBsInst, spi_initializer::common},
 };
 
-use crate::dto::search_item_dto::{
-    SearchItemAddReq, SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchQScopeKind, SearchItemSearchReq,
-    SearchItemSearchResp, SearchQueryMetricsReq, SearchQueryMetricsResp,
+use crate::{
+    dto::search_item_dto::{
+        AdvSearchItemQueryReq, SearchItemAddReq, SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchQScopeKind,
+        SearchItemSearchReq, SearchItemSearchResp, SearchItemSuggestReq, SearchItemSuggestResp, SearchQueryMetricsReq, SearchQueryMetricsResp, SearchQueryMetricsWhereReq,
+    },
+    search_enumeration::{SearchDataTypeKind, SearchQueryTimeWindowKind},
+    serv::pg::search_pg_item_serv::{package_groups, FUNCTION_EXT_SUFFIX_FLAG, FUNCTION_SUFFIX_FLAG},
 };
 
 use super::search_es_initializer;
 
+const AGG_GROUP_PREFIX: &str = "group_";
+const AGG_MISSING_SUFFIX: &str = "_missing";
+const AGG_GROUP_AGG: &str = "_group_agg";
+/// The maximum number of the records returned by the `top_hits` aggregation, limited by the `index.max_inner_result_window` of Elasticsearch
+const AGG_GROUP_AGG_MAX_SIZE: u32 = 100;
+/// The maximum number of the buckets of each dimension
+const AGG_GROUP_MAX_SIZE: u32 = 10000;
+/// Consistent with the PostgreSQL implementation, records without the dimension value are grouped into this value
+const EMPTY_DIMENSION_VALUE: &str = "\"empty\"";
+
 fn format_index(req_index: &str, ext: &HashMap<String, String>) -> String {
     if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
         format!("{key_prefix}{req_index}")
@@ -106,6 +121,7 @@ pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, ctx: &Ta
                 fetch_total: false,
             },
             adv_query: None,
+            highlight: None,
         },
         funs,
         ctx,
@@ -151,6 +167,7 @@ pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq,
             fetch_total: false,
         },
         adv_query: None,
+        highlight: None,
     })?;
     let mut search_result = client.raw_search(&index, &q, Some(1), Some(0), None).await?;
     let id = search_result.hits.hits.pop().ok_or_else(|| funs.err().conflict("search_es_item_serv", "modify", "not found record", "404-not-found-record"))?._id.clone();
@@ -239,6 +256,7 @@ pub async fn delete(tag: &str, key: &str, funs: &TardisFunsInst, _ctx: &TardisCo
             fetch_total: false,
         },
         adv_query: None,
+        highlight: None,
     })?;
     client.delete_by_query(&index, &q).await?;
 
@@ -272,6 +290,7 @@ pub async fn delete_by_ownership(tag: &str, onw_paths: &str, funs: &TardisFunsIn
             fetch_total: false,
         },
         adv_query: None,
+        highlight: None,
     })?;
     client.delete_by_query(&index, &q).await?;
 
@@ -279,12 +298,33 @@ pub async fn delete_by_ownership(tag: &str, onw_paths: &str, funs: &TardisFunsIn
 }
 
 pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<SearchItemSearchResp>> {
-    let q = gen_query_dsl(search_req)?;
-    let mut track_scores = None;
+    let mut q = TardisFuns::json.str_to_json(&gen_query_dsl(search_req)?)?;
     if let Some(sorts) = &search_req.sort {
         if sorts.iter().any(|sort| sort.field == "rank_title" || sort.field == "rank_content") {
-            track_scores = Some(true);
+            q["track_scores"] = json!(true);
+        }
+    }
+    q["size"] = json!(search_req.page.size);
+    q["from"] = json!((search_req.page.number - 1) * search_req.page.size as u32);
+    if search_req.page.fetch_total {
+        q["track_total_hits"] = json!(true);
+    }
+    let mut highlight_fields = vec![];
+    if let (Some(highlight), Some(_)) = (&search_req.highlight, &search_req.query.q) {
+        highlight_fields = highlight.get_fields(search_req.query.q_scope.as_ref())?;
+        let mut highlight_field_q = Map::new();
+        for field in &highlight_fields {
+            let mut field_q = json!({"number_of_fragments": highlight.get_number_of_fragments()});
+            if let Some(fragment_size) = highlight.fragment_size {
+                field_q["fragment_size"] = json!(fragment_size);
+            }
+            highlight_field_q.insert(field.clone(), field_q);
         }
+        q["highlight"] = json!({
+            "pre_tags": [highlight.get_pre_tag()],
+            "post_tags": [highlight.get_post_tag()],
+            "fields": highlight_field_q,
+        });
     }
     let (client, ext, _) = inst.inst::<TardisSearchClient>();
     let index = format_index(&search_req.tag, ext);
@@ -292,26 +332,21 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
         return Err(funs.err().bad_request("search_es_item_serv", "add", "index not exist", "400-search-index-not-exist"));
     }
 
-    let result = client
-        .raw_search(
-            &index,
-            &q,
-            Some(search_req.page.size as i32),
-            Some(((search_req.page.number - 1) * search_req.page.size as u32) as i32),
-            track_scores,
-        )
-        .await?;
+    let result = raw_search_json(client, &index, &q, "search", funs).await?;
 
     let mut total_size: i64 = 0;
     if search_req.page.fetch_total && total_size == 0 {
-        total_size = result.hits.total.value as i64;
+        total_size = result.pointer("/hits/total/value").and_then(Value::as_i64).unwrap_or_default();
     }
+    let empty_hits = vec![];
     let records = result
-        .hits
-        .hits
+        .pointer("/hits/hits")
+        .and_then(Value::as_array)
+        .unwrap_or(&empty_hits)
         .iter()
         .map(|raw_item| {
-            if let Ok(item) = TardisFuns::json.str_to_obj::<SearchItemAddReq>(&raw_item._source.clone().to_string()) {
+            if let Ok(item) = TardisFuns::json.json_to_obj::<SearchItemAddReq>(raw_item.get("_source").cloned().unwrap_or_default()) {
+                let score = raw_item.get("_score").and_then(Value::as_f64).unwrap_or_default() as f32;
                 Ok(SearchItemSearchResp {
                     kind: item.kind.clone(),
                     key: item.key.to_string(),
@@ -322,8 +357,14 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
                     create_time: item.create_time.unwrap_or_default(),
                     update_time: item.update_time.unwrap_or_default(),
                     ext: item.ext.unwrap_or_default(),
-                    rank_title: raw_item._score.unwrap_or_default(),
-                    rank_content: raw_item._score.unwrap_or_default(),
+                    rank_title: score,
+                    rank_content: score,
+                    highlight: if highlight_fields.is_empty() {
+                        None
+                    } else {
+                        let highlight = raw_item.get("highlight").cloned().unwrap_or_else(|| json!({}));
+                        Some(TardisFuns::json.json_to_obj::<HashMap<String, Vec<String>>>(highlight)?)
+                    },
                 })
             } else {
                 Err(funs.err().format_error("search_es_item_serv", "search", "search result format error", "500-result-format-error"))
@@ -338,57 +379,156 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
     })
 }
 
+pub async fn suggest(suggest_req: &SearchItemSuggestReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<SearchItemSuggestResp>> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = format_index(&suggest_req.tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Err(funs.err().bad_request("search_es_item_serv", "suggest", "index not exist", "400-search-index-not-exist"));
+    }
+    let q = suggest_req.q.trim();
+    // Titles with the prefix come first, followed by the similar titles
+    let mut should_q = vec![json!({"match_phrase_prefix": {"title": {"query": q, "boost": 2}}})];
+    if suggest_req.fuzzy.unwrap_or(false) {
+        should_q.push(json!({"match": {"title": {"query": q, "fuzziness": "AUTO"}}}));
+    }
+    let filter_q = gen_query_bool(
+        &suggest_req.ctx,
+        &SearchItemQueryReq {
+            kinds: suggest_req.kinds.clone(),
+            ..Default::default()
+        },
+        &None,
+    )?;
+    let q = json!({
+        "size": suggest_req.size.unwrap_or(10),
+        "_source": ["kind", "key", "title"],
+        "query": {
+            "bool": {
+                "filter": [filter_q],
+                "should": should_q,
+                "minimum_should_match": 1,
+            }
+        },
+    });
+    let result = raw_search_json(client, &index, &q, "suggest", funs).await?;
+    let empty_hits = vec![];
+    Ok(result
+        .pointer("/hits/hits")
+        .and_then(Value::as_array)
+        .unwrap_or(&empty_hits)
+        .iter()
+        .map(|raw_item| {
+            let source = raw_item.get("_source").unwrap_or(&Value::Null);
+            SearchItemSuggestResp {
+                kind: source.get("kind").and_then(Value::as_str).unwrap_or_default().to_string(),
+                key: source.get("key").and_then(Value::as_str).unwrap_or_default().to_string(),
+                title: source.get("title").and_then(Value::as_str).unwrap_or_default().to_string(),
+                rank: raw_item.get("_score").and_then(Value::as_f64).unwrap_or_default() as f32,
+            }
+        })
+        .collect())
+}
+
+/// Send the search request directly to get the parts of the response not provided by [`TardisSearchClient::raw_search`], such as aggregations and highlights
+async fn raw_search_json(client: &TardisSearchClient, index: &str, q: &Value, op: &str, funs: &TardisFunsInst) -> TardisResult<Value> {
+    let resp = client
+        .client
+        .post_str_to_str(
+            &format!("{}/{}/_search", client.server_url, index),
+            &q.to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    if resp.code != 200 {
+        return Err(funs.err().internal_error(
+            "search_es_item_serv",
+            op,
+            &format!("search error: {}", resp.body.unwrap_or_default()),
+            "500-search-request-error",
+        ));
+    }
+    TardisFuns::json.str_to_json(&resp.body.unwrap_or_default())
+}
+
 fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
+    let mut sort_q = vec![];
+    if let Some(sorts) = &search_req.sort {
+        for sort_item in sorts {
+            if sort_item.field.to_lowercase() == "key"
+                || sort_item.field.to_lowercase() == "title"
+                || sort_item.field.to_lowercase() == "owner"
+                || sort_item.field.to_lowercase() == "own_paths"
+                || sort_item.field.to_lowercase() == "create_time"
+                || sort_item.field.to_lowercase() == "update_time"
+            {
+                sort_q.push(json!({sort_item.field.clone(): { "order": sort_item.order.to_sql() }}));
+            } else if sort_item.field.to_lowercase() == "rank_title" || sort_item.field.to_lowercase() == "rank_content" {
+                sort_q.push(json!({"_score": { "order": sort_item.order.to_sql() }}));
+            } else {
+                let sort_ket = format!("ext.{}", sort_item.field.clone());
+                sort_q.push(json!({sort_ket: { "order": sort_item.order.to_sql() }}));
+            }
+        }
+    } else {
+        sort_q.push(json!({"create_time": { "order": "asc", "unmapped_type": "date"}}));
+    }
+    let q = json!({
+        "query": gen_query_bool(&search_req.ctx, &search_req.query, &search_req.adv_query)?,
+        "sort": sort_q,
+    });
+    Ok(q.to_string())
+}
+
+fn gen_query_bool(ctx: &SearchItemSearchCtxReq, query: &SearchItemQueryReq, adv_query: &Option<Vec<AdvSearchItemQueryReq>>) -> TardisResult<Value> {
     let mut must_q = vec![];
     let mut must_not_q = vec![];
     let mut should_q = vec![];
     let mut filter_q = vec![];
-    let mut sort_q = vec![];
 
     // ctx
     let mut ctx_q = vec![];
-    if let Some(accounts) = &search_req.ctx.accounts {
+    if let Some(accounts) = &ctx.accounts {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.accounts": accounts
             }
         }));
     }
-    if let Some(apps) = &search_req.ctx.apps {
+    if let Some(apps) = &ctx.apps {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.apps": apps
             }
         }));
     }
-    if let Some(tenants) = &search_req.ctx.tenants {
+    if let Some(tenants) = &ctx.tenants {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.tenants": tenants
             }
         }));
     }
-    if let Some(roles) = &search_req.ctx.roles {
+    if let Some(roles) = &ctx.roles {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.roles": roles
             }
         }));
     }
-    if let Some(groups) = &search_req.ctx.groups {
+    if let Some(groups) = &ctx.groups {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.groups": groups
             }
         }));
     }
-    if search_req.ctx.cond_by_or.unwrap_or(false) {
+    if ctx.cond_by_or.unwrap_or(false) {
         should_q.append(&mut ctx_q);
     } else {
         must_q.append(&mut ctx_q);
     }
     // query
-    if let Some(q) = &search_req.query.q {
+    if let Some(q) = &query.q {
         let q = q
             .chars()
             // Fixed like `syntax error in tsquery: "吴 林"`
@@ -400,7 +540,7 @@ fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
                 _ => c,
             })
             .collect::<String>();
-        match search_req.query.q_scope.as_ref().unwrap_or(&SearchItemSearchQScopeKind::Title) {
+        match query.q_scope.as_ref().unwrap_or(&SearchItemSearchQScopeKind::Title) {
             SearchItemSearchQScopeKind::Title => {
                 let q_q = if q.contains('|') {
                     let mut q_q_should = vec![];
@@ -495,45 +635,45 @@ fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
             }
         }
     }
-    if let Some(kinds) = &search_req.query.kinds {
+    if let Some(kinds) = &query.kinds {
         must_q.push(json!({
             "terms": {
                 "kind": kinds.clone()
             }
         }));
     }
-    if let Some(keys) = &search_req.query.keys {
+    if let Some(keys) = &query.keys {
         must_q.push(json!({
             "terms": {
                 "key": keys.clone()
             }
         }));
     }
-    if let Some(owners) = &search_req.query.owners {
+    if let Some(owners) = &query.owners {
         must_q.push(json!({
             "terms": {
                 "owner": owners.clone()
             }
         }));
     }
-    if let Some(own_paths) = &search_req.query.own_paths {
+    if let Some(own_paths) = &query.own_paths {
         must_q.push(json!({
             "terms": {
                 "own_paths": own_paths.clone()
             }
         }));
     }
-    if let (Some(create_time_start), Some(create_time_end)) = (&search_req.query.create_time_start, &search_req.query.create_time_end) {
+    if let (Some(create_time_start), Some(create_time_end)) = (&query.create_time_start, &query.create_time_end) {
         filter_q.push(json!({
             "range": {"create_time": {"gte": create_time_start, "lt": create_time_end}},
         }));
     }
-    if let (Some(update_time_start), Some(update_time_end)) = (&search_req.query.update_time_start, &search_req.query.update_time_end) {
+    if let (Some(update_time_start), Some(update_time_end)) = (&query.update_time_start, &query.update_time_end) {
         filter_q.push(json!({
             "range": {"update_time": {"gte": update_time_start, "lt": update_time_end}},
         }));
     }
-    if let Some(ext) = &search_req.query.ext {
+    if let Some(ext) = &query.ext {
         for cond_info in ext {
             let field = format!("ext.{}", cond_info.field.clone());
             match cond_info.op {
@@ -633,7 +773,7 @@ fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
         }
     }
 
-    if let Some(adv_query) = &search_req.adv_query {
+    if let Some(adv_query) = adv_query {
         let mut adv_query_must_q = vec![];
         let mut adv_query_should_q = vec![];
         for group_query in adv_query {
@@ -644,132 +784,7 @@ fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
                 } else {
                     cond_info.field.clone()
                 };
-                match cond_info.op {
-                    BasicQueryOpKind::Eq => {
-                        group_query_q.push(json!({
-                            "term": {field: cond_info.value.clone()}
-                        }));
-                    }
-                    BasicQueryOpKind::Ne => {
-                        group_query_q.push(json!({
-                            "bool": {
-                                "must_not": {
-                                    "term": { field: cond_info.value.clone()}
-                                }
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::Gt => {
-                        group_query_q.push(json!({
-                            "bool": {
-                                "filter": {
-                                    "range": {field: {"gt": cond_info.value.clone()}},
-                                }
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::Ge => {
-                        group_query_q.push(json!({
-                            "bool": {
-                                "filter": {
-                                    "range": {field: {"gte": cond_info.value.clone()}},
-                                }
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::Lt => {
-                        group_query_q.push(json!({
-                            "bool": {
-                                "filter": {
-                                    "range": {field: {"lt": cond_info.value.clone()}},
-                                }
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::Le => {
-                        group_query_q.push(json!({
-                            "bool": {
-                                "filter": {
-                                    "range": {field: {"lte": cond_info.value.clone()}},
-                                }
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::Like | BasicQueryOpKind::LLike | BasicQueryOpKind::RLike => {
-                        group_query_q.push(json!({
-                            "match": {field: cond_info.value.clone()}
-                        }));
-                    }
-                    BasicQueryOpKind::NotLike | BasicQueryOpKind::NotLLike | BasicQueryOpKind::NotRLike => {
-                        group_query_q.push(json!({
-                            "bool": {
-                                "must_not": {
-                                    "match": { field: cond_info.value.clone()}
-                                }
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::In => {
-                        let value = if cond_info.value.is_array() {
-                            cond_info.value.clone()
-                        } else {
-                            json!(vec![cond_info.value.clone()])
-                        };
-                        group_query_q.push(json!({
-                            "terms": {
-                                field: value
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::NotIn => {
-                        let value = if cond_info.value.is_array() {
-                            cond_info.value.clone()
-                        } else {
-                            json!(vec![cond_info.value.clone()])
-                        };
-                        group_query_q.push(json!({
-                            "bool": {
-                                "must_not": {
-                                    "terms": { field: value}
-                                }
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::IsNull => {
-                        group_query_q.push(json!({
-                            "bool": {
-                                "must_not": {
-                                    "exists": {"field": field}
-                                }
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::IsNotNull => {
-                        group_query_q.push(json!({
-                            "exists": {"field": field}
-                        }));
-                    }
-                    BasicQueryOpKind::IsNullOrEmpty => {
-                        group_query_q.push(json!({
-                            "bool": {
-                                "should": [
-                                    {"term": {field.clone(): "".to_string()}},
-                                    {"bool": {
-                                        "must_not": [{
-                                            "exists": {"field": field}
-                                        }],
-                                    }}
-                                ]
-                            }
-                        }));
-                    }
-                    BasicQueryOpKind::Len => {
-                        return Err(TardisError {
-                            code: "500-not-supports".to_owned(),
-                            message: "search_es_item_serv len op not supports".to_owned(),
-                        })
-                    }
-                }
+                group_query_q.push(gen_cond_q(field, &cond_info.op, &cond_info.value)?);
             }
             match group_query.group_by_or.unwrap_or(false) {
                 true => {
@@ -797,38 +812,111 @@ fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
                 }
         }));
     }
-    if let Some(sorts) = &search_req.sort {
-        for sort_item in sorts {
-            if sort_item.field.to_lowercase() == "key"
-                || sort_item.field.to_lowercase() == "title"
-                || sort_item.field.to_lowercase() == "owner"
-                || sort_item.field.to_lowercase() == "own_paths"
-                || sort_item.field.to_lowercase() == "create_time"
-                || sort_item.field.to_lowercase() == "update_time"
-            {
-                sort_q.push(json!({sort_item.field.clone(): { "order": sort_item.order.to_sql() }}));
-            } else if sort_item.field.to_lowercase() == "rank_title" || sort_item.field.to_lowercase() == "rank_content" {
-                sort_q.push(json!({"_score": { "order": sort_item.order.to_sql() }}));
-            } else {
-                let sort_ket = format!("ext.{}", sort_item.field.clone());
-                sort_q.push(json!({sort_ket: { "order": sort_item.order.to_sql() }}));
+    Ok(json!({
+        "bool": {
+            "must":must_q,
+            "must_not":must_not_q,
+            "should":should_q,
+            "filter": filter_q,
+        }
+    }))
+}
+
+fn gen_cond_q(field: String, op: &BasicQueryOpKind, value: &Value) -> TardisResult<Value> {
+    match op {
+        BasicQueryOpKind::Eq => Ok(json!({
+            "term": {field: value.clone()}
+        })),
+        BasicQueryOpKind::Ne => Ok(json!({
+            "bool": {
+                "must_not": {
+                    "term": { field: value.clone()}
+                }
+            }
+        })),
+        BasicQueryOpKind::Gt => Ok(json!({
+            "bool": {
+                "filter": {
+                    "range": {field: {"gt": value.clone()}},
+                }
             }
+        })),
+        BasicQueryOpKind::Ge => Ok(json!({
+            "bool": {
+                "filter": {
+                    "range": {field: {"gte": value.clone()}},
+                }
+            }
+        })),
+        BasicQueryOpKind::Lt => Ok(json!({
+            "bool": {
+                "filter": {
+                    "range": {field: {"lt": value.clone()}},
+                }
+            }
+        })),
+        BasicQueryOpKind::Le => Ok(json!({
+            "bool": {
+                "filter": {
+                    "range": {field: {"lte": value.clone()}},
+                }
+            }
+        })),
+        BasicQueryOpKind::Like | BasicQueryOpKind::LLike | BasicQueryOpKind::RLike => Ok(json!({
+            "match": {field: value.clone()}
+        })),
+        BasicQueryOpKind::NotLike | BasicQueryOpKind::NotLLike | BasicQueryOpKind::NotRLike => Ok(json!({
+            "bool": {
+                "must_not": {
+                    "match": { field: value.clone()}
+                }
+            }
+        })),
+        BasicQueryOpKind::In => {
+            let value = if value.is_array() { value.clone() } else { json!(vec![value.clone()]) };
+            Ok(json!({
+                "terms": {
+                    field: value
+                }
+            }))
         }
-    } else {
-        sort_q.push(json!({"create_time": { "order": "asc", "unmapped_type": "date"}}));
-    }
-    let q = json!({
-        "query": {
+        BasicQueryOpKind::NotIn => {
+            let value = if value.is_array() { value.clone() } else { json!(vec![value.clone()]) };
+            Ok(json!({
+                "bool": {
+                    "must_not": {
+                        "terms": { field: value}
+                    }
+                }
+            }))
+        }
+        BasicQueryOpKind::IsNull => Ok(json!({
             "bool": {
-                "must":must_q,
-                "must_not":must_not_q,
-                "should":should_q,
-                "filter": filter_q,
+                "must_not": {
+                    "exists": {"field": field}
+                }
             }
-        },
-        "sort": sort_q,
-    });
-    Ok(q.to_string())
+        })),
+        BasicQueryOpKind::IsNotNull => Ok(json!({
+            "exists": {"field": field}
+        })),
+        BasicQueryOpKind::IsNullOrEmpty => Ok(json!({
+            "bool": {
+                "should": [
+                    {"term": {field.clone(): "".to_string()}},
+                    {"bool": {
+                        "must_not": [{
+                            "exists": {"field": field}
+                        }],
+                    }}
+                ]
+            }
+        })),
+        BasicQueryOpKind::Len => Err(TardisError {
+            code: "500-not-supports".to_owned(),
+            message: "search_es_item_serv len op not supports".to_owned(),
+        }),
+    }
 }
 
 fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
@@ -844,10 +932,437 @@ fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
     }
 }
 
-pub async fn query_metrics(_query_req: &SearchQueryMetricsReq, funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<SearchQueryMetricsResp> {
-    Err(funs.err().format_error("search_es_item_serv", "query_metrics", "not supports", "500-not-supports"))
+pub async fn query_metrics(query_req: &SearchQueryMetricsReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchQueryMetricsResp> {
+    let conf_limit = query_req.conf_limit.unwrap_or(100);
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = format_index(&query_req.tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Err(funs.err().bad_request("search_es_item_serv", "query_metrics", "index not exist", "400-search-index-not-exist"));
+    }
+
+    // Package filter
+    let mut filter_q = vec![gen_query_bool(&query_req.ctx, &query_req.query, &query_req.adv_query)?];
+    if let Some(wheres) = &query_req._where {
+        let mut or_wheres_q = vec![];
+        for or_wheres in wheres {
+            let mut and_wheres_q = vec![];
+            for and_where in or_wheres {
+                // The ISO week number cannot be parsed by the Elasticsearch date format, so it is rejected explicitly instead of being treated as an illegal operation
+                if and_where.time_window == Some(SearchQueryTimeWindowKind::Week) {
+                    return Err(funs.err().bad_request(
+                        "metric",
+                        "query",
+                        &format!("The query column=[{}] with time_window=[week] is not supported by Elasticsearch.", &and_where.code),
+                        "400-spi-search-metric-week-not-supported",
+                    ));
+                }
+                let Some(where_q) = gen_metrics_where_q(and_where)? else {
+                    return Err(funs.err().not_found(
+                        "metric",
+                        "query",
+                        &format!(
+                            "The query column=[{}] type=[{}] operation=[{}] time_window=[{}] multi_values=[{}] is not legal.",
+                            &and_where.code,
+                            and_where.data_type.to_string().to_lowercase(),
+                            &and_where.op.to_sql(),
+                            &and_where.time_window.is_some(),
+                            and_where.multi_values.unwrap_or_default()
+                        ),
+                        "404-spi-stats-metric-op-not-legal",
+                    ));
+                };
+                and_wheres_q.push(where_q);
+            }
+            or_wheres_q.push(json!({"bool": {"must": and_wheres_q}}));
+        }
+        if !or_wheres_q.is_empty() {
+            filter_q.push(json!({"bool": {"should": or_wheres_q, "minimum_should_match": 1}}));
+        }
+    }
+
+    // Consistent with the PostgreSQL implementation, only the first `conf_limit` records (sorted by dimension order) are counted
+    let sort_q = if let Some(orders) = &query_req.dimension_order {
+        orders
+            .iter()
+            .map(|order| json!({metrics_field(order.in_ext, &order.code): {"order": if order.asc { "asc" } else { "desc" }, "unmapped_type": "keyword"}}))
+            .collect::<Vec<_>>()
+    } else {
+        vec![json!({"create_time": {"order": "desc", "unmapped_type": "date"}})]
+    };
+    let q = json!({
+        "query": {"bool": {"filter": filter_q}},
+        "sort": sort_q,
+    });
+    let keys = client
+        .raw_search(&index, &q.to_string(), Some(conf_limit as i32), Some(0), None)
+        .await?
+        .hits
+        .hits
+        .iter()
+        .filter_map(|raw_item| raw_item._source.get("key").cloned())
+        .collect::<Vec<_>>();
+
+    // Package measures
+    // (alias name, aggregation)
+    let mut measure_aggs = Map::new();
+    let mut select_measure_keys = vec![];
+    for select in &query_req.select {
+        let alias_name = metrics_alias(select.in_ext, &select.code, &select.fun.to_string().to_lowercase());
+        measure_aggs.insert(alias_name.clone(), select.fun.to_es_agg(&metrics_field(select.in_ext, &select.code)));
+        select_measure_keys.push(alias_name);
+    }
+    let mut havings = vec![];
+    if let Some(having_reqs) = &query_req.having {
+        for having in having_reqs {
+            let (Some(op), Some(value)) = (having_op_to_es(&having.op), having.value.as_f64()) else {
+                return Err(funs.err().not_found(
+                    "metric",
+                    "query",
+                    &format!(
+                        "The query column=[{}] type=[{}] operation=[{}] fun=[{}] is not legal.",
+                        &having.code,
+                        having.data_type.to_string().to_lowercase(),
+                        &having.op.to_sql(),
+                        &having.fun.to_string().to_lowercase()
+                    ),
+                    "404-spi-stats-metric-op-not-legal",
+                ));
+            };
+            let alias_name = metrics_alias(having.in_ext, &having.code, &having.fun.to_string().to_lowercase());
+            measure_aggs.entry(alias_name.clone()).or_insert_with(|| having.fun.to_es_agg(&metrics_field(having.in_ext, &having.code)));
+            havings.push((alias_name, op, value));
+        }
+    }
+    let ignore_group_agg = query_req.group.is_empty() || !query_req.group_agg.unwrap_or(false);
+    if !ignore_group_agg {
+        measure_aggs.insert(
+            AGG_GROUP_AGG.to_string(),
+            json!({
+                "top_hits": {
+                    "size": conf_limit.min(AGG_GROUP_AGG_MAX_SIZE),
+                    "_source": ["key", "own_paths", "create_time"],
+                    "sort": [{"create_time": {"order": "desc", "unmapped_type": "date"}}]
+                }
+            }),
+        );
+    }
+
+    // Package group
+    // Each dimension is a nested bucket aggregation, and each level carries the measures, so the rollup is naturally supported
+    let mut select_dimension_keys = vec![];
+    for group in &query_req.group {
+        if group.time_window.is_some() && group.data_type != SearchDataTypeKind::Date && group.data_type != SearchDataTypeKind::DateTime {
+            return Err(funs.err().not_found(
+                "metric",
+                "query",
+                &format!(
+                    "The group column=[{}] type=[{}] time_window=[{}] is not legal.",
+                    &group.code,
+                    group.data_type.to_string().to_lowercase(),
+                    &group.time_window.is_some(),
+                ),
+                "404-spi-stats-metric-op-not-legal",
+            ));
+        }
+        select_dimension_keys.push(metrics_alias(
+            group.in_ext,
+            &group.code,
+            &group.time_window.as_ref().map(|i| i.to_string().to_lowercase()).unwrap_or("".to_string()),
+        ));
+    }
+    let mut aggs = measure_aggs.clone();
+    for (idx, group) in query_req.group.iter().enumerate().rev() {
+        let field = metrics_field(group.in_ext, &group.code);
+        let mut bucket_agg = if let Some(time_window) = &group.time_window {
+            json!({
+                "date_histogram": {
+                    "field": field,
+                    "calendar_interval": time_window.to_es_calendar_interval(),
+                    "time_zone": "UTC",
+                    "min_doc_count": 1
+                }
+            })
+        } else {
+            json!({
+                "terms": {
+                    "field": field,
+                    "size": AGG_GROUP_MAX_SIZE
+                }
+            })
+        };
+        // Records without the dimension value are grouped into `"empty"`, consistent with the PostgreSQL implementation
+        let mut missing_agg = json!({
+            "missing": {
+                "field": field
+            }
+        });
+        if !aggs.is_empty() {
+            bucket_agg["aggs"] = Value::Object(aggs.clone());
+            missing_agg["aggs"] = Value::Object(aggs.clone());
+        }
+        aggs = measure_aggs.clone();
+        aggs.insert(format!("{AGG_GROUP_PREFIX}{idx}"), bucket_agg);
+        aggs.insert(format!("{AGG_GROUP_PREFIX}{idx}{AGG_MISSING_SUFFIX}"), missing_agg);
+    }
+
+    let q = json!({
+        "size": 0,
+        "query": {"bool": {"filter": [{"terms": {"key": keys}}]}},
+        "aggs": aggs,
+    });
+    let result = raw_search_json(client, &index, &q, "query_metrics", funs).await?;
+    let Some(root_bucket) = result.get("aggregations") else {
+        return Err(funs.err().format_error("search_es_item_serv", "query_metrics", "search result format error", "500-result-format-error"));
+    };
+
+    // Package rows in the same structure as the PostgreSQL implementation
+    let time_windows = query_req.group.iter().map(|group| group.time_window.clone()).collect::<Vec<_>>();
+    let mut rows = vec![];
+    package_metrics_rows(
+        root_bucket,
+        &select_dimension_keys,
+        &time_windows,
+        &mut vec![],
+        query_req.ignore_group_rollup.unwrap_or(false),
+        ignore_group_agg,
+        &mut rows,
+    );
+
+    // Package having
+    rows.retain(|row| {
+        havings.iter().all(|(alias_name, op, value)| {
+            let Some(row_value) = row.get(alias_name).and_then(Value::as_f64) else {
+                return false;
+            };
+            match *op {
+                "==" => row_value == *value,
+                "!=" => row_value != *value,
+                ">" => row_value > *value,
+                ">=" => row_value >= *value,
+                "<" => row_value < *value,
+                "<=" => row_value <= *value,
+                _ => false,
+            }
+        })
+    });
+
+    // Package metrics or group order
+    let mut orders = vec![];
+    if let Some(group_orders) = &query_req.group_order {
+        for order in group_orders {
+            orders.push((
+                metrics_alias(
+                    order.in_ext,
+                    &order.code,
+                    &order.time_window.as_ref().map(|i| i.to_string().to_lowercase()).unwrap_or("".to_string()),
+                ),
+                order.asc,
+            ));
+        }
+    }
+    if let Some(metrics_orders) = &query_req.metrics_order {
+        for order in metrics_orders {
+            orders.push((metrics_alias(order.in_ext, &order.code, &order.fun.to_string().to_lowercase()), order.asc));
+        }
+    }
+    if !orders.is_empty() {
+        rows.sort_by(|a, b| {
+            orders.iter().fold(Ordering::Equal, |ordering, (alias_name, asc)| {
+                ordering.then_with(|| {
+                    let ordering = cmp_metrics_value(a.get(alias_name).unwrap_or(&Value::Null), b.get(alias_name).unwrap_or(&Value::Null));
+                    if *asc {
+                        ordering
+                    } else {
+                        ordering.reverse()
+                    }
+                })
+            })
+        });
+    }
+    // package limit
+    if let Some(limit) = query_req.limit {
+        rows.truncate(limit as usize);
+    }
+
+    let show_names = select_dimension_keys.iter().chain(select_measure_keys.iter()).map(|alias_name| (alias_name.clone(), alias_name.clone())).collect::<HashMap<String, String>>();
+    Ok(SearchQueryMetricsResp {
+        tag: query_req.tag.to_string(),
+        show_names,
+        group: package_groups(select_dimension_keys, &select_measure_keys, ignore_group_agg, rows)
+            .map_err(|msg| TardisError::internal_error(&format!("Fail to package groups: {msg}"), "500-spi-stats-internal-error"))?,
+    })
+}
+
+fn metrics_field(in_ext: Option<bool>, code: &str) -> String {
+    if in_ext.unwrap_or(true) {
+        format!("ext.{code}")
+    } else {
+        code.to_string()
+    }
+}
+
+/// The alias name is the same as the PostgreSQL implementation, e.g. `status_ext___`, `act_hours_ext___sum`, `create_time__date`
+fn metrics_alias(in_ext: Option<bool>, code: &str, suffix: &str) -> String {
+    format!("{code}{}{FUNCTION_SUFFIX_FLAG}{suffix}", if in_ext.unwrap_or(true) { FUNCTION_EXT_SUFFIX_FLAG } else { "" })
+}
+
+fn gen_metrics_where_q(where_req: &SearchQueryMetricsWhereReq) -> TardisResult<Option<Value>> {
+    if where_req.value.is_null() {
+        return Ok(None);
+    }
+    let field = metrics_field(where_req.in_ext, &where_req.code);
+    let Some(time_window) = &where_req.time_window else {
+        return gen_cond_q(field, &where_req.op, &where_req.value).map(Some);
+    };
+    // Compare with the formatted time window, e.g. `2023-01` for the month window
+    let (Some((format, rounding)), Some(value)) = (time_window.to_es_range_format(), where_req.value.as_str()) else {
+        return Ok(None);
+    };
+    if where_req.data_type != SearchDataTypeKind::Date && where_req.data_type != SearchDataTypeKind::DateTime {
+        return Ok(None);
+    }
+    let value = format!("{value}||/{rounding}");
+    let range_q = |range: Value| json!({"range": {field.clone(): range}});
+    Ok(match where_req.op {
+        BasicQueryOpKind::Eq => Some(range_q(json!({"gte": value, "lte": value, "format": format}))),
+        BasicQueryOpKind::Ne => Some(json!({"bool": {"must_not": range_q(json!({"gte": value, "lte": value, "format": format}))}})),
+        BasicQueryOpKind::Gt => Some(range_q(json!({"gt": value, "format": format}))),
+        BasicQueryOpKind::Ge => Some(range_q(json!({"gte": value, "format": format}))),
+        BasicQueryOpKind::Lt => Some(range_q(json!({"lt": value, "format": format}))),
+        BasicQueryOpKind::Le => Some(range_q(json!({"lte": value, "format": format}))),
+        _ => None,
+    })
 }
 
-pub async fn refresh_tsv(_tag: &str, funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<()> {
-    Err(funs.err().format_error("search_es_item_serv", "refresh_tsv", "not supports", "500-not-supports"))
+fn having_op_to_es(op: &BasicQueryOpKind) -> Option<&'static str> {
+    match op {
+        BasicQueryOpKind::Eq => Some("=="),
+        BasicQueryOpKind::Ne => Some("!="),
+        BasicQueryOpKind::Gt => Some(">"),
+        BasicQueryOpKind::Ge => Some(">="),
+        BasicQueryOpKind::Lt => Some("<"),
+        BasicQueryOpKind::Le => Some("<="),
+        _ => None,
+    }
+}
+
+/// Flatten the nested bucket aggregations into rows like the result of `GROUP BY ROLLUP`,
+/// the dimension values of the rollup rows are `null`
+fn package_metrics_rows(
+    bucket: &Value,
+    select_dimension_keys: &[String],
+    time_windows: &[Option<SearchQueryTimeWindowKind>],
+    dimension_values: &mut Vec<Value>,
+    ignore_group_rollup: bool,
+    ignore_group_agg: bool,
+    rows: &mut Vec<Value>,
+) {
+    let level = dimension_values.len();
+    if level == select_dimension_keys.len() || !ignore_group_rollup {
+        let mut row = Map::new();
+        for (idx, dimension_key) in select_dimension_keys.iter().enumerate() {
+            row.insert(dimension_key.clone(), dimension_values.get(idx).cloned().unwrap_or(Value::Null));
+        }
+        if let Some(bucket) = bucket.as_object() {
+            for (measure_key, agg) in bucket {
+                if let Some(value) = agg.get("value") {
+                    row.insert(measure_key.clone(), normalize_metrics_value(measure_key, value));
+                }
+            }
+        }
+        if !ignore_group_agg {
+            let s_agg = bucket.pointer(&format!("/{AGG_GROUP_AGG}/hits/hits")).and_then(Value::as_array).map(|hits| {
+                hits.iter()
+                    .map(|hit| {
+                        let source = hit.get("_source").unwrap_or(&Value::Null);
+                        let ct = source
+                            .get("create_time")
+                            .and_then(Value::as_str)
+                            .and_then(|ct| DateTime::parse_from_rfc3339(ct).ok())
+                            .map(|ct| ct.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S").to_string())
+                            .unwrap_or_default();
+                        format!(
+                            "{} - {} - {}",
+                            source.get("key").and_then(Value::as_str).unwrap_or_default(),
+                            source.get("own_paths").and_then(Value::as_str).unwrap_or_default(),
+                            ct
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            });
+            row.insert("s_agg".to_string(), s_agg.map(Value::from).unwrap_or(Value::Null));
+        }
+        rows.push(Value::Object(row));
+    }
+    if level == select_dimension_keys.len() {
+        return;
+    }
+    let empty_buckets = vec![];
+    let sub_buckets = bucket.pointer(&format!("/{AGG_GROUP_PREFIX}{level}/buckets")).and_then(Value::as_array).unwrap_or(&empty_buckets);
+    for sub_bucket in sub_buckets {
+        let key = if let Some(time_window) = &time_windows[level] {
+            sub_bucket.get("key").and_then(Value::as_i64).and_then(|key| Utc.timestamp_millis_opt(key).single()).map(|key| time_window.format_es_bucket_key(&key))
+        } else {
+            sub_bucket.get("key_as_string").or_else(|| sub_bucket.get("key")).map(|key| match key {
+                Value::String(key) => key.clone(),
+                key => key.to_string(),
+            })
+        };
+        dimension_values.push(key.map(Value::from).unwrap_or(Value::Null));
+        package_metrics_rows(
+            sub_bucket,
+            select_dimension_keys,
+            time_windows,
+            dimension_values,
+            ignore_group_rollup,
+            ignore_group_agg,
+            rows,
+        );
+        dimension_values.pop();
+    }
+    if let Some(missing_bucket) =
+        bucket.get(format!("{AGG_GROUP_PREFIX}{level}{AGG_MISSING_SUFFIX}")).filter(|missing_bucket| missing_bucket.get("doc_count").and_then(Value::as_u64).unwrap_or(0) > 0)
+    {
+        dimension_values.push(json!(EMPTY_DIMENSION_VALUE));
+        package_metrics_rows(
+            missing_bucket,
+            select_dimension_keys,
+            time_windows,
+            dimension_values,
+            ignore_group_rollup,
+            ignore_group_agg,
+            rows,
+        );
+        dimension_values.pop();
+    }
+}
+
+/// Elasticsearch returns floating point numbers for the `sum`/`max`/`min` aggregations,
+/// convert the integral values to integers to be consistent with the PostgreSQL implementation
+fn normalize_metrics_value(measure_key: &str, value: &Value) -> Value {
+    if measure_key.ends_with(&format!("{FUNCTION_SUFFIX_FLAG}avg")) {
+        return value.clone();
+    }
+    match value.as_f64() {
+        Some(val) if val.fract() == 0.0 && val.abs() < i64::MAX as f64 => json!(val as i64),
+        _ => value.clone(),
+    }
+}
+
+/// Consistent with PostgreSQL, `null` is greater than any other value
+fn cmp_metrics_value(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (a, b) => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+pub async fn refresh_tsv(_tag: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<()> {
+    // The full-text index of Elasticsearch is maintained by itself, there is nothing to refresh
+    Ok(())
 }
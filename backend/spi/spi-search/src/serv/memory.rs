@@ -0,0 +1,2 @@
+pub mod search_memory_initializer;
+pub mod search_memory_item_serv;
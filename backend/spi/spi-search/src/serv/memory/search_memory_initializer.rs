@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    serde_json::Value,
+};
+
+#[derive(Default)]
+pub struct SearchMemoryStore {
+    /// Search items grouped by tag, in insertion order
+    pub(crate) items: HashMap<String, Vec<SearchMemoryItem>>,
+}
+
+#[derive(Clone)]
+pub(crate) struct SearchMemoryItem {
+    pub(crate) kind: String,
+    pub(crate) key: String,
+    pub(crate) title: String,
+    pub(crate) content: String,
+    pub(crate) owner: String,
+    pub(crate) own_paths: String,
+    pub(crate) create_time: DateTime<Utc>,
+    pub(crate) update_time: DateTime<Utc>,
+    pub(crate) ext: Value,
+    pub(crate) visit_keys: Option<Value>,
+}
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    spi_initializer::common_memory::init::<SearchMemoryStore>(bs_cert, ctx, mgr).await
+}
@@ -0,0 +1,799 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use bios_basic::{
+    enumeration::BasicQueryOpKind,
+    spi::{
+        spi_funs::SpiBsInst,
+        spi_initializer::common_memory::{self, SpiMemoryClient},
+    },
+};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, NaiveDate, TimeZone, Utc},
+    serde_json::{self, json, Map, Value},
+    web::web_resp::TardisPage,
+    TardisFunsInst,
+};
+
+use crate::{
+    dto::search_item_dto::{
+        AdvSearchItemQueryReq, SearchItemAddReq, SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchHighlightReq, SearchItemSearchQScopeKind,
+        SearchItemSearchReq, SearchItemSearchResp, SearchItemSearchSortKind, SearchItemSuggestReq, SearchItemSuggestResp, SearchQueryDimensionGroupReq, SearchQueryMetricsReq,
+        SearchQueryMetricsResp, SearchQueryMetricsWhereReq,
+    },
+    search_enumeration::{SearchQueryAggFunKind, SearchQueryTimeWindowKind},
+    serv::pg::search_pg_item_serv::{self, FUNCTION_EXT_SUFFIX_FLAG, FUNCTION_SUFFIX_FLAG},
+};
+
+use super::search_memory_initializer::{SearchMemoryItem, SearchMemoryStore};
+
+/// Default number of characters of each highlighted fragment, consistent with Elasticsearch
+const DEFAULT_HIGHLIGHT_FRAGMENT_SIZE: u16 = 100;
+/// Default similarity threshold of the fuzzy suggestion, consistent with ``pg_trgm.similarity_threshold``
+const SUGGEST_SIMILARITY_THRESHOLD: f32 = 0.3;
+/// Group value of the items without the dimension value, consistent with the PostgreSQL implementation
+const EMPTY_GROUP_VALUE: &str = "\"empty\"";
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<SearchMemoryStore> {
+    inst.inst::<SpiMemoryClient<SearchMemoryStore>>().0
+}
+
+pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let items = store.items.entry(add_req.tag.clone()).or_default();
+    if items.iter().any(|item| item.key == add_req.key.to_string()) {
+        return Err(funs.err().conflict(
+            "item",
+            "add",
+            &format!("search item [{}] already exists in [{}]", add_req.key, add_req.tag),
+            "409-spi-search-item-exist",
+        ));
+    }
+    items.push(SearchMemoryItem {
+        kind: add_req.kind.clone(),
+        key: add_req.key.to_string(),
+        title: add_req.title.clone(),
+        content: add_req.content.clone(),
+        owner: add_req.owner.clone().unwrap_or_default(),
+        own_paths: add_req.own_paths.clone().unwrap_or_default(),
+        create_time: add_req.create_time.unwrap_or_else(Utc::now),
+        update_time: add_req.update_time.unwrap_or_else(Utc::now),
+        ext: add_req.ext.clone().unwrap_or_else(|| json!({})),
+        visit_keys: add_req.visit_keys.as_ref().map(|visit_keys| visit_keys.to_sql()),
+    });
+    Ok(())
+}
+
+pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let item = store
+        .items
+        .get_mut(tag)
+        .and_then(|items| items.iter_mut().find(|item| item.key == key))
+        .ok_or_else(|| funs.err().not_found("item", "modify", &format!("search item [{key}] not found in [{tag}]"), "404-spi-search-item-not-exist"))?;
+    if let Some(kind) = &modify_req.kind {
+        item.kind = kind.clone();
+    }
+    if let Some(title) = &modify_req.title {
+        item.title = title.clone();
+    }
+    if let Some(content) = &modify_req.content {
+        item.content = content.clone();
+    }
+    if let Some(owner) = &modify_req.owner {
+        item.owner = owner.clone();
+    }
+    if let Some(own_paths) = &modify_req.own_paths {
+        item.own_paths = own_paths.clone();
+    }
+    if let Some(create_time) = modify_req.create_time {
+        item.create_time = create_time;
+    }
+    if let Some(update_time) = modify_req.update_time {
+        item.update_time = update_time;
+    }
+    if let Some(ext) = &modify_req.ext {
+        if modify_req.ext_override.unwrap_or(false) {
+            item.ext = ext.clone();
+        } else {
+            merge(&mut item.ext, ext.clone());
+        }
+    }
+    if let Some(visit_keys) = &modify_req.visit_keys {
+        item.visit_keys = Some(visit_keys.to_sql());
+    }
+    Ok(())
+}
+
+fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
+    match (a, b) {
+        (a @ &mut serde_json::Value::Object(_), serde_json::Value::Object(b)) => {
+            if let Some(a) = a.as_object_mut() {
+                for (k, v) in b {
+                    merge(a.entry(k).or_insert(serde_json::Value::Null), v);
+                }
+            }
+        }
+        (a, b) => *a = b,
+    }
+}
+
+pub async fn delete(tag: &str, key: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    if let Some(items) = get_client(inst).write().await.items.get_mut(tag) {
+        items.retain(|item| item.key != key);
+    }
+    Ok(())
+}
+
+pub async fn delete_by_ownership(tag: &str, own_paths: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    if let Some(items) = get_client(inst).write().await.items.get_mut(tag) {
+        items.retain(|item| item.own_paths != own_paths);
+    }
+    Ok(())
+}
+
+/// The fuzzy search is a case-insensitive substring match instead of the full-text search of the PostgreSQL implementation,
+/// so ``rank_title`` and ``rank_content`` are ``1`` when matched.
+pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<SearchItemSearchResp>> {
+    check_query_cond_values(&search_req.query, &search_req.adv_query, funs)?;
+    let q = search_req.query.q.as_ref().map(|q| q.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase());
+    let q_scope = search_req.query.q_scope.as_ref().unwrap_or(&SearchItemSearchQScopeKind::Title);
+    let query = &search_req.query;
+    let highlight_fields = match &search_req.highlight {
+        Some(highlight) if q.is_some() => Some(highlight.get_fields(query.q_scope.as_ref())?),
+        _ => None,
+    };
+
+    let store = get_client(inst).read().await;
+    let mut records = store
+        .items
+        .get(&search_req.tag)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| match &q {
+            Some(q) => match_q(item, q, q_scope).map(|(rank_title, rank_content)| (item, rank_title, rank_content)),
+            None => Some((item, 0.0, 0.0)),
+        })
+        .filter(|(item, _, _)| match_visit_keys(item, &search_req.ctx))
+        .filter(|(item, _, _)| match_query(item, query))
+        .filter(|(item, _, _)| match_adv_query(item, &search_req.adv_query))
+        .collect::<Vec<_>>();
+    if let Some(sort) = &search_req.sort {
+        records.sort_by(|(a, _, _), (b, _, _)| {
+            for sort_item in sort {
+                let (a_value, b_value) = (get_sort_value(a, &sort_item.field), get_sort_value(b, &sort_item.field));
+                // Consistent with PostgreSQL, null values are treated as larger than any non-null value
+                let ordering = match (&a_value, &b_value) {
+                    (Some(a_value), Some(b_value)) => common_memory::compare_value(a_value, b_value).unwrap_or(Ordering::Equal),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                };
+                let ordering = match sort_item.order {
+                    SearchItemSearchSortKind::Asc => ordering,
+                    SearchItemSearchSortKind::Desc => ordering.reverse(),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+    }
+    let total_size = if search_req.page.fetch_total { records.len() as u64 } else { 0 };
+    let records = records
+        .into_iter()
+        .skip(((search_req.page.number.max(1) - 1) * search_req.page.size as u32) as usize)
+        .take(search_req.page.size as usize)
+        .map(|(item, rank_title, rank_content)| SearchItemSearchResp {
+            highlight: search_req.highlight.as_ref().zip(q.as_ref()).zip(highlight_fields.as_ref()).map(|((highlight, q), highlight_fields)| {
+                highlight_fields
+                    .iter()
+                    .map(|field| {
+                        let text = if field == "title" { &item.title } else { &item.content };
+                        (field.to_string(), package_highlight_fragments(text, q, highlight))
+                    })
+                    .filter(|(_, fragments)| !fragments.is_empty())
+                    .collect()
+            }),
+            kind: item.kind.clone(),
+            key: item.key.clone(),
+            title: item.title.clone(),
+            content: if query.in_q_content.unwrap_or(false) { item.content.clone() } else { "".to_string() },
+            owner: item.owner.clone(),
+            own_paths: item.own_paths.clone(),
+            create_time: item.create_time,
+            update_time: item.update_time,
+            ext: item.ext.clone(),
+            rank_title,
+            rank_content,
+        })
+        .collect();
+    Ok(TardisPage {
+        page_size: search_req.page.size as u64,
+        page_number: search_req.page.number as u64,
+        total_size,
+        records,
+    })
+}
+
+/// Titles with the prefix come first, followed by the similar titles (trigram similarity, same as ``pg_trgm``).
+pub async fn suggest(suggest_req: &SearchItemSuggestReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<SearchItemSuggestResp>> {
+    let q = suggest_req.q.trim().to_lowercase();
+    let fuzzy = suggest_req.fuzzy.unwrap_or(false);
+    let store = get_client(inst).read().await;
+    let mut suggestions = store
+        .items
+        .get(&suggest_req.tag)
+        .into_iter()
+        .flatten()
+        .filter(|item| match_visit_keys(item, &suggest_req.ctx))
+        .filter(|item| suggest_req.kinds.as_ref().map(|kinds| kinds.is_empty() || kinds.contains(&item.kind)).unwrap_or(true))
+        .filter_map(|item| {
+            let prefix_matched = item.title.to_lowercase().starts_with(&q);
+            let similarity = trigram_similarity(&item.title, &q);
+            if !prefix_matched && !(fuzzy && similarity >= SUGGEST_SIMILARITY_THRESHOLD) {
+                return None;
+            }
+            Some(SearchItemSuggestResp {
+                kind: item.kind.clone(),
+                key: item.key.clone(),
+                title: item.title.clone(),
+                rank: if prefix_matched { 1.0 } else { 0.0 } + similarity,
+            })
+        })
+        .collect::<Vec<_>>();
+    suggestions.sort_by(|a, b| b.rank.partial_cmp(&a.rank).unwrap_or(Ordering::Equal).then_with(|| a.title.cmp(&b.title)));
+    suggestions.truncate(suggest_req.size.unwrap_or(10) as usize);
+    Ok(suggestions)
+}
+
+/// The semantics are the same as the SQL assembled by the PostgreSQL implementation: filter and limit the items,
+/// then group them (with ``ROLLUP`` unless ``ignore_group_rollup``), aggregate, filter by ``having``, sort and limit the groups.
+///
+/// The keys are unique in each tag of the store, so ``DISTINCT ON (key)`` is implied.
+pub async fn query_metrics(query_req: &SearchQueryMetricsReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchQueryMetricsResp> {
+    check_query_cond_values(&query_req.query, &query_req.adv_query, funs)?;
+    let conf_limit = query_req.conf_limit.unwrap_or(100);
+
+    // Check the legality of the conditions, same as the PostgreSQL implementation
+    for and_where in query_req._where.iter().flatten().flatten() {
+        if and_where
+            .data_type
+            .to_pg_where(
+                and_where.multi_values.unwrap_or(false),
+                &and_where.code,
+                &and_where.op,
+                1,
+                &and_where.value,
+                &and_where.time_window,
+            )?
+            .is_none()
+        {
+            return Err(funs.err().not_found(
+                "metric",
+                "query",
+                &format!(
+                    "The query column=[{}] type=[{}] operation=[{}] time_window=[{}] multi_values=[{}] is not legal.",
+                    &and_where.code,
+                    and_where.data_type.to_string().to_lowercase(),
+                    &and_where.op.to_sql(),
+                    &and_where.time_window.is_some(),
+                    and_where.multi_values.unwrap_or_default()
+                ),
+                "404-spi-stats-metric-op-not-legal",
+            ));
+        }
+    }
+    let mut select_dimension_keys = vec![];
+    for group in &query_req.group {
+        if group.data_type.to_pg_group(&group.code, &group.time_window).is_none() {
+            return Err(funs.err().not_found(
+                "metric",
+                "query",
+                &format!(
+                    "The group column=[{}] type=[{}] time_window=[{}] is not legal.",
+                    &group.code,
+                    group.data_type.to_string().to_lowercase(),
+                    &group.time_window.is_some(),
+                ),
+                "404-spi-stats-metric-op-not-legal",
+            ));
+        }
+        select_dimension_keys.push(metrics_alias(
+            group.in_ext,
+            &group.code,
+            &group.time_window.as_ref().map(|i| i.to_string().to_lowercase()).unwrap_or_default(),
+        ));
+    }
+    let select_measure_keys = query_req.select.iter().map(|select| metrics_alias(select.in_ext, &select.code, &select.fun.to_string().to_lowercase())).collect::<Vec<_>>();
+    for having in query_req.having.iter().flatten() {
+        if having.data_type.to_pg_having(false, &having.code, &having.op, 1, &having.value, Some(&having.fun))?.is_none() {
+            return Err(funs.err().not_found(
+                "metric",
+                "query",
+                &format!(
+                    "The query column=[{}] type=[{}] operation=[{}] fun=[{}] is not legal.",
+                    &having.code,
+                    having.data_type.to_string().to_lowercase(),
+                    &having.op.to_sql(),
+                    &having.fun.to_string().to_lowercase()
+                ),
+                "404-spi-stats-metric-op-not-legal",
+            ));
+        }
+    }
+    // (is group, index of the group or select, asc)
+    let mut orders = vec![];
+    for order in query_req.group_order.iter().flatten() {
+        let group_idx = query_req
+            .group
+            .iter()
+            .position(|group| group.code == order.code && group.in_ext.unwrap_or(true) == order.in_ext.unwrap_or(true) && group.time_window == order.time_window)
+            .ok_or_else(|| order_not_legal_err(&order.code, funs))?;
+        orders.push((true, group_idx, order.asc));
+    }
+    for order in query_req.metrics_order.iter().flatten() {
+        let select_idx = query_req
+            .select
+            .iter()
+            .position(|select| select.code == order.code && select.in_ext.unwrap_or(true) == order.in_ext.unwrap_or(true) && select.fun == order.fun)
+            .ok_or_else(|| order_not_legal_err(&order.code, funs))?;
+        orders.push((false, select_idx, order.asc));
+    }
+
+    // Filter, sort by the dimension order (the latest first by default) and limit the items
+    let q = query_req.query.q.as_ref().map(|q| q.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase());
+    let q_scope = query_req.query.q_scope.as_ref().unwrap_or(&SearchItemSearchQScopeKind::Title);
+    let store = get_client(inst).read().await;
+    let mut items = store
+        .items
+        .get(&query_req.tag)
+        .into_iter()
+        .flatten()
+        .filter(|item| q.as_ref().map(|q| match_q(item, q, q_scope).is_some()).unwrap_or(true))
+        .filter(|item| {
+            query_req
+                ._where
+                .as_ref()
+                .map(|or_wheres| or_wheres.is_empty() || or_wheres.iter().any(|and_wheres| and_wheres.iter().all(|and_where| match_metrics_where(item, and_where))))
+                .unwrap_or(true)
+        })
+        .filter(|item| match_visit_keys(item, &query_req.ctx))
+        .filter(|item| match_query(item, &query_req.query))
+        .filter(|item| match_adv_query(item, &query_req.adv_query))
+        .collect::<Vec<_>>();
+    items.sort_by(|a, b| b.create_time.cmp(&a.create_time));
+    if let Some(dimension_orders) = &query_req.dimension_order {
+        items.sort_by(|a, b| {
+            dimension_orders.iter().fold(Ordering::Equal, |ordering, order| {
+                ordering.then_with(|| {
+                    compare_nullable(
+                        get_metrics_value(a, order.in_ext, &order.code).as_ref(),
+                        get_metrics_value(b, order.in_ext, &order.code).as_ref(),
+                        order.asc,
+                    )
+                })
+            })
+        });
+    }
+    items.truncate(conf_limit as usize);
+
+    // Group, each level of the rollup is a prefix of the group values, the missing values are regarded as ``ROLLUP``
+    let levels = if query_req.group.is_empty() {
+        vec![0]
+    } else if query_req.ignore_group_rollup.unwrap_or(false) {
+        vec![query_req.group.len()]
+    } else {
+        (0..=query_req.group.len()).rev().collect()
+    };
+    let mut group_rows: HashMap<String, (Vec<Option<Value>>, Vec<&SearchMemoryItem>)> = HashMap::new();
+    if levels.contains(&0) {
+        let group_values = vec![None; query_req.group.len()];
+        group_rows.insert(format!("{group_values:?}"), (group_values, vec![]));
+    }
+    for &item in &items {
+        let mut group_values_set: Vec<Vec<Value>> = vec![vec![]];
+        for group in &query_req.group {
+            let values = get_group_values(item, group);
+            group_values_set = group_values_set.into_iter().flat_map(|prefix| values.iter().map(move |value| [prefix.clone(), vec![value.clone()]].concat())).collect();
+        }
+        for group_values in &group_values_set {
+            for level in &levels {
+                let group_values = (0..query_req.group.len()).map(|idx| if idx < *level { Some(group_values[idx].clone()) } else { None }).collect::<Vec<_>>();
+                group_rows.entry(format!("{group_values:?}")).or_insert_with(|| (group_values, vec![])).1.push(item);
+            }
+        }
+    }
+
+    // Aggregate and filter by having
+    let mut rows = group_rows
+        .into_values()
+        .map(|(group_values, items)| {
+            let measure_values =
+                query_req.select.iter().map(|select| aggregate(items.iter().map(|item| get_metrics_value(item, select.in_ext, &select.code)), &select.fun)).collect::<Vec<_>>();
+            (group_values, measure_values, items)
+        })
+        .filter(|(_, _, items)| {
+            query_req.having.iter().flatten().all(|having| {
+                aggregate(items.iter().map(|item| get_metrics_value(item, having.in_ext, &having.code)), &having.fun)
+                    .map(|having_value| common_memory::match_cond(Some(&json!(having_value)), &having.op, &having.value))
+                    .unwrap_or(false)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Sort and limit
+    rows.sort_by(|(a, _, _), (b, _, _)| a.iter().zip(b).fold(Ordering::Equal, |ordering, (a, b)| ordering.then_with(|| compare_nullable(a.as_ref(), b.as_ref(), true))));
+    if !orders.is_empty() {
+        rows.sort_by(|(a_group_values, a_measure_values, _), (b_group_values, b_measure_values, _)| {
+            orders.iter().fold(Ordering::Equal, |ordering, (is_group, idx, asc)| {
+                ordering.then_with(|| {
+                    if *is_group {
+                        compare_nullable(a_group_values[*idx].as_ref(), b_group_values[*idx].as_ref(), *asc)
+                    } else {
+                        compare_nullable(a_measure_values[*idx].map(|v| json!(v)).as_ref(), b_measure_values[*idx].map(|v| json!(v)).as_ref(), *asc)
+                    }
+                })
+            })
+        });
+    }
+    if let Some(limit) = query_req.limit {
+        rows.truncate(limit as usize);
+    }
+
+    // Package result, the same structure as the rows returned by the PostgreSQL implementation
+    let ignore_group_agg = query_req.group.is_empty() || !query_req.group_agg.unwrap_or(false);
+    let result = rows
+        .into_iter()
+        .map(|(group_values, measure_values, items)| {
+            let mut row = Map::new();
+            for (alias_name, group_value) in select_dimension_keys.iter().zip(group_values) {
+                row.insert(alias_name.clone(), group_value.unwrap_or(Value::Null));
+            }
+            for ((alias_name, select), measure_value) in select_measure_keys.iter().zip(&query_req.select).zip(measure_values) {
+                let measure_value = match measure_value {
+                    Some(measure_value) if select.fun == SearchQueryAggFunKind::Count => json!(measure_value as i64),
+                    Some(measure_value) => decimal_to_json(measure_value),
+                    None => Value::Null,
+                };
+                row.insert(alias_name.clone(), measure_value);
+            }
+            if !ignore_group_agg {
+                let s_agg = if items.is_empty() {
+                    Value::Null
+                } else {
+                    json!(items.iter().map(|item| format!("{} - {} - {}", item.key, item.own_paths, item.create_time.format("%Y-%m-%d %H:%M:%S"))).collect::<Vec<_>>().join(","))
+                };
+                row.insert("s_agg".to_string(), s_agg);
+            }
+            Value::Object(row)
+        })
+        .collect::<Vec<_>>();
+
+    let show_names = select_dimension_keys.iter().chain(select_measure_keys.iter()).map(|alias_name| (alias_name.clone(), alias_name.clone())).collect::<HashMap<String, String>>();
+    Ok(SearchQueryMetricsResp {
+        tag: query_req.tag.to_string(),
+        show_names,
+        group: search_pg_item_serv::package_groups(select_dimension_keys, &select_measure_keys, ignore_group_agg, result)
+            .map_err(|msg| TardisError::internal_error(&format!("Fail to package groups: {msg}"), "500-spi-stats-internal-error"))?,
+    })
+}
+
+pub async fn refresh_tsv(_tag: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<()> {
+    // There is no full-text index in the memory implementation
+    Ok(())
+}
+
+fn match_visit_keys(item: &SearchMemoryItem, search_ctx: &SearchItemSearchCtxReq) -> bool {
+    let Some(visit_keys) = &item.visit_keys else {
+        return true;
+    };
+    let req_ctx = search_ctx.to_sql();
+    let mut scope_matches = req_ctx
+        .iter()
+        .filter(|(_, scope_values)| !scope_values.is_empty())
+        .map(|(scope_key, scope_values)| scope_values.iter().any(|scope_value| common_memory::match_cond(visit_keys.get(*scope_key), &BasicQueryOpKind::In, &json!(scope_value))));
+    if search_ctx.cond_by_or.unwrap_or(false) {
+        scope_matches.any(|matched| matched)
+    } else {
+        scope_matches.all(|matched| matched)
+    }
+}
+
+/// The fuzzy search is a case-insensitive substring match, returns ``rank_title`` and ``rank_content`` when matched
+fn match_q(item: &SearchMemoryItem, q: &str, q_scope: &SearchItemSearchQScopeKind) -> Option<(f32, f32)> {
+    let title_matched = matches!(q_scope, SearchItemSearchQScopeKind::Title | SearchItemSearchQScopeKind::TitleContent) && item.title.to_lowercase().contains(q);
+    let content_matched = matches!(q_scope, SearchItemSearchQScopeKind::Content | SearchItemSearchQScopeKind::TitleContent) && item.content.to_lowercase().contains(q);
+    if !title_matched && !content_matched {
+        return None;
+    }
+    Some((if title_matched { 1.0 } else { 0.0 }, if content_matched { 1.0 } else { 0.0 }))
+}
+
+fn match_query(item: &SearchMemoryItem, query: &SearchItemQueryReq) -> bool {
+    query.kinds.as_ref().map(|kinds| kinds.is_empty() || kinds.contains(&item.kind)).unwrap_or(true)
+        && query.keys.as_ref().map(|keys| keys.is_empty() || keys.iter().any(|key| item.key.starts_with(&key.to_string()))).unwrap_or(true)
+        && query.owners.as_ref().map(|owners| owners.is_empty() || owners.iter().any(|owner| item.owner.starts_with(owner))).unwrap_or(true)
+        && query.own_paths.as_ref().map(|own_paths| own_paths.is_empty() || own_paths.contains(&item.own_paths)).unwrap_or(true)
+        && query.rlike_own_paths.as_ref().map(|own_paths| own_paths.is_empty() || own_paths.iter().any(|own_path| item.own_paths.starts_with(own_path))).unwrap_or(true)
+        && query.create_time_start.map(|create_time_start| item.create_time >= create_time_start).unwrap_or(true)
+        && query.create_time_end.map(|create_time_end| item.create_time <= create_time_end).unwrap_or(true)
+        && query.update_time_start.map(|update_time_start| item.update_time >= update_time_start).unwrap_or(true)
+        && query.update_time_end.map(|update_time_end| item.update_time <= update_time_end).unwrap_or(true)
+        && query.ext.iter().flatten().all(|cond| common_memory::match_cond(item.ext.get(&cond.field), &cond.op, &cond.value))
+}
+
+fn match_adv_query(item: &SearchMemoryItem, adv_query: &Option<Vec<AdvSearchItemQueryReq>>) -> bool {
+    let mut matched: Option<bool> = None;
+    for group_query in adv_query.iter().flatten() {
+        let Some(ext) = group_query.ext.as_ref().filter(|ext| !ext.is_empty()) else {
+            continue;
+        };
+        let mut cond_matches = ext.iter().map(|cond| {
+            let value = if cond.in_ext.unwrap_or(true) {
+                item.ext.get(&cond.field).cloned()
+            } else {
+                get_field_value(item, &cond.field)
+            };
+            common_memory::match_cond(value.as_ref(), &cond.op, &cond.value)
+        });
+        let group_matched = if group_query.ext_by_or.unwrap_or(false) {
+            cond_matches.any(|matched| matched)
+        } else {
+            cond_matches.all(|matched| matched)
+        };
+        matched = Some(match matched {
+            Some(matched) if group_query.group_by_or.unwrap_or(false) => matched || group_matched,
+            Some(matched) => matched && group_matched,
+            None => group_matched,
+        });
+    }
+    matched.unwrap_or(true)
+}
+
+fn check_query_cond_values(query: &SearchItemQueryReq, adv_query: &Option<Vec<AdvSearchItemQueryReq>>, funs: &TardisFunsInst) -> TardisResult<()> {
+    for cond in query.ext.iter().flatten() {
+        check_cond_value(&cond.field, &cond.op, &cond.value, funs)?;
+    }
+    for cond in adv_query.iter().flatten().flat_map(|group_query| group_query.ext.iter().flatten()) {
+        check_cond_value(&cond.field, &cond.op, &cond.value, funs)?;
+    }
+    Ok(())
+}
+
+/// The alias name is the same as the PostgreSQL implementation, e.g. ``status_ext___``, ``act_hours_ext___sum``, ``create_time__date``
+fn metrics_alias(in_ext: Option<bool>, code: &str, suffix: &str) -> String {
+    format!("{code}{}{FUNCTION_SUFFIX_FLAG}{suffix}", if in_ext.unwrap_or(true) { FUNCTION_EXT_SUFFIX_FLAG } else { "" })
+}
+
+fn order_not_legal_err(code: &str, funs: &TardisFunsInst) -> TardisError {
+    funs.err().not_found(
+        "metric",
+        "query",
+        &format!("The order column=[{code}] is not in the group or select."),
+        "404-spi-stats-metric-op-not-legal",
+    )
+}
+
+fn get_metrics_value(item: &SearchMemoryItem, in_ext: Option<bool>, code: &str) -> Option<Value> {
+    if in_ext.unwrap_or(true) {
+        item.ext.get(code).cloned()
+    } else {
+        get_field_value(item, code)
+    }
+    .filter(|value| !value.is_null())
+}
+
+fn match_metrics_where(item: &SearchMemoryItem, where_req: &SearchQueryMetricsWhereReq) -> bool {
+    let value = get_metrics_value(item, where_req.in_ext, &where_req.code);
+    let value = match &where_req.time_window {
+        Some(time_window) => value.as_ref().and_then(|value| time_window_value(value, time_window)).map(Value::from),
+        None => value,
+    };
+    common_memory::match_cond(value.as_ref(), &where_req.op, &where_req.value)
+}
+
+/// Same as the group column of the PostgreSQL implementation, the multi-valued column is expanded to one value per element (the empty one is regarded as ``""``),
+/// and the missing value is regarded as ``"empty"``
+fn get_group_values(item: &SearchMemoryItem, group: &SearchQueryDimensionGroupReq) -> Vec<Value> {
+    let in_ext = group.in_ext.unwrap_or(true);
+    let value = get_metrics_value(item, group.in_ext, &group.code);
+    if in_ext && group.multi_values.unwrap_or(false) {
+        return match value {
+            Some(Value::Array(values)) if !values.is_empty() => values,
+            _ => vec![json!("")],
+        };
+    }
+    let value = match value {
+        // Same as ``->>``, the extended values are texts
+        Some(Value::String(value)) => Some(value),
+        Some(value) if in_ext => Some(value.to_string()),
+        Some(value) => return vec![value],
+        None => None,
+    };
+    let value = match (&group.time_window, value) {
+        (Some(_), Some(value)) if value.is_empty() && in_ext => None,
+        (Some(time_window), Some(value)) => time_window_value(&json!(value), time_window),
+        (None, value) => value,
+        (_, None) => None,
+    };
+    vec![value.map(Value::from).unwrap_or_else(|| json!(EMPTY_GROUP_VALUE))]
+}
+
+/// Same as ``SearchQueryTimeWindowKind::to_sql``, the time is formatted in the ``UTC`` time zone
+fn time_window_value(value: &Value, time_window: &SearchQueryTimeWindowKind) -> Option<String> {
+    let value = value.as_str()?;
+    let time = DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)).map(|time| Utc.from_utc_datetime(&time)))?;
+    Some(time_window.format_es_bucket_key(&time))
+}
+
+/// Same as ``COALESCE(<value>::decimal,0)``
+fn to_decimal(value: Option<&Value>) -> f64 {
+    match value {
+        Some(Value::Number(value)) => value.as_f64().unwrap_or_default(),
+        Some(Value::String(value)) => value.parse().unwrap_or_default(),
+        _ => 0.0,
+    }
+}
+
+/// The ``decimal`` values are returned as strings by the PostgreSQL implementation
+fn decimal_to_json(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        json!((value as i64).to_string())
+    } else {
+        json!(value.to_string())
+    }
+}
+
+/// Same as ``SearchQueryAggFunKind::to_sql``, the aggregated value of no items is ``None`` except for ``count``
+fn aggregate(values: impl Iterator<Item = Option<Value>>, fun: &SearchQueryAggFunKind) -> Option<f64> {
+    if fun == &SearchQueryAggFunKind::Count {
+        return Some(values.flatten().count() as f64);
+    }
+    let values = values.map(|value| to_decimal(value.as_ref())).collect::<Vec<_>>();
+    if values.is_empty() {
+        return None;
+    }
+    Some(match fun {
+        SearchQueryAggFunKind::Sum => values.iter().sum(),
+        SearchQueryAggFunKind::Avg => values.iter().sum::<f64>() / values.len() as f64,
+        SearchQueryAggFunKind::Max => values.iter().copied().fold(f64::MIN, f64::max),
+        SearchQueryAggFunKind::Min => values.iter().copied().fold(f64::MAX, f64::min),
+        SearchQueryAggFunKind::Count => values.len() as f64,
+    })
+}
+
+/// Same as the default ``ORDER BY`` of PostgreSQL, the null values are last in ascending order and first in descending order
+fn compare_nullable(a: Option<&Value>, b: Option<&Value>, asc: bool) -> Ordering {
+    let ordering = match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => common_memory::compare_value(a, b).unwrap_or_else(|| a.to_string().cmp(&b.to_string())),
+    };
+    if asc {
+        ordering
+    } else {
+        ordering.reverse()
+    }
+}
+
+/// Wrap the case-insensitive matches of ``q`` with the highlight tags,
+/// the text around the matches is cut into fragments of ``fragment_size`` characters.
+fn package_highlight_fragments(text: &str, q: &str, highlight: &SearchItemSearchHighlightReq) -> Vec<String> {
+    let chars = text.chars().collect::<Vec<char>>();
+    let lower_chars = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect::<Vec<char>>();
+    let q = q.chars().collect::<Vec<char>>();
+    if q.is_empty() {
+        return vec![];
+    }
+    let mut matches = vec![];
+    let mut idx = 0;
+    while idx + q.len() <= lower_chars.len() {
+        if lower_chars[idx..idx + q.len()] == q[..] {
+            matches.push(idx);
+            idx += q.len();
+        } else {
+            idx += 1;
+        }
+    }
+    let fragment_size = highlight.fragment_size.unwrap_or(DEFAULT_HIGHLIGHT_FRAGMENT_SIZE) as usize;
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for &start_idx in &matches {
+        let prev_end = ranges.last().map(|(_, end)| *end).unwrap_or(0);
+        if start_idx + q.len() <= prev_end {
+            continue;
+        }
+        if ranges.len() >= highlight.get_number_of_fragments() as usize {
+            break;
+        }
+        // Center the match in the fragment
+        let start = start_idx.saturating_sub(fragment_size.saturating_sub(q.len()) / 2).max(prev_end);
+        let end = (start + fragment_size).max(start_idx + q.len()).min(chars.len());
+        ranges.push((start, end));
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let mut fragment = String::new();
+            let mut idx = start;
+            for &start_idx in matches.iter().filter(|start_idx| **start_idx >= start && **start_idx + q.len() <= end) {
+                fragment.extend(&chars[idx..start_idx]);
+                fragment.push_str(highlight.get_pre_tag());
+                fragment.extend(&chars[start_idx..start_idx + q.len()]);
+                fragment.push_str(highlight.get_post_tag());
+                idx = start_idx + q.len();
+            }
+            fragment.extend(&chars[idx..end]);
+            fragment
+        })
+        .collect()
+}
+
+/// Trigram similarity, consistent with the ``similarity`` function of ``pg_trgm``
+fn trigram_similarity(a: &str, b: &str) -> f32 {
+    fn trigrams(text: &str) -> HashSet<String> {
+        text.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .flat_map(|word| {
+                let chars = format!("  {word} ").chars().collect::<Vec<char>>();
+                chars.windows(3).map(|window| window.iter().collect::<String>()).collect::<Vec<String>>()
+            })
+            .collect()
+    }
+    let (a, b) = (trigrams(a), trigrams(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f32 / union as f32
+}
+
+/// Get the value of the basic field, used for the advanced query with ``in_ext = false``
+fn get_field_value(item: &SearchMemoryItem, field: &str) -> Option<Value> {
+    match field {
+        "kind" => Some(json!(item.kind)),
+        "key" => Some(json!(item.key)),
+        "title" => Some(json!(item.title)),
+        "content" => Some(json!(item.content)),
+        "owner" => Some(json!(item.owner)),
+        "own_paths" => Some(json!(item.own_paths)),
+        "create_time" => Some(json!(item.create_time)),
+        "update_time" => Some(json!(item.update_time)),
+        _ => None,
+    }
+}
+
+fn get_sort_value(item: &SearchMemoryItem, field: &str) -> Option<Value> {
+    match field.to_lowercase().as_str() {
+        "key" | "title" | "content" | "owner" | "own_paths" | "create_time" | "update_time" => get_field_value(item, &field.to_lowercase()),
+        _ => item.ext.get(field).filter(|value| !value.is_null()).cloned(),
+    }
+}
+
+/// Consistent with the PostgreSQL implementation, the condition without value is not legal (except for the null checks).
+fn check_cond_value(field: &str, op: &BasicQueryOpKind, value: &Value, funs: &TardisFunsInst) -> TardisResult<()> {
+    let legal = match value {
+        Value::Null => false,
+        Value::Array(values) => !values.is_empty() && (values.len() == 1 || op == &BasicQueryOpKind::In || op == &BasicQueryOpKind::NotIn),
+        _ => true,
+    };
+    if legal || op == &BasicQueryOpKind::IsNull || op == &BasicQueryOpKind::IsNotNull || op == &BasicQueryOpKind::IsNullOrEmpty {
+        Ok(())
+    } else {
+        Err(funs.err().not_found(
+            "item",
+            "search",
+            &format!("The ext field=[{}] value=[{}] operation=[{}] is not legal.", field, value, op),
+            "404-spi-search-op-not-legal",
+        ))
+    }
+}
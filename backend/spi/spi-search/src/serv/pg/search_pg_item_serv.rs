@@ -19,17 +19,18 @@ use bios_basic::{dto::BasicQueryCondInfo, enumeration::BasicQueryOpKind, helper:
 
 use crate::{
     dto::search_item_dto::{
-        AdvBasicQueryCondInfo, SearchItemAddReq, SearchItemModifyReq, SearchItemSearchQScopeKind, SearchItemSearchReq, SearchItemSearchResp, SearchQueryMetricsReq,
-        SearchQueryMetricsResp,
+        AdvBasicQueryCondInfo, SearchItemAddReq, SearchItemModifyReq, SearchItemSearchCtxReq, SearchItemSearchHighlightReq, SearchItemSearchQScopeKind, SearchItemSearchReq,
+        SearchItemSearchResp, SearchItemSuggestReq, SearchItemSuggestResp, SearchQueryMetricsReq, SearchQueryMetricsResp,
     },
     search_config::SearchConfig,
 };
 
 use super::search_pg_initializer;
 
-const FUNCTION_SUFFIX_FLAG: &str = "__";
-const FUNCTION_EXT_SUFFIX_FLAG: &str = "_ext_";
+pub(crate) const FUNCTION_SUFFIX_FLAG: &str = "__";
+pub(crate) const FUNCTION_EXT_SUFFIX_FLAG: &str = "_ext_";
 const INNER_FIELD: [&str; 7] = ["key", "title", "content", "owner", "own_paths", "create_time", "update_time"];
+const HIGHLIGHT_FRAGMENT_DELIMITER: &str = " ... ";
 
 pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let mut params = Vec::new();
@@ -327,30 +328,24 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
         select_fragments = ", 0::float4 AS rank_title, 0::float4 AS rank_content".to_string();
     }
 
-    // Add visit_keys filter
-    let req_ctx = search_req.ctx.to_sql();
-    if !req_ctx.is_empty() {
-        let mut where_visit_keys_fragments = Vec::new();
-        for (scope_key, scope_values) in req_ctx {
-            if scope_values.is_empty() {
-                continue;
-            }
-            if scope_values.len() == 1 {
-                where_visit_keys_fragments.push(format!("visit_keys -> '{scope_key}' ? ${}", sql_vals.len() + 1));
-            } else {
-                where_visit_keys_fragments.push(format!(
-                    "visit_keys -> '{scope_key}' ?| array[{}]",
-                    (0..scope_values.len()).map(|idx| format!("${}", sql_vals.len() + idx + 1)).collect::<Vec<String>>().join(", ")
-                ));
-            }
-            for scope_value in scope_values {
-                sql_vals.push(Value::from(scope_value));
-            }
+    // Package highlight
+    let mut highlight_fields = vec![];
+    let mut highlight_fragments = "".to_string();
+    if let (Some(highlight), Some(_)) = (&search_req.highlight, &search_req.query.q) {
+        highlight_fields = highlight.get_fields(search_req.query.q_scope.as_ref())?;
+        sql_vals.push(Value::from(package_highlight_options(highlight)));
+        for field in &highlight_fields {
+            highlight_fragments.push_str(&format!(
+                ", ts_headline('{}', COALESCE({field}, ''), query1 || query2, ${}) AS highlight_{field}",
+                get_tokenizer(),
+                sql_vals.len()
+            ));
         }
-        where_fragments.push(format!(
-            "(visit_keys IS NULL OR ({}))",
-            where_visit_keys_fragments.join(if search_req.ctx.cond_by_or.unwrap_or(false) { " OR " } else { " AND " })
-        ));
+    }
+
+    // Add visit_keys filter
+    if let Some(where_visit_keys_fragment) = package_visit_keys_filter(&search_req.ctx, &mut sql_vals) {
+        where_fragments.push(where_visit_keys_fragment);
     }
 
     if let Some(kinds) = &search_req.query.kinds {
@@ -719,7 +714,7 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
     let result = conn
         .query_all(
             format!(
-                r#"SELECT kind, key, title, owner, own_paths, create_time, update_time, ext{}{}{}
+                r#"SELECT kind, key, title, owner, own_paths, create_time, update_time, ext{}{}{}{}
 FROM {table_name}{}
 WHERE 
     {}
@@ -729,6 +724,7 @@ WHERE
                 if search_req.page.fetch_total { ", count(*) OVER() AS total" } else { "" },
                 if search_req.query.in_q_content.unwrap_or(false) { ", content" } else { "" },
                 select_fragments,
+                highlight_fragments,
                 from_fragments,
                 where_fragments.join(" AND "),
                 if sql_adv_query.is_empty() {
@@ -767,6 +763,24 @@ WHERE
                 ext: item.try_get("", "ext")?,
                 rank_title: item.try_get("", "rank_title")?,
                 rank_content: item.try_get("", "rank_content")?,
+                highlight: if highlight_fields.is_empty() {
+                    None
+                } else {
+                    Some(
+                        highlight_fields
+                            .iter()
+                            .map(|field| {
+                                let fragments = item
+                                    .try_get::<String>("", &format!("highlight_{field}"))?
+                                    .split(HIGHLIGHT_FRAGMENT_DELIMITER)
+                                    .filter(|fragment| !fragment.is_empty())
+                                    .map(|fragment| fragment.to_string())
+                                    .collect::<Vec<String>>();
+                                Ok((field.clone(), fragments))
+                            })
+                            .collect::<TardisResult<HashMap<String, Vec<String>>>>()?,
+                    )
+                },
             })
         })
         .collect::<TardisResult<Vec<SearchItemSearchResp>>>()?;
@@ -779,6 +793,108 @@ WHERE
     })
 }
 
+fn package_visit_keys_filter(search_ctx: &SearchItemSearchCtxReq, sql_vals: &mut Vec<Value>) -> Option<String> {
+    let req_ctx = search_ctx.to_sql();
+    if req_ctx.is_empty() {
+        return None;
+    }
+    let mut where_visit_keys_fragments = Vec::new();
+    for (scope_key, scope_values) in req_ctx {
+        if scope_values.is_empty() {
+            continue;
+        }
+        if scope_values.len() == 1 {
+            where_visit_keys_fragments.push(format!("visit_keys -> '{scope_key}' ? ${}", sql_vals.len() + 1));
+        } else {
+            where_visit_keys_fragments.push(format!(
+                "visit_keys -> '{scope_key}' ?| array[{}]",
+                (0..scope_values.len()).map(|idx| format!("${}", sql_vals.len() + idx + 1)).collect::<Vec<String>>().join(", ")
+            ));
+        }
+        for scope_value in scope_values {
+            sql_vals.push(Value::from(scope_value));
+        }
+    }
+    Some(format!(
+        "(visit_keys IS NULL OR ({}))",
+        where_visit_keys_fragments.join(if search_ctx.cond_by_or.unwrap_or(false) { " OR " } else { " AND " })
+    ))
+}
+
+/// Options of ``ts_headline``, see https://www.postgresql.org/docs/current/textsearch-controls.html#TEXTSEARCH-HEADLINE
+fn package_highlight_options(highlight: &SearchItemSearchHighlightReq) -> String {
+    // Double quotes in the option values are escaped by doubling them
+    let mut options = format!(
+        r#"StartSel="{}", StopSel="{}", MaxFragments={}, FragmentDelimiter="{HIGHLIGHT_FRAGMENT_DELIMITER}""#,
+        highlight.get_pre_tag().replace('"', r#""""#),
+        highlight.get_post_tag().replace('"', r#""""#),
+        highlight.get_number_of_fragments()
+    );
+    if let Some(fragment_size) = highlight.fragment_size {
+        // `MinWords` must be less than `MaxWords`
+        options.push_str(&format!(", MaxWords={fragment_size}, MinWords={}", (fragment_size / 2).max(1)));
+    }
+    options
+}
+
+pub async fn suggest(suggest_req: &SearchItemSuggestReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<SearchItemSuggestResp>> {
+    let mut sql_vals: Vec<Value> = vec![];
+    let q = suggest_req.q.trim();
+    sql_vals.push(Value::from(q));
+    sql_vals.push(Value::from(format!("{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))));
+    let mut where_fragments = vec![if suggest_req.fuzzy.unwrap_or(false) {
+        // `%` is the similarity operator of pg_trgm
+        "(title ILIKE $2 OR title % $1)".to_string()
+    } else {
+        "title ILIKE $2".to_string()
+    }];
+    if let Some(where_visit_keys_fragment) = package_visit_keys_filter(&suggest_req.ctx, &mut sql_vals) {
+        where_fragments.push(where_visit_keys_fragment);
+    }
+    if let Some(kinds) = &suggest_req.kinds {
+        if !kinds.is_empty() {
+            where_fragments.push(format!(
+                "kind = ANY (ARRAY[{}])",
+                (0..kinds.len()).map(|idx| format!("${}", sql_vals.len() + idx + 1)).collect::<Vec<String>>().join(",")
+            ));
+            for kind in kinds {
+                sql_vals.push(Value::from(kind.to_string()));
+            }
+        }
+    }
+    sql_vals.push(Value::from(suggest_req.size.unwrap_or(10)));
+
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &suggest_req.tag, ctx, false).await?;
+    // Titles with the prefix come first, followed by the similar titles
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT kind, key, title, ((CASE WHEN title ILIKE $2 THEN 1 ELSE 0 END) + similarity(title, $1))::float4 AS rank
+FROM {table_name}
+WHERE
+    {}
+ORDER BY rank DESC, title
+LIMIT ${}"#,
+                where_fragments.join(" AND "),
+                sql_vals.len()
+            ),
+            sql_vals,
+        )
+        .await?;
+    result
+        .into_iter()
+        .map(|item| {
+            Ok(SearchItemSuggestResp {
+                kind: item.try_get("", "kind")?,
+                key: item.try_get("", "key")?,
+                title: item.try_get("", "title")?,
+                rank: item.try_get("", "rank")?,
+            })
+        })
+        .collect::<TardisResult<Vec<SearchItemSuggestResp>>>()
+}
+
 fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
     match (a, b) {
         (a @ &mut serde_json::Value::Object(_), serde_json::Value::Object(b)) => {
@@ -1520,7 +1636,7 @@ pub async fn query_metrics(query_req: &SearchQueryMetricsReq, funs: &TardisFunsI
     })
 }
 
-fn package_groups(
+pub(crate) fn package_groups(
     curr_select_dimension_keys: Vec<String>,
     select_measure_keys: &Vec<String>,
     ignore_group_agg: bool,
@@ -1531,8 +1647,8 @@ fn package_groups(
         let mut leaf_node = Map::with_capacity(result.len());
         for measure_key in select_measure_keys {
             let val = first_result.get(measure_key).ok_or_else(|| format!("failed to get key {measure_key}"))?;
-            let val = if measure_key.ends_with(&format!("{FUNCTION_SUFFIX_FLAG}avg")) {
-                // Fix `avg` function return type error
+            let val = if measure_key.ends_with(&format!("{FUNCTION_SUFFIX_FLAG}avg")) && !val.is_number() {
+                // Fix `avg` function return type error of PostgreSQL (the Elasticsearch implementation returns a number)
                 let val = val
                     .as_str()
                     .ok_or_else(|| format!("value of field {measure_key} should be a string"))?
@@ -5,11 +5,15 @@ use bios_basic::spi_dispatch_service;
 use tardis::basic::result::TardisResult;
 use tardis::web::web_resp::TardisPage;
 
-use crate::dto::search_item_dto::{SearchItemAddReq, SearchItemModifyReq, SearchItemSearchReq, SearchItemSearchResp, SearchQueryMetricsReq, SearchQueryMetricsResp};
+use crate::dto::search_item_dto::{
+    SearchItemAddReq, SearchItemModifyReq, SearchItemSearchReq, SearchItemSearchResp, SearchItemSuggestReq, SearchItemSuggestResp, SearchQueryMetricsReq, SearchQueryMetricsResp,
+};
 use crate::search_initializer;
 
 #[cfg(feature = "spi-es")]
 use super::es;
+#[cfg(feature = "spi-memory")]
+use super::memory;
 #[cfg(feature = "spi-pg")]
 use super::pg;
 spi_dispatch_service! {
@@ -20,6 +24,8 @@ spi_dispatch_service! {
         spi_constants::SPI_PG_KIND_CODE => pg::search_pg_item_serv,
         #[cfg(feature = "spi-es")]
         spi_constants::SPI_ES_KIND_CODE => es::search_es_item_serv,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::search_memory_item_serv,
     },
     @method: {
         add(add_req: &mut SearchItemAddReq) -> TardisResult<()>;
@@ -27,6 +33,7 @@ spi_dispatch_service! {
         delete(tag: &str, key: &str) -> TardisResult<()>;
         delete_by_ownership(tag: &str, own_paths: &str) -> TardisResult<()>;
         search(search_req: &mut SearchItemSearchReq) -> TardisResult<TardisPage<SearchItemSearchResp>>;
+        suggest(suggest_req: &SearchItemSuggestReq) -> TardisResult<Vec<SearchItemSuggestResp>>;
         query_metrics(query_req: &SearchQueryMetricsReq) -> TardisResult<SearchQueryMetricsResp>;
         refresh_tsv(tag: &str) -> TardisResult<()>;
     }
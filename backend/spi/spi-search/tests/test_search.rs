@@ -17,6 +17,7 @@ use tardis::web::web_resp::Void;
 use tardis::{tokio, TardisFuns};
 mod init_search_container;
 mod test_search_item;
+mod test_search_metrics;
 
 #[tokio::test]
 async fn test_search() -> TardisResult<()> {
@@ -25,6 +26,7 @@ async fn test_search() -> TardisResult<()> {
     let _x = init_search_container::init().await?;
     init_data(spi_constants::SPI_ES_KIND_CODE, &env::var("TARDIS_FW.ES.URL").unwrap()).await?;
     init_data(spi_constants::SPI_PG_KIND_CODE, &env::var("TARDIS_FW.DB.URL").unwrap()).await?;
+    init_data(spi_constants::SPI_MEMORY_KIND_CODE, "memory://test").await?;
 
     Ok(())
 }
@@ -76,7 +78,8 @@ async fn init_data(code: &str, conn_uri: &str) -> TardisResult<()> {
 
     let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app001", bs_id), &Void {}).await;
 
-    test_search_item::test(&mut client).await?;
+    test_search_item::test(code, &mut client).await?;
+    test_search_metrics::test(code, &mut client).await?;
 
     client.set_auth(&ctx)?;
     client.delete(&format!("/ci/manage/bs/{}", bs_id)).await;
@@ -1,12 +1,13 @@
+use bios_basic::spi::spi_constants;
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_search::dto::search_item_dto::SearchItemSearchResp;
+use bios_spi_search::dto::search_item_dto::{SearchItemSearchResp, SearchItemSuggestResp};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::serde_json::json;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::{TardisPage, TardisResp, Void};
 
-pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+pub async fn test(kind_code: &str, client: &mut TestHttpClient) -> TardisResult<()> {
     client.set_auth(&TardisContext {
         own_paths: "t1/app001".to_string(),
         ak: "app001".to_string(),
@@ -89,18 +90,21 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     sleep(std::time::Duration::from_secs(2)).await;
 
     // Search without conditions
-    let search_result: TardisResp<TardisPage<SearchItemSearchResp>> = client
-        .put_resp(
-            "/ci/item/search",
-            &json!({
-                "tag":"feed2",
-                "ctx":{},
-                "query":{},
-                "page":{"number":1,"size":10,"fetch_total":true}
-            }),
-        )
-        .await;
-    assert!(search_result.code.starts_with("400"));
+    // The in-memory implementation has no table (index) of the tag, so the search of a nonexistent tag returns nothing instead of an error
+    if kind_code != spi_constants::SPI_MEMORY_KIND_CODE {
+        let search_result: TardisResp<TardisPage<SearchItemSearchResp>> = client
+            .put_resp(
+                "/ci/item/search",
+                &json!({
+                    "tag":"feed2",
+                    "ctx":{},
+                    "query":{},
+                    "page":{"number":1,"size":10,"fetch_total":true}
+                }),
+            )
+            .await;
+        assert!(search_result.code.starts_with("400"));
+    }
 
     let search_result: TardisPage<SearchItemSearchResp> = client
         .put(
@@ -138,6 +142,8 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     assert_eq!(search_result.total_size, 2);
     assert_eq!(search_result.records[0].key, "002");
     assert_eq!(search_result.records[1].key, "003");
+
+    // Search with highlight
     let search_result: TardisPage<SearchItemSearchResp> = client
         .put(
             "/ci/item/search",
@@ -147,49 +153,119 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
                     "apps":["003"]
                 },
                 "query":{
-                    "q": "类型 & 上传"
+                    "q": "新增"
                 },
+                "highlight":{},
                 "page":{"number":1,"size":10,"fetch_total":true}
             }),
         )
         .await;
-    assert_eq!(search_result.total_size, 0);
-    let search_result: TardisPage<SearchItemSearchResp> = client
-        .put(
+    assert_eq!(search_result.total_size, 2);
+    assert!(search_result.records[0].highlight.as_ref().unwrap().get("title").unwrap()[0].contains("<em>新增</em>"));
+    assert!(search_result.records[0].highlight.as_ref().unwrap().get("content").is_none());
+    let search_result: TardisResp<TardisPage<SearchItemSearchResp>> = client
+        .put_resp(
             "/ci/item/search",
             &json!({
                 "tag":"feed",
-                "ctx":{
-                    "apps":["003"]
-                },
+                "ctx":{},
                 "query":{
-                    "q": "类型 | 上传"
+                    "q": "新增"
                 },
+                "highlight":{"fields":["owner"]},
                 "page":{"number":1,"size":10,"fetch_total":true}
             }),
         )
         .await;
-    assert_eq!(search_result.total_size, 0);
+    assert!(search_result.code.starts_with("400"));
 
-    let search_result: TardisPage<SearchItemSearchResp> = client
+    // Suggest
+    let suggest_result: Vec<SearchItemSuggestResp> = client
         .put(
-            "/ci/item/search",
+            "/ci/item/suggest",
             &json!({
                 "tag":"feed",
                 "ctx":{
                     "apps":["003"]
                 },
-                "query":{
-                    "q": "类型 ｜ 上传",
-                    "q_scope": "title_content",
+                "q": "新增"
+            }),
+        )
+        .await;
+    assert_eq!(suggest_result.len(), 2);
+    assert!(suggest_result.iter().all(|suggest| suggest.title.starts_with("新增")));
+    let suggest_result: Vec<SearchItemSuggestResp> = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"feed",
+                "ctx":{
+                    "apps":["003"]
                 },
-                "page":{"number":1,"size":10,"fetch_total":true}
+                "q": "新增",
+                "kinds": ["task"],
+                "size": 1
             }),
         )
         .await;
-    assert_eq!(search_result.total_size, 2);
-    assert_eq!(search_result.records[0].key, "002");
-    assert_eq!(search_result.records[1].key, "003");
+    assert_eq!(suggest_result.len(), 1);
+    assert_eq!(suggest_result[0].key, "003");
+
+    // The fuzzy search of the in-memory implementation is a substring match without the tokenizer of the full-text search
+    if kind_code != spi_constants::SPI_MEMORY_KIND_CODE {
+        let search_result: TardisPage<SearchItemSearchResp> = client
+            .put(
+                "/ci/item/search",
+                &json!({
+                    "tag":"feed",
+                    "ctx":{
+                        "apps":["003"]
+                    },
+                    "query":{
+                        "q": "类型 & 上传"
+                    },
+                    "page":{"number":1,"size":10,"fetch_total":true}
+                }),
+            )
+            .await;
+        assert_eq!(search_result.total_size, 0);
+        let search_result: TardisPage<SearchItemSearchResp> = client
+            .put(
+                "/ci/item/search",
+                &json!({
+                    "tag":"feed",
+                    "ctx":{
+                        "apps":["003"]
+                    },
+                    "query":{
+                        "q": "类型 | 上传"
+                    },
+                    "page":{"number":1,"size":10,"fetch_total":true}
+                }),
+            )
+            .await;
+        assert_eq!(search_result.total_size, 0);
+
+        let search_result: TardisPage<SearchItemSearchResp> = client
+            .put(
+                "/ci/item/search",
+                &json!({
+                    "tag":"feed",
+                    "ctx":{
+                        "apps":["003"]
+                    },
+                    "query":{
+                        "q": "类型 ｜ 上传",
+                        "q_scope": "title_content",
+                    },
+                    "page":{"number":1,"size":10,"fetch_total":true}
+                }),
+            )
+            .await;
+        assert_eq!(search_result.total_size, 2);
+        assert_eq!(search_result.records[0].key, "002");
+        assert_eq!(search_result.records[1].key, "003");
+    }
 
     //  Search with ext
     let search_result: TardisPage<SearchItemSearchResp> = client
@@ -0,0 +1,123 @@
+use bios_basic::spi::spi_constants;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_search::dto::search_item_dto::SearchQueryMetricsResp;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::{json, Value};
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisResp, Void};
+
+const SUM_HOURS: &str = "hours_ext___sum";
+
+fn sum_hours(group: &Value, path: &[&str]) -> f64 {
+    let value = path.iter().fold(group, |value, key| &value[*key]);
+    let value = &value[SUM_HOURS];
+    // The sum of PostgreSQL is a decimal, which may be returned as a string
+    value.as_f64().or_else(|| value.as_str().and_then(|value| value.parse().ok())).unwrap_or_else(|| panic!("no {SUM_HOURS} in {path:?}"))
+}
+
+fn metrics_req(where_: Value, having: Value, metrics_order: Value, ignore_group_rollup: bool, limit: Value) -> Value {
+    json!({
+        "tag": "metrics",
+        "select": [{"code": "hours", "data_type": "int", "fun": "sum"}],
+        "group": [
+            {"code": "create_time", "in_ext": false, "data_type": "datetime", "time_window": "month"},
+            {"code": "kind", "in_ext": false, "data_type": "string"}
+        ],
+        "ignore_group_rollup": ignore_group_rollup,
+        "_where": where_,
+        "having": having,
+        "metrics_order": metrics_order,
+        "ctx": {},
+        "query": {},
+        "limit": limit
+    })
+}
+
+/// The same metrics request is executed on every backend, the results should have the same shape
+pub async fn test(kind_code: &str, client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "app001".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    })?;
+    for (key, kind, create_time, hours) in [
+        ("m001", "req", "2022-08-10T10:00:00.000Z", 2),
+        ("m002", "bug", "2022-08-20T10:00:00.000Z", 3),
+        ("m003", "task", "2022-09-05T10:00:00.000Z", 5),
+    ] {
+        let _: Void = client
+            .put(
+                "/ci/item",
+                &json!({
+                    "tag": "metrics",
+                    "kind": kind,
+                    "key": key,
+                    "title": key,
+                    "content": "",
+                    "owner": "account001",
+                    "own_paths": "t1/app001",
+                    "create_time": create_time,
+                    "update_time": create_time,
+                    "ext": {"hours": hours},
+                    "visit_keys": {}
+                }),
+            )
+            .await;
+    }
+    sleep(std::time::Duration::from_secs(1)).await;
+
+    let having = json!([{"code": "hours", "data_type": "int", "fun": "sum", "op": ">", "value": 2}]);
+
+    // Group by month and kind with rollup, the (2022-08, req) row is removed by having
+    let resp: SearchQueryMetricsResp = client.put("/ci/item/metrics", &metrics_req(Value::Null, having.clone(), Value::Null, false, Value::Null)).await;
+    assert_eq!(resp.tag, "metrics");
+    assert!(resp.show_names.contains_key(SUM_HOURS));
+    assert!(resp.show_names.contains_key("create_time__month"));
+    assert!(resp.show_names.contains_key("kind__"));
+    assert_eq!(resp.group.as_object().unwrap().len(), 3);
+    assert_eq!(sum_hours(&resp.group, &["ROLLUP", "ROLLUP"]), 10.0);
+    assert_eq!(sum_hours(&resp.group, &["2022-08", "ROLLUP"]), 5.0);
+    assert_eq!(sum_hours(&resp.group, &["2022-08", "bug"]), 3.0);
+    assert!(resp.group["2022-08"].get("req").is_none());
+    assert_eq!(sum_hours(&resp.group, &["2022-09", "ROLLUP"]), 5.0);
+    assert_eq!(sum_hours(&resp.group, &["2022-09", "task"]), 5.0);
+
+    // Without rollup
+    let resp: SearchQueryMetricsResp = client.put("/ci/item/metrics", &metrics_req(Value::Null, having.clone(), Value::Null, true, Value::Null)).await;
+    assert_eq!(resp.group.as_object().unwrap().len(), 2);
+    assert!(resp.group.get("ROLLUP").is_none());
+    assert!(resp.group["2022-08"].get("ROLLUP").is_none());
+    assert_eq!(sum_hours(&resp.group, &["2022-08", "bug"]), 3.0);
+    assert_eq!(sum_hours(&resp.group, &["2022-09", "task"]), 5.0);
+
+    // Order by the measure and limit
+    let order_desc = json!([{"code": "hours", "fun": "sum", "asc": false}]);
+    let resp: SearchQueryMetricsResp = client.put("/ci/item/metrics", &metrics_req(Value::Null, having.clone(), order_desc, false, json!(1))).await;
+    assert_eq!(resp.group.as_object().unwrap().len(), 1);
+    assert_eq!(sum_hours(&resp.group, &["ROLLUP", "ROLLUP"]), 10.0);
+    let order_asc = json!([{"code": "hours", "fun": "sum", "asc": true}]);
+    let resp: SearchQueryMetricsResp = client.put("/ci/item/metrics", &metrics_req(Value::Null, having.clone(), order_asc, false, json!(1))).await;
+    assert_eq!(resp.group.as_object().unwrap().len(), 1);
+    assert_eq!(sum_hours(&resp.group, &["2022-08", "bug"]), 3.0);
+
+    // Where with the time window
+    let where_month = json!([[{"code": "create_time", "in_ext": false, "data_type": "datetime", "op": "=", "value": "2022-08", "time_window": "month"}]]);
+    let resp: SearchQueryMetricsResp = client.put("/ci/item/metrics", &metrics_req(where_month, having.clone(), Value::Null, false, Value::Null)).await;
+    assert_eq!(resp.group.as_object().unwrap().len(), 2);
+    assert_eq!(sum_hours(&resp.group, &["ROLLUP", "ROLLUP"]), 5.0);
+    assert_eq!(sum_hours(&resp.group, &["2022-08", "bug"]), 3.0);
+    assert!(resp.group.get("2022-09").is_none());
+
+    if kind_code == spi_constants::SPI_ES_KIND_CODE {
+        // The week time window is not supported by the where condition of Elasticsearch
+        let where_week = json!([[{"code": "create_time", "in_ext": false, "data_type": "datetime", "op": "=", "value": "2022 32", "time_window": "week"}]]);
+        let resp: TardisResp<SearchQueryMetricsResp> = client.put_resp("/ci/item/metrics", &metrics_req(where_week, Value::Null, Value::Null, false, Value::Null)).await;
+        assert_eq!(resp.code, "400-spi-search-metric-query");
+    }
+
+    Ok(())
+}
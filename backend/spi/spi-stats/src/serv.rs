@@ -1,3 +1,5 @@
+#[cfg(feature = "spi-memory")]
+pub mod memory;
 pub mod pg;
 pub mod stats_conf_dim_serv;
 pub mod stats_conf_fact_col_serv;
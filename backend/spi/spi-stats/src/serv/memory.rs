@@ -0,0 +1,6 @@
+pub mod stats_memory_conf_dim_serv;
+pub mod stats_memory_conf_fact_col_serv;
+pub mod stats_memory_conf_fact_serv;
+pub mod stats_memory_initializer;
+pub mod stats_memory_metric_serv;
+pub mod stats_memory_record_serv;
@@ -0,0 +1,158 @@
+use std::cmp::Ordering;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_memory::SpiMemoryClient};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::Utc,
+    web::web_resp::TardisPage,
+    TardisFunsInst,
+};
+
+use crate::dto::stats_conf_dto::{StatsConfDimAddReq, StatsConfDimInfoResp, StatsConfDimModifyReq};
+
+use super::stats_memory_initializer::{StatsMemoryDimConf, StatsMemoryStore};
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<StatsMemoryStore> {
+    inst.inst::<SpiMemoryClient<StatsMemoryStore>>().0
+}
+
+pub(crate) async fn add(add_req: &StatsConfDimAddReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if store.dim_confs.iter().any(|conf| conf.key == add_req.key) {
+        return Err(funs.err().conflict(
+            "dim_conf",
+            "add",
+            "The dimension config already exists, please delete it and then add it.",
+            "409-spi-stats-dim-conf-exist",
+        ));
+    }
+    let now = Utc::now();
+    store.dim_confs.push(StatsMemoryDimConf {
+        key: add_req.key.clone(),
+        show_name: add_req.show_name.clone(),
+        stable_ds: add_req.stable_ds,
+        data_type: add_req.data_type.clone(),
+        hierarchy: add_req.hierarchy.clone().unwrap_or_default(),
+        remark: add_req.remark.clone().unwrap_or_default(),
+        dynamic_url: add_req.dynamic_url.clone(),
+        is_tree: add_req.is_tree.unwrap_or(false),
+        tree_dynamic_url: add_req.tree_dynamic_url.clone(),
+        rel_attribute_code: add_req.rel_attribute_code.clone().unwrap_or_default(),
+        rel_attribute_url: add_req.rel_attribute_url.clone(),
+        create_time: now,
+        update_time: now,
+    });
+    Ok(())
+}
+
+pub(crate) async fn modify(dim_conf_key: &str, modify_req: &StatsConfDimModifyReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if store.dim_online(dim_conf_key) {
+        return Err(funs.err().conflict(
+            "dim_conf",
+            "modify",
+            "The dimension instance table already exists, please delete it and then modify it.",
+            "409-spi-stats-dim-inst-exist",
+        ));
+    }
+    let Some(conf) = store.dim_confs.iter_mut().find(|conf| conf.key == dim_conf_key) else {
+        return Ok(());
+    };
+    if let Some(show_name) = &modify_req.show_name {
+        conf.show_name = show_name.clone();
+    }
+    if let Some(stable_ds) = modify_req.stable_ds {
+        conf.stable_ds = stable_ds;
+    }
+    if let Some(data_type) = &modify_req.data_type {
+        conf.data_type = data_type.clone();
+    }
+    if let Some(hierarchy) = &modify_req.hierarchy {
+        conf.hierarchy = hierarchy.clone();
+    }
+    if let Some(remark) = &modify_req.remark {
+        conf.remark = remark.clone();
+    }
+    if let Some(dynamic_url) = &modify_req.dynamic_url {
+        conf.dynamic_url = Some(dynamic_url.clone());
+    }
+    if let Some(is_tree) = modify_req.is_tree {
+        conf.is_tree = is_tree;
+    }
+    if let Some(tree_dynamic_url) = &modify_req.tree_dynamic_url {
+        conf.tree_dynamic_url = Some(tree_dynamic_url.clone());
+    }
+    if let Some(rel_attribute_code) = &modify_req.rel_attribute_code {
+        conf.rel_attribute_code = rel_attribute_code.clone();
+    }
+    if let Some(rel_attribute_url) = &modify_req.rel_attribute_url {
+        conf.rel_attribute_url = Some(rel_attribute_url.clone());
+    }
+    conf.update_time = Utc::now();
+    Ok(())
+}
+
+pub(crate) async fn delete(dim_conf_key: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if store.fact_col_confs.iter().any(|col_conf| col_conf.dim_rel_conf_dim_key.as_deref() == Some(dim_conf_key)) {
+        return Err(funs.err().conflict(
+            "dim_conf",
+            "delete",
+            "This dimension config has been used by some other fact config, please delete the fact config first.",
+            "409-spi-stats-dim-conf-used",
+        ));
+    }
+    store.dim_confs.retain(|conf| conf.key != dim_conf_key);
+    store.dim_records.remove(dim_conf_key);
+    Ok(())
+}
+
+pub(crate) async fn paginate(
+    dim_conf_key: Option<String>,
+    show_name: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    _funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<StatsConfDimInfoResp>> {
+    let store = get_client(inst).read().await;
+    let mut confs = store
+        .dim_confs
+        .iter()
+        .filter(|conf| dim_conf_key.as_ref().map(|dim_conf_key| &conf.key == dim_conf_key).unwrap_or(true))
+        .filter(|conf| show_name.as_ref().map(|show_name| conf.show_name.contains(show_name)).unwrap_or(true))
+        .collect::<Vec<_>>();
+    confs.sort_by(|a, b| {
+        let by_create = desc_by_create.map(|desc| if desc { b.create_time.cmp(&a.create_time) } else { a.create_time.cmp(&b.create_time) });
+        let by_update = desc_by_update.map(|desc| if desc { b.update_time.cmp(&a.update_time) } else { a.update_time.cmp(&b.update_time) });
+        by_create.unwrap_or(Ordering::Equal).then(by_update.unwrap_or(Ordering::Equal))
+    });
+    let total_size = confs.len() as u64;
+    let records = confs.into_iter().skip(((page_number.max(1) - 1) * page_size) as usize).take(page_size as usize).map(|conf| conf.to_resp(store.dim_online(&conf.key))).collect();
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size,
+        records,
+    })
+}
+
+pub(crate) async fn create_inst(dim_conf_key: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if !store.dim_confs.iter().any(|conf| conf.key == dim_conf_key) {
+        return Err(funs.err().not_found("fact_conf", "create_inst", "The dimension config does not exist.", "404-spi-stats-dim-conf-not-exist"));
+    }
+    if store.dim_online(dim_conf_key) {
+        return Err(funs.err().conflict(
+            "dim_inst",
+            "create_inst",
+            "The dimension instance table already exists, please delete it and then create it.",
+            "409-spi-stats-dim-inst-exist",
+        ));
+    }
+    store.dim_records.insert(dim_conf_key.to_string(), Vec::new());
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use std::cmp::Ordering;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_memory::SpiMemoryClient};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::Utc,
+    web::web_resp::TardisPage,
+    TardisFunsInst,
+};
+
+use crate::{
+    dto::stats_conf_dto::{StatsConfFactColAddReq, StatsConfFactColInfoResp, StatsConfFactColModifyReq},
+    stats_enumeration::StatsFactColKind,
+};
+
+use super::stats_memory_initializer::{StatsMemoryFactColConf, StatsMemoryStore};
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<StatsMemoryStore> {
+    inst.inst::<SpiMemoryClient<StatsMemoryStore>>().0
+}
+
+pub(crate) async fn add(fact_conf_key: &str, add_req: &StatsConfFactColAddReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if !store.fact_confs.iter().any(|conf| conf.key == fact_conf_key) {
+        return Err(funs.err().conflict("fact_col_conf", "add", "The fact config not exists.", "409-spi-stats-fact-conf-not-exist"));
+    }
+    if add_req.rel_external_id.is_none() && store.fact_online(fact_conf_key) {
+        return Err(funs.err().conflict(
+            "fact_col_conf",
+            "add",
+            "The fact instance table already exists, please delete it and then modify it.",
+            "409-spi-stats-fact-inst-exist",
+        ));
+    }
+    // An extended column conflicts with both the basic column and the extended column of the same external id
+    let exist = store.fact_col_confs.iter().any(|col_conf| {
+        col_conf.key == add_req.key
+            && col_conf.rel_conf_fact_key == fact_conf_key
+            && col_conf.kind == add_req.kind
+            && (col_conf.rel_external_id.is_empty() || add_req.rel_external_id.as_ref() == Some(&col_conf.rel_external_id))
+    });
+    if exist {
+        return Err(funs.err().conflict(
+            "fact_col_conf",
+            "add",
+            "The fact column config already exists, please delete it and then add it.",
+            "409-spi-stats-fact-conf-col-exist",
+        ));
+    }
+    if let Some(dim_rel_conf_dim_key) = &add_req.dim_rel_conf_dim_key {
+        if add_req.rel_external_id.is_none() && !store.dim_online(dim_rel_conf_dim_key) {
+            return Err(funs.err().conflict("fact_col_conf", "add", "The dimension config not online.", "409-spi-stats-dim-conf-not-online"));
+        }
+    }
+    let now = Utc::now();
+    store.fact_col_confs.push(StatsMemoryFactColConf {
+        key: add_req.key.clone(),
+        show_name: add_req.show_name.clone(),
+        kind: add_req.kind.clone(),
+        rel_conf_fact_key: fact_conf_key.to_string(),
+        remark: add_req.remark.clone().unwrap_or_default(),
+        rel_external_id: add_req.rel_external_id.clone().unwrap_or_default(),
+        dim_rel_conf_dim_key: add_req.dim_rel_conf_dim_key.clone(),
+        dim_multi_values: add_req.dim_multi_values,
+        dim_exclusive_rec: add_req.dim_exclusive_rec,
+        dim_data_type: add_req.dim_data_type.clone(),
+        dim_dynamic_url: add_req.dim_dynamic_url.clone(),
+        mes_data_distinct: add_req.mes_data_distinct,
+        mes_data_type: add_req.mes_data_type.clone(),
+        mes_frequency: add_req.mes_frequency.clone(),
+        mes_unit: add_req.mes_unit.clone(),
+        mes_act_by_dim_conf_keys: add_req.mes_act_by_dim_conf_keys.clone(),
+        rel_conf_fact_and_col_key: add_req.rel_conf_fact_and_col_key.clone(),
+        create_time: now,
+        update_time: now,
+    });
+    Ok(())
+}
+
+pub(crate) async fn modify(
+    fact_conf_key: &str,
+    fact_col_conf_key: &str,
+    modify_req: &StatsConfFactColModifyReq,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if store.fact_online(fact_conf_key) {
+        return Err(funs.err().conflict(
+            "fact_col_conf",
+            "modify",
+            "The fact instance table already exists, please delete it and then modify it.",
+            "409-spi-stats-fact-inst-exist",
+        ));
+    }
+    let rel_external_id = modify_req.rel_external_id.clone().unwrap_or_default();
+    let now = Utc::now();
+    for col_conf in store
+        .fact_col_confs
+        .iter_mut()
+        .filter(|col_conf| col_conf.key == fact_col_conf_key && col_conf.rel_conf_fact_key == fact_conf_key && col_conf.rel_external_id == rel_external_id)
+    {
+        if let Some(show_name) = &modify_req.show_name {
+            col_conf.show_name = show_name.clone();
+        }
+        if let Some(kind) = &modify_req.kind {
+            col_conf.kind = kind.clone();
+        }
+        if let Some(dim_rel_conf_dim_key) = &modify_req.dim_rel_conf_dim_key {
+            col_conf.dim_rel_conf_dim_key = Some(dim_rel_conf_dim_key.clone());
+        }
+        if let Some(dim_multi_values) = modify_req.dim_multi_values {
+            col_conf.dim_multi_values = Some(dim_multi_values);
+        }
+        if let Some(mes_data_distinct) = modify_req.mes_data_distinct {
+            col_conf.mes_data_distinct = Some(mes_data_distinct);
+        }
+        if let Some(mes_data_type) = &modify_req.mes_data_type {
+            col_conf.mes_data_type = Some(mes_data_type.clone());
+        }
+        if let Some(mes_frequency) = &modify_req.mes_frequency {
+            col_conf.mes_frequency = Some(mes_frequency.clone());
+        }
+        if let Some(mes_unit) = &modify_req.mes_unit {
+            col_conf.mes_unit = Some(mes_unit.clone());
+        }
+        if let Some(mes_act_by_dim_conf_keys) = &modify_req.mes_act_by_dim_conf_keys {
+            col_conf.mes_act_by_dim_conf_keys = Some(mes_act_by_dim_conf_keys.clone());
+        }
+        if let Some(rel_conf_fact_and_col_key) = &modify_req.rel_conf_fact_and_col_key {
+            col_conf.rel_conf_fact_and_col_key = Some(rel_conf_fact_and_col_key.clone());
+        }
+        if let Some(remark) = &modify_req.remark {
+            col_conf.remark = remark.clone();
+        }
+        if let Some(dim_exclusive_rec) = modify_req.dim_exclusive_rec {
+            col_conf.dim_exclusive_rec = Some(dim_exclusive_rec);
+        }
+        if let Some(dim_data_type) = &modify_req.dim_data_type {
+            col_conf.dim_data_type = Some(dim_data_type.clone());
+        }
+        if let Some(dim_dynamic_url) = &modify_req.dim_dynamic_url {
+            col_conf.dim_dynamic_url = Some(dim_dynamic_url.clone());
+        }
+        col_conf.update_time = now;
+    }
+    Ok(())
+}
+
+pub(crate) async fn delete(
+    fact_conf_key: &str,
+    fact_col_conf_key: Option<&str>,
+    rel_external_id: Option<String>,
+    kind: Option<StatsFactColKind>,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if rel_external_id.is_none() && store.fact_online(fact_conf_key) {
+        return Err(funs.err().conflict(
+            "fact_col_conf",
+            "delete",
+            "The fact instance table already exists, please delete it and then modify it.",
+            "409-spi-stats-fact-inst-exist",
+        ));
+    }
+    // Consistent with the PostgreSQL implementation, only the basic columns are deleted when the column key is specified without the external id
+    let rel_external_id = rel_external_id.or_else(|| fact_col_conf_key.map(|_| "".to_string()));
+    store.fact_col_confs.retain(|col_conf| {
+        let matched = col_conf.rel_conf_fact_key == fact_conf_key
+            && fact_col_conf_key.map(|fact_col_conf_key| col_conf.key == fact_col_conf_key).unwrap_or(true)
+            && kind.as_ref().map(|kind| &col_conf.kind == kind).unwrap_or(true)
+            && rel_external_id.as_ref().map(|rel_external_id| &col_conf.rel_external_id == rel_external_id).unwrap_or(true);
+        !matched
+    });
+    Ok(())
+}
+
+pub(crate) async fn find_by_fact_conf_key(fact_conf_key: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<StatsConfFactColInfoResp>> {
+    paginate(Some(fact_conf_key.to_string()), None, None, None, None, 1, u32::MAX, None, None, funs, ctx, inst).await.map(|page| page.records)
+}
+
+pub(crate) async fn paginate(
+    fact_conf_key: Option<String>,
+    fact_col_conf_key: Option<String>,
+    dim_key: Option<String>,
+    show_name: Option<String>,
+    rel_external_id: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    _funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<StatsConfFactColInfoResp>> {
+    let store = get_client(inst).read().await;
+    let mut col_confs = store
+        .fact_col_confs
+        .iter()
+        .filter(|col_conf| fact_conf_key.as_ref().map(|fact_conf_key| &col_conf.rel_conf_fact_key == fact_conf_key).unwrap_or(true))
+        .filter(|col_conf| fact_col_conf_key.as_ref().map(|fact_col_conf_key| &col_conf.key == fact_col_conf_key).unwrap_or(true))
+        .filter(|col_conf| dim_key.as_ref().map(|dim_key| col_conf.dim_rel_conf_dim_key.as_ref() == Some(dim_key)).unwrap_or(true))
+        .filter(|col_conf| show_name.as_ref().map(|show_name| col_conf.show_name.contains(show_name)).unwrap_or(true))
+        // The basic columns are always returned, the extended columns are returned only when the external id matches
+        .filter(|col_conf| col_conf.rel_external_id.is_empty() || rel_external_id.as_ref() == Some(&col_conf.rel_external_id))
+        .collect::<Vec<_>>();
+    col_confs.sort_by(|a, b| {
+        let by_create = desc_by_create.map(|desc| if desc { b.create_time.cmp(&a.create_time) } else { a.create_time.cmp(&b.create_time) });
+        let by_update = desc_by_update.map(|desc| if desc { b.update_time.cmp(&a.update_time) } else { a.update_time.cmp(&b.update_time) });
+        by_create.unwrap_or(Ordering::Equal).then(by_update.unwrap_or(Ordering::Equal))
+    });
+    let total_size = col_confs.len() as u64;
+    let records = col_confs.into_iter().skip(((page_number.max(1) - 1) as u64 * page_size as u64) as usize).take(page_size as usize).map(|col_conf| col_conf.to_resp()).collect();
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size,
+        records,
+    })
+}
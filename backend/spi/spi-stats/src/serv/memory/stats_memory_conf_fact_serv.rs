@@ -0,0 +1,194 @@
+use std::cmp::Ordering;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_memory::SpiMemoryClient};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::Utc,
+    web::web_resp::TardisPage,
+    TardisFunsInst,
+};
+
+use crate::{
+    dto::stats_conf_dto::{StatsConfFactAddReq, StatsConfFactInfoResp, StatsConfFactModifyReq},
+    stats_enumeration::StatsFactColKind,
+};
+
+use super::stats_memory_initializer::{StatsMemoryFactConf, StatsMemoryStore};
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<StatsMemoryStore> {
+    inst.inst::<SpiMemoryClient<StatsMemoryStore>>().0
+}
+
+pub(crate) async fn add(add_req: &StatsConfFactAddReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if store.fact_confs.iter().any(|conf| conf.key == add_req.key) {
+        return Err(funs.err().conflict(
+            "fact_conf",
+            "add",
+            "The fact config already exists, please delete it and then add it.",
+            "409-spi-stats-fact-conf-exist",
+        ));
+    }
+    let now = Utc::now();
+    store.fact_confs.push(StatsMemoryFactConf {
+        key: add_req.key.clone(),
+        show_name: add_req.show_name.clone(),
+        query_limit: add_req.query_limit,
+        remark: add_req.remark.clone().unwrap_or_default(),
+        redirect_path: add_req.redirect_path.clone(),
+        is_online: add_req.is_online.unwrap_or_default(),
+        create_time: now,
+        update_time: now,
+    });
+    Ok(())
+}
+
+pub(crate) async fn modify(fact_conf_key: &str, modify_req: &StatsConfFactModifyReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    let online = store.fact_online(fact_conf_key);
+    if online && modify_req.is_online.is_none() {
+        return Err(funs.err().conflict(
+            "fact_conf",
+            "modify",
+            "The fact instance table already exists, please delete it and then modify it.",
+            "409-spi-stats-fact-inst-exist",
+        ));
+    }
+    let Some(conf) = store.fact_confs.iter_mut().find(|conf| conf.key == fact_conf_key) else {
+        return Ok(());
+    };
+    // Consistent with the PostgreSQL implementation, only ``is_online`` can be modified after the fact instance is created
+    if !online {
+        if let Some(show_name) = &modify_req.show_name {
+            conf.show_name = show_name.clone();
+        }
+        if let Some(query_limit) = modify_req.query_limit {
+            conf.query_limit = query_limit;
+        }
+        if let Some(remark) = &modify_req.remark {
+            conf.remark = remark.clone();
+        }
+        if let Some(redirect_path) = &modify_req.redirect_path {
+            conf.redirect_path = Some(redirect_path.clone());
+        }
+    }
+    if let Some(is_online) = modify_req.is_online {
+        conf.is_online = is_online;
+    }
+    conf.update_time = Utc::now();
+    Ok(())
+}
+
+pub(crate) async fn delete(fact_conf_key: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    store.fact_confs.retain(|conf| conf.key != fact_conf_key);
+    store.fact_col_confs.retain(|col_conf| col_conf.rel_conf_fact_key != fact_conf_key);
+    store.fact_records.remove(fact_conf_key);
+    store.fact_del_records.remove(fact_conf_key);
+    Ok(())
+}
+
+pub(crate) async fn paginate(
+    fact_conf_keys: Option<Vec<String>>,
+    show_name: Option<String>,
+    dim_rel_conf_dim_keys: Option<Vec<String>>,
+    is_online: Option<bool>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    _funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<StatsConfFactInfoResp>> {
+    let store = get_client(inst).read().await;
+    let mut confs = store
+        .fact_confs
+        .iter()
+        .filter(|conf| fact_conf_keys.as_ref().map(|fact_conf_keys| fact_conf_keys.contains(&conf.key)).unwrap_or(true))
+        .filter(|conf| show_name.as_ref().map(|show_name| conf.show_name.contains(show_name)).unwrap_or(true))
+        .filter(|conf| is_online.map(|is_online| conf.is_online == is_online).unwrap_or(true))
+        .filter(|conf| {
+            // Consistent with the PostgreSQL implementation, the fact must be associated with all of the specified dimensions
+            dim_rel_conf_dim_keys
+                .as_ref()
+                .filter(|dim_rel_conf_dim_keys| !dim_rel_conf_dim_keys.is_empty())
+                .map(|dim_rel_conf_dim_keys| {
+                    store
+                        .fact_col_confs
+                        .iter()
+                        .filter(|col_conf| col_conf.rel_conf_fact_key == conf.key)
+                        .filter(|col_conf| col_conf.dim_rel_conf_dim_key.as_ref().map(|dim_key| dim_rel_conf_dim_keys.contains(dim_key)).unwrap_or(false))
+                        .count()
+                        == dim_rel_conf_dim_keys.len()
+                })
+                .unwrap_or(true)
+        })
+        .collect::<Vec<_>>();
+    confs.sort_by(|a, b| {
+        let by_create = desc_by_create.map(|desc| if desc { b.create_time.cmp(&a.create_time) } else { a.create_time.cmp(&b.create_time) });
+        let by_update = desc_by_update.map(|desc| if desc { b.update_time.cmp(&a.update_time) } else { a.update_time.cmp(&b.update_time) });
+        by_create.unwrap_or(Ordering::Equal).then(by_update.unwrap_or(Ordering::Equal))
+    });
+    let total_size = confs.len() as u64;
+    let records = confs.into_iter().skip(((page_number.max(1) - 1) * page_size) as usize).take(page_size as usize).map(|conf| conf.to_resp(store.fact_online(&conf.key))).collect();
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size,
+        records,
+    })
+}
+
+pub(crate) async fn create_inst(fact_conf_key: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    if !store.fact_confs.iter().any(|conf| conf.key == fact_conf_key) {
+        return Err(funs.err().not_found("fact_conf", "create_inst", "The fact config does not exist.", "404-spi-stats-fact-conf-not-exist"));
+    }
+    let fact_col_confs = store.fact_col_confs.iter().filter(|col_conf| col_conf.rel_conf_fact_key == fact_conf_key && col_conf.rel_external_id.is_empty()).collect::<Vec<_>>();
+    if fact_col_confs.is_empty() {
+        return Err(funs.err().not_found(
+            "fact_col_conf",
+            "create_inst",
+            "The fact column config does not exist.",
+            "404-spi-stats-fact-col-conf-not-exist",
+        ));
+    }
+    if store.fact_online(fact_conf_key) {
+        return Err(funs.err().conflict(
+            "fact_inst",
+            "create_inst",
+            "The fact instance table already exists, please delete it and then create it.",
+            "409-spi-stats-fact-inst-exist",
+        ));
+    }
+    for fact_col_conf in fact_col_confs {
+        match fact_col_conf.kind {
+            StatsFactColKind::Dimension => {
+                let Some(dim_conf_key) = &fact_col_conf.dim_rel_conf_dim_key else {
+                    return Err(funs.err().bad_request("fact_inst", "create", "Fail to get dimension config", "400-spi-stats-fail-to-get-dim-config-key"));
+                };
+                if !store.dim_online(dim_conf_key) {
+                    return Err(funs.err().conflict(
+                        "fact_inst",
+                        "create",
+                        &format!("The dimension config [{dim_conf_key}] not online."),
+                        "409-spi-stats-dim-conf-not-online",
+                    ));
+                }
+            }
+            StatsFactColKind::Measure if fact_col_conf.mes_data_type.is_none() => {
+                return Err(funs.err().conflict(
+                    "fact_inst",
+                    "create",
+                    "Config of kind StatsFactColKind::Measure should have a mes_data_type",
+                    "409-spi-stats-miss-mes-data-type",
+                ));
+            }
+            _ => {}
+        }
+    }
+    store.fact_records.insert(fact_conf_key.to_string(), Vec::new());
+    store.fact_del_records.insert(fact_conf_key.to_string(), Vec::new());
+    Ok(())
+}
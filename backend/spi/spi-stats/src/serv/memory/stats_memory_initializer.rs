@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    serde_json::{json, Map, Value},
+};
+
+use crate::{
+    dto::stats_conf_dto::{StatsConfDimInfoResp, StatsConfFactColInfoResp, StatsConfFactInfoResp},
+    stats_enumeration::{StatsDataTypeKind, StatsFactColKind},
+};
+
+#[derive(Default)]
+pub struct StatsMemoryStore {
+    pub(crate) dim_confs: Vec<StatsMemoryDimConf>,
+    pub(crate) fact_confs: Vec<StatsMemoryFactConf>,
+    pub(crate) fact_col_confs: Vec<StatsMemoryFactColConf>,
+    /// Dimension instance records grouped by dimension config key,
+    /// the existence of the group is equivalent to the existence of the ``stats_inst_dim_<key>`` table in the PostgreSQL implementation
+    pub(crate) dim_records: HashMap<String, Vec<StatsMemoryDimRecord>>,
+    /// Fact instance records grouped by fact config key, in insertion order,
+    /// the existence of the group is equivalent to the existence of the ``stats_inst_fact_<key>`` table in the PostgreSQL implementation
+    pub(crate) fact_records: HashMap<String, Vec<StatsMemoryFactRecord>>,
+    /// Deleted fact record keys grouped by fact config key, equivalent to the ``stats_inst_fact_<key>_del`` table
+    pub(crate) fact_del_records: HashMap<String, Vec<(String, DateTime<Utc>)>>,
+}
+
+impl StatsMemoryStore {
+    pub(crate) fn dim_online(&self, dim_conf_key: &str) -> bool {
+        self.dim_records.contains_key(dim_conf_key)
+    }
+
+    pub(crate) fn fact_online(&self, fact_conf_key: &str) -> bool {
+        self.fact_records.contains_key(fact_conf_key)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct StatsMemoryDimConf {
+    pub(crate) key: String,
+    pub(crate) show_name: String,
+    pub(crate) stable_ds: bool,
+    pub(crate) data_type: StatsDataTypeKind,
+    pub(crate) hierarchy: Vec<String>,
+    pub(crate) remark: String,
+    pub(crate) dynamic_url: Option<String>,
+    pub(crate) is_tree: bool,
+    pub(crate) tree_dynamic_url: Option<String>,
+    pub(crate) rel_attribute_code: Vec<String>,
+    pub(crate) rel_attribute_url: Option<String>,
+    pub(crate) create_time: DateTime<Utc>,
+    pub(crate) update_time: DateTime<Utc>,
+}
+
+impl StatsMemoryDimConf {
+    pub(crate) fn to_resp(&self, online: bool) -> StatsConfDimInfoResp {
+        StatsConfDimInfoResp {
+            key: self.key.clone(),
+            show_name: self.show_name.clone(),
+            stable_ds: self.stable_ds,
+            data_type: self.data_type.clone(),
+            hierarchy: self.hierarchy.clone(),
+            online,
+            remark: Some(self.remark.clone()),
+            dynamic_url: self.dynamic_url.clone(),
+            is_tree: self.is_tree,
+            tree_dynamic_url: self.tree_dynamic_url.clone(),
+            rel_attribute_code: Some(self.rel_attribute_code.clone()),
+            rel_attribute_url: self.rel_attribute_url.clone(),
+            create_time: self.create_time,
+            update_time: self.update_time,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct StatsMemoryFactConf {
+    pub(crate) key: String,
+    pub(crate) show_name: String,
+    pub(crate) query_limit: i32,
+    pub(crate) remark: String,
+    pub(crate) redirect_path: Option<String>,
+    pub(crate) is_online: bool,
+    pub(crate) create_time: DateTime<Utc>,
+    pub(crate) update_time: DateTime<Utc>,
+}
+
+impl StatsMemoryFactConf {
+    pub(crate) fn to_resp(&self, online: bool) -> StatsConfFactInfoResp {
+        StatsConfFactInfoResp {
+            key: self.key.clone(),
+            show_name: self.show_name.clone(),
+            query_limit: self.query_limit,
+            online,
+            remark: Some(self.remark.clone()),
+            is_online: self.is_online,
+            redirect_path: self.redirect_path.clone(),
+            create_time: self.create_time,
+            update_time: self.update_time,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct StatsMemoryFactColConf {
+    pub(crate) key: String,
+    pub(crate) show_name: String,
+    pub(crate) kind: StatsFactColKind,
+    pub(crate) rel_conf_fact_key: String,
+    pub(crate) remark: String,
+    /// Empty string means the column is not an extended column, consistent with the PostgreSQL implementation
+    pub(crate) rel_external_id: String,
+    pub(crate) dim_rel_conf_dim_key: Option<String>,
+    pub(crate) dim_multi_values: Option<bool>,
+    pub(crate) dim_exclusive_rec: Option<bool>,
+    pub(crate) dim_data_type: Option<StatsDataTypeKind>,
+    pub(crate) dim_dynamic_url: Option<String>,
+    pub(crate) mes_data_distinct: Option<bool>,
+    pub(crate) mes_data_type: Option<StatsDataTypeKind>,
+    pub(crate) mes_frequency: Option<String>,
+    pub(crate) mes_unit: Option<String>,
+    pub(crate) mes_act_by_dim_conf_keys: Option<Vec<String>>,
+    pub(crate) rel_conf_fact_and_col_key: Option<String>,
+    pub(crate) create_time: DateTime<Utc>,
+    pub(crate) update_time: DateTime<Utc>,
+}
+
+impl StatsMemoryFactColConf {
+    pub(crate) fn to_resp(&self) -> StatsConfFactColInfoResp {
+        StatsConfFactColInfoResp {
+            key: self.key.clone(),
+            show_name: self.show_name.clone(),
+            kind: self.kind.clone(),
+            dim_rel_conf_dim_key: self.dim_rel_conf_dim_key.clone(),
+            dim_multi_values: self.dim_multi_values,
+            dim_data_type: self.dim_data_type.clone(),
+            dim_dynamic_url: self.dim_dynamic_url.clone(),
+            mes_data_distinct: self.mes_data_distinct,
+            mes_data_type: self.mes_data_type.clone(),
+            mes_frequency: self.mes_frequency.clone(),
+            mes_unit: self.mes_unit.clone(),
+            mes_act_by_dim_conf_keys: self.mes_act_by_dim_conf_keys.clone(),
+            rel_conf_fact_key: Some(self.rel_conf_fact_key.clone()),
+            rel_conf_fact_and_col_key: self.rel_conf_fact_and_col_key.clone(),
+            rel_external_id: Some(self.rel_external_id.clone()),
+            dim_exclusive_rec: self.dim_exclusive_rec.map(|dim_exclusive_rec| dim_exclusive_rec.to_string()),
+            remark: Some(self.remark.clone()),
+            create_time: self.create_time,
+            update_time: self.update_time,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct StatsMemoryDimRecord {
+    pub(crate) key: Value,
+    pub(crate) show_name: String,
+    pub(crate) hierarchy: Option<u64>,
+    /// The primary key values of each level, equivalent to the ``key0 .. keyN`` fields
+    pub(crate) hierarchy_keys: Vec<String>,
+    pub(crate) ct: DateTime<Utc>,
+    pub(crate) et: Option<DateTime<Utc>>,
+}
+
+impl StatsMemoryDimRecord {
+    /// Convert to the same structure as a row of the ``stats_inst_dim_<key>`` table
+    pub(crate) fn to_json(&self) -> Value {
+        let mut record = Map::new();
+        record.insert("key".to_string(), self.key.clone());
+        record.insert("show_name".to_string(), json!(self.show_name));
+        if let Some(hierarchy) = self.hierarchy {
+            record.insert("hierarchy".to_string(), json!(hierarchy));
+        }
+        for (idx, hierarchy_key) in self.hierarchy_keys.iter().enumerate() {
+            record.insert(format!("key{idx}"), json!(hierarchy_key));
+        }
+        record.insert("ct".to_string(), json!(self.ct));
+        record.insert("et".to_string(), json!(self.et));
+        Value::Object(record)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct StatsMemoryFactRecord {
+    pub(crate) key: String,
+    pub(crate) own_paths: String,
+    pub(crate) idempotent_id: String,
+    pub(crate) ext: Value,
+    /// Values of the fact columns
+    pub(crate) data: Map<String, Value>,
+    pub(crate) ct: DateTime<Utc>,
+}
+
+impl StatsMemoryFactRecord {
+    /// Convert to the same structure as a row of the ``stats_inst_fact_<key>`` table
+    pub(crate) fn to_json(&self) -> Value {
+        let mut record = Map::new();
+        record.insert("key".to_string(), json!(self.key));
+        record.insert("own_paths".to_string(), json!(self.own_paths));
+        record.insert("ext".to_string(), self.ext.clone());
+        record.insert("idempotent_id".to_string(), json!(self.idempotent_id));
+        for (k, v) in &self.data {
+            record.insert(k.to_string(), v.clone());
+        }
+        record.insert("ct".to_string(), json!(self.ct));
+        Value::Object(record)
+    }
+}
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    spi_initializer::common_memory::init::<StatsMemoryStore>(bs_cert, ctx, mgr).await
+}
@@ -0,0 +1,480 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::spi::{
+    spi_funs::SpiBsInst,
+    spi_initializer::common_memory::{self, SpiMemoryClient},
+};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone, Utc},
+    serde_json::{self, json, Map, Value},
+    TardisFunsInst,
+};
+
+use crate::{
+    dto::stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp},
+    serv::pg::stats_pg_metric_serv::{self, StatsConfInfo, FUNCTION_SUFFIX_FLAG},
+    stats_enumeration::{StatsDataTypeKind, StatsFactColKind, StatsQueryAggFunKind, StatsQueryTimeWindowKind},
+};
+
+use super::stats_memory_initializer::{StatsMemoryFactRecord, StatsMemoryStore};
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<StatsMemoryStore> {
+    inst.inst::<SpiMemoryClient<StatsMemoryStore>>().0
+}
+
+/// 查询指标.
+///
+/// The semantics are the same as the SQL assembled by the PostgreSQL implementation: filter, deduplicate and limit the fact records,
+/// then group them (with ``ROLLUP`` unless ``ignore_group_rollup``), aggregate, filter by ``having``, sort and limit the groups.
+///
+/// The only difference is that grouping by ``ct`` returns the aggregated value of each time window,
+/// rather than the window function result over the grouped rows of the PostgreSQL implementation.
+///
+/// 语义与PostgreSQL实现组装的SQL一致，唯一的区别是按``ct``分组时返回每个时间窗口的聚合值。
+pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<StatsQueryMetricsResp> {
+    let store = get_client(inst).read().await;
+    let (query_limit, dim_conf_info, measure_conf_info, conf_info) = stats_pg_metric_serv::package_conf_info(query_req, get_conf_info(query_req, &store), funs)?;
+    if !store.fact_online(&query_req.from) {
+        return Err(funs.err().conflict("metric", "query", "The fact config not online.", "409-spi-stats-fact-conf-not-online"));
+    }
+    let ct_agg = query_req.group.iter().any(|i| i.code == "ct");
+    let mes_distinct = query_req.select.iter().any(|i| measure_conf_info.get(&i.code).map(|conf| conf.mes_data_distinct.unwrap_or(true)).unwrap_or(false));
+
+    // Package filter, the legality of the conditions is the same as the PostgreSQL implementation
+    let mut wheres = vec![];
+    if let Some(or_wheres) = &query_req._where {
+        for and_wheres in or_wheres {
+            let mut conds = vec![];
+            for and_where in and_wheres {
+                let col_conf = conf_info.get(&and_where.code).ok_or_else(|| missing_conf_err("where", &and_where.code, funs))?;
+                let col_data_type = if col_conf.col_kind == StatsFactColKind::Dimension {
+                    col_conf.dim_data_type.as_ref()
+                } else {
+                    col_conf.mes_data_type.as_ref()
+                }
+                .ok_or_else(|| missing_conf_err("where", &and_where.code, funs))?;
+                let multi_values = col_conf.dim_multi_values.unwrap_or(false);
+                if col_data_type.to_pg_where(multi_values, &and_where.code, &and_where.op, 1, &and_where.value, &and_where.time_window)?.is_none() {
+                    return Err(funs.err().not_found(
+                        "metric",
+                        "query",
+                        &format!(
+                            "The query column=[{}] type=[{}] operation=[{}] time_window=[{}] multi_values=[{}] is not legal.",
+                            &and_where.code,
+                            col_data_type.to_string().to_lowercase(),
+                            &and_where.op.to_sql(),
+                            &and_where.time_window.is_some(),
+                            multi_values
+                        ),
+                        "404-spi-stats-metric-op-not-legal",
+                    ));
+                }
+                conds.push((and_where, col_data_type, multi_values));
+            }
+            wheres.push(conds);
+        }
+    }
+
+    // Package group, (column config, alias name, time window)
+    let mut groups = vec![];
+    for group in &query_req.group {
+        let col_conf = dim_conf_info.get(&group.code).ok_or_else(|| missing_conf_err("group", &group.code, funs))?;
+        let col_data_type = col_conf.dim_data_type.as_ref().ok_or_else(|| missing_conf_err("group", &group.code, funs))?;
+        if col_data_type.to_pg_group(&group.code, col_conf.dim_multi_values.unwrap_or(false), &group.time_window).is_none() {
+            return Err(funs.err().not_found(
+                "metric",
+                "query",
+                &format!(
+                    "The group column=[{}] type=[{}] time_window=[{}] is not legal.",
+                    &group.code,
+                    col_data_type.to_string().to_lowercase(),
+                    &group.time_window.is_some(),
+                ),
+                "404-spi-stats-metric-op-not-legal",
+            ));
+        }
+        let alias_name = format!(
+            "{}{FUNCTION_SUFFIX_FLAG}{}",
+            group.code,
+            group.time_window.as_ref().map(|i| i.to_string().to_lowercase()).unwrap_or_default()
+        );
+        groups.push((col_conf, alias_name, &group.time_window));
+    }
+
+    // Package select, (column config, alias name, function)
+    let mut selects = vec![];
+    for select in &query_req.select {
+        let col_conf = measure_conf_info.get(&select.code).ok_or_else(|| missing_conf_err("select", &select.code, funs))?;
+        col_conf.mes_data_type.as_ref().ok_or_else(|| missing_conf_err("select", &select.code, funs))?;
+        let alias_name = format!("{}{FUNCTION_SUFFIX_FLAG}{}", select.code, select.fun.to_string().to_lowercase());
+        selects.push((col_conf, alias_name, &select.fun));
+    }
+
+    // Package having, (index of the select, having)
+    let mut havings = vec![];
+    for having in query_req.having.iter().flatten() {
+        let col_conf = conf_info.get(&having.code).ok_or_else(|| missing_conf_err("having", &having.code, funs))?;
+        let mes_data_type = col_conf.mes_data_type.as_ref().ok_or_else(|| missing_conf_err("having", &having.code, funs))?;
+        if mes_data_type.to_pg_having(false, &having.code, &having.op, 1, &having.value, Some(&having.fun))?.is_none() {
+            return Err(funs.err().not_found(
+                "metric",
+                "query",
+                &format!(
+                    "The query column=[{}] type=[{}] operation=[{}] fun=[{}] is not legal.",
+                    &having.code,
+                    mes_data_type.to_string().to_lowercase(),
+                    &having.op.to_sql(),
+                    &having.fun.to_string().to_lowercase()
+                ),
+                "404-spi-stats-metric-op-not-legal",
+            ));
+        }
+        let select_idx =
+            query_req.select.iter().position(|select| select.code == having.code && select.fun == having.fun).ok_or_else(|| missing_conf_err("having", &having.code, funs))?;
+        havings.push((select_idx, having));
+    }
+
+    // Filter by ownership, time range and deleted records
+    let del_records = store.fact_del_records.get(&query_req.from).map(|records| records.as_slice()).unwrap_or_default();
+    let mut records = store
+        .fact_records
+        .get(&query_req.from)
+        .into_iter()
+        .flatten()
+        .filter(|record| {
+            if let Some(own_paths) = &query_req.own_paths {
+                own_paths.contains(&record.own_paths)
+            } else {
+                record.own_paths.starts_with(&ctx.own_paths)
+            }
+        })
+        .filter(|record| record.ct >= query_req.start_time && record.ct <= query_req.end_time)
+        .filter(|record| !del_records.iter().any(|(key, ct)| key == &record.key && ct >= &query_req.start_time && ct <= &query_req.end_time))
+        .collect::<Vec<_>>();
+    // Deduplicate, keep the latest record of each key (of each day when grouping by ct), same as ``DISTINCT ON``
+    if !query_req.ignore_distinct.unwrap_or(false) && mes_distinct {
+        records.sort_by(|a, b| a.key.cmp(&b.key).then(b.ct.cmp(&a.ct)));
+        let mut distinct_keys = HashSet::new();
+        records.retain(|record| distinct_keys.insert(if ct_agg { format!("{}:{}", record.key, record.ct.day()) } else { record.key.clone() }));
+    } else {
+        records.sort_by(|a, b| b.ct.cmp(&a.ct));
+    }
+    records.retain(|record| {
+        wheres.is_empty()
+            || wheres.iter().any(|conds| {
+                conds.iter().all(|(and_where, col_data_type, multi_values)| {
+                    let value = get_col_value(record, &and_where.code, and_where.rel_external_id.as_ref().is_some_and(|i| !i.is_empty()));
+                    let value = if *multi_values {
+                        Some(Value::Array(get_multi_values(value)))
+                    } else if let Some(time_window) = &and_where.time_window {
+                        Some(time_window_value(value.as_ref(), time_window))
+                    } else if **col_data_type == StatsDataTypeKind::Date || **col_data_type == StatsDataTypeKind::DateTime {
+                        Some(value.unwrap_or(json!("1970-01-01T00:00:00Z")))
+                    } else {
+                        value
+                    };
+                    common_memory::match_cond(value.as_ref(), &and_where.op, &and_where.value)
+                })
+            })
+    });
+    if let Some(orders) = &query_req.dimension_order {
+        let mut order_confs = vec![];
+        for order in orders {
+            let col_conf = dim_conf_info.get(&order.code).ok_or_else(|| missing_conf_err("order", &order.code, funs))?;
+            order_confs.push((order, is_ext(col_conf)));
+        }
+        records.sort_by(|a, b| {
+            order_confs.iter().fold(Ordering::Equal, |ordering, (order, from_ext)| {
+                ordering.then_with(|| {
+                    compare_nullable(
+                        get_col_value(a, &order.code, *from_ext).as_ref(),
+                        get_col_value(b, &order.code, *from_ext).as_ref(),
+                        order.asc,
+                    )
+                })
+            })
+        });
+    }
+    records.truncate(query_limit.max(0) as usize);
+
+    // Group, each level of the rollup is a prefix of the group values, the missing values are regarded as ``ROLLUP``
+    let levels = if groups.is_empty() {
+        vec![0]
+    } else if query_req.ignore_group_rollup.unwrap_or(false) {
+        vec![groups.len()]
+    } else {
+        (0..=groups.len()).rev().collect()
+    };
+    let mut group_rows: HashMap<String, (Vec<Option<Value>>, Vec<&StatsMemoryFactRecord>)> = HashMap::new();
+    if levels.contains(&0) {
+        let group_values = vec![None; groups.len()];
+        group_rows.insert(format!("{group_values:?}"), (group_values, vec![]));
+    }
+    for &record in &records {
+        let mut group_values_set: Vec<Vec<Value>> = vec![vec![]];
+        for (col_conf, _, time_window) in &groups {
+            let values = get_group_values(get_col_value(record, &col_conf.col_key, is_ext(col_conf)), col_conf, time_window);
+            group_values_set = group_values_set.into_iter().flat_map(|prefix| values.iter().map(move |value| [prefix.clone(), vec![value.clone()]].concat())).collect();
+        }
+        for group_values in &group_values_set {
+            for level in &levels {
+                let group_values = (0..groups.len()).map(|idx| if idx < *level { Some(group_values[idx].clone()) } else { None }).collect::<Vec<_>>();
+                group_rows.entry(format!("{group_values:?}")).or_insert_with(|| (group_values, vec![])).1.push(record);
+            }
+        }
+    }
+
+    // Aggregate and filter by having
+    let mut rows = group_rows
+        .into_values()
+        .map(|(group_values, records)| {
+            let measure_values = selects
+                .iter()
+                .map(|(col_conf, _, fun)| aggregate(records.iter().map(|record| get_col_value(record, &col_conf.col_key, is_ext(col_conf))), fun))
+                .collect::<Vec<_>>();
+            (group_values, measure_values, records)
+        })
+        .filter(|(_, measure_values, _)| {
+            havings.iter().all(|(select_idx, having)| {
+                measure_values[*select_idx].map(|measure_value| common_memory::match_cond(Some(&json!(measure_value)), &having.op, &having.value)).unwrap_or(false)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Sort and limit
+    rows.sort_by(|(a, _, _), (b, _, _)| a.iter().zip(b).fold(Ordering::Equal, |ordering, (a, b)| ordering.then_with(|| compare_nullable(a.as_ref(), b.as_ref(), true))));
+    if query_req.group_order.is_some() || query_req.metrics_order.is_some() {
+        let mut orders = vec![];
+        for order in query_req.group_order.iter().flatten() {
+            let group_idx = query_req
+                .group
+                .iter()
+                .position(|group| group.code == order.code && group.time_window == order.time_window)
+                .ok_or_else(|| missing_conf_err("order", &order.code, funs))?;
+            orders.push((true, group_idx, order.asc));
+        }
+        for order in query_req.metrics_order.iter().flatten() {
+            let select_idx =
+                query_req.select.iter().position(|select| select.code == order.code && select.fun == order.fun).ok_or_else(|| missing_conf_err("order", &order.code, funs))?;
+            orders.push((false, select_idx, order.asc));
+        }
+        rows.sort_by(|(a_group_values, a_measure_values, _), (b_group_values, b_measure_values, _)| {
+            orders.iter().fold(Ordering::Equal, |ordering, (is_group, idx, asc)| {
+                ordering.then_with(|| {
+                    if *is_group {
+                        compare_nullable(a_group_values[*idx].as_ref(), b_group_values[*idx].as_ref(), *asc)
+                    } else {
+                        compare_nullable(a_measure_values[*idx].map(|v| json!(v)).as_ref(), b_measure_values[*idx].map(|v| json!(v)).as_ref(), *asc)
+                    }
+                })
+            })
+        });
+    }
+    if let Some(limit) = query_req.limit {
+        rows.truncate(limit as usize);
+    }
+
+    // Package result, the same structure as the rows returned by the PostgreSQL implementation
+    let ignore_group_agg = groups.is_empty() || !query_req.group_agg.unwrap_or(false);
+    let result = rows
+        .into_iter()
+        .map(|(group_values, measure_values, records)| {
+            let mut row = Map::new();
+            for ((_, alias_name, _), group_value) in groups.iter().zip(group_values) {
+                row.insert(alias_name.clone(), group_value.unwrap_or(Value::Null));
+            }
+            for ((_, alias_name, fun), measure_value) in selects.iter().zip(measure_values) {
+                let measure_value = match measure_value {
+                    Some(measure_value) if **fun == StatsQueryAggFunKind::Count => json!(measure_value as i64),
+                    Some(measure_value) => decimal_to_json(measure_value),
+                    None => Value::Null,
+                };
+                row.insert(alias_name.clone(), measure_value);
+            }
+            if !ignore_group_agg {
+                let s_agg = if records.is_empty() {
+                    Value::Null
+                } else {
+                    json!(records.iter().map(|record| format!("{} - {} - {}", record.key, record.own_paths, record.ct.format("%Y-%m-%d %H:%M:%S"))).collect::<Vec<_>>().join(","))
+                };
+                row.insert("s_agg".to_string(), s_agg);
+            }
+            Value::Object(row)
+        })
+        .collect::<Vec<_>>();
+
+    let select_dimension_keys = groups.iter().map(|(_, alias_name, _)| alias_name.clone()).collect::<Vec<_>>();
+    let select_measure_keys = selects.iter().map(|(_, alias_name, _)| alias_name.clone()).collect::<Vec<_>>();
+    let show_names = groups
+        .iter()
+        .map(|(col_conf, alias_name, _)| (alias_name.clone(), col_conf.show_name.clone()))
+        .chain(selects.iter().map(|(col_conf, alias_name, _)| (alias_name.clone(), col_conf.show_name.clone())))
+        .collect::<HashMap<String, String>>();
+    // The dimension records are read through the store again
+    drop(store);
+    let dim_record_agg = stats_pg_metric_serv::package_dim_record_agg(conf_info.clone(), funs, ctx).await?;
+    Ok(StatsQueryMetricsResp {
+        from: query_req.from.to_string(),
+        show_names,
+        group: stats_pg_metric_serv::package_groups(dim_record_agg, conf_info, select_dimension_keys, &select_measure_keys, ignore_group_agg, result)
+            .map_err(|msg| TardisError::internal_error(&format!("Fail to package groups: {msg}"), "500-spi-stats-internal-error"))?,
+    })
+}
+
+/// Get the column configs of the queried fact, same as the join of the column, fact and dimension configs in the PostgreSQL implementation
+fn get_conf_info(query_req: &StatsQueryMetricsReq, store: &StatsMemoryStore) -> Vec<StatsConfInfo> {
+    let Some(fact_conf) = store.fact_confs.iter().find(|fact_conf| fact_conf.key == query_req.from) else {
+        return vec![];
+    };
+    let rel_external_ids = stats_pg_metric_serv::package_rel_external_id_agg(query_req);
+    store
+        .fact_col_confs
+        .iter()
+        .filter(|col_conf| col_conf.rel_conf_fact_key == fact_conf.key && col_conf.kind != StatsFactColKind::Ext)
+        .filter(|col_conf| rel_external_ids.as_ref().map(|rel_external_ids| rel_external_ids.contains(&col_conf.rel_external_id)).unwrap_or(col_conf.rel_external_id.is_empty()))
+        .map(|col_conf| {
+            let dim_conf = col_conf.dim_rel_conf_dim_key.as_ref().and_then(|dim_conf_key| store.dim_confs.iter().find(|dim_conf| &dim_conf.key == dim_conf_key));
+            StatsConfInfo {
+                col_key: col_conf.key.clone(),
+                show_name: col_conf.show_name.clone(),
+                col_kind: col_conf.kind.clone(),
+                dim_multi_values: col_conf.dim_multi_values,
+                mes_data_distinct: col_conf.mes_data_distinct,
+                mes_data_type: col_conf.mes_data_type.clone(),
+                dim_rel_conf_dim_key: col_conf.dim_rel_conf_dim_key.clone(),
+                dim_data_type: Some(dim_conf.map(|dim_conf| dim_conf.data_type.clone()).or_else(|| col_conf.dim_data_type.clone()).unwrap_or(StatsDataTypeKind::String)),
+                dim_hierarchy: dim_conf.map(|dim_conf| dim_conf.hierarchy.clone()),
+                rel_external_id: Some(col_conf.rel_external_id.clone()),
+                query_limit: fact_conf.query_limit,
+            }
+        })
+        .collect()
+}
+
+fn missing_conf_err(kind: &str, code: &str, funs: &TardisFunsInst) -> TardisError {
+    funs.err().not_found(
+        "metric",
+        "query",
+        &format!("Missing config for {kind} code [{code}] does not exist."),
+        "500-spi-stats-internal-error",
+    )
+}
+
+fn is_ext(col_conf: &StatsConfInfo) -> bool {
+    col_conf.rel_external_id.as_ref().is_some_and(|i| !i.is_empty())
+}
+
+/// Get the value of the column from the fact record, the extended columns are stored in the ``ext`` field
+fn get_col_value(record: &StatsMemoryFactRecord, code: &str, from_ext: bool) -> Option<Value> {
+    match code {
+        "key" => Some(json!(record.key)),
+        "ct" => Some(json!(record.ct)),
+        "_count" => Some(json!(1)),
+        _ if from_ext => record.ext.get(code).cloned(),
+        _ => record.data.get(code).cloned(),
+    }
+    .filter(|value| !value.is_null())
+}
+
+/// Get the elements of the multi-valued column, the extended column may store the array as a JSON string
+fn get_multi_values(value: Option<Value>) -> Vec<Value> {
+    match value {
+        Some(Value::Array(values)) => values,
+        Some(Value::String(value)) => serde_json::from_str::<Vec<Value>>(&value).unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// Same as ``StatsDataTypeKind::to_pg_group``, the multi-valued column is expanded to one value per element and the empty one is regarded as ``""``
+fn get_group_values(value: Option<Value>, col_conf: &StatsConfInfo, time_window: &Option<StatsQueryTimeWindowKind>) -> Vec<Value> {
+    if col_conf.dim_multi_values.unwrap_or(false) {
+        let values = get_multi_values(value);
+        return if values.is_empty() { vec![json!("")] } else { values };
+    }
+    if let Some(time_window) = time_window {
+        return vec![time_window_value(value.as_ref(), time_window)];
+    }
+    vec![match col_conf.dim_data_type.as_ref().unwrap_or(&StatsDataTypeKind::String) {
+        StatsDataTypeKind::String => value.unwrap_or(json!("")),
+        StatsDataTypeKind::Int | StatsDataTypeKind::Float | StatsDataTypeKind::Double => decimal_to_json(to_decimal(value.as_ref())),
+        StatsDataTypeKind::Boolean => value.unwrap_or(json!(false)),
+        StatsDataTypeKind::Date | StatsDataTypeKind::DateTime => value.unwrap_or(Value::Null),
+    }]
+}
+
+/// Same as ``StatsQueryTimeWindowKind::to_sql``, the time is converted to the ``Asia/Shanghai`` time zone,
+/// and the missing time is regarded as ``1970-01-01 00:00:00 +00:00``
+fn time_window_value(value: Option<&Value>, time_window: &StatsQueryTimeWindowKind) -> Value {
+    let time = value
+        .and_then(Value::as_str)
+        .and_then(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.with_timezone(&Utc))
+                .ok()
+                .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)).map(|time| Utc.from_utc_datetime(&time)))
+        })
+        .or_else(|| Utc.timestamp_opt(0, 0).single());
+    let Some(time) = time.zip(FixedOffset::east_opt(8 * 3600)).map(|(time, offset)| time.with_timezone(&offset)) else {
+        return Value::Null;
+    };
+    json!(match time_window {
+        StatsQueryTimeWindowKind::Date | StatsQueryTimeWindowKind::Day => time.format("%Y-%m-%d").to_string(),
+        StatsQueryTimeWindowKind::Hour => time.format("%Y-%m-%d %H").to_string(),
+        StatsQueryTimeWindowKind::Week => format!("{} {}", time.iso_week().year(), time.iso_week().week()),
+        StatsQueryTimeWindowKind::Month => time.format("%Y-%m").to_string(),
+        StatsQueryTimeWindowKind::Year => time.format("%Y").to_string(),
+    })
+}
+
+/// Same as ``COALESCE(<value>::decimal,0)``
+fn to_decimal(value: Option<&Value>) -> f64 {
+    match value {
+        Some(Value::Number(value)) => value.as_f64().unwrap_or_default(),
+        Some(Value::String(value)) => value.parse().unwrap_or_default(),
+        _ => 0.0,
+    }
+}
+
+/// The ``decimal`` values are returned as strings by the PostgreSQL implementation
+fn decimal_to_json(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        json!((value as i64).to_string())
+    } else {
+        json!(value.to_string())
+    }
+}
+
+/// Same as ``StatsQueryAggFunKind::to_sql``, the aggregated value of no records is ``None`` except for ``count``
+fn aggregate(values: impl Iterator<Item = Option<Value>>, fun: &StatsQueryAggFunKind) -> Option<f64> {
+    if fun == &StatsQueryAggFunKind::Count {
+        return Some(values.flatten().count() as f64);
+    }
+    let values = values.map(|value| to_decimal(value.as_ref())).collect::<Vec<_>>();
+    if values.is_empty() {
+        return None;
+    }
+    Some(match fun {
+        StatsQueryAggFunKind::Sum => values.iter().sum(),
+        StatsQueryAggFunKind::Avg => values.iter().sum::<f64>() / values.len() as f64,
+        StatsQueryAggFunKind::Max => values.iter().copied().fold(f64::MIN, f64::max),
+        StatsQueryAggFunKind::Min => values.iter().copied().fold(f64::MAX, f64::min),
+        StatsQueryAggFunKind::Count => values.len() as f64,
+    })
+}
+
+/// Same as the default ``ORDER BY`` of PostgreSQL, the null values are last in ascending order and first in descending order
+fn compare_nullable(a: Option<&Value>, b: Option<&Value>, asc: bool) -> Ordering {
+    let ordering = match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => common_memory::compare_value(a, b).unwrap_or_else(|| a.to_string().cmp(&b.to_string())),
+    };
+    if asc {
+        ordering
+    } else {
+        ordering.reverse()
+    }
+}
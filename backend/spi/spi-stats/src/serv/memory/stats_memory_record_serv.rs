@@ -0,0 +1,538 @@
+use std::collections::HashSet;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_memory::SpiMemoryClient};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    serde_json::{self, json, Map, Value},
+    web::web_resp::TardisPage,
+    TardisFunsInst,
+};
+
+use crate::{
+    dto::stats_record_dto::{StatsDimRecordAddReq, StatsFactRecordLoadReq, StatsFactRecordsLoadReq},
+    stats_enumeration::{StatsDataTypeKind, StatsFactColKind},
+};
+
+use super::stats_memory_initializer::{StatsMemoryDimRecord, StatsMemoryFactColConf, StatsMemoryFactRecord, StatsMemoryStore};
+
+fn get_client(inst: &SpiBsInst) -> &SpiMemoryClient<StatsMemoryStore> {
+    inst.inst::<SpiMemoryClient<StatsMemoryStore>>().0
+}
+
+fn check_fact_online(fact_conf_key: &str, op: &str, store: &StatsMemoryStore, funs: &TardisFunsInst) -> TardisResult<()> {
+    if !store.fact_online(fact_conf_key) {
+        return Err(funs.err().conflict("fact_record", op, "The fact config not online.", "409-spi-stats-fact-conf-not-online"));
+    }
+    Ok(())
+}
+
+fn check_dim_online(dim_conf_key: &str, op: &str, store: &StatsMemoryStore, funs: &TardisFunsInst) -> TardisResult<()> {
+    if !store.dim_online(dim_conf_key) {
+        return Err(funs.err().conflict("dim_record", op, "The dimension config not online.", "409-spi-stats-dim-conf-not-online"));
+    }
+    Ok(())
+}
+
+/// Get the basic fact column configs (without extended columns), same as ``find_by_fact_conf_key``
+fn get_fact_col_confs(fact_conf_key: &str, store: &StatsMemoryStore) -> Vec<StatsMemoryFactColConf> {
+    store.fact_col_confs.iter().filter(|col_conf| col_conf.rel_conf_fact_key == fact_conf_key && col_conf.rel_external_id.is_empty()).cloned().collect()
+}
+
+/// Get the data type of the fact column, the dimension column uses the data type of the associated dimension config
+fn get_fact_col_data_type(fact_col_conf: &StatsMemoryFactColConf, op: &str, store: &StatsMemoryStore, funs: &TardisFunsInst) -> TardisResult<Option<StatsDataTypeKind>> {
+    match fact_col_conf.kind {
+        StatsFactColKind::Dimension => {
+            let Some(dim_conf_key) = fact_col_conf.dim_rel_conf_dim_key.as_ref() else {
+                return Err(funs.err().not_found("fact_record", op, "Fail to get conf_dim_key", "400-spi-stats-fail-to-get-dim-config-key"));
+            };
+            let Some(dim_conf) = store.dim_confs.iter().find(|dim_conf| &dim_conf.key == dim_conf_key) else {
+                return Err(funs.err().not_found(
+                    "fact_record",
+                    op,
+                    &format!("Fail to get dim_conf by key [{dim_conf_key}]"),
+                    "400-spi-stats-fail-to-get-dim-config-key",
+                ));
+            };
+            Ok(Some(dim_conf.data_type.clone()))
+        }
+        StatsFactColKind::Measure => {
+            let Some(mes_data_type) = fact_col_conf.mes_data_type.as_ref() else {
+                return Err(funs.err().bad_request(
+                    "fact_record",
+                    op,
+                    "Col_conf.mes_data_type shouldn't be empty while fact_col_conf.kind is Measure",
+                    "400-spi-stats-invalid-request",
+                ));
+            };
+            Ok(Some(mes_data_type.clone()))
+        }
+        StatsFactColKind::Ext => Ok(None),
+    }
+}
+
+/// Check the value of the fact column, the rules are the same as the conversion to the column value in the PostgreSQL implementation
+fn check_fact_col_value(fact_col_conf: &StatsMemoryFactColConf, value: &Value, op: &str, store: &StatsMemoryStore, funs: &TardisFunsInst) -> TardisResult<()> {
+    match get_fact_col_data_type(fact_col_conf, op, store, funs)? {
+        Some(data_type) if fact_col_conf.kind == StatsFactColKind::Dimension && fact_col_conf.dim_multi_values.unwrap_or(false) => {
+            data_type.json_to_sea_orm_value_array(value, false)?;
+        }
+        Some(data_type) => {
+            data_type.json_to_sea_orm_value(value, false)?;
+        }
+        None => {
+            if !value.is_string() {
+                return Err(funs.err().bad_request(
+                    "fact_record",
+                    op,
+                    &format!("For the key [{}], value: [{value}] is not a string", fact_col_conf.key),
+                    "400-spi-stats-invalid-request",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Same as the default values of the columns in the PostgreSQL implementation
+fn default_value(data_type: &StatsDataTypeKind) -> Value {
+    match data_type {
+        StatsDataTypeKind::String => json!(""),
+        StatsDataTypeKind::Int => json!(0),
+        StatsDataTypeKind::Float | StatsDataTypeKind::Double => json!(0.0),
+        StatsDataTypeKind::Boolean => json!(false),
+        StatsDataTypeKind::Date => json!(Utc::now().date_naive()),
+        StatsDataTypeKind::DateTime => json!(Utc::now()),
+    }
+}
+
+fn merge(a: &mut Value, b: Value) {
+    match (a, b) {
+        (a @ &mut Value::Object(_), Value::Object(b)) => {
+            if let Some(a) = a.as_object_mut() {
+                for (k, v) in b {
+                    merge(a.entry(k).or_insert(Value::Null), v);
+                }
+            }
+        }
+        (a, b) => *a = b,
+    }
+}
+
+pub(crate) async fn get_fact_record_latest(
+    fact_conf_key: &str,
+    fact_record_keys: impl IntoIterator<Item = &str>,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Vec<Value>> {
+    let store = get_client(inst).read().await;
+    check_fact_online(fact_conf_key, "load", &store, funs)?;
+    let records = store.fact_records.get(fact_conf_key).map(|records| records.as_slice()).unwrap_or_default();
+    let mut keys = HashSet::new();
+    Ok(fact_record_keys
+        .into_iter()
+        .filter(|key| keys.insert(key.to_string()))
+        .filter_map(|key| records.iter().filter(|record| record.key == key).max_by_key(|record| record.ct))
+        .map(|record| record.to_json())
+        .collect())
+}
+
+pub(crate) async fn get_fact_record_paginated(
+    fact_conf_key: &str,
+    fact_record_key: &str,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<Value>> {
+    let store = get_client(inst).read().await;
+    check_fact_online(fact_conf_key, "load", &store, funs)?;
+    let mut records = store.fact_records.get(fact_conf_key).into_iter().flatten().filter(|record| record.key == fact_record_key).collect::<Vec<_>>();
+    if let Some(desc_by_create) = desc_by_create {
+        records.sort_by(|a, b| if desc_by_create { b.ct.cmp(&a.ct) } else { a.ct.cmp(&b.ct) });
+    }
+    let total_size = records.len() as u64;
+    let records = records.into_iter().skip(((page_number.max(1) - 1) * page_size) as usize).take(page_size as usize).map(|record| record.to_json()).collect();
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size,
+        records,
+    })
+}
+
+pub(crate) async fn fact_record_load(
+    fact_conf_key: &str,
+    fact_record_key: &str,
+    add_req: StatsFactRecordLoadReq,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_fact_online(fact_conf_key, "load", &store, funs)?;
+    let fact_col_conf_set = get_fact_col_confs(fact_conf_key, &store);
+    let req_data = add_req.data.as_object().ok_or_else(|| funs.err().bad_request("fact_record", "load", "Data should be an map", "400-spi-stats-invalid-request"))?;
+    // 如果存在幂等id 且已经存在对应数据,则根据丢弃数据
+    if let Some(idempotent_id) = &add_req.idempotent_id {
+        if store.fact_records.get(fact_conf_key).into_iter().flatten().any(|record| &record.idempotent_id == idempotent_id) {
+            if !add_req.ignore_updates.unwrap_or(true) {
+                return fact_records_modify(fact_conf_key, idempotent_id, req_data, &fact_col_conf_set, &mut *store, funs);
+            }
+            return Ok(());
+        }
+    }
+    let latest_record = store.fact_records.get(fact_conf_key).into_iter().flatten().filter(|record| record.key == fact_record_key).max_by_key(|record| record.ct).cloned();
+    let mut data = Map::new();
+    for (req_fact_col_key, req_fact_col_value) in req_data {
+        let fact_col_conf = fact_col_conf_set.iter().find(|col_conf| &col_conf.key == req_fact_col_key).ok_or_else(|| {
+            funs.err().not_found(
+                "fact_record",
+                "load",
+                &format!("The fact column config [{req_fact_col_key}] not exists."),
+                "404-spi-stats-fact-col-conf-not-exist",
+            )
+        })?;
+        check_fact_col_value(fact_col_conf, req_fact_col_value, "load", &store, funs)?;
+        data.insert(req_fact_col_key.to_string(), req_fact_col_value.clone());
+    }
+    // The missing columns are filled with the latest record, or the default values if there is no latest record
+    for fact_col_conf in &fact_col_conf_set {
+        if data.contains_key(&fact_col_conf.key) {
+            continue;
+        }
+        if let Some(latest_record) = &latest_record {
+            if let Some(value) = latest_record.data.get(&fact_col_conf.key) {
+                data.insert(fact_col_conf.key.clone(), value.clone());
+            }
+        } else if let Some(data_type) = get_fact_col_data_type(fact_col_conf, "load", &store, funs)? {
+            let value = default_value(&data_type);
+            if fact_col_conf.kind == StatsFactColKind::Dimension && fact_col_conf.dim_multi_values.unwrap_or(false) {
+                data.insert(fact_col_conf.key.clone(), json!([value]));
+            } else {
+                data.insert(fact_col_conf.key.clone(), value);
+            }
+        }
+    }
+    let mut ext = latest_record.map(|latest_record| latest_record.ext).unwrap_or_else(|| json!({}));
+    merge(&mut ext, add_req.ext.unwrap_or_else(|| json!({})));
+    store.fact_records.entry(fact_conf_key.to_string()).or_default().push(StatsMemoryFactRecord {
+        key: fact_record_key.to_string(),
+        own_paths: add_req.own_paths,
+        idempotent_id: add_req.idempotent_id.unwrap_or_default(),
+        ext,
+        data,
+        ct: add_req.ct,
+    });
+    Ok(())
+}
+
+pub(crate) async fn fact_records_load(
+    fact_conf_key: &str,
+    add_req_set: Vec<StatsFactRecordsLoadReq>,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_fact_online(fact_conf_key, "load_set", &store, funs)?;
+    let fact_col_conf_set = get_fact_col_confs(fact_conf_key, &store);
+    let mut new_records = vec![];
+    for add_req in add_req_set {
+        let Some(req_data) = add_req.data.as_object() else {
+            return Err(funs.err().bad_request(
+                "fact_record",
+                "load_set",
+                &format!("add_req.data should be a map value, got {data}", data = add_req.data),
+                "400-spi-stats-invalid-request",
+            ));
+        };
+        // 如果存在幂等id 且已经存在对应数据,则丢弃数据
+        if let Some(idempotent_id) = &add_req.idempotent_id {
+            if store.fact_records.get(fact_conf_key).into_iter().flatten().any(|record| &record.idempotent_id == idempotent_id) {
+                if !add_req.ignore_updates.unwrap_or(true) {
+                    return fact_records_modify(fact_conf_key, idempotent_id, req_data, &fact_col_conf_set, &mut *store, funs);
+                }
+                continue;
+            }
+        }
+        let mut data = Map::new();
+        for fact_col_conf in &fact_col_conf_set {
+            if data.contains_key(&fact_col_conf.key) {
+                continue;
+            }
+            let req_fact_col_value = req_data.get(&fact_col_conf.key).ok_or_else(|| {
+                funs.err().bad_request(
+                    "fact_record",
+                    "load_set",
+                    &format!(
+                        "The fact instance record [{}][{}] is missing a required column [{}].",
+                        fact_conf_key, add_req.key, fact_col_conf.key
+                    ),
+                    "400-spi-stats-fact-inst-record-missing-column",
+                )
+            })?;
+            check_fact_col_value(fact_col_conf, req_fact_col_value, "load_set", &store, funs)?;
+            data.insert(fact_col_conf.key.clone(), req_fact_col_value.clone());
+        }
+        new_records.push(StatsMemoryFactRecord {
+            key: add_req.key,
+            own_paths: add_req.own_paths,
+            idempotent_id: add_req.idempotent_id.unwrap_or_default(),
+            ext: add_req.ext.unwrap_or_else(|| json!({})),
+            data,
+            ct: add_req.ct,
+        });
+    }
+    store.fact_records.entry(fact_conf_key.to_string()).or_default().extend(new_records);
+    Ok(())
+}
+
+fn fact_records_modify(
+    fact_conf_key: &str,
+    idempotent_id: &str,
+    req_data: &Map<String, Value>,
+    fact_col_conf_set: &[StatsMemoryFactColConf],
+    store: &mut StatsMemoryStore,
+    funs: &TardisFunsInst,
+) -> TardisResult<()> {
+    if req_data.is_empty() {
+        return Err(funs.err().bad_request("fact_record", "load", "The fact column no data", "400-spi-stats-invalid-request"));
+    }
+    for (req_fact_col_key, req_fact_col_value) in req_data {
+        let fact_col_conf = fact_col_conf_set.iter().find(|col_conf| &col_conf.key == req_fact_col_key).ok_or_else(|| {
+            funs.err().not_found(
+                "fact_record",
+                "load",
+                &format!("The fact column config [{req_fact_col_key}] not exists."),
+                "404-spi-stats-fact-col-conf-not-exist",
+            )
+        })?;
+        check_fact_col_value(fact_col_conf, req_fact_col_value, "load", store, funs)?;
+    }
+    for record in store.fact_records.get_mut(fact_conf_key).into_iter().flatten().filter(|record| record.idempotent_id == idempotent_id) {
+        for (req_fact_col_key, req_fact_col_value) in req_data {
+            record.data.insert(req_fact_col_key.to_string(), req_fact_col_value.clone());
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn fact_record_delete(fact_conf_key: &str, fact_record_key: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    fact_records_delete(fact_conf_key, &[fact_record_key.to_string()], funs, ctx, inst).await
+}
+
+pub(crate) async fn fact_records_delete(
+    fact_conf_key: &str,
+    fact_record_delete_keys: &[String],
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_fact_online(fact_conf_key, "delete_set", &store, funs)?;
+    let now = Utc::now();
+    store.fact_del_records.entry(fact_conf_key.to_string()).or_default().extend(fact_record_delete_keys.iter().map(|key| (key.to_string(), now)));
+    Ok(())
+}
+
+pub(crate) async fn fact_records_delete_by_ownership(fact_conf_key: &str, own_paths: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_fact_online(fact_conf_key, "delete_set", &store, funs)?;
+    if let Some(records) = store.fact_records.get_mut(fact_conf_key) {
+        records.retain(|record| record.own_paths != own_paths);
+    }
+    Ok(())
+}
+
+pub(crate) async fn fact_records_delete_by_dim_key(
+    fact_conf_key: &str,
+    dim_conf_key: &str,
+    dim_record_key: Option<serde_json::Value>,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_fact_online(fact_conf_key, "delete_set", &store, funs)?;
+    let Some(dim_conf) = store.dim_confs.iter().find(|dim_conf| dim_conf.key == dim_conf_key) else {
+        return Err(funs.err().not_found("fact_record", "find", "The dimension config does not exist.", "404-spi-stats-dim-conf-not-exist"));
+    };
+    if let Some(dim_record_key) = &dim_record_key {
+        dim_conf.data_type.json_to_sea_orm_value(dim_record_key, false)?;
+    }
+    // Consistent with the PostgreSQL implementation, the first column is used when there is no column associated with the dimension
+    let fact_col_conf_set = get_fact_col_confs(fact_conf_key, &store);
+    let fact_col_conf_key = fact_col_conf_set
+        .iter()
+        .find(|col_conf| col_conf.dim_rel_conf_dim_key.as_deref() == Some(dim_conf_key))
+        .or_else(|| fact_col_conf_set.first())
+        .ok_or_else(|| funs.err().not_found("fact_record", "delete_set", "The fact config does not exist.", "404-spi-stats-fact-conf-not-exist"))?
+        .key
+        .clone();
+    let mut delete_keys = vec![];
+    for record in store.fact_records.get(fact_conf_key).into_iter().flatten() {
+        let matched = dim_record_key.as_ref().map(|dim_record_key| record.data.get(&fact_col_conf_key) == Some(dim_record_key)).unwrap_or(true);
+        if matched && !delete_keys.contains(&record.key) {
+            delete_keys.push(record.key.clone());
+        }
+    }
+    let now = Utc::now();
+    store.fact_del_records.entry(fact_conf_key.to_string()).or_default().extend(delete_keys.into_iter().map(|key| (key, now)));
+    Ok(())
+}
+
+pub(crate) async fn fact_records_clean(fact_conf_key: &str, before_ct: Option<DateTime<Utc>>, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_fact_online(fact_conf_key, "clean", &store, funs)?;
+    if let Some(records) = store.fact_records.get_mut(fact_conf_key) {
+        if let Some(before_ct) = before_ct {
+            records.retain(|record| record.ct > before_ct);
+        } else {
+            records.clear();
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn dim_record_add(dim_conf_key: String, add_req: StatsDimRecordAddReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_dim_online(&dim_conf_key, "add", &store, funs)?;
+    let Some(dim_conf) = store.dim_confs.iter().find(|dim_conf| dim_conf.key == dim_conf_key).cloned() else {
+        return Err(funs.err().not_found("dim_record", "add", "The dimension config does not exist.", "404-spi-stats-dim-conf-not-exist"));
+    };
+    if !dim_conf.stable_ds {
+        return Err(funs.err().bad_request(
+            "dim_record",
+            "add",
+            &format!("The dimension config [{}] stable_ds is false, so adding dimension records is not supported.", &dim_conf_key),
+            "400-spi-stats-dim-conf-stable-ds-false",
+        ));
+    }
+    if dim_conf.hierarchy.is_empty() && add_req.parent_key.is_some() {
+        return Err(funs.err().bad_request(
+            "dim_record",
+            "add",
+            &format!("The dimension config [{}] not allow hierarchy.", &dim_conf_key),
+            "400-spi-stats-dim-conf-not-hierarchy",
+        ));
+    }
+    dim_conf.data_type.json_to_sea_orm_value(&add_req.key, false)?;
+    let records = store.dim_records.entry(dim_conf_key).or_default();
+    if records.iter().any(|record| record.key == add_req.key) {
+        return Err(funs.err().conflict(
+            "dim_record",
+            "add",
+            "The dimension instance record already exists, please delete it and then add it.",
+            "409-spi-stats-dim-inst-record-exist",
+        ));
+    }
+    let key_str = add_req.key.as_str().map(|key| key.to_string()).unwrap_or_else(|| add_req.key.to_string());
+    let mut hierarchy_keys = vec!["".to_string(); dim_conf.hierarchy.len()];
+    let hierarchy = if let Some(parent_key) = &add_req.parent_key {
+        let parent_record = records.iter().find(|record| &record.key == parent_key).ok_or_else(|| {
+            funs.err().not_found(
+                "dim_record",
+                "add",
+                &format!("The parent dimension instance record [{parent_key}] not exists."),
+                "404-spi-stats-dim-inst-record-not-exist",
+            )
+        })?;
+        let parent_hierarchy = parent_record.hierarchy.unwrap_or_default() as usize;
+        if parent_hierarchy + 1 >= dim_conf.hierarchy.len() {
+            return Err(funs.err().conflict(
+                "dim_record",
+                "add",
+                "The dimension instance record hierarchy is too deep.",
+                "409-spi-stats-dim-inst-record-hierarchy-too-deep",
+            ));
+        }
+        hierarchy_keys[..=parent_hierarchy].clone_from_slice(&parent_record.hierarchy_keys[..=parent_hierarchy]);
+        hierarchy_keys[parent_hierarchy + 1] = key_str;
+        Some(parent_hierarchy as u64 + 1)
+    } else if !dim_conf.hierarchy.is_empty() {
+        hierarchy_keys[0] = key_str;
+        Some(0)
+    } else {
+        None
+    };
+    records.push(StatsMemoryDimRecord {
+        key: add_req.key,
+        show_name: add_req.show_name,
+        hierarchy,
+        hierarchy_keys,
+        ct: Utc::now(),
+        et: None,
+    });
+    Ok(())
+}
+
+pub(crate) async fn dim_record_paginate(
+    dim_conf_key: String,
+    dim_record_key: Option<serde_json::Value>,
+    show_name: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    _desc_by_update: Option<bool>,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<serde_json::Value>> {
+    let store = get_client(inst).read().await;
+    let Some(dim_conf) = store.dim_confs.iter().find(|dim_conf| dim_conf.key == dim_conf_key) else {
+        return Err(funs.err().not_found("dim_record", "find", "The dimension config does not exist.", "404-spi-stats-dim-conf-not-exist"));
+    };
+    check_dim_online(&dim_conf_key, "find", &store, funs)?;
+    if let Some(dim_record_key) = &dim_record_key {
+        dim_conf.data_type.json_to_sea_orm_value(dim_record_key, false)?;
+    }
+    let mut records = store
+        .dim_records
+        .get(&dim_conf_key)
+        .into_iter()
+        .flatten()
+        .filter(|record| dim_record_key.as_ref().map(|dim_record_key| &record.key == dim_record_key).unwrap_or(true))
+        .filter(|record| show_name.as_ref().map(|show_name| record.show_name.contains(show_name)).unwrap_or(true))
+        .collect::<Vec<_>>();
+    // The dimension records have no update time, so only the create time is used for sorting
+    if let Some(desc_by_create) = desc_by_create {
+        records.sort_by(|a, b| if desc_by_create { b.ct.cmp(&a.ct) } else { a.ct.cmp(&b.ct) });
+    }
+    let total_size = records.len() as u64;
+    let records = records.into_iter().skip(((page_number.max(1) - 1) * page_size) as usize).take(page_size as usize).map(|record| record.to_json()).collect();
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size,
+        records,
+    })
+}
+
+pub(crate) async fn dim_record_delete(dim_conf_key: String, dim_record_key: serde_json::Value, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_dim_online(&dim_conf_key, "delete", &store, funs)?;
+    let now = Utc::now();
+    for record in store.dim_records.get_mut(&dim_conf_key).into_iter().flatten().filter(|record| record.key == dim_record_key) {
+        record.et = Some(now);
+    }
+    Ok(())
+}
+
+pub(crate) async fn dim_record_real_delete(
+    dim_conf_key: String,
+    dim_record_key: serde_json::Value,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let mut store = get_client(inst).write().await;
+    check_dim_online(&dim_conf_key, "delete", &store, funs)?;
+    if let Some(records) = store.dim_records.get_mut(&dim_conf_key) {
+        records.retain(|record| record.key != dim_record_key);
+    }
+    Ok(())
+}
@@ -22,7 +22,7 @@ use crate::{
     stats_enumeration::{StatsDataTypeKind, StatsFactColKind},
 };
 
-const FUNCTION_SUFFIX_FLAG: &str = "__";
+pub(crate) const FUNCTION_SUFFIX_FLAG: &str = "__";
 
 /// 查询指标.
 ///
@@ -152,7 +152,7 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         )
         .await?;
 
-    let mut conf_info = conf_info
+    let conf_info = conf_info
         .into_iter()
         .map(|item: sea_orm::prelude::QueryResult| {
             Ok(StatsConfInfo {
@@ -191,100 +191,10 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         })
         .collect::<TardisResult<Vec<StatsConfInfo>>>()?;
 
-    let query_limit = match conf_info.as_slice() {
-        [] => {
-            return Err(funs.err().not_found(
-                "metric",
-                "query",
-                &format!("The query fact [{}] does not exist.", query_req.from),
-                "404-spi-stats-metric-fact-not-exist",
-            ));
-        }
-        [first, ..] => first.query_limit,
-    };
-    // Add default dimension
-    conf_info.push(StatsConfInfo {
-        col_key: "key".to_string(),
-        show_name: "主键".to_string(),
-        col_kind: StatsFactColKind::Measure,
-        dim_multi_values: Some(false),
-        mes_data_distinct: Some(true),
-        mes_data_type: Some(StatsDataTypeKind::String),
-        dim_rel_conf_dim_key: None,
-        dim_data_type: None,
-        dim_hierarchy: None,
-        rel_external_id: None,
-        query_limit,
-    });
-    conf_info.push(StatsConfInfo {
-        col_key: "ct".to_string(),
-        show_name: "创建时间".to_string(),
-        col_kind: StatsFactColKind::Dimension,
-        dim_multi_values: Some(false),
-        mes_data_distinct: Some(true),
-        mes_data_type: None,
-        dim_rel_conf_dim_key: None,
-        dim_data_type: Some(StatsDataTypeKind::DateTime),
-        dim_hierarchy: None,
-        rel_external_id: None,
-        query_limit,
-    });
-    conf_info.push(StatsConfInfo {
-        col_key: "_count".to_string(),
-        show_name: "虚构计算数".to_string(),
-        col_kind: StatsFactColKind::Measure,
-        dim_multi_values: Some(false),
-        mes_data_distinct: Some(true),
-        mes_data_type: Some(StatsDataTypeKind::Int),
-        dim_rel_conf_dim_key: None,
-        dim_data_type: None,
-        dim_hierarchy: None,
-        rel_external_id: None,
-        query_limit,
-    });
+    let (query_limit, dim_conf_info, measure_conf_info, conf_info) = self::package_conf_info(query_req, conf_info, funs)?;
     // todo 需要更改使用with
     let ct_agg = query_req.group.iter().any(|i| i.code == "ct");
     let conf_limit = query_limit;
-    // Dimension configuration, used for group and group_order
-    // 纬度配置,用于group以及group_order
-    let dim_conf_info =
-        conf_info.iter().filter(|i| i.col_kind == StatsFactColKind::Dimension).map(|v| (v.col_key.clone().to_string(), v.clone())).collect::<HashMap<String, StatsConfInfo>>();
-    // Measure configuration, used for select and metrics_order
-    // 度量配置,用于select以及metrics_order
-    let measure_conf_info =
-        conf_info.iter().filter(|i| i.col_kind == StatsFactColKind::Measure).map(|v| (v.col_key.clone().to_string(), v.clone())).collect::<HashMap<String, StatsConfInfo>>();
-    // Not distinguish between dimensions and measures, used for fields in where and having conditions
-    // 不区分维度和度量,用于where及having条件的字段
-    let conf_info = conf_info.into_iter().map(|v| (v.col_key.clone().to_string(), v)).collect::<HashMap<String, StatsConfInfo>>();
-    if query_req.select.iter().any(|i| !measure_conf_info.contains_key(&i.code.to_string()))
-        // should be equivalent: 
-        // original: || query_req.group.iter().any(|i| !dim_conf_info.contains_key(&i.code) || dim_conf_info.get(&i.code).unwrap().col_kind != StatsFactColKind::Dimension))
-        // (!contain || not_dim) => !(contain && is_dim)
-        || query_req.group.iter().any(|i| !dim_conf_info.get(&i.code.to_string()).is_some_and(|i|i.col_kind == StatsFactColKind::Dimension))
-        || query_req
-            .group_order
-            .as_ref()
-            .map(|orders| orders.iter().any(|order| !query_req.group.iter().any(|group| group.code == order.code && group.time_window == order.time_window)))
-            .unwrap_or(false)
-        || query_req
-            .metrics_order
-            .as_ref()
-            .map(|orders| orders.iter().any(|order| !query_req.select.iter().any(|select| order.code == select.code && order.fun == select.fun)))
-            .unwrap_or(false)
-        || query_req
-            .having
-            .as_ref()
-            .map(|havings| havings.iter().any(|having| !query_req.select.iter().any(|select| having.code == select.code && having.fun == select.fun)))
-            .unwrap_or(false)
-        || query_req._where.as_ref().map(|or_wheres| or_wheres.iter().any(|and_wheres| and_wheres.iter().any(|where_| !conf_info.contains_key(&where_.code.to_string())))).unwrap_or(false)
-    {
-        return Err(funs.err().not_found(
-            "metric",
-            "query",
-            "The query some dimension or measures does not exist.",
-            "404-spi-stats-metric-dim-mea-not-exist",
-        ));
-    }
     let mes_distinct = query_req.select.iter().any(|i| {
         if let Some(conf) = measure_conf_info.get(&i.code.to_string()) {
             return conf.mes_data_distinct.unwrap_or(true);
@@ -723,8 +633,112 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
     })
 }
 
+/// Add the built-in columns to the column configs of the queried fact, and check that the query only refers to the existing dimensions and measures.
+///
+/// 为查询的事实的列配置添加内置列，并检查查询只引用了存在的维度及度量.
+///
+/// Returns the query limit of the fact, the dimension configs, the measure configs and all the configs, keyed by the column key.
+pub(crate) fn package_conf_info(
+    query_req: &StatsQueryMetricsReq,
+    mut conf_info: Vec<StatsConfInfo>,
+    funs: &TardisFunsInst,
+) -> TardisResult<(i32, StatsConfInfoMap, StatsConfInfoMap, StatsConfInfoMap)> {
+    let query_limit = match conf_info.as_slice() {
+        [] => {
+            return Err(funs.err().not_found(
+                "metric",
+                "query",
+                &format!("The query fact [{}] does not exist.", query_req.from),
+                "404-spi-stats-metric-fact-not-exist",
+            ));
+        }
+        [first, ..] => first.query_limit,
+    };
+    // Add default dimension
+    conf_info.push(StatsConfInfo {
+        col_key: "key".to_string(),
+        show_name: "主键".to_string(),
+        col_kind: StatsFactColKind::Measure,
+        dim_multi_values: Some(false),
+        mes_data_distinct: Some(true),
+        mes_data_type: Some(StatsDataTypeKind::String),
+        dim_rel_conf_dim_key: None,
+        dim_data_type: None,
+        dim_hierarchy: None,
+        rel_external_id: None,
+        query_limit,
+    });
+    conf_info.push(StatsConfInfo {
+        col_key: "ct".to_string(),
+        show_name: "创建时间".to_string(),
+        col_kind: StatsFactColKind::Dimension,
+        dim_multi_values: Some(false),
+        mes_data_distinct: Some(true),
+        mes_data_type: None,
+        dim_rel_conf_dim_key: None,
+        dim_data_type: Some(StatsDataTypeKind::DateTime),
+        dim_hierarchy: None,
+        rel_external_id: None,
+        query_limit,
+    });
+    conf_info.push(StatsConfInfo {
+        col_key: "_count".to_string(),
+        show_name: "虚构计算数".to_string(),
+        col_kind: StatsFactColKind::Measure,
+        dim_multi_values: Some(false),
+        mes_data_distinct: Some(true),
+        mes_data_type: Some(StatsDataTypeKind::Int),
+        dim_rel_conf_dim_key: None,
+        dim_data_type: None,
+        dim_hierarchy: None,
+        rel_external_id: None,
+        query_limit,
+    });
+    // Dimension configuration, used for group and group_order
+    // 纬度配置,用于group以及group_order
+    let dim_conf_info =
+        conf_info.iter().filter(|i| i.col_kind == StatsFactColKind::Dimension).map(|v| (v.col_key.clone().to_string(), v.clone())).collect::<HashMap<String, StatsConfInfo>>();
+    // Measure configuration, used for select and metrics_order
+    // 度量配置,用于select以及metrics_order
+    let measure_conf_info =
+        conf_info.iter().filter(|i| i.col_kind == StatsFactColKind::Measure).map(|v| (v.col_key.clone().to_string(), v.clone())).collect::<HashMap<String, StatsConfInfo>>();
+    // Not distinguish between dimensions and measures, used for fields in where and having conditions
+    // 不区分维度和度量,用于where及having条件的字段
+    let conf_info = conf_info.into_iter().map(|v| (v.col_key.clone().to_string(), v)).collect::<HashMap<String, StatsConfInfo>>();
+    if query_req.select.iter().any(|i| !measure_conf_info.contains_key(&i.code.to_string()))
+        // should be equivalent: 
+        // original: || query_req.group.iter().any(|i| !dim_conf_info.contains_key(&i.code) || dim_conf_info.get(&i.code).unwrap().col_kind != StatsFactColKind::Dimension))
+        // (!contain || not_dim) => !(contain && is_dim)
+        || query_req.group.iter().any(|i| !dim_conf_info.get(&i.code.to_string()).is_some_and(|i|i.col_kind == StatsFactColKind::Dimension))
+        || query_req
+            .group_order
+            .as_ref()
+            .map(|orders| orders.iter().any(|order| !query_req.group.iter().any(|group| group.code == order.code && group.time_window == order.time_window)))
+            .unwrap_or(false)
+        || query_req
+            .metrics_order
+            .as_ref()
+            .map(|orders| orders.iter().any(|order| !query_req.select.iter().any(|select| order.code == select.code && order.fun == select.fun)))
+            .unwrap_or(false)
+        || query_req
+            .having
+            .as_ref()
+            .map(|havings| havings.iter().any(|having| !query_req.select.iter().any(|select| having.code == select.code && having.fun == select.fun)))
+            .unwrap_or(false)
+        || query_req._where.as_ref().map(|or_wheres| or_wheres.iter().any(|and_wheres| and_wheres.iter().any(|where_| !conf_info.contains_key(&where_.code.to_string())))).unwrap_or(false)
+    {
+        return Err(funs.err().not_found(
+            "metric",
+            "query",
+            "The query some dimension or measures does not exist.",
+            "404-spi-stats-metric-dim-mea-not-exist",
+        ));
+    }
+    Ok((query_limit, dim_conf_info, measure_conf_info, conf_info))
+}
+
 // TODO 下钻 上探
-async fn package_dim_record_agg(
+pub(crate) async fn package_dim_record_agg(
     conf_info: HashMap<String, StatsConfInfo>,
     funs: &TardisFunsInst,
     ctx: &TardisContext,
@@ -755,7 +769,7 @@ async fn package_dim_record_agg(
     Ok(result)
 }
 
-fn package_groups(
+pub(crate) fn package_groups(
     dim_record_agg: HashMap<String, HashMap<String, serde_json::Value>>,
     conf_info: HashMap<String, StatsConfInfo>,
     curr_select_dimension_keys: Vec<String>,
@@ -877,7 +891,7 @@ fn package_groups_agg(record: serde_json::Value) -> Result<serde_json::Value, St
     }
 }
 
-fn package_rel_external_id_agg(query_req: &StatsQueryMetricsReq) -> Option<HashSet<String>> {
+pub(crate) fn package_rel_external_id_agg(query_req: &StatsQueryMetricsReq) -> Option<HashSet<String>> {
     let mut rel_external_ids = HashSet::new();
     rel_external_ids.insert("".to_string());
     if let Some(rel_external_id) = &query_req.rel_external_id {
@@ -930,7 +944,7 @@ fn package_rel_external_id_agg(query_req: &StatsQueryMetricsReq) -> Option<HashS
 }
 
 #[derive(sea_orm::FromQueryResult, Clone)]
-struct StatsConfInfo {
+pub(crate) struct StatsConfInfo {
     pub col_key: String,
     pub show_name: String,
     pub col_kind: StatsFactColKind,
@@ -943,3 +957,5 @@ struct StatsConfInfo {
     pub rel_external_id: Option<String>,
     pub query_limit: i32,
 }
+
+pub(crate) type StatsConfInfoMap = HashMap<String, StatsConfInfo>;
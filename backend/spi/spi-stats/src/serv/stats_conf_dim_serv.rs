@@ -6,6 +6,8 @@ use bios_basic::spi_dispatch_service;
 use tardis::basic::result::TardisResult;
 use tardis::web::web_resp::TardisPage;
 
+#[cfg(feature = "spi-memory")]
+use super::memory;
 use super::pg;
 
 spi_dispatch_service! {
@@ -14,6 +16,8 @@ spi_dispatch_service! {
     @dispatch: {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_dim_serv,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::stats_memory_conf_dim_serv,
     },
     @method: {
         add(add_req: &StatsConfDimAddReq) -> TardisResult<()>;
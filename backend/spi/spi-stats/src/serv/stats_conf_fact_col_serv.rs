@@ -7,6 +7,8 @@ use bios_basic::spi_dispatch_service;
 use tardis::basic::result::TardisResult;
 use tardis::web::web_resp::TardisPage;
 
+#[cfg(feature = "spi-memory")]
+use super::memory;
 use super::pg;
 
 spi_dispatch_service! {
@@ -15,6 +17,8 @@ spi_dispatch_service! {
     @dispatch: {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_fact_col_serv,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::stats_memory_conf_fact_col_serv,
     },
     @method: {
         add(fact_conf_key: &str, add_req: &StatsConfFactColAddReq) -> TardisResult<()>;
@@ -7,6 +7,8 @@ use tardis::TardisFunsInst;
 use crate::dto::stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp};
 use crate::stats_initializer;
 
+#[cfg(feature = "spi-memory")]
+use super::memory;
 use super::pg;
 
 pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<StatsQueryMetricsResp> {
@@ -14,6 +16,8 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
     match inst.kind_code() {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_metric_serv::query_metrics(query_req, funs, ctx, &inst).await,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::stats_memory_metric_serv::query_metrics(query_req, funs, ctx, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -8,6 +8,8 @@ use tardis::chrono::{DateTime, Utc};
 use tardis::serde_json::{self, Value};
 use tardis::web::web_resp::TardisPage;
 
+#[cfg(feature = "spi-memory")]
+use super::memory;
 use super::pg;
 spi_dispatch_service! {
     @mgr: true,
@@ -15,6 +17,8 @@ spi_dispatch_service! {
     @dispatch: {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_record_serv,
+        #[cfg(feature = "spi-memory")]
+        spi_constants::SPI_MEMORY_KIND_CODE => memory::stats_memory_record_serv,
     },
     @method: {
         get_fact_record_latest(fact_conf_key: &str, fact_record_key: impl IntoIterator<Item = &str>) -> TardisResult<Vec<serde_json::Value>>;
@@ -436,7 +436,7 @@ impl StatsQueryTimeWindowKind {
                     Self::is_null_empty_column(column_name, None)
                 ),
                 StatsQueryTimeWindowKind::Week => format!(
-                    "CONCAT(date_part('year', {}), ' ',
+                    "CONCAT(date_part('isoyear', {}), ' ',
                     date_part('week', {}))",
                     Self::is_null_empty_column(column_name, None),
                     Self::is_null_empty_column(column_name, None)
@@ -479,7 +479,7 @@ impl StatsQueryTimeWindowKind {
                     Self::is_null_empty_column(column_name, None)
                 ),
                 StatsQueryTimeWindowKind::Week => format!(
-                    "CONCAT(date_part('year', {}), ' ',
+                    "CONCAT(date_part('isoyear', {}), ' ',
                     date_part('week', {}))",
                     Self::is_null_empty_column(column_name, None),
                     Self::is_null_empty_column(column_name, None)
@@ -25,6 +25,10 @@ pub struct AuthConfig {
     pub cache_key_aksk_info: String,
     pub cache_key_crypto_key: String,
     pub cache_key_double_auth_info: String,
+    /// Cache key prefix of the grants of the access tokens issued by the OAuth2 provider of IAM,
+    /// these tokens are only accepted when the ``oauth2_api_scope`` is granted
+    pub cache_key_oauth2_access_token: String,
+    pub oauth2_api_scope: String,
 
     pub cache_key_res_info: String,
     pub cache_key_res_changed_info: String,
@@ -70,6 +74,8 @@ impl Default for AuthConfig {
             cache_key_crypto_key: "auth:crypto:key:".to_string(),
             // ..:<account_id>
             cache_key_double_auth_info: "iam:cache:double_auth:info:".to_string(),
+            cache_key_oauth2_access_token: "iam:cache:oauth2:access_token:".to_string(),
+            oauth2_api_scope: "api".to_string(),
             cache_key_res_info: "iam:res:info".to_string(),
             cache_key_res_changed_info: "iam:res:changed:info:".to_string(),
             cache_key_res_changed_timer_sec: 30,
@@ -1,3 +1,5 @@
 pub const DOMAIN_CODE: &str = "auth";
 // see cargo.toml lib.name
 pub const PACKAGE_NAME: &str = "bios_auth";
+/// Token kind of the access tokens issued by the OAuth2 provider of IAM
+pub const TOKEN_KIND_OAUTH2: &str = "TokenOauth2";
@@ -16,7 +16,7 @@ use crate::dto::auth_kernel_dto::{AuthResult, ResContainerLeafInfo, SignWebHookR
 use crate::helper::auth_common_helper;
 use crate::{
     auth_config::AuthConfig,
-    auth_constants::DOMAIN_CODE,
+    auth_constants::{DOMAIN_CODE, TOKEN_KIND_OAUTH2},
     dto::auth_kernel_dto::{AuthContext, AuthReq},
 };
 
@@ -230,6 +230,9 @@ pub async fn get_token_context(token: &str, app_id: &str, config: &AuthConfig, c
                 )
                 .await?;
         }
+        if account_info[0] == TOKEN_KIND_OAUTH2 {
+            check_oauth2_scope(token, config, cache_client).await?;
+        }
         account_info[1].to_string()
     } else {
         return Err(TardisError::unauthorized(&format!("[Auth] Token [{token}] is not legal"), "401-auth-req-token-not-exist"));
@@ -238,6 +241,28 @@ pub async fn get_token_context(token: &str, app_id: &str, config: &AuthConfig, c
     Ok(context)
 }
 
+/// The access token issued by the OAuth2 provider carries the whole context of the account,
+/// so it can only call the apis when the api scope is granted, the other scopes (e.g. openid, profile) are for the identity only
+async fn check_oauth2_scope(token: &str, config: &AuthConfig, cache_client: &TardisCacheClient) -> TardisResult<()> {
+    let scopes = if let Some(grant) = cache_client.get(&format!("{}{}", config.cache_key_oauth2_access_token, token)).await? {
+        TardisFuns::json
+            .str_to_obj::<tardis::serde_json::Value>(&grant)?
+            .get("scopes")
+            .and_then(|scopes| scopes.as_array())
+            .map(|scopes| scopes.iter().filter_map(|scope| scope.as_str().map(|scope| scope.to_string())).collect::<Vec<_>>())
+            .unwrap_or_default()
+    } else {
+        return Err(TardisError::unauthorized(&format!("[Auth] Token [{token}] is not legal"), "401-auth-req-token-not-exist"));
+    };
+    if !scopes.contains(&config.oauth2_api_scope) {
+        return Err(TardisError::forbidden(
+            &format!("[Auth] Token [{token}] is not granted the scope [{}]", config.oauth2_api_scope),
+            "403-auth-req-token-scope-insufficient",
+        ));
+    }
+    Ok(())
+}
+
 async fn get_account_context(token: &str, account_id: &str, app_id: &str, config: &AuthConfig, cache_client: &TardisCacheClient) -> TardisResult<TardisContext> {
     let mut context: TardisContext = if let Some(context) = cache_client.hget(&format!("{}{}", config.cache_key_account_info, account_id), app_id).await? {
         TardisFuns::json.str_to_obj::<TardisContext>(&context)?
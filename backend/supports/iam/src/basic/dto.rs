@@ -5,9 +5,11 @@ pub mod iam_cert_conf_dto;
 pub mod iam_cert_dto;
 pub mod iam_config_dto;
 pub mod iam_filer_dto;
+pub mod iam_oauth2_provider_dto;
 pub mod iam_open_dto;
 pub mod iam_platform_dto;
 pub mod iam_res_dto;
 pub mod iam_role_dto;
+pub mod iam_scim_dto;
 pub mod iam_set_dto;
 pub mod iam_tenant_dto;
@@ -8,7 +8,7 @@ use tardis::web::poem_openapi;
 
 use crate::basic::dto::iam_cert_conf_dto::IamCertConfLdapResp;
 use crate::basic::serv::iam_cert_ldap_serv::ldap::LdapSearchResp;
-use crate::iam_enumeration::{IamAccountLockStateKind, IamAccountLogoutTypeKind, IamAccountStatusKind};
+use crate::iam_enumeration::{IamAccountLockStateKind, IamAccountLogoutTypeKind, IamAccountStatusKind, IamCertKernelKind};
 use bios_basic::rbum::rbum_enumeration::{RbumCertStatusKind, RbumScopeLevelKind};
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -243,6 +243,19 @@ pub struct IamAccountInfoResp {
     pub roles: HashMap<String, String>,
     pub groups: HashMap<String, String>,
     pub apps: Vec<IamAccountAppInfoResp>,
+    /// Returned instead of the token when the second factor is required by the tenant
+    /// 租户要求二次认证时返回（此时不返回token）
+    pub mfa_challenge: Option<IamAccountMfaChallengeResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamAccountMfaChallengeResp {
+    /// Ticket to complete the login by the second factor
+    /// 用于完成二次认证登录的票据
+    pub ticket: String,
+    /// Second factor kinds that the account can use
+    /// 账号可用的二次认证凭证类型
+    pub kinds: Vec<IamCertKernelKind>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
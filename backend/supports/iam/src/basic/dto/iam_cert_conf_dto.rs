@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::basic::serv::iam_cert_ldap_serv::AccountFieldMap;
 use serde::{Deserialize, Serialize};
 use tardis::basic::field::TrimString;
@@ -89,6 +91,8 @@ pub struct IamCertConfOAuth2AddOrModifyReq {
     pub ak: TrimString,
     #[oai(validator(min_length = "2", max_length = "2000"))]
     pub sk: TrimString,
+    /// Required when the supplier is ``Oidc`` / 供应商为 ``Oidc`` 时必填
+    pub oidc: Option<IamCertConfOidcConf>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -97,6 +101,87 @@ pub struct IamCertConfOAuth2Resp {
     pub ak: String,
     #[oai(validator(min_length = "2", max_length = "2000"))]
     pub sk: String,
+    pub oidc: Option<IamCertConfOidcConf>,
+}
+
+/// Generic OIDC supplier configuration, the ak / sk of the cert conf are the client id / secret
+/// 通用OIDC供应商配置，凭证配置的ak/sk即为client id/secret
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IamCertConfOidcConf {
+    /// Issuer url, the endpoints are discovered by ``{issuer}/.well-known/openid-configuration``
+    /// 签发者地址，通过 ``{issuer}/.well-known/openid-configuration`` 发现各端点
+    #[oai(validator(min_length = "2", max_length = "2000"))]
+    pub issuer: String,
+    /// Override the discovered authorization endpoint / 覆盖发现的授权端点
+    #[oai(default)]
+    #[serde(default)]
+    pub authorization_endpoint: Option<String>,
+    /// Override the discovered token endpoint / 覆盖发现的令牌端点
+    #[oai(default)]
+    #[serde(default)]
+    pub token_endpoint: Option<String>,
+    /// Override the discovered userinfo endpoint / 覆盖发现的用户信息端点
+    #[oai(default)]
+    #[serde(default)]
+    pub userinfo_endpoint: Option<String>,
+    /// Override the discovered jwks uri / 覆盖发现的jwks地址
+    #[oai(default)]
+    #[serde(default)]
+    pub jwks_uri: Option<String>,
+    /// ``client_secret_basic`` (default) or ``client_secret_post``
+    #[oai(default)]
+    #[serde(default)]
+    pub token_endpoint_auth_method: Option<String>,
+    /// Default is ``openid profile email`` / 默认为 ``openid profile email``
+    #[oai(default)]
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[oai(default)]
+    #[serde(default)]
+    pub claim_mapping: IamCertOidcClaimMapping,
+    /// Add the account when it logs in for the first time, regardless of the self-registration of the tenant
+    /// 首次登录时自动创建账号，不受租户自注册配置影响
+    #[oai(default)]
+    #[serde(default)]
+    pub jit_provisioning: bool,
+    /// Claim of the groups, e.g. ``groups`` or ``realm_access.roles`` / 用户组声明，如 ``groups`` 或 ``realm_access.roles``
+    #[oai(default)]
+    #[serde(default)]
+    pub groups_claim: Option<String>,
+    /// Group -> role ids, the roles are synced on each login, the mapped roles no longer in the groups are removed / 用户组 -> 角色id，每次登录时同步角色，移除不再属于用户组的映射角色
+    #[oai(default)]
+    #[serde(default)]
+    pub role_mapping: HashMap<String, Vec<String>>,
+    /// Group -> org node ids, the org nodes are synced on each login like the roles / 用户组 -> 组织节点id，每次登录时同步组织节点，同角色
+    #[oai(default)]
+    #[serde(default)]
+    pub org_mapping: HashMap<String, Vec<String>>,
+}
+
+/// Claim names of the account fields, the nested claims are separated by ``.``
+/// 账号字段对应的声明名，嵌套声明以 ``.`` 分隔
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IamCertOidcClaimMapping {
+    /// Default is ``sub`` / 默认为 ``sub``
+    #[oai(default)]
+    #[serde(default)]
+    pub open_id: Option<String>,
+    /// Default is ``name`` / 默认为 ``name``
+    #[oai(default)]
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Default is ``email`` / 默认为 ``email``
+    #[oai(default)]
+    #[serde(default)]
+    pub mail: Option<String>,
+    /// Default is ``phone_number`` / 默认为 ``phone_number``
+    #[oai(default)]
+    #[serde(default)]
+    pub phone: Option<String>,
+    /// Claim -> account attribute name / 声明 -> 账号扩展属性名
+    #[oai(default)]
+    #[serde(default)]
+    pub exts: HashMap<String, String>,
 }
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct IamCertConfAkSkAddOrModifyReq {
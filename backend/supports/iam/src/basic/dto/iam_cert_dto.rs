@@ -1,10 +1,12 @@
 use std::collections::HashSet;
 
+use crate::basic::dto::iam_oauth2_provider_dto::IamOAuth2ClientConf;
 use crate::iam_enumeration::{IamCertExtKind, WayToAdd, WayToDelete};
 use bios_basic::rbum::rbum_enumeration::RbumCertStatusKind;
 use serde::{Deserialize, Serialize};
 use tardis::basic::field::TrimString;
 use tardis::chrono::{DateTime, Utc};
+use tardis::serde_json::Value;
 use tardis::web::poem_openapi;
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -253,6 +255,12 @@ pub struct IamCertLdapAddOrModifyReq {
 pub struct IamCertAkSkAddReq {
     pub tenant_id: String,
     pub app_id: Option<String>,
+    /// Configuration when the aksk is used as an OAuth2 client
+    /// 该aksk作为OAuth2客户端时的配置
+    pub oauth2_client: Option<IamOAuth2ClientConf>,
+    /// Whether the aksk is the SCIM provisioning credential of the tenant, it can't be an OAuth2 client at the same time
+    /// 该aksk是否为租户的SCIM供应凭证，不能同时作为OAuth2客户端
+    pub scim: Option<bool>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -280,3 +288,55 @@ pub struct IamCertDecodeRequest {
 pub struct IamCertModifyVisibilityRequest {
     pub sk_invisible: bool,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertTotpEnrollResp {
+    /// Base32 encoded secret, for manual input
+    /// Base32编码的密钥，用于手动输入
+    pub secret: String,
+    /// ``otpauth://`` uri, to be shown as a QR code
+    /// ``otpauth://`` 地址，用于生成二维码
+    pub provisioning_uri: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertTotpCodeReq {
+    /// One-time password, or a recovery code when validating
+    /// 一次性密码，验证时也可使用恢复码
+    #[oai(validator(min_length = "6", max_length = "255"))]
+    pub code: TrimString,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertTotpBindResp {
+    pub id: String,
+    /// Recovery codes, each can be used once instead of the one-time password, only returned here
+    /// 恢复码，每个可代替一次性密码使用一次，仅在此处返回
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertWebAuthnRegisterReq {
+    /// Name of the credential, e.g. ``YubiKey``
+    /// 凭证名称，如 ``YubiKey``
+    #[oai(validator(min_length = "1", max_length = "255"))]
+    pub name: TrimString,
+    /// ``PublicKeyCredential`` returned by ``navigator.credentials.create()``
+    /// ``navigator.credentials.create()`` 返回的 ``PublicKeyCredential``
+    pub credential: Value,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertWebAuthnValidateReq {
+    /// ``PublicKeyCredential`` returned by ``navigator.credentials.get()``
+    /// ``navigator.credentials.get()`` 返回的 ``PublicKeyCredential``
+    pub credential: Value,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCertWebAuthnResp {
+    pub id: String,
+    pub name: String,
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use tardis::web::poem_openapi;
+
+use crate::iam_enumeration::Oauth2GrantType;
+
+/// OAuth2 client configuration, stored in the ext of the aksk cert whose ak is the client id
+/// OAuth2客户端配置，保存在aksk证书（ak即client id）的扩展信息中
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IamOAuth2ClientConf {
+    /// Allowed redirect uris, exactly matched / 允许的回调地址，精确匹配
+    #[oai(default)]
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    /// Allowed grant types / 允许的授权类型
+    #[oai(default)]
+    #[serde(default)]
+    pub grant_types: Vec<Oauth2GrantType>,
+    /// Allowed scopes, also the default scopes when the request has no scope / 允许的scope，请求未指定时即为默认scope
+    #[oai(default)]
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Public client (e.g. SPA, native app) that can't keep the secret, PKCE is required
+    /// 无法保存密钥的公开客户端（如单页应用、原生应用），必须使用PKCE
+    #[oai(default)]
+    #[serde(default)]
+    pub public_client: bool,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IamOAuth2AuthorizeReq {
+    /// Only ``code`` is supported / 仅支持 ``code``
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: Option<String>,
+    /// Space separated scopes / 空格分隔的scope
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    /// ``S256`` or ``plain``, default is ``plain`` / ``S256`` 或 ``plain``，默认为 ``plain``
+    pub code_challenge_method: Option<String>,
+    /// Returned in the id_token / 在id_token中返回
+    pub nonce: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct IamOAuth2TokenReq {
+    /// ``authorization_code``, ``client_credentials``, ``refresh_token`` or ``password``
+    pub grant_type: String,
+    /// Client id, can also be passed by the HTTP Basic authorization / 客户端id，也可通过HTTP Basic认证传递
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Tenant id of the account, used by the password grant of the global client / 账号的租户id，全局客户端的密码授权使用
+    pub tenant_id: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamOAuth2TokenResp {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    pub scope: String,
+    /// Returned when the ``openid`` scope is granted / 授予 ``openid`` scope时返回
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
+
+/// Error response defined by RFC 6749 / RFC 6749定义的错误响应
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamOAuth2ErrorResp {
+    pub error: String,
+    pub error_description: String,
+}
+
+/// Request of the token revocation (RFC 7009) and introspection (RFC 7662)
+/// 令牌撤销（RFC 7009）及内省（RFC 7662）请求
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IamOAuth2TokenOpReq {
+    pub token: String,
+    /// ``access_token`` or ``refresh_token``
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct IamOAuth2IntrospectResp {
+    pub active: bool,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// Account id, or the app / tenant id of the client credentials grant / 账号id，客户端凭证授权时为应用或租户id
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamOidcUserInfoResp {
+    /// Account id / 账号id
+    pub sub: String,
+    /// Returned with the ``profile`` scope / ``profile`` scope时返回
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Returned with the ``email`` scope / ``email`` scope时返回
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Returned with the ``phone`` scope / ``phone`` scope时返回
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<String>,
+    pub tenant_id: String,
+}
+
+/// OpenID Provider metadata / OpenID提供方元数据
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamOidcDiscoveryResp {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub revocation_endpoint: String,
+    pub introspection_endpoint: String,
+    pub response_types_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub scopes_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamOidcJwksResp {
+    pub keys: Vec<IamOidcJwkResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamOidcJwkResp {
+    pub kty: String,
+    #[oai(rename = "use")]
+    #[serde(rename = "use")]
+    pub key_use: String,
+    pub alg: String,
+    pub kid: String,
+    /// Base64url encoded modulus / Base64url编码的模数
+    pub n: String,
+    /// Base64url encoded exponent / Base64url编码的指数
+    pub e: String,
+}
+
+/// Grant behind an authorization code, an access token or a refresh token, kept in the cache
+/// 授权码、访问令牌或刷新令牌对应的授权信息，保存在缓存中
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IamOAuth2Grant {
+    pub client_id: String,
+    pub tenant_id: String,
+    /// Empty for the client credentials grant / 客户端凭证授权时为空
+    pub account_id: Option<String>,
+    /// The app / tenant id of the client / 客户端所属的应用或租户id
+    pub client_rel_id: String,
+    pub scopes: Vec<String>,
+    pub redirect_uri: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub nonce: Option<String>,
+    /// Time of the user authentication / 用户认证时间
+    pub auth_time: i64,
+    pub issued_at: i64,
+    pub expires_at: i64,
+    /// The access token issued with the refresh token / 与刷新令牌一同签发的访问令牌
+    pub access_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IamOidcIdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub aud: String,
+    pub exp: i64,
+    pub iat: i64,
+    pub auth_time: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub tenant_id: String,
+}
@@ -49,6 +49,17 @@ pub struct IamOpenRuleResp {
     pub api_call_cumulative_count: Option<u32>,
 }
 
+#[derive(Serialize, Deserialize, Debug, poem_openapi::Object)]
+pub struct IamOpenQuotaResp {
+    pub cert_id: String,
+    pub passed: bool,
+    pub api_call_frequency: Option<i64>,
+    pub api_call_frequency_remaining: Option<i64>,
+    pub api_call_frequency_reset_sec: Option<i64>,
+    pub api_call_count: Option<i64>,
+    pub api_call_count_remaining: Option<i64>,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct IamOpenAkSkAddReq {
     pub tenant_id: String,
@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tardis::serde_json::Value;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::types::{ParseFromJSON, ToJSON, Type};
+
+/// SCIM user, mapped to the account of the tenant (RFC 7643 section 4.1)
+/// SCIM用户，映射为租户的账号（RFC 7643 第4.1节）
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
+pub struct IamScimUser {
+    #[oai(default)]
+    pub schemas: Vec<String>,
+    /// Account id / 账号id
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Login name, i.e. the ak of the username / password cert / 登录名，即用户名密码凭证的ak
+    #[oai(default)]
+    pub user_name: String,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<IamScimName>,
+    /// Account name / 账号名称
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    /// Write only / 只写
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// The mail cert / 邮箱凭证
+    #[oai(default, skip_serializing_if_is_empty)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub emails: Vec<IamScimMultiValued>,
+    /// The phone cert / 手机号凭证
+    #[oai(default, skip_serializing_if_is_empty)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub phone_numbers: Vec<IamScimMultiValued>,
+    /// Orgs of the account, read only / 账号所属组织，只读
+    #[oai(default, skip_serializing_if_is_empty)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<IamScimMember>,
+    /// Account attrs of the tenant / 租户的账号扩展属性
+    #[oai(rename = "urn:ietf:params:scim:schemas:extension:bios:2.0:User", skip_serializing_if_is_none)]
+    #[serde(rename = "urn:ietf:params:scim:schemas:extension:bios:2.0:User", skip_serializing_if = "Option::is_none")]
+    pub ext: Option<HashMap<String, String>>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<IamScimMeta>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
+pub struct IamScimName {
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct IamScimMultiValued {
+    pub value: String,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary: Option<bool>,
+}
+
+/// Member of the group, or the group of the user / 组的成员，或用户所属的组
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct IamScimMember {
+    /// Account id or org node id / 账号id或组织节点id
+    pub value: String,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+    #[oai(rename = "$ref", skip_serializing_if_is_none)]
+    #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
+    pub r#ref: Option<String>,
+}
+
+/// SCIM group, mapped to the node of the default org set of the tenant (RFC 7643 section 4.2)
+/// SCIM组，映射为租户默认组织集合的节点（RFC 7643 第4.2节）
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
+pub struct IamScimGroup {
+    #[oai(default)]
+    pub schemas: Vec<String>,
+    /// Org node id / 组织节点id
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[oai(default)]
+    pub display_name: String,
+    /// Accounts of the org node / 组织节点下的账号
+    #[oai(default)]
+    pub members: Vec<IamScimMember>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<IamScimMeta>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
+pub struct IamScimMeta {
+    pub resource_type: String,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    pub location: String,
+    /// Weak ETag of the resource / 资源的弱ETag
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimListResp<T: Type + ParseFromJSON + ToJSON> {
+    pub schemas: Vec<String>,
+    pub total_results: usize,
+    /// 1-based index of the first resource / 第一个资源的索引，从1开始
+    pub start_index: usize,
+    pub items_per_page: usize,
+    #[oai(rename = "Resources")]
+    #[serde(rename = "Resources")]
+    pub resources: Vec<T>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct IamScimPatchReq {
+    #[oai(default)]
+    pub schemas: Vec<String>,
+    #[oai(rename = "Operations", default)]
+    #[serde(rename = "Operations")]
+    pub operations: Vec<IamScimPatchOp>,
+}
+
+/// Patch operation (RFC 7644 section 3.5.2) / 修改操作（RFC 7644 第3.5.2节）
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct IamScimPatchOp {
+    /// ``add``, ``replace`` or ``remove``, case insensitive / ``add``、``replace`` 或 ``remove``，不区分大小写
+    pub op: String,
+    /// e.g. ``userName``, ``name.givenName``, ``emails[type eq "work"].value``, ``members[value eq "<id>"]``
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
+pub struct IamScimBulkReq {
+    #[oai(default)]
+    pub schemas: Vec<String>,
+    /// Stop processing after the number of errors, all operations are processed when empty
+    /// 错误数达到该值后停止处理，为空时处理全部操作
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fail_on_errors: Option<usize>,
+    #[oai(rename = "Operations", default)]
+    #[serde(rename = "Operations")]
+    pub operations: Vec<IamScimBulkOp>,
+}
+
+/// Bulk operation, the created resource can be referenced by ``bulkId:<bulkId>`` in the subsequent operations
+/// 批量操作，后续操作可通过 ``bulkId:<bulkId>`` 引用已创建的资源
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
+pub struct IamScimBulkOp {
+    /// ``POST``, ``PUT``, ``PATCH`` or ``DELETE``
+    pub method: String,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bulk_id: Option<String>,
+    /// Same as the ``If-Match`` header / 同 ``If-Match`` 请求头
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// e.g. ``/Users``, ``/Groups/<id>``
+    pub path: String,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimBulkResp {
+    pub schemas: Vec<String>,
+    #[oai(rename = "Operations")]
+    #[serde(rename = "Operations")]
+    pub operations: Vec<IamScimBulkOpResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimBulkOpResp {
+    pub method: String,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bulk_id: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// HTTP status code / HTTP状态码
+    pub status: String,
+    /// The error of the failed operation / 失败操作的错误
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<IamScimErrorResp>,
+}
+
+/// Error response defined by RFC 7644 section 3.12 / RFC 7644 第3.12节定义的错误响应
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimErrorResp {
+    pub schemas: Vec<String>,
+    pub status: String,
+    /// e.g. ``invalidFilter``, ``invalidPath``, ``invalidValue``, ``mutability``, ``uniqueness``
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scim_type: Option<String>,
+    pub detail: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimServiceProviderConfigResp {
+    pub schemas: Vec<String>,
+    pub documentation_uri: Option<String>,
+    pub patch: IamScimSupportedResp,
+    pub bulk: IamScimBulkSupportedResp,
+    pub filter: IamScimFilterSupportedResp,
+    pub change_password: IamScimSupportedResp,
+    pub sort: IamScimSupportedResp,
+    pub etag: IamScimSupportedResp,
+    pub authentication_schemes: Vec<IamScimAuthenticationSchemeResp>,
+    pub meta: IamScimMeta,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamScimSupportedResp {
+    pub supported: bool,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimBulkSupportedResp {
+    pub supported: bool,
+    pub max_operations: usize,
+    pub max_payload_size: usize,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimFilterSupportedResp {
+    pub supported: bool,
+    pub max_results: usize,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamScimAuthenticationSchemeResp {
+    pub r#type: String,
+    pub name: String,
+    pub description: String,
+    pub primary: bool,
+}
+
+/// Schema definition (RFC 7643 section 7) / 模式定义（RFC 7643 第7节）
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimSchemaResp {
+    pub schemas: Vec<String>,
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub attributes: Vec<IamScimSchemaAttrResp>,
+    pub meta: IamScimMeta,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimSchemaAttrResp {
+    pub name: String,
+    pub r#type: String,
+    pub multi_valued: bool,
+    pub description: String,
+    pub required: bool,
+    pub case_exact: bool,
+    pub mutability: String,
+    pub returned: String,
+    pub uniqueness: String,
+    #[oai(skip_serializing_if_is_empty)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sub_attributes: Vec<IamScimSchemaAttrResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+#[oai(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct IamScimResourceTypeResp {
+    pub schemas: Vec<String>,
+    pub id: String,
+    pub name: String,
+    pub endpoint: String,
+    pub description: String,
+    pub schema: String,
+    pub schema_extensions: Vec<IamScimSchemaExtensionResp>,
+    pub meta: IamScimMeta,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct IamScimSchemaExtensionResp {
+    pub schema: String,
+    pub required: bool,
+}
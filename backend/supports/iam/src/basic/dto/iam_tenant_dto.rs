@@ -113,6 +113,8 @@ pub struct IamTenantConfigReq {
     pub cert_conf_by_user_pwd: Option<IamCertConfUserPwdAddOrModifyReq>,
     pub cert_conf_by_phone_vcode: Option<bool>,
     pub cert_conf_by_mail_vcode: Option<bool>,
+    pub cert_conf_by_totp: Option<bool>,
+    pub cert_conf_by_webauthn: Option<bool>,
     pub token_default_coexist_num: Option<i16>,
     pub cert_conf_by_oauth2: Option<Vec<IamCertConfOAuth2AddOrModifyReq>>,
     pub cert_conf_by_ldap: Option<IamCertConfLdapAddOrModifyReq>,
@@ -124,6 +126,8 @@ pub struct IamTenantConfigResp {
     pub cert_conf_by_user_pwd: IamCertConfUserPwdResp,
     pub cert_conf_by_phone_vcode: bool,
     pub cert_conf_by_mail_vcode: bool,
+    pub cert_conf_by_totp: bool,
+    pub cert_conf_by_webauthn: bool,
     pub token_default_coexist_num: i16,
     pub cert_conf_by_oauth2: Option<Vec<IamCertConfOAuth2Resp>>,
     pub cert_conf_by_ldap: Option<Vec<IamCertConfLdapResp>>,
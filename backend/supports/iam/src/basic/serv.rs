@@ -6,18 +6,23 @@ pub mod iam_cert_aksk_serv;
 #[cfg(feature = "ldap_client")]
 pub mod iam_cert_ldap_serv;
 pub mod iam_cert_mail_vcode_serv;
+pub mod iam_cert_mfa_serv;
 pub mod iam_cert_oauth2_serv;
 pub mod iam_cert_phone_vcode_serv;
 pub mod iam_cert_serv;
 pub mod iam_cert_token_serv;
+pub mod iam_cert_totp_serv;
 pub mod iam_cert_user_pwd_serv;
+pub mod iam_cert_webauthn_serv;
 pub mod iam_config_serv;
 pub mod iam_key_cache_serv;
+pub mod iam_oauth2_provider_serv;
 pub mod iam_open_serv;
 pub mod iam_platform_serv;
 pub mod iam_rel_serv;
 pub mod iam_res_serv;
 pub mod iam_role_serv;
+pub mod iam_scim_serv;
 pub mod iam_set_serv;
 pub mod iam_tenant_serv;
 pub mod oauth2_spi;
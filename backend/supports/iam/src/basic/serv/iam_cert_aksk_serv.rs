@@ -1,5 +1,5 @@
 use bios_basic::rbum::dto::rbum_cert_conf_dto::{RbumCertConfAddReq, RbumCertConfModifyReq};
-use bios_basic::rbum::dto::rbum_cert_dto::RbumCertAddReq;
+use bios_basic::rbum::dto::rbum_cert_dto::{RbumCertAddReq, RbumCertModifyReq};
 use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertConfFilterReq, RbumCertFilterReq};
 use bios_basic::rbum::rbum_enumeration::{RbumCertConfStatusKind, RbumCertRelKind, RbumCertStatusKind};
 use bios_basic::rbum::serv::rbum_cert_serv::{RbumCertConfServ, RbumCertServ};
@@ -7,14 +7,15 @@ use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
 use tardis::basic::field::TrimString;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    TardisFunsInst,
+    TardisFuns, TardisFunsInst,
 };
 
 use crate::basic::dto::iam_cert_conf_dto::IamCertConfAkSkAddOrModifyReq;
 use crate::basic::dto::iam_cert_dto::IamCertAkSkAddReq;
+use crate::basic::dto::iam_oauth2_provider_dto::IamOAuth2ClientConf;
 use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
 use crate::iam_config::IamBasicConfigApi;
-use crate::iam_constants::RBUM_SYSTEM_OWNER;
+use crate::iam_constants::{RBUM_CERT_SUPPLIER_SCIM, RBUM_SYSTEM_OWNER};
 use crate::iam_enumeration::IamCertKernelKind;
 
 pub struct IamCertAkSkServ;
@@ -88,6 +89,15 @@ impl IamCertAkSkServ {
     }
 
     pub async fn add_cert(add_req: &IamCertAkSkAddReq, ak: &str, sk: &str, rel_rbum_cert_conf_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let scim = add_req.scim.unwrap_or(false);
+        if scim && (add_req.oauth2_client.is_some() || add_req.app_id.is_some()) {
+            return Err(funs.err().bad_request(
+                "iam_cert_aksk",
+                "add",
+                "the SCIM credential must belong to the tenant and can't be an OAuth2 client",
+                "400-iam-cert-aksk-scim-invalid",
+            ));
+        }
         let cert_conf = RbumCertConfServ::peek_rbum(
             rel_rbum_cert_conf_id,
             &RbumCertConfFilterReq {
@@ -119,9 +129,9 @@ impl IamCertAkSkServ {
                 sk: Some(sk.into()),
                 sk_invisible: None,
                 kind: None,
-                supplier: None,
+                supplier: if scim { Some(RBUM_CERT_SUPPLIER_SCIM.to_string()) } else { None },
                 vcode: None,
-                ext: None,
+                ext: add_req.oauth2_client.as_ref().map(|oauth2_client| TardisFuns::json.obj_to_string(oauth2_client)).transpose()?,
                 start_time: None,
                 end_time: None,
                 conn_uri: None,
@@ -139,6 +149,36 @@ impl IamCertAkSkServ {
         IamIdentCacheServ::add_aksk(ak, sk, &tenant_id, app_id, cert_conf.expire_sec, funs).await?;
         Ok(id)
     }
+
+    /// Set the OAuth2 client configuration of the aksk cert, see [crate::basic::serv::iam_oauth2_provider_serv::IamOAuth2ProviderServ]
+    pub async fn modify_oauth2_client(id: &str, oauth2_client: &IamOAuth2ClientConf, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        if RbumCertServ::peek_rbum(id, &RbumCertFilterReq::default(), funs, ctx).await?.supplier == RBUM_CERT_SUPPLIER_SCIM {
+            return Err(funs.err().bad_request(
+                "iam_cert_aksk",
+                "modify_oauth2_client",
+                "the SCIM credential can't be an OAuth2 client",
+                "400-iam-cert-aksk-scim-invalid",
+            ));
+        }
+        RbumCertServ::modify_rbum(
+            id,
+            &mut RbumCertModifyReq {
+                ak: None,
+                sk: None,
+                sk_invisible: None,
+                ignore_check_sk: false,
+                ext: Some(TardisFuns::json.obj_to_string(oauth2_client)?),
+                start_time: None,
+                end_time: None,
+                conn_uri: None,
+                status: None,
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
     pub async fn delete_cert(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
         let resp = RbumCertServ::peek_rbum(id, &RbumCertFilterReq { ..Default::default() }, funs, ctx).await?;
         RbumCertServ::delete_rbum(id, funs, ctx).await?;
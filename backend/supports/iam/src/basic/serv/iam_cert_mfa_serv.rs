@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::{TardisFuns, TardisFunsInst};
+
+use crate::basic::dto::iam_account_dto::{IamAccountInfoResp, IamAccountMfaChallengeResp};
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
+use crate::basic::serv::iam_config_serv::IamConfigServ;
+use crate::iam_config::IamConfig;
+use crate::iam_enumeration::{IamCertKernelKind, IamConfigKind};
+
+/// The login waiting for the second factor
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IamCertMfaTicket {
+    pub tenant_id: Option<String>,
+    pub account_id: String,
+    pub token_kind: Option<String>,
+    pub access_token: Option<String>,
+    pub kinds: Vec<IamCertKernelKind>,
+}
+
+impl IamCertMfaTicket {
+    pub fn mock_ctx(&self) -> TardisContext {
+        TardisContext {
+            own_paths: self.tenant_id.clone().unwrap_or_default(),
+            owner: self.account_id.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Login second step by the MFA policy of the tenant, see [IamConfigKind::Mfa]
+pub struct IamCertMfaServ;
+
+impl IamCertMfaServ {
+    /// Get the second factor kinds to be verified when the account logs in, empty means the second step is not required.
+    ///
+    /// The second step is required when the ``Mfa`` config of the tenant is enabled and the account has bound any of the configured kinds,
+    /// so the accounts without the second factor can still log in to bind one.
+    pub async fn get_required_kinds(tenant_id: &str, account_id: &str, funs: &TardisFunsInst) -> TardisResult<Vec<IamCertKernelKind>> {
+        let mock_ctx = TardisContext {
+            own_paths: tenant_id.to_string(),
+            owner: account_id.to_string(),
+            ..Default::default()
+        };
+        let Some(config) = IamConfigServ::get_config_by_code_and_item_id(&IamConfigKind::Mfa, tenant_id, funs, &mock_ctx).await? else {
+            return Ok(vec![]);
+        };
+        if config.disabled {
+            return Ok(vec![]);
+        }
+        let second_factors = IamCertKernelKind::second_factors();
+        let mut configured_kinds =
+            config.value1.split(',').filter_map(|kind| IamCertKernelKind::from_str(kind.trim()).ok()).filter(|kind| second_factors.contains(kind)).collect::<Vec<_>>();
+        if configured_kinds.is_empty() {
+            configured_kinds = second_factors;
+        }
+        let mut kinds = vec![];
+        for kind in configured_kinds {
+            let bound = match kind {
+                IamCertKernelKind::Totp => IamCertTotpServ::exist_cert(account_id, funs, &mock_ctx).await?,
+                IamCertKernelKind::WebAuthn => !IamCertWebAuthnServ::find_certs_by_account(account_id, funs, &mock_ctx).await?.is_empty(),
+                _ => false,
+            };
+            if bound {
+                kinds.push(kind);
+            }
+        }
+        Ok(kinds)
+    }
+
+    /// Keep the login in a ticket, and return it instead of the token
+    pub async fn challenge(
+        tenant_id: Option<String>,
+        account_id: &str,
+        token_kind: Option<String>,
+        access_token: Option<String>,
+        kinds: Vec<IamCertKernelKind>,
+        funs: &TardisFunsInst,
+    ) -> TardisResult<IamAccountInfoResp> {
+        let ticket = TardisFuns::field.nanoid_len(32);
+        let ticket_info = IamCertMfaTicket {
+            tenant_id,
+            account_id: account_id.to_string(),
+            token_kind,
+            access_token,
+            kinds: kinds.clone(),
+        };
+        Self::set_ticket(&ticket, &ticket_info, funs).await?;
+        Ok(IamAccountInfoResp {
+            account_id: account_id.to_string(),
+            account_name: "".to_string(),
+            token: "".to_string(),
+            access_token: None,
+            roles: HashMap::new(),
+            groups: HashMap::new(),
+            apps: vec![],
+            mfa_challenge: Some(IamAccountMfaChallengeResp { ticket, kinds }),
+        })
+    }
+
+    /// Get the ticket which allows the second factor of the kind
+    pub async fn get_ticket(ticket: &str, kind: &IamCertKernelKind, funs: &TardisFunsInst) -> TardisResult<IamCertMfaTicket> {
+        let ticket_info = funs.cache().get(&format!("{}{}", funs.conf::<IamConfig>().mfa.cache_key_ticket_, ticket)).await?;
+        let ticket_info = match ticket_info {
+            Some(ticket_info) => TardisFuns::json.str_to_obj::<IamCertMfaTicket>(&ticket_info)?,
+            None => return Err(funs.err().unauthorized("iam_cert_mfa", "get_ticket", "mfa ticket is invalid or expired", "401-iam-cert-mfa-ticket-invalid")),
+        };
+        if !ticket_info.kinds.contains(kind) {
+            return Err(funs.err().bad_request(
+                "iam_cert_mfa",
+                "get_ticket",
+                &format!("second factor {kind} is not allowed"),
+                "400-iam-cert-mfa-kind-invalid",
+            ));
+        }
+        Ok(ticket_info)
+    }
+
+    /// Count the second factor verification of the account before it's performed,
+    /// it's rejected when the failed verifications exceed the ``ticket_err_times``.
+    ///
+    /// The count is increased before the verification, so that the concurrent verifications can't exceed the limit.
+    /// It's shared by the login and the double auth of the account, so a new login ticket doesn't reset it,
+    /// and the account is locked for ``verify_lock_sec`` once the limit is exceeded.
+    pub async fn start_verification(account_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        let iam_conf = funs.conf::<IamConfig>();
+        let key = format!("{}{}", iam_conf.mfa.cache_key_verify_times_, account_id);
+        let times = funs.cache().incr(&key, 1).await? as u32;
+        if times == 1 {
+            funs.cache().expire(&key, iam_conf.mfa.state_expire_sec as i64).await?;
+        } else if times == iam_conf.mfa.ticket_err_times + 1 {
+            funs.cache().expire(&key, iam_conf.mfa.verify_lock_sec as i64).await?;
+        }
+        if times > iam_conf.mfa.ticket_err_times {
+            return Err(funs.err().unauthorized(
+                "iam_cert_mfa",
+                "start_verification",
+                "too many failed verifications, please try again later",
+                "401-iam-cert-mfa-verify-locked",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reset the count of the verification after it is passed
+    pub async fn finish_verification(account_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.cache().del(&format!("{}{}", funs.conf::<IamConfig>().mfa.cache_key_verify_times_, account_id)).await?;
+        Ok(())
+    }
+
+    /// Check that the account has passed the double auth, see [crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ::add_double_auth]
+    pub async fn check_double_auth(account_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        if funs.cache().get(&format!("{}{}", funs.conf::<IamConfig>().cache_key_double_auth_info, account_id)).await?.is_none() {
+            return Err(funs.err().unauthorized("iam_cert_mfa", "check_double_auth", "double auth is required", "401-iam-cert-mfa-double-auth-required"));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_ticket(ticket: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.cache().del(&format!("{}{}", funs.conf::<IamConfig>().mfa.cache_key_ticket_, ticket)).await?;
+        Ok(())
+    }
+
+    async fn set_ticket(ticket: &str, ticket_info: &IamCertMfaTicket, funs: &TardisFunsInst) -> TardisResult<()> {
+        let iam_conf = funs.conf::<IamConfig>();
+        funs.cache()
+            .set_ex(
+                &format!("{}{}", iam_conf.mfa.cache_key_ticket_, ticket),
+                &TardisFuns::json.obj_to_string(ticket_info)?,
+                iam_conf.mfa.state_expire_sec,
+            )
+            .await
+    }
+}
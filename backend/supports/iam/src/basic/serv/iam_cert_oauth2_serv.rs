@@ -5,10 +5,11 @@ use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::field::TrimString;
 use tardis::basic::result::TardisResult;
+use tardis::serde_json::Value;
 use tardis::{TardisFuns, TardisFunsInst};
 
 use crate::basic::dto::iam_account_dto::IamAccountAggAddReq;
-use crate::basic::dto::iam_cert_conf_dto::{IamCertConfOAuth2AddOrModifyReq, IamCertConfOAuth2Resp};
+use crate::basic::dto::iam_cert_conf_dto::{IamCertConfOAuth2AddOrModifyReq, IamCertConfOAuth2Resp, IamCertConfOidcConf};
 use crate::basic::dto::iam_cert_dto::IamCertOAuth2AddOrModifyReq;
 use crate::basic::dto::iam_filer_dto::IamTenantFilterReq;
 use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
@@ -27,6 +28,7 @@ use super::clients::iam_search_client::IamSearchClient;
 use super::iam_account_serv::IamAccountServ;
 use super::iam_cert_serv::IamCertServ;
 use super::iam_tenant_serv::IamTenantServ;
+use super::oauth2_spi::iam_cert_oauth2_spi_oidc::IamCertOAuth2SpiOidc;
 use super::oauth2_spi::iam_cert_oauth2_spi_wechat_mp::IamCertOAuth2SpiWeChatMp;
 
 pub struct IamCertOAuth2Serv;
@@ -39,6 +41,7 @@ impl IamCertOAuth2Serv {
         funs: &TardisFunsInst,
         ctx: &TardisContext,
     ) -> TardisResult<String> {
+        Self::check_cert_conf(&cert_supplier, add_req, funs)?;
         RbumCertConfServ::add_rbum(
             &mut RbumCertConfAddReq {
                 kind: TrimString(IamCertExtKind::OAuth2.to_string()),
@@ -73,6 +76,7 @@ impl IamCertOAuth2Serv {
     }
 
     pub async fn modify_cert_conf(id: &str, modify_req: &IamCertConfOAuth2AddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        Self::check_cert_conf(&IamCertOAuth2Supplier::parse(&modify_req.supplier)?, modify_req, funs)?;
         RbumCertConfServ::modify_rbum(
             id,
             &mut RbumCertConfModifyReq {
@@ -193,7 +197,14 @@ impl IamCertOAuth2Serv {
         Ok(result)
     }
 
-    pub async fn get_or_add_account(cert_supplier: IamCertOAuth2Supplier, code: &str, tenant_id: &str, funs: &TardisFunsInst) -> TardisResult<(String, String)> {
+    /// The ``state`` is required by the ``Oidc`` supplier, see [IamCertOAuth2SpiOidc::get_authorize_url]
+    pub async fn get_or_add_account(
+        cert_supplier: IamCertOAuth2Supplier,
+        code: &str,
+        state: Option<String>,
+        tenant_id: &str,
+        funs: &TardisFunsInst,
+    ) -> TardisResult<(String, String)> {
         let cert_conf_id =
             IamCertServ::get_cert_conf_id_by_kind_supplier(&IamCertExtKind::OAuth2.to_string(), &cert_supplier.to_string(), Some(tenant_id.to_string()), funs).await?;
         let mut mock_ctx = TardisContext {
@@ -201,12 +212,33 @@ impl IamCertOAuth2Serv {
             ..Default::default()
         };
         let cert_conf = Self::get_cert_conf(&cert_conf_id, funs, &mock_ctx).await?;
-        let client = Self::get_access_token_func(cert_supplier);
+        let oidc_conf = match cert_supplier {
+            IamCertOAuth2Supplier::Oidc => Some(Self::get_oidc_conf(&cert_conf, funs)?),
+            _ => None,
+        };
+        let client = Self::get_access_token_func(cert_supplier, oidc_conf.clone(), tenant_id, state);
         let oauth_token_info = client.get_access_token(code, &cert_conf.ak, &cert_conf.sk, funs).await?;
+        let oidc_account_info = if let Some(oidc_conf) = &oidc_conf {
+            Some(IamCertOAuth2SpiOidc::map_account(
+                oidc_conf,
+                oauth_token_info.claims.as_ref().unwrap_or(&Value::Null),
+                funs,
+            )?)
+        } else {
+            None
+        };
         if let Some(account_id) = Self::get_cert_rel_account_by_open_id(&oauth_token_info.open_id, &cert_conf_id, funs, &mock_ctx).await? {
+            if let (Some(oidc_conf), Some(oidc_account_info)) = (&oidc_conf, &oidc_account_info) {
+                IamCertOAuth2SpiOidc::sync_mapped_roles_and_orgs(&account_id, oidc_conf, oidc_account_info, funs, &mock_ctx).await?;
+            }
             return Ok((account_id, oauth_token_info.access_token));
         }
-        if !IamTenantServ::get_item(tenant_id, &IamTenantFilterReq::default(), funs, &mock_ctx).await?.account_self_reg {
+        let can_register = if let Some(oidc_conf) = &oidc_conf {
+            oidc_conf.jit_provisioning
+        } else {
+            IamTenantServ::get_item(tenant_id, &IamTenantFilterReq::default(), funs, &mock_ctx).await?.account_self_reg
+        };
+        if !can_register {
             return Err(funs.err().not_found(
                 "rbum_cert",
                 "get_or_add_account",
@@ -225,12 +257,12 @@ impl IamCertOAuth2Serv {
                 cert_password: Some(TrimString(format!("{}0Pw$", TardisFuns::field.nanoid_len(6)))),
                 cert_phone: None,
                 cert_mail: None,
-                role_ids: None,
-                org_node_ids: None,
+                role_ids: oidc_account_info.as_ref().map(|info| info.role_ids.clone()),
+                org_node_ids: oidc_account_info.as_ref().map(|info| info.org_node_ids.clone()),
                 scope_level: None,
                 disabled: None,
                 icon: None,
-                exts: HashMap::new(),
+                exts: oidc_account_info.as_ref().map(|info| info.exts.clone()).unwrap_or_default(),
                 status: Some(Pending),
                 temporary: None,
                 lock_status: None,
@@ -252,19 +284,66 @@ impl IamCertOAuth2Serv {
             &mock_ctx,
         )
         .await?;
+        if let Some(oidc_account_info) = &oidc_account_info {
+            IamCertOAuth2SpiOidc::add_contact_certs(&account_id, oidc_account_info, funs, &mock_ctx).await?;
+        }
         IamSearchClient::async_add_or_modify_account_search(&account_id, Box::new(false), "", funs, &mock_ctx).await?;
         mock_ctx.execute_task().await?;
         Ok((account_id, oauth_token_info.access_token))
     }
 
-    fn get_access_token_func(supplier: IamCertOAuth2Supplier) -> Box<dyn IamCertOAuth2Spi> {
+    fn get_access_token_func(supplier: IamCertOAuth2Supplier, oidc_conf: Option<IamCertConfOidcConf>, tenant_id: &str, state: Option<String>) -> Box<dyn IamCertOAuth2Spi> {
         match supplier {
             // IamCertOAuth2Supplier::Weibo => {}
             IamCertOAuth2Supplier::Github => Box::new(IamCertOAuth2SpiGithub),
             IamCertOAuth2Supplier::WechatMp => Box::new(IamCertOAuth2SpiWeChatMp),
+            IamCertOAuth2Supplier::Oidc => Box::new(IamCertOAuth2SpiOidc::new(oidc_conf.unwrap_or_default(), tenant_id, state)),
         }
     }
 
+    /// Get the url to redirect to for the login, only the ``Oidc`` supplier is supported
+    pub async fn get_authorize_url(cert_supplier: IamCertOAuth2Supplier, tenant_id: &str, redirect_uri: &str, funs: &TardisFunsInst) -> TardisResult<String> {
+        if cert_supplier != IamCertOAuth2Supplier::Oidc {
+            return Err(funs.err().bad_request(
+                "iam_cert_oauth2",
+                "get_authorize_url",
+                &format!("oauth2 supplier {cert_supplier} does not support generating the authorize url"),
+                "400-iam-cert-oauth-authorize-url-unsupported",
+            ));
+        }
+        let cert_conf_id =
+            IamCertServ::get_cert_conf_id_by_kind_supplier(&IamCertExtKind::OAuth2.to_string(), &cert_supplier.to_string(), Some(tenant_id.to_string()), funs).await?;
+        let mock_ctx = TardisContext {
+            own_paths: tenant_id.to_string(),
+            ..Default::default()
+        };
+        let cert_conf = Self::get_cert_conf(&cert_conf_id, funs, &mock_ctx).await?;
+        IamCertOAuth2SpiOidc::get_authorize_url(&Self::get_oidc_conf(&cert_conf, funs)?, &cert_conf.ak, tenant_id, redirect_uri, funs).await
+    }
+
+    fn get_oidc_conf(cert_conf: &IamCertConfOAuth2Resp, funs: &TardisFunsInst) -> TardisResult<IamCertConfOidcConf> {
+        cert_conf.oidc.clone().ok_or_else(|| {
+            funs.err().not_found(
+                "iam_cert_oauth2",
+                "get_oidc_conf",
+                "oidc configuration is not found",
+                "404-iam-cert-oauth-oidc-conf-not-exist",
+            )
+        })
+    }
+
+    fn check_cert_conf(supplier: &IamCertOAuth2Supplier, req: &IamCertConfOAuth2AddOrModifyReq, funs: &TardisFunsInst) -> TardisResult<()> {
+        if supplier == &IamCertOAuth2Supplier::Oidc && req.oidc.as_ref().map(|oidc| oidc.issuer.trim().is_empty()).unwrap_or(true) {
+            return Err(funs.err().bad_request(
+                "iam_cert_oauth2",
+                "check_cert_conf",
+                "the issuer of the oidc configuration is required",
+                "400-iam-cert-oauth-oidc-conf-invalid",
+            ));
+        }
+        Ok(())
+    }
+
     pub async fn add_or_enable_cert_conf(
         supplier: IamCertOAuth2Supplier,
         add_req: &IamCertConfOAuth2AddOrModifyReq,
@@ -306,4 +385,6 @@ pub struct IamCertOAuth2TokenInfo {
     pub refresh_token: Option<String>,
     pub token_expires_ms: Option<u32>,
     pub union_id: Option<String>,
+    /// The verified id_token claims merged with the userinfo, only for the ``Oidc`` supplier
+    pub claims: Option<Value>,
 }
@@ -42,6 +42,7 @@ use crate::basic::dto::iam_filer_dto::{IamAccountFilterReq, IamResFilterReq, Iam
 use crate::basic::serv::iam_account_serv::IamAccountServ;
 use crate::basic::serv::iam_cert_ldap_serv::IamCertLdapServ;
 use crate::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
+use crate::basic::serv::iam_cert_mfa_serv::IamCertMfaServ;
 use crate::basic::serv::iam_cert_phone_vcode_serv::IamCertPhoneVCodeServ;
 use crate::basic::serv::iam_cert_token_serv::IamCertTokenServ;
 use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
@@ -1166,6 +1167,22 @@ impl IamCertServ {
         access_token: Option<String>,
         ip: Option<String>,
         funs: &TardisFunsInst,
+    ) -> TardisResult<IamAccountInfoResp> {
+        let mfa_kinds = IamCertMfaServ::get_required_kinds(tenant_id.as_deref().unwrap_or_default(), account_id, funs).await?;
+        if !mfa_kinds.is_empty() {
+            return IamCertMfaServ::challenge(tenant_id, account_id, token_kind, access_token, mfa_kinds, funs).await;
+        }
+        Self::package_tardis_context_and_resp_without_mfa(tenant_id, account_id, token_kind, access_token, ip, funs).await
+    }
+
+    /// Package the login response without the second step, it should only be called when the first step is passed and the second step is passed or not required
+    pub async fn package_tardis_context_and_resp_without_mfa(
+        tenant_id: Option<String>,
+        account_id: &str,
+        token_kind: Option<String>,
+        access_token: Option<String>,
+        ip: Option<String>,
+        funs: &TardisFunsInst,
     ) -> TardisResult<IamAccountInfoResp> {
         let token_kind = IamCertTokenKind::parse(&token_kind);
         let token = TardisFuns::crypto.key.generate_token()?;
@@ -1224,6 +1241,7 @@ impl IamCertServ {
             roles: account_agg.roles,
             groups: account_agg.groups,
             apps: account_agg.apps,
+            mfa_challenge: None,
         };
         IamIdentCacheServ::add_contexts(&account_info, tenant_id, funs).await?;
         Ok(account_info)
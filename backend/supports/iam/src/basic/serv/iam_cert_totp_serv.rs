@@ -0,0 +1,286 @@
+use bios_basic::rbum::dto::rbum_cert_conf_dto::RbumCertConfAddReq;
+use bios_basic::rbum::dto::rbum_cert_dto::{RbumCertAddReq, RbumCertSummaryResp};
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertConfFilterReq, RbumCertFilterReq};
+use bios_basic::rbum::helper::rbum_scope_helper::get_max_level_id_by_context;
+use bios_basic::rbum::rbum_enumeration::{RbumCertConfStatusKind, RbumCertRelKind, RbumCertStatusKind};
+use bios_basic::rbum::serv::rbum_cert_serv::{RbumCertConfServ, RbumCertServ};
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::Utc;
+use tardis::{TardisFuns, TardisFunsInst};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::basic::dto::iam_cert_dto::{IamCertTotpBindResp, IamCertTotpEnrollResp};
+use crate::basic::dto::iam_filer_dto::IamAccountFilterReq;
+use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::iam_config::{IamBasicConfigApi, IamConfig};
+use crate::iam_enumeration::IamCertKernelKind;
+
+const TOTP_DIGITS: usize = 6;
+const TOTP_SKEW: u8 = 1;
+const TOTP_STEP: u64 = 30;
+const RECOVERY_CODE_LEN: usize = 10;
+const RECOVERY_CODE_ALPHABET: [char; 32] = [
+    '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'j', 'k', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0',
+];
+
+/// Ext of the TOTP cert
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct IamCertTotpExt {
+    /// Sha256 of the unused recovery codes
+    recovery_codes: Vec<String>,
+    /// The last accepted time step, the one-time password can't be used twice
+    last_time_step: u64,
+}
+
+/// TOTP (RFC 6238) second factor, the ak of the cert is the account id and the sk is the base32 encoded secret.
+pub struct IamCertTotpServ;
+
+impl IamCertTotpServ {
+    pub async fn add_cert_conf(rel_iam_item_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let id = RbumCertConfServ::add_rbum(
+            &mut RbumCertConfAddReq {
+                kind: TrimString(IamCertKernelKind::Totp.to_string()),
+                supplier: None,
+                name: TrimString(IamCertKernelKind::Totp.to_string()),
+                note: None,
+                ak_note: None,
+                ak_rule: None,
+                sk_note: None,
+                sk_rule: None,
+                ext: None,
+                sk_need: Some(true),
+                sk_dynamic: Some(false),
+                sk_encrypted: Some(false),
+                repeatable: None,
+                is_basic: Some(false),
+                rest_by_kinds: None,
+                expire_sec: None,
+                sk_lock_cycle_sec: None,
+                sk_lock_err_times: None,
+                sk_lock_duration_sec: None,
+                coexist_num: Some(1),
+                conn_uri: None,
+                status: RbumCertConfStatusKind::Enabled,
+                rel_rbum_domain_id: funs.iam_basic_domain_iam_id(),
+                rel_rbum_item_id: rel_iam_item_id,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    pub async fn add_or_enable_cert_conf(rel_iam_item_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let cert_result = RbumCertConfServ::do_find_one_rbum(
+            &RbumCertConfFilterReq {
+                kind: Some(TrimString(IamCertKernelKind::Totp.to_string())),
+                rel_rbum_item_id: rel_iam_item_id.clone(),
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        let result = if let Some(cert_result) = cert_result {
+            IamCertServ::enabled_cert_conf(&cert_result.id, funs, ctx).await?;
+            cert_result.id
+        } else {
+            Self::add_cert_conf(rel_iam_item_id, funs, ctx).await?
+        };
+        Ok(result)
+    }
+
+    /// Generate a secret for the current account, it takes effect after [Self::bind]
+    pub async fn enroll(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertTotpEnrollResp> {
+        let ctx = IamAccountServ::new_context_if_account_is_global(ctx, funs).await?;
+        let rel_rbum_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::Totp.to_string(), get_max_level_id_by_context(&ctx), funs).await?;
+        if Self::find_cert(&ctx.owner, &rel_rbum_cert_conf_id, funs, &ctx).await?.is_some() {
+            return Err(funs.err().conflict("iam_cert_totp", "enroll", "totp is already bound", "409-iam-cert-totp-exist"));
+        }
+        let account = IamAccountServ::peek_item(
+            &ctx.owner,
+            &IamAccountFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            &ctx,
+        )
+        .await?;
+        let iam_conf = funs.conf::<IamConfig>();
+        let mfa_conf = &iam_conf.mfa;
+        let secret = Secret::generate_secret()
+            .to_bytes()
+            .map_err(|e| funs.err().internal_error("iam_cert_totp", "enroll", &format!("generate secret error: {e}"), "500-iam-cert-totp-secret-error"))?;
+        // ':' is the separator of the label of the provisioning uri
+        let totp = TOTP::new(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            TOTP_SKEW,
+            TOTP_STEP,
+            secret,
+            Some(mfa_conf.totp_issuer.replace(':', "_")),
+            account.name.replace(':', "_"),
+        )
+        .map_err(|e| funs.err().internal_error("iam_cert_totp", "enroll", &format!("generate totp error: {e}"), "500-iam-cert-totp-secret-error"))?;
+        let secret = totp.get_secret_base32();
+        funs.cache().set_ex(&format!("{}{}", mfa_conf.cache_key_totp_enrollment_, ctx.owner), &secret, mfa_conf.state_expire_sec).await?;
+        Ok(IamCertTotpEnrollResp {
+            secret,
+            provisioning_uri: totp.get_url(),
+        })
+    }
+
+    /// Bind the enrolled secret to the current account by the one-time password generated by the authenticator app
+    pub async fn bind(code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamCertTotpBindResp> {
+        let ctx = IamAccountServ::new_context_if_account_is_global(ctx, funs).await?;
+        let rel_rbum_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::Totp.to_string(), get_max_level_id_by_context(&ctx), funs).await?;
+        let iam_conf = funs.conf::<IamConfig>();
+        let mfa_conf = &iam_conf.mfa;
+        let enrollment_key = format!("{}{}", mfa_conf.cache_key_totp_enrollment_, ctx.owner);
+        let secret = funs
+            .cache()
+            .get(&enrollment_key)
+            .await?
+            .ok_or_else(|| funs.err().bad_request("iam_cert_totp", "bind", "totp enrollment is expired", "400-iam-cert-totp-enrollment-expired"))?;
+        let time_step = Self::verify_code(&secret, code, 0, funs)?.ok_or_else(|| Self::code_err("bind", funs))?;
+        funs.cache().del(&enrollment_key).await?;
+        let recovery_codes = (0..mfa_conf.totp_recovery_code_num).map(|_| nanoid::nanoid!(RECOVERY_CODE_LEN, &RECOVERY_CODE_ALPHABET)).collect::<Vec<_>>();
+        let ext = IamCertTotpExt {
+            recovery_codes: recovery_codes.iter().map(|recovery_code| Self::digest(recovery_code)).collect(),
+            last_time_step: time_step,
+        };
+        let id = RbumCertServ::add_rbum(
+            &mut RbumCertAddReq {
+                ak: TrimString(ctx.owner.clone()),
+                sk: Some(TrimString(secret)),
+                sk_invisible: Some(true),
+                kind: None,
+                supplier: None,
+                vcode: None,
+                ext: Some(TardisFuns::json.obj_to_string(&ext)?),
+                start_time: None,
+                end_time: None,
+                conn_uri: None,
+                status: RbumCertStatusKind::Enabled,
+                rel_rbum_cert_conf_id: Some(rel_rbum_cert_conf_id),
+                rel_rbum_kind: RbumCertRelKind::Item,
+                rel_rbum_id: ctx.owner.clone(),
+                is_outside: false,
+                ignore_check_sk: false,
+            },
+            funs,
+            &ctx,
+        )
+        .await?;
+        Ok(IamCertTotpBindResp { id, recovery_codes })
+    }
+
+    /// Validate the one-time password or a recovery code of the account, the used recovery code is removed
+    pub async fn validate(account_id: &str, code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let ctx = IamAccountServ::is_global_account_context(account_id, funs, ctx).await?;
+        let rel_rbum_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::Totp.to_string(), get_max_level_id_by_context(&ctx), funs).await?;
+        let cert = Self::find_cert(account_id, &rel_rbum_cert_conf_id, funs, &ctx)
+            .await?
+            .ok_or_else(|| funs.err().not_found("iam_cert_totp", "validate", "totp is not bound", "404-iam-cert-totp-not-exist"))?;
+        let secret = RbumCertServ::show_sk(
+            &cert.id,
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some("".to_string()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            &ctx,
+        )
+        .await?;
+        let mut ext = TardisFuns::json.str_to_obj::<IamCertTotpExt>(&cert.ext)?;
+        if let Some(time_step) = Self::verify_code(&secret, code, ext.last_time_step, funs)? {
+            ext.last_time_step = time_step;
+        } else if let Some(idx) = ext.recovery_codes.iter().position(|recovery_code| recovery_code == &Self::digest(code)) {
+            ext.recovery_codes.remove(idx);
+        } else {
+            return Err(Self::code_err("validate", funs));
+        }
+        // the concurrent validations of the same code are serialized by the ext, only the first one consumes it
+        if !RbumCertServ::modify_ext_if_unchanged(&cert.id, &cert.ext, &TardisFuns::json.obj_to_string(&ext)?, funs, &ctx).await? {
+            return Err(Self::code_err("validate", funs));
+        }
+        Ok(())
+    }
+
+    /// Whether the account has bound the totp
+    pub async fn exist_cert(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        let ctx = IamAccountServ::is_global_account_context(account_id, funs, ctx).await?;
+        let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&IamCertKernelKind::Totp.to_string(), get_max_level_id_by_context(&ctx), funs).await? else {
+            return Ok(false);
+        };
+        Ok(Self::find_cert(account_id, &cert_conf.id, funs, &ctx).await?.is_some())
+    }
+
+    pub async fn delete_cert(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let ctx = IamAccountServ::is_global_account_context(account_id, funs, ctx).await?;
+        let rel_rbum_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::Totp.to_string(), get_max_level_id_by_context(&ctx), funs).await?;
+        if let Some(cert) = Self::find_cert(account_id, &rel_rbum_cert_conf_id, funs, &ctx).await? {
+            RbumCertServ::delete_rbum(&cert.id, funs, &ctx).await?;
+        }
+        Ok(())
+    }
+
+    async fn find_cert(account_id: &str, rel_rbum_cert_conf_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<RbumCertSummaryResp>> {
+        RbumCertServ::find_one_rbum(
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some("".to_string()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                rel_rbum_id: Some(account_id.to_string()),
+                rel_rbum_cert_conf_ids: Some(vec![rel_rbum_cert_conf_id.to_string()]),
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    /// Return the matched time step, which should be greater than ``last_time_step``
+    fn verify_code(secret: &str, code: &str, last_time_step: u64, funs: &TardisFunsInst) -> TardisResult<Option<u64>> {
+        if code.len() != TOTP_DIGITS || !code.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(None);
+        }
+        let secret = Secret::Encoded(secret.to_string())
+            .to_bytes()
+            .map_err(|e| funs.err().internal_error("iam_cert_totp", "verify_code", &format!("totp secret is invalid: {e}"), "500-iam-cert-totp-secret-error"))?;
+        let totp = TOTP::new_unchecked(Algorithm::SHA1, TOTP_DIGITS, TOTP_SKEW, TOTP_STEP, secret, None, "".to_string());
+        let current_time_step = Utc::now().timestamp() as u64 / TOTP_STEP;
+        let time_step = (current_time_step.saturating_sub(TOTP_SKEW as u64)..=current_time_step + TOTP_SKEW as u64)
+            .filter(|time_step| *time_step > last_time_step)
+            .find(|time_step| totp.generate(time_step * TOTP_STEP) == code);
+        Ok(time_step)
+    }
+
+    fn digest(recovery_code: &str) -> String {
+        format!("{:x}", Sha256::digest(recovery_code.trim().to_lowercase().as_bytes()))
+    }
+
+    fn code_err(op: &str, funs: &TardisFunsInst) -> TardisError {
+        funs.err().unauthorized("iam_cert_totp", op, "one-time password is invalid", "401-iam-cert-totp-code-invalid")
+    }
+}
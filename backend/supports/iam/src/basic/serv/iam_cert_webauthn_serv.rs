@@ -0,0 +1,326 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use bios_basic::rbum::dto::rbum_cert_conf_dto::RbumCertConfAddReq;
+use bios_basic::rbum::dto::rbum_cert_dto::{RbumCertAddReq, RbumCertModifyReq, RbumCertSummaryResp};
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertConfFilterReq, RbumCertFilterReq};
+use bios_basic::rbum::helper::rbum_scope_helper::get_max_level_id_by_context;
+use bios_basic::rbum::rbum_enumeration::{RbumCertConfStatusKind, RbumCertRelKind, RbumCertStatusKind};
+use bios_basic::rbum::serv::rbum_cert_serv::{RbumCertConfServ, RbumCertServ};
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::Value;
+use tardis::{TardisFuns, TardisFunsInst};
+use webauthn_rs::prelude::{Passkey, PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential, Url, Uuid, Webauthn, WebauthnBuilder};
+
+use crate::basic::dto::iam_cert_dto::IamCertWebAuthnResp;
+use crate::basic::dto::iam_filer_dto::IamAccountFilterReq;
+use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::iam_config::{IamBasicConfigApi, IamConfig};
+use crate::iam_enumeration::IamCertKernelKind;
+
+const STATE_REGISTRATION: &str = "reg:";
+const STATE_AUTHENTICATION: &str = "auth:";
+
+/// Ext of the WebAuthn cert
+#[derive(Serialize, Deserialize, Debug)]
+struct IamCertWebAuthnExt {
+    name: String,
+    passkey: Passkey,
+}
+
+/// WebAuthn (passkey / security key) second factor,
+/// an account can register multiple credentials, the ak of the cert is the credential id.
+pub struct IamCertWebAuthnServ;
+
+impl IamCertWebAuthnServ {
+    pub async fn add_cert_conf(rel_iam_item_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let id = RbumCertConfServ::add_rbum(
+            &mut RbumCertConfAddReq {
+                kind: TrimString(IamCertKernelKind::WebAuthn.to_string()),
+                supplier: None,
+                name: TrimString(IamCertKernelKind::WebAuthn.to_string()),
+                note: None,
+                ak_note: None,
+                ak_rule: None,
+                sk_note: None,
+                sk_rule: None,
+                ext: None,
+                sk_need: Some(false),
+                sk_dynamic: Some(false),
+                sk_encrypted: Some(false),
+                repeatable: None,
+                is_basic: Some(false),
+                rest_by_kinds: None,
+                expire_sec: None,
+                sk_lock_cycle_sec: None,
+                sk_lock_err_times: None,
+                sk_lock_duration_sec: None,
+                coexist_num: Some(1),
+                conn_uri: None,
+                status: RbumCertConfStatusKind::Enabled,
+                rel_rbum_domain_id: funs.iam_basic_domain_iam_id(),
+                rel_rbum_item_id: rel_iam_item_id,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    pub async fn add_or_enable_cert_conf(rel_iam_item_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let cert_result = RbumCertConfServ::do_find_one_rbum(
+            &RbumCertConfFilterReq {
+                kind: Some(TrimString(IamCertKernelKind::WebAuthn.to_string())),
+                rel_rbum_item_id: rel_iam_item_id.clone(),
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        let result = if let Some(cert_result) = cert_result {
+            IamCertServ::enabled_cert_conf(&cert_result.id, funs, ctx).await?;
+            cert_result.id
+        } else {
+            Self::add_cert_conf(rel_iam_item_id, funs, ctx).await?
+        };
+        Ok(result)
+    }
+
+    /// Start registering a credential of the current account,
+    /// return the ``PublicKeyCredentialCreationOptions`` for ``navigator.credentials.create()``
+    pub async fn start_register(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Value> {
+        let ctx = IamAccountServ::new_context_if_account_is_global(ctx, funs).await?;
+        let rel_rbum_cert_conf_id = Self::get_cert_conf_id(&ctx, funs).await?;
+        let mut exclude_credentials = vec![];
+        for cert in Self::find_certs(&ctx.owner, &rel_rbum_cert_conf_id, funs, &ctx).await? {
+            exclude_credentials.push(TardisFuns::json.str_to_obj::<IamCertWebAuthnExt>(&cert.ext)?.passkey.cred_id().clone());
+        }
+        let account = IamAccountServ::peek_item(
+            &ctx.owner,
+            &IamAccountFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            &ctx,
+        )
+        .await?;
+        let (options, state) =
+            Self::webauthn(funs)?.start_passkey_registration(Self::user_unique_id(&ctx.owner), &account.name, &account.name, Some(exclude_credentials)).map_err(|e| {
+                funs.err().internal_error(
+                    "iam_cert_webauthn",
+                    "start_register",
+                    &format!("start registration error: {e}"),
+                    "500-iam-cert-webauthn-error",
+                )
+            })?;
+        Self::set_state(STATE_REGISTRATION, &ctx.owner, &state, funs).await?;
+        TardisFuns::json.obj_to_json(&options)
+    }
+
+    /// Finish the registration by the credential created by the authenticator, return the cert id
+    pub async fn finish_register(name: &str, credential: &Value, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let ctx = IamAccountServ::new_context_if_account_is_global(ctx, funs).await?;
+        let rel_rbum_cert_conf_id = Self::get_cert_conf_id(&ctx, funs).await?;
+        let state = Self::take_state::<PasskeyRegistration>(STATE_REGISTRATION, &ctx.owner, funs).await?;
+        let credential = TardisFuns::json.json_to_obj::<RegisterPublicKeyCredential>(credential.clone())?;
+        let passkey = Self::webauthn(funs)?.finish_passkey_registration(&credential, &state).map_err(|e| Self::credential_err("finish_register", &e.to_string(), funs))?;
+        let ext = IamCertWebAuthnExt { name: name.to_string(), passkey };
+        RbumCertServ::add_rbum(
+            &mut RbumCertAddReq {
+                ak: TrimString(URL_SAFE_NO_PAD.encode(ext.passkey.cred_id())),
+                sk: None,
+                sk_invisible: None,
+                kind: None,
+                supplier: None,
+                vcode: None,
+                ext: Some(TardisFuns::json.obj_to_string(&ext)?),
+                start_time: None,
+                end_time: None,
+                conn_uri: None,
+                status: RbumCertStatusKind::Enabled,
+                rel_rbum_cert_conf_id: Some(rel_rbum_cert_conf_id),
+                rel_rbum_kind: RbumCertRelKind::Item,
+                rel_rbum_id: ctx.owner.clone(),
+                is_outside: false,
+                ignore_check_sk: false,
+            },
+            funs,
+            &ctx,
+        )
+        .await
+    }
+
+    /// Start authenticating the account by the registered credentials,
+    /// return the ``PublicKeyCredentialRequestOptions`` for ``navigator.credentials.get()``
+    pub async fn start_authenticate(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Value> {
+        let ctx = IamAccountServ::is_global_account_context(account_id, funs, ctx).await?;
+        let rel_rbum_cert_conf_id = Self::get_cert_conf_id(&ctx, funs).await?;
+        let mut passkeys = vec![];
+        for cert in Self::find_certs(account_id, &rel_rbum_cert_conf_id, funs, &ctx).await? {
+            passkeys.push(TardisFuns::json.str_to_obj::<IamCertWebAuthnExt>(&cert.ext)?.passkey);
+        }
+        if passkeys.is_empty() {
+            return Err(funs.err().not_found(
+                "iam_cert_webauthn",
+                "start_authenticate",
+                "webauthn credential is not registered",
+                "404-iam-cert-webauthn-not-exist",
+            ));
+        }
+        let (options, state) = Self::webauthn(funs)?.start_passkey_authentication(&passkeys).map_err(|e| {
+            funs.err().internal_error(
+                "iam_cert_webauthn",
+                "start_authenticate",
+                &format!("start authentication error: {e}"),
+                "500-iam-cert-webauthn-error",
+            )
+        })?;
+        Self::set_state(STATE_AUTHENTICATION, account_id, &state, funs).await?;
+        TardisFuns::json.obj_to_json(&options)
+    }
+
+    /// Finish the authentication by the assertion of the authenticator, the state can only be used once
+    pub async fn finish_authenticate(account_id: &str, credential: &Value, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let ctx = IamAccountServ::is_global_account_context(account_id, funs, ctx).await?;
+        let state = Self::take_state::<PasskeyAuthentication>(STATE_AUTHENTICATION, account_id, funs).await?;
+        let credential = TardisFuns::json.json_to_obj::<PublicKeyCredential>(credential.clone())?;
+        let result = Self::webauthn(funs)?.finish_passkey_authentication(&credential, &state).map_err(|e| Self::credential_err("finish_authenticate", &e.to_string(), funs))?;
+        // Keep the signature counter up to date to detect the cloned authenticators
+        let rel_rbum_cert_conf_id = Self::get_cert_conf_id(&ctx, funs).await?;
+        let cred_id = URL_SAFE_NO_PAD.encode(result.cred_id());
+        if let Some(cert) = Self::find_certs(account_id, &rel_rbum_cert_conf_id, funs, &ctx).await?.into_iter().find(|cert| cert.ak == cred_id) {
+            let mut ext = TardisFuns::json.str_to_obj::<IamCertWebAuthnExt>(&cert.ext)?;
+            if ext.passkey.update_credential(&result) == Some(true) {
+                RbumCertServ::modify_rbum(
+                    &cert.id,
+                    &mut RbumCertModifyReq {
+                        ak: None,
+                        sk: None,
+                        sk_invisible: None,
+                        ignore_check_sk: false,
+                        ext: Some(TardisFuns::json.obj_to_string(&ext)?),
+                        start_time: None,
+                        end_time: None,
+                        conn_uri: None,
+                        status: None,
+                    },
+                    funs,
+                    &ctx,
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn find_certs_by_account(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<IamCertWebAuthnResp>> {
+        let ctx = IamAccountServ::is_global_account_context(account_id, funs, ctx).await?;
+        let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&IamCertKernelKind::WebAuthn.to_string(), get_max_level_id_by_context(&ctx), funs).await? else {
+            return Ok(vec![]);
+        };
+        let mut result = vec![];
+        for cert in Self::find_certs(account_id, &cert_conf.id, funs, &ctx).await? {
+            result.push(IamCertWebAuthnResp {
+                name: TardisFuns::json.str_to_obj::<IamCertWebAuthnExt>(&cert.ext)?.name,
+                id: cert.id,
+                create_time: cert.create_time,
+                update_time: cert.update_time,
+            });
+        }
+        Ok(result)
+    }
+
+    pub async fn delete_cert(id: &str, account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let ctx = IamAccountServ::is_global_account_context(account_id, funs, ctx).await?;
+        let rel_rbum_cert_conf_id = Self::get_cert_conf_id(&ctx, funs).await?;
+        if !Self::find_certs(account_id, &rel_rbum_cert_conf_id, funs, &ctx).await?.iter().any(|cert| cert.id == id) {
+            return Err(funs.err().not_found("iam_cert_webauthn", "delete", "webauthn credential is not registered", "404-iam-cert-webauthn-not-exist"));
+        }
+        RbumCertServ::delete_rbum(id, funs, &ctx).await?;
+        Ok(())
+    }
+
+    async fn get_cert_conf_id(ctx: &TardisContext, funs: &TardisFunsInst) -> TardisResult<String> {
+        IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::WebAuthn.to_string(), get_max_level_id_by_context(ctx), funs).await
+    }
+
+    async fn find_certs(account_id: &str, rel_rbum_cert_conf_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<RbumCertSummaryResp>> {
+        RbumCertServ::find_rbums(
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some("".to_string()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                rel_rbum_id: Some(account_id.to_string()),
+                rel_rbum_cert_conf_ids: Some(vec![rel_rbum_cert_conf_id.to_string()]),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    async fn set_state<T: Serialize>(kind: &str, account_id: &str, state: &T, funs: &TardisFunsInst) -> TardisResult<()> {
+        let iam_conf = funs.conf::<IamConfig>();
+        funs.cache()
+            .set_ex(
+                &format!("{}{}{}", iam_conf.mfa.cache_key_webauthn_state_, kind, account_id),
+                &TardisFuns::json.obj_to_string(state)?,
+                iam_conf.mfa.state_expire_sec,
+            )
+            .await
+    }
+
+    async fn take_state<T: DeserializeOwned>(kind: &str, account_id: &str, funs: &TardisFunsInst) -> TardisResult<T> {
+        let key = format!("{}{}{}", funs.conf::<IamConfig>().mfa.cache_key_webauthn_state_, kind, account_id);
+        let state = funs.cache().get(&key).await?;
+        funs.cache().del(&key).await?;
+        match state {
+            Some(state) => TardisFuns::json.str_to_obj(&state),
+            None => Err(funs.err().bad_request("iam_cert_webauthn", "take_state", "webauthn ceremony is expired", "400-iam-cert-webauthn-expired")),
+        }
+    }
+
+    fn webauthn(funs: &TardisFunsInst) -> TardisResult<Webauthn> {
+        let iam_conf = funs.conf::<IamConfig>();
+        let rp_origin = Url::parse(&iam_conf.mfa.webauthn_rp_origin).map_err(|e| {
+            funs.err().internal_error(
+                "iam_cert_webauthn",
+                "webauthn",
+                &format!("relying party origin is invalid: {e}"),
+                "500-iam-cert-webauthn-error",
+            )
+        })?;
+        WebauthnBuilder::new(&iam_conf.mfa.webauthn_rp_id, &rp_origin)
+            .and_then(|builder| builder.rp_name(&iam_conf.mfa.webauthn_rp_name).build())
+            .map_err(|e| funs.err().internal_error("iam_cert_webauthn", "webauthn", &format!("relying party is invalid: {e}"), "500-iam-cert-webauthn-error"))
+    }
+
+    /// The user handle should not contain the personally identifying information, so the digest of the account id is used
+    fn user_unique_id(account_id: &str) -> Uuid {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&Sha256::digest(account_id.as_bytes())[..16]);
+        Uuid::from_bytes(bytes)
+    }
+
+    fn credential_err(op: &str, msg: &str, funs: &TardisFunsInst) -> TardisError {
+        funs.err().unauthorized("iam_cert_webauthn", op, &format!("webauthn credential is invalid: {msg}"), "401-iam-cert-webauthn-invalid")
+    }
+}
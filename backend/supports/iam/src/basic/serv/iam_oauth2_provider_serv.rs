@@ -0,0 +1,776 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertConfFilterReq, RbumCertFilterReq};
+use bios_basic::rbum::rbum_enumeration::{RbumCertRelKind, RbumCertStatusKind};
+use bios_basic::rbum::serv::rbum_cert_serv::{RbumCertConfServ, RbumCertServ};
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, LineEnding};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::Utc;
+use tardis::{TardisFuns, TardisFunsInst};
+
+use crate::basic::dto::iam_filer_dto::IamAccountFilterReq;
+use crate::basic::dto::iam_oauth2_provider_dto::{
+    IamOAuth2AuthorizeReq, IamOAuth2ClientConf, IamOAuth2ErrorResp, IamOAuth2Grant, IamOAuth2IntrospectResp, IamOAuth2TokenOpReq, IamOAuth2TokenReq, IamOAuth2TokenResp,
+    IamOidcDiscoveryResp, IamOidcIdTokenClaims, IamOidcJwkResp, IamOidcJwksResp, IamOidcUserInfoResp,
+};
+use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_cert_mfa_serv::IamCertMfaServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
+use crate::basic::serv::iam_tenant_serv::IamTenantServ;
+use crate::iam_config::IamConfig;
+use crate::iam_constants::RBUM_CERT_SUPPLIER_SCIM;
+use crate::iam_enumeration::{IamCertKernelKind, IamCertTokenKind, Oauth2GrantType};
+
+const ERROR_INVALID_REQUEST: &str = "invalid_request";
+const ERROR_INVALID_CLIENT: &str = "invalid_client";
+const ERROR_INVALID_GRANT: &str = "invalid_grant";
+const ERROR_INVALID_SCOPE: &str = "invalid_scope";
+const ERROR_INVALID_TOKEN: &str = "invalid_token";
+const ERROR_INSUFFICIENT_SCOPE: &str = "insufficient_scope";
+const ERROR_ACCESS_DENIED: &str = "access_denied";
+const ERROR_UNAUTHORIZED_CLIENT: &str = "unauthorized_client";
+const ERROR_UNSUPPORTED_GRANT_TYPE: &str = "unsupported_grant_type";
+const ERROR_UNSUPPORTED_RESPONSE_TYPE: &str = "unsupported_response_type";
+
+const SCOPE_OPENID: &str = "openid";
+const SCOPE_PROFILE: &str = "profile";
+const SCOPE_EMAIL: &str = "email";
+const SCOPE_PHONE: &str = "phone";
+/// The access token can only call the apis (with the context of the account) when this scope is granted,
+/// see ``oauth2_api_scope`` of the auth config
+const SCOPE_API: &str = "api";
+
+const TOKEN_TYPE_HINT_REFRESH_TOKEN: &str = "refresh_token";
+const CODE_CHALLENGE_METHOD_S256: &str = "S256";
+const CODE_CHALLENGE_METHOD_PLAIN: &str = "plain";
+
+/// The aksk cert used as an OAuth2 client
+struct IamOAuth2Client {
+    client_id: String,
+    tenant_id: String,
+    rel_id: String,
+    conf: Option<IamOAuth2ClientConf>,
+}
+
+impl IamOAuth2Client {
+    fn allows_grant(&self, grant_type: &Oauth2GrantType) -> bool {
+        match &self.conf {
+            // the aksk without OAuth2 configuration keeps working with the client credentials grant only
+            None => grant_type == &Oauth2GrantType::ClientCredentials,
+            Some(conf) => {
+                conf.grant_types.contains(grant_type) && !(conf.public_client && (grant_type == &Oauth2GrantType::ClientCredentials || grant_type == &Oauth2GrantType::Password))
+            }
+        }
+    }
+
+    fn allowed_scopes(&self) -> Vec<String> {
+        self.conf.as_ref().map(|conf| conf.scopes.clone()).unwrap_or_default()
+    }
+
+    fn is_public(&self) -> bool {
+        self.conf.as_ref().map(|conf| conf.public_client).unwrap_or(false)
+    }
+}
+
+enum IamOAuth2TokenKind {
+    AccessToken,
+    RefreshToken,
+}
+
+/// OAuth2 authorization server and OpenID provider, so that the internal apps can use IAM as their IdP.
+///
+/// The aksk certs are the clients, the ak is the client id and the sk is the client secret.
+/// The access tokens are registered as ``TokenOauth2`` tokens, so the gateway recognizes them like the login tokens.
+pub struct IamOAuth2ProviderServ;
+
+impl IamOAuth2ProviderServ {
+    pub fn issuer(funs: &TardisFunsInst) -> String {
+        let conf = funs.conf::<IamConfig>();
+        if conf.oauth2_provider.issuer.is_empty() {
+            conf.iam_base_url.trim_end_matches('/').to_string()
+        } else {
+            conf.oauth2_provider.issuer.trim_end_matches('/').to_string()
+        }
+    }
+
+    pub fn discovery(funs: &TardisFunsInst) -> IamOidcDiscoveryResp {
+        let issuer = Self::issuer(funs);
+        IamOidcDiscoveryResp {
+            authorization_endpoint: format!("{issuer}/cp/oauth2/authorize"),
+            token_endpoint: format!("{issuer}/cp/oauth2/token"),
+            userinfo_endpoint: format!("{issuer}/cp/oauth2/userinfo"),
+            jwks_uri: format!("{issuer}/cp/oauth2/jwks"),
+            revocation_endpoint: format!("{issuer}/cp/oauth2/revoke"),
+            introspection_endpoint: format!("{issuer}/cp/oauth2/introspect"),
+            issuer,
+            response_types_supported: vec!["code".to_string()],
+            grant_types_supported: vec![
+                "authorization_code".to_string(),
+                "client_credentials".to_string(),
+                "refresh_token".to_string(),
+                "password".to_string(),
+            ],
+            subject_types_supported: vec!["public".to_string()],
+            id_token_signing_alg_values_supported: vec!["RS256".to_string()],
+            scopes_supported: vec![
+                SCOPE_OPENID.to_string(),
+                SCOPE_PROFILE.to_string(),
+                SCOPE_EMAIL.to_string(),
+                SCOPE_PHONE.to_string(),
+                SCOPE_API.to_string(),
+            ],
+            token_endpoint_auth_methods_supported: vec!["client_secret_basic".to_string(), "client_secret_post".to_string(), "none".to_string()],
+            code_challenge_methods_supported: vec![CODE_CHALLENGE_METHOD_S256.to_string(), CODE_CHALLENGE_METHOD_PLAIN.to_string()],
+            claims_supported: ["sub", "iss", "aud", "exp", "iat", "auth_time", "nonce", "name", "email", "phone_number", "tenant_id"]
+                .into_iter()
+                .map(|claim| claim.to_string())
+                .collect(),
+        }
+    }
+
+    pub async fn jwks(funs: &TardisFunsInst) -> TardisResult<IamOidcJwksResp> {
+        let mut keys = vec![Self::signing_key(funs)?.to_public_key()];
+        // the previous keys are published during the key rotation, so the id_tokens signed by them can still be verified
+        for pem in &funs.conf::<IamConfig>().oauth2_provider.previous_signing_keys {
+            let key = match RsaPublicKey::from_public_key_pem(pem).ok().or_else(|| RsaPublicKey::from_pkcs1_pem(pem).ok()) {
+                Some(key) => key,
+                None => Self::parse_private_key(pem, funs)?.to_public_key(),
+            };
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        Ok(IamOidcJwksResp {
+            keys: keys
+                .iter()
+                .map(|key| IamOidcJwkResp {
+                    kty: "RSA".to_string(),
+                    key_use: "sig".to_string(),
+                    alg: "RS256".to_string(),
+                    kid: Self::key_id(key),
+                    n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+                    e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+                })
+                .collect(),
+        })
+    }
+
+    /// Issue an authorization code to the logged-in account and return the uri to redirect to
+    pub async fn authorize(authorize_req: &IamOAuth2AuthorizeReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        if authorize_req.response_type != "code" {
+            return Err(Self::err(ERROR_UNSUPPORTED_RESPONSE_TYPE, "only the code response type is supported", funs));
+        }
+        if ctx.owner.is_empty() {
+            return Err(Self::err(ERROR_ACCESS_DENIED, "the account is not logged in", funs));
+        }
+        let client = Self::get_client(&authorize_req.client_id, funs).await?;
+        if !client.allows_grant(&Oauth2GrantType::AuthorizationCode) {
+            return Err(Self::err(ERROR_UNAUTHORIZED_CLIENT, "the client is not allowed to use the authorization code grant", funs));
+        }
+        let redirect_uris = client.conf.as_ref().map(|conf| conf.redirect_uris.clone()).unwrap_or_default();
+        // exact match, no prefix or pattern
+        let redirect_uri = match &authorize_req.redirect_uri {
+            Some(redirect_uri) if redirect_uris.contains(redirect_uri) => redirect_uri.clone(),
+            None if redirect_uris.len() == 1 => redirect_uris[0].clone(),
+            _ => return Err(Self::err(ERROR_INVALID_REQUEST, "redirect_uri is not registered by the client", funs)),
+        };
+        if client.is_public() && authorize_req.code_challenge.is_none() {
+            return Err(Self::err(ERROR_INVALID_REQUEST, "code_challenge is required for the public client", funs));
+        }
+        if let Some(code_challenge_method) = &authorize_req.code_challenge_method {
+            if code_challenge_method != CODE_CHALLENGE_METHOD_S256 && code_challenge_method != CODE_CHALLENGE_METHOD_PLAIN {
+                return Err(Self::err(
+                    ERROR_INVALID_REQUEST,
+                    &format!("code_challenge_method {code_challenge_method} is not supported"),
+                    funs,
+                ));
+            }
+        }
+        let tenant_id = ctx.own_paths.split('/').next().unwrap_or_default().to_string();
+        if !client.tenant_id.is_empty() && client.tenant_id != tenant_id {
+            return Err(Self::err(ERROR_ACCESS_DENIED, "the account does not belong to the tenant of the client", funs));
+        }
+        let scopes = Self::grant_scopes(authorize_req.scope.as_deref(), &client.allowed_scopes(), funs)?;
+        let conf = funs.conf::<IamConfig>();
+        let now = Utc::now().timestamp();
+        let grant = IamOAuth2Grant {
+            client_id: client.client_id,
+            tenant_id,
+            account_id: Some(ctx.owner.clone()),
+            client_rel_id: client.rel_id,
+            scopes,
+            // only the redirect_uri in the request has to be repeated in the token request
+            redirect_uri: authorize_req.redirect_uri.clone(),
+            code_challenge: authorize_req.code_challenge.clone(),
+            code_challenge_method: authorize_req
+                .code_challenge
+                .as_ref()
+                .map(|_| authorize_req.code_challenge_method.clone().unwrap_or_else(|| CODE_CHALLENGE_METHOD_PLAIN.to_string())),
+            nonce: authorize_req.nonce.clone(),
+            auth_time: now,
+            issued_at: now,
+            expires_at: now + conf.oauth2_provider.code_expire_sec as i64,
+            access_token: None,
+        };
+        let code = TardisFuns::crypto.key.generate_token()?;
+        funs.cache()
+            .set_ex(
+                &format!("{}{}", conf.oauth2_provider.cache_key_code_, code),
+                &TardisFuns::json.obj_to_string(&grant)?,
+                conf.oauth2_provider.code_expire_sec,
+            )
+            .await?;
+        let mut redirect_uri = format!(
+            "{redirect_uri}{}code={}",
+            if redirect_uri.contains('?') { "&" } else { "?" },
+            utf8_percent_encode(&code, NON_ALPHANUMERIC)
+        );
+        if let Some(state) = &authorize_req.state {
+            redirect_uri = format!("{redirect_uri}&state={}", utf8_percent_encode(state, NON_ALPHANUMERIC));
+        }
+        Ok(redirect_uri)
+    }
+
+    pub async fn token(token_req: &IamOAuth2TokenReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamOAuth2TokenResp> {
+        let grant_type = Oauth2GrantType::parse(&token_req.grant_type)
+            .map_err(|_| Self::err(ERROR_UNSUPPORTED_GRANT_TYPE, &format!("grant_type {} is not supported", token_req.grant_type), funs))?;
+        let client_id = token_req.client_id.as_deref().ok_or_else(|| Self::err(ERROR_INVALID_REQUEST, "client_id is required", funs))?;
+        let client = Self::authenticate_client(client_id, token_req.client_secret.as_deref(), funs).await?;
+        if !client.allows_grant(&grant_type) {
+            return Err(Self::err(
+                ERROR_UNAUTHORIZED_CLIENT,
+                &format!("the client is not allowed to use the {grant_type} grant"),
+                funs,
+            ));
+        }
+        let now = Utc::now().timestamp();
+        match grant_type {
+            Oauth2GrantType::AuthorizationCode => {
+                let code = token_req.code.as_deref().ok_or_else(|| Self::err(ERROR_INVALID_REQUEST, "code is required", funs))?;
+                let code_key = format!("{}{}", funs.conf::<IamConfig>().oauth2_provider.cache_key_code_, code);
+                // the code can only be used once, only the request that removes it can redeem it
+                let grant = Self::take_grant(&code_key, funs).await?.ok_or_else(|| Self::err(ERROR_INVALID_GRANT, "code is invalid or expired", funs))?;
+                if grant.client_id != client.client_id {
+                    return Err(Self::err(ERROR_INVALID_GRANT, "code was issued to another client", funs));
+                }
+                let redirect_uri_matched = match (&grant.redirect_uri, &token_req.redirect_uri) {
+                    (Some(_), _) => token_req.redirect_uri == grant.redirect_uri,
+                    (None, Some(redirect_uri)) => client.conf.as_ref().map(|conf| conf.redirect_uris.contains(redirect_uri)).unwrap_or(false),
+                    (None, None) => true,
+                };
+                if !redirect_uri_matched {
+                    return Err(Self::err(ERROR_INVALID_GRANT, "redirect_uri does not match the authorization request", funs));
+                }
+                if !Self::verify_code_verifier(&grant, token_req.code_verifier.as_deref()) {
+                    return Err(Self::err(ERROR_INVALID_GRANT, "code_verifier is invalid", funs));
+                }
+                Self::issue(&client, grant, funs).await
+            }
+            Oauth2GrantType::ClientCredentials => {
+                let grant = IamOAuth2Grant {
+                    client_id: client.client_id.clone(),
+                    tenant_id: client.tenant_id.clone(),
+                    account_id: None,
+                    client_rel_id: client.rel_id.clone(),
+                    scopes: Self::grant_scopes(token_req.scope.as_deref(), &client.allowed_scopes(), funs)?,
+                    auth_time: now,
+                    ..Default::default()
+                };
+                Self::issue(&client, grant, funs).await
+            }
+            Oauth2GrantType::RefreshToken => {
+                let refresh_token = token_req.refresh_token.as_deref().ok_or_else(|| Self::err(ERROR_INVALID_REQUEST, "refresh_token is required", funs))?;
+                // the request is checked before the refresh token is used up, the refresh token of another client is left untouched
+                let grant = Self::get_grant(refresh_token, &IamOAuth2TokenKind::RefreshToken, funs)
+                    .await?
+                    .filter(|grant| grant.client_id == client.client_id)
+                    .ok_or_else(|| Self::err(ERROR_INVALID_GRANT, "refresh_token is invalid or expired", funs))?;
+                // the refreshed scopes can only be narrowed
+                let scopes = match &token_req.scope {
+                    Some(scope) => Self::grant_scopes(Some(scope), &grant.scopes, funs)?,
+                    None => grant.scopes,
+                };
+                // the refresh token is rotated, only the request that removes the used one can refresh
+                let mut grant = Self::take_grant(&Self::grant_key(refresh_token, &IamOAuth2TokenKind::RefreshToken, funs), funs)
+                    .await?
+                    .filter(|grant| grant.client_id == client.client_id)
+                    .ok_or_else(|| Self::err(ERROR_INVALID_GRANT, "refresh_token is invalid or expired", funs))?;
+                grant.scopes = scopes;
+                grant.nonce = None;
+                Self::issue(&client, grant, funs).await
+            }
+            Oauth2GrantType::Password => {
+                let username = token_req.username.as_deref().ok_or_else(|| Self::err(ERROR_INVALID_REQUEST, "username is required", funs))?;
+                let password = token_req.password.as_deref().ok_or_else(|| Self::err(ERROR_INVALID_REQUEST, "password is required", funs))?;
+                let tenant_id = if client.tenant_id.is_empty() {
+                    token_req.tenant_id.clone().unwrap_or_default()
+                } else {
+                    client.tenant_id.clone()
+                };
+                if !tenant_id.is_empty() && IamTenantServ::is_disabled(&tenant_id, funs).await? {
+                    return Err(Self::err(ERROR_INVALID_GRANT, &format!("tenant {tenant_id} is disabled"), funs));
+                }
+                let (_, _, account_id) = IamCertServ::validate_by_ak_and_sk(
+                    username,
+                    password,
+                    None,
+                    Some(&RbumCertRelKind::Item),
+                    false,
+                    Some(tenant_id.clone()),
+                    Some(vec![&IamCertKernelKind::UserPwd.to_string()]),
+                    ip,
+                    funs,
+                )
+                .await
+                .map_err(|e| Self::err(ERROR_INVALID_GRANT, &e.message, funs))?;
+                // the password grant has no second step, so it is rejected when a second factor is required by the tenant
+                if !IamCertMfaServ::get_required_kinds(&tenant_id, &account_id, funs).await?.is_empty() {
+                    return Err(Self::err(ERROR_INVALID_GRANT, "mfa required, use the authorization code grant instead", funs));
+                }
+                let grant = IamOAuth2Grant {
+                    client_id: client.client_id.clone(),
+                    tenant_id,
+                    account_id: Some(account_id),
+                    client_rel_id: client.rel_id.clone(),
+                    scopes: Self::grant_scopes(token_req.scope.as_deref(), &client.allowed_scopes(), funs)?,
+                    auth_time: now,
+                    ..Default::default()
+                };
+                Self::issue(&client, grant, funs).await
+            }
+        }
+    }
+
+    pub async fn userinfo(access_token: &str, funs: &TardisFunsInst) -> TardisResult<IamOidcUserInfoResp> {
+        let grant = Self::get_grant(access_token, &IamOAuth2TokenKind::AccessToken, funs)
+            .await?
+            .ok_or_else(|| Self::err(ERROR_INVALID_TOKEN, "access token is invalid or expired", funs))?;
+        let account_id = match &grant.account_id {
+            Some(account_id) if grant.scopes.iter().any(|scope| scope == SCOPE_OPENID) => account_id.clone(),
+            _ => return Err(Self::err(ERROR_INSUFFICIENT_SCOPE, "the openid scope of an account is required", funs)),
+        };
+        let ctx = Self::grant_ctx(&grant);
+        let name = if grant.scopes.iter().any(|scope| scope == SCOPE_PROFILE) {
+            Some(Self::get_account_name(&account_id, funs, &ctx).await?)
+        } else {
+            None
+        };
+        let email = if grant.scopes.iter().any(|scope| scope == SCOPE_EMAIL) {
+            IamCertServ::get_kernel_cert(&account_id, &IamCertKernelKind::MailVCode, funs, &ctx).await.ok().map(|cert| cert.ak)
+        } else {
+            None
+        };
+        let phone_number = if grant.scopes.iter().any(|scope| scope == SCOPE_PHONE) {
+            IamCertServ::get_kernel_cert(&account_id, &IamCertKernelKind::PhoneVCode, funs, &ctx).await.ok().map(|cert| cert.ak)
+        } else {
+            None
+        };
+        Ok(IamOidcUserInfoResp {
+            sub: account_id,
+            name,
+            email,
+            phone_number,
+            tenant_id: grant.tenant_id,
+        })
+    }
+
+    /// Introspect the token (RFC 7662), only the confidential clients (e.g. a resource server) can introspect the tokens
+    pub async fn introspect(op_req: &IamOAuth2TokenOpReq, funs: &TardisFunsInst) -> TardisResult<IamOAuth2IntrospectResp> {
+        let client_id = op_req.client_id.as_deref().ok_or_else(|| Self::err(ERROR_INVALID_CLIENT, "client_id is required", funs))?;
+        // the public client has no secret, anyone could use its client_id to probe the tokens
+        if op_req.client_secret.is_none() {
+            return Err(Self::err(ERROR_INVALID_CLIENT, "client authentication is required", funs));
+        }
+        Self::authenticate_client(client_id, op_req.client_secret.as_deref(), funs).await?;
+        for kind in Self::token_kinds(op_req.token_type_hint.as_deref()) {
+            if let Some(grant) = Self::get_grant(&op_req.token, &kind, funs).await? {
+                return Ok(IamOAuth2IntrospectResp {
+                    active: true,
+                    scope: Some(grant.scopes.join(" ")),
+                    client_id: Some(grant.client_id),
+                    sub: Some(grant.account_id.unwrap_or(grant.client_rel_id)),
+                    token_type: match kind {
+                        IamOAuth2TokenKind::AccessToken => Some("Bearer".to_string()),
+                        IamOAuth2TokenKind::RefreshToken => None,
+                    },
+                    exp: Some(grant.expires_at),
+                    iat: Some(grant.issued_at),
+                });
+            }
+        }
+        Ok(IamOAuth2IntrospectResp {
+            active: false,
+            ..Default::default()
+        })
+    }
+
+    /// Revoke the token (RFC 7009), revoking a refresh token also revokes the access token issued with it
+    pub async fn revoke(op_req: &IamOAuth2TokenOpReq, funs: &TardisFunsInst) -> TardisResult<()> {
+        let client_id = op_req.client_id.as_deref().ok_or_else(|| Self::err(ERROR_INVALID_CLIENT, "client_id is required", funs))?;
+        let client = Self::authenticate_client(client_id, op_req.client_secret.as_deref(), funs).await?;
+        for kind in Self::token_kinds(op_req.token_type_hint.as_deref()) {
+            if let Some(grant) = Self::get_grant(&op_req.token, &kind, funs).await? {
+                if grant.client_id != client.client_id {
+                    return Err(Self::err(ERROR_UNAUTHORIZED_CLIENT, "the token was issued to another client", funs));
+                }
+                match kind {
+                    IamOAuth2TokenKind::AccessToken => Self::revoke_access_token(&op_req.token, funs).await?,
+                    IamOAuth2TokenKind::RefreshToken => {
+                        funs.cache().del(&Self::grant_key(&op_req.token, &IamOAuth2TokenKind::RefreshToken, funs)).await?;
+                        if let Some(access_token) = &grant.access_token {
+                            Self::revoke_access_token(access_token, funs).await?;
+                        }
+                    }
+                }
+                return Ok(());
+            }
+        }
+        // invalid tokens are ignored
+        Ok(())
+    }
+
+    /// Convert the error to the error response defined by RFC 6749
+    pub fn error_resp(error: &TardisError) -> IamOAuth2ErrorResp {
+        let kind = if let Some((_, kind)) = error.code.split_once("-oauth2-") {
+            kind.to_string()
+        } else if error.code.starts_with('5') {
+            "server_error".to_string()
+        } else {
+            ERROR_INVALID_REQUEST.to_string()
+        };
+        IamOAuth2ErrorResp {
+            error: kind,
+            error_description: error.message.clone(),
+        }
+    }
+
+    async fn issue(client: &IamOAuth2Client, mut grant: IamOAuth2Grant, funs: &TardisFunsInst) -> TardisResult<IamOAuth2TokenResp> {
+        let conf = funs.conf::<IamConfig>();
+        let now = Utc::now().timestamp();
+        let access_token = TardisFuns::crypto.key.generate_token()?;
+        let expire_sec = conf.oauth2_provider.access_token_expire_sec;
+        if let Some(account_id) = &grant.account_id {
+            // cache the contexts of the account so that the gateway can resolve the token, the disabled or locked accounts are rejected here,
+            // the gateway only accepts the token with the api scope, the identity scopes don't grant the context of the account
+            IamCertServ::package_tardis_account_context_and_resp(account_id, &grant.tenant_id, access_token.clone(), None, funs, &Self::grant_ctx(&grant))
+                .await
+                .map_err(|e| Self::err(ERROR_INVALID_GRANT, &e.message, funs))?;
+        }
+        IamIdentCacheServ::add_token(
+            &access_token,
+            &IamCertTokenKind::TokenOauth2,
+            grant.account_id.as_deref().unwrap_or(&grant.client_rel_id),
+            None,
+            expire_sec,
+            conf.oauth2_provider.token_coexist_num,
+            funs,
+        )
+        .await?;
+        grant.issued_at = now;
+        grant.expires_at = now + expire_sec;
+        grant.redirect_uri = None;
+        grant.code_challenge = None;
+        grant.code_challenge_method = None;
+        grant.access_token = None;
+        funs.cache()
+            .set_ex(
+                &Self::grant_key(&access_token, &IamOAuth2TokenKind::AccessToken, funs),
+                &TardisFuns::json.obj_to_string(&grant)?,
+                expire_sec as u64,
+            )
+            .await?;
+        // no refresh token for the client credentials grant
+        let refresh_token = if grant.account_id.is_some() && client.allows_grant(&Oauth2GrantType::RefreshToken) {
+            let refresh_token = TardisFuns::crypto.key.generate_token()?;
+            let refresh_grant = IamOAuth2Grant {
+                expires_at: now + conf.oauth2_provider.refresh_token_expire_sec as i64,
+                access_token: Some(access_token.clone()),
+                ..grant.clone()
+            };
+            funs.cache()
+                .set_ex(
+                    &Self::grant_key(&refresh_token, &IamOAuth2TokenKind::RefreshToken, funs),
+                    &TardisFuns::json.obj_to_string(&refresh_grant)?,
+                    conf.oauth2_provider.refresh_token_expire_sec,
+                )
+                .await?;
+            Some(refresh_token)
+        } else {
+            None
+        };
+        let id_token = if grant.account_id.is_some() && grant.scopes.iter().any(|scope| scope == SCOPE_OPENID) {
+            Some(Self::sign_id_token(&grant, funs).await?)
+        } else {
+            None
+        };
+        Ok(IamOAuth2TokenResp {
+            access_token,
+            token_type: "Bearer".to_string(),
+            expires_in: expire_sec,
+            refresh_token,
+            scope: grant.scopes.join(" "),
+            id_token,
+        })
+    }
+
+    async fn sign_id_token(grant: &IamOAuth2Grant, funs: &TardisFunsInst) -> TardisResult<String> {
+        let account_id = grant.account_id.clone().unwrap_or_default();
+        let name = if grant.scopes.iter().any(|scope| scope == SCOPE_PROFILE) {
+            Some(Self::get_account_name(&account_id, funs, &Self::grant_ctx(grant)).await?)
+        } else {
+            None
+        };
+        let now = Utc::now().timestamp();
+        let claims = IamOidcIdTokenClaims {
+            iss: Self::issuer(funs),
+            sub: account_id,
+            aud: grant.client_id.clone(),
+            exp: now + funs.conf::<IamConfig>().oauth2_provider.id_token_expire_sec,
+            iat: now,
+            auth_time: grant.auth_time,
+            nonce: grant.nonce.clone(),
+            name,
+            tenant_id: grant.tenant_id.clone(),
+        };
+        let key = Self::signing_key(funs)?;
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(Self::key_id(&key.to_public_key()));
+        let pem = key.to_pkcs8_pem(LineEnding::LF).map_err(|e| Self::signing_err(&e.to_string(), funs))?;
+        let encoding_key = EncodingKey::from_rsa_pem(pem.as_bytes()).map_err(|e| Self::signing_err(&e.to_string(), funs))?;
+        jsonwebtoken::encode(&header, &claims, &encoding_key).map_err(|e| Self::signing_err(&e.to_string(), funs))
+    }
+
+    /// The configured signing key, it must be shared by all the nodes and survive the restarts, so it is never generated
+    fn signing_key(funs: &TardisFunsInst) -> TardisResult<RsaPrivateKey> {
+        let pem = &funs.conf::<IamConfig>().oauth2_provider.signing_key;
+        if pem.is_empty() {
+            return Err(funs.err().internal_error(
+                "oauth2",
+                "sign",
+                "the signing key of the OAuth2 provider is not configured",
+                "500-iam-oauth2-signing-key-not-configured",
+            ));
+        }
+        Self::parse_private_key(pem, funs)
+    }
+
+    fn parse_private_key(pem: &str, funs: &TardisFunsInst) -> TardisResult<RsaPrivateKey> {
+        RsaPrivateKey::from_pkcs8_pem(pem).or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem)).map_err(|e| Self::signing_err(&e.to_string(), funs))
+    }
+
+    fn key_id(key: &RsaPublicKey) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(key.n().to_bytes_be()))[..16].to_string()
+    }
+
+    async fn get_client(client_id: &str, funs: &TardisFunsInst) -> TardisResult<IamOAuth2Client> {
+        let basic = RbumBasicFilterReq {
+            own_paths: Some("".to_string()),
+            with_sub_own_paths: true,
+            ignore_scope: true,
+            ..Default::default()
+        };
+        let ctx = TardisContext::default();
+        let certs = RbumCertServ::find_detail_rbums(
+            &RbumCertFilterReq {
+                basic: basic.clone(),
+                ak: Some(client_id.to_string()),
+                status: Some(RbumCertStatusKind::Enabled),
+                rel_rbum_kind: Some(RbumCertRelKind::Item),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            &ctx,
+        )
+        .await?;
+        for cert in certs {
+            // the SCIM credential can't be an OAuth2 client
+            if cert.supplier == RBUM_CERT_SUPPLIER_SCIM {
+                continue;
+            }
+            let Some(rel_rbum_cert_conf_id) = &cert.rel_rbum_cert_conf_id else {
+                continue;
+            };
+            let is_aksk = RbumCertConfServ::count_rbums(
+                &RbumCertConfFilterReq {
+                    basic: RbumBasicFilterReq {
+                        ids: Some(vec![rel_rbum_cert_conf_id.clone()]),
+                        ..basic.clone()
+                    },
+                    kind: Some(TrimString(IamCertKernelKind::AkSk.to_string())),
+                    ..Default::default()
+                },
+                funs,
+                &ctx,
+            )
+            .await?
+                > 0;
+            if is_aksk {
+                return Ok(IamOAuth2Client {
+                    client_id: client_id.to_string(),
+                    tenant_id: cert.own_paths.split('/').next().unwrap_or_default().to_string(),
+                    rel_id: cert.rel_rbum_id,
+                    conf: if cert.ext.is_empty() { None } else { Some(TardisFuns::json.str_to_obj(&cert.ext)?) },
+                });
+            }
+        }
+        Err(Self::err(ERROR_INVALID_CLIENT, &format!("client {client_id} is not found"), funs))
+    }
+
+    /// Authenticate the confidential client by the secret, the public client has no secret
+    async fn authenticate_client(client_id: &str, client_secret: Option<&str>, funs: &TardisFunsInst) -> TardisResult<IamOAuth2Client> {
+        if let Some(client_secret) = client_secret {
+            IamCertServ::validate_by_ak_and_sk(
+                client_id,
+                client_secret,
+                None,
+                Some(&RbumCertRelKind::Item),
+                false,
+                None,
+                Some(vec![&IamCertKernelKind::AkSk.to_string()]),
+                None,
+                funs,
+            )
+            .await
+            .map_err(|_| Self::err(ERROR_INVALID_CLIENT, "client authentication failed", funs))?;
+            Self::get_client(client_id, funs).await
+        } else {
+            let client = Self::get_client(client_id, funs).await?;
+            if !client.is_public() {
+                return Err(Self::err(ERROR_INVALID_CLIENT, "client authentication failed", funs));
+            }
+            Ok(client)
+        }
+    }
+
+    /// The requested scopes must be allowed, all the allowed scopes are granted when the request has no scope
+    fn grant_scopes(requested: Option<&str>, allowed: &[String], funs: &TardisFunsInst) -> TardisResult<Vec<String>> {
+        let mut requested = requested.unwrap_or_default().split_whitespace().map(|scope| scope.to_string()).collect::<Vec<_>>();
+        if requested.is_empty() {
+            return Ok(allowed.to_vec());
+        }
+        if let Some(scope) = requested.iter().find(|scope| !allowed.contains(scope)) {
+            return Err(Self::err(ERROR_INVALID_SCOPE, &format!("scope {scope} is not allowed"), funs));
+        }
+        requested.dedup();
+        Ok(requested)
+    }
+
+    fn verify_code_verifier(grant: &IamOAuth2Grant, code_verifier: Option<&str>) -> bool {
+        match (&grant.code_challenge, code_verifier) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(code_challenge), Some(code_verifier)) => {
+                if grant.code_challenge_method.as_deref() == Some(CODE_CHALLENGE_METHOD_S256) {
+                    &URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes())) == code_challenge
+                } else {
+                    code_verifier == code_challenge
+                }
+            }
+        }
+    }
+
+    async fn get_grant(token: &str, kind: &IamOAuth2TokenKind, funs: &TardisFunsInst) -> TardisResult<Option<IamOAuth2Grant>> {
+        if let Some(grant) = funs.cache().get(&Self::grant_key(token, kind, funs)).await? {
+            Ok(Some(TardisFuns::json.str_to_obj(&grant)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get and delete the grant atomically, so that concurrent requests can't redeem the same one twice
+    async fn take_grant(key: &str, funs: &TardisFunsInst) -> TardisResult<Option<IamOAuth2Grant>> {
+        let grant: Option<String> = funs
+            .cache()
+            .script(
+                r#"
+                   local grant = redis.call('GET', KEYS[1])
+                   if grant then
+                       redis.call('DEL', KEYS[1])
+                   end
+                   return grant
+               "#,
+            )
+            .key(key)
+            .invoke()
+            .await?;
+        match grant {
+            Some(grant) => Ok(Some(TardisFuns::json.str_to_obj(&grant)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn grant_key(token: &str, kind: &IamOAuth2TokenKind, funs: &TardisFunsInst) -> String {
+        let conf = funs.conf::<IamConfig>();
+        match kind {
+            IamOAuth2TokenKind::AccessToken => format!("{}{}", conf.oauth2_provider.cache_key_access_token_, token),
+            IamOAuth2TokenKind::RefreshToken => format!("{}{}", conf.oauth2_provider.cache_key_refresh_token_, token),
+        }
+    }
+
+    fn token_kinds(token_type_hint: Option<&str>) -> [IamOAuth2TokenKind; 2] {
+        if token_type_hint == Some(TOKEN_TYPE_HINT_REFRESH_TOKEN) {
+            [IamOAuth2TokenKind::RefreshToken, IamOAuth2TokenKind::AccessToken]
+        } else {
+            [IamOAuth2TokenKind::AccessToken, IamOAuth2TokenKind::RefreshToken]
+        }
+    }
+
+    async fn revoke_access_token(access_token: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.cache().del(&Self::grant_key(access_token, &IamOAuth2TokenKind::AccessToken, funs)).await?;
+        IamIdentCacheServ::delete_token_by_token(access_token, None, funs).await
+    }
+
+    async fn get_account_name(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        Ok(IamAccountServ::get_item(
+            account_id,
+            &IamAccountFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some("".to_string()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?
+        .name)
+    }
+
+    fn grant_ctx(grant: &IamOAuth2Grant) -> TardisContext {
+        TardisContext {
+            own_paths: grant.tenant_id.clone(),
+            owner: grant.account_id.clone().unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    fn err(kind: &str, msg: &str, funs: &TardisFunsInst) -> TardisError {
+        let locale_code = format!("iam-oauth2-{}", kind.replace('_', "-"));
+        match kind {
+            ERROR_INVALID_CLIENT | ERROR_INVALID_TOKEN | ERROR_INSUFFICIENT_SCOPE | ERROR_ACCESS_DENIED => {
+                funs.err().unauthorized("oauth2", kind, msg, &format!("401-{locale_code}"))
+            }
+            _ => funs.err().bad_request("oauth2", kind, msg, &format!("400-{locale_code}")),
+        }
+    }
+
+    fn signing_err(msg: &str, funs: &TardisFunsInst) -> TardisError {
+        funs.err().internal_error("oauth2", "sign", &format!("the signing key is invalid: {msg}"), "500-iam-oauth2-signing-key-invalid")
+    }
+}
@@ -3,7 +3,7 @@ use bios_basic::rbum::{
         rbum_cert_dto::RbumCertModifyReq,
         rbum_filer_dto::{RbumBasicFilterReq, RbumCertFilterReq, RbumRelExtFilterReq, RbumRelFilterReq},
         rbum_rel_agg_dto::{RbumRelAggAddReq, RbumRelEnvAggAddReq},
-        rbum_rel_dto::RbumRelAddReq,
+        rbum_rel_dto::{RbumRelAddReq, RbumRelQuotaResp},
     },
     rbum_enumeration::{RbumRelEnvKind, RbumRelFromKind},
     serv::{
@@ -15,7 +15,7 @@ use bios_basic::rbum::{
 };
 use itertools::Itertools;
 use tardis::{
-    basic::{dto::TardisContext, field::TrimString, result::TardisResult},
+    basic::{dto::TardisContext, error::TardisError, field::TrimString, result::TardisResult},
     chrono::DateTime,
     TardisFuns, TardisFunsInst,
 };
@@ -29,7 +29,7 @@ use crate::{
         iam_cert_conf_dto::IamCertConfAkSkAddOrModifyReq,
         iam_cert_dto::IamCertAkSkAddReq,
         iam_filer_dto::IamResFilterReq,
-        iam_open_dto::{IamOpenAddOrModifyProductReq, IamOpenAkSkAddReq, IamOpenAkSkResp, IamOpenBindAkProductReq, IamOpenRuleResp},
+        iam_open_dto::{IamOpenAddOrModifyProductReq, IamOpenAkSkAddReq, IamOpenAkSkResp, IamOpenBindAkProductReq, IamOpenQuotaResp, IamOpenRuleResp},
         iam_res_dto::{IamResAddReq, IamResDetailResp, IamResModifyReq},
     },
     iam_config::IamConfig,
@@ -518,6 +518,99 @@ impl IamOpenServ {
         })
     }
 
+    /// Consume the api call quota of the cert
+    ///
+    /// 消耗凭证的API调用配额
+    ///
+    /// Returns a ``429`` error when the call frequency or call count limit is exceeded.
+    ///
+    /// 超出调用频率或调用次数限制时返回 ``429`` 错误。
+    pub async fn consume_api_call_quota(cert_id_req: Option<String>, ak_req: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamOpenQuotaResp> {
+        let (cert_id, rel_id) = Self::find_cert_spec_rel_id(cert_id_req, ak_req, funs, ctx).await?;
+        let quota = RbumRelServ::consume_rel_quota(&rel_id, 1, funs).await?;
+        if !quota.passed {
+            return Err(TardisError::custom(
+                "429",
+                &format!(
+                    "api call quota of cert {} exceeded, call frequency resets after {} secs",
+                    cert_id,
+                    quota.call_frequency_reset_sec.unwrap_or_default()
+                ),
+                "429-iam-open-quota-exceeded",
+            ));
+        }
+        Ok(Self::package_quota_resp(cert_id, quota))
+    }
+
+    /// Get the api call quota of the cert
+    ///
+    /// 获取凭证的API调用配额
+    pub async fn get_api_call_quota(cert_id_req: Option<String>, ak_req: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamOpenQuotaResp> {
+        let (cert_id, rel_id) = Self::find_cert_spec_rel_id(cert_id_req, ak_req, funs, ctx).await?;
+        let quota = RbumRelServ::get_rel_quota(&rel_id, funs).await?;
+        Ok(Self::package_quota_resp(cert_id, quota))
+    }
+
+    fn package_quota_resp(cert_id: String, quota: RbumRelQuotaResp) -> IamOpenQuotaResp {
+        IamOpenQuotaResp {
+            cert_id,
+            passed: quota.passed,
+            api_call_frequency: quota.call_frequency_limit,
+            api_call_frequency_remaining: quota.call_frequency_remaining,
+            api_call_frequency_reset_sec: quota.call_frequency_reset_sec,
+            api_call_count: quota.call_count_limit,
+            api_call_count_remaining: quota.call_count_remaining,
+        }
+    }
+
+    async fn find_cert_spec_rel_id(cert_id_req: Option<String>, ak_req: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<(String, String)> {
+        if cert_id_req.is_none() && ak_req.is_none() {
+            return Err(funs.err().bad_request(
+                "iam_open",
+                "find_cert_spec_rel_id",
+                "cert_id and ak cannot be empty at the same time",
+                "400-iam-open-cert-require",
+            ));
+        }
+        let cert_id = RbumCertServ::find_one_detail_rbum(
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                id: cert_id_req,
+                ak: ak_req,
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await?
+        .ok_or_else(|| funs.err().not_found("iam_open", "find_cert_spec_rel_id", "cert not found", "404-iam-res-not-exist"))?
+        .id;
+        let rel_id = RbumRelServ::find_detail_rbums(
+            &RbumRelFilterReq {
+                basic: RbumBasicFilterReq {
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                from_rbum_id: Some(cert_id.clone()),
+                from_rbum_kind: Some(RbumRelFromKind::Cert),
+                tag: Some(IamRelKind::IamCertSpec.to_string()),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await?
+        .pop()
+        .ok_or_else(|| funs.err().not_found("iam_open", "find_cert_spec_rel_id", "cert is not bound to any spec", "404-iam-open-spec-not-bind"))?
+        .id;
+        Ok((cert_id, rel_id))
+    }
+
     pub async fn general_cert(add_req: IamOpenAkSkAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamOpenAkSkResp> {
         let rel_iam_item_id = IamTenantServ::get_id_by_ctx(ctx, funs)?;
         let cert_conf = IamCertServ::get_cert_conf_id_by_kind(IamCertKernelKind::AkSk.to_string().as_str(), Some(rel_iam_item_id.clone()), funs).await;
@@ -542,6 +635,8 @@ impl IamOpenServ {
             &IamCertAkSkAddReq {
                 tenant_id: add_req.tenant_id,
                 app_id: add_req.app_id,
+                oauth2_client: None,
+                scim: None,
             },
             &ak,
             &sk,
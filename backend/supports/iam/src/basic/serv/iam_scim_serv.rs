@@ -0,0 +1,1709 @@
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::rbum::dto::rbum_cert_dto::RbumCertSummaryResp;
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertFilterReq, RbumSetCateFilterReq, RbumSetItemFilterReq};
+use bios_basic::rbum::dto::rbum_set_item_dto::RbumSetItemDetailResp;
+use bios_basic::rbum::rbum_enumeration::RbumCertRelKind;
+use bios_basic::rbum::serv::rbum_cert_serv::RbumCertServ;
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use bios_basic::rbum::serv::rbum_item_serv::{RbumItemCrudOperation, RbumItemServ};
+use bios_basic::rbum::serv::rbum_set_serv::{RbumSetCateServ, RbumSetItemServ};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::{DateTime, SecondsFormat, Utc};
+use tardis::db::reldb_client::IdResp;
+use tardis::db::sea_orm::sea_query::{Alias, Expr, Query};
+use tardis::serde_json::{json, Value};
+use tardis::web::poem_openapi::types::{ParseFromJSON, ToJSON, Type};
+use tardis::{TardisFuns, TardisFunsInst};
+
+use crate::basic::dto::iam_account_dto::{IamAccountAggAddReq, IamAccountAggModifyReq};
+use crate::basic::dto::iam_cert_dto::{IamCertUserNameNewReq, IamCertUserPwdRestReq};
+use crate::basic::dto::iam_filer_dto::IamAccountFilterReq;
+use crate::basic::dto::iam_scim_dto::{
+    IamScimAuthenticationSchemeResp, IamScimBulkOp, IamScimBulkOpResp, IamScimBulkReq, IamScimBulkResp, IamScimBulkSupportedResp, IamScimErrorResp, IamScimFilterSupportedResp,
+    IamScimGroup, IamScimListResp, IamScimMember, IamScimMeta, IamScimMultiValued, IamScimName, IamScimPatchOp, IamScimPatchReq, IamScimResourceTypeResp, IamScimSchemaAttrResp,
+    IamScimSchemaExtensionResp, IamScimSchemaResp, IamScimServiceProviderConfigResp, IamScimSupportedResp, IamScimUser,
+};
+use crate::basic::dto::iam_set_dto::{IamSetCateAddReq, IamSetCateModifyReq, IamSetItemAddReq};
+use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_attr_serv::IamAttrServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use crate::basic::serv::iam_set_serv::IamSetServ;
+use crate::basic::serv::iam_tenant_serv::IamTenantServ;
+use crate::iam_config::{IamBasicConfigApi, IamConfig};
+use crate::iam_constants;
+use crate::iam_constants::RBUM_CERT_SUPPLIER_SCIM;
+use crate::iam_enumeration::{IamCertKernelKind, IamSetKind};
+
+pub const SCHEMA_USER: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const SCHEMA_GROUP: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+/// The account attrs of the tenant, see [IamAttrServ]
+pub const SCHEMA_USER_EXT: &str = "urn:ietf:params:scim:schemas:extension:bios:2.0:User";
+pub const SCHEMA_LIST_RESP: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+pub const SCHEMA_PATCH_OP: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+pub const SCHEMA_BULK_REQ: &str = "urn:ietf:params:scim:api:messages:2.0:BulkRequest";
+pub const SCHEMA_BULK_RESP: &str = "urn:ietf:params:scim:api:messages:2.0:BulkResponse";
+pub const SCHEMA_ERROR: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+const SCHEMA_SERVICE_PROVIDER_CONFIG: &str = "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+const SCHEMA_RESOURCE_TYPE: &str = "urn:ietf:params:scim:schemas:core:2.0:ResourceType";
+const SCHEMA_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:Schema";
+
+const RESOURCE_TYPE_USER: &str = "User";
+const RESOURCE_TYPE_GROUP: &str = "Group";
+const ENDPOINT_USERS: &str = "Users";
+const ENDPOINT_GROUPS: &str = "Groups";
+
+const ERROR_INVALID_FILTER: &str = "invalidFilter";
+const ERROR_INVALID_PATH: &str = "invalidPath";
+const ERROR_NO_TARGET: &str = "noTarget";
+const ERROR_INVALID_VALUE: &str = "invalidValue";
+const ERROR_INVALID_SYNTAX: &str = "invalidSyntax";
+const ERROR_MUTABILITY: &str = "mutability";
+const ERROR_TOO_MANY: &str = "tooMany";
+const ERROR_UNIQUENESS: &str = "uniqueness";
+
+const BULK_ID_PREFIX: &str = "bulkId:";
+
+/// Attribute names are case insensitive in SCIM, the names of the request are normalized to these ones
+const CANONICAL_ATTRS: [&str; 30] = [
+    "schemas",
+    "id",
+    "externalId",
+    "userName",
+    "name",
+    "formatted",
+    "familyName",
+    "givenName",
+    "displayName",
+    "active",
+    "password",
+    "emails",
+    "phoneNumbers",
+    "groups",
+    "members",
+    "value",
+    "display",
+    "type",
+    "primary",
+    "$ref",
+    "meta",
+    "Operations",
+    "op",
+    "path",
+    "method",
+    "bulkId",
+    "version",
+    "data",
+    "failOnErrors",
+    SCHEMA_USER_EXT,
+];
+
+/// SCIM filter (RFC 7644 section 3.4.2.2), the value path (e.g. ``emails[type eq "work"]``) is only supported by the patch path
+#[derive(Debug, Clone)]
+enum ScimFilter {
+    Compare { attr: String, op: String, value: Value },
+    Present(String),
+    And(Box<ScimFilter>, Box<ScimFilter>),
+    Or(Box<ScimFilter>, Box<ScimFilter>),
+    Not(Box<ScimFilter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ScimFilterToken {
+    Word(String),
+    Str(String),
+    LeftParen,
+    RightParen,
+}
+
+/// Patch path, e.g. ``name.givenName``, ``members[value eq "<id>"]``, ``urn:ietf:params:scim:schemas:extension:bios:2.0:User:<attr>``
+#[derive(Debug)]
+struct ScimPatchPath {
+    attr: String,
+    filter: Option<ScimFilter>,
+    sub_attr: Option<String>,
+}
+
+/// SCIM 2.0 provisioning, the users are the accounts of the tenant and the groups are the nodes of the default org set of the tenant.
+///
+/// SCIM 2.0供应，用户即租户的账号，组即租户默认组织集合的节点。
+pub struct IamScimServ;
+
+impl IamScimServ {
+    /// Authenticate by the SCIM credential of the tenant, the credential is passed by ``Authorization: Bearer <ak>:<sk>`` or ``Authorization: Basic base64(<ak>:<sk>)``.
+    ///
+    /// Only the aksk created with ``scim`` is accepted (see [crate::basic::dto::iam_cert_dto::IamCertAkSkAddReq]),
+    /// so the general aksk and the OAuth2 clients can't provision the accounts.
+    /// The aksk of the app is rejected, because the accounts and orgs are managed by the tenant.
+    pub async fn auth(authorization: Option<&str>, funs: &TardisFunsInst) -> TardisResult<TardisContext> {
+        let Some((ak, sk)) = authorization.and_then(Self::parse_credentials) else {
+            return Err(funs.err().unauthorized("iam_scim", "auth", "missing or invalid authorization", "401-iam-scim-auth-invalid"));
+        };
+        let (cert_id, _, _) = IamCertServ::validate_by_ak_and_sk(
+            &ak,
+            &sk,
+            None,
+            Some(&RbumCertRelKind::Item),
+            false,
+            None,
+            Some(vec![&IamCertKernelKind::AkSk.to_string()]),
+            None,
+            funs,
+        )
+        .await
+        .map_err(|_| funs.err().unauthorized("iam_scim", "auth", "aksk is invalid", "401-iam-scim-auth-invalid"))?;
+        let cert = RbumCertServ::peek_rbum(
+            &cert_id,
+            &RbumCertFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some("".to_string()),
+                    with_sub_own_paths: true,
+                    ignore_scope: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            funs,
+            &TardisContext::default(),
+        )
+        .await?;
+        if cert.supplier != RBUM_CERT_SUPPLIER_SCIM || !cert.ext.is_empty() {
+            return Err(funs.err().unauthorized("iam_scim", "auth", "only the SCIM credential is allowed", "401-iam-scim-auth-invalid"));
+        }
+        if cert.own_paths.is_empty() || cert.own_paths.contains('/') {
+            return Err(funs.err().unauthorized("iam_scim", "auth", "only the aksk of the tenant is allowed", "401-iam-scim-auth-invalid"));
+        }
+        if IamTenantServ::is_disabled(&cert.own_paths, funs).await? {
+            return Err(funs.err().unauthorized("iam_scim", "auth", &format!("tenant {} is disabled", cert.own_paths), "401-iam-scim-auth-invalid"));
+        }
+        Ok(TardisContext {
+            own_paths: cert.own_paths,
+            ak,
+            ..Default::default()
+        })
+    }
+
+    // ------------------------------------ Users ------------------------------------
+
+    pub async fn get_user(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        let account = IamAccountServ::get_item(id, &Self::account_filter(Some(vec![id.to_string()]), None, ctx), funs, ctx).await?;
+        let user_name = Self::find_cert(id, &IamCertKernelKind::UserPwd, funs, ctx).await?.map(|cert| cert.ak).unwrap_or_default();
+        let emails = Self::find_cert(id, &IamCertKernelKind::MailVCode, funs, ctx)
+            .await?
+            .map(|cert| {
+                vec![IamScimMultiValued {
+                    value: cert.ak,
+                    r#type: Some("work".to_string()),
+                    primary: Some(true),
+                }]
+            })
+            .unwrap_or_default();
+        let phone_numbers = Self::find_cert(id, &IamCertKernelKind::PhoneVCode, funs, ctx)
+            .await?
+            .map(|cert| {
+                vec![IamScimMultiValued {
+                    value: cert.ak,
+                    r#type: Some("mobile".to_string()),
+                    primary: Some(true),
+                }]
+            })
+            .unwrap_or_default();
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let groups = Self::find_org_items(&set_id, None, Some(id.to_string()), funs, ctx)
+            .await?
+            .into_iter()
+            .filter_map(|item| {
+                item.rel_rbum_set_cate_id.map(|cate_id| IamScimMember {
+                    r#ref: Some(Self::location(ENDPOINT_GROUPS, &cate_id, funs)),
+                    value: cate_id,
+                    display: item.rel_rbum_set_cate_name,
+                })
+            })
+            .collect();
+        let ext = IamAttrServ::find_account_attr_values(id, funs, ctx).await?;
+        let ext = if ext.is_empty() { None } else { Some(ext) };
+        let mut user = IamScimUser {
+            schemas: if ext.is_some() {
+                vec![SCHEMA_USER.to_string(), SCHEMA_USER_EXT.to_string()]
+            } else {
+                vec![SCHEMA_USER.to_string()]
+            },
+            id: Some(account.id.clone()),
+            user_name,
+            name: Some(IamScimName {
+                formatted: Some(account.name.clone()),
+                ..Default::default()
+            }),
+            display_name: Some(account.name),
+            active: Some(!account.disabled),
+            password: None,
+            emails,
+            phone_numbers,
+            groups,
+            ext,
+            meta: Some(Self::meta(
+                RESOURCE_TYPE_USER,
+                ENDPOINT_USERS,
+                &account.id,
+                &account.create_time,
+                &account.update_time,
+                funs,
+            )),
+        };
+        Self::set_version(&mut user, |user| &mut user.meta)?;
+        Ok(user)
+    }
+
+    pub async fn find_users(
+        filter: Option<&str>,
+        start_index: Option<usize>,
+        count: Option<usize>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<IamScimListResp<IamScimUser>> {
+        let max_results = funs.conf::<IamConfig>().scim.filter_max_results;
+        let start_index = start_index.unwrap_or(1).max(1);
+        let count = count.unwrap_or(max_results).min(max_results);
+        let filter = Self::parse_filter_opt(filter, funs)?;
+        let ids = Self::find_user_candidate_ids(filter.as_ref(), funs, ctx).await?;
+        let Some(filter) = filter else {
+            let mut users = vec![];
+            for id in ids.iter().skip(start_index - 1).take(count) {
+                users.push(Self::get_user(id, funs, ctx).await?);
+            }
+            return Ok(Self::list_resp(ids.len(), start_index, users));
+        };
+        if ids.len() > max_results {
+            return Err(Self::err(ERROR_TOO_MANY, &format!("the filter matches more than {max_results} users"), funs));
+        }
+        let mut users = vec![];
+        for id in ids {
+            let user = Self::get_user(&id, funs, ctx).await?;
+            if Self::filter_matches(&filter, &TardisFuns::json.obj_to_json(&user)?) {
+                users.push(user);
+            }
+        }
+        let total = users.len();
+        Ok(Self::list_resp(total, start_index, users.into_iter().skip(start_index - 1).take(count).collect()))
+    }
+
+    pub async fn add_user(add_req: &IamScimUser, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        if add_req.user_name.trim().is_empty() {
+            return Err(Self::err(ERROR_INVALID_VALUE, "userName is required", funs));
+        }
+        let exts = Self::check_exts(add_req.ext.clone().unwrap_or_default(), funs, ctx).await?;
+        let account_id = IamAccountServ::add_account_agg(
+            &IamAccountAggAddReq {
+                id: None,
+                name: TrimString(Self::account_name(add_req)),
+                cert_user_name: TrimString(add_req.user_name.trim().to_string()),
+                cert_password: add_req.password.clone().map(TrimString),
+                cert_phone: Self::primary_value(&add_req.phone_numbers).map(TrimString),
+                cert_mail: Self::primary_value(&add_req.emails).map(TrimString),
+                role_ids: None,
+                org_node_ids: None,
+                lock_status: None,
+                scope_level: None,
+                disabled: add_req.active.map(|active| !active),
+                logout_type: None,
+                labor_type: None,
+                temporary: None,
+                icon: None,
+                exts,
+                status: None,
+            },
+            false,
+            funs,
+            ctx,
+        )
+        .await?;
+        Self::get_user(&account_id, funs, ctx).await
+    }
+
+    /// Replace the user, the attributes not present are removed except ``active`` and ``password``
+    pub async fn replace_user(id: &str, modify_req: &IamScimUser, if_match: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        Self::lock_for_update(RbumItemServ::get_table_name(), id, funs).await?;
+        let current = Self::get_user(id, funs, ctx).await?;
+        Self::check_version(if_match, &current.meta)?;
+        Self::do_replace_user(&current, modify_req, funs, ctx).await?;
+        Self::get_user(id, funs, ctx).await
+    }
+
+    pub async fn patch_user(id: &str, patch_req: &IamScimPatchReq, if_match: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimUser> {
+        Self::lock_for_update(RbumItemServ::get_table_name(), id, funs).await?;
+        let current = Self::get_user(id, funs, ctx).await?;
+        Self::check_version(if_match, &current.meta)?;
+        let mut resource = TardisFuns::json.obj_to_json(&current)?;
+        Self::apply_patch_ops(&mut resource, &patch_req.operations, funs)?;
+        let modify_req = Self::parse_resource::<IamScimUser>(resource, funs)?;
+        Self::do_replace_user(&current, &modify_req, funs, ctx).await?;
+        Self::get_user(id, funs, ctx).await
+    }
+
+    pub async fn delete_user(id: &str, if_match: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        Self::lock_for_update(RbumItemServ::get_table_name(), id, funs).await?;
+        let current = Self::get_user(id, funs, ctx).await?;
+        Self::check_version(if_match, &current.meta)?;
+        IamAccountServ::delete_item_with_all_rels(id, funs, ctx).await?;
+        Ok(())
+    }
+
+    async fn do_replace_user(current: &IamScimUser, modify_req: &IamScimUser, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let id = current.id.clone().unwrap_or_default();
+        let user_name = modify_req.user_name.trim();
+        if user_name.is_empty() {
+            return Err(Self::err(ERROR_INVALID_VALUE, "userName is required", funs));
+        }
+        if user_name != current.user_name || modify_req.password.is_some() {
+            let cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::UserPwd.to_string(), Some(ctx.own_paths.clone()), funs).await?;
+            if user_name != current.user_name {
+                // the sk is encrypted with the ak, so the login name can't be changed without the password
+                let Some(password) = &modify_req.password else {
+                    return Err(Self::err(ERROR_MUTABILITY, "userName can only be changed together with the password", funs));
+                };
+                IamCertUserPwdServ::modify_ak_cert(
+                    &id,
+                    &IamCertUserNameNewReq {
+                        original_ak: TrimString(current.user_name.clone()),
+                        new_ak: TrimString(user_name.to_string()),
+                        sk: TrimString(password.clone()),
+                    },
+                    &cert_conf_id,
+                    funs,
+                    ctx,
+                )
+                .await?;
+            } else if let Some(password) = &modify_req.password {
+                IamCertUserPwdServ::reset_sk_to_enable_status(
+                    &IamCertUserPwdRestReq {
+                        new_sk: Some(TrimString(password.clone())),
+                    },
+                    &id,
+                    &cert_conf_id,
+                    funs,
+                    ctx,
+                )
+                .await?;
+            }
+        }
+        let mail = Self::primary_value(&modify_req.emails);
+        if mail.is_none() && !current.emails.is_empty() {
+            Self::delete_cert(&id, &IamCertKernelKind::MailVCode, funs, ctx).await?;
+        }
+        let phone = Self::primary_value(&modify_req.phone_numbers);
+        if phone.is_none() && !current.phone_numbers.is_empty() {
+            Self::delete_cert(&id, &IamCertKernelKind::PhoneVCode, funs, ctx).await?;
+        }
+        let mut exts = Self::check_exts(modify_req.ext.clone().unwrap_or_default(), funs, ctx).await?;
+        for attr_name in current.ext.iter().flat_map(|ext| ext.keys()) {
+            exts.entry(attr_name.clone()).or_default();
+        }
+        let name = Self::account_name(modify_req);
+        IamAccountServ::modify_account_agg(
+            &id,
+            &IamAccountAggModifyReq {
+                name: if Some(&name) != current.display_name.as_ref() { Some(TrimString(name)) } else { None },
+                disabled: modify_req.active.map(|active| !active),
+                cert_phone: phone.filter(|phone| Some(phone) != Self::primary_value(&current.phone_numbers).as_ref()).map(TrimString),
+                cert_mail: mail.filter(|mail| Some(mail) != Self::primary_value(&current.emails).as_ref()).map(TrimString),
+                exts: if exts.is_empty() { None } else { Some(exts) },
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    async fn find_user_candidate_ids(filter: Option<&ScimFilter>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<String>> {
+        let mut ids = None;
+        let mut name = None;
+        if let Some(filter) = filter {
+            let cert_kind = if let Some(id) = Self::find_clause(filter, &["id"], &["eq"]) {
+                ids = Some(vec![id]);
+                None
+            } else if let Some(user_name) = Self::find_clause(filter, &["userName"], &["eq"]) {
+                Some((IamCertKernelKind::UserPwd, user_name))
+            } else if let Some(mail) = Self::find_clause(filter, &["emails", "emails.value"], &["eq"]) {
+                Some((IamCertKernelKind::MailVCode, mail))
+            } else {
+                Self::find_clause(filter, &["phoneNumbers", "phoneNumbers.value"], &["eq"]).map(|phone| (IamCertKernelKind::PhoneVCode, phone))
+            };
+            if let Some((kind, ak)) = cert_kind {
+                ids = Some(Self::find_account_ids_by_cert(&kind, &ak, funs, ctx).await?);
+            }
+            name = Self::find_clause(filter, &["displayName", "name.formatted"], &["eq", "co", "sw"]);
+        }
+        if ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+            return Ok(vec![]);
+        }
+        IamAccountServ::find_id_items(&Self::account_filter(ids, name, ctx), Some(false), None, funs, ctx).await
+    }
+
+    async fn find_account_ids_by_cert(kind: &IamCertKernelKind, ak: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<String>> {
+        let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&kind.to_string(), Some(ctx.own_paths.clone()), funs).await? else {
+            return Ok(vec![]);
+        };
+        let certs = RbumCertServ::find_rbums(
+            &RbumCertFilterReq {
+                ak: Some(ak.to_string()),
+                rel_rbum_kind: Some(RbumCertRelKind::Item),
+                rel_rbum_cert_conf_ids: Some(vec![cert_conf.id]),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(certs.into_iter().map(|cert| cert.rel_rbum_id).collect())
+    }
+
+    async fn find_cert(account_id: &str, kind: &IamCertKernelKind, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<RbumCertSummaryResp>> {
+        let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&kind.to_string(), Some(ctx.own_paths.clone()), funs).await? else {
+            return Ok(None);
+        };
+        RbumCertServ::find_one_rbum(
+            &RbumCertFilterReq {
+                rel_rbum_kind: Some(RbumCertRelKind::Item),
+                rel_rbum_id: Some(account_id.to_string()),
+                rel_rbum_cert_conf_ids: Some(vec![cert_conf.id]),
+                ..Default::default()
+            },
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    async fn delete_cert(account_id: &str, kind: &IamCertKernelKind, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        if let Some(cert) = Self::find_cert(account_id, kind, funs, ctx).await? {
+            IamCertServ::delete_cert(&cert.id, funs, ctx).await?;
+        }
+        Ok(())
+    }
+
+    /// Only the account attrs of the tenant are allowed
+    async fn check_exts(exts: HashMap<String, String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<HashMap<String, String>> {
+        if exts.is_empty() {
+            return Ok(exts);
+        }
+        let attr_names = IamAttrServ::find_account_attrs(funs, ctx).await?.into_iter().map(|attr| attr.name).collect::<HashSet<_>>();
+        if let Some(unknown) = exts.keys().find(|name| !attr_names.contains(*name)) {
+            return Err(Self::err(ERROR_INVALID_VALUE, &format!("account attr {unknown} is not defined"), funs));
+        }
+        Ok(exts)
+    }
+
+    fn account_filter(ids: Option<Vec<String>>, name: Option<String>, ctx: &TardisContext) -> IamAccountFilterReq {
+        IamAccountFilterReq {
+            basic: RbumBasicFilterReq {
+                own_paths: Some(ctx.own_paths.clone()),
+                ignore_scope: true,
+                ids,
+                name,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn account_name(user: &IamScimUser) -> String {
+        let name = user.name.clone().unwrap_or_default();
+        [
+            user.display_name.clone(),
+            name.formatted,
+            Some(format!("{} {}", name.given_name.unwrap_or_default(), name.family_name.unwrap_or_default())),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| user.user_name.trim().to_string())
+    }
+
+    fn primary_value(values: &[IamScimMultiValued]) -> Option<String> {
+        values.iter().find(|value| value.primary == Some(true)).or_else(|| values.first()).map(|value| value.value.trim().to_string()).filter(|value| !value.is_empty())
+    }
+
+    // ------------------------------------ Groups ------------------------------------
+
+    pub async fn get_group(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let Some(cate) = IamSetServ::find_set_cate(
+            &RbumSetCateFilterReq {
+                basic: RbumBasicFilterReq {
+                    ids: Some(vec![id.to_string()]),
+                    ..Default::default()
+                },
+                rel_rbum_set_id: Some(set_id.clone()),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await?
+        .pop() else {
+            return Err(funs.err().not_found("iam_scim", "get_group", &format!("group {id} is not found"), "404-iam-scim-group-not-exist"));
+        };
+        let members = Self::find_org_items(&set_id, Some(id.to_string()), None, funs, ctx)
+            .await?
+            .into_iter()
+            .map(|item| IamScimMember {
+                r#ref: Some(Self::location(ENDPOINT_USERS, &item.rel_rbum_item_id, funs)),
+                value: item.rel_rbum_item_id,
+                display: Some(item.rel_rbum_item_name),
+            })
+            .collect();
+        let mut group = IamScimGroup {
+            schemas: vec![SCHEMA_GROUP.to_string()],
+            id: Some(cate.id.clone()),
+            display_name: cate.name,
+            members,
+            meta: Some(Self::meta(RESOURCE_TYPE_GROUP, ENDPOINT_GROUPS, &cate.id, &cate.create_time, &cate.update_time, funs)),
+        };
+        Self::set_version(&mut group, |group| &mut group.meta)?;
+        Ok(group)
+    }
+
+    pub async fn find_groups(
+        filter: Option<&str>,
+        start_index: Option<usize>,
+        count: Option<usize>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<IamScimListResp<IamScimGroup>> {
+        let max_results = funs.conf::<IamConfig>().scim.filter_max_results;
+        let start_index = start_index.unwrap_or(1).max(1);
+        let count = count.unwrap_or(max_results).min(max_results);
+        let filter = Self::parse_filter_opt(filter, funs)?;
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let ids = IamSetServ::find_set_cate(
+            &RbumSetCateFilterReq {
+                basic: RbumBasicFilterReq {
+                    ids: filter.as_ref().and_then(|filter| Self::find_clause(filter, &["id"], &["eq"])).map(|id| vec![id]),
+                    name: filter.as_ref().and_then(|filter| Self::find_clause(filter, &["displayName"], &["eq", "co", "sw"])),
+                    ..Default::default()
+                },
+                rel_rbum_set_id: Some(set_id),
+                ..Default::default()
+            },
+            Some(false),
+            None,
+            funs,
+            ctx,
+        )
+        .await?
+        .into_iter()
+        .map(|cate| cate.id)
+        .collect::<Vec<_>>();
+        let Some(filter) = filter else {
+            let mut groups = vec![];
+            for id in ids.iter().skip(start_index - 1).take(count) {
+                groups.push(Self::get_group(id, funs, ctx).await?);
+            }
+            return Ok(Self::list_resp(ids.len(), start_index, groups));
+        };
+        if ids.len() > max_results {
+            return Err(Self::err(ERROR_TOO_MANY, &format!("the filter matches more than {max_results} groups"), funs));
+        }
+        let mut groups = vec![];
+        for id in ids {
+            let group = Self::get_group(&id, funs, ctx).await?;
+            if Self::filter_matches(&filter, &TardisFuns::json.obj_to_json(&group)?) {
+                groups.push(group);
+            }
+        }
+        let total = groups.len();
+        Ok(Self::list_resp(total, start_index, groups.into_iter().skip(start_index - 1).take(count).collect()))
+    }
+
+    /// Add the group under the root of the org tree
+    pub async fn add_group(add_req: &IamScimGroup, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        if add_req.display_name.trim().is_empty() {
+            return Err(Self::err(ERROR_INVALID_VALUE, "displayName is required", funs));
+        }
+        let member_ids = add_req.members.iter().map(|member| member.value.clone()).collect::<HashSet<_>>();
+        Self::check_members(&member_ids, funs, ctx).await?;
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let cate_id = IamSetServ::add_set_cate(
+            &set_id,
+            &IamSetCateAddReq {
+                name: TrimString(add_req.display_name.trim().to_string()),
+                scope_level: None,
+                bus_code: None,
+                icon: None,
+                sort: None,
+                ext: None,
+                rbum_parent_cate_id: None,
+            },
+            funs,
+            ctx,
+        )
+        .await?;
+        Self::add_members(&set_id, &cate_id, member_ids, funs, ctx).await?;
+        Self::get_group(&cate_id, funs, ctx).await
+    }
+
+    pub async fn replace_group(id: &str, modify_req: &IamScimGroup, if_match: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        Self::lock_for_update(RbumSetCateServ::get_table_name(), id, funs).await?;
+        let current = Self::get_group(id, funs, ctx).await?;
+        Self::check_version(if_match, &current.meta)?;
+        Self::do_replace_group(&current, modify_req, funs, ctx).await?;
+        Self::get_group(id, funs, ctx).await
+    }
+
+    pub async fn patch_group(id: &str, patch_req: &IamScimPatchReq, if_match: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimGroup> {
+        Self::lock_for_update(RbumSetCateServ::get_table_name(), id, funs).await?;
+        let current = Self::get_group(id, funs, ctx).await?;
+        Self::check_version(if_match, &current.meta)?;
+        let mut resource = TardisFuns::json.obj_to_json(&current)?;
+        Self::apply_patch_ops(&mut resource, &patch_req.operations, funs)?;
+        let modify_req = Self::parse_resource::<IamScimGroup>(resource, funs)?;
+        Self::do_replace_group(&current, &modify_req, funs, ctx).await?;
+        Self::get_group(id, funs, ctx).await
+    }
+
+    /// Delete the group and its memberships, the group with sub nodes can't be deleted
+    pub async fn delete_group(id: &str, if_match: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        Self::lock_for_update(RbumSetCateServ::get_table_name(), id, funs).await?;
+        let current = Self::get_group(id, funs, ctx).await?;
+        Self::check_version(if_match, &current.meta)?;
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        for item in Self::find_org_items(&set_id, Some(id.to_string()), None, funs, ctx).await? {
+            Self::delete_org_item(item, funs, ctx).await?;
+        }
+        IamSetServ::delete_set_cate(id, funs, ctx).await?;
+        Ok(())
+    }
+
+    async fn do_replace_group(current: &IamScimGroup, modify_req: &IamScimGroup, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let id = current.id.clone().unwrap_or_default();
+        let display_name = modify_req.display_name.trim();
+        if display_name.is_empty() {
+            return Err(Self::err(ERROR_INVALID_VALUE, "displayName is required", funs));
+        }
+        if display_name != current.display_name {
+            IamSetServ::modify_set_cate(
+                &id,
+                &IamSetCateModifyReq {
+                    name: Some(TrimString(display_name.to_string())),
+                    scope_level: None,
+                    bus_code: None,
+                    icon: None,
+                    sort: None,
+                    ext: None,
+                },
+                funs,
+                ctx,
+            )
+            .await?;
+        }
+        let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+        let member_ids = modify_req.members.iter().map(|member| member.value.clone()).collect::<HashSet<_>>();
+        let stored_items = Self::find_org_items(&set_id, Some(id.clone()), None, funs, ctx).await?;
+        let stored_member_ids = stored_items.iter().map(|item| item.rel_rbum_item_id.clone()).collect::<HashSet<_>>();
+        let added_member_ids = member_ids.difference(&stored_member_ids).cloned().collect();
+        Self::check_members(&added_member_ids, funs, ctx).await?;
+        Self::add_members(&set_id, &id, added_member_ids, funs, ctx).await?;
+        for item in stored_items.into_iter().filter(|item| !member_ids.contains(&item.rel_rbum_item_id)) {
+            Self::delete_org_item(item, funs, ctx).await?;
+        }
+        Ok(())
+    }
+
+    /// The members must be the accounts of the tenant
+    async fn check_members(account_ids: &HashSet<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        if account_ids.is_empty() {
+            return Ok(());
+        }
+        let exist_account_ids = IamAccountServ::find_id_items(&Self::account_filter(Some(account_ids.iter().cloned().collect()), None, ctx), None, None, funs, ctx).await?;
+        if let Some(account_id) = account_ids.iter().find(|account_id| !exist_account_ids.contains(account_id)) {
+            return Err(Self::err(ERROR_INVALID_VALUE, &format!("member {account_id} is not found"), funs));
+        }
+        Ok(())
+    }
+
+    async fn add_members(set_id: &str, cate_id: &str, account_ids: HashSet<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        for account_id in account_ids {
+            IamSetServ::add_set_item(
+                &IamSetItemAddReq {
+                    set_id: set_id.to_string(),
+                    set_cate_id: cate_id.to_string(),
+                    sort: 0,
+                    rel_rbum_item_id: account_id,
+                },
+                funs,
+                ctx,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// The accounts of the org node, including the disabled ones
+    async fn find_org_items(
+        set_id: &str,
+        cate_id: Option<String>,
+        account_id: Option<String>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<Vec<RbumSetItemDetailResp>> {
+        RbumSetItemServ::find_detail_rbums(
+            &RbumSetItemFilterReq {
+                rel_rbum_set_id: Some(set_id.to_string()),
+                rel_rbum_set_cate_ids: cate_id.map(|cate_id| vec![cate_id]),
+                rel_rbum_item_ids: account_id.map(|account_id| vec![account_id]),
+                rel_rbum_item_kind_ids: Some(vec![funs.iam_basic_kind_account_id()]),
+                rel_rbum_item_can_not_exist: Some(false),
+                ..Default::default()
+            },
+            None,
+            None,
+            funs,
+            ctx,
+        )
+        .await
+    }
+
+    async fn delete_org_item(item: RbumSetItemDetailResp, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        // IamSetServ::delete_set_item only finds the enabled accounts
+        if item.rel_rbum_item_disabled {
+            RbumSetItemServ::delete_rbum(&item.id, funs, ctx).await?;
+        } else {
+            IamSetServ::delete_set_item(&item.id, funs, ctx).await?;
+        }
+        Ok(())
+    }
+
+    // ------------------------------------ Bulk ------------------------------------
+
+    /// Process the operations in order, each operation is committed independently.
+    pub async fn bulk(bulk_req: &IamScimBulkReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimBulkResp> {
+        let max_operations = funs.conf::<IamConfig>().scim.bulk_max_operations;
+        if bulk_req.operations.len() > max_operations {
+            return Err(TardisError::custom(
+                "413",
+                &format!("the number of operations exceeds the max operations {max_operations}"),
+                "413-iam-scim-bulk-too-large",
+            ));
+        }
+        let mut bulk_ids = HashMap::new();
+        let mut errors = 0;
+        let mut operations = vec![];
+        for operation in &bulk_req.operations {
+            if bulk_req.fail_on_errors.is_some_and(|fail_on_errors| fail_on_errors > 0 && errors >= fail_on_errors) {
+                break;
+            }
+            let method = operation.method.to_uppercase();
+            match Self::bulk_operation(&method, operation, &bulk_ids, ctx).await {
+                Ok((id, meta, status)) => {
+                    if let (Some(bulk_id), Some(id)) = (&operation.bulk_id, id) {
+                        bulk_ids.insert(bulk_id.clone(), id);
+                    }
+                    operations.push(IamScimBulkOpResp {
+                        method,
+                        bulk_id: operation.bulk_id.clone(),
+                        version: meta.as_ref().and_then(|meta| meta.version.clone()),
+                        location: meta.map(|meta| meta.location),
+                        status: status.to_string(),
+                        response: None,
+                    });
+                }
+                Err(error) => {
+                    errors += 1;
+                    let error_resp = Self::error_resp(&error);
+                    operations.push(IamScimBulkOpResp {
+                        method,
+                        bulk_id: operation.bulk_id.clone(),
+                        version: None,
+                        location: None,
+                        status: error_resp.status.clone(),
+                        response: Some(error_resp),
+                    });
+                }
+            }
+        }
+        Ok(IamScimBulkResp {
+            schemas: vec![SCHEMA_BULK_RESP.to_string()],
+            operations,
+        })
+    }
+
+    async fn bulk_operation(
+        method: &str,
+        operation: &IamScimBulkOp,
+        bulk_ids: &HashMap<String, String>,
+        ctx: &TardisContext,
+    ) -> TardisResult<(Option<String>, Option<IamScimMeta>, u16)> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let path = Self::resolve_bulk_id(&operation.path, bulk_ids, &funs)?;
+        let data = match operation.data.clone() {
+            Some(data) => Some(Self::resolve_bulk_ids(data, bulk_ids, &funs)?),
+            None => None,
+        };
+        let (endpoint, id) = match path.trim_matches('/').split('/').collect::<Vec<_>>().as_slice() {
+            [endpoint] => (endpoint.to_string(), None),
+            [endpoint, id] => (endpoint.to_string(), Some(id.to_string())),
+            _ => return Err(Self::err(ERROR_INVALID_PATH, &format!("path {path} is invalid"), &funs)),
+        };
+        if endpoint != ENDPOINT_USERS && endpoint != ENDPOINT_GROUPS {
+            return Err(Self::err(ERROR_INVALID_PATH, &format!("path {path} is invalid"), &funs));
+        }
+        let if_match = operation.version.as_deref();
+        funs.begin().await?;
+        let (meta, status) = match (method, id) {
+            ("POST", None) => {
+                let data = data.ok_or_else(|| Self::err(ERROR_INVALID_SYNTAX, "data is required", &funs))?;
+                if endpoint == ENDPOINT_USERS {
+                    (Self::add_user(&Self::parse_resource(data, &funs)?, &funs, ctx).await?.meta, 201)
+                } else {
+                    (Self::add_group(&Self::parse_resource(data, &funs)?, &funs, ctx).await?.meta, 201)
+                }
+            }
+            ("PUT", Some(id)) => {
+                let data = data.ok_or_else(|| Self::err(ERROR_INVALID_SYNTAX, "data is required", &funs))?;
+                if endpoint == ENDPOINT_USERS {
+                    (Self::replace_user(&id, &Self::parse_resource(data, &funs)?, if_match, &funs, ctx).await?.meta, 200)
+                } else {
+                    (Self::replace_group(&id, &Self::parse_resource(data, &funs)?, if_match, &funs, ctx).await?.meta, 200)
+                }
+            }
+            ("PATCH", Some(id)) => {
+                let data = data.ok_or_else(|| Self::err(ERROR_INVALID_SYNTAX, "data is required", &funs))?;
+                if endpoint == ENDPOINT_USERS {
+                    (Self::patch_user(&id, &Self::parse_resource(data, &funs)?, if_match, &funs, ctx).await?.meta, 200)
+                } else {
+                    (Self::patch_group(&id, &Self::parse_resource(data, &funs)?, if_match, &funs, ctx).await?.meta, 200)
+                }
+            }
+            ("DELETE", Some(id)) => {
+                if endpoint == ENDPOINT_USERS {
+                    Self::delete_user(&id, if_match, &funs, ctx).await?;
+                } else {
+                    Self::delete_group(&id, if_match, &funs, ctx).await?;
+                }
+                (None, 204)
+            }
+            _ => return Err(Self::err(ERROR_INVALID_SYNTAX, &format!("method {method} is not supported by path {path}"), &funs)),
+        };
+        funs.commit().await?;
+        // the created id is the last segment of the location
+        let id = meta.as_ref().and_then(|meta| meta.location.rsplit('/').next().map(|id| id.to_string()));
+        Ok((id, meta, status))
+    }
+
+    fn resolve_bulk_ids(value: Value, bulk_ids: &HashMap<String, String>, funs: &TardisFunsInst) -> TardisResult<Value> {
+        Ok(match value {
+            Value::String(value) => Value::String(Self::resolve_bulk_id(&value, bulk_ids, funs)?),
+            Value::Array(items) => Value::Array(items.into_iter().map(|item| Self::resolve_bulk_ids(item, bulk_ids, funs)).collect::<TardisResult<Vec<_>>>()?),
+            Value::Object(attrs) => Value::Object(attrs.into_iter().map(|(key, value)| Ok((key, Self::resolve_bulk_ids(value, bulk_ids, funs)?))).collect::<TardisResult<_>>()?),
+            value => value,
+        })
+    }
+
+    fn resolve_bulk_id(value: &str, bulk_ids: &HashMap<String, String>, funs: &TardisFunsInst) -> TardisResult<String> {
+        let Some(pos) = value.find(BULK_ID_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let bulk_id = value[pos + BULK_ID_PREFIX.len()..].split('/').next().unwrap_or_default();
+        let Some(id) = bulk_ids.get(bulk_id) else {
+            return Err(Self::err(ERROR_INVALID_VALUE, &format!("bulkId {bulk_id} is not resolved"), funs));
+        };
+        Ok(value.replacen(&format!("{BULK_ID_PREFIX}{bulk_id}"), id, 1))
+    }
+
+    // ------------------------------------ Discovery ------------------------------------
+
+    pub fn get_service_provider_config(funs: &TardisFunsInst) -> IamScimServiceProviderConfigResp {
+        let iam_conf = funs.conf::<IamConfig>();
+        let scim_conf = &iam_conf.scim;
+        IamScimServiceProviderConfigResp {
+            schemas: vec![SCHEMA_SERVICE_PROVIDER_CONFIG.to_string()],
+            documentation_uri: None,
+            patch: IamScimSupportedResp { supported: true },
+            bulk: IamScimBulkSupportedResp {
+                supported: true,
+                max_operations: scim_conf.bulk_max_operations,
+                max_payload_size: scim_conf.bulk_max_payload_size,
+            },
+            filter: IamScimFilterSupportedResp {
+                supported: true,
+                max_results: scim_conf.filter_max_results,
+            },
+            change_password: IamScimSupportedResp { supported: false },
+            sort: IamScimSupportedResp { supported: false },
+            etag: IamScimSupportedResp { supported: true },
+            authentication_schemes: vec![
+                IamScimAuthenticationSchemeResp {
+                    r#type: "oauthbearertoken".to_string(),
+                    name: "AK/SK Bearer Token".to_string(),
+                    description: "Authentication by the bearer token <ak>:<sk> of the aksk cert of the tenant".to_string(),
+                    primary: true,
+                },
+                IamScimAuthenticationSchemeResp {
+                    r#type: "httpbasic".to_string(),
+                    name: "HTTP Basic".to_string(),
+                    description: "Authentication by the ak and sk of the aksk cert of the tenant".to_string(),
+                    primary: false,
+                },
+            ],
+            meta: Self::discovery_meta("ServiceProviderConfig", "ServiceProviderConfig", funs),
+        }
+    }
+
+    pub fn find_resource_types(funs: &TardisFunsInst) -> IamScimListResp<IamScimResourceTypeResp> {
+        let resource_types = vec![
+            IamScimResourceTypeResp {
+                schemas: vec![SCHEMA_RESOURCE_TYPE.to_string()],
+                id: RESOURCE_TYPE_USER.to_string(),
+                name: RESOURCE_TYPE_USER.to_string(),
+                endpoint: format!("/{ENDPOINT_USERS}"),
+                description: "Account of the tenant".to_string(),
+                schema: SCHEMA_USER.to_string(),
+                schema_extensions: vec![IamScimSchemaExtensionResp {
+                    schema: SCHEMA_USER_EXT.to_string(),
+                    required: false,
+                }],
+                meta: Self::discovery_meta("ResourceType", &format!("ResourceTypes/{RESOURCE_TYPE_USER}"), funs),
+            },
+            IamScimResourceTypeResp {
+                schemas: vec![SCHEMA_RESOURCE_TYPE.to_string()],
+                id: RESOURCE_TYPE_GROUP.to_string(),
+                name: RESOURCE_TYPE_GROUP.to_string(),
+                endpoint: format!("/{ENDPOINT_GROUPS}"),
+                description: "Node of the org tree of the tenant".to_string(),
+                schema: SCHEMA_GROUP.to_string(),
+                schema_extensions: vec![],
+                meta: Self::discovery_meta("ResourceType", &format!("ResourceTypes/{RESOURCE_TYPE_GROUP}"), funs),
+            },
+        ];
+        Self::list_resp(resource_types.len(), 1, resource_types)
+    }
+
+    /// The user extension schema is generated from the account attrs of the tenant
+    pub async fn find_schemas(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimListResp<IamScimSchemaResp>> {
+        let multi_valued_attrs = |description: &str| {
+            vec![
+                IamScimSchemaAttrResp {
+                    required: true,
+                    ..Self::schema_attr("value", "string", description)
+                },
+                Self::schema_attr("type", "string", "A label indicating the attribute's function"),
+                Self::schema_attr("primary", "boolean", "Indicate the primary or preferred attribute value"),
+            ]
+        };
+        let member_attrs = |description: &str, mutability: &str| {
+            vec![
+                IamScimSchemaAttrResp {
+                    mutability: mutability.to_string(),
+                    ..Self::schema_attr("value", "string", description)
+                },
+                IamScimSchemaAttrResp {
+                    mutability: "readOnly".to_string(),
+                    ..Self::schema_attr("display", "string", "A human-readable name")
+                },
+                IamScimSchemaAttrResp {
+                    mutability: "readOnly".to_string(),
+                    ..Self::schema_attr("$ref", "reference", "The URI of the resource")
+                },
+            ]
+        };
+        let user_schema = IamScimSchemaResp {
+            schemas: vec![SCHEMA_SCHEMA.to_string()],
+            id: SCHEMA_USER.to_string(),
+            name: RESOURCE_TYPE_USER.to_string(),
+            description: "Account of the tenant".to_string(),
+            attributes: vec![
+                IamScimSchemaAttrResp {
+                    required: true,
+                    uniqueness: "server".to_string(),
+                    ..Self::schema_attr("userName", "string", "Login name, changing it requires the password")
+                },
+                IamScimSchemaAttrResp {
+                    sub_attributes: vec![
+                        Self::schema_attr("formatted", "string", "Account name"),
+                        Self::schema_attr("familyName", "string", "Family name, only used to compose the account name"),
+                        Self::schema_attr("givenName", "string", "Given name, only used to compose the account name"),
+                    ],
+                    ..Self::schema_attr("name", "complex", "Name of the account")
+                },
+                Self::schema_attr("displayName", "string", "Account name"),
+                Self::schema_attr("active", "boolean", "Whether the account is enabled"),
+                IamScimSchemaAttrResp {
+                    mutability: "writeOnly".to_string(),
+                    returned: "never".to_string(),
+                    ..Self::schema_attr("password", "string", "Password of the account")
+                },
+                IamScimSchemaAttrResp {
+                    multi_valued: true,
+                    sub_attributes: multi_valued_attrs("Mail address"),
+                    ..Self::schema_attr("emails", "complex", "Mail credential, only the primary one is kept")
+                },
+                IamScimSchemaAttrResp {
+                    multi_valued: true,
+                    sub_attributes: multi_valued_attrs("Phone number"),
+                    ..Self::schema_attr("phoneNumbers", "complex", "Phone credential, only the primary one is kept")
+                },
+                IamScimSchemaAttrResp {
+                    multi_valued: true,
+                    mutability: "readOnly".to_string(),
+                    sub_attributes: member_attrs("Org node id", "readOnly"),
+                    ..Self::schema_attr("groups", "complex", "Org nodes of the account")
+                },
+            ],
+            meta: Self::discovery_meta("Schema", &format!("Schemas/{SCHEMA_USER}"), funs),
+        };
+        let group_schema = IamScimSchemaResp {
+            schemas: vec![SCHEMA_SCHEMA.to_string()],
+            id: SCHEMA_GROUP.to_string(),
+            name: RESOURCE_TYPE_GROUP.to_string(),
+            description: "Node of the org tree of the tenant".to_string(),
+            attributes: vec![
+                IamScimSchemaAttrResp {
+                    required: true,
+                    ..Self::schema_attr("displayName", "string", "Org node name")
+                },
+                IamScimSchemaAttrResp {
+                    multi_valued: true,
+                    sub_attributes: member_attrs("Account id", "immutable"),
+                    ..Self::schema_attr("members", "complex", "Accounts of the org node")
+                },
+            ],
+            meta: Self::discovery_meta("Schema", &format!("Schemas/{SCHEMA_GROUP}"), funs),
+        };
+        let user_ext_schema = IamScimSchemaResp {
+            schemas: vec![SCHEMA_SCHEMA.to_string()],
+            id: SCHEMA_USER_EXT.to_string(),
+            name: "BiosUser".to_string(),
+            description: "Account attrs of the tenant".to_string(),
+            attributes: IamAttrServ::find_account_attrs(funs, ctx)
+                .await?
+                .into_iter()
+                .filter(|attr| !attr.secret)
+                .map(|attr| IamScimSchemaAttrResp {
+                    required: attr.required,
+                    ..Self::schema_attr(&attr.name, "string", &attr.label)
+                })
+                .collect(),
+            meta: Self::discovery_meta("Schema", &format!("Schemas/{SCHEMA_USER_EXT}"), funs),
+        };
+        let schemas = vec![user_schema, group_schema, user_ext_schema];
+        Ok(Self::list_resp(schemas.len(), 1, schemas))
+    }
+
+    pub async fn get_schema(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<IamScimSchemaResp> {
+        Self::find_schemas(funs, ctx)
+            .await?
+            .resources
+            .into_iter()
+            .find(|schema| schema.id.eq_ignore_ascii_case(id))
+            .ok_or_else(|| funs.err().not_found("iam_scim", "get_schema", &format!("schema {id} is not found"), "404-iam-scim-schema-not-exist"))
+    }
+
+    fn schema_attr(name: &str, r#type: &str, description: &str) -> IamScimSchemaAttrResp {
+        IamScimSchemaAttrResp {
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+            multi_valued: false,
+            description: description.to_string(),
+            required: false,
+            case_exact: false,
+            mutability: "readWrite".to_string(),
+            returned: "default".to_string(),
+            uniqueness: "none".to_string(),
+            sub_attributes: vec![],
+        }
+    }
+
+    // ------------------------------------ Common ------------------------------------
+
+    /// Convert the error to the SCIM error response, the ``scimType`` is carried by the error code as ``<status>-iam-scim-<scimType>``
+    pub fn error_resp(error: &TardisError) -> IamScimErrorResp {
+        let status = error.code.get(..3).filter(|status| status.chars().all(|c| c.is_ascii_digit())).unwrap_or("500").to_string();
+        let scim_type = if let Some((_, scim_type)) = error.code.split_once("-scim-") {
+            Some(scim_type.to_string())
+        } else if status == "409" {
+            Some(ERROR_UNIQUENESS.to_string())
+        } else {
+            None
+        };
+        IamScimErrorResp {
+            schemas: vec![SCHEMA_ERROR.to_string()],
+            status,
+            scim_type,
+            detail: error.message.clone(),
+        }
+    }
+
+    /// Parse the resource or request, the attribute names are case insensitive
+    pub fn parse_resource<T: DeserializeOwned>(mut value: Value, funs: &TardisFunsInst) -> TardisResult<T> {
+        Self::normalize(&mut value);
+        TardisFuns::json.json_to_obj(value).map_err(|e| Self::err(ERROR_INVALID_SYNTAX, &format!("request is invalid: {}", e.message), funs))
+    }
+
+    /// Lock the row of the resource until the end of the transaction,
+    /// so that the version checked by ``If-Match`` can't be changed by the concurrent requests before the update
+    async fn lock_for_update(table_name: &str, id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+        funs.db().get_dto::<IdResp>(Query::select().column(Alias::new("id")).from(Alias::new(table_name)).and_where(Expr::col(Alias::new("id")).eq(id)).lock_exclusive()).await?;
+        Ok(())
+    }
+
+    /// Check the ``If-Match`` header with the weak comparison
+    fn check_version(if_match: Option<&str>, meta: &Option<IamScimMeta>) -> TardisResult<()> {
+        let Some(if_match) = if_match.map(|if_match| if_match.trim()).filter(|if_match| !if_match.is_empty() && *if_match != "*") else {
+            return Ok(());
+        };
+        let version = meta.as_ref().and_then(|meta| meta.version.as_deref()).map(|version| version.trim_start_matches("W/"));
+        if if_match.split(',').any(|expected| Some(expected.trim().trim_start_matches("W/")) == version) {
+            Ok(())
+        } else {
+            Err(TardisError::custom("412", "the version of the resource is changed", "412-iam-scim-version-mismatch"))
+        }
+    }
+
+    fn set_version<T: Serialize>(resource: &mut T, meta: impl Fn(&mut T) -> &mut Option<IamScimMeta>) -> TardisResult<()> {
+        let version = format!("W/\"{}\"", TardisFuns::crypto.digest.md5(&TardisFuns::json.obj_to_string(resource)?)?);
+        if let Some(meta) = meta(resource) {
+            meta.version = Some(version);
+        }
+        Ok(())
+    }
+
+    fn meta(resource_type: &str, endpoint: &str, id: &str, create_time: &DateTime<Utc>, update_time: &DateTime<Utc>, funs: &TardisFunsInst) -> IamScimMeta {
+        IamScimMeta {
+            resource_type: resource_type.to_string(),
+            created: Some(create_time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            last_modified: Some(update_time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            location: Self::location(endpoint, id, funs),
+            version: None,
+        }
+    }
+
+    fn discovery_meta(resource_type: &str, path: &str, funs: &TardisFunsInst) -> IamScimMeta {
+        IamScimMeta {
+            resource_type: resource_type.to_string(),
+            created: None,
+            last_modified: None,
+            location: format!("{}/ci/scim/v2/{path}", funs.conf::<IamConfig>().iam_base_url.trim_end_matches('/')),
+            version: None,
+        }
+    }
+
+    fn location(endpoint: &str, id: &str, funs: &TardisFunsInst) -> String {
+        format!("{}/ci/scim/v2/{endpoint}/{id}", funs.conf::<IamConfig>().iam_base_url.trim_end_matches('/'))
+    }
+
+    fn list_resp<T: Type + ParseFromJSON + ToJSON>(total_results: usize, start_index: usize, resources: Vec<T>) -> IamScimListResp<T> {
+        IamScimListResp {
+            schemas: vec![SCHEMA_LIST_RESP.to_string()],
+            total_results,
+            start_index,
+            items_per_page: resources.len(),
+            resources,
+        }
+    }
+
+    fn parse_credentials(authorization: &str) -> Option<(String, String)> {
+        let (scheme, credentials) = authorization.trim().split_once(' ')?;
+        let credentials = if scheme.eq_ignore_ascii_case("Bearer") {
+            credentials.trim().to_string()
+        } else if scheme.eq_ignore_ascii_case("Basic") {
+            TardisFuns::crypto.base64.decode_to_string(credentials.trim()).ok()?
+        } else {
+            return None;
+        };
+        let (ak, sk) = credentials.split_once(':')?;
+        Some((ak.to_string(), sk.to_string()))
+    }
+
+    fn err(scim_type: &str, msg: &str, funs: &TardisFunsInst) -> TardisError {
+        funs.err().bad_request("scim", scim_type, msg, &format!("400-iam-scim-{scim_type}"))
+    }
+
+    /// Normalize the attribute names and the values sent as strings by some clients (e.g. ``"active": "False"``)
+    fn normalize(value: &mut Value) {
+        match value {
+            Value::Object(attrs) => {
+                for (key, mut value) in std::mem::take(attrs) {
+                    let key = Self::canonical_attr(&key);
+                    if key == SCHEMA_USER_EXT {
+                        // the values of the account attrs are strings
+                        if let Value::Object(ext) = &mut value {
+                            for ext_value in ext.values_mut() {
+                                *ext_value = match ext_value {
+                                    Value::String(_) => continue,
+                                    Value::Null => Value::String("".to_string()),
+                                    other => Value::String(other.to_string()),
+                                };
+                            }
+                        }
+                    } else {
+                        Self::normalize(&mut value);
+                        if key == "active" || key == "primary" {
+                            if let Some(bool_value) = value.as_str().and_then(|bool_value| bool_value.to_lowercase().parse::<bool>().ok()) {
+                                value = Value::Bool(bool_value);
+                            }
+                        }
+                    }
+                    attrs.insert(key, value);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(Self::normalize),
+            _ => {}
+        }
+    }
+
+    fn canonical_attr(attr: &str) -> String {
+        CANONICAL_ATTRS.iter().find(|canonical| canonical.eq_ignore_ascii_case(attr)).map(|canonical| canonical.to_string()).unwrap_or_else(|| attr.to_string())
+    }
+
+    /// Split the schema urn prefix, the urn of the extension is returned as the attribute
+    fn split_urn(path: &str) -> (Option<&'static str>, &str) {
+        for urn in [SCHEMA_USER_EXT, SCHEMA_USER, SCHEMA_GROUP] {
+            if path.len() >= urn.len() && path.is_char_boundary(urn.len()) && path[..urn.len()].eq_ignore_ascii_case(urn) {
+                let rest = &path[urn.len()..];
+                if rest.is_empty() || rest.starts_with(':') {
+                    let rest = rest.strip_prefix(':').unwrap_or(rest);
+                    return (if urn == SCHEMA_USER_EXT { Some(SCHEMA_USER_EXT) } else { None }, rest);
+                }
+            }
+        }
+        (None, path)
+    }
+
+    // ------------------------------------ Filter ------------------------------------
+
+    fn parse_filter_opt(filter: Option<&str>, funs: &TardisFunsInst) -> TardisResult<Option<ScimFilter>> {
+        match filter.map(|filter| filter.trim()).filter(|filter| !filter.is_empty()) {
+            Some(filter) => Ok(Some(Self::parse_filter(filter).map_err(|msg| Self::err(ERROR_INVALID_FILTER, &msg, funs))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn parse_filter(filter: &str) -> Result<ScimFilter, String> {
+        let tokens = Self::tokenize_filter(filter)?;
+        let mut pos = 0;
+        let filter = Self::parse_filter_or(&tokens, &mut pos)?;
+        if pos < tokens.len() {
+            return Err(format!("unexpected token {:?} in filter", tokens[pos]));
+        }
+        Ok(filter)
+    }
+
+    fn tokenize_filter(filter: &str) -> Result<Vec<ScimFilterToken>, String> {
+        let mut tokens = vec![];
+        let mut chars = filter.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                c if c.is_whitespace() => {}
+                '(' => tokens.push(ScimFilterToken::LeftParen),
+                ')' => tokens.push(ScimFilterToken::RightParen),
+                '"' => {
+                    let mut value = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('\\') => value.push(chars.next().ok_or("unterminated string in filter")?),
+                            Some('"') => break,
+                            Some(c) => value.push(c),
+                            None => return Err("unterminated string in filter".to_string()),
+                        }
+                    }
+                    tokens.push(ScimFilterToken::Str(value));
+                }
+                c => {
+                    let mut word = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                            break;
+                        }
+                        word.push(c);
+                        chars.next();
+                    }
+                    tokens.push(ScimFilterToken::Word(word));
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn parse_filter_or(tokens: &[ScimFilterToken], pos: &mut usize) -> Result<ScimFilter, String> {
+        let mut filter = Self::parse_filter_and(tokens, pos)?;
+        while Self::next_keyword(tokens, *pos, "or") {
+            *pos += 1;
+            filter = ScimFilter::Or(Box::new(filter), Box::new(Self::parse_filter_and(tokens, pos)?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_filter_and(tokens: &[ScimFilterToken], pos: &mut usize) -> Result<ScimFilter, String> {
+        let mut filter = Self::parse_filter_factor(tokens, pos)?;
+        while Self::next_keyword(tokens, *pos, "and") {
+            *pos += 1;
+            filter = ScimFilter::And(Box::new(filter), Box::new(Self::parse_filter_factor(tokens, pos)?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_filter_factor(tokens: &[ScimFilterToken], pos: &mut usize) -> Result<ScimFilter, String> {
+        if Self::next_keyword(tokens, *pos, "not") {
+            *pos += 1;
+            return Ok(ScimFilter::Not(Box::new(Self::parse_filter_factor(tokens, pos)?)));
+        }
+        match tokens.get(*pos) {
+            Some(ScimFilterToken::LeftParen) => {
+                *pos += 1;
+                let filter = Self::parse_filter_or(tokens, pos)?;
+                if tokens.get(*pos) != Some(&ScimFilterToken::RightParen) {
+                    return Err("missing ) in filter".to_string());
+                }
+                *pos += 1;
+                Ok(filter)
+            }
+            Some(ScimFilterToken::Word(attr)) => {
+                let op = match tokens.get(*pos + 1) {
+                    Some(ScimFilterToken::Word(op)) => op.to_lowercase(),
+                    _ => return Err(format!("missing operator after {attr} in filter")),
+                };
+                *pos += 2;
+                if op == "pr" {
+                    return Ok(ScimFilter::Present(attr.clone()));
+                }
+                if !["eq", "ne", "co", "sw", "ew", "gt", "ge", "lt", "le"].contains(&op.as_str()) {
+                    return Err(format!("operator {op} is not supported"));
+                }
+                let value = match tokens.get(*pos) {
+                    Some(ScimFilterToken::Str(value)) => Value::String(value.clone()),
+                    Some(ScimFilterToken::Word(value)) => match value.to_lowercase().as_str() {
+                        "true" => Value::Bool(true),
+                        "false" => Value::Bool(false),
+                        "null" => Value::Null,
+                        _ => value.parse::<f64>().map(|number| json!(number)).map_err(|_| format!("value {value} is invalid in filter"))?,
+                    },
+                    _ => return Err(format!("missing value after {attr} {op} in filter")),
+                };
+                *pos += 1;
+                Ok(ScimFilter::Compare { attr: attr.clone(), op, value })
+            }
+            token => Err(format!("unexpected token {token:?} in filter")),
+        }
+    }
+
+    fn next_keyword(tokens: &[ScimFilterToken], pos: usize, keyword: &str) -> bool {
+        matches!(tokens.get(pos), Some(ScimFilterToken::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Find the string value of the clause that must be satisfied, it's used to narrow the candidates by the index
+    fn find_clause(filter: &ScimFilter, attrs: &[&str], ops: &[&str]) -> Option<String> {
+        match filter {
+            ScimFilter::Compare {
+                attr,
+                op,
+                value: Value::String(value),
+            } if ops.contains(&op.as_str()) && attrs.iter().any(|expected| expected.eq_ignore_ascii_case(Self::split_urn(attr).1)) && Self::split_urn(attr).0.is_none() => {
+                Some(value.clone())
+            }
+            ScimFilter::And(left, right) => Self::find_clause(left, attrs, ops).or_else(|| Self::find_clause(right, attrs, ops)),
+            _ => None,
+        }
+    }
+
+    fn filter_matches(filter: &ScimFilter, resource: &Value) -> bool {
+        match filter {
+            ScimFilter::And(left, right) => Self::filter_matches(left, resource) && Self::filter_matches(right, resource),
+            ScimFilter::Or(left, right) => Self::filter_matches(left, resource) || Self::filter_matches(right, resource),
+            ScimFilter::Not(filter) => !Self::filter_matches(filter, resource),
+            ScimFilter::Present(attr) => Self::resolve_attr(resource, attr).iter().any(|value| match value {
+                Value::Null => false,
+                Value::String(value) => !value.is_empty(),
+                Value::Array(items) => !items.is_empty(),
+                _ => true,
+            }),
+            ScimFilter::Compare { attr, op, value } => {
+                let case_exact = Self::split_urn(attr).1.eq_ignore_ascii_case("id");
+                let values = Self::resolve_attr(resource, attr);
+                if op == "ne" {
+                    !values.iter().any(|actual| Self::compare_value(actual, "eq", value, case_exact))
+                } else {
+                    values.iter().any(|actual| Self::compare_value(actual, op, value, case_exact))
+                }
+            }
+        }
+    }
+
+    /// Resolve the values of the attribute path, the multi-valued attributes are flattened and compared by their ``value``
+    fn resolve_attr<'a>(resource: &'a Value, attr: &str) -> Vec<&'a Value> {
+        let (ext, attr) = Self::split_urn(attr);
+        let mut values = vec![resource];
+        if let Some(ext) = ext {
+            values = values.into_iter().filter_map(|value| Self::get_attr(value, ext)).collect();
+        }
+        for name in attr.split('.').filter(|name| !name.is_empty()) {
+            values = values
+                .into_iter()
+                .flat_map(|value| match value {
+                    Value::Array(items) => items.iter().collect::<Vec<_>>(),
+                    value => vec![value],
+                })
+                .filter_map(|value| Self::get_attr(value, name))
+                .collect();
+        }
+        values
+            .into_iter()
+            .flat_map(|value| match value {
+                Value::Array(items) => items.iter().collect::<Vec<_>>(),
+                value => vec![value],
+            })
+            .map(|value| if value.is_object() { Self::get_attr(value, "value").unwrap_or(value) } else { value })
+            .collect()
+    }
+
+    fn get_attr<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+        value.as_object()?.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value)
+    }
+
+    fn compare_value(actual: &Value, op: &str, expected: &Value, case_exact: bool) -> bool {
+        match (actual, expected) {
+            (Value::String(actual), Value::String(expected)) => {
+                let (actual, expected) = if case_exact {
+                    (actual.clone(), expected.clone())
+                } else {
+                    (actual.to_lowercase(), expected.to_lowercase())
+                };
+                match op {
+                    "eq" => actual == expected,
+                    "co" => actual.contains(&expected),
+                    "sw" => actual.starts_with(&expected),
+                    "ew" => actual.ends_with(&expected),
+                    "gt" => actual > expected,
+                    "ge" => actual >= expected,
+                    "lt" => actual < expected,
+                    "le" => actual <= expected,
+                    _ => false,
+                }
+            }
+            (Value::Number(actual), Value::Number(expected)) => {
+                let (actual, expected) = (actual.as_f64().unwrap_or_default(), expected.as_f64().unwrap_or_default());
+                match op {
+                    "eq" => actual == expected,
+                    "gt" => actual > expected,
+                    "ge" => actual >= expected,
+                    "lt" => actual < expected,
+                    "le" => actual <= expected,
+                    _ => false,
+                }
+            }
+            (Value::Bool(actual), Value::Bool(expected)) => op == "eq" && actual == expected,
+            (actual, Value::Null) => op == "eq" && actual.is_null(),
+            _ => false,
+        }
+    }
+
+    // ------------------------------------ Patch ------------------------------------
+
+    /// Apply the patch operations to the resource, the result is saved as a replacement
+    fn apply_patch_ops(resource: &mut Value, operations: &[IamScimPatchOp], funs: &TardisFunsInst) -> TardisResult<()> {
+        if operations.is_empty() {
+            return Err(Self::err(ERROR_INVALID_SYNTAX, "Operations is required", funs));
+        }
+        for operation in operations {
+            let path = operation.path.as_deref().map(|path| path.trim()).filter(|path| !path.is_empty());
+            match operation.op.to_lowercase().as_str() {
+                op @ ("add" | "replace") => {
+                    let is_add = op == "add";
+                    let Some(value) = &operation.value else {
+                        return Err(Self::err(ERROR_INVALID_VALUE, &format!("value is required by {op}"), funs));
+                    };
+                    if let Some(path) = path {
+                        Self::patch_set(resource, &Self::parse_patch_path(path, funs)?, value.clone(), is_add, funs)?;
+                    } else {
+                        let Value::Object(attrs) = value else {
+                            return Err(Self::err(ERROR_INVALID_VALUE, "value must be an object when the path is empty", funs));
+                        };
+                        for (attr, value) in attrs {
+                            Self::patch_set(resource, &Self::parse_patch_path(attr, funs)?, value.clone(), is_add, funs)?;
+                        }
+                    }
+                }
+                "remove" => {
+                    let Some(path) = path else {
+                        return Err(Self::err(ERROR_NO_TARGET, "path is required by remove", funs));
+                    };
+                    Self::patch_remove(resource, &Self::parse_patch_path(path, funs)?, operation.value.as_ref());
+                }
+                op => return Err(Self::err(ERROR_INVALID_SYNTAX, &format!("op {op} is not supported"), funs)),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_patch_path(path: &str, funs: &TardisFunsInst) -> TardisResult<ScimPatchPath> {
+        let (ext, path) = Self::split_urn(path);
+        if let Some(ext) = ext {
+            return Ok(ScimPatchPath {
+                attr: ext.to_string(),
+                filter: None,
+                sub_attr: if path.is_empty() { None } else { Some(path.to_string()) },
+            });
+        }
+        let (attr, filter, sub_attr) = if let Some(start) = path.find('[') {
+            let Some(end) = path.rfind(']').filter(|end| *end > start) else {
+                return Err(Self::err(ERROR_INVALID_PATH, &format!("path {path} is invalid"), funs));
+            };
+            let filter = Self::parse_filter(&path[start + 1..end]).map_err(|msg| Self::err(ERROR_INVALID_PATH, &msg, funs))?;
+            let sub_attr = match &path[end + 1..] {
+                "" => None,
+                sub_attr => match sub_attr.strip_prefix('.') {
+                    Some(sub_attr) => Some(sub_attr),
+                    None => return Err(Self::err(ERROR_INVALID_PATH, &format!("path {path} is invalid"), funs)),
+                },
+            };
+            (&path[..start], Some(filter), sub_attr)
+        } else if let Some((attr, sub_attr)) = path.split_once('.') {
+            (attr, None, Some(sub_attr))
+        } else {
+            (path, None, None)
+        };
+        if attr.is_empty() || sub_attr.is_some_and(|sub_attr| sub_attr.is_empty()) {
+            return Err(Self::err(ERROR_INVALID_PATH, &format!("path {path} is invalid"), funs));
+        }
+        Ok(ScimPatchPath {
+            attr: Self::canonical_attr(attr),
+            filter,
+            sub_attr: sub_attr.map(Self::canonical_attr),
+        })
+    }
+
+    fn patch_set(resource: &mut Value, path: &ScimPatchPath, value: Value, is_add: bool, funs: &TardisFunsInst) -> TardisResult<()> {
+        let Some(attrs) = resource.as_object_mut() else {
+            return Err(Self::err(ERROR_INVALID_SYNTAX, "resource is invalid", funs));
+        };
+        match (&path.filter, &path.sub_attr) {
+            (None, None) => {
+                let target = attrs.entry(path.attr.clone()).or_insert(Value::Null);
+                if is_add {
+                    Self::merge_value(target, value);
+                } else {
+                    *target = value;
+                }
+            }
+            (None, Some(sub_attr)) => {
+                let target = attrs.entry(path.attr.clone()).or_insert(Value::Null);
+                if target.is_null() {
+                    *target = json!({});
+                }
+                let Some(target) = target.as_object_mut() else {
+                    return Err(Self::err(ERROR_INVALID_PATH, &format!("attribute {} is not complex", path.attr), funs));
+                };
+                let target = target.entry(sub_attr.clone()).or_insert(Value::Null);
+                if is_add {
+                    Self::merge_value(target, value);
+                } else {
+                    *target = value;
+                }
+            }
+            (Some(filter), sub_attr) => {
+                let Some(Value::Array(items)) = attrs.get_mut(&path.attr) else {
+                    return Err(Self::err(ERROR_NO_TARGET, &format!("attribute {} has no value", path.attr), funs));
+                };
+                let mut matched = false;
+                for item in items.iter_mut().filter(|item| Self::filter_matches(filter, item)) {
+                    matched = true;
+                    if let Some(sub_attr) = sub_attr {
+                        if let Some(item) = item.as_object_mut() {
+                            item.insert(sub_attr.clone(), value.clone());
+                        }
+                    } else if is_add {
+                        Self::merge_value(item, value.clone());
+                    } else {
+                        *item = value.clone();
+                    }
+                }
+                if !matched {
+                    return Err(Self::err(ERROR_NO_TARGET, &format!("no value of attribute {} matches the filter", path.attr), funs));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn patch_remove(resource: &mut Value, path: &ScimPatchPath, value: Option<&Value>) {
+        let Some(attrs) = resource.as_object_mut() else {
+            return;
+        };
+        match (&path.filter, &path.sub_attr) {
+            (None, None) => {
+                // some clients remove the members by the value instead of the filter
+                let removed_by_value = match (attrs.get_mut(&path.attr), value) {
+                    (Some(Value::Array(items)), Some(Value::Array(removed_items))) => {
+                        items.retain(|item| !removed_items.iter().any(|removed_item| Self::same_item(item, removed_item)));
+                        true
+                    }
+                    _ => false,
+                };
+                if !removed_by_value {
+                    attrs.remove(&path.attr);
+                }
+            }
+            (None, Some(sub_attr)) => {
+                if let Some(Value::Object(target)) = attrs.get_mut(&path.attr) {
+                    target.remove(sub_attr);
+                }
+            }
+            (Some(filter), None) => {
+                if let Some(Value::Array(items)) = attrs.get_mut(&path.attr) {
+                    items.retain(|item| !Self::filter_matches(filter, item));
+                }
+            }
+            (Some(filter), Some(sub_attr)) => {
+                if let Some(Value::Array(items)) = attrs.get_mut(&path.attr) {
+                    for item in items.iter_mut().filter(|item| Self::filter_matches(filter, item)) {
+                        if let Some(item) = item.as_object_mut() {
+                            item.remove(sub_attr);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The multi-valued attributes are appended (the same value is ignored) and the complex attributes are merged
+    fn merge_value(target: &mut Value, value: Value) {
+        match (target, value) {
+            (Value::Array(items), Value::Array(added_items)) => {
+                for added_item in added_items {
+                    if !items.iter().any(|item| Self::same_item(item, &added_item)) {
+                        items.push(added_item);
+                    }
+                }
+            }
+            (Value::Array(items), added_item) => {
+                if !items.iter().any(|item| Self::same_item(item, &added_item)) {
+                    items.push(added_item);
+                }
+            }
+            (Value::Object(attrs), Value::Object(added_attrs)) => {
+                for (key, value) in added_attrs {
+                    attrs.insert(key, value);
+                }
+            }
+            (target, value) => *target = value,
+        }
+    }
+
+    fn same_item(item: &Value, other: &Value) -> bool {
+        match (Self::get_attr(item, "value"), Self::get_attr(other, "value")) {
+            (Some(value), Some(other_value)) => value == other_value,
+            _ => item == other,
+        }
+    }
+}
@@ -31,7 +31,9 @@ use crate::basic::serv::iam_cert_ldap_serv::IamCertLdapServ;
 use crate::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
 use crate::basic::serv::iam_cert_phone_vcode_serv::IamCertPhoneVCodeServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
 use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
 use crate::basic::serv::iam_set_serv::IamSetServ;
 use crate::iam_config::{IamBasicConfigApi, IamBasicInfoManager, IamConfig};
@@ -266,7 +268,7 @@ impl IamTenantServ {
 
         if let Some(cert_conf_by_oauth2) = &add_req.cert_conf_by_oauth2 {
             for add_req in cert_conf_by_oauth2 {
-                IamCertOAuth2Serv::add_or_enable_cert_conf(IamCertOAuth2Supplier::WechatMp, add_req, &tenant_id, funs, &tenant_ctx).await?;
+                IamCertOAuth2Serv::add_or_enable_cert_conf(IamCertOAuth2Supplier::parse(&add_req.supplier)?, add_req, &tenant_id, funs, &tenant_ctx).await?;
             }
         }
         if let Some(cert_conf_by_ldap) = &add_req.cert_conf_by_ldap {
@@ -386,6 +388,8 @@ impl IamTenantServ {
         if modify_req.cert_conf_by_user_pwd.is_none()
             && modify_req.cert_conf_by_phone_vcode.is_none()
             && modify_req.cert_conf_by_mail_vcode.is_none()
+            && modify_req.cert_conf_by_totp.is_none()
+            && modify_req.cert_conf_by_webauthn.is_none()
             && modify_req.cert_conf_by_oauth2.is_none()
             && modify_req.cert_conf_by_ldap.is_none()
             && modify_req.token_default_coexist_num.is_none()
@@ -401,6 +405,12 @@ impl IamTenantServ {
         if modify_req.cert_conf_by_mail_vcode.is_some() {
             log_tasks.push(("修改认证方式为邮箱".to_string(), "ModifyCertifiedWay".to_string()));
         }
+        if modify_req.cert_conf_by_totp.is_some() {
+            log_tasks.push(("修改认证方式为动态口令".to_string(), "ModifyCertifiedWay".to_string()));
+        }
+        if modify_req.cert_conf_by_webauthn.is_some() {
+            log_tasks.push(("修改认证方式为安全密钥".to_string(), "ModifyCertifiedWay".to_string()));
+        }
         for (op_describe, op_kind) in log_tasks {
             let _ = IamLogClient::add_ctx_task(LogParamTag::SecurityAlarm, None, op_describe, Some(op_kind), ctx).await;
         }
@@ -431,6 +441,26 @@ impl IamTenantServ {
             }
         }
 
+        if let Some(cert_conf_by_totp) = modify_req.cert_conf_by_totp {
+            if let Some(cert_conf_by_totp_id) = cert_confs.iter().find(|r| r.kind == IamCertKernelKind::Totp.to_string()).map(|r| r.id.clone()) {
+                if !cert_conf_by_totp {
+                    IamCertServ::disable_cert_conf(&cert_conf_by_totp_id, funs, ctx).await?;
+                }
+            } else if cert_conf_by_totp {
+                IamCertTotpServ::add_or_enable_cert_conf(Some(id.into()), funs, ctx).await?;
+            }
+        }
+
+        if let Some(cert_conf_by_webauthn) = modify_req.cert_conf_by_webauthn {
+            if let Some(cert_conf_by_webauthn_id) = cert_confs.iter().find(|r| r.kind == IamCertKernelKind::WebAuthn.to_string()).map(|r| r.id.clone()) {
+                if !cert_conf_by_webauthn {
+                    IamCertServ::disable_cert_conf(&cert_conf_by_webauthn_id, funs, ctx).await?;
+                }
+            } else if cert_conf_by_webauthn {
+                IamCertWebAuthnServ::add_or_enable_cert_conf(Some(id.into()), funs, ctx).await?;
+            }
+        }
+
         if let Some(token_default_coexist_num) = &modify_req.token_default_coexist_num {
             if let Some(cert_conf_by_token_default_id) = cert_confs.iter().find(|r| r.kind == IamCertTokenKind::TokenDefault.to_string()).map(|r| r.id.clone()) {
                 IamCertTokenServ::modify_cert_conf(
@@ -549,6 +579,8 @@ impl IamTenantServ {
                 cert_conf_by_user_pwd: TardisFuns::json.str_to_obj(&cert_conf_by_user_pwd.ext)?,
                 cert_conf_by_phone_vcode: cert_confs.iter().any(|r| r.kind == IamCertKernelKind::PhoneVCode.to_string()),
                 cert_conf_by_mail_vcode: cert_confs.iter().any(|r| r.kind == IamCertKernelKind::MailVCode.to_string()),
+                cert_conf_by_totp: cert_confs.iter().any(|r| r.kind == IamCertKernelKind::Totp.to_string()),
+                cert_conf_by_webauthn: cert_confs.iter().any(|r| r.kind == IamCertKernelKind::WebAuthn.to_string()),
                 config,
                 cert_conf_by_oauth2,
                 cert_conf_by_ldap,
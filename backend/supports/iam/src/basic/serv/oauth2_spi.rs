@@ -1,2 +1,3 @@
 pub mod iam_cert_oauth2_spi_github;
+pub mod iam_cert_oauth2_spi_oidc;
 pub mod iam_cert_oauth2_spi_wechat_mp;
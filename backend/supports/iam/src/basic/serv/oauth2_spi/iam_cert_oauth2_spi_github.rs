@@ -58,6 +58,7 @@ impl IamCertOAuth2Spi for IamCertOAuth2SpiGithub {
                     refresh_token: None,
                     token_expires_ms: None,
                     union_id: None,
+                    claims: None,
                 })
             } else {
                 Err(funs.err().not_found(
@@ -0,0 +1,475 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::log::{trace, warn};
+use tardis::serde_json::Value;
+use tardis::{TardisFuns, TardisFunsInst};
+
+use crate::basic::dto::iam_cert_conf_dto::IamCertConfOidcConf;
+use crate::basic::dto::iam_cert_dto::{IamCertMailVCodeAddReq, IamCertPhoneVCodeAddReq};
+use crate::basic::dto::iam_set_dto::IamSetItemAddReq;
+use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
+use crate::basic::serv::iam_cert_oauth2_serv::{IamCertOAuth2Spi, IamCertOAuth2TokenInfo};
+use crate::basic::serv::iam_cert_phone_vcode_serv::IamCertPhoneVCodeServ;
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_role_serv::IamRoleServ;
+use crate::basic::serv::iam_set_serv::IamSetServ;
+use crate::iam_config::IamConfig;
+use crate::iam_enumeration::{IamCertKernelKind, IamSetKind};
+
+const DEFAULT_SCOPES: [&str; 3] = ["openid", "profile", "email"];
+const TOKEN_ENDPOINT_AUTH_METHOD_POST: &str = "client_secret_post";
+
+/// Generic OIDC supplier, e.g. Keycloak, Azure AD, Okta.
+///
+/// The authorization code flow with PKCE is used, the login state (nonce, code verifier and redirect uri)
+/// is kept in the cache by the ``state`` returned to the frontend.
+pub struct IamCertOAuth2SpiOidc {
+    conf: IamCertConfOidcConf,
+    tenant_id: String,
+    state: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct IamCertOidcLoginState {
+    tenant_id: String,
+    redirect_uri: String,
+    nonce: String,
+    code_verifier: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct IamCertOidcProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: Option<String>,
+    jwks_uri: String,
+}
+
+/// The account mapped from the claims
+#[derive(Debug, Clone, Default)]
+pub struct IamCertOidcAccountInfo {
+    pub open_id: String,
+    pub name: Option<String>,
+    pub mail: Option<String>,
+    /// Whether the mail has been verified by the supplier (``email_verified`` claim)
+    pub mail_verified: bool,
+    pub phone: Option<String>,
+    /// Whether the phone has been verified by the supplier (``phone_number_verified`` claim)
+    pub phone_verified: bool,
+    pub exts: HashMap<String, String>,
+    pub role_ids: Vec<String>,
+    pub org_node_ids: Vec<String>,
+}
+
+impl IamCertOAuth2SpiOidc {
+    pub fn new(conf: IamCertConfOidcConf, tenant_id: &str, state: Option<String>) -> Self {
+        IamCertOAuth2SpiOidc {
+            conf,
+            tenant_id: tenant_id.to_string(),
+            state,
+        }
+    }
+
+    /// Generate the url of the authorization endpoint, the ``state`` in it should be passed back when login
+    pub async fn get_authorize_url(conf: &IamCertConfOidcConf, client_id: &str, tenant_id: &str, redirect_uri: &str, funs: &TardisFunsInst) -> TardisResult<String> {
+        let metadata = Self::get_metadata(conf, funs).await?;
+        let state = TardisFuns::field.nanoid_len(32);
+        let login_state = IamCertOidcLoginState {
+            tenant_id: tenant_id.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            nonce: TardisFuns::field.nanoid_len(32),
+            code_verifier: TardisFuns::field.nanoid_len(64),
+        };
+        let iam_conf = funs.conf::<IamConfig>();
+        funs.cache()
+            .set_ex(
+                &format!("{}{}", iam_conf.cache_key_oidc_login_state_, state),
+                &TardisFuns::json.obj_to_string(&login_state)?,
+                iam_conf.cache_key_oidc_login_state_expire_sec,
+            )
+            .await?;
+        let scopes = if conf.scopes.is_empty() { DEFAULT_SCOPES.join(" ") } else { conf.scopes.join(" ") };
+        let params = [
+            ("response_type", "code".to_string()),
+            ("client_id", client_id.to_string()),
+            ("redirect_uri", redirect_uri.to_string()),
+            ("scope", scopes),
+            ("state", state),
+            ("nonce", login_state.nonce),
+            ("code_challenge", URL_SAFE_NO_PAD.encode(Sha256::digest(login_state.code_verifier.as_bytes()))),
+            ("code_challenge_method", "S256".to_string()),
+        ];
+        Ok(format!(
+            "{}{}{}",
+            metadata.authorization_endpoint,
+            if metadata.authorization_endpoint.contains('?') { "&" } else { "?" },
+            Self::encode_form(&params)
+        ))
+    }
+
+    /// Map the claims to the account by the claim mapping
+    pub fn map_account(conf: &IamCertConfOidcConf, claims: &Value, funs: &TardisFunsInst) -> TardisResult<IamCertOidcAccountInfo> {
+        let mapping = &conf.claim_mapping;
+        let open_id_claim = mapping.open_id.as_deref().unwrap_or("sub");
+        let open_id = Self::get_claim_str(claims, open_id_claim).ok_or_else(|| {
+            funs.err().unauthorized(
+                "oauth_spi_oidc",
+                "map_account",
+                &format!("oauth get user info error:missing claim [{open_id_claim}]"),
+                "401-iam-cert-oauth-oidc-claim-missing",
+            )
+        })?;
+        let exts = mapping.exts.iter().filter_map(|(claim, attr_name)| Self::get_claim_str(claims, claim).map(|value| (attr_name.clone(), value))).collect();
+        let groups = match conf.groups_claim.as_deref().and_then(|claim| Self::get_claim(claims, claim)) {
+            Some(Value::Array(groups)) => groups.iter().filter_map(Self::claim_to_string).collect::<Vec<_>>(),
+            Some(group) => Self::claim_to_string(group).into_iter().collect(),
+            None => vec![],
+        };
+        let mut role_ids: Vec<String> = vec![];
+        let mut org_node_ids: Vec<String> = vec![];
+        for group in &groups {
+            for role_id in conf.role_mapping.get(group).into_iter().flatten() {
+                if !role_ids.contains(role_id) {
+                    role_ids.push(role_id.clone());
+                }
+            }
+            for org_node_id in conf.org_mapping.get(group).into_iter().flatten() {
+                if !org_node_ids.contains(org_node_id) {
+                    org_node_ids.push(org_node_id.clone());
+                }
+            }
+        }
+        Ok(IamCertOidcAccountInfo {
+            open_id,
+            name: Self::get_claim_str(claims, mapping.name.as_deref().unwrap_or("name")),
+            mail: Self::get_claim_str(claims, mapping.mail.as_deref().unwrap_or("email")),
+            mail_verified: Self::get_claim_bool(claims, "email_verified"),
+            phone: Self::get_claim_str(claims, mapping.phone.as_deref().unwrap_or("phone_number")),
+            phone_verified: Self::get_claim_bool(claims, "phone_number_verified"),
+            exts,
+            role_ids,
+            org_node_ids,
+        })
+    }
+
+    /// Sync the mapped roles and org nodes of the account on each login.
+    ///
+    /// The missing ones are added, and the ones no longer mapped from the groups are removed.
+    /// Only the roles and org nodes listed in the mapping are managed, the ones assigned in IAM are kept.
+    pub async fn sync_mapped_roles_and_orgs(
+        account_id: &str,
+        conf: &IamCertConfOidcConf,
+        account_info: &IamCertOidcAccountInfo,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<()> {
+        let managed_role_ids = conf.role_mapping.values().flatten().collect::<HashSet<_>>();
+        if !managed_role_ids.is_empty() {
+            let stored_role_ids = IamAccountServ::find_simple_rel_roles(account_id, true, None, None, funs, ctx).await?.into_iter().map(|role| role.rel_id).collect::<Vec<_>>();
+            for role_id in &account_info.role_ids {
+                if !stored_role_ids.contains(role_id) {
+                    IamRoleServ::add_rel_account(role_id, account_id, None, funs, ctx).await?;
+                }
+            }
+            for stored_role_id in &stored_role_ids {
+                if managed_role_ids.contains(stored_role_id) && !account_info.role_ids.contains(stored_role_id) {
+                    IamRoleServ::delete_rel_account(stored_role_id, account_id, None, funs, ctx).await?;
+                }
+            }
+        }
+        let managed_org_node_ids = conf.org_mapping.values().flatten().collect::<HashSet<_>>();
+        if !managed_org_node_ids.is_empty() {
+            let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, funs, ctx).await?;
+            let stored_items = IamSetServ::find_set_items(Some(set_id.clone()), None, Some(account_id.to_string()), None, false, None, funs, ctx).await?;
+            let stored_org_node_ids = stored_items.iter().filter_map(|item| item.rel_rbum_set_cate_id.clone()).collect::<Vec<_>>();
+            for org_node_id in &account_info.org_node_ids {
+                if !stored_org_node_ids.contains(org_node_id) {
+                    IamSetServ::add_set_item(
+                        &IamSetItemAddReq {
+                            set_id: set_id.clone(),
+                            set_cate_id: org_node_id.to_string(),
+                            sort: 0,
+                            rel_rbum_item_id: account_id.to_string(),
+                        },
+                        funs,
+                        ctx,
+                    )
+                    .await?;
+                }
+            }
+            for stored_item in stored_items {
+                if let Some(org_node_id) = &stored_item.rel_rbum_set_cate_id {
+                    if managed_org_node_ids.contains(org_node_id) && !account_info.org_node_ids.contains(org_node_id) {
+                        IamSetServ::delete_set_item(&stored_item.id, funs, ctx).await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the mail and phone certs of the provisioned account, failures are ignored so as not to block the login.
+    ///
+    /// Only the mail and phone verified by the supplier are added, otherwise anyone could take over the login by vcode of others' mail or phone.
+    pub async fn add_contact_certs(account_id: &str, account_info: &IamCertOidcAccountInfo, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        if let Some(mail) = account_info.mail.as_ref().filter(|_| account_info.mail_verified) {
+            if let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&IamCertKernelKind::MailVCode.to_string(), Some(ctx.own_paths.clone()), funs).await? {
+                // the mail has been verified by the supplier
+                if let Err(e) = IamCertMailVCodeServ::add_cert_skip_activate(&IamCertMailVCodeAddReq { mail: mail.clone() }, account_id, &cert_conf.id, funs, ctx).await {
+                    warn!("[Iam] oidc add mail cert of account {} error: {:?}", account_id, e);
+                }
+            }
+        }
+        if let Some(phone) = account_info.phone.as_ref().filter(|_| account_info.phone_verified) {
+            if let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&IamCertKernelKind::PhoneVCode.to_string(), Some(ctx.own_paths.clone()), funs).await? {
+                if let Err(e) = IamCertPhoneVCodeServ::add_cert(&IamCertPhoneVCodeAddReq { phone: TrimString(phone.clone()) }, account_id, &cert_conf.id, funs, ctx).await {
+                    warn!("[Iam] oidc add phone cert of account {} error: {:?}", account_id, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_metadata(conf: &IamCertConfOidcConf, funs: &TardisFunsInst) -> TardisResult<IamCertOidcProviderMetadata> {
+        let issuer = conf.issuer.trim_end_matches('/').to_string();
+        let mut metadata = if let (Some(authorization_endpoint), Some(token_endpoint), Some(jwks_uri)) = (&conf.authorization_endpoint, &conf.token_endpoint, &conf.jwks_uri) {
+            IamCertOidcProviderMetadata {
+                issuer: issuer.clone(),
+                authorization_endpoint: authorization_endpoint.clone(),
+                token_endpoint: token_endpoint.clone(),
+                userinfo_endpoint: None,
+                jwks_uri: jwks_uri.clone(),
+            }
+        } else {
+            let iam_conf = funs.conf::<IamConfig>();
+            let cache_key = format!("{}{}", iam_conf.cache_key_oidc_metadata_, issuer);
+            if let Some(metadata) = funs.cache().get(&cache_key).await? {
+                TardisFuns::json.str_to_obj(&metadata)?
+            } else {
+                let result = funs.web_client().get_to_str(&format!("{issuer}/.well-known/openid-configuration"), None).await?;
+                trace!("iam oauth2 spi [Oidc] discovery response: {:?}", result);
+                if result.code != 200 {
+                    return Err(funs.err().not_found(
+                        "oauth_spi_oidc",
+                        "get_metadata",
+                        &format!("oauth get provider metadata error:[{}]{}", result.code, result.body.unwrap_or_default()),
+                        "500-iam-cert-oauth-get-metadata-error",
+                    ));
+                }
+                let metadata = result.body.unwrap_or_default();
+                let parsed_metadata: IamCertOidcProviderMetadata = TardisFuns::json.str_to_obj(&metadata)?;
+                funs.cache().set_ex(&cache_key, &metadata, iam_conf.cache_key_oidc_metadata_expire_sec).await?;
+                parsed_metadata
+            }
+        };
+        if let Some(authorization_endpoint) = &conf.authorization_endpoint {
+            metadata.authorization_endpoint = authorization_endpoint.clone();
+        }
+        if let Some(token_endpoint) = &conf.token_endpoint {
+            metadata.token_endpoint = token_endpoint.clone();
+        }
+        if let Some(jwks_uri) = &conf.jwks_uri {
+            metadata.jwks_uri = jwks_uri.clone();
+        }
+        if conf.userinfo_endpoint.is_some() {
+            metadata.userinfo_endpoint = conf.userinfo_endpoint.clone();
+        }
+        Ok(metadata)
+    }
+
+    async fn verify_id_token(id_token: &str, metadata: &IamCertOidcProviderMetadata, ak: &str, sk: &str, nonce: &str, funs: &TardisFunsInst) -> TardisResult<Value> {
+        let header = jsonwebtoken::decode_header(id_token).map_err(|e| Self::id_token_err(&e.to_string(), funs))?;
+        let key = match header.alg {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => DecodingKey::from_secret(sk.as_bytes()),
+            _ => {
+                let result = funs.web_client().get_to_str(&metadata.jwks_uri, None).await?;
+                if result.code != 200 {
+                    return Err(funs.err().not_found(
+                        "oauth_spi_oidc",
+                        "verify_id_token",
+                        &format!("oauth get jwks error:[{}]", result.code),
+                        "500-iam-cert-oauth-get-jwks-error",
+                    ));
+                }
+                let jwks: JwkSet = TardisFuns::json.str_to_obj(&result.body.unwrap_or_default())?;
+                let jwk = match &header.kid {
+                    Some(kid) => jwks.find(kid),
+                    None => jwks.keys.first(),
+                }
+                .ok_or_else(|| Self::id_token_err("signing key is not found", funs))?;
+                DecodingKey::from_jwk(jwk).map_err(|e| Self::id_token_err(&e.to_string(), funs))?
+            }
+        };
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[ak]);
+        validation.set_issuer(&[&metadata.issuer]);
+        let claims = jsonwebtoken::decode::<Value>(id_token, &key, &validation).map_err(|e| Self::id_token_err(&e.to_string(), funs))?.claims;
+        if claims.get("nonce").and_then(|v| v.as_str()) != Some(nonce) {
+            return Err(Self::id_token_err("nonce does not match", funs));
+        }
+        Ok(claims)
+    }
+
+    async fn get_userinfo(metadata: &IamCertOidcProviderMetadata, access_token: &str, funs: &TardisFunsInst) -> TardisResult<Option<Value>> {
+        let Some(userinfo_endpoint) = &metadata.userinfo_endpoint else {
+            return Ok(None);
+        };
+        let headers = vec![
+            ("Authorization".to_string(), format!("Bearer {access_token}")),
+            ("Accept".to_string(), "application/json".to_string()),
+        ];
+        let result = funs.web_client().get_to_str(userinfo_endpoint, headers).await?;
+        trace!("iam oauth2 spi [Oidc] get user info response: {:?}", result);
+        if result.code != 200 {
+            return Err(funs.err().not_found("oauth_spi_oidc", "get_userinfo", "oauth get user info error", "500-iam-cert-oauth-get-user-info-error"));
+        }
+        Ok(Some(TardisFuns::json.str_to_obj(&result.body.unwrap_or_default())?))
+    }
+
+    fn get_claim<'a>(claims: &'a Value, path: &str) -> Option<&'a Value> {
+        path.split('.').try_fold(claims, |value, key| value.get(key))
+    }
+
+    fn get_claim_str(claims: &Value, path: &str) -> Option<String> {
+        Self::get_claim(claims, path).and_then(Self::claim_to_string).filter(|value| !value.is_empty())
+    }
+
+    /// Some suppliers return the boolean claims as strings
+    fn get_claim_bool(claims: &Value, path: &str) -> bool {
+        match Self::get_claim(claims, path) {
+            Some(Value::Bool(value)) => *value,
+            Some(Value::String(value)) => value.eq_ignore_ascii_case("true"),
+            _ => false,
+        }
+    }
+
+    fn claim_to_string(value: &Value) -> Option<String> {
+        match value {
+            Value::String(value) => Some(value.clone()),
+            Value::Number(value) => Some(value.to_string()),
+            Value::Bool(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+
+    fn encode_form(params: &[(&str, String)]) -> String {
+        params.iter().map(|(key, value)| format!("{key}={}", utf8_percent_encode(value, NON_ALPHANUMERIC))).collect::<Vec<_>>().join("&")
+    }
+
+    fn id_token_err(msg: &str, funs: &TardisFunsInst) -> TardisError {
+        funs.err().unauthorized(
+            "oauth_spi_oidc",
+            "verify_id_token",
+            &format!("oauth id_token is invalid:{msg}"),
+            "401-iam-cert-oauth-id-token-invalid",
+        )
+    }
+}
+
+#[async_trait]
+impl IamCertOAuth2Spi for IamCertOAuth2SpiOidc {
+    async fn get_access_token(&self, code: &str, ak: &str, sk: &str, funs: &TardisFunsInst) -> TardisResult<IamCertOAuth2TokenInfo> {
+        // https://openid.net/specs/openid-connect-core-1_0.html#CodeFlowAuth
+        let state = self.state.as_deref().ok_or_else(|| funs.err().bad_request("oauth_spi_oidc", "get_access_token", "state is required", "400-iam-cert-oauth-state-invalid"))?;
+        let state_key = format!("{}{}", funs.conf::<IamConfig>().cache_key_oidc_login_state_, state);
+        // the state can only be used once, it is got and deleted atomically so that the concurrent logins can't use the same one
+        let login_state: Option<String> = funs
+            .cache()
+            .script(
+                r#"
+                   local login_state = redis.call('GET', KEYS[1])
+                   if login_state then
+                       redis.call('DEL', KEYS[1])
+                   end
+                   return login_state
+               "#,
+            )
+            .key(&state_key)
+            .invoke()
+            .await?;
+        let login_state: IamCertOidcLoginState = match login_state {
+            Some(login_state) => TardisFuns::json.str_to_obj(&login_state)?,
+            None => {
+                return Err(funs.err().unauthorized("oauth_spi_oidc", "get_access_token", "state is invalid or expired", "401-iam-cert-oauth-state-invalid"));
+            }
+        };
+        if login_state.tenant_id != self.tenant_id {
+            return Err(funs.err().unauthorized("oauth_spi_oidc", "get_access_token", "state is invalid or expired", "401-iam-cert-oauth-state-invalid"));
+        }
+        let metadata = Self::get_metadata(&self.conf, funs).await?;
+        let mut params = vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code.to_string()),
+            ("redirect_uri", login_state.redirect_uri.clone()),
+            ("code_verifier", login_state.code_verifier.clone()),
+        ];
+        let mut headers = vec![
+            ("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()),
+            ("Accept".to_string(), "application/json".to_string()),
+        ];
+        if self.conf.token_endpoint_auth_method.as_deref() == Some(TOKEN_ENDPOINT_AUTH_METHOD_POST) {
+            params.push(("client_id", ak.to_string()));
+            params.push(("client_secret", sk.to_string()));
+        } else {
+            let credentials = format!("{}:{}", utf8_percent_encode(ak, NON_ALPHANUMERIC), utf8_percent_encode(sk, NON_ALPHANUMERIC));
+            headers.push(("Authorization".to_string(), format!("Basic {}", STANDARD.encode(credentials))));
+        }
+        let result = funs.web_client().post_str_to_str(&metadata.token_endpoint, &Self::encode_form(&params), headers).await?;
+        trace!("iam oauth2 spi [Oidc] get access token response: {:?}", result);
+        if result.code != 200 {
+            return Err(funs.err().not_found(
+                "oauth_spi_oidc",
+                "get_access_token",
+                &format!("oauth get access token error:[{}]{}", result.code, result.body.unwrap_or_default()),
+                "500-iam-cert-oauth-get-access-token-error",
+            ));
+        }
+        let result = TardisFuns::json.str_to_obj::<Value>(&result.body.unwrap_or_default())?;
+        let access_token = result.get("access_token").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let id_token = result.get("id_token").and_then(|v| v.as_str()).ok_or_else(|| {
+            funs.err().not_found(
+                "oauth_spi_oidc",
+                "get_access_token",
+                "oauth get access token error:missing field [id_token]",
+                "500-iam-cert-oauth-get-access-token-error",
+            )
+        })?;
+        let mut claims = Self::verify_id_token(id_token, &metadata, ak, sk, &login_state.nonce, funs).await?;
+        if let Some(Value::Object(userinfo)) = Self::get_userinfo(&metadata, &access_token, funs).await? {
+            if userinfo.get("sub") != claims.get("sub") {
+                return Err(Self::id_token_err("sub of the userinfo does not match", funs));
+            }
+            if let Value::Object(claims) = &mut claims {
+                claims.extend(userinfo);
+            }
+        }
+        let open_id = Self::map_account(&self.conf, &claims, funs)?.open_id;
+        Ok(IamCertOAuth2TokenInfo {
+            open_id,
+            access_token,
+            refresh_token: result.get("refresh_token").and_then(|v| v.as_str()).map(|v| v.to_string()),
+            token_expires_ms: result.get("expires_in").and_then(|v| v.as_u64()).map(|v| (v * 1000) as u32),
+            union_id: None,
+            claims: Some(claims),
+        })
+    }
+
+    async fn get_account_name(&self, oauth2_info: IamCertOAuth2TokenInfo, funs: &TardisFunsInst) -> TardisResult<String> {
+        let account_info = Self::map_account(&self.conf, &oauth2_info.claims.unwrap_or_default(), funs)?;
+        Ok(account_info.name.unwrap_or(account_info.open_id))
+    }
+}
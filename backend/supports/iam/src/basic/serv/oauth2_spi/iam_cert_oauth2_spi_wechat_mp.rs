@@ -70,6 +70,7 @@ impl IamCertOAuth2Spi for IamCertOAuth2SpiWeChatMp {
             refresh_token: None,
             token_expires_ms: None,
             union_id,
+            claims: None,
         })
     }
 
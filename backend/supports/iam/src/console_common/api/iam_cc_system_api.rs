@@ -1,9 +1,10 @@
+use bios_basic::process::dto::task_processor_dto::{AsyncTaskDetailResp, AsyncTaskFilterReq, AsyncTaskStatusKind};
 use bios_basic::process::task_processor::TaskProcessor;
 use tardis::serde_json::Value;
 use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem_openapi;
-use tardis::web::poem_openapi::param::Path;
-use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
+use tardis::web::poem_openapi::param::{Path, Query};
+use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
 use crate::iam_config::IamConfig;
 use crate::iam_constants::{self, IAM_AVATAR};
@@ -41,18 +42,77 @@ impl IamCcSystemApi {
         let task_ids = task_ids.0.split(',');
         for task_id in task_ids {
             let task_id = task_id.parse().map_err(|_| funs.err().format_error("system", "task", "task id format error", "406-iam-task-id-format"))?;
-            TaskProcessor::stop_task_with_event(
+            TaskProcessor::cancel_task(
                 &funs.conf::<IamConfig>().cache_key_async_task_status,
                 task_id,
-                &funs.cache(),
                 IAM_AVATAR.to_owned(),
                 Some(vec![format!("account/{}", ctx.0.owner)]),
+                &funs,
+                &ctx.0,
             )
             .await?;
         }
         TardisResp::ok(Void {})
     }
 
+    /// Retry Async Task
+    /// 重试异步任务
+    ///
+    /// Only failed or cancelled tasks can be retried
+    /// 只有失败或已取消的任务可以重试
+    #[oai(path = "/task/retry/:task_id", method = "put")]
+    async fn retry_task(&self, task_id: Path<u64>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = iam_constants::get_tardis_inst();
+        TaskProcessor::retry_task(
+            &funs.conf::<IamConfig>().cache_key_async_task_status,
+            task_id.0,
+            IAM_AVATAR.to_owned(),
+            Some(vec![format!("account/{}", ctx.0.owner)]),
+            &funs,
+            &ctx.0,
+        )
+        .await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Get Async Task Detail
+    /// 获取异步任务详情
+    #[oai(path = "/task/detail/:task_id", method = "get")]
+    async fn get_task(&self, task_id: Path<u64>, ctx: TardisContextExtractor) -> TardisApiResult<Option<AsyncTaskDetailResp>> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = TaskProcessor::get_task(&funs.conf::<IamConfig>().cache_key_async_task_status, task_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Find Async Tasks
+    /// 查询异步任务
+    #[oai(path = "/task", method = "get")]
+    async fn paginate_tasks(
+        &self,
+        task_type: Query<Option<String>>,
+        status: Query<Option<AsyncTaskStatusKind>>,
+        with_sub: Query<Option<bool>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<AsyncTaskDetailResp>> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = TaskProcessor::paginate_tasks(
+            &funs.conf::<IamConfig>().cache_key_async_task_status,
+            &AsyncTaskFilterReq {
+                task_type: task_type.0,
+                status: status.0,
+                with_sub_own_paths: with_sub.0.unwrap_or(false),
+            },
+            page_number.0,
+            page_size.0,
+            &funs,
+            &ctx.0,
+        )
+        .await?;
+        TardisResp::ok(result)
+    }
+
     /// Get Task Process Data
     /// 获取任务处理数据
     #[oai(path = "/task/process/:task_id", method = "get")]
@@ -25,6 +25,7 @@ use bios_sdk_invoke::clients::spi_log_client::{LogItemFindReq, SpiLogClient};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     chrono::{DateTime, Duration, Utc},
+    serde_json::Value,
     TardisFunsInst,
 };
 
@@ -34,120 +35,126 @@ pub struct IamCcAccountTaskServ;
 
 impl IamCcAccountTaskServ {
     pub async fn execute_account_search_task(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<String>> {
-        let task_ctx = ctx.clone();
-        TaskProcessor::execute_task_with_ctx(
+        TaskProcessor::execute_registered_task(
             &funs.conf::<IamConfig>().cache_key_async_task_status,
-            move |_task_id| async move {
-                let funs = iam_constants::get_tardis_inst();
-                let account_list = IamAccountServ::find_id_items(
-                    &IamAccountFilterReq {
-                        basic: RbumBasicFilterReq {
-                            ignore_scope: false,
-                            rel_ctx_owner: false,
-                            own_paths: Some(task_ctx.own_paths.clone()),
-                            with_sub_own_paths: true,
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    None,
-                    None,
-                    &funs,
-                    &task_ctx,
-                )
-                .await?;
-                let mut num = 0;
-                for account in account_list {
-                    let id = account;
-                    num += 1;
-                    if num % 100 == 0 {
-                        tardis::tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                    }
-                    IamSearchClient::async_add_or_modify_account_search(&id, Box::new(true), "", &funs, &task_ctx).await?;
-                    task_ctx.execute_task().await?;
-                }
-                Ok(())
-            },
-            &funs.cache(),
+            iam_constants::TASK_TYPE_ACCOUNT_SEARCH,
+            Value::Null,
+            0,
             IAM_AVATAR.to_owned(),
             Some(vec![format!("account/{}", ctx.owner)]),
+            funs,
             ctx,
         )
         .await?;
         Ok(None)
     }
 
+    pub(crate) async fn do_execute_account_search_task(_task_id: u64, _params: Value, task_ctx: TardisContext) -> TardisResult<Value> {
+        let funs = iam_constants::get_tardis_inst();
+        let account_list = IamAccountServ::find_id_items(
+            &IamAccountFilterReq {
+                basic: RbumBasicFilterReq {
+                    ignore_scope: false,
+                    rel_ctx_owner: false,
+                    own_paths: Some(task_ctx.own_paths.clone()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+            None,
+            &funs,
+            &task_ctx,
+        )
+        .await?;
+        let mut num = 0;
+        for account in account_list {
+            let id = account;
+            num += 1;
+            if num % 100 == 0 {
+                tardis::tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            }
+            IamSearchClient::async_add_or_modify_account_search(&id, Box::new(true), "", &funs, &task_ctx).await?;
+            task_ctx.execute_task().await?;
+        }
+        Ok(Value::Null)
+    }
+
     pub async fn execute_account_task(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<String>> {
-        let task_ctx = ctx.clone();
-        TaskProcessor::execute_task_with_ctx(
+        TaskProcessor::execute_registered_task(
             &funs.conf::<IamConfig>().cache_key_async_task_status,
-            move |_task_id| async move {
-                let mut funs = iam_constants::get_tardis_inst();
-                funs.begin().await?;
-                let account_list = IamAccountServ::find_items(
-                    &IamAccountFilterReq {
-                        basic: RbumBasicFilterReq {
-                            ignore_scope: false,
-                            rel_ctx_owner: false,
-                            own_paths: Some(task_ctx.own_paths.clone()),
-                            with_sub_own_paths: true,
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    None,
-                    None,
-                    &funs,
-                    &task_ctx,
-                )
-                .await?;
-                let admin_account_list = IamRelServ::find_to_simple_rels(&IamRelKind::IamAccountRole, &funs.iam_basic_role_sys_admin_id(), None, None, &funs, &task_ctx)
-                    .await?
-                    .iter()
-                    .map(|r| r.rel_id.clone())
-                    .collect::<Vec<String>>();
-                let platform_config = IamPlatformServ::get_platform_config_agg(&funs, &task_ctx).await?;
-                let mut num = 0;
-                for account in account_list {
-                    let id = account.id.clone();
-                    if admin_account_list.contains(&id) {
-                        continue;
-                    }
-                    num += 1;
-                    if num % 100 == 0 {
-                        tardis::tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                    }
-                    match account.scope_level.clone() {
-                        bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::Private => {
-                            if !account.own_paths.is_empty() {
-                                let tenant_config = IamTenantServ::get_tenant_config_agg(&account.own_paths, &funs, &task_ctx).await?;
-                                Self::task_modify_account_agg(account, tenant_config.config, &funs, &task_ctx).await?;
-                            } else {
-                                Self::task_modify_account_agg(account, platform_config.config.clone(), &funs, &task_ctx).await?;
-                            }
-                        }
-                        bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::Root => {
-                            Self::task_modify_account_agg(account, platform_config.config.clone(), &funs, &task_ctx).await?;
-                        }
-                        bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::L1 => {}
-                        bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::L2 => {}
-                        bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::L3 => {}
-                    }
-                    IamSearchClient::async_add_or_modify_account_search(&id, Box::new(true), "", &funs, &task_ctx).await?;
-                }
-                funs.commit().await?;
-                task_ctx.execute_task().await?;
-                Ok(())
-            },
-            &funs.cache(),
+            iam_constants::TASK_TYPE_ACCOUNT,
+            Value::Null,
+            0,
             IAM_AVATAR.to_owned(),
             Some(vec![format!("account/{}", ctx.owner)]),
+            funs,
             ctx,
         )
         .await?;
         Ok(None)
     }
 
+    pub(crate) async fn do_execute_account_task(_task_id: u64, _params: Value, task_ctx: TardisContext) -> TardisResult<Value> {
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let account_list = IamAccountServ::find_items(
+            &IamAccountFilterReq {
+                basic: RbumBasicFilterReq {
+                    ignore_scope: false,
+                    rel_ctx_owner: false,
+                    own_paths: Some(task_ctx.own_paths.clone()),
+                    with_sub_own_paths: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+            None,
+            &funs,
+            &task_ctx,
+        )
+        .await?;
+        let admin_account_list = IamRelServ::find_to_simple_rels(&IamRelKind::IamAccountRole, &funs.iam_basic_role_sys_admin_id(), None, None, &funs, &task_ctx)
+            .await?
+            .iter()
+            .map(|r| r.rel_id.clone())
+            .collect::<Vec<String>>();
+        let platform_config = IamPlatformServ::get_platform_config_agg(&funs, &task_ctx).await?;
+        let mut num = 0;
+        for account in account_list {
+            let id = account.id.clone();
+            if admin_account_list.contains(&id) {
+                continue;
+            }
+            num += 1;
+            if num % 100 == 0 {
+                tardis::tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            }
+            match account.scope_level.clone() {
+                bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::Private => {
+                    if !account.own_paths.is_empty() {
+                        let tenant_config = IamTenantServ::get_tenant_config_agg(&account.own_paths, &funs, &task_ctx).await?;
+                        Self::task_modify_account_agg(account, tenant_config.config, &funs, &task_ctx).await?;
+                    } else {
+                        Self::task_modify_account_agg(account, platform_config.config.clone(), &funs, &task_ctx).await?;
+                    }
+                }
+                bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::Root => {
+                    Self::task_modify_account_agg(account, platform_config.config.clone(), &funs, &task_ctx).await?;
+                }
+                bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::L1 => {}
+                bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::L2 => {}
+                bios_basic::rbum::rbum_enumeration::RbumScopeLevelKind::L3 => {}
+            }
+            IamSearchClient::async_add_or_modify_account_search(&id, Box::new(true), "", &funs, &task_ctx).await?;
+        }
+        funs.commit().await?;
+        task_ctx.execute_task().await?;
+        Ok(Value::Null)
+    }
+
     async fn task_modify_account_agg(account: IamAccountSummaryResp, configs: Vec<IamConfigSummaryResp>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
         let (account_temporary_expire, account_temporary_sleep_expire, account_temporary_sleep_logout_expire, account_inactivity_lock) = Self::config(configs);
         let tag: String = LogParamTag::Token.into();
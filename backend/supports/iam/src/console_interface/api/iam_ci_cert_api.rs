@@ -3,7 +3,9 @@ use std::collections::HashMap;
 use crate::basic::dto::iam_account_dto::IamAccountExtSysResp;
 use crate::basic::dto::iam_cert_conf_dto::IamCertConfLdapResp;
 use crate::basic::dto::iam_cert_dto::{IamCertAkSkAddReq, IamCertAkSkResp, IamCertDecodeRequest, IamOauth2AkSkResp, IamThirdPartyCertExtAddReq, IamThirdPartyCertExtModifyReq};
+use crate::basic::dto::iam_oauth2_provider_dto::IamOAuth2ClientConf;
 use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_cert_aksk_serv::IamCertAkSkServ;
 use crate::basic::serv::iam_cert_ldap_serv::IamCertLdapServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
 use crate::console_interface::serv::iam_ci_cert_aksk_serv::IamCiCertAkSkServ;
@@ -69,6 +71,26 @@ impl IamCiCertManageApi {
         TardisResp::ok(Void {})
     }
 
+    /// Modify the OAuth2 client configuration of aksk Cert
+    /// 修改aksk证书的OAuth2客户端配置
+    #[oai(path = "/aksk/:id/oauth2", method = "put")]
+    async fn modify_aksk_oauth2_client(
+        &self,
+        id: Path<String>,
+        modify_req: Json<IamOAuth2ClientConf>,
+        mut ctx: TardisContextExtractor,
+        request: &Request,
+    ) -> TardisApiResult<Void> {
+        let mut funs = iam_constants::get_tardis_inst();
+        check_without_owner_and_unsafe_fill_ctx(request, &funs, &mut ctx.0)?;
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        funs.begin().await?;
+        IamCertAkSkServ::modify_oauth2_client(&id.0, &modify_req.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+
     /// Get token
     /// 获取token
     #[oai(path = "/token", method = "get")]
@@ -8,7 +8,7 @@ use tardis::web::poem_openapi::param::{Path, Query};
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::basic::dto::iam_open_dto::{IamOpenAddOrModifyProductReq, IamOpenAkSkAddReq, IamOpenAkSkResp, IamOpenBindAkProductReq, IamOpenRuleResp};
+use crate::basic::dto::iam_open_dto::{IamOpenAddOrModifyProductReq, IamOpenAkSkAddReq, IamOpenAkSkResp, IamOpenBindAkProductReq, IamOpenQuotaResp, IamOpenRuleResp};
 use crate::basic::serv::iam_cert_serv::IamCertServ;
 use crate::basic::serv::iam_open_serv::IamOpenServ;
 use crate::iam_constants;
@@ -85,6 +85,35 @@ impl IamCiOpenApi {
         TardisResp::ok(result)
     }
 
+    /// Get api call quota
+    /// 获取API调用配额
+    #[oai(path = "/quota", method = "get")]
+    async fn get_api_call_quota(&self, cert_id: Query<Option<String>>, ak: Query<Option<String>>, _request: &Request) -> TardisApiResult<IamOpenQuotaResp> {
+        let funs = iam_constants::get_tardis_inst();
+        let global_ctx = TardisContext {
+            own_paths: "".to_string(),
+            ..Default::default()
+        };
+        let result = IamOpenServ::get_api_call_quota(cert_id.0, ak.0, &funs, &global_ctx).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Consume api call quota
+    /// 消耗API调用配额
+    ///
+    /// Return 429 when the quota is exceeded.
+    /// 超出配额时返回429。
+    #[oai(path = "/quota", method = "put")]
+    async fn consume_api_call_quota(&self, cert_id: Query<Option<String>>, ak: Query<Option<String>>, _request: &Request) -> TardisApiResult<IamOpenQuotaResp> {
+        let funs = iam_constants::get_tardis_inst();
+        let global_ctx = TardisContext {
+            own_paths: "".to_string(),
+            ..Default::default()
+        };
+        let result = IamOpenServ::consume_api_call_quota(cert_id.0, ak.0, &funs, &global_ctx).await?;
+        TardisResp::ok(result)
+    }
+
     /// Refresh cumulative number of api calls
     /// 刷新API累计调用数 (定时任务)
     #[oai(path = "/refresh_cert_cumulative_count", method = "post")]
@@ -0,0 +1,436 @@
+use bios_basic::helper::request_helper::try_set_real_ip_from_req_to_ctx;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::Value;
+use tardis::web::poem::Request;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::{Path, Query};
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::poem_openapi::types::ToJSON;
+use tardis::TardisFunsInst;
+
+use crate::basic::dto::iam_scim_dto::{
+    IamScimBulkResp, IamScimErrorResp, IamScimGroup, IamScimListResp, IamScimMeta, IamScimResourceTypeResp, IamScimSchemaResp, IamScimServiceProviderConfigResp, IamScimUser,
+};
+use crate::basic::serv::iam_scim_serv::IamScimServ;
+use crate::iam_config::IamConfig;
+use crate::iam_constants;
+
+#[derive(Clone, Default)]
+pub struct IamCiScimApi;
+
+/// Response of the SCIM endpoints, the errors are returned in the form defined by RFC 7644 instead of the tardis response
+/// SCIM端点的响应，错误以RFC 7644定义的格式而非tardis响应返回
+#[derive(poem_openapi::ApiResponse)]
+pub enum IamScimApiResp<T: ToJSON> {
+    #[oai(status = 200)]
+    Ok(Json<T>, #[oai(header = "ETag")] Option<String>),
+    #[oai(status = 201)]
+    Created(Json<T>, #[oai(header = "ETag")] Option<String>, #[oai(header = "Location")] String),
+    #[oai(status = 204)]
+    NoContent,
+    #[oai(status = 304)]
+    NotModified,
+    #[oai(status = 400)]
+    BadRequest(Json<IamScimErrorResp>),
+    #[oai(status = 401)]
+    Unauthorized(Json<IamScimErrorResp>, #[oai(header = "WWW-Authenticate")] String),
+    #[oai(status = 404)]
+    NotFound(Json<IamScimErrorResp>),
+    #[oai(status = 409)]
+    Conflict(Json<IamScimErrorResp>),
+    #[oai(status = 412)]
+    PreconditionFailed(Json<IamScimErrorResp>),
+    #[oai(status = 413)]
+    PayloadTooLarge(Json<IamScimErrorResp>),
+    #[oai(status = 500)]
+    InternalError(Json<IamScimErrorResp>),
+}
+
+impl<T: ToJSON> IamScimApiResp<T> {
+    fn ok(resp: T, meta: &Option<IamScimMeta>) -> Self {
+        IamScimApiResp::Ok(Json(resp), meta.as_ref().and_then(|meta| meta.version.clone()))
+    }
+
+    /// Return ``304`` if the version matches the ``If-None-Match`` header
+    fn ok_or_not_modified(resp: T, meta: &Option<IamScimMeta>, request: &Request) -> Self {
+        let version = meta.as_ref().and_then(|meta| meta.version.as_deref());
+        match (header(request, "If-None-Match"), version) {
+            (Some(if_none_match), Some(version)) if if_none_match.split(',').any(|expected| expected.trim() == version) => IamScimApiResp::NotModified,
+            _ => Self::ok(resp, meta),
+        }
+    }
+
+    fn created(resp: T, meta: &Option<IamScimMeta>) -> Self {
+        let location = meta.as_ref().map(|meta| meta.location.clone()).unwrap_or_default();
+        IamScimApiResp::Created(Json(resp), meta.as_ref().and_then(|meta| meta.version.clone()), location)
+    }
+}
+
+impl<T: ToJSON> From<TardisError> for IamScimApiResp<T> {
+    fn from(error: TardisError) -> Self {
+        let error_resp = IamScimServ::error_resp(&error);
+        match error_resp.status.as_str() {
+            "400" => IamScimApiResp::BadRequest(Json(error_resp)),
+            "401" | "403" => IamScimApiResp::Unauthorized(Json(error_resp), "Bearer realm=\"scim\"".to_string()),
+            "404" => IamScimApiResp::NotFound(Json(error_resp)),
+            "409" => IamScimApiResp::Conflict(Json(error_resp)),
+            "412" => IamScimApiResp::PreconditionFailed(Json(error_resp)),
+            "413" => IamScimApiResp::PayloadTooLarge(Json(error_resp)),
+            _ => IamScimApiResp::InternalError(Json(error_resp)),
+        }
+    }
+}
+
+/// Interface Console SCIM 2.0 API
+/// 接口控制台SCIM 2.0 API
+///
+/// Provision the accounts (Users) and the org nodes (Groups) of the tenant by the external identity providers (RFC 7643/7644),
+/// the requests are authenticated by the aksk of the tenant rather than the gateway, so the gateway needs to let ``/ci/scim/v2`` through.
+/// 供外部身份源供应租户的账号（Users）及组织节点（Groups）（RFC 7643/7644），
+/// 请求使用租户的aksk而非网关认证，故网关需放行``/ci/scim/v2``。
+#[poem_openapi::OpenApi(prefix_path = "/ci/scim/v2", tag = "bios_basic::ApiTag::Interface")]
+impl IamCiScimApi {
+    /// Find Users
+    /// 查找用户
+    #[oai(path = "/Users", method = "get")]
+    async fn find_users(
+        &self,
+        filter: Query<Option<String>>,
+        #[oai(name = "startIndex")] start_index: Query<Option<usize>>,
+        count: Query<Option<usize>>,
+        request: &Request,
+    ) -> IamScimApiResp<IamScimListResp<IamScimUser>> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            IamScimServ::find_users(filter.0.as_deref(), start_index.0, count.0, &funs, &ctx).await
+        }
+        .await;
+        match result {
+            Ok(resp) => IamScimApiResp::ok(resp, &None),
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Add User
+    /// 添加用户
+    #[oai(path = "/Users", method = "post")]
+    async fn add_user(&self, add_req: Json<Value>, request: &Request) -> IamScimApiResp<IamScimUser> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            funs.begin().await?;
+            let user = IamScimServ::add_user(&IamScimServ::parse_resource(add_req.0, &funs)?, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(user)
+        }
+        .await;
+        match result {
+            Ok(user) => {
+                let meta = user.meta.clone();
+                IamScimApiResp::created(user, &meta)
+            }
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Get User
+    /// 获取用户
+    #[oai(path = "/Users/:id", method = "get")]
+    async fn get_user(&self, id: Path<String>, request: &Request) -> IamScimApiResp<IamScimUser> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            IamScimServ::get_user(&id.0, &funs, &ctx).await
+        }
+        .await;
+        match result {
+            Ok(user) => {
+                let meta = user.meta.clone();
+                IamScimApiResp::ok_or_not_modified(user, &meta, request)
+            }
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Replace User
+    /// 替换用户
+    #[oai(path = "/Users/:id", method = "put")]
+    async fn replace_user(&self, id: Path<String>, modify_req: Json<Value>, request: &Request) -> IamScimApiResp<IamScimUser> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            funs.begin().await?;
+            let user = IamScimServ::replace_user(&id.0, &IamScimServ::parse_resource(modify_req.0, &funs)?, header(request, "If-Match"), &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(user)
+        }
+        .await;
+        match result {
+            Ok(user) => {
+                let meta = user.meta.clone();
+                IamScimApiResp::ok(user, &meta)
+            }
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Patch User
+    /// 修改用户
+    #[oai(path = "/Users/:id", method = "patch")]
+    async fn patch_user(&self, id: Path<String>, patch_req: Json<Value>, request: &Request) -> IamScimApiResp<IamScimUser> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            funs.begin().await?;
+            let user = IamScimServ::patch_user(&id.0, &IamScimServ::parse_resource(patch_req.0, &funs)?, header(request, "If-Match"), &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(user)
+        }
+        .await;
+        match result {
+            Ok(user) => {
+                let meta = user.meta.clone();
+                IamScimApiResp::ok(user, &meta)
+            }
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Delete User
+    /// 删除用户
+    #[oai(path = "/Users/:id", method = "delete")]
+    async fn delete_user(&self, id: Path<String>, request: &Request) -> IamScimApiResp<IamScimUser> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            funs.begin().await?;
+            IamScimServ::delete_user(&id.0, header(request, "If-Match"), &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await
+        }
+        .await;
+        match result {
+            Ok(_) => IamScimApiResp::NoContent,
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Find Groups
+    /// 查找组
+    #[oai(path = "/Groups", method = "get")]
+    async fn find_groups(
+        &self,
+        filter: Query<Option<String>>,
+        #[oai(name = "startIndex")] start_index: Query<Option<usize>>,
+        count: Query<Option<usize>>,
+        request: &Request,
+    ) -> IamScimApiResp<IamScimListResp<IamScimGroup>> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            IamScimServ::find_groups(filter.0.as_deref(), start_index.0, count.0, &funs, &ctx).await
+        }
+        .await;
+        match result {
+            Ok(resp) => IamScimApiResp::ok(resp, &None),
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Add Group
+    /// 添加组
+    #[oai(path = "/Groups", method = "post")]
+    async fn add_group(&self, add_req: Json<Value>, request: &Request) -> IamScimApiResp<IamScimGroup> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            funs.begin().await?;
+            let group = IamScimServ::add_group(&IamScimServ::parse_resource(add_req.0, &funs)?, &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(group)
+        }
+        .await;
+        match result {
+            Ok(group) => {
+                let meta = group.meta.clone();
+                IamScimApiResp::created(group, &meta)
+            }
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Get Group
+    /// 获取组
+    #[oai(path = "/Groups/:id", method = "get")]
+    async fn get_group(&self, id: Path<String>, request: &Request) -> IamScimApiResp<IamScimGroup> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            IamScimServ::get_group(&id.0, &funs, &ctx).await
+        }
+        .await;
+        match result {
+            Ok(group) => {
+                let meta = group.meta.clone();
+                IamScimApiResp::ok_or_not_modified(group, &meta, request)
+            }
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Replace Group
+    /// 替换组
+    #[oai(path = "/Groups/:id", method = "put")]
+    async fn replace_group(&self, id: Path<String>, modify_req: Json<Value>, request: &Request) -> IamScimApiResp<IamScimGroup> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            funs.begin().await?;
+            let group = IamScimServ::replace_group(&id.0, &IamScimServ::parse_resource(modify_req.0, &funs)?, header(request, "If-Match"), &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(group)
+        }
+        .await;
+        match result {
+            Ok(group) => {
+                let meta = group.meta.clone();
+                IamScimApiResp::ok(group, &meta)
+            }
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Patch Group
+    /// 修改组
+    #[oai(path = "/Groups/:id", method = "patch")]
+    async fn patch_group(&self, id: Path<String>, patch_req: Json<Value>, request: &Request) -> IamScimApiResp<IamScimGroup> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            funs.begin().await?;
+            let group = IamScimServ::patch_group(&id.0, &IamScimServ::parse_resource(patch_req.0, &funs)?, header(request, "If-Match"), &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await?;
+            Ok::<_, TardisError>(group)
+        }
+        .await;
+        match result {
+            Ok(group) => {
+                let meta = group.meta.clone();
+                IamScimApiResp::ok(group, &meta)
+            }
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Delete Group
+    /// 删除组
+    #[oai(path = "/Groups/:id", method = "delete")]
+    async fn delete_group(&self, id: Path<String>, request: &Request) -> IamScimApiResp<IamScimGroup> {
+        let mut funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            funs.begin().await?;
+            IamScimServ::delete_group(&id.0, header(request, "If-Match"), &funs, &ctx).await?;
+            funs.commit().await?;
+            ctx.execute_task().await
+        }
+        .await;
+        match result {
+            Ok(_) => IamScimApiResp::NoContent,
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Bulk Operations, each operation is committed independently
+    /// 批量操作，每个操作独立提交
+    #[oai(path = "/Bulk", method = "post")]
+    async fn bulk(&self, bulk_req: Json<Value>, request: &Request) -> IamScimApiResp<IamScimBulkResp> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            let max_payload_size = funs.conf::<IamConfig>().scim.bulk_max_payload_size;
+            if header(request, "Content-Length").and_then(|size| size.parse::<usize>().ok()).is_some_and(|size| size > max_payload_size) {
+                return Err(TardisError::custom(
+                    "413",
+                    &format!("the payload exceeds the max payload size {max_payload_size}"),
+                    "413-iam-scim-bulk-too-large",
+                ));
+            }
+            let resp = IamScimServ::bulk(&IamScimServ::parse_resource(bulk_req.0, &funs)?, &funs, &ctx).await?;
+            ctx.execute_task().await?;
+            Ok(resp)
+        }
+        .await;
+        match result {
+            Ok(resp) => IamScimApiResp::ok(resp, &None),
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Get Service Provider Config
+    /// 获取服务提供方配置
+    #[oai(path = "/ServiceProviderConfig", method = "get")]
+    async fn get_service_provider_config(&self) -> Json<IamScimServiceProviderConfigResp> {
+        let funs = iam_constants::get_tardis_inst();
+        Json(IamScimServ::get_service_provider_config(&funs))
+    }
+
+    /// Find Resource Types
+    /// 查找资源类型
+    #[oai(path = "/ResourceTypes", method = "get")]
+    async fn find_resource_types(&self) -> Json<IamScimListResp<IamScimResourceTypeResp>> {
+        let funs = iam_constants::get_tardis_inst();
+        Json(IamScimServ::find_resource_types(&funs))
+    }
+
+    /// Find Schemas, the user extension schema contains the account attrs of the tenant
+    /// 查找模式，用户扩展模式包含租户的账号扩展属性
+    #[oai(path = "/Schemas", method = "get")]
+    async fn find_schemas(&self, request: &Request) -> IamScimApiResp<IamScimListResp<IamScimSchemaResp>> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            IamScimServ::find_schemas(&funs, &ctx).await
+        }
+        .await;
+        match result {
+            Ok(resp) => IamScimApiResp::ok(resp, &None),
+            Err(error) => error.into(),
+        }
+    }
+
+    /// Get Schema
+    /// 获取模式
+    #[oai(path = "/Schemas/:id", method = "get")]
+    async fn get_schema(&self, id: Path<String>, request: &Request) -> IamScimApiResp<IamScimSchemaResp> {
+        let funs = iam_constants::get_tardis_inst();
+        let result = async {
+            let ctx = scim_ctx(request, &funs).await?;
+            IamScimServ::get_schema(&id.0, &funs, &ctx).await
+        }
+        .await;
+        match result {
+            Ok(resp) => IamScimApiResp::ok(resp, &None),
+            Err(error) => error.into(),
+        }
+    }
+}
+
+async fn scim_ctx(request: &Request, funs: &TardisFunsInst) -> TardisResult<TardisContext> {
+    let ctx = IamScimServ::auth(header(request, "Authorization"), funs).await?;
+    try_set_real_ip_from_req_to_ctx(request, &ctx).await?;
+    Ok(ctx)
+}
+
+fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request.headers().get(name).and_then(|value| value.to_str().ok())
+}
@@ -1,49 +1,33 @@
 use crate::basic::dto::iam_cert_dto::IamOauth2AkSkResp;
-use crate::basic::serv::iam_cert_serv::IamCertServ;
-use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
-use crate::iam_enumeration::{IamCertKernelKind, IamCertTokenKind, Oauth2GrantType};
-use bios_basic::rbum::rbum_enumeration::RbumCertRelKind;
+use crate::basic::dto::iam_oauth2_provider_dto::IamOAuth2TokenReq;
+use crate::basic::serv::iam_oauth2_provider_serv::IamOAuth2ProviderServ;
+use crate::iam_enumeration::Oauth2GrantType;
 use tardis::basic::result::TardisResult;
-use tardis::{TardisFuns, TardisFunsInst};
+use tardis::TardisFunsInst;
 
 pub struct IamCiOauth2AkSkServ;
 
 impl IamCiOauth2AkSkServ {
-    pub async fn generate_token(
-        grant_type: Oauth2GrantType,
-        client_id: &str,
-        client_secret: &str,
-        _scope: Option<String>,
-        funs: TardisFunsInst,
-    ) -> TardisResult<IamOauth2AkSkResp> {
-        let (_, _, rel_iam_item_id) = IamCertServ::validate_by_ak_and_sk(
-            client_id,
-            client_secret,
-            None,
-            Some(&RbumCertRelKind::Item),
-            false,
-            None,
-            Some(vec![&IamCertKernelKind::AkSk.to_string()]),
+    /// Compatible with the previous token api, see [IamOAuth2ProviderServ::token] for the standard one
+    pub async fn generate_token(grant_type: Oauth2GrantType, client_id: &str, client_secret: &str, scope: Option<String>, funs: TardisFunsInst) -> TardisResult<IamOauth2AkSkResp> {
+        let resp = IamOAuth2ProviderServ::token(
+            &IamOAuth2TokenReq {
+                grant_type: grant_type.to_string(),
+                client_id: Some(client_id.to_string()),
+                client_secret: Some(client_secret.to_string()),
+                scope,
+                ..Default::default()
+            },
             None,
             &funs,
         )
         .await?;
-        match grant_type {
-            Oauth2GrantType::AuthorizationCode => {}
-            Oauth2GrantType::Password => {}
-            Oauth2GrantType::ClientCredentials => {}
-        }
-
-        let access_token = TardisFuns::crypto.key.generate_token()?;
-        let refresh_token = TardisFuns::crypto.key.generate_token()?;
-        let expire_sec = 30 * 24 * 60 * 60;
-        IamIdentCacheServ::add_token(&access_token.clone(), &IamCertTokenKind::TokenOauth2, &rel_iam_item_id, None, expire_sec, 1, &funs).await?;
         Ok(IamOauth2AkSkResp {
-            access_token,
-            token_type: "Bearer".to_string(),
-            expires_in: expire_sec.to_string(),
-            refresh_token,
-            scope: "".to_string(),
+            access_token: resp.access_token,
+            token_type: resp.token_type,
+            expires_in: resp.expires_in.to_string(),
+            refresh_token: resp.refresh_token.unwrap_or_default(),
+            scope: resp.scope,
         })
     }
 }
@@ -1,5 +1,6 @@
 pub mod iam_cp_account_api;
 pub mod iam_cp_app_api;
 pub mod iam_cp_cert_api;
+pub mod iam_cp_oauth2_api;
 pub mod iam_cp_role_api;
 pub mod iam_cp_tenant_api;
@@ -4,6 +4,7 @@ use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertFilterRe
 use bios_basic::rbum::helper::rbum_scope_helper::get_max_level_id_by_context;
 use tardis::basic::dto::TardisContext;
 use tardis::log;
+use tardis::serde_json::Value;
 use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem_openapi;
 use tardis::web::poem_openapi::param::Query;
@@ -13,24 +14,29 @@ use tardis::TardisFuns;
 
 use crate::basic::dto::iam_account_dto::{IamAccountInfoResp, IamAccountInfoWithUserPwdAkResp, IamCpUserPwdBindResp};
 use crate::basic::dto::iam_cert_dto::{
-    IamCertGenericValidateSkReq, IamCertMailVCodeActivateReq, IamCertMailVCodeAddReq, IamCertPhoneVCodeAddReq, IamCertPhoneVCodeBindReq, IamCertPwdNewReq, IamCertUserNameNewReq,
-    IamCertUserPwdModifyReq, IamCertUserPwdRestReq, IamContextFetchReq,
+    IamCertGenericValidateSkReq, IamCertMailVCodeActivateReq, IamCertMailVCodeAddReq, IamCertPhoneVCodeAddReq, IamCertPhoneVCodeBindReq, IamCertPwdNewReq, IamCertTotpBindResp,
+    IamCertTotpCodeReq, IamCertTotpEnrollResp, IamCertUserNameNewReq, IamCertUserPwdModifyReq, IamCertUserPwdRestReq, IamCertWebAuthnRegisterReq, IamCertWebAuthnResp,
+    IamCertWebAuthnValidateReq, IamContextFetchReq,
 };
 use crate::basic::serv::iam_account_serv::IamAccountServ;
 use crate::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
 use crate::basic::serv::iam_cert_phone_vcode_serv::IamCertPhoneVCodeServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
 use crate::basic::serv::iam_cert_token_serv::IamCertTokenServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
 use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
 use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
 use crate::basic::serv::iam_tenant_serv::IamTenantServ;
 use crate::console_passport::dto::iam_cp_cert_dto::{
-    IamCpExistMailVCodeReq, IamCpExistPhoneVCodeReq, IamCpLdapLoginReq, IamCpMailVCodeLoginGenVCodeReq, IamCpMailVCodeLoginReq, IamCpOAuth2LoginReq,
-    IamCpPhoneVCodeLoginGenVCodeReq, IamCpPhoneVCodeLoginSendVCodeReq, IamCpUserPwdBindWithLdapReq, IamCpUserPwdCheckReq, IamCpUserPwdLoginReq,
+    IamCpExistMailVCodeReq, IamCpExistPhoneVCodeReq, IamCpLdapLoginReq, IamCpMailVCodeLoginGenVCodeReq, IamCpMailVCodeLoginReq, IamCpMfaTotpLoginReq, IamCpMfaWebAuthnLoginReq,
+    IamCpMfaWebAuthnStartLoginReq, IamCpOAuth2LoginReq, IamCpPhoneVCodeLoginGenVCodeReq, IamCpPhoneVCodeLoginSendVCodeReq, IamCpUserPwdBindWithLdapReq, IamCpUserPwdCheckReq,
+    IamCpUserPwdLoginReq,
 };
 #[cfg(feature = "ldap_client")]
 use crate::console_passport::serv::iam_cp_cert_ldap_serv::IamCpCertLdapServ;
 use crate::console_passport::serv::iam_cp_cert_mail_vcode_serv::IamCpCertMailVCodeServ;
+use crate::console_passport::serv::iam_cp_cert_mfa_serv::IamCpCertMfaServ;
 use crate::console_passport::serv::iam_cp_cert_oauth2_serv::IamCpCertOAuth2Serv;
 use crate::console_passport::serv::iam_cp_cert_phone_vcode_serv::IamCpCertPhoneVCodeServ;
 use crate::console_passport::serv::iam_cp_cert_user_pwd_serv::IamCpCertUserPwdServ;
@@ -199,6 +205,18 @@ impl IamCpCertApi {
         TardisResp::ok(Void {})
     }
 
+    /// Get the authorize url of general oauth2, only the ``Oidc`` supplier is supported
+    /// 获取通用oauth2授权地址，仅支持 ``Oidc`` 供应商
+    ///
+    /// Redirect to the returned url, and then login with the ``code`` and ``state`` passed back to the ``redirect_uri``.
+    /// 跳转到返回的地址，之后使用回调到 ``redirect_uri`` 的 ``code`` 及 ``state`` 登录。
+    #[oai(path = "/login/oauth2/:supplier/authorize", method = "get")]
+    async fn get_oauth2_authorize_url(&self, supplier: Path<String>, tenant_id: Query<String>, redirect_uri: Query<String>) -> TardisApiResult<String> {
+        let funs = iam_constants::get_tardis_inst();
+        let url = IamCpCertOAuth2Serv::get_authorize_url(IamCertOAuth2Supplier::parse(&supplier.0)?, &tenant_id.0, &redirect_uri.0, &funs).await?;
+        TardisResp::ok(url)
+    }
+
     /// Login by general oauth2
     /// 通用oauth2登录
     #[oai(path = "/login/oauth2/:supplier", method = "put")]
@@ -239,6 +257,168 @@ impl IamCpCertApi {
         TardisResp::ok(Void {})
     }
 
+    /// Enroll TOTP By Current Account
+    /// 当前账号申请动态口令
+    ///
+    /// Scan the returned ``provisioning_uri`` by the authenticator app, and then bind it with the generated one-time password.
+    /// 使用身份验证器扫描返回的 ``provisioning_uri`` ，之后使用生成的一次性密码绑定。
+    #[oai(path = "/cert/totp/enroll", method = "put")]
+    async fn enroll_totp(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<IamCertTotpEnrollResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCertTotpServ::enroll(&funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Bind TOTP By Current Account
+    /// 当前账号绑定动态口令
+    #[oai(path = "/cert/totp/bind", method = "put")]
+    async fn bind_totp(&self, req: Json<IamCertTotpCodeReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<IamCertTotpBindResp> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let resp = IamCertTotpServ::bind(req.0.code.as_ref(), &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Unbind TOTP By Current Account
+    /// 当前账号解绑动态口令
+    ///
+    /// The double auth is required.
+    /// 需要先进行二次认证。
+    #[oai(path = "/cert/totp", method = "delete")]
+    async fn delete_totp(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        IamCpCertMfaServ::delete_totp(&funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Start Registering WebAuthn Credential By Current Account
+    /// 当前账号开始注册安全密钥
+    ///
+    /// Return the options for ``navigator.credentials.create()``.
+    /// 返回 ``navigator.credentials.create()`` 的参数。
+    #[oai(path = "/cert/webauthn/register/start", method = "put")]
+    async fn start_register_webauthn(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Value> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCertWebAuthnServ::start_register(&funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Finish Registering WebAuthn Credential By Current Account
+    /// 当前账号完成注册安全密钥
+    #[oai(path = "/cert/webauthn/register/finish", method = "put")]
+    async fn finish_register_webauthn(&self, req: Json<IamCertWebAuthnRegisterReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<String> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let id = IamCertWebAuthnServ::finish_register(req.0.name.as_ref(), &req.0.credential, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(id)
+    }
+
+    /// Find WebAuthn Credentials By Current Account
+    /// 获取当前账号的安全密钥
+    #[oai(path = "/cert/webauthn", method = "get")]
+    async fn find_webauthn_certs(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Vec<IamCertWebAuthnResp>> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCertWebAuthnServ::find_certs_by_account(&ctx.0.owner, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Delete WebAuthn Credential By Current Account
+    /// 删除当前账号的安全密钥
+    ///
+    /// The double auth is required.
+    /// 需要先进行二次认证。
+    #[oai(path = "/cert/webauthn/:id", method = "delete")]
+    async fn delete_webauthn_cert(&self, id: Path<String>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        IamCpCertMfaServ::delete_webauthn_cert(&id.0, &funs, &ctx.0).await?;
+        funs.commit().await?;
+        ctx.0.execute_task().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Login by TOTP, the second step of the login which returns ``mfa_challenge``
+    /// 动态口令登录，用于返回 ``mfa_challenge`` 的登录的第二步
+    #[oai(path = "/login/mfa/totp", method = "put")]
+    async fn login_by_totp(&self, login_req: Json<IamCpMfaTotpLoginReq>, request: &Request) -> TardisApiResult<IamAccountInfoResp> {
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let resp = IamCpCertMfaServ::login_by_totp(&login_req.0, try_get_real_ip_from_req(request).await?, &funs).await?;
+        funs.commit().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Start Login by WebAuthn, the second step of the login which returns ``mfa_challenge``
+    /// 开始安全密钥登录，用于返回 ``mfa_challenge`` 的登录的第二步
+    ///
+    /// Return the options for ``navigator.credentials.get()``.
+    /// 返回 ``navigator.credentials.get()`` 的参数。
+    #[oai(path = "/login/mfa/webauthn/start", method = "put")]
+    async fn start_login_by_webauthn(&self, login_req: Json<IamCpMfaWebAuthnStartLoginReq>) -> TardisApiResult<Value> {
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCpCertMfaServ::start_login_by_webauthn(&login_req.0, &funs).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Login by WebAuthn, the second step of the login which returns ``mfa_challenge``
+    /// 安全密钥登录，用于返回 ``mfa_challenge`` 的登录的第二步
+    #[oai(path = "/login/mfa/webauthn", method = "put")]
+    async fn login_by_webauthn(&self, login_req: Json<IamCpMfaWebAuthnLoginReq>, request: &Request) -> TardisApiResult<IamAccountInfoResp> {
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        let resp = IamCpCertMfaServ::login_by_webauthn(&login_req.0, try_get_real_ip_from_req(request).await?, &funs).await?;
+        funs.commit().await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Validate TOTP By Current Account
+    /// 通过当前账号验证动态口令
+    #[oai(path = "/validate/totp", method = "put")]
+    async fn validate_by_totp(&self, req: Json<IamCertTotpCodeReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        IamCpCertMfaServ::validate_by_totp(req.0.code.as_ref(), &funs, &IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?).await?;
+        funs.commit().await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Start Validating WebAuthn By Current Account
+    /// 通过当前账号开始验证安全密钥
+    #[oai(path = "/validate/webauthn/start", method = "put")]
+    async fn start_validate_by_webauthn(&self, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Value> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let funs = iam_constants::get_tardis_inst();
+        let resp = IamCertWebAuthnServ::start_authenticate(&ctx.0.owner, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Validate WebAuthn By Current Account
+    /// 通过当前账号验证安全密钥
+    #[oai(path = "/validate/webauthn", method = "put")]
+    async fn validate_by_webauthn(&self, req: Json<IamCertWebAuthnValidateReq>, ctx: TardisContextExtractor, request: &Request) -> TardisApiResult<Void> {
+        try_set_real_ip_from_req_to_ctx(request, &ctx.0).await?;
+        let mut funs = iam_constants::get_tardis_inst();
+        funs.begin().await?;
+        IamCpCertMfaServ::validate_by_webauthn(&req.0.credential, &funs, &IamAccountServ::new_context_if_account_is_global(&ctx.0, &funs).await?).await?;
+        funs.commit().await?;
+        TardisResp::ok(Void {})
+    }
+
     // /// Add Mail-VCode Cert
     // /// Send Activation Mail
     // #[oai(path = "/cert/mailvcode/send", method = "put")]
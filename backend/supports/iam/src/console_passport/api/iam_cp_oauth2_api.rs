@@ -0,0 +1,181 @@
+use percent_encoding::percent_decode_str;
+use tardis::basic::result::TardisResult;
+use tardis::web::context_extractor::TardisContextExtractor;
+use tardis::web::poem::Request;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::Query;
+use tardis::web::poem_openapi::payload::{Form, Json};
+use tardis::web::poem_openapi::types::ToJSON;
+use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
+use tardis::TardisFuns;
+
+use crate::basic::dto::iam_oauth2_provider_dto::{
+    IamOAuth2AuthorizeReq, IamOAuth2ErrorResp, IamOAuth2IntrospectResp, IamOAuth2TokenOpReq, IamOAuth2TokenReq, IamOAuth2TokenResp, IamOidcDiscoveryResp, IamOidcJwksResp,
+    IamOidcUserInfoResp,
+};
+use crate::basic::serv::iam_oauth2_provider_serv::IamOAuth2ProviderServ;
+use crate::iam_constants;
+use bios_basic::helper::request_helper::try_get_real_ip_from_req;
+
+#[derive(Clone, Default)]
+pub struct IamCpOAuth2Api;
+#[derive(Clone, Default)]
+pub struct IamCpOidcDiscoveryApi;
+
+/// Response of the OAuth2 endpoints, the errors are returned in the form defined by RFC 6749 instead of the tardis response
+/// OAuth2端点的响应，错误以RFC 6749定义的格式而非tardis响应返回
+#[derive(poem_openapi::ApiResponse)]
+pub enum IamOAuth2ApiResp<T: ToJSON> {
+    #[oai(status = 200)]
+    Ok(Json<T>, #[oai(header = "Cache-Control")] String),
+    #[oai(status = 400)]
+    BadRequest(Json<IamOAuth2ErrorResp>),
+    #[oai(status = 401)]
+    Unauthorized(Json<IamOAuth2ErrorResp>, #[oai(header = "WWW-Authenticate")] String),
+    #[oai(status = 500)]
+    InternalError(Json<IamOAuth2ErrorResp>),
+}
+
+impl<T: ToJSON> From<TardisResult<T>> for IamOAuth2ApiResp<T> {
+    fn from(result: TardisResult<T>) -> Self {
+        match result {
+            Ok(resp) => IamOAuth2ApiResp::Ok(Json(resp), "no-store".to_string()),
+            Err(error) => {
+                let error_resp = IamOAuth2ProviderServ::error_resp(&error);
+                if error.code.starts_with("401") {
+                    let challenge = format!("Bearer error=\"{}\"", error_resp.error);
+                    IamOAuth2ApiResp::Unauthorized(Json(error_resp), challenge)
+                } else if error.code.starts_with('5') {
+                    IamOAuth2ApiResp::InternalError(Json(error_resp))
+                } else {
+                    IamOAuth2ApiResp::BadRequest(Json(error_resp))
+                }
+            }
+        }
+    }
+}
+
+/// Passport Console OAuth2 Provider API
+/// 通行证控制台OAuth2提供方API
+///
+/// IAM as the OAuth2 authorization server and OpenID provider of the internal apps,
+/// the clients are the aksk certs with the OAuth2 client configuration.
+/// IAM作为内部应用的OAuth2授权服务及OpenID提供方，客户端为配置了OAuth2客户端信息的aksk证书。
+#[poem_openapi::OpenApi(prefix_path = "/cp/oauth2", tag = "bios_basic::ApiTag::Passport")]
+impl IamCpOAuth2Api {
+    /// Authorize by the logged-in account, return the uri (with the code and state) to redirect to
+    /// 当前登录账号授权，返回需跳转的地址（包含code及state）
+    #[oai(path = "/authorize", method = "get")]
+    #[allow(clippy::too_many_arguments)]
+    async fn authorize(
+        &self,
+        response_type: Query<String>,
+        client_id: Query<String>,
+        redirect_uri: Query<Option<String>>,
+        scope: Query<Option<String>>,
+        state: Query<Option<String>>,
+        code_challenge: Query<Option<String>>,
+        code_challenge_method: Query<Option<String>>,
+        nonce: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<String> {
+        let funs = iam_constants::get_tardis_inst();
+        let redirect_uri = IamOAuth2ProviderServ::authorize(
+            &IamOAuth2AuthorizeReq {
+                response_type: response_type.0,
+                client_id: client_id.0,
+                redirect_uri: redirect_uri.0,
+                scope: scope.0,
+                state: state.0,
+                code_challenge: code_challenge.0,
+                code_challenge_method: code_challenge_method.0,
+                nonce: nonce.0,
+            },
+            &funs,
+            &ctx.0,
+        )
+        .await?;
+        TardisResp::ok(redirect_uri)
+    }
+
+    /// Token endpoint, the client can be authenticated by the HTTP Basic authorization or the form
+    /// 令牌端点，客户端可通过HTTP Basic认证或表单认证
+    #[oai(path = "/token", method = "post")]
+    async fn token(&self, mut token_req: Form<IamOAuth2TokenReq>, request: &Request) -> IamOAuth2ApiResp<IamOAuth2TokenResp> {
+        let funs = iam_constants::get_tardis_inst();
+        if let Some((client_id, client_secret)) = basic_auth(request) {
+            token_req.0.client_id = Some(client_id);
+            token_req.0.client_secret = Some(client_secret);
+        }
+        let ip = match try_get_real_ip_from_req(request).await {
+            Ok(ip) => ip,
+            Err(error) => return Err(error).into(),
+        };
+        IamOAuth2ProviderServ::token(&token_req.0, ip, &funs).await.into()
+    }
+
+    /// Get the claims of the account by the access token
+    /// 通过访问令牌获取账号信息
+    #[oai(path = "/userinfo", method = "get")]
+    async fn userinfo(&self, request: &Request) -> IamOAuth2ApiResp<IamOidcUserInfoResp> {
+        let funs = iam_constants::get_tardis_inst();
+        let access_token = request.headers().get("Authorization").and_then(|value| value.to_str().ok()).and_then(|value| value.strip_prefix("Bearer ")).unwrap_or_default();
+        IamOAuth2ProviderServ::userinfo(access_token.trim(), &funs).await.into()
+    }
+
+    /// Introspect the token (RFC 7662)
+    /// 令牌内省（RFC 7662）
+    #[oai(path = "/introspect", method = "post")]
+    async fn introspect(&self, mut op_req: Form<IamOAuth2TokenOpReq>, request: &Request) -> IamOAuth2ApiResp<IamOAuth2IntrospectResp> {
+        let funs = iam_constants::get_tardis_inst();
+        if let Some((client_id, client_secret)) = basic_auth(request) {
+            op_req.0.client_id = Some(client_id);
+            op_req.0.client_secret = Some(client_secret);
+        }
+        IamOAuth2ProviderServ::introspect(&op_req.0, &funs).await.into()
+    }
+
+    /// Revoke the token (RFC 7009)
+    /// 撤销令牌（RFC 7009）
+    #[oai(path = "/revoke", method = "post")]
+    async fn revoke(&self, mut op_req: Form<IamOAuth2TokenOpReq>, request: &Request) -> IamOAuth2ApiResp<Void> {
+        let funs = iam_constants::get_tardis_inst();
+        if let Some((client_id, client_secret)) = basic_auth(request) {
+            op_req.0.client_id = Some(client_id);
+            op_req.0.client_secret = Some(client_secret);
+        }
+        IamOAuth2ProviderServ::revoke(&op_req.0, &funs).await.map(|_| Void {}).into()
+    }
+
+    /// Get the public keys to verify the id_token
+    /// 获取验证id_token的公钥
+    #[oai(path = "/jwks", method = "get")]
+    async fn jwks(&self) -> TardisApiResult<IamOidcJwksResp> {
+        let funs = iam_constants::get_tardis_inst();
+        TardisResp::ok(IamOAuth2ProviderServ::jwks(&funs).await?)
+    }
+}
+
+/// Passport Console OpenID Discovery API
+/// 通行证控制台OpenID发现API
+#[poem_openapi::OpenApi(prefix_path = "/.well-known", tag = "bios_basic::ApiTag::Passport")]
+impl IamCpOidcDiscoveryApi {
+    /// Get the OpenID provider metadata
+    /// 获取OpenID提供方元数据
+    #[oai(path = "/openid-configuration", method = "get")]
+    async fn discovery(&self) -> Json<IamOidcDiscoveryResp> {
+        let funs = iam_constants::get_tardis_inst();
+        Json(IamOAuth2ProviderServ::discovery(&funs))
+    }
+}
+
+/// Client id and secret of the HTTP Basic authorization, they are form urlencoded as RFC 6749 required
+fn basic_auth(request: &Request) -> Option<(String, String)> {
+    let credentials = request.headers().get("Authorization")?.to_str().ok()?.strip_prefix("Basic ")?;
+    let credentials = TardisFuns::crypto.base64.decode_to_string(credentials.trim()).ok()?;
+    let (client_id, client_secret) = credentials.split_once(':')?;
+    Some((
+        percent_decode_str(client_id).decode_utf8().ok()?.to_string(),
+        percent_decode_str(client_secret).decode_utf8().ok()?.to_string(),
+    ))
+}
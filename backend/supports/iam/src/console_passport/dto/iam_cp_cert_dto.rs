@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use tardis::basic::field::TrimString;
+use tardis::serde_json::Value;
 use tardis::web::poem_openapi;
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -76,6 +77,8 @@ pub struct IamCpOAuth2LoginReq {
     pub code: TrimString,
     #[oai(validator(min_length = "2", max_length = "255"))]
     pub tenant_id: String,
+    /// Returned by the authorization of the ``Oidc`` supplier / ``Oidc`` 供应商授权时返回
+    pub state: Option<String>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -115,3 +118,31 @@ pub struct IamCpUserPwdBindReq {
     #[oai(validator(min_length = "2", max_length = "255"))]
     pub sk: TrimString,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCpMfaTotpLoginReq {
+    /// Ticket of the login challenge
+    /// 登录质询的票据
+    pub ticket: String,
+    /// One-time password or recovery code
+    /// 一次性密码或恢复码
+    #[oai(validator(min_length = "6", max_length = "255"))]
+    pub code: TrimString,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCpMfaWebAuthnStartLoginReq {
+    /// Ticket of the login challenge
+    /// 登录质询的票据
+    pub ticket: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct IamCpMfaWebAuthnLoginReq {
+    /// Ticket of the login challenge
+    /// 登录质询的票据
+    pub ticket: String,
+    /// ``PublicKeyCredential`` returned by ``navigator.credentials.get()``
+    /// ``navigator.credentials.get()`` 返回的 ``PublicKeyCredential``
+    pub credential: Value,
+}
@@ -2,6 +2,7 @@ pub mod iam_cp_account_serv;
 #[cfg(feature = "ldap_client")]
 pub mod iam_cp_cert_ldap_serv;
 pub mod iam_cp_cert_mail_vcode_serv;
+pub mod iam_cp_cert_mfa_serv;
 pub mod iam_cp_cert_oauth2_serv;
 pub mod iam_cp_cert_phone_vcode_serv;
 pub mod iam_cp_cert_user_pwd_serv;
@@ -50,6 +50,7 @@ impl IamCpCertLdapServ {
                 roles: HashMap::new(),
                 groups: HashMap::new(),
                 apps: vec![],
+                mfa_challenge: None,
             };
             IamAccountInfoWithUserPwdAkResp {
                 iam_account_info_resp,
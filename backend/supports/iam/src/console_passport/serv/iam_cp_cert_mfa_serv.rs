@@ -0,0 +1,80 @@
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::Value;
+use tardis::TardisFunsInst;
+
+use crate::basic::dto::iam_account_dto::IamAccountInfoResp;
+use crate::basic::serv::iam_cert_mfa_serv::{IamCertMfaServ, IamCertMfaTicket};
+use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use crate::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
+use crate::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
+use crate::console_passport::dto::iam_cp_cert_dto::{IamCpMfaTotpLoginReq, IamCpMfaWebAuthnLoginReq, IamCpMfaWebAuthnStartLoginReq};
+use crate::iam_enumeration::IamCertKernelKind;
+
+pub struct IamCpCertMfaServ;
+
+impl IamCpCertMfaServ {
+    pub async fn login_by_totp(login_req: &IamCpMfaTotpLoginReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamAccountInfoResp> {
+        let ticket = IamCertMfaServ::get_ticket(&login_req.ticket, &IamCertKernelKind::Totp, funs).await?;
+        Self::start_login_verification(&login_req.ticket, &ticket, funs).await?;
+        IamCertTotpServ::validate(&ticket.account_id, login_req.code.as_ref(), funs, &ticket.mock_ctx()).await?;
+        Self::finish_login(&login_req.ticket, ticket, ip, funs).await
+    }
+
+    pub async fn start_login_by_webauthn(login_req: &IamCpMfaWebAuthnStartLoginReq, funs: &TardisFunsInst) -> TardisResult<Value> {
+        let ticket = IamCertMfaServ::get_ticket(&login_req.ticket, &IamCertKernelKind::WebAuthn, funs).await?;
+        IamCertWebAuthnServ::start_authenticate(&ticket.account_id, funs, &ticket.mock_ctx()).await
+    }
+
+    pub async fn login_by_webauthn(login_req: &IamCpMfaWebAuthnLoginReq, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamAccountInfoResp> {
+        let ticket = IamCertMfaServ::get_ticket(&login_req.ticket, &IamCertKernelKind::WebAuthn, funs).await?;
+        Self::start_login_verification(&login_req.ticket, &ticket, funs).await?;
+        IamCertWebAuthnServ::finish_authenticate(&ticket.account_id, &login_req.credential, funs, &ticket.mock_ctx()).await?;
+        Self::finish_login(&login_req.ticket, ticket, ip, funs).await
+    }
+
+    /// Validate the totp of the current account, it's used for the resources which need double auth
+    pub async fn validate_by_totp(code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        // the one-time password is short, so the failed verifications are limited like the login
+        IamCertMfaServ::start_verification(&ctx.owner, funs).await?;
+        IamCertTotpServ::validate(&ctx.owner, code, funs, ctx).await?;
+        IamCertMfaServ::finish_verification(&ctx.owner, funs).await?;
+        IamIdentCacheServ::add_double_auth(&ctx.owner, funs).await
+    }
+
+    /// Validate the webauthn credential of the current account, it's used for the resources which need double auth
+    pub async fn validate_by_webauthn(credential: &Value, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        IamCertMfaServ::start_verification(&ctx.owner, funs).await?;
+        IamCertWebAuthnServ::finish_authenticate(&ctx.owner, credential, funs, ctx).await?;
+        IamCertMfaServ::finish_verification(&ctx.owner, funs).await?;
+        IamIdentCacheServ::add_double_auth(&ctx.owner, funs).await
+    }
+
+    /// Unbind the totp of the current account, the double auth is required so that a leaked token can't remove the second factor
+    pub async fn delete_totp(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        IamCertMfaServ::check_double_auth(&ctx.owner, funs).await?;
+        IamCertTotpServ::delete_cert(&ctx.owner, funs, ctx).await
+    }
+
+    /// Delete the webauthn credential of the current account, the double auth is required like [Self::delete_totp]
+    pub async fn delete_webauthn_cert(id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        IamCertMfaServ::check_double_auth(&ctx.owner, funs).await?;
+        IamCertWebAuthnServ::delete_cert(id, &ctx.owner, funs, ctx).await
+    }
+
+    /// The login ticket is invalidated once the verifications of the account are locked
+    async fn start_login_verification(ticket: &str, ticket_info: &IamCertMfaTicket, funs: &TardisFunsInst) -> TardisResult<()> {
+        if let Err(e) = IamCertMfaServ::start_verification(&ticket_info.account_id, funs).await {
+            IamCertMfaServ::delete_ticket(ticket, funs).await?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn finish_login(ticket: &str, ticket_info: IamCertMfaTicket, ip: Option<String>, funs: &TardisFunsInst) -> TardisResult<IamAccountInfoResp> {
+        IamCertMfaServ::finish_verification(&ticket_info.account_id, funs).await?;
+        IamCertMfaServ::delete_ticket(ticket, funs).await?;
+        IamCertServ::package_tardis_context_and_resp_without_mfa(ticket_info.tenant_id, &ticket_info.account_id, ticket_info.token_kind, ticket_info.access_token, ip, funs).await
+    }
+}
@@ -44,6 +44,12 @@ pub struct IamConfig {
     pub cache_key_sync_ldap_status: String,
     pub cache_key_sync_ldap_task_lock: String,
     pub cache_key_gateway_rule_info_: String,
+    // state -> IamCertOidcLoginState
+    pub cache_key_oidc_login_state_: String,
+    pub cache_key_oidc_login_state_expire_sec: u64,
+    // issuer -> OpenID provider metadata
+    pub cache_key_oidc_metadata_: String,
+    pub cache_key_oidc_metadata_expire_sec: u64,
     pub mail_template_cert_activate_title: String,
     pub mail_template_cert_activate_content: String,
     pub mail_template_cert_login_title: String,
@@ -67,6 +73,9 @@ pub struct IamConfig {
     pub third_integration_schedule_code: String,
     pub init_menu_json_path: String,
     pub ldap: IamLdapConfig,
+    pub oauth2_provider: IamOAuth2ProviderConfig,
+    pub mfa: IamMfaConfig,
+    pub scim: IamScimConfig,
 
     pub spi: IamSpiConfig,
     pub iam_base_url: String,
@@ -82,7 +91,31 @@ pub struct IamLdapConfig {
     pub port: u16,
     pub dc: String,
     pub bind_dn: String,
+    /// Password of the administrator, it must be changed from the default value when ``modify_enabled`` is set
+    /// 管理员密码，设置 ``modify_enabled`` 时必须修改默认值
     pub bind_password: String,
+    /// LDAPS port, 0 means the LDAPS listener is disabled
+    /// LDAPS端口，0表示不启用LDAPS监听
+    pub tls_port: u16,
+    /// Certificate chain (PEM) of LDAPS and StartTLS, TLS is disabled when it's empty
+    /// LDAPS及StartTLS的证书链（PEM），为空时不启用TLS
+    pub tls_cert: String,
+    /// Private key (PEM) of LDAPS and StartTLS
+    /// LDAPS及StartTLS的私钥（PEM）
+    pub tls_key: String,
+    /// Reject the passwords sent by the plain connections
+    /// 拒绝通过明文连接发送的密码
+    pub tls_required: bool,
+    /// Whether to allow the Modify operation and the PasswordModify extended operation
+    /// 是否允许Modify操作及PasswordModify扩展操作
+    pub modify_enabled: bool,
+    /// Tenant whose passwords can be reset by the administrator without the original password,
+    /// empty means the administrator has to provide the original password as well
+    /// 管理员可不提供原密码重置密码的租户，为空表示管理员也须提供原密码
+    pub admin_reset_tenant_id: String,
+    /// Max page size of the paged results control
+    /// 分页结果控制的最大分页大小
+    pub max_page_size: usize,
 }
 
 impl Default for IamLdapConfig {
@@ -92,6 +125,139 @@ impl Default for IamLdapConfig {
             dc: "bios".to_string(),
             bind_dn: "CN=ldapadmin,DC=bios".to_string(),
             bind_password: "KDi234!ds".to_string(),
+            tls_port: 0,
+            tls_cert: "".to_string(),
+            tls_key: "".to_string(),
+            tls_required: false,
+            modify_enabled: false,
+            admin_reset_tenant_id: "".to_string(),
+            max_page_size: 1000,
+        }
+    }
+}
+
+/// OAuth2 authorization server and OpenID provider config
+/// OAuth2授权服务及OpenID提供方配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct IamOAuth2ProviderConfig {
+    /// Issuer identifier, the ``iam_base_url`` is used when empty
+    /// 签发者标识，为空时使用 ``iam_base_url``
+    pub issuer: String,
+    /// RSA private key (PEM) used to sign the id_token, required by the id_token and the JWKS
+    /// 用于签名id_token的RSA私钥（PEM），签发id_token及发布JWKS时必填
+    pub signing_key: String,
+    /// RSA keys (PEM, public or private) replaced by the ``signing_key``, still published in the JWKS during the key rotation
+    /// 已被 ``signing_key`` 替换的RSA密钥（PEM，公钥或私钥），密钥轮换期间仍发布在JWKS中
+    pub previous_signing_keys: Vec<String>,
+    pub code_expire_sec: u64,
+    pub access_token_expire_sec: i64,
+    pub refresh_token_expire_sec: u64,
+    pub id_token_expire_sec: i64,
+    /// Number of the coexisting access tokens of an account or a client, 0 means unlimited
+    /// 账号或客户端可同时存在的访问令牌数，0表示不限制
+    pub token_coexist_num: i16,
+    // code -> IamOAuth2Grant
+    pub cache_key_code_: String,
+    // access_token -> IamOAuth2Grant
+    pub cache_key_access_token_: String,
+    // refresh_token -> IamOAuth2Grant
+    pub cache_key_refresh_token_: String,
+}
+
+impl Default for IamOAuth2ProviderConfig {
+    fn default() -> Self {
+        IamOAuth2ProviderConfig {
+            issuer: "".to_string(),
+            signing_key: "".to_string(),
+            previous_signing_keys: vec![],
+            code_expire_sec: 300,
+            access_token_expire_sec: 2 * 60 * 60,
+            refresh_token_expire_sec: 30 * 24 * 60 * 60,
+            id_token_expire_sec: 60 * 60,
+            token_coexist_num: 10,
+            cache_key_code_: "iam:cache:oauth2:code:".to_string(),
+            cache_key_access_token_: "iam:cache:oauth2:access_token:".to_string(),
+            cache_key_refresh_token_: "iam:cache:oauth2:refresh_token:".to_string(),
+        }
+    }
+}
+
+/// Second factor (TOTP / WebAuthn) config
+/// 二次认证（TOTP / WebAuthn）配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct IamMfaConfig {
+    /// Issuer shown in the authenticator app
+    /// 身份验证器应用中显示的签发者
+    pub totp_issuer: String,
+    pub totp_recovery_code_num: usize,
+    /// Relying party id of WebAuthn, it should be the (registrable) domain of the frontend
+    /// WebAuthn依赖方id，应为前端的（可注册）域名
+    pub webauthn_rp_id: String,
+    /// Origin of the frontend, e.g. ``https://bios.example.com``
+    /// 前端的源，如 ``https://bios.example.com``
+    pub webauthn_rp_origin: String,
+    pub webauthn_rp_name: String,
+    /// Expiration of the enrollment / authentication state and the login ticket
+    /// 注册/认证状态及登录票据的有效期
+    pub state_expire_sec: u64,
+    /// Max failed second factor verifications (both the login and the double auth) of the account within ``state_expire_sec``,
+    /// the account's verifications are locked for ``verify_lock_sec`` after that
+    /// 账号在 ``state_expire_sec`` 秒内第二因素验证（含登录及二次认证）的最大失败次数，超过后锁定 ``verify_lock_sec`` 秒
+    pub ticket_err_times: u32,
+    pub verify_lock_sec: u64,
+    // account_id -> pending TOTP secret
+    pub cache_key_totp_enrollment_: String,
+    // account_id -> WebAuthn registration / authentication state
+    pub cache_key_webauthn_state_: String,
+    // ticket -> IamCertMfaTicket
+    pub cache_key_ticket_: String,
+    // account_id -> verification times
+    pub cache_key_verify_times_: String,
+}
+
+impl Default for IamMfaConfig {
+    fn default() -> Self {
+        IamMfaConfig {
+            totp_issuer: "BIOS".to_string(),
+            totp_recovery_code_num: 10,
+            webauthn_rp_id: "localhost".to_string(),
+            webauthn_rp_origin: "http://localhost:8080".to_string(),
+            webauthn_rp_name: "BIOS".to_string(),
+            state_expire_sec: 5 * 60,
+            ticket_err_times: 5,
+            verify_lock_sec: 30 * 60,
+            cache_key_totp_enrollment_: "iam:cache:mfa:totp:enrollment:".to_string(),
+            cache_key_webauthn_state_: "iam:cache:mfa:webauthn:state:".to_string(),
+            cache_key_ticket_: "iam:cache:mfa:ticket:".to_string(),
+            cache_key_verify_times_: "iam:cache:mfa:verify:times:".to_string(),
+        }
+    }
+}
+
+/// SCIM 2.0 provisioning config
+/// SCIM 2.0供应配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct IamScimConfig {
+    /// Max operations of a bulk request
+    /// 批量请求的最大操作数
+    pub bulk_max_operations: usize,
+    /// Max payload size (bytes) of a bulk request
+    /// 批量请求的最大负载（字节）
+    pub bulk_max_payload_size: usize,
+    /// Max resources returned by a query, also the limit of the resources evaluated by a filter
+    /// 查询返回的最大资源数，也是过滤器可计算的资源数上限
+    pub filter_max_results: usize,
+}
+
+impl Default for IamScimConfig {
+    fn default() -> Self {
+        IamScimConfig {
+            bulk_max_operations: 1000,
+            bulk_max_payload_size: 1024 * 1024,
+            filter_max_results: 200,
         }
     }
 }
@@ -165,6 +331,9 @@ impl Default for IamConfig {
             phone_template_cert_login_content: "IAM Service Phone Credentials Activation".to_string(),
             init_menu_json_path: "config/init-menu-default.json".to_string(),
             ldap: IamLdapConfig::default(),
+            oauth2_provider: IamOAuth2ProviderConfig::default(),
+            mfa: IamMfaConfig::default(),
+            scim: IamScimConfig::default(),
             cache_key_async_task_status: "iam:cache:task:status".to_string(),
             cache_key_sync_ldap_status: "iam:cache:sync:ldap:status".to_string(),
             cache_key_sync_ldap_task_lock: "iam:cache:sync:ldap:taskId".to_string(),
@@ -178,6 +347,10 @@ impl Default for IamConfig {
             strict_security_mode: false,
             crypto_conf: CryptoConf::default(),
             cache_key_gateway_rule_info_: "sg:plugin:".to_string(),
+            cache_key_oidc_login_state_: "iam:cache:oidc:state:".to_string(),
+            cache_key_oidc_login_state_expire_sec: 10 * 60,
+            cache_key_oidc_metadata_: "iam:cache:oidc:metadata:".to_string(),
+            cache_key_oidc_metadata_expire_sec: 60 * 60,
             gateway_openapi_path: "/op-api".to_string(),
             vcode_cd_in_sec: crate::iam_constants::DEFAULT_V_CODE_CD_IN_SEC,
         }
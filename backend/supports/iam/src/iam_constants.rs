@@ -46,12 +46,19 @@ pub const RBUM_SCOPE_LEVEL_APP: RbumScopeLevelKind = RbumScopeLevelKind::L2;
 
 pub const RBUM_CERT_CONF_TOKEN_EXPIRE_SEC: i64 = 60 * 60 * 24 * 7;
 pub const RBUM_CERT_CONF_TOKEN_DEFAULT_COEXIST_NUM: i16 = 5;
+/// Supplier of the aksk cert used as the SCIM provisioning credential of the tenant
+pub const RBUM_CERT_SUPPLIER_SCIM: &str = "scim";
 
 pub const EVENT_EXECUTE_TASK_EXTERNAL: &str = "iam/execute_task_external";
 pub const EVENT_STOP_TASK_EXTERNAL: &str = "iam/stop_task_external";
 pub const EVENT_SET_TASK_PROCESS_DATA_EXTERNAL: &str = "iam/set_task_process_data";
 pub const IAM_AVATAR: &str = env!("CARGO_PKG_NAME");
 
+pub const TASK_TYPE_ORG: &str = "iam/org_task";
+pub const TASK_TYPE_ACCOUNT: &str = "iam/account_task";
+pub const TASK_TYPE_ACCOUNT_SEARCH: &str = "iam/account_search_task";
+pub const TASK_TYPE_ROLE: &str = "iam/role_task";
+
 pub const OPENAPI_GATEWAY_PLUGIN_TIME_RANGE: &str = "redis-time-range:opres-time-range";
 pub const OPENAPI_GATEWAY_PLUGIN_LIMIT: &str = "redis-limit:opres-limit";
 pub const OPENAPI_GATEWAY_PLUGIN_COUNT: &str = "redis-count:opres-count";
@@ -51,6 +51,17 @@ pub enum IamCertKernelKind {
     MailVCode,
     PhoneVCode,
     AkSk,
+    /// Time-based one-time password (RFC 6238), only used as the second factor
+    Totp,
+    /// WebAuthn credential (passkey / security key), only used as the second factor
+    WebAuthn,
+}
+
+impl IamCertKernelKind {
+    /// The kinds that can be used as the second factor
+    pub fn second_factors() -> Vec<IamCertKernelKind> {
+        vec![IamCertKernelKind::Totp, IamCertKernelKind::WebAuthn]
+    }
 }
 
 #[derive(Display, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, strum::EnumString)]
@@ -67,6 +78,8 @@ pub enum IamCertOAuth2Supplier {
     // Weibo,
     Github,
     WechatMp,
+    /// Generic OIDC supplier, e.g. Keycloak, Azure AD, Okta
+    Oidc,
 }
 
 impl IamCertOAuth2Supplier {
@@ -176,9 +189,14 @@ impl IamSetCateKind {
 
 #[derive(Display, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, strum::EnumString)]
 pub enum Oauth2GrantType {
+    #[strum(to_string = "AuthorizationCode", serialize = "authorization_code")]
     AuthorizationCode,
+    #[strum(to_string = "Password", serialize = "password")]
     Password,
+    #[strum(to_string = "ClientCredentials", serialize = "client_credentials")]
     ClientCredentials,
+    #[strum(to_string = "RefreshToken", serialize = "refresh_token")]
+    RefreshToken,
 }
 
 impl Oauth2GrantType {
@@ -326,6 +344,8 @@ pub enum IamConfigDataTypeKind {
     TimeRange,
     // ip段
     Ips,
+    // 凭证类型，多个以逗号分隔
+    CertKinds,
 }
 impl IamConfigDataTypeKind {
     pub fn parse(kind: &str) -> TardisResult<IamConfigDataTypeKind> {
@@ -342,6 +362,7 @@ impl IamConfigDataTypeKind {
             IamConfigDataTypeKind::DatetimeRange => None,
             IamConfigDataTypeKind::TimeRange => None,
             IamConfigDataTypeKind::Ips => None,
+            IamConfigDataTypeKind::CertKinds => None,
         }
     }
 
@@ -355,6 +376,7 @@ impl IamConfigDataTypeKind {
             IamConfigDataTypeKind::DatetimeRange => None,
             IamConfigDataTypeKind::TimeRange => None,
             IamConfigDataTypeKind::Ips => None,
+            IamConfigDataTypeKind::CertKinds => None,
         }
     }
 
@@ -368,6 +390,7 @@ impl IamConfigDataTypeKind {
             IamConfigDataTypeKind::DatetimeRange => None,
             IamConfigDataTypeKind::TimeRange => None,
             IamConfigDataTypeKind::Ips => None,
+            IamConfigDataTypeKind::CertKinds => None,
         }
     }
 }
@@ -392,6 +415,8 @@ pub enum IamConfigKind {
     AuditLogCapacity,
     /// 在线人数限制 #人
     MaxOnline,
+    /// 登录二次认证，账号已绑定的二次认证凭证需在登录时验证 #凭证类型，为空表示所有二次认证凭证
+    Mfa,
 }
 
 impl IamConfigKind {
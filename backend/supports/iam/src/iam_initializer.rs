@@ -1,3 +1,4 @@
+use bios_basic::process::task_processor::TaskProcessor;
 use bios_basic::rbum::rbum_enumeration::{RbumCertStatusKind, RbumScopeLevelKind};
 use bios_sdk_invoke::invoke_initializer;
 use tardis::basic::dto::TardisContext;
@@ -35,10 +36,13 @@ use crate::console_common::api::{
     iam_cc_account_api, iam_cc_account_task_api, iam_cc_app_api, iam_cc_app_set_api, iam_cc_config_api, iam_cc_org_api, iam_cc_org_task_api, iam_cc_res_api, iam_cc_role_api,
     iam_cc_system_api, iam_cc_tenant_api,
 };
+use crate::console_common::serv::iam_cc_account_task_serv::IamCcAccountTaskServ;
+use crate::console_common::serv::iam_cc_org_task_serv::IamCcOrgTaskServ;
+use crate::console_common::serv::iam_cc_role_task_serv::IamCcRoleTaskServ;
 use crate::console_interface::api::{
-    iam_ci_account_api, iam_ci_app_api, iam_ci_app_set_api, iam_ci_cert_api, iam_ci_open_api, iam_ci_org_api, iam_ci_res_api, iam_ci_role_api, iam_ci_system_api,
+    iam_ci_account_api, iam_ci_app_api, iam_ci_app_set_api, iam_ci_cert_api, iam_ci_open_api, iam_ci_org_api, iam_ci_res_api, iam_ci_role_api, iam_ci_scim_api, iam_ci_system_api,
 };
-use crate::console_passport::api::{iam_cp_account_api, iam_cp_app_api, iam_cp_cert_api, iam_cp_tenant_api};
+use crate::console_passport::api::{iam_cp_account_api, iam_cp_app_api, iam_cp_cert_api, iam_cp_oauth2_api, iam_cp_tenant_api};
 use crate::console_system::api::{
     iam_cs_account_api, iam_cs_account_attr_api, iam_cs_cert_api, iam_cs_org_api, iam_cs_platform_api, iam_cs_res_api, iam_cs_role_api, iam_cs_spi_data_api, iam_cs_tenant_api,
 };
@@ -83,6 +87,8 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
                     iam_cp_cert_api::IamCpCertApi,
                     #[cfg(feature = "ldap_client")]
                     iam_cp_cert_api::IamCpCertLdapApi,
+                    iam_cp_oauth2_api::IamCpOAuth2Api,
+                    iam_cp_oauth2_api::IamCpOidcDiscoveryApi,
                     iam_cp_tenant_api::IamCpTenantApi,
                 ),
                 (
@@ -129,6 +135,7 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
                     iam_ci_system_api::IamCiSystemApi,
                     iam_ci_open_api::IamCiOpenApi,
                     iam_ci_org_api::IamCiOrgApi,
+                    iam_ci_scim_api::IamCiScimApi,
                 ),
             )), // .middlewares(EncryptMW),
         )
@@ -138,7 +145,6 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
 
 pub async fn init_db(mut funs: TardisFunsInst) -> TardisResult<Option<(String, String)>> {
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<IamConfig>().rbum.clone()).await?;
-    // TaskProcessor::subscribe_task(&funs).await?;
     invoke_initializer::init(funs.module_code(), funs.conf::<IamConfig>().invoke.clone())?;
     funs.begin().await?;
     let ctx = get_first_account_context(iam_constants::RBUM_KIND_CODE_IAM_ACCOUNT, iam_constants::COMPONENT_CODE, &funs).await?;
@@ -158,9 +164,20 @@ pub async fn init_db(mut funs: TardisFunsInst) -> TardisResult<Option<(String, S
         Some((name, password))
     };
     funs.commit().await?;
+    init_task(&funs).await?;
     Ok(sysadmin_info)
 }
 
+/// Register the asynchronous task functions and take over the tasks left by the stopped nodes
+async fn init_task(funs: &TardisFunsInst) -> TardisResult<()> {
+    // The org, account and role tasks create or modify data step by step, re-executing them after an interruption may produce duplicates
+    TaskProcessor::register_task_fun(iam_constants::TASK_TYPE_ORG, false, IamCcOrgTaskServ::do_execute_org_task).await;
+    TaskProcessor::register_task_fun(iam_constants::TASK_TYPE_ACCOUNT, false, IamCcAccountTaskServ::do_execute_account_task).await;
+    TaskProcessor::register_task_fun(iam_constants::TASK_TYPE_ACCOUNT_SEARCH, true, IamCcAccountTaskServ::do_execute_account_search_task).await;
+    TaskProcessor::register_task_fun(iam_constants::TASK_TYPE_ROLE, false, IamCcRoleTaskServ::do_execute_role_task).await;
+    TaskProcessor::init_registry(funs).await
+}
+
 async fn init_basic_info<'a>(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     let kind_tenant_id = RbumKindServ::get_rbum_kind_id_by_code(iam_constants::RBUM_KIND_CODE_IAM_TENANT, funs)
         .await?
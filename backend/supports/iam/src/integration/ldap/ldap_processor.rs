@@ -1,12 +1,66 @@
+use std::collections::HashMap;
+
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
 use tardis::basic::result::TardisResult;
 use tardis::{TardisFuns, TardisFunsInst};
 
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumCertFilterReq, RbumRelFilterReq, RbumSetCateFilterReq, RbumSetItemFilterReq};
+use bios_basic::rbum::rbum_enumeration::RbumCertRelKind;
 use bios_basic::rbum::serv::rbum_cert_serv::RbumCertServ;
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_basic::rbum::serv::rbum_rel_serv::RbumRelServ;
+use bios_basic::rbum::serv::rbum_set_serv::RbumSetItemServ;
 
+use crate::basic::dto::iam_account_dto::IamAccountAggModifyReq;
+use crate::basic::dto::iam_cert_dto::{IamCertUserPwdModifyReq, IamCertUserPwdRestReq};
+use crate::basic::dto::iam_filer_dto::{IamAccountFilterReq, IamRoleFilterReq};
+use crate::basic::serv::iam_account_serv::IamAccountServ;
 use crate::basic::serv::iam_cert_serv::IamCertServ;
+use crate::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use crate::basic::serv::iam_role_serv::IamRoleServ;
+use crate::basic::serv::iam_set_serv::IamSetServ;
 use crate::console_passport::serv::iam_cp_cert_user_pwd_serv::IamCpCertUserPwdServ;
+use crate::iam_config::IamBasicConfigApi;
 use crate::iam_constants;
-use crate::iam_enumeration::IamCertKernelKind;
+use crate::iam_enumeration::{IamCertKernelKind, IamRelKind, IamSetKind};
+
+/// Account exposed by the ldap server
+pub struct LdapAccount {
+    pub id: String,
+    pub tenant_id: String,
+    /// Login name, format: <tenant Id in hexadecimal>/<ak>
+    pub cn: String,
+    pub ak: String,
+    pub name: String,
+    pub mail: Option<String>,
+    pub phone: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LdapGroupKind {
+    Role,
+    Org,
+}
+
+/// Group exposed by the ldap server, the groups are the roles and the org nodes of the tenant
+pub struct LdapGroup {
+    pub kind: LdapGroupKind,
+    /// Format: <tenant Id in hexadecimal>/<role code or org node bus code, the id is used when it's empty>
+    pub cn: String,
+    pub name: String,
+    pub member_account_ids: Vec<String>,
+}
+
+/// Narrow down the groups to be found, `None` means no restriction
+#[derive(Debug, Default, Clone)]
+pub struct LdapGroupFilter {
+    /// The role code or the org node bus code, the id is used when it's empty
+    pub code: Option<String>,
+    /// Only the groups containing the account are found, and their members are limited to this account
+    pub member_account_id: Option<String>,
+}
 
 pub async fn check_exist(account_name_with_tenant: &str) -> TardisResult<bool> {
     //Ok(true)
@@ -27,9 +81,335 @@ pub async fn check_cert(account_name_with_tenant: &str, pwd: &str) -> TardisResu
     }
 }
 
-// pub async fn get_account() -> TardisResult<bool> {
-//
-// }
+pub async fn get_tenant_id(account_name_with_tenant: &str) -> TardisResult<String> {
+    let funs = iam_constants::get_tardis_inst();
+    get_basic_info(account_name_with_tenant, &funs).await.map(|(tenant_id, _)| tenant_id)
+}
+
+/// Get the enabled account by the login name
+pub async fn get_account(account_name_with_tenant: &str) -> TardisResult<Option<LdapAccount>> {
+    let funs = iam_constants::get_tardis_inst();
+    let (tenant_id, ak) = get_basic_info(account_name_with_tenant, &funs).await?;
+    let ctx = tenant_ctx(&tenant_id);
+    let rbum_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::UserPwd.to_string(), Some(tenant_id.clone()), &funs).await?;
+    let Some(account_id) = IamCpCertUserPwdServ::get_cert_rel_account_by_user_name(&ak, &rbum_cert_conf_id, &funs, &ctx).await? else {
+        return Ok(None);
+    };
+    Ok(do_find_accounts(&tenant_id, Some(vec![account_id]), &funs).await?.pop())
+}
+
+/// Find the enabled accounts with the user name of the tenant, the platform accounts are found when the tenant id is empty.
+///
+/// The accounts are limited to the given ids when present.
+pub async fn find_accounts(tenant_id: &str, account_ids: Option<Vec<String>>) -> TardisResult<Vec<LdapAccount>> {
+    let funs = iam_constants::get_tardis_inst();
+    if account_ids.as_ref().is_some_and(|account_ids| account_ids.is_empty()) {
+        return Ok(vec![]);
+    }
+    do_find_accounts(tenant_id, account_ids, &funs).await
+}
+
+/// Find the account id of the tenant by the common name
+pub async fn find_account_id_by_cn(tenant_id: &str, cn: &str) -> TardisResult<Option<String>> {
+    let Some(ak) = strip_cn_prefix(tenant_id, cn) else {
+        return Ok(None);
+    };
+    let funs = iam_constants::get_tardis_inst();
+    let ctx = tenant_ctx(tenant_id);
+    let rbum_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::UserPwd.to_string(), Some(tenant_id.to_string()), &funs).await?;
+    IamCpCertUserPwdServ::get_cert_rel_account_by_user_name(ak, &rbum_cert_conf_id, &funs, &ctx).await
+}
+
+/// Find the account id of the tenant by the mail
+pub async fn find_account_id_by_mail(tenant_id: &str, mail: &str) -> TardisResult<Option<String>> {
+    let funs = iam_constants::get_tardis_inst();
+    let ctx = tenant_ctx(tenant_id);
+    let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&IamCertKernelKind::MailVCode.to_string(), Some(tenant_id.to_string()), &funs).await? else {
+        return Ok(None);
+    };
+    let cert = RbumCertServ::find_one_rbum(
+        &RbumCertFilterReq {
+            ak: Some(mail.to_string()),
+            rel_rbum_kind: Some(RbumCertRelKind::Item),
+            rel_rbum_cert_conf_ids: Some(vec![cert_conf.id]),
+            ..Default::default()
+        },
+        &funs,
+        &ctx,
+    )
+    .await?;
+    Ok(cert.map(|cert| cert.rel_rbum_id))
+}
+
+async fn do_find_accounts(tenant_id: &str, account_ids: Option<Vec<String>>, funs: &TardisFunsInst) -> TardisResult<Vec<LdapAccount>> {
+    let ctx = tenant_ctx(tenant_id);
+    let accounts = IamAccountServ::find_items(
+        &IamAccountFilterReq {
+            basic: RbumBasicFilterReq {
+                own_paths: Some(tenant_id.to_string()),
+                ignore_scope: true,
+                enabled: Some(true),
+                ids: account_ids,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(false),
+        None,
+        funs,
+        &ctx,
+    )
+    .await?;
+    if accounts.is_empty() {
+        return Ok(vec![]);
+    }
+    let account_ids = accounts.iter().map(|account| account.id.clone()).collect::<Vec<_>>();
+    let user_names = find_cert_aks(tenant_id, &IamCertKernelKind::UserPwd, &account_ids, funs, &ctx).await?;
+    let mut mails = find_cert_aks(tenant_id, &IamCertKernelKind::MailVCode, &account_ids, funs, &ctx).await?;
+    let mut phones = find_cert_aks(tenant_id, &IamCertKernelKind::PhoneVCode, &account_ids, funs, &ctx).await?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|account| {
+            // the account without user name can't login by ldap
+            let ak = user_names.get(&account.id)?.clone();
+            Some(LdapAccount {
+                cn: format!("{}{}", cn_prefix(tenant_id), ak),
+                tenant_id: tenant_id.to_string(),
+                ak,
+                name: account.name,
+                mail: mails.remove(&account.id),
+                phone: phones.remove(&account.id),
+                id: account.id,
+            })
+        })
+        .collect())
+}
+
+/// Account id -> ak
+async fn find_cert_aks(tenant_id: &str, kind: &IamCertKernelKind, account_ids: &[String], funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<HashMap<String, String>> {
+    let Some(cert_conf) = IamCertServ::get_cert_conf_id_and_ext_opt_by_kind(&kind.to_string(), Some(tenant_id.to_string()), funs).await? else {
+        return Ok(HashMap::new());
+    };
+    let certs = RbumCertServ::find_rbums(
+        &RbumCertFilterReq {
+            rel_rbum_kind: Some(RbumCertRelKind::Item),
+            rel_rbum_ids: Some(account_ids.to_vec()),
+            rel_rbum_cert_conf_ids: Some(vec![cert_conf.id]),
+            ..Default::default()
+        },
+        None,
+        None,
+        funs,
+        ctx,
+    )
+    .await?;
+    Ok(certs.into_iter().map(|cert| (cert.rel_rbum_id, cert.ak)).collect())
+}
+
+/// Find the roles and the org nodes of the tenant with their member accounts
+pub async fn find_groups(tenant_id: &str, filter: &LdapGroupFilter) -> TardisResult<Vec<LdapGroup>> {
+    let funs = iam_constants::get_tardis_inst();
+    let ctx = tenant_ctx(tenant_id);
+    let code_matches = |code: &str, id: &str| filter.code.as_ref().map_or(true, |c| c == if code.is_empty() { id } else { code });
+    let mut groups = vec![];
+    let roles = IamRoleServ::find_items(
+        &IamRoleFilterReq {
+            basic: RbumBasicFilterReq {
+                own_paths: Some(tenant_id.to_string()),
+                enabled: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(false),
+        None,
+        &funs,
+        &ctx,
+    )
+    .await?
+    .into_iter()
+    .filter(|role| code_matches(&role.code, &role.id))
+    .collect::<Vec<_>>();
+    if !roles.is_empty() {
+        // Find the members of all roles at once
+        let mut role_members = HashMap::<String, Vec<String>>::new();
+        for rel in RbumRelServ::find_rbums(
+            &RbumRelFilterReq {
+                basic: RbumBasicFilterReq {
+                    own_paths: Some(tenant_id.to_string()),
+                    with_sub_own_paths: true,
+                    ignore_scope: true,
+                    ..Default::default()
+                },
+                tag: Some(IamRelKind::IamAccountRole.to_string()),
+                from_rbum_id: filter.member_account_id.clone(),
+                to_rbum_item_id: if roles.len() == 1 { Some(roles[0].id.clone()) } else { None },
+                ..Default::default()
+            },
+            None,
+            None,
+            &funs,
+            &ctx,
+        )
+        .await?
+        {
+            role_members.entry(rel.to_rbum_item_id).or_default().push(rel.from_rbum_id);
+        }
+        for role in roles {
+            let member_account_ids = role_members.remove(&role.id).unwrap_or_default();
+            if filter.member_account_id.is_some() && member_account_ids.is_empty() {
+                continue;
+            }
+            groups.push(LdapGroup {
+                kind: LdapGroupKind::Role,
+                cn: format!("{}{}", cn_prefix(tenant_id), if role.code.is_empty() { &role.id } else { &role.code }),
+                member_account_ids,
+                name: role.name,
+            });
+        }
+    }
+    let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, &funs, &ctx).await?;
+    let cates = IamSetServ::find_set_cate(
+        &RbumSetCateFilterReq {
+            rel_rbum_set_id: Some(set_id.clone()),
+            ..Default::default()
+        },
+        Some(false),
+        None,
+        &funs,
+        &ctx,
+    )
+    .await?
+    .into_iter()
+    .filter(|cate| code_matches(&cate.bus_code, &cate.id))
+    .collect::<Vec<_>>();
+    if cates.is_empty() {
+        return Ok(groups);
+    }
+    let mut org_members = HashMap::<String, Vec<String>>::new();
+    for item in RbumSetItemServ::find_detail_rbums(
+        &RbumSetItemFilterReq {
+            rel_rbum_set_id: Some(set_id),
+            rel_rbum_set_cate_ids: filter.code.as_ref().map(|_| cates.iter().map(|cate| cate.id.clone()).collect()),
+            rel_rbum_item_ids: filter.member_account_id.clone().map(|account_id| vec![account_id]),
+            rel_rbum_item_kind_ids: Some(vec![funs.iam_basic_kind_account_id()]),
+            rel_rbum_item_can_not_exist: Some(false),
+            rel_rbum_item_disabled: Some(false),
+            ..Default::default()
+        },
+        None,
+        None,
+        &funs,
+        &ctx,
+    )
+    .await?
+    {
+        if let Some(cate_id) = item.rel_rbum_set_cate_id {
+            org_members.entry(cate_id).or_default().push(item.rel_rbum_item_id);
+        }
+    }
+    for cate in cates {
+        let member_account_ids = org_members.remove(&cate.id).unwrap_or_default();
+        if filter.member_account_id.is_some() && member_account_ids.is_empty() {
+            continue;
+        }
+        groups.push(LdapGroup {
+            kind: LdapGroupKind::Org,
+            cn: format!("{}{}", cn_prefix(tenant_id), if cate.bus_code.is_empty() { &cate.id } else { &cate.bus_code }),
+            member_account_ids,
+            name: cate.name,
+        });
+    }
+    Ok(groups)
+}
+
+/// Modify the password of the account, the password policy is checked by [IamCertUserPwdServ].
+///
+/// The password is reset when the original password is absent, which is only allowed for the administrator.
+pub async fn modify_password(account: &LdapAccount, original_password: Option<&str>, new_password: &str) -> TardisResult<()> {
+    let mut funs = iam_constants::get_tardis_inst();
+    let ctx = account_ctx(account);
+    let rbum_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::UserPwd.to_string(), Some(account.tenant_id.clone()), &funs).await?;
+    funs.begin().await?;
+    if let Some(original_password) = original_password {
+        IamCertUserPwdServ::modify_cert(
+            &IamCertUserPwdModifyReq {
+                original_sk: TrimString(original_password.to_string()),
+                new_sk: TrimString(new_password.to_string()),
+            },
+            &account.id,
+            &rbum_cert_conf_id,
+            &funs,
+            &ctx,
+        )
+        .await?;
+    } else {
+        IamCertUserPwdServ::reset_sk(
+            &IamCertUserPwdRestReq {
+                new_sk: Some(TrimString(new_password.to_string())),
+            },
+            &account.id,
+            &rbum_cert_conf_id,
+            &funs,
+            &ctx,
+        )
+        .await?;
+    }
+    funs.commit().await?;
+    ctx.execute_task().await
+}
+
+/// Modify the name, mail and phone of the account
+pub async fn modify_account(account: &LdapAccount, name: Option<String>, mail: Option<String>, phone: Option<String>) -> TardisResult<()> {
+    let mut funs = iam_constants::get_tardis_inst();
+    let ctx = account_ctx(account);
+    funs.begin().await?;
+    IamAccountServ::modify_account_agg(
+        &account.id,
+        &IamAccountAggModifyReq {
+            name: name.map(TrimString),
+            cert_mail: mail.map(TrimString),
+            cert_phone: phone.map(TrimString),
+            ..Default::default()
+        },
+        &funs,
+        &ctx,
+    )
+    .await?;
+    funs.commit().await?;
+    ctx.execute_task().await
+}
+
+/// The prefix of the common name, it's empty for the platform
+pub fn cn_prefix(tenant_id: &str) -> String {
+    if tenant_id.is_empty() {
+        "".to_string()
+    } else {
+        format!("{}/", TardisFuns::crypto.hex.encode(tenant_id))
+    }
+}
+
+/// Strip the prefix of the tenant from the common name, the hexadecimal prefix is case-insensitive
+pub fn strip_cn_prefix<'a>(tenant_id: &str, cn: &'a str) -> Option<&'a str> {
+    let prefix = cn_prefix(tenant_id);
+    cn.get(..prefix.len()).filter(|p| p.eq_ignore_ascii_case(&prefix)).map(|_| &cn[prefix.len()..])
+}
+
+fn tenant_ctx(tenant_id: &str) -> TardisContext {
+    TardisContext {
+        own_paths: tenant_id.to_string(),
+        ..Default::default()
+    }
+}
+
+fn account_ctx(account: &LdapAccount) -> TardisContext {
+    TardisContext {
+        own_paths: account.tenant_id.clone(),
+        owner: account.id.clone(),
+        ak: account.ak.clone(),
+        ..Default::default()
+    }
+}
 
 async fn get_basic_info<'a>(account_name_with_tenant: &str, funs: &TardisFunsInst) -> TardisResult<(String, String)> {
     let mut account_name_with_tenant = account_name_with_tenant.split('/');
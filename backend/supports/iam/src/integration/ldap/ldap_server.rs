@@ -48,13 +48,35 @@
 //!   -v /opt/volumes/gitlab/var/log/gitlab:/var/log/gitlab \
 //!   -v /opt/volumes/gitlab/var/opt/gitlab:/var/opt/gitlab \
 //!   -dit gitlab/gitlab-ce
+//! ## Directory layout
 //!
+//! * Users: `CN=<account name>,DC=<dc>`, the account name of the tenant is prefixed with `<hex(tenant id)>/`
+//! * Roles: `CN=<role code>,OU=Roles,DC=<dc>`
+//! * Orgs: `CN=<org node code>,OU=Orgs,DC=<dc>`
+//!
+//! The user entries carry `memberOf` and the group entries carry `member`, both of which are DNs.
+//!
+//! ## TLS
+//!
+//! When `tls_cert` and `tls_key` are configured, the plain port supports StartTLS and, if `tls_port` is set, an LDAPS port is started as well.
+//!
+//! ## Modify
+//!
+//! When `modify_enabled` is set, the Modify operation (`userPassword`, `displayName`, `mail`, `mobile`) and the PasswordModify extended operation (RFC 3062) are supported,
+//! the password policy is still checked by IAM.
+//!
+//! The `bind_password` must be changed from the default value to enable it, and the administrator can only reset the passwords
+//! without the original password in the `admin_reset_tenant_id` tenant.
+//!
+use std::collections::HashMap;
 use std::net;
 use std::str::FromStr;
 use std::sync::Arc;
 
+use ldap3_proto::proto::{LdapControl, LdapExtendedRequest, LdapExtendedResponse, LdapModifyRequest, LdapModifyType, LdapOp, LdapResult, LdapSearchScope, LdapSubstringFilter};
 use ldap3_proto::simple::*;
 use ldap3_proto::LdapCodec;
+use sha2::{Digest, Sha256};
 use tardis::basic::error::TardisError;
 use tardis::basic::result::TardisResult;
 use tardis::futures::SinkExt;
@@ -62,26 +84,50 @@ use tardis::futures::StreamExt;
 use tardis::log::{error, info, trace};
 
 use tardis::regex::Regex;
+use tardis::tokio::io::{AsyncRead, AsyncWrite};
 use tardis::tokio::net::{TcpListener, TcpStream};
 use tardis::{tokio, TardisFuns};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
 use tokio_util::codec::{FramedRead, FramedWrite};
 
 use crate::iam_config::{IamConfig, IamLdapConfig};
 use crate::iam_constants;
-use crate::integration::ldap::ldap_processor;
+use crate::integration::ldap::ldap_processor::{self, LdapAccount, LdapGroupFilter, LdapGroupKind};
+
+const OID_START_TLS: &str = "1.3.6.1.4.1.1466.20037";
+const OID_PASSWORD_MODIFY: &str = "1.3.6.1.4.1.4203.1.11.1";
+const OID_WHOAMI: &str = "1.3.6.1.4.1.4203.1.11.3";
+const OID_PAGED_RESULTS: &str = "1.2.840.113556.1.4.319";
 
 lazy_static! {
     static ref CN_R: Regex = Regex::new(r"(,|^)[cC][nN]=(.+?)(,|$)").expect("Regular parsing error");
 }
 
+enum LdapBound {
+    Anonymous,
+    Admin,
+    Account(LdapAccount),
+}
+
 struct LdapSession {
     dn: String,
+    bound: LdapBound,
+    // Whether the connection is protected by TLS
+    secure: bool,
+    // Whether the connection can be upgraded by StartTLS
+    start_tls_enabled: bool,
 }
 
 impl LdapSession {
     pub async fn do_bind(&mut self, req: &SimpleBindRequest, config: &IamLdapConfig) -> LdapMsg {
-        if req.dn == config.bind_dn && req.pw == config.bind_password {
+        self.bound = LdapBound::Anonymous;
+        if config.tls_required && !self.secure && !req.pw.is_empty() {
+            return req.gen_error(LdapResultCode::ConfidentialityRequired, "TLS is required".to_string());
+        }
+        if req.dn == config.bind_dn && constant_time_eq(&req.pw, &config.bind_password) {
             self.dn = req.dn.to_string();
+            self.bound = LdapBound::Admin;
             req.gen_success()
         } else if req.dn.is_empty() && req.pw.is_empty() {
             self.dn = "Anonymous".to_string();
@@ -93,7 +139,14 @@ impl LdapSession {
             match extract_cn(&req.dn) {
                 None => req.gen_invalid_cred(),
                 Some(cn) => match ldap_processor::check_cert(&cn, &req.pw).await {
-                    Ok(true) => req.gen_success(),
+                    Ok(true) => match ldap_processor::get_account(&cn).await {
+                        Ok(Some(account)) => {
+                            self.bound = LdapBound::Account(account);
+                            req.gen_success()
+                        }
+                        Ok(None) => req.gen_invalid_cred(),
+                        Err(_) => req.gen_error(LdapResultCode::Unavailable, "Service internal error".to_string()),
+                    },
                     Ok(false) => req.gen_invalid_cred(),
                     Err(_) => req.gen_error(LdapResultCode::Unavailable, "Service internal error".to_string()),
                 },
@@ -101,112 +154,206 @@ impl LdapSession {
         }
     }
 
-    pub async fn do_search(&mut self, req: &SearchRequest, config: &IamLdapConfig) -> Vec<LdapMsg> {
-        match &req.filter {
-            LdapFilter::And(_) | LdapFilter::Or(_) | LdapFilter::Not(_) | LdapFilter::Substring(_, _) => {
-                vec![req.gen_error(LdapResultCode::Other, "This operation is not currently supported".to_string())]
-            }
-            LdapFilter::Equality(_, cn) => {
-                if !req.base.to_lowercase().contains(&format!("DC={}", config.dc).to_lowercase()) {
-                    return vec![req.gen_error(LdapResultCode::NoSuchObject, "DN is invalid".to_string())];
+    pub async fn do_search(&mut self, req: &SearchRequest, ctrl: &[LdapControl], config: &IamLdapConfig) -> Vec<LdapMsg> {
+        if req.base.is_empty() {
+            return self.do_search_root_dse(req, config);
+        }
+        if matches!(self.bound, LdapBound::Anonymous) {
+            return vec![req.gen_error(LdapResultCode::InsufficentAccessRights, "Bind is required".to_string())];
+        }
+        if !req.base.to_lowercase().contains(&format!("DC={}", config.dc).to_lowercase()) {
+            return vec![req.gen_error(LdapResultCode::NoSuchObject, "DN is invalid".to_string())];
+        }
+        let tenant_id = match self.get_search_tenant_id(req).await {
+            Ok(tenant_id) => tenant_id,
+            Err(_) => return vec![req.gen_error(LdapResultCode::NoSuchObject, "Tenant not exist".to_string())],
+        };
+        let entries = match build_entries(&tenant_id, &req.filter, config).await {
+            Ok(entries) => entries,
+            Err(_) => return vec![req.gen_error(LdapResultCode::Unavailable, "Service internal error".to_string())],
+        };
+        let base = normalize_dn(&req.base);
+        let entries = entries
+            .into_iter()
+            .filter(|entry| in_scope(&entry.dn, &base, &req.scope) && filter_matches(entry, &req.filter))
+            .map(|entry| select_attrs(entry, &req.attrs))
+            .collect::<Vec<_>>();
+
+        let paged = ctrl.iter().find_map(|ctrl| match ctrl {
+            LdapControl::SimplePagedResults { size, cookie } => Some((*size, cookie.clone())),
+            _ => None,
+        });
+        let Some((size, cookie)) = paged else {
+            return entries.into_iter().map(|entry| req.gen_result_entry(entry)).chain([req.gen_success()]).collect();
+        };
+        let Some((entries, next_cookie)) = take_page(entries, size.max(0) as usize, &cookie, config.max_page_size) else {
+            return vec![req.gen_error(LdapResultCode::UnwillingToPerform, "Paged results cookie is invalid".to_string())];
+        };
+        let mut done = req.gen_success();
+        done.ctrl = vec![LdapControl::SimplePagedResults { size: 0, cookie: next_cookie }];
+        entries.into_iter().map(|entry| req.gen_result_entry(entry)).chain([done]).collect()
+    }
+
+    fn do_search_root_dse(&self, req: &SearchRequest, config: &IamLdapConfig) -> Vec<LdapMsg> {
+        // https://ldap.com/dit-and-the-ldap-root-dse/
+        // https://docs.oracle.com/cd/E19957-01/817-6707/srvrinfo.html
+        let mut extensions = vec![OID_WHOAMI];
+        if config.modify_enabled {
+            extensions.push(OID_PASSWORD_MODIFY);
+        }
+        if self.start_tls_enabled && !self.secure {
+            extensions.push(OID_START_TLS);
+        }
+        let entry = LdapSearchResultEntry {
+            dn: format!("DC={}", config.dc),
+            attributes: vec![
+                new_attr("objectClass", vec!["top"]),
+                new_attr("namingContexts", vec![format!("DC={}", config.dc)]),
+                new_attr("supportedLDAPVersion", vec!["3"]),
+                new_attr("supportedExtension", extensions),
+                new_attr("supportedControl", vec![OID_PAGED_RESULTS]),
+            ],
+        };
+        vec![req.gen_result_entry(select_attrs(entry, &req.attrs)), req.gen_success()]
+    }
+
+    /// The administrator can search any tenant, which is resolved from the base or the filter, otherwise the platform is used.
+    /// The account can only search its own tenant.
+    async fn get_search_tenant_id(&self, req: &SearchRequest) -> TardisResult<String> {
+        if let LdapBound::Account(account) = &self.bound {
+            return Ok(account.tenant_id.clone());
+        }
+        let cn = extract_cn(&req.base).or_else(|| {
+            find_equality_value(&req.filter, &["cn", "uid", "sAMAccountName", "member", "uniqueMember", "memberOf"]).and_then(|value| {
+                if value.contains('=') {
+                    extract_cn(value)
+                } else {
+                    Some(value.to_string())
                 }
-                match ldap_processor::check_exist(cn).await {
-                    Ok(true) => vec![
-                        req.gen_result_entry(LdapSearchResultEntry {
-                            dn: format!("CN={},DC={}", cn, config.dc),
-                            attributes: vec![
-                                LdapPartialAttribute {
-                                    atype: "sAMAccountName".to_string(),
-                                    vals: vec![cn.to_string().into()],
-                                },
-                                // TODO
-                                LdapPartialAttribute {
-                                    atype: "mail".to_string(),
-                                    vals: vec![format!("{cn}@example.com").into()],
-                                },
-                                // TODO
-                                LdapPartialAttribute {
-                                    atype: "cn".to_string(),
-                                    vals: vec![cn.to_string().into()],
-                                },
-                                // TODO
-                                LdapPartialAttribute {
-                                    atype: "givenName".to_string(),
-                                    vals: vec!["".to_string().into()],
-                                },
-                                // TODO
-                                LdapPartialAttribute {
-                                    atype: "sn".to_string(),
-                                    vals: vec![cn.to_string().into()],
-                                },
-                            ],
-                        }),
-                        req.gen_success(),
-                    ],
-                    Ok(false) => vec![req.gen_error(LdapResultCode::NoSuchObject, "CN not exist".to_string())],
-                    Err(_) => vec![req.gen_error(LdapResultCode::Unavailable, "Service internal error".to_string())],
+            })
+        });
+        match cn {
+            Some(cn) => ldap_processor::get_tenant_id(&cn).await,
+            None => Ok("".to_string()),
+        }
+    }
+
+    pub async fn do_modify(&mut self, msgid: i32, req: &LdapModifyRequest, config: &IamLdapConfig) -> LdapMsg {
+        if !config.modify_enabled {
+            return gen_modify_resp(msgid, LdapResultCode::UnwillingToPerform, "Modify is disabled");
+        }
+        let account = match self.get_modify_target(Some(&req.dn)).await {
+            Ok(account) => account,
+            Err((code, message)) => return gen_modify_resp(msgid, code, &message),
+        };
+        let mut original_password = None;
+        let mut new_password = None;
+        let mut name = None;
+        let mut mail = None;
+        let mut phone = None;
+        for change in &req.changes {
+            let value = change.modification.vals.first().map(|v| String::from_utf8_lossy(v).to_string());
+            match (change.modification.atype.to_lowercase().as_str(), &change.operation) {
+                ("userpassword", LdapModifyType::Delete) => original_password = value,
+                ("userpassword", _) => new_password = value,
+                ("displayname", LdapModifyType::Replace) => name = value,
+                ("mail", LdapModifyType::Add | LdapModifyType::Replace) => mail = value,
+                ("mobile" | "telephonenumber", LdapModifyType::Add | LdapModifyType::Replace) => phone = value,
+                _ => {
+                    return gen_modify_resp(
+                        msgid,
+                        LdapResultCode::UnwillingToPerform,
+                        &format!("Attribute {} can not be modified", change.modification.atype),
+                    )
                 }
             }
-            LdapFilter::Present(k) => {
-                if k == "objectClass" && req.base.is_empty() {
-                    // https://ldap.com/dit-and-the-ldap-root-dse/
-                    // https://docs.oracle.com/cd/E19957-01/817-6707/srvrinfo.html
-                    return vec![
-                        req.gen_result_entry(LdapSearchResultEntry {
-                            dn: format!("DC={}", config.dc),
-                            attributes: vec![],
-                        }),
-                        req.gen_success(),
-                    ];
-                }
-                if !req.base.to_lowercase().contains(&format!("DC={}", config.dc).to_lowercase()) {
-                    return vec![req.gen_error(LdapResultCode::NoSuchObject, "DN is invalid".to_string())];
-                }
-                match extract_cn(&req.base) {
-                    None => vec![req.gen_error(LdapResultCode::NoSuchObject, "CN is invalid".to_string())],
-                    Some(cn) => match ldap_processor::check_exist(&cn).await {
-                        Ok(true) => vec![
-                            req.gen_result_entry(LdapSearchResultEntry {
-                                dn: format!("CN={},DC={}", cn, config.dc),
-                                attributes: vec![
-                                    LdapPartialAttribute {
-                                        atype: "sAMAccountName".to_string(),
-                                        vals: vec![cn.clone().into()],
-                                    },
-                                    // TODO
-                                    LdapPartialAttribute {
-                                        atype: "mail".to_string(),
-                                        vals: vec![format!("{}@example.com", cn.clone()).into()],
-                                    },
-                                    // TODO
-                                    LdapPartialAttribute {
-                                        atype: "cn".to_string(),
-                                        vals: vec![cn.clone().into()],
-                                    },
-                                    // TODO
-                                    LdapPartialAttribute {
-                                        atype: "givenName".to_string(),
-                                        vals: vec!["".to_string().into()],
-                                    },
-                                    // TODO
-                                    LdapPartialAttribute {
-                                        atype: "sn".to_string(),
-                                        vals: vec![cn.clone().into()],
-                                    },
-                                ],
-                            }),
-                            req.gen_success(),
-                        ],
-                        Ok(false) => vec![req.gen_error(LdapResultCode::NoSuchObject, "CN not exist".to_string())],
-                        Err(_) => vec![req.gen_error(LdapResultCode::Unavailable, "Service internal error".to_string())],
-                    },
+        }
+        if let Some(new_password) = new_password {
+            if config.tls_required && !self.secure {
+                return gen_modify_resp(msgid, LdapResultCode::ConfidentialityRequired, "TLS is required");
+            }
+            if let Err((code, message)) = self.check_original_password(&account, original_password.as_deref(), config) {
+                return gen_modify_resp(msgid, code, &message);
+            }
+            if let Err(e) = ldap_processor::modify_password(&account, original_password.as_deref(), &new_password).await {
+                return gen_modify_resp(msgid, error_to_code(&e), &e.message);
+            }
+        }
+        if name.is_some() || mail.is_some() || phone.is_some() {
+            if let Err(e) = ldap_processor::modify_account(&account, name, mail, phone).await {
+                return gen_modify_resp(msgid, error_to_code(&e), &e.message);
+            }
+        }
+        gen_modify_resp(msgid, LdapResultCode::Success, "")
+    }
+
+    pub async fn do_password_modify(&mut self, msgid: i32, req: &LdapExtendedRequest, config: &IamLdapConfig) -> LdapMsg {
+        if !config.modify_enabled {
+            return gen_extended_resp(msgid, LdapResultCode::UnwillingToPerform, "Password modify is disabled", None);
+        }
+        if config.tls_required && !self.secure {
+            return gen_extended_resp(msgid, LdapResultCode::ConfidentialityRequired, "TLS is required", None);
+        }
+        let Some((user_identity, original_password, new_password)) = parse_password_modify_req(req.value.as_deref().unwrap_or_default()) else {
+            return gen_extended_resp(msgid, LdapResultCode::ProtocolError, "Request value is invalid", None);
+        };
+        // Generating a password by the server is not supported
+        let Some(new_password) = new_password else {
+            return gen_extended_resp(msgid, LdapResultCode::UnwillingToPerform, "The new password is required", None);
+        };
+        let account = match self.get_modify_target(user_identity.as_deref()).await {
+            Ok(account) => account,
+            Err((code, message)) => return gen_extended_resp(msgid, code, &message, None),
+        };
+        if let Err((code, message)) = self.check_original_password(&account, original_password.as_deref(), config) {
+            return gen_extended_resp(msgid, code, &message, None);
+        }
+        match ldap_processor::modify_password(&account, original_password.as_deref(), &new_password).await {
+            Ok(_) => gen_extended_resp(msgid, LdapResultCode::Success, "", None),
+            Err(e) => gen_extended_resp(msgid, error_to_code(&e), &e.message, None),
+        }
+    }
+
+    /// The administrator can modify any account, the account can only modify itself.
+    async fn get_modify_target(&self, dn_or_cn: Option<&str>) -> Result<LdapAccount, (LdapResultCode, String)> {
+        let cn = match (dn_or_cn, &self.bound) {
+            (_, LdapBound::Anonymous) => return Err((LdapResultCode::InsufficentAccessRights, "Bind is required".to_string())),
+            (Some(dn_or_cn), _) => {
+                if dn_or_cn.contains('=') {
+                    extract_cn(dn_or_cn).ok_or((LdapResultCode::NoSuchObject, "CN is invalid".to_string()))?
+                } else {
+                    dn_or_cn.to_string()
                 }
             }
-            _ => {
-                // TODO
-                Vec::new()
+            (None, LdapBound::Account(account)) => account.cn.clone(),
+            (None, LdapBound::Admin) => return Err((LdapResultCode::UnwillingToPerform, "The user identity is required".to_string())),
+        };
+        let account = match ldap_processor::get_account(&cn).await {
+            Ok(Some(account)) => account,
+            Ok(None) => return Err((LdapResultCode::NoSuchObject, "CN not exist".to_string())),
+            Err(_) => return Err((LdapResultCode::Unavailable, "Service internal error".to_string())),
+        };
+        self.check_modify_target(&account)?;
+        Ok(account)
+    }
+
+    fn check_modify_target(&self, account: &LdapAccount) -> Result<(), (LdapResultCode, String)> {
+        match &self.bound {
+            LdapBound::Anonymous => Err((LdapResultCode::InsufficentAccessRights, "Bind is required".to_string())),
+            LdapBound::Account(bound_account) if bound_account.id != account.id => {
+                Err((LdapResultCode::InsufficentAccessRights, "Only the account itself can be modified".to_string()))
             }
+            _ => Ok(()),
+        }
+    }
+
+    /// Only the administrator can reset the password of the accounts in the ``admin_reset_tenant_id`` tenant without the original password.
+    fn check_original_password(&self, account: &LdapAccount, original_password: Option<&str>, config: &IamLdapConfig) -> Result<(), (LdapResultCode, String)> {
+        let admin_reset = matches!(self.bound, LdapBound::Admin) && !config.admin_reset_tenant_id.is_empty() && account.tenant_id == config.admin_reset_tenant_id;
+        if original_password.is_none() && !admin_reset {
+            return Err((LdapResultCode::UnwillingToPerform, "The original password is required".to_string()));
         }
+        Ok(())
     }
 
     pub fn do_whoami(&mut self, req: &WhoamiRequest) -> LdapMsg {
@@ -214,6 +361,11 @@ impl LdapSession {
     }
 }
 
+/// Compare the digests so that neither the content nor the length of the password leaks through the timing.
+fn constant_time_eq(left: &str, right: &str) -> bool {
+    Sha256::digest(left.as_bytes()).iter().zip(Sha256::digest(right.as_bytes()).iter()).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
 fn extract_cn(dn: &str) -> Option<String> {
     match CN_R.captures(dn) {
         None => None,
@@ -221,57 +373,435 @@ fn extract_cn(dn: &str) -> Option<String> {
     }
 }
 
-#[allow(clippy::blocks_in_conditions)]
-async fn handle_client(socket: TcpStream, _addr: net::SocketAddr, config: Arc<IamConfig>) {
-    let config = &config.ldap;
+fn normalize_dn(dn: &str) -> String {
+    dn.split(',').map(|rdn| rdn.split('=').map(|s| s.trim()).collect::<Vec<_>>().join("=")).collect::<Vec<_>>().join(",").to_lowercase()
+}
+
+fn new_attr<T: ToString>(atype: &str, vals: Vec<T>) -> LdapPartialAttribute {
+    LdapPartialAttribute {
+        atype: atype.to_string(),
+        vals: vals.into_iter().map(|val| val.to_string().into_bytes()).collect(),
+    }
+}
+
+fn user_dn(cn: &str, config: &IamLdapConfig) -> String {
+    format!("CN={},DC={}", cn, config.dc)
+}
+
+fn group_dn(kind: &LdapGroupKind, cn: &str, config: &IamLdapConfig) -> String {
+    match kind {
+        LdapGroupKind::Role => format!("CN={},OU=Roles,DC={}", cn, config.dc),
+        LdapGroupKind::Org => format!("CN={},OU=Orgs,DC={}", cn, config.dc),
+    }
+}
+
+/// Build the user and group entries of the tenant.
+///
+/// When the filter requires an equality of cn, uid, sAMAccountName or mail, only the matching account with its groups
+/// and the matching groups with their members are loaded. The other entries loaded this way are incomplete,
+/// but they are dropped by the filter anyway.
+async fn build_entries(tenant_id: &str, filter: &LdapFilter, config: &IamLdapConfig) -> TardisResult<Vec<LdapSearchResultEntry>> {
+    let (accounts, groups) = match find_required_equality(filter, &["cn", "uid", "sAMAccountName", "mail"]) {
+        None => (
+            ldap_processor::find_accounts(tenant_id, None).await?,
+            ldap_processor::find_groups(tenant_id, &LdapGroupFilter::default()).await?,
+        ),
+        Some((atype, value)) => {
+            let account_id = if atype.eq_ignore_ascii_case("mail") {
+                ldap_processor::find_account_id_by_mail(tenant_id, value).await?
+            } else {
+                ldap_processor::find_account_id_by_cn(tenant_id, value).await?
+            };
+            let mut groups = match &account_id {
+                Some(account_id) => {
+                    ldap_processor::find_groups(
+                        tenant_id,
+                        &LdapGroupFilter {
+                            member_account_id: Some(account_id.clone()),
+                            ..Default::default()
+                        },
+                    )
+                    .await?
+                }
+                None => vec![],
+            };
+            let mut account_ids = account_id.into_iter().collect::<Vec<_>>();
+            // The groups only share the cn with the accounts
+            let code = if atype.eq_ignore_ascii_case("cn") {
+                ldap_processor::strip_cn_prefix(tenant_id, value)
+            } else {
+                None
+            };
+            if let Some(code) = code {
+                let named_groups = ldap_processor::find_groups(
+                    tenant_id,
+                    &LdapGroupFilter {
+                        code: Some(code.to_string()),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                groups.retain(|group| !named_groups.iter().any(|named_group| named_group.kind == group.kind && named_group.cn == group.cn));
+                account_ids.extend(named_groups.iter().flat_map(|group| group.member_account_ids.iter().cloned()));
+                groups.extend(named_groups);
+            }
+            account_ids.sort();
+            account_ids.dedup();
+            (ldap_processor::find_accounts(tenant_id, Some(account_ids)).await?, groups)
+        }
+    };
+    let account_dns = accounts.iter().map(|account| (account.id.as_str(), user_dn(&account.cn, config))).collect::<HashMap<_, _>>();
+    let mut member_of: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut entries = Vec::with_capacity(accounts.len() + groups.len());
+    for group in &groups {
+        let dn = group_dn(&group.kind, &group.cn, config);
+        let members = group.member_account_ids.iter().filter(|account_id| account_dns.contains_key(account_id.as_str())).collect::<Vec<_>>();
+        for account_id in &members {
+            member_of.entry(account_id.as_str()).or_default().push(dn.clone());
+        }
+        let mut attributes = vec![
+            new_attr("objectClass", vec!["top", "groupOfNames", "group"]),
+            new_attr("cn", vec![&group.cn]),
+            new_attr("displayName", vec![&group.name]),
+            new_attr("description", vec![&group.name]),
+        ];
+        if !members.is_empty() {
+            attributes.push(new_attr("member", members.iter().map(|account_id| &account_dns[account_id.as_str()]).collect()));
+        }
+        entries.push(LdapSearchResultEntry { dn, attributes });
+    }
+    for account in &accounts {
+        let mut attributes = vec![
+            new_attr("objectClass", vec!["top", "person", "organizationalPerson", "inetOrgPerson", "user"]),
+            new_attr("cn", vec![&account.cn]),
+            new_attr("uid", vec![&account.cn]),
+            new_attr("sAMAccountName", vec![&account.cn]),
+            new_attr("displayName", vec![&account.name]),
+            new_attr("sn", vec![&account.name]),
+        ];
+        if let Some(mail) = &account.mail {
+            attributes.push(new_attr("mail", vec![mail]));
+        }
+        if let Some(phone) = &account.phone {
+            attributes.push(new_attr("mobile", vec![phone]));
+            attributes.push(new_attr("telephoneNumber", vec![phone]));
+        }
+        if let Some(group_dns) = member_of.remove(account.id.as_str()) {
+            attributes.push(new_attr("memberOf", group_dns));
+        }
+        entries.push(LdapSearchResultEntry {
+            dn: account_dns[account.id.as_str()].clone(),
+            attributes,
+        });
+    }
+    Ok(entries)
+}
+
+fn in_scope(dn: &str, base: &str, scope: &LdapSearchScope) -> bool {
+    let dn = normalize_dn(dn);
+    match scope {
+        LdapSearchScope::Base => dn == base,
+        LdapSearchScope::OneLevel => dn.strip_suffix(base).and_then(|rdn| rdn.strip_suffix(',')).is_some_and(|rdn| !rdn.is_empty() && !rdn.contains(',')),
+        _ => dn == base || dn.ends_with(&format!(",{base}")),
+    }
+}
+
+fn attr_values(entry: &LdapSearchResultEntry, atype: &str) -> Vec<String> {
+    entry
+        .attributes
+        .iter()
+        .filter(|attr| attr.atype.eq_ignore_ascii_case(atype))
+        .flat_map(|attr| attr.vals.iter().map(|val| String::from_utf8_lossy(val).to_string()))
+        .collect()
+}
+
+fn filter_matches(entry: &LdapSearchResultEntry, filter: &LdapFilter) -> bool {
+    match filter {
+        LdapFilter::And(filters) => filters.iter().all(|filter| filter_matches(entry, filter)),
+        LdapFilter::Or(filters) => filters.iter().any(|filter| filter_matches(entry, filter)),
+        LdapFilter::Not(filter) => !filter_matches(entry, filter),
+        LdapFilter::Present(atype) => atype.eq_ignore_ascii_case("objectClass") || !attr_values(entry, atype).is_empty(),
+        LdapFilter::Equality(atype, value) => {
+            // The values of member and memberOf are DNs, so compare them in the normalized form
+            let value = normalize_dn(value);
+            attr_values(entry, atype).iter().any(|val| normalize_dn(val) == value)
+        }
+        LdapFilter::Substring(atype, substring) => attr_values(entry, atype).iter().any(|val| substring_matches(val, substring)),
+        _ => false,
+    }
+}
+
+fn substring_matches(value: &str, filter: &LdapSubstringFilter) -> bool {
+    let value = value.to_lowercase();
+    let mut rest = value.as_str();
+    if let Some(initial) = &filter.initial {
+        match rest.strip_prefix(initial.to_lowercase().as_str()) {
+            Some(r) => rest = r,
+            None => return false,
+        }
+    }
+    for any in &filter.any {
+        let any = any.to_lowercase();
+        match rest.find(&any) {
+            Some(pos) => rest = &rest[pos + any.len()..],
+            None => return false,
+        }
+    }
+    match &filter.final_ {
+        Some(final_) => rest.ends_with(&final_.to_lowercase()),
+        None => true,
+    }
+}
+
+/// Find the equality that all matching entries must satisfy, return the attribute type and the value
+fn find_required_equality<'a>(filter: &'a LdapFilter, atypes: &[&str]) -> Option<(&'a str, &'a str)> {
+    match filter {
+        LdapFilter::Equality(atype, value) if atypes.iter().any(|a| a.eq_ignore_ascii_case(atype)) => Some((atype.as_str(), value.as_str())),
+        LdapFilter::And(filters) => filters.iter().find_map(|filter| find_required_equality(filter, atypes)),
+        _ => None,
+    }
+}
+
+fn find_equality_value<'a>(filter: &'a LdapFilter, atypes: &[&str]) -> Option<&'a str> {
+    match filter {
+        LdapFilter::Equality(atype, value) if atypes.iter().any(|a| a.eq_ignore_ascii_case(atype)) => Some(value.as_str()),
+        LdapFilter::And(filters) | LdapFilter::Or(filters) => filters.iter().find_map(|filter| find_equality_value(filter, atypes)),
+        _ => None,
+    }
+}
+
+/// Take a page of the entries, the cookie is the offset of the page, return the page and the cookie of the next page.
+///
+/// A size of zero means abandoning the paged search, the cookie of the next page is empty when there is no more entry.
+fn take_page<T>(entries: Vec<T>, size: usize, cookie: &str, max_page_size: usize) -> Option<(Vec<T>, String)> {
+    let offset = if cookie.is_empty() { 0 } else { cookie.parse::<usize>().ok()?.min(entries.len()) };
+    let size = size.min(max_page_size);
+    let end = (offset + size).min(entries.len());
+    let next_cookie = if size > 0 && end < entries.len() { end.to_string() } else { "".to_string() };
+    Some((entries.into_iter().skip(offset).take(end - offset).collect(), next_cookie))
+}
+
+fn select_attrs(mut entry: LdapSearchResultEntry, attrs: &[String]) -> LdapSearchResultEntry {
+    if attrs.is_empty() || attrs.iter().any(|attr| attr == "*") {
+        return entry;
+    }
+    // "1.1" means no attributes, which is filtered out naturally
+    entry.attributes.retain(|attr| attrs.iter().any(|a| a.eq_ignore_ascii_case(&attr.atype)));
+    entry
+}
+
+fn ldap_result(code: LdapResultCode, message: &str) -> LdapResult {
+    LdapResult {
+        code,
+        matcheddn: "".to_string(),
+        message: message.to_string(),
+        referral: vec![],
+    }
+}
+
+fn gen_modify_resp(msgid: i32, code: LdapResultCode, message: &str) -> LdapMsg {
+    LdapMsg {
+        msgid,
+        op: LdapOp::ModifyResponse(ldap_result(code, message)),
+        ctrl: vec![],
+    }
+}
+
+fn gen_extended_resp(msgid: i32, code: LdapResultCode, message: &str, name: Option<String>) -> LdapMsg {
+    LdapMsg {
+        msgid,
+        op: LdapOp::ExtendedResponse(LdapExtendedResponse {
+            res: ldap_result(code, message),
+            name,
+            value: None,
+        }),
+        ctrl: vec![],
+    }
+}
+
+fn error_to_code(error: &TardisError) -> LdapResultCode {
+    match error.code.get(..3) {
+        Some("400") | Some("409") => LdapResultCode::ConstraintViolation,
+        Some("401") => LdapResultCode::InvalidCredentials,
+        Some("404") => LdapResultCode::NoSuchObject,
+        _ => LdapResultCode::Other,
+    }
+}
+
+/// Read a BER element, return the tag, the content and the rest
+fn read_ber(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, data) = data.split_first()?;
+    let (&len, data) = data.split_first()?;
+    let (len, data) = if len & 0x80 == 0 {
+        (len as usize, data)
+    } else {
+        let len_bytes = (len & 0x7f) as usize;
+        if len_bytes == 0 || len_bytes > 4 || data.len() < len_bytes {
+            return None;
+        }
+        (data[..len_bytes].iter().fold(0usize, |len, b| (len << 8) | *b as usize), &data[len_bytes..])
+    };
+    if data.len() < len {
+        return None;
+    }
+    Some((tag, &data[..len], &data[len..]))
+}
+
+/// Parse the value of the PasswordModify request, return the user identity, the old password and the new password.
+///
+/// ```text
+/// PasswdModifyRequestValue ::= SEQUENCE {
+///   userIdentity    [0]  OCTET STRING OPTIONAL
+///   oldPasswd       [1]  OCTET STRING OPTIONAL
+///   newPasswd       [2]  OCTET STRING OPTIONAL }
+/// ```
+fn parse_password_modify_req(value: &[u8]) -> Option<(Option<String>, Option<String>, Option<String>)> {
+    let mut result = (None, None, None);
+    if value.is_empty() {
+        return Some(result);
+    }
+    let (tag, mut content, _) = read_ber(value)?;
+    if tag != 0x30 {
+        return None;
+    }
+    while !content.is_empty() {
+        let (tag, field, rest) = read_ber(content)?;
+        let field = Some(String::from_utf8(field.to_vec()).ok()?);
+        match tag {
+            0x80 => result.0 = field,
+            0x81 => result.1 = field,
+            0x82 => result.2 = field,
+            _ => return None,
+        }
+        content = rest;
+    }
+    Some(result)
+}
+
+/// Serve the LDAP messages, return the stream when it should be upgraded by StartTLS.
+async fn serve<S>(socket: S, session: &mut LdapSession, config: &IamLdapConfig) -> Option<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     let (r, w) = tokio::io::split(socket);
     let mut reqs = FramedRead::new(r, LdapCodec);
     let mut resp = FramedWrite::new(w, LdapCodec);
 
-    let mut session = LdapSession { dn: "Anonymous".to_string() };
-
     while let Some(msg) = reqs.next().await {
-        let server_op = match msg.map_err(|_e| ()).and_then(|msg| {
-            trace!("[TardisLdapServer] Received message:{:?}", msg);
-            ServerOps::try_from(msg)
-        }) {
-            Ok(v) => v,
-            Err(_) => {
-                let _err = resp.send(DisconnectionNotice::gen(LdapResultCode::Other, "Internal Server Error")).await;
-                let _err = resp.flush().await;
-                return;
-            }
+        let Ok(msg) = msg else {
+            let _err = resp.send(DisconnectionNotice::gen(LdapResultCode::Other, "Internal Server Error")).await;
+            let _err = resp.flush().await;
+            return None;
         };
-
-        let result = match server_op {
-            ServerOps::SimpleBind(req) => vec![session.do_bind(&req, config).await],
-            ServerOps::Search(req) => session.do_search(&req, config).await,
-            ServerOps::Unbind(_) => {
-                // No need to notify on unbind (per rfc4511)
-                return;
+        trace!("[TardisLdapServer] Received message:{:?}", msg);
+        let result = match msg.op {
+            LdapOp::ExtendedRequest(ext_req) if ext_req.name == OID_START_TLS => {
+                if !session.start_tls_enabled || session.secure {
+                    vec![gen_extended_resp(
+                        msg.msgid,
+                        LdapResultCode::Unavailable,
+                        "StartTLS is not available",
+                        Some(OID_START_TLS.to_string()),
+                    )]
+                } else {
+                    if resp.send(gen_extended_resp(msg.msgid, LdapResultCode::Success, "", Some(OID_START_TLS.to_string()))).await.is_err() || resp.flush().await.is_err() {
+                        return None;
+                    }
+                    // The client must not send any message before the TLS handshake
+                    if !reqs.read_buffer().is_empty() {
+                        return None;
+                    }
+                    return Some(reqs.into_inner().unsplit(resp.into_inner()));
+                }
             }
-            ServerOps::Whoami(req) => vec![session.do_whoami(&req)],
-            ServerOps::Compare(_) => {
-                // No need to notify on Compare (per rfc4511)
-                return;
+            LdapOp::ExtendedRequest(ext_req) if ext_req.name == OID_PASSWORD_MODIFY => vec![session.do_password_modify(msg.msgid, &ext_req, config).await],
+            LdapOp::ModifyRequest(modify_req) => vec![session.do_modify(msg.msgid, &modify_req, config).await],
+            op => {
+                let ctrl = msg.ctrl.clone();
+                let Ok(server_op) = ServerOps::try_from(LdapMsg {
+                    msgid: msg.msgid,
+                    op,
+                    ctrl: msg.ctrl,
+                }) else {
+                    let _err = resp.send(DisconnectionNotice::gen(LdapResultCode::Other, "Internal Server Error")).await;
+                    let _err = resp.flush().await;
+                    return None;
+                };
+                match server_op {
+                    ServerOps::SimpleBind(req) => vec![session.do_bind(&req, config).await],
+                    ServerOps::Search(req) => session.do_search(&req, &ctrl, config).await,
+                    ServerOps::Unbind(_) => {
+                        // No need to notify on unbind (per rfc4511)
+                        return None;
+                    }
+                    ServerOps::Whoami(req) => vec![session.do_whoami(&req)],
+                    ServerOps::Compare(_) => {
+                        // No need to notify on Compare (per rfc4511)
+                        return None;
+                    }
+                }
             }
         };
 
         for rmsg in result.into_iter() {
             if resp.send(rmsg).await.is_err() {
-                return;
+                return None;
             }
         }
         if resp.flush().await.is_err() {
-            return;
+            return None;
         }
     }
+    None
 }
 
-pub async fn start() -> TardisResult<()> {
-    let config = TardisFuns::cs_config::<IamConfig>(iam_constants::COMPONENT_CODE);
+async fn handle_client(socket: TcpStream, addr: net::SocketAddr, config: Arc<IamConfig>, tls_acceptor: Option<TlsAcceptor>, implicit_tls: bool) {
     let config = &config.ldap;
-    let addr_str = format!("0.0.0.0:{}", config.port);
+    let mut session = LdapSession {
+        dn: "Anonymous".to_string(),
+        bound: LdapBound::Anonymous,
+        secure: false,
+        start_tls_enabled: tls_acceptor.is_some() && !implicit_tls,
+    };
+    let socket = if implicit_tls {
+        Some(socket)
+    } else {
+        // Returned only when StartTLS is requested
+        serve(socket, &mut session, config).await
+    };
+    let (Some(socket), Some(tls_acceptor)) = (socket, tls_acceptor) else {
+        return;
+    };
+    match tls_acceptor.accept(socket).await {
+        Ok(socket) => {
+            session.secure = true;
+            serve(socket, &mut session, config).await;
+        }
+        Err(e) => error!("[TardisLdapServer] TLS handshake with {} error: {}", addr, e.to_string()),
+    }
+}
+
+fn build_tls_acceptor(config: &IamLdapConfig) -> TardisResult<Option<TlsAcceptor>> {
+    if config.tls_cert.trim().is_empty() || config.tls_key.trim().is_empty() {
+        return Ok(None);
+    }
+    let certs = rustls_pemfile::certs(&mut config.tls_cert.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TardisError::format_error(&format!("[TardisLdapServer] Certificate error: {e:?}"), "406-iam-ldap-tls-error"))?;
+    let key = rustls_pemfile::private_key(&mut config.tls_key.as_bytes())
+        .map_err(|e| TardisError::format_error(&format!("[TardisLdapServer] Private key error: {e:?}"), "406-iam-ldap-tls-error"))?
+        .ok_or_else(|| TardisError::format_error("[TardisLdapServer] Private key not found", "406-iam-ldap-tls-error"))?;
+    let tls_config = ServerConfig::builder_with_provider(Arc::new(tokio_rustls::rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| TardisError::format_error(&format!("[TardisLdapServer] TLS error: {e:?}"), "406-iam-ldap-tls-error"))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| TardisError::format_error(&format!("[TardisLdapServer] TLS error: {e:?}"), "406-iam-ldap-tls-error"))?;
+    Ok(Some(TlsAcceptor::from(Arc::new(tls_config))))
+}
+
+async fn listen(port: u16, tls_acceptor: Option<TlsAcceptor>, implicit_tls: bool) -> TardisResult<String> {
+    let addr_str = format!("0.0.0.0:{}", port);
     let addr = net::SocketAddr::from_str(&addr_str).map_err(|e| TardisError::format_error(&format!("[TardisLdapServer] Address error: {e:?}"), "406-iam-ldap-addr-error"))?;
     let listener = Box::new(TcpListener::bind(&addr).await?);
     tokio::spawn(async move {
@@ -279,7 +809,7 @@ pub async fn start() -> TardisResult<()> {
             match listener.accept().await {
                 Ok((socket, addr)) => {
                     let config = TardisFuns::cs_config::<IamConfig>(iam_constants::COMPONENT_CODE);
-                    tokio::spawn(handle_client(socket, addr, config));
+                    tokio::spawn(handle_client(socket, addr, config, tls_acceptor.clone(), implicit_tls));
                 }
                 Err(e) => {
                     error!("[TardisLdapServer] Received error: {}", e.to_string())
@@ -287,6 +817,332 @@ pub async fn start() -> TardisResult<()> {
             }
         }
     });
+    Ok(addr_str)
+}
+
+pub async fn start() -> TardisResult<()> {
+    let config = TardisFuns::cs_config::<IamConfig>(iam_constants::COMPONENT_CODE);
+    let config = &config.ldap;
+    if config.modify_enabled && (config.bind_password.is_empty() || config.bind_password == IamLdapConfig::default().bind_password) {
+        return Err(TardisError::format_error(
+            "[TardisLdapServer] Modify requires the bind password to be changed from the default value",
+            "406-iam-ldap-config-error",
+        ));
+    }
+    let tls_acceptor = build_tls_acceptor(config)?;
+    if config.tls_port > 0 && tls_acceptor.is_none() {
+        return Err(TardisError::format_error(
+            "[TardisLdapServer] LDAPS requires the certificate and the private key",
+            "406-iam-ldap-tls-error",
+        ));
+    }
+    let addr_str = listen(config.port, tls_acceptor.clone(), false).await?;
     info!("[TardisLdapServer] Started ldap://{}", addr_str);
+    if config.tls_port > 0 {
+        let addr_str = listen(config.tls_port, tls_acceptor, true).await?;
+        info!("[TardisLdapServer] Started ldaps://{}", addr_str);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use tardis::tokio;
+
+    use super::*;
+
+    fn user_entry() -> LdapSearchResultEntry {
+        LdapSearchResultEntry {
+            dn: "CN=74656e616e74/alice,DC=bios".to_string(),
+            attributes: vec![
+                new_attr("objectClass", vec!["top", "person"]),
+                new_attr("cn", vec!["74656e616e74/alice"]),
+                new_attr("displayName", vec!["Alice Smith"]),
+                new_attr("memberOf", vec!["CN=74656e616e74/admin,OU=Roles,DC=bios"]),
+            ],
+        }
+    }
+
+    fn equality(atype: &str, value: &str) -> LdapFilter {
+        LdapFilter::Equality(atype.to_string(), value.to_string())
+    }
+
+    fn substring(initial: Option<&str>, any: Vec<&str>, final_: Option<&str>) -> LdapSubstringFilter {
+        LdapSubstringFilter {
+            initial: initial.map(|s| s.to_string()),
+            any: any.into_iter().map(|s| s.to_string()).collect(),
+            final_: final_.map(|s| s.to_string()),
+        }
+    }
+
+    fn account(id: &str) -> LdapAccount {
+        LdapAccount {
+            id: id.to_string(),
+            tenant_id: "tenant".to_string(),
+            cn: format!("74656e616e74/{id}"),
+            ak: id.to_string(),
+            name: id.to_string(),
+            mail: None,
+            phone: None,
+        }
+    }
+
+    fn session(bound: LdapBound) -> LdapSession {
+        LdapSession {
+            dn: "".to_string(),
+            bound,
+            secure: true,
+            start_tls_enabled: false,
+        }
+    }
+
+    fn password_modify_value(fields: &[(u8, &str)]) -> Vec<u8> {
+        let content = fields.iter().flat_map(|(tag, value)| [vec![*tag, value.len() as u8], value.as_bytes().to_vec()].concat()).collect::<Vec<_>>();
+        [vec![0x30, content.len() as u8], content].concat()
+    }
+
+    fn result_code(msg: &LdapMsg) -> LdapResultCode {
+        match &msg.op {
+            LdapOp::ExtendedResponse(resp) => resp.res.code.clone(),
+            LdapOp::ModifyResponse(res) => res.code.clone(),
+            _ => panic!("unexpected response {:?}", msg.op),
+        }
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let entry = user_entry();
+        assert!(filter_matches(&entry, &equality("CN", "74656E616E74/ALICE")));
+        assert!(!filter_matches(&entry, &equality("cn", "74656e616e74/bob")));
+        // The DN values are compared in the normalized form
+        assert!(filter_matches(&entry, &equality("memberOf", "cn = 74656e616e74/admin, ou=roles, dc=bios")));
+
+        assert!(filter_matches(&entry, &LdapFilter::Present("objectClass".to_string())));
+        assert!(filter_matches(&entry, &LdapFilter::Present("displayName".to_string())));
+        assert!(!filter_matches(&entry, &LdapFilter::Present("mail".to_string())));
+
+        assert!(filter_matches(
+            &entry,
+            &LdapFilter::And(vec![equality("objectClass", "person"), equality("cn", "74656e616e74/alice")])
+        ));
+        assert!(!filter_matches(
+            &entry,
+            &LdapFilter::And(vec![equality("objectClass", "person"), equality("cn", "74656e616e74/bob")])
+        ));
+        assert!(filter_matches(
+            &entry,
+            &LdapFilter::Or(vec![equality("cn", "74656e616e74/bob"), equality("cn", "74656e616e74/alice")])
+        ));
+        assert!(!filter_matches(
+            &entry,
+            &LdapFilter::Or(vec![equality("cn", "74656e616e74/bob"), LdapFilter::Present("mail".to_string())])
+        ));
+        assert!(filter_matches(&entry, &LdapFilter::Not(Box::new(equality("objectClass", "groupOfNames")))));
+        assert!(!filter_matches(&entry, &LdapFilter::Not(Box::new(equality("objectClass", "person")))));
+
+        assert!(filter_matches(
+            &entry,
+            &LdapFilter::Substring("displayName".to_string(), substring(Some("alice"), vec![], None))
+        ));
+        assert!(filter_matches(
+            &entry,
+            &LdapFilter::Substring("displayName".to_string(), substring(None, vec![], Some("SMITH")))
+        ));
+        assert!(!filter_matches(&entry, &LdapFilter::Substring("mail".to_string(), substring(Some("alice"), vec![], None))));
+        // Unsupported filters never match
+        assert!(!filter_matches(&entry, &LdapFilter::Approx("cn".to_string(), "74656e616e74/alice".to_string())));
+    }
+
+    #[test]
+    fn test_substring_matches() {
+        assert!(substring_matches("Alice Smith", &substring(Some("ali"), vec!["e s"], Some("th"))));
+        assert!(substring_matches("Alice Smith", &substring(None, vec!["ice", "mi"], None)));
+        assert!(!substring_matches("Alice Smith", &substring(Some("smi"), vec![], None)));
+        assert!(!substring_matches("Alice Smith", &substring(None, vec![], Some("alice"))));
+        // The parts must appear in order without overlapping
+        assert!(!substring_matches("Alice Smith", &substring(None, vec!["smi", "ali"], None)));
+        assert!(!substring_matches("abc", &substring(Some("ab"), vec![], Some("bc"))));
+    }
+
+    #[test]
+    fn test_find_required_equality() {
+        let atypes = ["cn", "uid", "sAMAccountName", "mail"];
+        assert_eq!(find_required_equality(&equality("UID", "alice"), &atypes), Some(("UID", "alice")));
+        assert_eq!(
+            find_required_equality(&LdapFilter::And(vec![equality("objectClass", "person"), equality("mail", "alice@example.com")]), &atypes),
+            Some(("mail", "alice@example.com"))
+        );
+        // The equalities under Or and Not are not required
+        assert_eq!(find_required_equality(&LdapFilter::Or(vec![equality("cn", "alice"), equality("cn", "bob")]), &atypes), None);
+        assert_eq!(find_required_equality(&LdapFilter::Not(Box::new(equality("cn", "alice"))), &atypes), None);
+        assert_eq!(find_required_equality(&equality("displayName", "alice"), &atypes), None);
+    }
+
+    #[test]
+    fn test_in_scope() {
+        let base = normalize_dn("DC=bios");
+        assert!(in_scope("CN=alice,DC=bios", &base, &LdapSearchScope::Subtree));
+        assert!(in_scope("CN=admin,OU=Roles,DC=bios", &base, &LdapSearchScope::Subtree));
+        assert!(in_scope("CN=alice,DC=bios", &base, &LdapSearchScope::OneLevel));
+        assert!(!in_scope("CN=admin,OU=Roles,DC=bios", &base, &LdapSearchScope::OneLevel));
+        assert!(!in_scope("CN=alice,DC=bios", &base, &LdapSearchScope::Base));
+        assert!(in_scope("CN=alice, DC=bios", &normalize_dn("cn=alice,dc=bios"), &LdapSearchScope::Base));
+    }
+
+    #[test]
+    fn test_take_page() {
+        let entries = (0..5).collect::<Vec<_>>();
+        let (page, cookie) = take_page(entries.clone(), 2, "", 1000).unwrap();
+        assert_eq!(page, vec![0, 1]);
+        assert_eq!(cookie, "2");
+        let (page, cookie) = take_page(entries.clone(), 2, &cookie, 1000).unwrap();
+        assert_eq!(page, vec![2, 3]);
+        assert_eq!(cookie, "4");
+        let (page, cookie) = take_page(entries.clone(), 2, &cookie, 1000).unwrap();
+        assert_eq!(page, vec![4]);
+        assert_eq!(cookie, "");
+
+        // The page size is limited by the config
+        let (page, cookie) = take_page(entries.clone(), 10, "", 3).unwrap();
+        assert_eq!(page, vec![0, 1, 2]);
+        assert_eq!(cookie, "3");
+        // A size of zero abandons the paged search
+        let (page, cookie) = take_page(entries.clone(), 0, "2", 1000).unwrap();
+        assert!(page.is_empty());
+        assert_eq!(cookie, "");
+        // The offset beyond the entries returns nothing
+        let (page, cookie) = take_page(entries.clone(), 2, "10", 1000).unwrap();
+        assert!(page.is_empty());
+        assert_eq!(cookie, "");
+        assert!(take_page(entries.clone(), 2, "abc", 1000).is_none());
+        assert!(take_page(entries, 2, "-1", 1000).is_none());
+    }
+
+    #[test]
+    fn test_parse_password_modify_req() {
+        assert_eq!(parse_password_modify_req(&[]), Some((None, None, None)));
+        assert_eq!(
+            parse_password_modify_req(&password_modify_value(&[(0x80, "CN=alice,DC=bios"), (0x81, "old"), (0x82, "new")])),
+            Some((Some("CN=alice,DC=bios".to_string()), Some("old".to_string()), Some("new".to_string())))
+        );
+        assert_eq!(
+            parse_password_modify_req(&password_modify_value(&[(0x82, "new")])),
+            Some((None, None, Some("new".to_string())))
+        );
+        // The long form of the length
+        let password = "p".repeat(200);
+        let mut value = vec![0x30, 0x81, 203, 0x82, 0x81, 200];
+        value.extend(password.as_bytes());
+        assert_eq!(parse_password_modify_req(&value), Some((None, None, Some(password))));
+
+        // Not a sequence
+        assert_eq!(parse_password_modify_req(&[0x04, 0x03, b'a', b'b', b'c']), None);
+        // Unknown field
+        assert_eq!(parse_password_modify_req(&password_modify_value(&[(0x83, "new")])), None);
+        // Truncated content
+        let mut value = password_modify_value(&[(0x81, "old"), (0x82, "new")]);
+        value.truncate(value.len() - 1);
+        assert_eq!(parse_password_modify_req(&value), None);
+        // The field is longer than the sequence
+        assert_eq!(parse_password_modify_req(&[0x30, 0x03, 0x82, 0x05, b'a']), None);
+        // Invalid length bytes
+        assert_eq!(parse_password_modify_req(&[0x30, 0x80]), None);
+        assert_eq!(parse_password_modify_req(&[0x30, 0x85, 0, 0, 0, 0, 1, 0]), None);
+        assert_eq!(parse_password_modify_req(&[0x30]), None);
+        // Invalid UTF-8
+        assert_eq!(parse_password_modify_req(&[0x30, 0x04, 0x82, 0x02, 0xff, 0xfe]), None);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("KDi234!ds", "KDi234!ds"));
+        assert!(!constant_time_eq("KDi234!ds", "KDi234!dS"));
+        assert!(!constant_time_eq("KDi234!ds", "KDi234!ds "));
+        assert!(!constant_time_eq("KDi234!ds", ""));
+    }
+
+    #[test]
+    fn test_modify_permission() {
+        let alice = account("alice");
+        let bob = account("bob");
+
+        let config = IamLdapConfig {
+            admin_reset_tenant_id: "tenant".to_string(),
+            ..Default::default()
+        };
+        let other_tenant_account = LdapAccount {
+            tenant_id: "other".to_string(),
+            ..account("carol")
+        };
+
+        let admin = session(LdapBound::Admin);
+        assert!(admin.check_modify_target(&bob).is_ok());
+        assert!(admin.check_original_password(&bob, None, &config).is_ok());
+        // The administrator can only reset the passwords of the configured tenant
+        assert_eq!(
+            admin.check_original_password(&other_tenant_account, None, &config).unwrap_err().0,
+            LdapResultCode::UnwillingToPerform
+        );
+        assert!(admin.check_original_password(&other_tenant_account, Some("old"), &config).is_ok());
+        assert_eq!(
+            admin.check_original_password(&bob, None, &IamLdapConfig::default()).unwrap_err().0,
+            LdapResultCode::UnwillingToPerform
+        );
+
+        let bound = session(LdapBound::Account(account("alice")));
+        assert!(bound.check_modify_target(&alice).is_ok());
+        assert_eq!(bound.check_modify_target(&bob).unwrap_err().0, LdapResultCode::InsufficentAccessRights);
+        assert!(bound.check_original_password(&alice, Some("old"), &config).is_ok());
+        assert_eq!(bound.check_original_password(&alice, None, &config).unwrap_err().0, LdapResultCode::UnwillingToPerform);
+
+        let anonymous = session(LdapBound::Anonymous);
+        assert_eq!(anonymous.check_modify_target(&alice).unwrap_err().0, LdapResultCode::InsufficentAccessRights);
+        assert_eq!(anonymous.check_original_password(&alice, None, &config).unwrap_err().0, LdapResultCode::UnwillingToPerform);
+    }
+
+    #[tokio::test]
+    async fn test_do_password_modify() {
+        let config = IamLdapConfig {
+            modify_enabled: true,
+            ..Default::default()
+        };
+        let req = |value: Vec<u8>| LdapExtendedRequest {
+            name: OID_PASSWORD_MODIFY.to_string(),
+            value: Some(value),
+        };
+        let mut admin = session(LdapBound::Admin);
+        assert_eq!(
+            result_code(&admin.do_password_modify(1, &req(vec![0x30, 0x05, 0x82]), &config).await),
+            LdapResultCode::ProtocolError
+        );
+        assert_eq!(
+            result_code(&admin.do_password_modify(1, &req(password_modify_value(&[(0x80, "CN=alice,DC=bios")])), &config).await),
+            LdapResultCode::UnwillingToPerform
+        );
+        // The administrator must specify the user identity
+        assert_eq!(
+            result_code(&admin.do_password_modify(1, &req(password_modify_value(&[(0x82, "new")])), &config).await),
+            LdapResultCode::UnwillingToPerform
+        );
+        let mut anonymous = session(LdapBound::Anonymous);
+        assert_eq!(
+            result_code(&anonymous.do_password_modify(1, &req(password_modify_value(&[(0x81, "old"), (0x82, "new")])), &config).await),
+            LdapResultCode::InsufficentAccessRights
+        );
+        assert_eq!(
+            result_code(&admin.do_password_modify(1, &req(password_modify_value(&[(0x80, "CN=alice,DC=bios"), (0x82, "new")])), &IamLdapConfig::default()).await),
+            LdapResultCode::UnwillingToPerform
+        );
+        let plain_config = IamLdapConfig {
+            modify_enabled: true,
+            tls_required: true,
+            ..Default::default()
+        };
+        let mut plain = session(LdapBound::Admin);
+        plain.secure = false;
+        assert_eq!(
+            result_code(&plain.do_password_modify(1, &req(password_modify_value(&[(0x80, "CN=alice,DC=bios"), (0x82, "new")])), &plain_config).await),
+            LdapResultCode::ConfidentialityRequired
+        );
+    }
+}
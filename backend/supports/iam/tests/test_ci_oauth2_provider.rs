@@ -0,0 +1,444 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use bios_iam::basic::dto::iam_cert_dto::IamCertAkSkAddReq;
+use bios_iam::basic::dto::iam_oauth2_provider_dto::{IamOAuth2AuthorizeReq, IamOAuth2ClientConf, IamOAuth2TokenOpReq, IamOAuth2TokenReq, IamOidcIdTokenClaims};
+use bios_iam::basic::serv::iam_cert_aksk_serv::IamCertAkSkServ;
+use bios_iam::basic::serv::iam_oauth2_provider_serv::IamOAuth2ProviderServ;
+use bios_iam::console_interface::serv::iam_ci_cert_aksk_serv::IamCiCertAkSkServ;
+use bios_iam::console_interface::serv::iam_ci_oauth2_token_serv::IamCiOauth2AkSkServ;
+use bios_iam::iam_constants;
+use bios_iam::iam_enumeration::Oauth2GrantType;
+use jsonwebtoken::{DecodingKey, Validation};
+use percent_encoding::percent_decode_str;
+use sha2::{Digest, Sha256};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::futures::future::join_all;
+use tardis::log::info;
+
+pub async fn test(context1: &TardisContext) -> TardisResult<()> {
+    let funs = iam_constants::get_tardis_inst();
+
+    info!("【test_ci_oauth2_provider】 : Add Clients");
+    let client = IamCiCertAkSkServ::general_cert(
+        IamCertAkSkAddReq {
+            tenant_id: context1.own_paths.clone(),
+            app_id: None,
+            oauth2_client: Some(IamOAuth2ClientConf {
+                redirect_uris: vec!["https://app1.example.com/callback".to_string()],
+                grant_types: vec![Oauth2GrantType::AuthorizationCode, Oauth2GrantType::RefreshToken, Oauth2GrantType::ClientCredentials],
+                scopes: vec!["openid".to_string(), "profile".to_string(), "email".to_string()],
+                public_client: false,
+            }),
+            scim: None,
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    let public_client = IamCiCertAkSkServ::general_cert(
+        IamCertAkSkAddReq {
+            tenant_id: context1.own_paths.clone(),
+            app_id: None,
+            oauth2_client: None,
+            scim: None,
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    IamCertAkSkServ::modify_oauth2_client(
+        &public_client.id,
+        &IamOAuth2ClientConf {
+            redirect_uris: vec!["https://spa.example.com/callback".to_string()],
+            grant_types: vec![Oauth2GrantType::AuthorizationCode, Oauth2GrantType::ClientCredentials],
+            scopes: vec!["openid".to_string()],
+            public_client: true,
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    let legacy_client = IamCiCertAkSkServ::general_cert(
+        IamCertAkSkAddReq {
+            tenant_id: context1.own_paths.clone(),
+            app_id: None,
+            oauth2_client: None,
+            scim: None,
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+
+    info!("【test_ci_oauth2_provider】 : Authorization Code Grant");
+    let code_verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string();
+    let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+    let authorize_req = IamOAuth2AuthorizeReq {
+        response_type: "code".to_string(),
+        client_id: client.ak.clone(),
+        redirect_uri: None,
+        scope: Some("openid profile".to_string()),
+        state: Some("s 1".to_string()),
+        code_challenge: Some(code_challenge.clone()),
+        code_challenge_method: Some("S256".to_string()),
+        nonce: Some("n1".to_string()),
+    };
+    assert_eq!(
+        IamOAuth2ProviderServ::authorize(
+            &IamOAuth2AuthorizeReq {
+                redirect_uri: Some("https://evil.example.com/callback".to_string()),
+                ..authorize_req.clone()
+            },
+            &funs,
+            context1
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-invalid_request"
+    );
+    assert_eq!(
+        IamOAuth2ProviderServ::authorize(
+            &IamOAuth2AuthorizeReq {
+                scope: Some("openid phone".to_string()),
+                ..authorize_req.clone()
+            },
+            &funs,
+            context1
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-invalid_scope"
+    );
+    let redirect_uri = IamOAuth2ProviderServ::authorize(&authorize_req, &funs, context1).await?;
+    assert!(redirect_uri.starts_with("https://app1.example.com/callback?code="));
+    assert!(redirect_uri.ends_with("&state=s%201"));
+    let code = percent_decode_str(redirect_uri.split("code=").nth(1).unwrap().split('&').next().unwrap()).decode_utf8_lossy().to_string();
+
+    let token_req = IamOAuth2TokenReq {
+        grant_type: "authorization_code".to_string(),
+        client_id: Some(client.ak.clone()),
+        client_secret: Some(client.sk.clone()),
+        code: Some(code.clone()),
+        code_verifier: Some("invalid".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        IamOAuth2ProviderServ::token(&token_req, None, &funs).await.unwrap_err().code,
+        "400-iam-oauth2-invalid_grant"
+    );
+    // the code is consumed even if the exchange failed
+    assert_eq!(
+        IamOAuth2ProviderServ::token(
+            &IamOAuth2TokenReq {
+                code_verifier: Some(code_verifier.clone()),
+                ..token_req
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-invalid_grant"
+    );
+    let code = percent_decode_str(IamOAuth2ProviderServ::authorize(&authorize_req, &funs, context1).await?.split("code=").nth(1).unwrap().split('&').next().unwrap())
+        .decode_utf8_lossy()
+        .to_string();
+    assert_eq!(
+        IamOAuth2ProviderServ::token(
+            &IamOAuth2TokenReq {
+                grant_type: "authorization_code".to_string(),
+                client_id: Some(client.ak.clone()),
+                client_secret: Some("invalid".to_string()),
+                code: Some(code.clone()),
+                code_verifier: Some(code_verifier.clone()),
+                ..Default::default()
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-oauth2-invalid_client"
+    );
+    // the redirect_uri of the authorization request must be repeated exactly
+    let explicit_authorize_req = IamOAuth2AuthorizeReq {
+        redirect_uri: Some("https://app1.example.com/callback".to_string()),
+        ..authorize_req.clone()
+    };
+    let code = percent_decode_str(IamOAuth2ProviderServ::authorize(&explicit_authorize_req, &funs, context1).await?.split("code=").nth(1).unwrap().split('&').next().unwrap())
+        .decode_utf8_lossy()
+        .to_string();
+    assert_eq!(
+        IamOAuth2ProviderServ::token(
+            &IamOAuth2TokenReq {
+                grant_type: "authorization_code".to_string(),
+                client_id: Some(client.ak.clone()),
+                client_secret: Some(client.sk.clone()),
+                code: Some(code),
+                code_verifier: Some(code_verifier.clone()),
+                ..Default::default()
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-invalid_grant"
+    );
+    let code = percent_decode_str(IamOAuth2ProviderServ::authorize(&authorize_req, &funs, context1).await?.split("code=").nth(1).unwrap().split('&').next().unwrap())
+        .decode_utf8_lossy()
+        .to_string();
+    let token_resp = IamOAuth2ProviderServ::token(
+        &IamOAuth2TokenReq {
+            grant_type: "authorization_code".to_string(),
+            client_id: Some(client.ak.clone()),
+            client_secret: Some(client.sk.clone()),
+            code: Some(code),
+            redirect_uri: Some("https://app1.example.com/callback".to_string()),
+            code_verifier: Some(code_verifier.clone()),
+            ..Default::default()
+        },
+        None,
+        &funs,
+    )
+    .await?;
+    assert_eq!(token_resp.token_type, "Bearer");
+    assert_eq!(token_resp.scope, "openid profile");
+    assert!(token_resp.refresh_token.is_some());
+
+    info!("【test_ci_oauth2_provider】 : Verify Id Token");
+    let jwks = IamOAuth2ProviderServ::jwks(&funs).await?;
+    // the signing key and the previous key in rotation
+    assert_eq!(jwks.keys.len(), 2);
+    assert_ne!(jwks.keys[0].kid, jwks.keys[1].kid);
+    let jwk = &jwks.keys[0];
+    let mut validation = Validation::new(jsonwebtoken::Algorithm::RS256);
+    validation.set_audience(&[client.ak.clone()]);
+    validation.set_issuer(&[IamOAuth2ProviderServ::discovery(&funs).issuer]);
+    let id_token = jsonwebtoken::decode::<IamOidcIdTokenClaims>(
+        token_resp.id_token.as_deref().unwrap(),
+        &DecodingKey::from_rsa_components(&jwk.n, &jwk.e).unwrap(),
+        &validation,
+    )
+    .unwrap();
+    assert_eq!(id_token.header.kid, Some(jwk.kid.clone()));
+    assert_eq!(id_token.claims.sub, context1.owner);
+    assert_eq!(id_token.claims.tenant_id, context1.own_paths);
+    assert_eq!(id_token.claims.nonce, Some("n1".to_string()));
+    assert!(id_token.claims.name.is_some());
+
+    info!("【test_ci_oauth2_provider】 : User Info");
+    let userinfo = IamOAuth2ProviderServ::userinfo(&token_resp.access_token, &funs).await?;
+    assert_eq!(userinfo.sub, context1.owner);
+    assert_eq!(userinfo.name, id_token.claims.name);
+    assert!(userinfo.email.is_none());
+
+    info!("【test_ci_oauth2_provider】 : Refresh Token Grant");
+    let refresh_token_req = IamOAuth2TokenReq {
+        grant_type: "refresh_token".to_string(),
+        client_id: Some(client.ak.clone()),
+        client_secret: Some(client.sk.clone()),
+        refresh_token: token_resp.refresh_token.clone(),
+        scope: Some("openid".to_string()),
+        ..Default::default()
+    };
+    let refreshed_token_resp = IamOAuth2ProviderServ::token(&refresh_token_req, None, &funs).await?;
+    assert_eq!(refreshed_token_resp.scope, "openid");
+    assert_ne!(refreshed_token_resp.refresh_token, token_resp.refresh_token);
+    // the used refresh token is rotated
+    assert_eq!(
+        IamOAuth2ProviderServ::token(&refresh_token_req, None, &funs).await.unwrap_err().code,
+        "400-iam-oauth2-invalid_grant"
+    );
+    // the scopes can't be extended
+    assert_eq!(
+        IamOAuth2ProviderServ::token(
+            &IamOAuth2TokenReq {
+                refresh_token: refreshed_token_resp.refresh_token.clone(),
+                scope: Some("openid profile".to_string()),
+                ..refresh_token_req
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-invalid_scope"
+    );
+
+    // the concurrent requests can't redeem the same code or refresh token twice
+    let code = percent_decode_str(IamOAuth2ProviderServ::authorize(&authorize_req, &funs, context1).await?.split("code=").nth(1).unwrap().split('&').next().unwrap())
+        .decode_utf8_lossy()
+        .to_string();
+    let code_token_req = IamOAuth2TokenReq {
+        grant_type: "authorization_code".to_string(),
+        client_id: Some(client.ak.clone()),
+        client_secret: Some(client.sk.clone()),
+        code: Some(code),
+        code_verifier: Some(code_verifier.clone()),
+        ..Default::default()
+    };
+    let results = join_all((0..5).map(|_| IamOAuth2ProviderServ::token(&code_token_req, None, &funs))).await;
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    let concurrent_refresh_token_req = IamOAuth2TokenReq {
+        grant_type: "refresh_token".to_string(),
+        client_id: Some(client.ak.clone()),
+        client_secret: Some(client.sk.clone()),
+        refresh_token: results.into_iter().find_map(|result| result.ok()).unwrap().refresh_token,
+        ..Default::default()
+    };
+    let results = join_all((0..5).map(|_| IamOAuth2ProviderServ::token(&concurrent_refresh_token_req, None, &funs))).await;
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+
+    info!("【test_ci_oauth2_provider】 : Introspect And Revoke");
+    let op_req = IamOAuth2TokenOpReq {
+        token: refreshed_token_resp.access_token.clone(),
+        token_type_hint: None,
+        client_id: Some(client.ak.clone()),
+        client_secret: Some(client.sk.clone()),
+    };
+    let introspect_resp = IamOAuth2ProviderServ::introspect(&op_req, &funs).await?;
+    assert!(introspect_resp.active);
+    assert_eq!(introspect_resp.sub, Some(context1.owner.clone()));
+    assert_eq!(introspect_resp.client_id, Some(client.ak.clone()));
+    // only the confidential clients can introspect the tokens
+    assert_eq!(
+        IamOAuth2ProviderServ::introspect(
+            &IamOAuth2TokenOpReq {
+                client_id: Some(public_client.ak.clone()),
+                client_secret: None,
+                ..op_req.clone()
+            },
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-oauth2-invalid_client"
+    );
+    assert_eq!(
+        IamOAuth2ProviderServ::revoke(
+            &IamOAuth2TokenOpReq {
+                client_id: Some(legacy_client.ak.clone()),
+                client_secret: Some(legacy_client.sk.clone()),
+                ..op_req.clone()
+            },
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-unauthorized_client"
+    );
+    IamOAuth2ProviderServ::revoke(
+        &IamOAuth2TokenOpReq {
+            token: refreshed_token_resp.refresh_token.clone().unwrap(),
+            token_type_hint: Some("refresh_token".to_string()),
+            ..op_req.clone()
+        },
+        &funs,
+    )
+    .await?;
+    // revoking the refresh token also revokes the access token issued with it
+    assert!(!IamOAuth2ProviderServ::introspect(&op_req, &funs).await?.active);
+    assert_eq!(
+        IamOAuth2ProviderServ::userinfo(&refreshed_token_resp.access_token, &funs).await.unwrap_err().code,
+        "401-iam-oauth2-invalid_token"
+    );
+    IamOAuth2ProviderServ::revoke(&op_req, &funs).await?;
+
+    info!("【test_ci_oauth2_provider】 : Client Credentials Grant");
+    let client_credentials_resp = IamOAuth2ProviderServ::token(
+        &IamOAuth2TokenReq {
+            grant_type: "client_credentials".to_string(),
+            client_id: Some(client.ak.clone()),
+            client_secret: Some(client.sk.clone()),
+            scope: Some("email".to_string()),
+            ..Default::default()
+        },
+        None,
+        &funs,
+    )
+    .await?;
+    assert!(client_credentials_resp.refresh_token.is_none());
+    assert!(client_credentials_resp.id_token.is_none());
+    assert_eq!(
+        IamOAuth2ProviderServ::userinfo(&client_credentials_resp.access_token, &funs).await.unwrap_err().code,
+        "401-iam-oauth2-insufficient_scope"
+    );
+    assert_eq!(
+        IamOAuth2ProviderServ::token(
+            &IamOAuth2TokenReq {
+                grant_type: "client_credentials".to_string(),
+                client_id: Some(client.ak.clone()),
+                client_secret: Some(client.sk.clone()),
+                scope: Some("admin".to_string()),
+                ..Default::default()
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-invalid_scope"
+    );
+    // the public client can't use the client credentials grant
+    assert_eq!(
+        IamOAuth2ProviderServ::token(
+            &IamOAuth2TokenReq {
+                grant_type: "client_credentials".to_string(),
+                client_id: Some(public_client.ak.clone()),
+                ..Default::default()
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-unauthorized_client"
+    );
+    assert_eq!(
+        IamOAuth2ProviderServ::authorize(
+            &IamOAuth2AuthorizeReq {
+                client_id: public_client.ak.clone(),
+                code_challenge: None,
+                code_challenge_method: None,
+                scope: None,
+                ..authorize_req.clone()
+            },
+            &funs,
+            context1
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-oauth2-invalid_request"
+    );
+
+    info!("【test_ci_oauth2_provider】 : Legacy Client");
+    let legacy_resp = IamCiOauth2AkSkServ::generate_token(
+        Oauth2GrantType::ClientCredentials,
+        &legacy_client.ak,
+        &legacy_client.sk,
+        None,
+        iam_constants::get_tardis_inst(),
+    )
+    .await?;
+    assert!(!legacy_resp.access_token.is_empty());
+    assert!(legacy_resp.refresh_token.is_empty());
+    assert!(IamCiOauth2AkSkServ::generate_token(Oauth2GrantType::Password, &legacy_client.ak, &legacy_client.sk, None, iam_constants::get_tardis_inst()).await.is_err());
+
+    let discovery = IamOAuth2ProviderServ::discovery(&funs);
+    assert_eq!(discovery.token_endpoint, format!("{}/cp/oauth2/token", discovery.issuer));
+    assert!(discovery.code_challenge_methods_supported.contains(&"S256".to_string()));
+    Ok(())
+}
@@ -96,6 +96,8 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
             }),
             cert_conf_by_phone_vcode: Some(true),
             cert_conf_by_mail_vcode: Some(true),
+            cert_conf_by_totp: None,
+            cert_conf_by_webauthn: None,
             cert_conf_by_oauth2: None,
             cert_conf_by_ldap: None,
             config: None,
@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+
+use bios_iam::basic::dto::iam_cert_conf_dto::IamCertConfUserPwdAddOrModifyReq;
+use bios_iam::basic::dto::iam_cert_dto::IamCertUserPwdRestReq;
+use bios_iam::basic::serv::iam_cert_serv::IamCertServ;
+use bios_iam::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use bios_iam::basic::serv::iam_tenant_serv::IamTenantServ;
+use bios_iam::iam_constants;
+use bios_iam::iam_enumeration::IamCertKernelKind;
+use bios_iam::integration::ldap::ldap_processor::{self, LdapGroupFilter};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::TardisFuns;
+
+pub async fn test(context1: &TardisContext) -> TardisResult<()> {
+    let funs = iam_constants::get_tardis_inst();
+    let tenant_id = IamTenantServ::get_id_by_ctx(context1, &funs)?;
+    let account_id = context1.owner.clone();
+
+    info!("【test_iam_ldap_server】 : Find Accounts");
+    let account = ldap_processor::find_accounts(&tenant_id, Some(vec![account_id.clone()])).await?.pop().unwrap();
+    assert_eq!(account.id, account_id);
+    assert_eq!(account.cn, format!("{}{}", ldap_processor::cn_prefix(&tenant_id), account.ak));
+    assert!(ldap_processor::find_accounts(&tenant_id, Some(vec![])).await?.is_empty());
+    assert_eq!(
+        ldap_processor::find_account_id_by_cn(&tenant_id, &format!("{}{}", ldap_processor::cn_prefix(&tenant_id).to_uppercase(), account.ak)).await?,
+        Some(account_id.clone())
+    );
+    assert_eq!(ldap_processor::find_account_id_by_cn(&tenant_id, &account.ak).await?, None);
+    assert_eq!(
+        ldap_processor::find_account_id_by_cn(&tenant_id, &format!("{}xxx", ldap_processor::cn_prefix(&tenant_id))).await?,
+        None
+    );
+
+    info!("【test_iam_ldap_server】 : Find Groups");
+    let groups = ldap_processor::find_groups(&tenant_id, &LdapGroupFilter::default()).await?;
+    let member_groups = ldap_processor::find_groups(
+        &tenant_id,
+        &LdapGroupFilter {
+            member_account_id: Some(account_id.clone()),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(member_groups.iter().all(|group| group.member_account_ids == vec![account_id.clone()]));
+    assert_eq!(
+        member_groups.iter().map(|group| (group.kind, group.cn.clone())).collect::<HashSet<_>>(),
+        groups.iter().filter(|group| group.member_account_ids.contains(&account_id)).map(|group| (group.kind, group.cn.clone())).collect::<HashSet<_>>()
+    );
+    for group in &groups {
+        let code = ldap_processor::strip_cn_prefix(&tenant_id, &group.cn).unwrap();
+        let named_groups = ldap_processor::find_groups(
+            &tenant_id,
+            &LdapGroupFilter {
+                code: Some(code.to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        let named_group = named_groups.iter().find(|named_group| named_group.kind == group.kind && named_group.cn == group.cn).unwrap();
+        assert_eq!(
+            named_group.member_account_ids.iter().collect::<HashSet<_>>(),
+            group.member_account_ids.iter().collect::<HashSet<_>>()
+        );
+    }
+
+    info!("【test_iam_ldap_server】 : Reset Password");
+    let cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::UserPwd.to_string(), Some(tenant_id.clone()), &funs).await?;
+    IamCertUserPwdServ::reset_sk_to_enable_status(
+        &IamCertUserPwdRestReq {
+            new_sk: Some(TrimString("Ldap0Pw$1234".to_string())),
+        },
+        &account_id,
+        &cert_conf_id,
+        &funs,
+        context1,
+    )
+    .await?;
+    assert!(ldap_processor::check_cert(&account.cn, "Ldap0Pw$1234").await?);
+    assert_eq!(
+        ldap_processor::modify_password(&account, None, &format!("{}0Pw$1234", account.ak)).await.unwrap_err().code,
+        "400-iam-cert-sk-contains-ak"
+    );
+
+    info!("【test_iam_ldap_server】 : Modify Password With Tenant Policy");
+    let cert_conf = TardisFuns::json.str_to_obj::<IamCertConfUserPwdAddOrModifyReq>(&IamCertServ::get_cert_conf(&cert_conf_id, None, &funs, context1).await?.ext)?;
+    IamCertUserPwdServ::modify_cert_conf(
+        &cert_conf_id,
+        &IamCertConfUserPwdAddOrModifyReq {
+            sk_rule_len_min: 10,
+            sk_rule_len_max: 30,
+            sk_rule_need_num: true,
+            sk_rule_need_uppercase: true,
+            sk_rule_need_lowercase: true,
+            sk_rule_need_spec_char: true,
+            ..cert_conf.clone()
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    assert_eq!(
+        ldap_processor::modify_password(&account, Some("Ldap0Pw$1234"), "Ld0$").await.unwrap_err().code,
+        "400-rbum-cert-conf-sk-rule-not-match"
+    );
+    assert_eq!(
+        ldap_processor::modify_password(&account, Some("Ldap0Pw$1234"), "ldappassword").await.unwrap_err().code,
+        "400-rbum-cert-conf-sk-rule-not-match"
+    );
+    assert_eq!(
+        ldap_processor::modify_password(&account, Some("Ldap0Pw$1234"), &format!("{}0Pw$1234", account.ak)).await.unwrap_err().code,
+        "400-iam-cert-sk-contains-ak"
+    );
+    // The rejected passwords don't take effect
+    assert!(ldap_processor::check_cert(&account.cn, "Ldap0Pw$1234").await?);
+    ldap_processor::modify_password(&account, Some("Ldap0Pw$1234"), "Ldap1Pw$5678").await?;
+    assert!(ldap_processor::check_cert(&account.cn, "Ldap1Pw$5678").await?);
+    assert!(!ldap_processor::check_cert(&account.cn, "Ldap0Pw$1234").await?);
+    IamCertUserPwdServ::modify_cert_conf(&cert_conf_id, &cert_conf, &funs, context1).await?;
+
+    Ok(())
+}
@@ -0,0 +1,339 @@
+use bios_iam::basic::dto::iam_cert_dto::{IamCertAkSkAddReq, IamCertUserPwdRestReq};
+use bios_iam::basic::dto::iam_config_dto::IamConfigAggOrModifyReq;
+use bios_iam::basic::dto::iam_oauth2_provider_dto::{IamOAuth2ClientConf, IamOAuth2TokenReq};
+use bios_iam::basic::serv::iam_cert_mfa_serv::IamCertMfaServ;
+use bios_iam::basic::serv::iam_cert_serv::IamCertServ;
+use bios_iam::basic::serv::iam_cert_totp_serv::IamCertTotpServ;
+use bios_iam::basic::serv::iam_cert_user_pwd_serv::IamCertUserPwdServ;
+use bios_iam::basic::serv::iam_cert_webauthn_serv::IamCertWebAuthnServ;
+use bios_iam::basic::serv::iam_config_serv::IamConfigServ;
+use bios_iam::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
+use bios_iam::basic::serv::iam_oauth2_provider_serv::IamOAuth2ProviderServ;
+use bios_iam::basic::serv::iam_tenant_serv::IamTenantServ;
+use bios_iam::console_interface::serv::iam_ci_cert_aksk_serv::IamCiCertAkSkServ;
+use bios_iam::console_passport::dto::iam_cp_cert_dto::{IamCpMfaTotpLoginReq, IamCpMfaWebAuthnStartLoginReq};
+use bios_iam::console_passport::serv::iam_cp_cert_mfa_serv::IamCpCertMfaServ;
+use bios_iam::iam_config::IamConfig;
+use bios_iam::iam_constants;
+use bios_iam::iam_enumeration::{IamCertKernelKind, IamConfigDataTypeKind, IamConfigKind, Oauth2GrantType};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::Utc;
+use tardis::futures::future::join_all;
+use tardis::log::info;
+use tardis::serde_json::json;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+fn gen_code(secret: &str, offset_sec: i64) -> String {
+    let totp = TOTP::new_unchecked(Algorithm::SHA1, 6, 1, 30, Secret::Encoded(secret.to_string()).to_bytes().unwrap(), None, "".to_string());
+    totp.generate((Utc::now().timestamp() + offset_sec) as u64)
+}
+
+pub async fn test(context1: &TardisContext) -> TardisResult<()> {
+    let mut funs = iam_constants::get_tardis_inst();
+    funs.begin().await?;
+    let tenant_id = IamTenantServ::get_id_by_ctx(context1, &funs)?;
+    let account_id = context1.owner.clone();
+
+    info!("【test_iam_mfa】 : Enroll Totp");
+    assert_eq!(IamCertTotpServ::enroll(&funs, context1).await.unwrap_err().code, "404-iam-iam_cert_conf-get");
+    IamCertTotpServ::add_or_enable_cert_conf(Some(tenant_id.clone()), &funs, context1).await?;
+    let enrollment = IamCertTotpServ::enroll(&funs, context1).await?;
+    assert!(enrollment.provisioning_uri.starts_with("otpauth://totp/BIOS:"));
+    assert!(enrollment.provisioning_uri.contains(&format!("secret={}", enrollment.secret)));
+    assert!(!IamCertTotpServ::exist_cert(&account_id, &funs, context1).await?);
+    assert_eq!(IamCertTotpServ::bind("000000x", &funs, context1).await.unwrap_err().code, "401-iam-iam_cert_totp-bind");
+    let bind_resp = IamCertTotpServ::bind(&gen_code(&enrollment.secret, 0), &funs, context1).await?;
+    assert_eq!(bind_resp.recovery_codes.len(), 10);
+    assert!(IamCertTotpServ::exist_cert(&account_id, &funs, context1).await?);
+    assert_eq!(IamCertTotpServ::enroll(&funs, context1).await.unwrap_err().code, "409-iam-iam_cert_totp-enroll");
+
+    info!("【test_iam_mfa】 : Validate Totp");
+    // the one-time password used by binding can't be replayed
+    assert_eq!(
+        IamCertTotpServ::validate(&account_id, &gen_code(&enrollment.secret, 0), &funs, context1).await.unwrap_err().code,
+        "401-iam-iam_cert_totp-validate"
+    );
+    IamCertTotpServ::validate(&account_id, &gen_code(&enrollment.secret, 30), &funs, context1).await?;
+    IamCertTotpServ::validate(&account_id, &bind_resp.recovery_codes[0].to_uppercase(), &funs, context1).await?;
+    assert_eq!(
+        IamCertTotpServ::validate(&account_id, &bind_resp.recovery_codes[0], &funs, context1).await.unwrap_err().code,
+        "401-iam-iam_cert_totp-validate"
+    );
+
+    info!("【test_iam_mfa】 : Login Without Mfa Policy");
+    assert!(IamCertMfaServ::get_required_kinds(&tenant_id, &account_id, &funs).await?.is_empty());
+    let resp = IamCertServ::package_tardis_context_and_resp(Some(tenant_id.clone()), &account_id, None, None, None, &funs).await?;
+    assert!(resp.mfa_challenge.is_none());
+    assert!(!resp.token.is_empty());
+    let user_pwd_cert_conf_id = IamCertServ::get_cert_conf_id_by_kind(&IamCertKernelKind::UserPwd.to_string(), Some(tenant_id.clone()), &funs).await?;
+    IamCertUserPwdServ::reset_sk_to_enable_status(
+        &IamCertUserPwdRestReq {
+            new_sk: Some(TrimString("Mfa0Pw$1234".to_string())),
+        },
+        &account_id,
+        &user_pwd_cert_conf_id,
+        &funs,
+        context1,
+    )
+    .await?;
+    let client = IamCiCertAkSkServ::general_cert(
+        IamCertAkSkAddReq {
+            tenant_id: tenant_id.clone(),
+            app_id: None,
+            oauth2_client: Some(IamOAuth2ClientConf {
+                redirect_uris: vec![],
+                grant_types: vec![Oauth2GrantType::Password],
+                scopes: vec![],
+                public_client: false,
+            }),
+            scim: None,
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    let password_token_req = IamOAuth2TokenReq {
+        grant_type: "password".to_string(),
+        client_id: Some(client.ak.clone()),
+        client_secret: Some(client.sk.clone()),
+        username: Some("bios".to_string()),
+        password: Some("Mfa0Pw$1234".to_string()),
+        ..Default::default()
+    };
+    assert!(!IamOAuth2ProviderServ::token(&password_token_req, None, &funs).await?.access_token.is_empty());
+
+    info!("【test_iam_mfa】 : Login With Mfa Policy");
+    IamConfigServ::add_or_modify_batch(
+        &tenant_id,
+        vec![IamConfigAggOrModifyReq {
+            name: None,
+            data_type: IamConfigDataTypeKind::CertKinds,
+            note: None,
+            value1: Some(format!("{},{}", IamCertKernelKind::Totp, IamCertKernelKind::UserPwd)),
+            value2: None,
+            ext: None,
+            disabled: Some(false),
+            code: IamConfigKind::Mfa,
+        }],
+        &funs,
+        context1,
+    )
+    .await?;
+    assert_eq!(IamCertMfaServ::get_required_kinds(&tenant_id, &account_id, &funs).await?, vec![IamCertKernelKind::Totp]);
+    // the password grant of OAuth2 can't bypass the second factor
+    assert_eq!(
+        IamOAuth2ProviderServ::token(&password_token_req, None, &funs).await.unwrap_err().code,
+        "400-iam-oauth2-invalid_grant"
+    );
+    let resp = IamCertServ::package_tardis_context_and_resp(Some(tenant_id.clone()), &account_id, None, None, None, &funs).await?;
+    assert!(resp.token.is_empty());
+    assert!(resp.roles.is_empty());
+    let challenge = resp.mfa_challenge.unwrap();
+    assert_eq!(challenge.kinds, vec![IamCertKernelKind::Totp]);
+    assert_eq!(
+        IamCpCertMfaServ::start_login_by_webauthn(&IamCpMfaWebAuthnStartLoginReq { ticket: challenge.ticket.clone() }, &funs).await.unwrap_err().code,
+        "400-iam-iam_cert_mfa-get_ticket"
+    );
+    assert_eq!(
+        IamCpCertMfaServ::login_by_totp(
+            &IamCpMfaTotpLoginReq {
+                ticket: challenge.ticket.clone(),
+                code: TrimString("000000".to_string()),
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-iam_cert_totp-validate"
+    );
+    let resp = IamCpCertMfaServ::login_by_totp(
+        &IamCpMfaTotpLoginReq {
+            ticket: challenge.ticket.clone(),
+            code: TrimString(bind_resp.recovery_codes[1].clone()),
+        },
+        None,
+        &funs,
+    )
+    .await?;
+    assert!(resp.mfa_challenge.is_none());
+    assert!(!resp.token.is_empty());
+    assert_eq!(resp.account_id, account_id);
+    // the ticket can only be used once
+    // the account is locked even if the code is valid, and the ticket is invalidated
+    assert_eq!(
+        IamCpCertMfaServ::login_by_totp(
+            &IamCpMfaTotpLoginReq {
+                ticket: challenge.ticket.clone(),
+                code: TrimString(bind_resp.recovery_codes[2].clone()),
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-iam_cert_mfa-start_verification"
+    );
+    assert_eq!(
+        IamCpCertMfaServ::login_by_totp(
+            &IamCpMfaTotpLoginReq {
+                ticket: challenge.ticket,
+                code: TrimString(bind_resp.recovery_codes[2].clone()),
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-iam_cert_mfa-get_ticket"
+    );
+    // a new ticket doesn't reset the failed verifications of the account
+    let challenge = IamCertServ::package_tardis_context_and_resp(Some(tenant_id.clone()), &account_id, None, None, None, &funs).await?.mfa_challenge.unwrap();
+    assert_eq!(
+        IamCpCertMfaServ::login_by_totp(
+            &IamCpMfaTotpLoginReq {
+                ticket: challenge.ticket,
+                code: TrimString(bind_resp.recovery_codes[2].clone()),
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-iam_cert_mfa-start_verification"
+    );
+    // the double auth shares the failed verifications with the login
+    assert_eq!(
+        IamCpCertMfaServ::validate_by_totp(&bind_resp.recovery_codes[2], &funs, context1).await.unwrap_err().code,
+        "401-iam-iam_cert_mfa-start_verification"
+    );
+    funs.cache().del(&format!("{}{}", funs.conf::<IamConfig>().mfa.cache_key_verify_times_, account_id)).await?;
+
+    info!("【test_iam_mfa】 : Ticket Error Times");
+    let challenge = IamCertServ::package_tardis_context_and_resp(Some(tenant_id.clone()), &account_id, None, None, None, &funs).await?.mfa_challenge.unwrap();
+    for _ in 0..5 {
+        assert_eq!(
+            IamCpCertMfaServ::login_by_totp(
+                &IamCpMfaTotpLoginReq {
+                    ticket: challenge.ticket.clone(),
+                    code: TrimString("000000".to_string()),
+                },
+                None,
+                &funs
+            )
+            .await
+            .unwrap_err()
+            .code,
+            "401-iam-iam_cert_totp-validate"
+        );
+    }
+    // the account is locked even if the code is valid, and the ticket is invalidated
+    assert_eq!(
+        IamCpCertMfaServ::login_by_totp(
+            &IamCpMfaTotpLoginReq {
+                ticket: challenge.ticket.clone(),
+                code: TrimString(bind_resp.recovery_codes[2].clone()),
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-iam_cert_mfa-start_verification"
+    );
+    assert_eq!(
+        IamCpCertMfaServ::login_by_totp(
+            &IamCpMfaTotpLoginReq {
+                ticket: challenge.ticket,
+                code: TrimString(bind_resp.recovery_codes[2].clone()),
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-iam_cert_mfa-get_ticket"
+    );
+    // a new ticket doesn't reset the failed verifications of the account
+    let challenge = IamCertServ::package_tardis_context_and_resp(Some(tenant_id.clone()), &account_id, None, None, None, &funs).await?.mfa_challenge.unwrap();
+    assert_eq!(
+        IamCpCertMfaServ::login_by_totp(
+            &IamCpMfaTotpLoginReq {
+                ticket: challenge.ticket,
+                code: TrimString(bind_resp.recovery_codes[2].clone()),
+            },
+            None,
+            &funs
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "401-iam-iam_cert_mfa-start_verification"
+    );
+    // the double auth shares the failed verifications with the login
+    assert_eq!(
+        IamCpCertMfaServ::validate_by_totp(&bind_resp.recovery_codes[2], &funs, context1).await.unwrap_err().code,
+        "401-iam-iam_cert_mfa-start_verification"
+    );
+    funs.cache().del(&format!("{}{}", funs.conf::<IamConfig>().mfa.cache_key_verify_times_, account_id)).await?;
+
+    info!("【test_iam_mfa】 : Concurrent Validation");
+    // the same recovery code can't be redeemed twice by the concurrent validations
+    let results = join_all((0..5).map(|_| IamCertTotpServ::validate(&account_id, &bind_resp.recovery_codes[3], &funs, context1))).await;
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+
+    info!("【test_iam_mfa】 : Double Auth Error Times");
+    for _ in 0..5 {
+        assert_eq!(
+            IamCpCertMfaServ::validate_by_totp("000000", &funs, context1).await.unwrap_err().code,
+            "401-iam-iam_cert_totp-validate"
+        );
+    }
+    // the double auth is locked even if the code is valid
+    assert_eq!(
+        IamCpCertMfaServ::validate_by_totp(&bind_resp.recovery_codes[4], &funs, context1).await.unwrap_err().code,
+        "401-iam-iam_cert_mfa-start_verification"
+    );
+    funs.cache().del(&format!("{}{}", funs.conf::<IamConfig>().mfa.cache_key_verify_times_, account_id)).await?;
+    IamCpCertMfaServ::validate_by_totp(&bind_resp.recovery_codes[4], &funs, context1).await?;
+
+    info!("【test_iam_mfa】 : WebAuthn");
+    IamCertWebAuthnServ::add_or_enable_cert_conf(Some(tenant_id.clone()), &funs, context1).await?;
+    assert!(IamCertWebAuthnServ::find_certs_by_account(&account_id, &funs, context1).await?.is_empty());
+    assert_eq!(
+        IamCertWebAuthnServ::start_authenticate(&account_id, &funs, context1).await.unwrap_err().code,
+        "404-iam-iam_cert_webauthn-start_authenticate"
+    );
+    let options = IamCertWebAuthnServ::start_register(&funs, context1).await?;
+    assert_eq!(options["publicKey"]["rp"]["id"], "localhost");
+    assert!(options["publicKey"]["challenge"].is_string());
+    assert!(IamCertWebAuthnServ::finish_register("key1", &json!({}), &funs, context1).await.is_err());
+    // the registration state can only be used once
+    assert_eq!(
+        IamCertWebAuthnServ::finish_register("key1", &json!({}), &funs, context1).await.unwrap_err().code,
+        "400-iam-iam_cert_webauthn-take_state"
+    );
+
+    info!("【test_iam_mfa】 : Unbind Totp");
+    // the second factor can't be removed without the double auth
+    IamIdentCacheServ::delete_double_auth(&account_id, &funs).await?;
+    assert_eq!(
+        IamCpCertMfaServ::delete_totp(&funs, context1).await.unwrap_err().code,
+        "401-iam-iam_cert_mfa-check_double_auth"
+    );
+    IamCpCertMfaServ::validate_by_totp(&bind_resp.recovery_codes[5], &funs, context1).await?;
+    IamCpCertMfaServ::delete_totp(&funs, context1).await?;
+    assert!(!IamCertTotpServ::exist_cert(&account_id, &funs, context1).await?);
+    assert!(IamCertMfaServ::get_required_kinds(&tenant_id, &account_id, &funs).await?.is_empty());
+
+    funs.rollback().await?;
+    Ok(())
+}
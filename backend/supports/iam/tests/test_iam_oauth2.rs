@@ -33,7 +33,7 @@ pub async fn test(tenant1_admin_context: &TardisContext) -> TardisResult<()> {
     )
     .await?;
 
-    let account = IamCertOAuth2Serv::get_or_add_account(IamCertOAuth2Supplier::Github, code, id, &funs).await?;
+    let account = IamCertOAuth2Serv::get_or_add_account(IamCertOAuth2Supplier::Github, code, None, id, &funs).await?;
     info!("account info= {:?}", account);
     Ok(())
 }
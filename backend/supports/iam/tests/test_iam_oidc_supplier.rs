@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_iam::basic::dto::iam_account_dto::IamAccountAddReq;
+use bios_iam::basic::dto::iam_cert_conf_dto::{IamCertConfOAuth2AddOrModifyReq, IamCertConfOidcConf, IamCertOidcClaimMapping};
+use bios_iam::basic::dto::iam_role_dto::IamRoleAddReq;
+use bios_iam::basic::dto::iam_set_dto::{IamSetCateAddReq, IamSetItemAddReq};
+use bios_iam::basic::serv::iam_account_serv::IamAccountServ;
+use bios_iam::basic::serv::iam_cert_oauth2_serv::IamCertOAuth2Serv;
+use bios_iam::basic::serv::iam_role_serv::IamRoleServ;
+use bios_iam::basic::serv::iam_set_serv::IamSetServ;
+use bios_iam::basic::serv::iam_tenant_serv::IamTenantServ;
+use bios_iam::basic::serv::oauth2_spi::iam_cert_oauth2_spi_oidc::IamCertOAuth2SpiOidc;
+use bios_iam::iam_constants;
+use bios_iam::iam_enumeration::{IamCertOAuth2Supplier, IamSetKind};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::serde_json::json;
+use tardis::TardisFunsInst;
+
+pub async fn test(context1: &TardisContext) -> TardisResult<()> {
+    let mut funs = iam_constants::get_tardis_inst();
+    funs.begin().await?;
+    let tenant_id = IamTenantServ::get_id_by_ctx(context1, &funs)?;
+
+    info!("【test_iam_oidc_supplier】 : Add Cert Conf");
+    let oidc_conf = IamCertConfOidcConf {
+        issuer: "https://idp.example.com/realms/bios".to_string(),
+        authorization_endpoint: Some("https://idp.example.com/realms/bios/protocol/openid-connect/auth".to_string()),
+        token_endpoint: Some("https://idp.example.com/realms/bios/protocol/openid-connect/token".to_string()),
+        userinfo_endpoint: None,
+        jwks_uri: Some("https://idp.example.com/realms/bios/protocol/openid-connect/certs".to_string()),
+        token_endpoint_auth_method: None,
+        scopes: vec![],
+        claim_mapping: IamCertOidcClaimMapping {
+            name: Some("preferred_username".to_string()),
+            exts: HashMap::from([("department".to_string(), "dept".to_string())]),
+            ..Default::default()
+        },
+        jit_provisioning: true,
+        groups_claim: Some("realm_access.roles".to_string()),
+        role_mapping: HashMap::from([
+            ("developer".to_string(), vec!["role1".to_string()]),
+            ("admin".to_string(), vec!["role1".to_string(), "role2".to_string()]),
+        ]),
+        org_mapping: HashMap::from([("developer".to_string(), vec!["org1".to_string()])]),
+    };
+    let add_req = IamCertConfOAuth2AddOrModifyReq {
+        supplier: TrimString(IamCertOAuth2Supplier::Oidc.to_string()),
+        ak: TrimString("bios-client".to_string()),
+        sk: TrimString("bios-secret".to_string()),
+        oidc: None,
+    };
+    assert_eq!(
+        IamCertOAuth2Serv::add_or_enable_cert_conf(IamCertOAuth2Supplier::Oidc, &add_req, &tenant_id, &funs, context1).await.unwrap_err().code,
+        "400-iam-iam_cert_oauth2-check_cert_conf"
+    );
+    IamCertOAuth2Serv::add_or_enable_cert_conf(
+        IamCertOAuth2Supplier::Oidc,
+        &IamCertConfOAuth2AddOrModifyReq {
+            oidc: Some(oidc_conf.clone()),
+            ..add_req
+        },
+        &tenant_id,
+        &funs,
+        context1,
+    )
+    .await?;
+
+    info!("【test_iam_oidc_supplier】 : Get Authorize Url");
+    assert_eq!(
+        IamCertOAuth2Serv::get_authorize_url(IamCertOAuth2Supplier::Github, &tenant_id, "https://bios.example.com/callback", &funs).await.unwrap_err().code,
+        "400-iam-iam_cert_oauth2-get_authorize_url"
+    );
+    let authorize_url = IamCertOAuth2Serv::get_authorize_url(IamCertOAuth2Supplier::Oidc, &tenant_id, "https://bios.example.com/callback", &funs).await?;
+    assert!(authorize_url.starts_with("https://idp.example.com/realms/bios/protocol/openid-connect/auth?response_type=code&client_id=bios%2Dclient&"));
+    assert!(authorize_url.contains("&scope=openid%20profile%20email&"));
+    assert!(authorize_url.contains("&state="));
+    assert!(authorize_url.contains("&nonce="));
+    assert!(authorize_url.contains("&code_challenge="));
+    assert!(authorize_url.ends_with("&code_challenge_method=S256"));
+
+    info!("【test_iam_oidc_supplier】 : Login With Invalid State");
+    assert_eq!(
+        IamCertOAuth2Serv::get_or_add_account(IamCertOAuth2Supplier::Oidc, "code1", None, &tenant_id, &funs).await.unwrap_err().code,
+        "400-iam-oauth_spi_oidc-get_access_token"
+    );
+    assert_eq!(
+        IamCertOAuth2Serv::get_or_add_account(IamCertOAuth2Supplier::Oidc, "code1", Some("invalid".to_string()), &tenant_id, &funs).await.unwrap_err().code,
+        "401-iam-oauth_spi_oidc-get_access_token"
+    );
+
+    info!("【test_iam_oidc_supplier】 : Map Account");
+    let account_info = IamCertOAuth2SpiOidc::map_account(
+        &oidc_conf,
+        &json!({
+            "sub": "f0a1b2c3",
+            "preferred_username": "bios_user",
+            "email": "bios_user@example.com",
+            "department": "R&D",
+            "realm_access": {"roles": ["developer", "admin", "guest"]}
+        }),
+        &funs,
+    )?;
+    assert_eq!(account_info.open_id, "f0a1b2c3");
+    assert_eq!(account_info.name, Some("bios_user".to_string()));
+    assert_eq!(account_info.mail, Some("bios_user@example.com".to_string()));
+    // the unverified mail isn't added as a cert
+    assert!(!account_info.mail_verified);
+    assert_eq!(account_info.phone, None);
+    assert_eq!(account_info.exts.get("dept"), Some(&"R&D".to_string()));
+    assert_eq!(account_info.role_ids, vec!["role1".to_string(), "role2".to_string()]);
+    assert_eq!(account_info.org_node_ids, vec!["org1".to_string()]);
+    let account_info = IamCertOAuth2SpiOidc::map_account(
+        &oidc_conf,
+        &json!({"sub": "f0a1b2c3", "email": "bios_user@example.com", "email_verified": true, "phone_number": "18600000000", "phone_number_verified": "false"}),
+        &funs,
+    )?;
+    assert!(account_info.mail_verified);
+    assert!(!account_info.phone_verified);
+    // a single group is also supported
+    let account_info = IamCertOAuth2SpiOidc::map_account(&oidc_conf, &json!({"sub": "f0a1b2c3", "realm_access": {"roles": "admin"}}), &funs)?;
+    assert_eq!(account_info.name, None);
+    assert_eq!(account_info.role_ids, vec!["role1".to_string(), "role2".to_string()]);
+    assert!(account_info.org_node_ids.is_empty());
+    assert_eq!(
+        IamCertOAuth2SpiOidc::map_account(&oidc_conf, &json!({"preferred_username": "bios_user"}), &funs).unwrap_err().code,
+        "401-iam-oauth_spi_oidc-map_account"
+    );
+
+    info!("【test_iam_oidc_supplier】 : Sync Mapped Roles And Orgs");
+    let mut role_ids = vec![];
+    for code in ["oidc_role1", "oidc_role2", "oidc_role3"] {
+        role_ids.push(
+            IamRoleServ::add_item(
+                &mut IamRoleAddReq {
+                    code: Some(TrimString(code.to_string())),
+                    name: TrimString(code.to_string()),
+                    icon: None,
+                    sort: None,
+                    disabled: None,
+                    scope_level: None,
+                    kind: None,
+                    extend_role_id: None,
+                    in_embed: None,
+                    in_base: None,
+                },
+                &funs,
+                context1,
+            )
+            .await?,
+        );
+    }
+    let set_id = IamSetServ::get_default_set_id_by_ctx(&IamSetKind::Org, &funs, context1).await?;
+    let mut org_node_ids = vec![];
+    for code in ["oidc_org1", "oidc_org2"] {
+        org_node_ids.push(
+            IamSetServ::add_set_cate(
+                &set_id,
+                &IamSetCateAddReq {
+                    bus_code: Some(TrimString(code.to_string())),
+                    name: TrimString(code.to_string()),
+                    icon: None,
+                    sort: None,
+                    ext: None,
+                    rbum_parent_cate_id: None,
+                    scope_level: None,
+                },
+                &funs,
+                context1,
+            )
+            .await?,
+        );
+    }
+    let account_id = IamAccountServ::add_item(
+        &mut IamAccountAddReq {
+            id: None,
+            name: TrimString("oidc_account".to_string()),
+            icon: None,
+            scope_level: None,
+            disabled: None,
+            temporary: None,
+            status: None,
+            lock_status: None,
+            logout_type: None,
+            labor_type: None,
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    // the role and the org node assigned in IAM are not managed by the mapping
+    IamRoleServ::add_rel_account(&role_ids[2], &account_id, None, &funs, context1).await?;
+    IamSetServ::add_set_item(
+        &IamSetItemAddReq {
+            set_id: set_id.clone(),
+            set_cate_id: org_node_ids[1].clone(),
+            sort: 0,
+            rel_rbum_item_id: account_id.clone(),
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    let sync_conf = IamCertConfOidcConf {
+        role_mapping: HashMap::from([("developer".to_string(), vec![role_ids[0].clone()]), ("admin".to_string(), vec![role_ids[1].clone()])]),
+        org_mapping: HashMap::from([("developer".to_string(), vec![org_node_ids[0].clone()])]),
+        ..oidc_conf
+    };
+    let login_ctx = TardisContext {
+        own_paths: tenant_id.clone(),
+        ..Default::default()
+    };
+    let account_info = IamCertOAuth2SpiOidc::map_account(&sync_conf, &json!({"sub": "s001", "realm_access": {"roles": ["developer"]}}), &funs)?;
+    IamCertOAuth2SpiOidc::sync_mapped_roles_and_orgs(&account_id, &sync_conf, &account_info, &funs, &login_ctx).await?;
+    assert_eq!(find_role_ids(&account_id, &funs, &login_ctx).await?, sorted(vec![role_ids[0].clone(), role_ids[2].clone()]));
+    assert_eq!(
+        find_org_node_ids(&set_id, &account_id, &funs, &login_ctx).await?,
+        sorted(vec![org_node_ids[0].clone(), org_node_ids[1].clone()])
+    );
+    // the mapped roles and org nodes no longer in the groups are removed on the next login
+    let account_info = IamCertOAuth2SpiOidc::map_account(&sync_conf, &json!({"sub": "s001", "realm_access": {"roles": ["admin"]}}), &funs)?;
+    IamCertOAuth2SpiOidc::sync_mapped_roles_and_orgs(&account_id, &sync_conf, &account_info, &funs, &login_ctx).await?;
+    assert_eq!(find_role_ids(&account_id, &funs, &login_ctx).await?, sorted(vec![role_ids[1].clone(), role_ids[2].clone()]));
+    assert_eq!(find_org_node_ids(&set_id, &account_id, &funs, &login_ctx).await?, vec![org_node_ids[1].clone()]);
+
+    funs.rollback().await?;
+    Ok(())
+}
+
+async fn find_role_ids(account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<String>> {
+    Ok(sorted(
+        IamAccountServ::find_simple_rel_roles(account_id, true, None, None, funs, ctx).await?.into_iter().map(|role| role.rel_id).collect(),
+    ))
+}
+
+async fn find_org_node_ids(set_id: &str, account_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<String>> {
+    Ok(sorted(
+        IamSetServ::find_set_items(Some(set_id.to_string()), None, Some(account_id.to_string()), None, false, None, funs, ctx)
+            .await?
+            .into_iter()
+            .filter_map(|item| item.rel_rbum_set_cate_id)
+            .collect(),
+    ))
+}
+
+fn sorted(mut ids: Vec<String>) -> Vec<String> {
+    ids.sort();
+    ids
+}
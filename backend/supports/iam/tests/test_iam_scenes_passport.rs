@@ -774,6 +774,7 @@ pub async fn login_by_oauth2(client: &mut BIOSWebTestClient) -> TardisResult<()>
                     supplier: TrimString(IamCertOAuth2Supplier::WechatMp.to_string()),
                     ak: TrimString(app_id.to_string()),
                     sk: TrimString(secret.to_string()),
+                    oidc: None,
                 }]),
                 cert_conf_by_ldap: None,
             },
@@ -789,6 +790,7 @@ pub async fn login_by_oauth2(client: &mut BIOSWebTestClient) -> TardisResult<()>
             &IamCpOAuth2LoginReq {
                 code: TrimString(code.to_string()),
                 tenant_id,
+                state: None,
             },
         )
         .await;
@@ -0,0 +1,309 @@
+use bios_iam::basic::dto::iam_cert_dto::IamCertAkSkAddReq;
+use bios_iam::basic::dto::iam_oauth2_provider_dto::IamOAuth2ClientConf;
+use bios_iam::basic::dto::iam_scim_dto::{IamScimBulkReq, IamScimGroup, IamScimPatchReq, IamScimUser};
+use bios_iam::basic::serv::iam_cert_aksk_serv::IamCertAkSkServ;
+use bios_iam::basic::serv::iam_scim_serv::{IamScimServ, SCHEMA_BULK_REQ, SCHEMA_GROUP, SCHEMA_PATCH_OP, SCHEMA_USER};
+use bios_iam::console_interface::serv::iam_ci_cert_aksk_serv::IamCiCertAkSkServ;
+use bios_iam::iam_constants;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::serde_json::json;
+use tardis::TardisFuns;
+
+pub async fn test(context1: &TardisContext) -> TardisResult<()> {
+    let funs = iam_constants::get_tardis_inst();
+
+    info!("【test_iam_scim】 : Auth");
+    let aksk = IamCiCertAkSkServ::general_cert(
+        IamCertAkSkAddReq {
+            tenant_id: context1.own_paths.clone(),
+            app_id: None,
+            oauth2_client: None,
+            scim: Some(true),
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    assert_eq!(IamScimServ::auth(None, &funs).await.unwrap_err().code, "401-iam-iam_scim-auth");
+    // the general aksk can't be used as the SCIM credential
+    let general_aksk = IamCiCertAkSkServ::general_cert(
+        IamCertAkSkAddReq {
+            tenant_id: context1.own_paths.clone(),
+            app_id: None,
+            oauth2_client: None,
+            scim: None,
+        },
+        &funs,
+        context1,
+    )
+    .await?;
+    assert_eq!(
+        IamScimServ::auth(Some(&format!("Bearer {}:{}", general_aksk.ak, general_aksk.sk)), &funs).await.unwrap_err().code,
+        "401-iam-iam_scim-auth"
+    );
+    // the SCIM credential can't be an OAuth2 client
+    assert_eq!(
+        IamCiCertAkSkServ::general_cert(
+            IamCertAkSkAddReq {
+                tenant_id: context1.own_paths.clone(),
+                app_id: None,
+                oauth2_client: Some(IamOAuth2ClientConf::default()),
+                scim: Some(true),
+            },
+            &funs,
+            context1,
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-iam_cert_aksk-add"
+    );
+    assert_eq!(
+        IamCertAkSkServ::modify_oauth2_client(&aksk.id, &IamOAuth2ClientConf::default(), &funs, context1).await.unwrap_err().code,
+        "400-iam-iam_cert_aksk-modify_oauth2_client"
+    );
+    assert_eq!(
+        IamScimServ::auth(Some(&format!("Bearer {}:xxx", aksk.ak)), &funs).await.unwrap_err().code,
+        "401-iam-iam_scim-auth"
+    );
+    let basic_ctx = IamScimServ::auth(Some(&format!("Basic {}", TardisFuns::crypto.base64.encode(format!("{}:{}", aksk.ak, aksk.sk)))), &funs).await?;
+    assert_eq!(basic_ctx.own_paths, context1.own_paths);
+    let ctx = IamScimServ::auth(Some(&format!("Bearer {}:{}", aksk.ak, aksk.sk)), &funs).await?;
+    assert_eq!(ctx.own_paths, context1.own_paths);
+
+    info!("【test_iam_scim】 : Add User");
+    let user = IamScimServ::add_user(
+        &IamScimServ::parse_resource::<IamScimUser>(
+            json!({
+                "schemas": [SCHEMA_USER],
+                "UserName": "scim_user1",
+                "name": {"givenName": "Scim", "familyName": "User1"},
+                "active": "True"
+            }),
+            &funs,
+        )?,
+        &funs,
+        &ctx,
+    )
+    .await?;
+    let user_id = user.id.clone().unwrap();
+    assert_eq!(user.user_name, "scim_user1");
+    assert_eq!(user.display_name, Some("Scim User1".to_string()));
+    assert_eq!(user.active, Some(true));
+    assert!(user.password.is_none());
+    assert!(user.meta.as_ref().unwrap().location.ends_with(&format!("/ci/scim/v2/Users/{user_id}")));
+    assert!(user.meta.as_ref().unwrap().version.as_ref().unwrap().starts_with("W/\""));
+    assert_eq!(
+        IamScimServ::add_user(&IamScimServ::parse_resource::<IamScimUser>(json!({"displayName": "no user name"}), &funs)?, &funs, &ctx).await.unwrap_err().code,
+        "400-iam-scim-invalidValue"
+    );
+    assert_eq!(
+        IamScimServ::parse_resource::<IamScimUser>(json!({"userName": ["scim_user1"]}), &funs).unwrap_err().code,
+        "400-iam-scim-invalidSyntax"
+    );
+
+    info!("【test_iam_scim】 : Find Users");
+    let users = IamScimServ::find_users(Some("UserName EQ \"scim_user1\""), None, None, &funs, &ctx).await?;
+    assert_eq!(users.total_results, 1);
+    assert_eq!(users.resources[0].id, Some(user_id.clone()));
+    let users = IamScimServ::find_users(Some("displayName sw \"Scim\" and (active eq true or userName pr)"), None, None, &funs, &ctx).await?;
+    assert_eq!(users.total_results, 1);
+    let users = IamScimServ::find_users(Some("userName eq \"scim_user1\" and not (active eq true)"), None, None, &funs, &ctx).await?;
+    assert_eq!(users.total_results, 0);
+    let users = IamScimServ::find_users(Some("userName eq \"not_exist\""), None, None, &funs, &ctx).await?;
+    assert_eq!(users.total_results, 0);
+    let users = IamScimServ::find_users(None, Some(1), Some(1), &funs, &ctx).await?;
+    assert!(users.total_results >= 1);
+    assert_eq!(users.items_per_page, 1);
+    assert_eq!(
+        IamScimServ::find_users(Some("userName eq"), None, None, &funs, &ctx).await.unwrap_err().code,
+        "400-iam-scim-invalidFilter"
+    );
+    assert_eq!(
+        IamScimServ::find_users(Some("userName xx \"scim_user1\""), None, None, &funs, &ctx).await.unwrap_err().code,
+        "400-iam-scim-invalidFilter"
+    );
+
+    info!("【test_iam_scim】 : Patch User");
+    let version = user.meta.as_ref().unwrap().version.clone().unwrap();
+    let user = IamScimServ::patch_user(
+        &user_id,
+        &IamScimServ::parse_resource::<IamScimPatchReq>(
+            json!({
+                "schemas": [SCHEMA_PATCH_OP],
+                "Operations": [
+                    {"op": "Replace", "value": {"active": "False"}},
+                    {"op": "replace", "path": "displayName", "value": "Scim User1 Modified"}
+                ]
+            }),
+            &funs,
+        )?,
+        Some(&version),
+        &funs,
+        &ctx,
+    )
+    .await?;
+    assert_eq!(user.active, Some(false));
+    assert_eq!(user.display_name, Some("Scim User1 Modified".to_string()));
+    assert_ne!(user.meta.as_ref().unwrap().version, Some(version.clone()));
+    // the version is changed by the last patch
+    assert_eq!(
+        IamScimServ::patch_user(
+            &user_id,
+            &IamScimServ::parse_resource::<IamScimPatchReq>(json!({"Operations": [{"op": "replace", "path": "active", "value": true}]}), &funs)?,
+            Some(&version),
+            &funs,
+            &ctx,
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "412"
+    );
+    assert_eq!(
+        IamScimServ::patch_user(
+            &user_id,
+            &IamScimServ::parse_resource::<IamScimPatchReq>(json!({"Operations": [{"op": "replace", "path": "userName", "value": "scim_user1_new"}]}), &funs)?,
+            None,
+            &funs,
+            &ctx,
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-scim-mutability"
+    );
+    assert_eq!(
+        IamScimServ::patch_user(
+            &user_id,
+            &IamScimServ::parse_resource::<IamScimPatchReq>(json!({"Operations": [{"op": "remove"}]}), &funs)?,
+            None,
+            &funs,
+            &ctx,
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-scim-noTarget"
+    );
+    let users = IamScimServ::find_users(Some("userName eq \"scim_user1\" and active eq false"), None, None, &funs, &ctx).await?;
+    assert_eq!(users.total_results, 1);
+
+    info!("【test_iam_scim】 : Group");
+    let group = IamScimServ::add_group(
+        &IamScimServ::parse_resource::<IamScimGroup>(
+            json!({
+                "schemas": [SCHEMA_GROUP],
+                "displayName": "scim_group1",
+                "members": [{"value": user_id}]
+            }),
+            &funs,
+        )?,
+        &funs,
+        &ctx,
+    )
+    .await?;
+    let group_id = group.id.clone().unwrap();
+    assert_eq!(group.members.len(), 1);
+    assert_eq!(group.members[0].value, user_id);
+    let user = IamScimServ::get_user(&user_id, &funs, &ctx).await?;
+    assert_eq!(user.groups.len(), 1);
+    assert_eq!(user.groups[0].value, group_id);
+    assert_eq!(user.groups[0].display, Some("scim_group1".to_string()));
+    let groups = IamScimServ::find_groups(Some("displayName eq \"scim_group1\""), None, None, &funs, &ctx).await?;
+    assert_eq!(groups.total_results, 1);
+    assert_eq!(
+        IamScimServ::add_group(
+            &IamScimServ::parse_resource::<IamScimGroup>(json!({"displayName": "scim_group2", "members": [{"value": "not_exist"}]}), &funs)?,
+            &funs,
+            &ctx,
+        )
+        .await
+        .unwrap_err()
+        .code,
+        "400-iam-scim-invalidValue"
+    );
+    let group = IamScimServ::patch_group(
+        &group_id,
+        &IamScimServ::parse_resource::<IamScimPatchReq>(
+            json!({"Operations": [
+                {"op": "remove", "path": format!("members[value eq \"{user_id}\"]")},
+                {"op": "replace", "path": "displayName", "value": "scim_group1_modified"}
+            ]}),
+            &funs,
+        )?,
+        None,
+        &funs,
+        &ctx,
+    )
+    .await?;
+    assert!(group.members.is_empty());
+    assert_eq!(group.display_name, "scim_group1_modified");
+    IamScimServ::delete_group(&group_id, None, &funs, &ctx).await?;
+    assert!(IamScimServ::get_group(&group_id, &funs, &ctx).await.unwrap_err().code.starts_with("404"));
+
+    info!("【test_iam_scim】 : Bulk");
+    let bulk_resp = IamScimServ::bulk(
+        &IamScimServ::parse_resource::<IamScimBulkReq>(
+            json!({
+                "schemas": [SCHEMA_BULK_REQ],
+                "Operations": [
+                    {"method": "POST", "path": "/Users", "bulkId": "u2", "data": {"userName": "scim_user2", "displayName": "Scim User2"}},
+                    {"method": "POST", "path": "/Groups", "bulkId": "g2", "data": {"displayName": "scim_group2", "members": [{"value": "bulkId:u2"}]}},
+                    {"method": "PATCH", "path": "/Users/bulkId:u3", "data": {"Operations": [{"op": "replace", "path": "active", "value": false}]}},
+                    {"method": "DELETE", "path": "/Groups/bulkId:g2"}
+                ]
+            }),
+            &funs,
+        )?,
+        &funs,
+        &ctx,
+    )
+    .await?;
+    assert_eq!(bulk_resp.operations.len(), 4);
+    assert_eq!(bulk_resp.operations[0].status, "201");
+    assert!(bulk_resp.operations[0].location.is_some());
+    assert_eq!(bulk_resp.operations[1].status, "201");
+    assert_eq!(bulk_resp.operations[2].status, "400");
+    assert_eq!(bulk_resp.operations[2].response.as_ref().unwrap().scim_type, Some("invalidValue".to_string()));
+    assert_eq!(bulk_resp.operations[3].status, "204");
+    let user2 = IamScimServ::find_users(Some("userName eq \"scim_user2\""), None, None, &funs, &ctx).await?;
+    assert_eq!(user2.total_results, 1);
+    assert!(user2.resources[0].groups.is_empty());
+    let bulk_resp = IamScimServ::bulk(
+        &IamScimServ::parse_resource::<IamScimBulkReq>(
+            json!({
+                "failOnErrors": 1,
+                "Operations": [
+                    {"method": "DELETE", "path": "/Users/not_exist"},
+                    {"method": "DELETE", "path": format!("/Users/{}", user2.resources[0].id.clone().unwrap())}
+                ]
+            }),
+            &funs,
+        )?,
+        &funs,
+        &ctx,
+    )
+    .await?;
+    assert_eq!(bulk_resp.operations.len(), 1);
+    assert!(bulk_resp.operations[0].status.starts_with('4'));
+
+    info!("【test_iam_scim】 : Discovery");
+    let schemas = IamScimServ::find_schemas(&funs, &ctx).await?;
+    assert_eq!(schemas.total_results, 3);
+    assert_eq!(IamScimServ::get_schema(SCHEMA_USER, &funs, &ctx).await?.name, "User");
+    let service_provider_config = IamScimServ::get_service_provider_config(&funs);
+    assert!(service_provider_config.patch.supported);
+    assert!(service_provider_config.bulk.supported);
+    assert!(service_provider_config.etag.supported);
+    assert_eq!(IamScimServ::find_resource_types(&funs).total_results, 2);
+
+    info!("【test_iam_scim】 : Delete User");
+    IamScimServ::delete_user(&user_id, None, &funs, &ctx).await?;
+    assert!(IamScimServ::get_user(&user_id, &funs, &ctx).await.unwrap_err().code.starts_with("404"));
+    IamScimServ::delete_user(&user2.resources[0].id.clone().unwrap(), None, &funs, &ctx).await?;
+
+    Ok(())
+}
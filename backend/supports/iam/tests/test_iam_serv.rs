@@ -18,6 +18,7 @@ mod test_cc_cert_conf;
 mod test_cc_res;
 mod test_cc_role;
 mod test_cc_set;
+mod test_ci_oauth2_provider;
 mod test_ci_open;
 mod test_cp_all;
 mod test_cs_tenant;
@@ -25,7 +26,11 @@ mod test_ct_app;
 mod test_ct_basic;
 mod test_ct_tenant;
 mod test_iam_cert_sync;
+mod test_iam_ldap_server;
+mod test_iam_mfa;
 mod test_iam_oauth2;
+mod test_iam_oidc_supplier;
+mod test_iam_scim;
 mod test_key_cache;
 
 #[tokio::test]
@@ -122,6 +127,11 @@ async fn test_iam_serv() -> TardisResult<()> {
     )
     .await?;
     test_ci_open::test(&tenant1_admin_context).await?;
+    test_ci_oauth2_provider::test(&tenant1_admin_context).await?;
+    test_iam_oidc_supplier::test(&tenant1_admin_context).await?;
+    test_iam_mfa::test(&tenant1_admin_context).await?;
+    test_iam_scim::test(&tenant1_admin_context).await?;
+    test_iam_ldap_server::test(&tenant1_admin_context).await?;
     test_key_cache::test(&system_admin_context).await?;
     // test_iam_oauth2::test(&tenant1_admin_context).await?;
     let conf_ldap_add_or_modify_req = test_basic::gen_test_ldap_conf();
@@ -4,10 +4,11 @@ use serde::{Deserialize, Serialize};
 pub mod event {
     use asteroid_mq::prelude::*;
 
-    use super::{FlowFrontChangeReq, FlowPostChangeReq};
+    use super::{FlowFrontChangeReq, FlowPostChangeReq, FlowSlaBreachEvent};
     pub const FLOW_AVATAR: &str = "flow";
     pub const EVENT_FRONT_CHANGE: &str = "flow/front_change";
     pub const EVENT_POST_CHANGE: &str = "flow/post_change";
+    pub const EVENT_SLA_BREACH: &str = "flow/sla_breach";
 
     impl EventAttribute for FlowFrontChangeReq {
         const SUBJECT: Subject = Subject::const_new("flow/front_change");
@@ -15,6 +16,10 @@ pub mod event {
     impl EventAttribute for FlowPostChangeReq {
         const SUBJECT: Subject = Subject::const_new("flow/post_change");
     }
+    impl EventAttribute for FlowSlaBreachEvent {
+        const BROADCAST: bool = true;
+        const SUBJECT: Subject = Subject::const_new("flow/sla_breach");
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -27,3 +32,15 @@ pub struct FlowPostChangeReq {
     pub inst_id: String,
     pub next_transition_id: String,
 }
+
+/// Published when a flow instance stays in a state longer than the SLA of the state
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FlowSlaBreachEvent {
+    pub inst_id: String,
+    pub rel_business_obj_id: String,
+    pub flow_model_id: String,
+    pub state_id: String,
+    pub assigned_to: String,
+    pub sla_secs: i64,
+    pub elapsed_secs: i64,
+}
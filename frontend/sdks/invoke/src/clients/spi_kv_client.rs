@@ -19,6 +19,10 @@ pub mod event {
     impl EventAttribute for super::KvItemDeleteReq {
         const SUBJECT: Subject = Subject::const_new("kv/delete");
     }
+    impl EventAttribute for super::KvItemChangeEvent {
+        const BROADCAST: bool = true;
+        const SUBJECT: Subject = Subject::const_new("kv/change");
+    }
 }
 #[derive(Clone, Debug, Default)]
 pub struct SpiKvClient;
@@ -35,6 +39,12 @@ pub struct KvItemDeleteReq {
     pub key: String,
 }
 
+/// Broadcast to all spi-kv nodes when an item is changed, used to wake up the watchers
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct KvItemChangeEvent {
+    pub key: String,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Clone, Debug)]
 pub struct KvItemSummaryResp {
     #[oai(validator(min_length = "2"))]
@@ -57,6 +67,20 @@ pub struct KvItemDetailResp {
     pub update_time: DateTime<Utc>,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Clone, Debug)]
+pub struct KvItemWatchResp {
+    pub revision: i64,
+    pub changes: Vec<KvItemChangeResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Clone, Debug)]
+pub struct KvItemChangeResp {
+    pub key: String,
+    pub revision: i64,
+    pub deleted: bool,
+    pub item: Option<KvItemSummaryResp>,
+}
+
 impl SpiKvClient {
     pub async fn add_or_modify_item<T: ?Sized + Serialize>(
         key: &str,
@@ -168,4 +192,29 @@ impl SpiKvClient {
             .await?;
         Ok(())
     }
+
+    /// Wait for the changes of the items matching the key prefix after the revision, ``revision`` is ``None`` to get the latest revision
+    pub async fn watch_items(
+        key_prefix: &str,
+        key_like: Option<bool>,
+        revision: Option<i64>,
+        timeout_sec: Option<u32>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<Option<KvItemWatchResp>> {
+        let kv_url = BaseSpiClient::module_url(InvokeModuleKind::Kv, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let mut url = format!("{kv_url}/ci/item/watch?key_prefix={key_prefix}");
+        if let Some(key_like) = key_like {
+            url = format!("{url}&key_like={key_like}");
+        }
+        if let Some(revision) = revision {
+            url = format!("{url}&revision={revision}");
+        }
+        if let Some(timeout_sec) = timeout_sec {
+            url = format!("{url}&timeout_sec={timeout_sec}");
+        }
+        let resp = funs.web_client().get::<TardisResp<KvItemWatchResp>>(&url, headers.clone()).await?;
+        BaseSpiClient::package_resp(resp)
+    }
 }
@@ -0,0 +1,41 @@
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::web::web_resp::TardisResp;
+use tardis::TardisFunsInst;
+
+use crate::invoke_enumeration::InvokeModuleKind;
+
+use super::base_spi_client::BaseSpiClient;
+
+#[derive(Clone, Debug, Default)]
+pub struct SpiObjectClient;
+
+impl SpiObjectClient {
+    pub async fn presign_put_obj_url(object_path: &str, exp_secs: u32, private: Option<bool>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<String>> {
+        let object_url = BaseSpiClient::module_url(InvokeModuleKind::Object, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let mut url = format!("{object_url}/ci/obj/presign/put?object_path={object_path}&exp_secs={exp_secs}");
+        if let Some(private) = private {
+            url = format!("{url}&private={private}");
+        }
+        let resp = funs.web_client().get::<TardisResp<String>>(&url, headers).await?;
+        BaseSpiClient::package_resp(resp)
+    }
+
+    /// Upload the content as an object through the presigned url
+    pub async fn put_obj(object_path: &str, content: &str, private: Option<bool>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let Some(url) = Self::presign_put_obj_url(object_path, 60, private, funs, ctx).await? else {
+            return Err(funs.err().not_found("spi-object", "put_obj", "Presigned url not found", "404-spi-object-presign-url-not-exist"));
+        };
+        let resp = funs.web_client().put_str_to_str(&url, content, vec![]).await?;
+        if resp.code >= 300 {
+            return Err(funs.err().conflict(
+                "spi-object",
+                "put_obj",
+                &format!("Upload object {object_path} failed with status {}", resp.code),
+                "409-spi-object-upload-failed",
+            ));
+        }
+        Ok(())
+    }
+}